@@ -29,3 +29,44 @@ pub mod conv_u16le {
         v.get()
     }
 }
+
+/// Asserts that a value encodes to exactly the given reference bytes,
+/// and that decoding those bytes yields an equal value.
+///
+/// This is intended for checking `bitfield_struct` layouts (or any other zerocopy type)
+/// against byte fixtures captured from Valhalla.
+///
+/// ```
+/// use bit_twiddling_helpers::assert_bitfield_round_trip;
+/// use zerocopy::{LE, U32};
+///
+/// assert_bitfield_round_trip!(U32::<LE>::new(42), &[42, 0, 0, 0]);
+/// ```
+#[macro_export]
+macro_rules! assert_bitfield_round_trip {
+    ($value:expr, $bytes:expr $(,)?) => {
+        $crate::__assert_bitfield_round_trip(&$value, $bytes)
+    };
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn __assert_bitfield_round_trip<T>(value: &T, bytes: &[u8])
+where
+    T: zerocopy::FromBytes
+        + zerocopy::IntoBytes
+        + zerocopy::Immutable
+        + PartialEq
+        + core::fmt::Debug,
+{
+    assert_eq!(
+        value.as_bytes(),
+        bytes,
+        "encoded value does not match the reference bytes"
+    );
+    let decoded = T::read_from_bytes(bytes).expect("reference bytes have the wrong length");
+    assert_eq!(
+        &decoded, value,
+        "decoding the reference bytes did not produce the original value"
+    );
+}
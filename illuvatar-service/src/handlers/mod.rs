@@ -1 +1,63 @@
+use valhalla_proto::Api;
+use valhalla_response::Warning;
+
 pub mod status;
+
+/// Collects any warnings raised upstream (e.g. by Loki or Thor) during request processing.
+///
+/// Returns `None` if there were no warnings so that the field is omitted from the response.
+pub fn collect_warnings(request: &Api) -> Option<Vec<Warning>> {
+    let warnings: Vec<Warning> = request
+        .info
+        .as_ref()?
+        .warnings
+        .iter()
+        .map(|warning| Warning::new(warning.code, warning.description.clone()))
+        .collect();
+
+    if warnings.is_empty() {
+        None
+    } else {
+        Some(warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use valhalla_proto::{CodedDescription, Info};
+
+    #[test]
+    fn collect_warnings_omits_empty() {
+        assert_eq!(collect_warnings(&Api::default()), None);
+
+        let request = Api {
+            info: Some(Info::default()),
+            ..Default::default()
+        };
+        assert_eq!(collect_warnings(&request), None);
+    }
+
+    #[test]
+    fn collect_warnings_preserves_code_and_text() {
+        let request = Api {
+            info: Some(Info {
+                warnings: vec![CodedDescription {
+                    description: "best_paths has been deprecated, use alternates instead"
+                        .to_string(),
+                    code: 100,
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            collect_warnings(&request),
+            Some(vec![Warning::new(
+                100,
+                "best_paths has been deprecated, use alternates instead"
+            )])
+        );
+    }
+}
@@ -1,6 +1,12 @@
 use http::StatusCode;
 use serde_json::json;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
 use tracing::error;
+use valhalla_graphtile::graph_tile::GraphTile;
+use valhalla_graphtile::tile_provider::{
+    BlueGreenTileProvider, GraphTileProvider, TrafficTileProvider,
+};
 use valhalla_microservice::WorkerResult;
 use valhalla_proto::Api;
 use valhalla_proto::options::Format;
@@ -10,17 +16,109 @@ use valhalla_proto::status::{
 };
 use valhalla_response::StatusResponse;
 
-pub fn status(request: Api) -> WorkerResult {
+use super::collect_warnings;
+
+/// How long the last traffic update time is reused before the tile headers are scanned again.
+const LAST_UPDATE_CACHE_DURATION: Duration = Duration::from_secs(10);
+
+/// Live traffic configuration, used to report the freshness of the traffic feed.
+#[derive(Default)]
+pub struct TrafficStatus {
+    /// The live traffic extract (if any).
+    extract: Option<TrafficTileProvider<false>>,
+    /// How often the traffic extract is expected to be updated.
+    update_interval: Option<Duration>,
+    /// The last update time, and when it was read.
+    ///
+    /// Finding it means reading every tile header, which is too slow to repeat for every status request.
+    cached_last_update: Mutex<Option<(Instant, Option<u64>)>>,
+}
+
+impl TrafficStatus {
+    pub fn new(
+        extract: Option<TrafficTileProvider<false>>,
+        update_interval: Option<Duration>,
+    ) -> Self {
+        let status = Self {
+            extract,
+            update_interval,
+            cached_last_update: Mutex::default(),
+        };
+        // Read the headers up front, so that the first status request doesn't have to
+        status.last_update();
+        status
+    }
+
+    /// The most recent update time across all traffic tiles (seconds since the epoch).
+    ///
+    /// The extract is updated in place, so this is cached for [`LAST_UPDATE_CACHE_DURATION`].
+    fn last_update(&self) -> Option<u64> {
+        let extract = self.extract.as_ref()?;
+        let mut cached = self
+            .cached_last_update
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some((read_at, last_update)) = *cached
+            && read_at.elapsed() < LAST_UPDATE_CACHE_DURATION
+        {
+            return last_update;
+        }
+
+        // SAFETY: Valhalla writers only ever update speeds and timestamps in place,
+        // so the tile headers are valid for the life of the memory map.
+        let last_update = match unsafe { extract.last_update() } {
+            Ok(last_update) => last_update,
+            Err(e) => {
+                error!("Unable to read the traffic extract headers: {e}");
+                None
+            }
+        };
+        *cached = Some((Instant::now(), last_update));
+        last_update
+    }
+}
+
+/// The dataset ID of a tileset, read from the header of its first tile.
+///
+/// All tiles from the same build share a dataset ID.
+fn dataset_id<P: GraphTileProvider>(provider: &P) -> Option<u64> {
+    let result = provider.available_tiles().and_then(|tiles| {
+        tiles
+            .first()
+            .map(|&graph_id| {
+                provider.with_tile_containing(graph_id, |tile| tile.header().dataset_id.get())
+            })
+            .transpose()
+    });
+    match result {
+        Ok(dataset_id) => dataset_id,
+        Err(e) => {
+            error!("Unable to read the tileset dataset ID: {e}");
+            None
+        }
+    }
+}
+
+pub fn status<P: GraphTileProvider>(
+    request: Api,
+    traffic: &TrafficStatus,
+    tiles: Option<&BlueGreenTileProvider<P>>,
+) -> WorkerResult {
     if let Some(options) = &request.options
         && Format::try_from(options.format) == Ok(Format::Pbf)
     {
         unimplemented!("TODO: PBF status")
     } else {
-        json_status(request)
+        json_status(request, traffic, tiles)
     }
 }
 
-fn json_status(request: Api) -> WorkerResult {
+fn json_status<P: GraphTileProvider>(
+    request: Api,
+    traffic: &TrafficStatus,
+    tiles: Option<&BlueGreenTileProvider<P>>,
+) -> WorkerResult {
+    let warnings = collect_warnings(&request);
     let Some(status) = request.status else {
         error!("Unexpected internal request without status info.");
 
@@ -32,6 +130,13 @@ fn json_status(request: Api) -> WorkerResult {
         );
     };
 
+    // Both sides are read together, so a concurrent swap can't make them look the same
+    let (dataset_id, candidate_dataset_id) = tiles
+        .map(BlueGreenTileProvider::current_and_candidate)
+        .map_or((None, None), |(current, candidate)| {
+            (dataset_id(current), dataset_id(candidate))
+        });
+
     let res = StatusResponse {
         version: status.version,
         tileset_last_modified: status.tileset_last_modified,
@@ -50,6 +155,11 @@ fn json_status(request: Api) -> WorkerResult {
         osm_changeset: status
             .has_osm_changeset
             .map(|HasOsmChangeset::OsmChangeset(v)| v),
+        traffic_last_update: traffic.last_update(),
+        traffic_update_interval: traffic.update_interval.map(|interval| interval.as_secs()),
+        dataset_id,
+        candidate_dataset_id,
+        warnings,
     };
 
     WorkerResult::json(StatusCode::OK, res)
@@ -58,8 +168,32 @@ fn json_status(request: Api) -> WorkerResult {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use valhalla_graphtile::tile_provider::TarballTileProvider;
     use valhalla_proto::prost::Message;
 
+    /// No blue/green tilesets.
+    const NO_TILES: Option<&BlueGreenTileProvider<TarballTileProvider<false>>> = None;
+
+    /// Copies the Andorra fixture to `path`, giving every tile the given dataset ID.
+    fn tileset_with_dataset_id(path: &Path, dataset_id: u64) -> TarballTileProvider<false> {
+        let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles.tar");
+        std::fs::copy(fixture, path).unwrap();
+        let writer = TarballTileProvider::new_mutable(path).unwrap();
+        for graph_id in writer.available_tiles().unwrap() {
+            // SAFETY: Nothing else is reading the copy yet
+            unsafe {
+                writer
+                    .update_tile(graph_id, |builder| Ok(builder.with_dataset_id(dataset_id)))
+                    .unwrap();
+            }
+        }
+        drop(writer);
+        TarballTileProvider::new_readonly(path).unwrap()
+    }
+
     #[test]
     fn status_happy_path() {
         // Happy path test for generating a status response
@@ -88,7 +222,7 @@ mod tests {
             status_code,
             headers,
             body,
-        } = status(request)
+        } = status(request, &TrafficStatus::default(), NO_TILES)
         else {
             panic!("Expected an HTTP response.");
         };
@@ -105,4 +239,81 @@ mod tests {
             insta::assert_yaml_snapshot!(status_res);
         }
     }
+
+    #[test]
+    fn status_with_traffic() {
+        let extract_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-traffic.tar");
+        let traffic = TrafficStatus::new(
+            Some(TrafficTileProvider::new_readonly(extract_path).unwrap()),
+            Some(Duration::from_mins(5)),
+        );
+        let request = Api {
+            status: Some(valhalla_proto::Status::default()),
+            ..Default::default()
+        };
+
+        let WorkerResult::HttpResponse { body, .. } = status(request, &traffic, NO_TILES) else {
+            panic!("Expected an HTTP response.");
+        };
+        let status_res: StatusResponse = serde_json::from_slice(&body).unwrap();
+
+        // The fixture has never been updated
+        assert_eq!(status_res.traffic_last_update, None);
+        assert_eq!(status_res.traffic_update_interval, Some(300));
+
+        // Status requests within the cache duration reuse the last scan of the headers
+        *traffic.cached_last_update.lock().unwrap() = Some((Instant::now(), Some(1_760_000_000)));
+        assert_eq!(traffic.last_update(), Some(1_760_000_000));
+    }
+
+    #[test]
+    fn status_reports_tilesets_while_swapping() {
+        let dir = std::env::temp_dir().join(format!("illuvatar-tilesets-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let tiles = BlueGreenTileProvider::new(
+            tileset_with_dataset_id(&dir.join("blue.tar"), 1),
+            tileset_with_dataset_id(&dir.join("green.tar"), 2),
+        );
+        let request = Api {
+            status: Some(valhalla_proto::Status::default()),
+            ..Default::default()
+        };
+        let dataset_ids = || {
+            let WorkerResult::HttpResponse { body, .. } =
+                status(request.clone(), &TrafficStatus::default(), Some(&tiles))
+            else {
+                panic!("Expected an HTTP response.");
+            };
+            let status_res: StatusResponse = serde_json::from_slice(&body).unwrap();
+            (status_res.dataset_id, status_res.candidate_dataset_id)
+        };
+        assert_eq!(dataset_ids(), (Some(1), Some(2)));
+
+        let done = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    while !done.load(Ordering::Relaxed) {
+                        let ids = dataset_ids();
+                        assert!(
+                            ids == (Some(1), Some(2)) || ids == (Some(2), Some(1)),
+                            "Unexpected tilesets mid-swap: {ids:?}"
+                        );
+                    }
+                });
+            }
+
+            for _ in 0..100 {
+                tiles.swap();
+                std::thread::yield_now();
+            }
+            tiles.swap();
+            done.store(true, Ordering::Relaxed);
+        });
+        assert_eq!(dataset_ids(), (Some(2), Some(1)));
+
+        drop(tiles);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
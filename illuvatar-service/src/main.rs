@@ -1,18 +1,31 @@
 #![doc = include_str!("../README.md")]
 
 use clap::Parser;
+use handlers::status::TrafficStatus;
 use http::StatusCode;
 use serde_json::json;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use tracing::{error, info};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{EnvFilter, Layer};
+use valhalla_graphtile::tile_provider::{
+    BlueGreenTileProvider, TarballTileProvider, TrafficTileProvider,
+};
+use valhalla_microservice::service_limits::ServiceLimits;
 use valhalla_microservice::{Error, ValhallaMicroserviceBuilder, WorkerResult};
 use valhalla_proto::Api;
 use valhalla_proto::options::Action;
+use valhalla_response::error::codes;
 
 mod handlers;
 
+/// How long to wait for work in flight to finish when shutting down.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Cli {
@@ -23,8 +36,56 @@ struct Cli {
     /// The Valhalla loopback socket endpoint.
     #[arg(env, long, default_value = "ipc:///tmp/loopback")]
     loopback_socket_endpoint: String,
+
+    /// The Valhalla interrupt socket endpoint (typically `ipc:///tmp/interrupt`).
+    ///
+    /// When set, requests are dropped once the client has gone away.
+    #[arg(env, long)]
+    interrupt_socket_endpoint: Option<String>,
+
+    /// The longest a request may be worked on before it gets a timeout error, in seconds.
+    #[arg(env, long)]
+    max_processing_time: Option<u64>,
+
+    /// The address to serve Prometheus metrics on (e.g. `0.0.0.0:9090`).
+    ///
+    /// Metrics are not served when this is not set.
+    #[arg(env, long)]
+    metrics_address: Option<SocketAddr>,
+
+    /// The live traffic extract (traffic.tar).
+    ///
+    /// When set, the time of the last traffic update is included in status responses.
+    #[arg(env, long)]
+    traffic_extract: Option<PathBuf>,
+
+    /// The expected interval between live traffic updates, in seconds.
+    ///
+    /// This is reported in status responses so that monitoring can detect a stale feed.
+    #[arg(env, long)]
+    traffic_update_interval: Option<u64>,
+
+    /// The Valhalla config file (valhalla.json) to read service limits from.
+    ///
+    /// Valhalla's default limits are applied when this is not set.
+    #[arg(env, long)]
+    valhalla_config: Option<PathBuf>,
+
+    /// The tile extract (tiles.tar) serving production traffic, for blue/green tileset switching.
+    ///
+    /// Status responses report the dataset IDs of both tilesets.
+    /// Send the service a `SIGHUP` to swap the current and candidate tilesets.
+    #[arg(env, long, requires = "candidate_tile_extract")]
+    tile_extract: Option<PathBuf>,
+
+    /// The candidate tile extract, which takes over from `--tile-extract` on a swap.
+    #[arg(env, long, requires = "tile_extract")]
+    candidate_tile_extract: Option<PathBuf>,
 }
 
+/// The blue/green tilesets, both read from tile extracts.
+type Tilesets = BlueGreenTileProvider<TarballTileProvider<false>>;
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
@@ -37,9 +98,60 @@ async fn main() -> anyhow::Result<()> {
         // TODO: We should probably optionally add Sentry here (behind a feature flag).
         .init();
 
-    let service_builder =
+    let traffic = TrafficStatus::new(
+        cli.traffic_extract
+            .as_ref()
+            .map(TrafficTileProvider::new_readonly)
+            .transpose()?,
+        cli.traffic_update_interval.map(Duration::from_secs),
+    );
+
+    let limits = match &cli.valhalla_config {
+        Some(path) => ServiceLimits::from_config_file(path)?,
+        None => ServiceLimits::default(),
+    };
+
+    let tiles = match (&cli.tile_extract, &cli.candidate_tile_extract) {
+        (Some(current), Some(candidate)) => Some(Arc::new(BlueGreenTileProvider::new(
+            TarballTileProvider::new_readonly(current)?,
+            TarballTileProvider::new_readonly(candidate)?,
+        ))),
+        _ => None,
+    };
+    #[cfg(unix)]
+    if let Some(tiles) = &tiles {
+        let mut hangups = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+        let tiles = tiles.clone();
+        tokio::spawn(async move {
+            while hangups.recv().await.is_some() {
+                tiles.swap();
+                info!("SIGHUP received; swapped the current and candidate tilesets");
+            }
+        });
+    }
+
+    let mut service_builder =
         ValhallaMicroserviceBuilder::new(upstream_socket_endpoint, loopback_socket_endpoint);
-    let mut service = service_builder.build(handle_message).await?;
+    if let Some(interrupt_socket_endpoint) = &cli.interrupt_socket_endpoint {
+        service_builder = service_builder.with_interrupt_socket_endpoint(interrupt_socket_endpoint);
+    }
+    if let Some(max_processing_time) = cli.max_processing_time {
+        service_builder =
+            service_builder.with_max_processing_time(Duration::from_secs(max_processing_time));
+    }
+    // Status requests are quick enough that checking for interrupts isn't worth it
+    let mut service = service_builder
+        .build(move |req, _cancellation| handle_message(req, &traffic, tiles.as_deref(), &limits))
+        .await?;
+
+    if let Some(metrics_address) = cli.metrics_address {
+        let metrics = service.metrics().clone();
+        tokio::spawn(async move {
+            if let Err(e) = metrics.serve(metrics_address).await {
+                error!("Failed to serve metrics on {metrics_address}: {e}");
+            }
+        });
+    }
 
     info!(
         "Ilúvatar service started (upstream = {upstream_socket_endpoint}, loopback = {loopback_socket_endpoint})"
@@ -49,7 +161,7 @@ async fn main() -> anyhow::Result<()> {
         tokio::select! {
             _ = tokio::signal::ctrl_c() => {
                 info!("Ctrl-C received; shutting down...");
-                return Ok(());
+                return Ok(service.shutdown(SHUTDOWN_TIMEOUT).await?);
             }
             message = service.tick() => match message {
                 Ok(()) => {
@@ -58,7 +170,7 @@ async fn main() -> anyhow::Result<()> {
                 Err(Error::UpstreamShuttingDown) => {
                     // Graceful shutdown path.
                     info!("Upstream shutting down...");
-                    return Ok(());
+                    return Ok(service.shutdown(SHUTDOWN_TIMEOUT).await?);
                 }
                 Err(Error::InvalidMessage(e)) => {
                     error!("{e}");
@@ -71,7 +183,12 @@ async fn main() -> anyhow::Result<()> {
     }
 }
 
-fn handle_message(req: Api) -> WorkerResult {
+fn handle_message(
+    req: Api,
+    traffic: &TrafficStatus,
+    tiles: Option<&Tilesets>,
+    limits: &ServiceLimits,
+) -> WorkerResult {
     let Some(options) = &req.options else {
         return WorkerResult::json(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -81,11 +198,16 @@ fn handle_message(req: Api) -> WorkerResult {
         );
     };
 
+    if let Err(e) = limits.check(options) {
+        return e.into();
+    }
+
     match Action::try_from(options.action) {
-        Ok(Action::Status) => handlers::status::status(req),
+        Ok(Action::Status) => handlers::status::status(req, traffic, tiles),
         Ok(_) => {
             // Valhalla literally has a switch fallthrough here, but I'm not sure that's wise...
-            unimplemented!("TODO: Narrative builder!");
+            // TODO: Narrative builder! (Traffic advisories are waiting on it too; see the README.)
+            WorkerResult::valhalla_error(codes::NOT_IMPLEMENTED)
         }
         Err(_) => WorkerResult::json(
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -0,0 +1,209 @@
+#!/usr/bin/env python3
+"""Dumps every directed edge in a set of tiles as JSON lines, decoding the raw tile bytes.
+
+This is a stand-in for `dump_directed_edges.cc` when Valhalla isn't installed.
+It writes the same fields in the same format, but reads them straight from the bytes,
+following the bit layouts in Valhalla's `baldr/graphtileheader.h` and `baldr/directededge.h`.
+It deliberately shares no code with the Rust crate, so the fixture it produces
+is an independent check on our accessors.
+
+Usage:
+
+    decode_directed_edges.py <tile_dir> <level/tile_id>... > andorra-directed-edges.jsonl
+"""
+
+import json
+import sys
+from pathlib import Path
+
+# Tile sizes (in degrees) of each hierarchy level, for building tile paths
+TILE_SIZES = {0: 4.0, 1: 1.0, 2: 0.25, 3: 0.25}
+
+HEADER_SIZE = 272
+NODE_SIZE = 32
+TRANSITION_SIZE = 8
+DIRECTED_EDGE_SIZE = 48
+
+
+def tile_path(tile_dir, level, tile_id):
+    """The path of a tile, like Valhalla's `GraphTile::FileSuffix`."""
+    size = TILE_SIZES[level]
+    max_id = int(360 / size) * int(180 / size) - 1
+    digits = len(str(max_id))
+    digits += -digits % 3
+    padded = str(tile_id).zfill(digits)
+    groups = [padded[i : i + 3] for i in range(0, digits, 3)]
+    return Path(tile_dir, str(level), *groups[:-1], groups[-1] + ".gph")
+
+
+class Bits:
+    """Reads consecutive bitfields (least significant bits first) from a little-endian word."""
+
+    def __init__(self, word):
+        self.word = word
+        self.offset = 0
+
+    def take(self, width):
+        value = (self.word >> self.offset) & ((1 << width) - 1)
+        self.offset += width
+        return value
+
+
+def word(data, offset, size=8):
+    return int.from_bytes(data[offset : offset + size], "little")
+
+
+def header_counts(data):
+    """The node, transition, and directed edge counts from the tile header."""
+    counts = Bits(word(data, 40))
+    node_count = counts.take(21)
+    directed_edge_count = counts.take(21)
+    transition_count = Bits(word(data, 48, 4)).take(22)
+    return node_count, transition_count, directed_edge_count
+
+
+def decode_directed_edge(data):
+    """Decodes the 48 byte `DirectedEdge` struct into the fields of the C++ dump."""
+    bits = Bits(word(data, 0))
+    end_node = bits.take(46)
+    bits.take(8)  # restrictions
+    opp_index = bits.take(7)
+    forward = bits.take(1)
+    leaves_tile = bits.take(1)
+    ctry_crossing = bits.take(1)
+
+    bits = Bits(word(data, 8))
+    edgeinfo_offset = bits.take(25)
+    bits.take(12)  # access_restriction
+    bits.take(12)  # start_restriction
+    bits.take(12)  # end_restriction
+    bits.take(1)  # complex_restriction
+    destonly = bits.take(1)
+    not_thru = bits.take(1)
+
+    bits = Bits(word(data, 16))
+    speed = bits.take(8)
+    free_flow_speed = bits.take(8)
+    constrained_flow_speed = bits.take(8)
+    truck_speed = bits.take(8)
+    bits.take(8)  # name_consistency
+    use = bits.take(6)
+    lanecount = bits.take(4)
+    density = bits.take(4)
+    classification = bits.take(3)
+    surface = bits.take(3)
+    toll = bits.take(1)
+    roundabout = bits.take(1)
+    truck_route = bits.take(1)
+    has_predicted_speed = bits.take(1)
+
+    bits = Bits(word(data, 24))
+    forwardaccess = bits.take(12)
+    reverseaccess = bits.take(12)
+    bits.take(5)  # max_up_slope
+    bits.take(5)  # max_down_slope
+    bits.take(3)  # sac_scale
+    cyclelane = bits.take(2)
+    bike_network = bits.take(1)
+    bits.take(1)  # use_sidepath
+    bits.take(1)  # dismount
+    bits.take(1)  # sidewalk_left
+    bits.take(1)  # sidewalk_right
+    bits.take(1)  # shoulder
+    bits.take(1)  # lane_conn
+    bits.take(1)  # turnlanes
+    bits.take(1)  # sign
+    internal = bits.take(1)
+    bits.take(1)  # tunnel
+    bits.take(1)  # bridge
+    traffic_signal = bits.take(1)
+    bits.take(1)  # seasonal
+    bits.take(1)  # deadend
+    bss_connection = bits.take(1)
+    stop_sign = bits.take(1)
+    yield_sign = bits.take(1)
+
+    bits = Bits(word(data, 32))
+    bits.take(24)  # turntype
+    edge_to_left = bits.take(8)
+    length = bits.take(24)
+
+    # The stop impacts share their bits with the transit line ID
+    bits = Bits(word(data, 40))
+    stopimpact = bits.take(24)
+    edge_to_right = bits.take(8)
+    localedgeidx = bits.take(7)
+    opp_local_idx = bits.take(7)
+    bits.take(7)  # shortcut
+    superseded = bits.take(7)
+    is_shortcut = bits.take(1)
+
+    # Same order as the C++ dump
+    return dict(
+        end_node=end_node,
+        opp_index=opp_index,
+        edgeinfo_offset=edgeinfo_offset,
+        forward=forward,
+        leaves_tile=leaves_tile,
+        length=length,
+        speed=speed,
+        free_flow_speed=free_flow_speed,
+        constrained_flow_speed=constrained_flow_speed,
+        truck_speed=truck_speed,
+        use=use,
+        classification=classification,
+        surface=surface,
+        lanecount=lanecount,
+        density=density,
+        toll=toll,
+        roundabout=roundabout,
+        truck_route=truck_route,
+        has_predicted_speed=has_predicted_speed,
+        internal=internal,
+        is_shortcut=is_shortcut,
+        superseded=superseded,
+        forwardaccess=forwardaccess,
+        reverseaccess=reverseaccess,
+        cyclelane=cyclelane,
+        bike_network=bike_network,
+        destonly=destonly,
+        not_thru=not_thru,
+        ctry_crossing=ctry_crossing,
+        bss_connection=bss_connection,
+        localedgeidx=localedgeidx,
+        opp_local_idx=opp_local_idx,
+        stopimpact=stopimpact,
+        edge_to_left=edge_to_left,
+        edge_to_right=edge_to_right,
+        traffic_signal=traffic_signal,
+        stop_sign=stop_sign,
+        yield_sign=yield_sign,
+    )
+
+
+def main(argv):
+    if len(argv) < 3:
+        print(f"Usage: {argv[0]} <tile_dir> <level/tile_id>...", file=sys.stderr)
+        return 1
+
+    tile_dir = argv[1]
+    for arg in argv[2:]:
+        level, slash, tile_id = arg.partition("/")
+        if not slash:
+            print(f"Expected a tile in level/tile_id format: {arg}", file=sys.stderr)
+            return 1
+
+        data = tile_path(tile_dir, int(level), int(tile_id)).read_bytes()
+        node_count, transition_count, directed_edge_count = header_counts(data)
+        edges_offset = HEADER_SIZE + node_count * NODE_SIZE + transition_count * TRANSITION_SIZE
+        for index in range(directed_edge_count):
+            offset = edges_offset + index * DIRECTED_EDGE_SIZE
+            edge = decode_directed_edge(data[offset : offset + DIRECTED_EDGE_SIZE])
+            line = {"tile": arg, "index": index, **edge}
+            print(json.dumps(line, separators=(",", ":")))
+
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main(sys.argv))
@@ -0,0 +1,102 @@
+// Dumps every directed edge in a set of tiles as JSON lines, using Valhalla's own accessors.
+//
+// The output is used as a reference fixture for the directed edge decoding tests
+// (see `test_directed_edges_match_valhalla` in src/graph_tile/directed_edge.rs).
+//
+// Build against an installed Valhalla (headers and libvalhalla), e.g.:
+//
+//   c++ -std=c++17 -O2 dump_directed_edges.cc -o dump_directed_edges $(pkg-config --cflags --libs libvalhalla)
+//
+// Usage:
+//
+//   dump_directed_edges <tile_dir> <level/tile_id>... > andorra-directed-edges.jsonl
+
+#include <cstdlib>
+#include <iostream>
+#include <string>
+
+#include <valhalla/baldr/graphid.h>
+#include <valhalla/baldr/graphtile.h>
+
+using namespace valhalla::baldr;
+
+int main(int argc, char** argv) {
+  if (argc < 3) {
+    std::cerr << "Usage: " << argv[0] << " <tile_dir> <level/tile_id>..." << std::endl;
+    return EXIT_FAILURE;
+  }
+
+  const std::string tile_dir = argv[1];
+  for (int i = 2; i < argc; ++i) {
+    const std::string arg = argv[i];
+    const auto slash = arg.find('/');
+    if (slash == std::string::npos) {
+      std::cerr << "Expected a tile in level/tile_id format: " << arg << std::endl;
+      return EXIT_FAILURE;
+    }
+    const GraphId tile_id(std::stoul(arg.substr(slash + 1)), std::stoul(arg.substr(0, slash)), 0);
+
+    const auto tile = GraphTile::Create(tile_dir, tile_id);
+    if (!tile) {
+      std::cerr << "Unable to load tile " << arg << std::endl;
+      return EXIT_FAILURE;
+    }
+
+    for (uint32_t index = 0; index < tile->header()->directededgecount(); ++index) {
+      const DirectedEdge* de = tile->directededge(index);
+      // Pack the per-transition attributes the same way they are stored
+      uint32_t stop_impacts = 0, edges_to_left = 0, edges_to_right = 0;
+      for (uint32_t idx = 0; idx < 8; ++idx) {
+        stop_impacts |= de->stopimpact(idx) << (idx * 3);
+        edges_to_left |= de->edge_to_left(idx) << idx;
+        edges_to_right |= de->edge_to_right(idx) << idx;
+      }
+      // clang-format off
+      std::cout << "{"
+                << "\"tile\":\"" << arg << "\","
+                << "\"index\":" << index << ","
+                << "\"end_node\":" << de->endnode().value << ","
+                << "\"opp_index\":" << de->opp_index() << ","
+                << "\"edgeinfo_offset\":" << de->edgeinfo_offset() << ","
+                << "\"forward\":" << de->forward() << ","
+                << "\"leaves_tile\":" << de->leaves_tile() << ","
+                << "\"length\":" << de->length() << ","
+                << "\"speed\":" << de->speed() << ","
+                << "\"free_flow_speed\":" << de->free_flow_speed() << ","
+                << "\"constrained_flow_speed\":" << de->constrained_flow_speed() << ","
+                << "\"truck_speed\":" << de->truck_speed() << ","
+                << "\"use\":" << static_cast<int>(de->use()) << ","
+                << "\"classification\":" << static_cast<int>(de->classification()) << ","
+                << "\"surface\":" << static_cast<int>(de->surface()) << ","
+                << "\"lanecount\":" << de->lanecount() << ","
+                << "\"density\":" << de->density() << ","
+                << "\"toll\":" << de->toll() << ","
+                << "\"roundabout\":" << de->roundabout() << ","
+                << "\"truck_route\":" << de->truck_route() << ","
+                << "\"has_predicted_speed\":" << de->has_predicted_speed() << ","
+                << "\"internal\":" << de->internal() << ","
+                << "\"is_shortcut\":" << de->is_shortcut() << ","
+                << "\"superseded\":" << de->superseded() << ","
+                << "\"forwardaccess\":" << de->forwardaccess() << ","
+                << "\"reverseaccess\":" << de->reverseaccess() << ","
+                << "\"cyclelane\":" << static_cast<int>(de->cyclelane()) << ","
+                << "\"bike_network\":" << de->bike_network() << ","
+                << "\"destonly\":" << de->destonly() << ","
+                << "\"not_thru\":" << de->not_thru() << ","
+                << "\"ctry_crossing\":" << de->ctry_crossing() << ","
+                << "\"bss_connection\":" << de->bss_connection() << ","
+                << "\"localedgeidx\":" << de->localedgeidx() << ","
+                << "\"opp_local_idx\":" << de->opp_local_idx() << ","
+                << "\"stopimpact\":" << stop_impacts << ","
+                << "\"edge_to_left\":" << edges_to_left << ","
+                << "\"edge_to_right\":" << edges_to_right << ","
+                << "\"traffic_signal\":" << de->traffic_signal() << ","
+                << "\"stop_sign\":" << de->stop_sign() << ","
+                << "\"yield_sign\":" << de->yield_sign()
+                << "}\n";
+      // clang-format on
+    }
+  }
+
+  return EXIT_SUCCESS;
+}
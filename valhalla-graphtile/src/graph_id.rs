@@ -1,8 +1,11 @@
 use crate::tile_hierarchy::{STANDARD_LEVELS, TRANSIT_LEVEL};
 #[cfg(feature = "serde")]
-use serde::{Serialize, Serializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Visitor};
 use std::fmt::{Display, Formatter};
+use std::io::BufRead;
+use std::num::ParseIntError;
 use std::path::PathBuf;
+use std::str::FromStr;
 use thiserror::Error;
 use zerocopy::{LE, U64};
 use zerocopy_derive::{FromBytes, Immutable, IntoBytes, Unaligned};
@@ -37,6 +40,34 @@ pub enum InvalidGraphIdError {
     InvalidGraphId,
 }
 
+#[derive(Debug, Error, PartialEq)]
+pub enum GraphIdParseError {
+    #[error("Unrecognized graph ID format (expected a u64 or level/tile/index).")]
+    UnrecognizedFormat,
+    #[error("Invalid {component} in graph ID: {source}")]
+    InvalidComponent {
+        component: &'static str,
+        source: ParseIntError,
+    },
+    #[error(transparent)]
+    InvalidGraphId(#[from] InvalidGraphIdError),
+}
+
+/// An error encountered while reading a list of graph IDs (see [`GraphId::parse_many`]).
+#[derive(Debug, Error)]
+pub enum GraphIdListError {
+    #[error("I/O error reading graph ID list: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Line {line}: {source} (input: {content:?})")]
+    Parse {
+        /// The line number (1-based).
+        line: usize,
+        /// The offending input, without comments or surrounding whitespace.
+        content: String,
+        source: GraphIdParseError,
+    },
+}
+
 /// An Identifier of a node or an edge within the tiled, hierarchical graph.
 /// It packs a hierarchy level, tile ID, and an identifier within
 /// the tile/level into a 64-bit integer.
@@ -263,6 +294,91 @@ impl GraphId {
         // Build and return the final string
         Ok(PathBuf::from(self.level().to_string()).join(tile_id_component))
     }
+
+    /// Parses a list of graph IDs, one per line.
+    ///
+    /// Each line may use any format accepted by the [`FromStr`] impl
+    /// (raw `u64` values and `level/tile/index` triples can be mixed freely).
+    /// Blank lines are skipped, and anything following a `#` is treated as a comment.
+    ///
+    /// Errors are reported per line, so callers can decide whether to skip bad entries
+    /// or report all of them at once rather than bailing on the first one.
+    pub fn parse_many<R: BufRead>(
+        reader: R,
+    ) -> impl Iterator<Item = Result<GraphId, GraphIdListError>> {
+        reader
+            .lines()
+            .enumerate()
+            .filter_map(|(i, line)| match line {
+                Ok(line) => {
+                    let content = line.split('#').next().unwrap_or_default().trim();
+                    if content.is_empty() {
+                        None
+                    } else {
+                        Some(content.parse().map_err(|source| GraphIdListError::Parse {
+                            line: i + 1,
+                            content: content.to_string(),
+                            source,
+                        }))
+                    }
+                }
+                Err(e) => Some(Err(e.into())),
+            })
+    }
+
+    /// Reads a whole list of graph IDs (see [`GraphId::parse_many`] for the format).
+    ///
+    /// # Errors
+    ///
+    /// If any lines can't be read or parsed, returns the errors for all of them
+    /// (so they can be fixed in one go).
+    pub fn read_list<R: BufRead>(reader: R) -> Result<Vec<GraphId>, Vec<GraphIdListError>> {
+        let mut graph_ids = Vec::new();
+        let mut errors = Vec::new();
+        for result in Self::parse_many(reader) {
+            match result {
+                Ok(graph_id) => graph_ids.push(graph_id),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(graph_ids)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl FromStr for GraphId {
+    type Err = GraphIdParseError;
+
+    /// Parses a graph ID from either its raw `u64` value or a `level/tile/index` triple.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(id) = s.parse::<u64>() {
+            return Ok(GraphId::try_from_id(id)?);
+        }
+
+        let parse_component = |component, value: &str| {
+            value
+                .trim()
+                .parse::<u64>()
+                .map_err(|source| GraphIdParseError::InvalidComponent { component, source })
+        };
+
+        let mut parts = s.split('/');
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(level), Some(tile_id), Some(index), None) => {
+                let level = u8::try_from(parse_component("level", level)?)
+                    .map_err(|_| InvalidGraphIdError::Level)?;
+                let tile_id = parse_component("tile ID", tile_id)?;
+                let index = parse_component("index", index)?;
+                Ok(GraphId::try_from_components(level, tile_id, index)?)
+            }
+            _ => Err(GraphIdParseError::UnrecognizedFormat),
+        }
+    }
 }
 
 impl Display for GraphId {
@@ -276,6 +392,9 @@ impl Display for GraphId {
     }
 }
 
+/// Serializes as the raw `u64` value.
+///
+/// Use [`hierarchical_graph_id`] for the `level/tile/index` string representation.
 #[cfg(feature = "serde")]
 impl Serialize for GraphId {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -286,6 +405,91 @@ impl Serialize for GraphId {
     }
 }
 
+/// Accepts either the raw `u64` value, or a `level/tile/index` string.
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for GraphId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct GraphIdVisitor;
+
+        impl Visitor<'_> for GraphIdVisitor {
+            type Value = GraphId;
+
+            fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+                formatter.write_str("a graph ID (as a u64 or level/tile/index string)")
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                GraphId::try_from_id(v).map_err(E::custom)
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                let v = u64::try_from(v).map_err(E::custom)?;
+                self.visit_u64(v)
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                v.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(GraphIdVisitor)
+    }
+}
+
+/// Serde helpers which represent a [`GraphId`] as a `level/tile/index` string
+/// (the same format used by Valhalla tooling like `valhalla_ways_to_edges`).
+///
+/// Use this via the `with` attribute:
+///
+/// ```
+/// # use serde::{Deserialize, Serialize};
+/// # use valhalla_graphtile::GraphId;
+/// #[derive(Serialize, Deserialize)]
+/// struct Edge {
+///     #[serde(with = "valhalla_graphtile::hierarchical_graph_id")]
+///     id: GraphId,
+/// }
+/// ```
+///
+/// Deserialization also accepts raw `u64` values.
+#[cfg(feature = "serde")]
+pub mod hierarchical_graph_id {
+    use super::GraphId;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// Serializes the graph ID as a `level/tile/index` string.
+    ///
+    /// # Errors
+    ///
+    /// Only fails if the underlying serializer does.
+    pub fn serialize<S>(graph_id: &GraphId, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(&format_args!(
+            "{}/{}/{}",
+            graph_id.level(),
+            graph_id.tile_id(),
+            graph_id.feature_index()
+        ))
+    }
+
+    /// Deserializes a graph ID from either a `level/tile/index` string or a raw `u64`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the value is not in one of these formats, or is not a valid graph ID.
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<GraphId, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        GraphId::deserialize(deserializer)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -404,4 +608,105 @@ mod tests {
             Ok("3/001/000/000.gph".into())
         );
     }
+
+    #[test]
+    fn test_from_str() {
+        let expected = GraphId::try_from_components(0, 3015, 12).unwrap();
+        assert_eq!("0/3015/12".parse(), Ok(expected));
+        assert_eq!(expected.value().to_string().parse(), Ok(expected));
+        assert_eq!(" 0 / 3015 / 12 ".parse(), Ok(expected));
+
+        assert_eq!(
+            "0/3015".parse::<GraphId>(),
+            Err(GraphIdParseError::UnrecognizedFormat)
+        );
+        assert_eq!(
+            "8/0/0".parse::<GraphId>(),
+            Err(GraphIdParseError::InvalidGraphId(
+                InvalidGraphIdError::Level
+            ))
+        );
+        assert!(matches!(
+            "0/x/0".parse::<GraphId>(),
+            Err(GraphIdParseError::InvalidComponent {
+                component: "tile ID",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_many() {
+        let input = "# Edges to avoid\n\
+            0/3015/12\n\
+            \n\
+            16889572344463360  # raw ID\n\
+            2/762485/nope\n\
+            1/47701/0\n";
+        let results: Vec<_> = GraphId::parse_many(input.as_bytes()).collect();
+        assert_eq!(results.len(), 4);
+
+        assert_eq!(
+            results[0].as_ref().unwrap(),
+            &GraphId::try_from_components(0, 3015, 12).unwrap()
+        );
+        assert_eq!(
+            results[1].as_ref().unwrap(),
+            &GraphId::try_from_id(16_889_572_344_463_360).unwrap()
+        );
+        let Err(GraphIdListError::Parse { line, content, .. }) = &results[2] else {
+            panic!("Expected a parse error; got {:?}", results[2]);
+        };
+        assert_eq!(*line, 5);
+        assert_eq!(content, "2/762485/nope");
+        assert_eq!(
+            results[3].as_ref().unwrap(),
+            &GraphId::try_from_components(1, 47701, 0).unwrap()
+        );
+
+        // Reading the whole list reports every bad line
+        let errors = GraphId::read_list(format!("{input}x\n").as_bytes()).unwrap_err();
+        let lines: Vec<_> = errors
+            .iter()
+            .map(|e| match e {
+                GraphIdListError::Parse { line, .. } => *line,
+                GraphIdListError::Io(e) => panic!("Unexpected I/O error: {e}"),
+            })
+            .collect();
+        assert_eq!(lines, vec![5, 7]);
+
+        let graph_ids = GraphId::read_list("0/3015/12\n1/47701/0 # comment\n".as_bytes()).unwrap();
+        assert_eq!(graph_ids.len(), 2);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Wrapper {
+            raw: GraphId,
+            #[serde(with = "hierarchical_graph_id")]
+            hierarchical: GraphId,
+        }
+
+        let graph_id = GraphId::try_from_components(2, 762_485, 42).unwrap();
+        let wrapper = Wrapper {
+            raw: graph_id,
+            hierarchical: graph_id,
+        };
+
+        let json = serde_json::to_value(&wrapper).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"raw": graph_id.value(), "hierarchical": "2/762485/42"})
+        );
+        assert_eq!(serde_json::from_value::<Wrapper>(json).unwrap(), wrapper);
+
+        // Either representation is accepted for deserialization
+        let json = serde_json::json!({"raw": "2/762485/42", "hierarchical": graph_id.value()});
+        assert_eq!(serde_json::from_value::<Wrapper>(json).unwrap(), wrapper);
+
+        assert!(serde_json::from_value::<GraphId>(serde_json::json!("2/762485")).is_err());
+        assert!(serde_json::from_value::<GraphId>(serde_json::json!(-1)).is_err());
+    }
 }
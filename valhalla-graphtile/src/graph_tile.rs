@@ -6,12 +6,16 @@
 //! Start at the [`GraphTileView`], which can reinterpret a byte slice safely as a tile,
 //! and work down from there as needed.
 //! For writing tiles, a safe builder API is provided in [`GraphTileBuilder`].
+use std::borrow::Cow;
+use std::collections::HashSet;
+#[cfg(feature = "fs")]
 use std::sync::Arc;
 use thiserror::Error;
 use zerocopy::{FromBytes, I16, LE, U32};
 
 use enumset::EnumSet;
-use geo::{CoordFloat, Point, coord};
+use geo::{CoordFloat, Point, Rect, coord};
+#[cfg(feature = "fs")]
 use memmap2::MmapRaw;
 use num_traits::FromPrimitive;
 use self_cell::self_cell;
@@ -27,33 +31,46 @@ use std::sync::LazyLock;
 mod access_restriction;
 mod admin;
 mod builder;
+mod complex_restriction;
 mod directed_edge;
 mod edge_info;
 mod header;
 mod node;
 pub mod predicted_speeds;
 mod sign;
+mod speed;
 mod transit;
 mod turn_lane;
+mod validation;
 
+use crate::AsCowStr;
+use crate::graph_tile::complex_restriction::decode_complex_restrictions;
 use crate::graph_tile::predicted_speeds::{
-    COEFFICIENT_COUNT, PredictedSpeedCodecError, PredictedSpeeds,
+    BUCKETS_PER_WEEK, COEFFICIENT_COUNT, PredictedSpeedCodecError, PredictedSpeeds,
 };
+use crate::graph_tile::turn_lane::decode_turn_lanes;
 use crate::spatial::DistanceApproximator;
+use crate::tile_provider::GraphTileProviderError;
+use crate::traffic_tile::TrafficSpeed;
 pub use crate::{
     Access,
     graph_id::{GraphId, InvalidGraphIdError},
 };
 pub use access_restriction::{AccessRestriction, AccessRestrictionType};
-pub use admin::Admin;
-pub use builder::GraphTileBuilder;
+pub use admin::{Admin, AdminInfo};
+pub use builder::{GraphTileBuilder, NewDirectedEdge, NewNode};
+pub use complex_restriction::{ComplexRestriction, RestrictionType, TimeDomain};
 pub use directed_edge::{DirectedEdge, DirectedEdgeExt};
-pub use edge_info::EdgeInfo;
+pub use edge_info::{EdgeInfo, HEADING_SAMPLE_DISTANCE, TaggedValue, TaggedValueType};
 pub use header::GraphTileHeader;
 pub use node::{NodeInfo, NodeTransition};
 pub use sign::{Sign, SignType};
+pub use speed::{EdgeSpeed, SpeedSource};
 pub use transit::{TransitDeparture, TransitRoute, TransitSchedule, TransitStop, TransitTransfer};
-pub use turn_lane::TurnLane;
+pub use turn_lane::{
+    TurnLane, TurnLaneDirection, UnknownTurnLaneDirectionError, parse_osm_turn_lanes,
+};
+pub use validation::{ValidationIssue, ValidationReport};
 
 #[derive(Debug, Error)]
 pub enum GraphTileDecodingError {
@@ -80,6 +97,22 @@ pub enum GraphTileDecodingError {
     UnsupportedTileLevel(u8),
 }
 
+impl GraphTileDecodingError {
+    /// Could retrying the same operation succeed?
+    ///
+    /// Decoding is deterministic, so this is always false.
+    pub const fn is_retryable(&self) -> bool {
+        false
+    }
+
+    /// Does this error indicate that the tile data is corrupt (or otherwise invalid)?
+    ///
+    /// This is true for everything except tiles which are valid, but unsupported.
+    pub const fn is_data_corruption(&self) -> bool {
+        !matches!(self, Self::UnsupportedTileLevel(_))
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum GraphTileBuildError {
     #[error("{0}")]
@@ -92,6 +125,10 @@ pub enum GraphTileBuildError {
         "Bitfield overflow: Value {value} for field {field} exceeds the allowed number of bits."
     )]
     BitfieldOverflow { field: String, value: usize },
+    #[error("Unsupported tile level: {0} (only the standard hierarchy levels are supported).")]
+    UnsupportedLevel(u8),
+    #[error("Invalid feature: {0}.")]
+    InvalidFeature(String),
     #[error("{0:?}")]
     PredictedSpeedCodec(#[from] PredictedSpeedCodecError),
     #[error(
@@ -102,6 +139,12 @@ pub enum GraphTileBuildError {
     IoError(#[from] std::io::Error),
     #[error("Poisoned cache lock when writing tile: {0}")]
     PoisonedCacheLock(String),
+    #[error("Error loading tile: {0}")]
+    LoadError(#[from] GraphTileProviderError),
+    #[error(
+        "The tile would change size from {expected} to {actual} bytes, but it must be rewritten in place."
+    )]
+    TileSizeChanged { expected: usize, actual: usize },
 }
 
 #[derive(Debug, Error)]
@@ -112,6 +155,25 @@ pub enum LookupError {
     InvalidIndex,
 }
 
+impl LookupError {
+    /// Could retrying the same lookup succeed?
+    ///
+    /// Lookups are deterministic, so this is always false.
+    pub const fn is_retryable(&self) -> bool {
+        false
+    }
+
+    /// Does this error indicate that the tile data is corrupt?
+    ///
+    /// Lookup errors are attributed to the graph ID that was requested,
+    /// so this is always false.
+    /// If the ID came from the tile itself (e.g. an end node),
+    /// the caller should treat an [`LookupError::InvalidIndex`] as corruption instead.
+    pub const fn is_data_corruption(&self) -> bool {
+        false
+    }
+}
+
 pub trait GraphTile {
     /// Gets the Graph ID of the tile.
     fn graph_id(&self) -> GraphId;
@@ -155,6 +217,21 @@ pub trait GraphTile {
     /// Gets a slice of all (outbound) transitions (links to other levels) from a node.
     fn get_transitions(&self, node: &NodeInfo) -> &[NodeTransition];
 
+    /// Gets a slice of all transitions (links to other levels) from the node with the given ID.
+    ///
+    /// This is a convenience wrapper around [`get_node`](GraphTile::get_node)
+    /// and [`get_transitions`](GraphTile::get_transitions).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the node ID cannot be contained in this tile
+    /// or the index is invalid.
+    #[inline]
+    fn get_transitions_for_node(&self, node_id: GraphId) -> Result<&[NodeTransition], LookupError> {
+        let node = self.get_node(node_id)?;
+        Ok(self.get_transitions(node))
+    }
+
     /// Gets a reference to the directed edge in this tile by graph ID.
     ///
     /// # Errors
@@ -193,6 +270,52 @@ pub trait GraphTile {
         access_modes: EnumSet<Access>,
     ) -> Vec<&AccessRestriction>;
 
+    /// Gets the turn lanes at the end of a directed edge, from left to right.
+    ///
+    /// Each lane is the set of directions that it allows.
+    /// The result is empty if the edge has no turn lanes.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the turn lane text can't be decoded.
+    fn get_turn_lanes(
+        &self,
+        directed_edge_index: u32,
+    ) -> Result<Vec<EnumSet<TurnLaneDirection>>, GraphTileDecodingError>;
+
+    /// Gets the signs for a directed edge (ex: exit numbers and destinations), with their text.
+    ///
+    /// Signs which belong to a node with the same index (junction and toll names)
+    /// are excluded, as are linguistic records and signs with tagged text.
+    /// The result is empty if the edge has no signs.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a sign's text offset is outside the text list.
+    fn get_edge_signs(
+        &self,
+        directed_edge_index: u32,
+    ) -> Result<Vec<(SignType, Cow<'_, str>)>, GraphTileDecodingError>;
+
+    /// Gets the complex (multi-edge) restrictions involving a directed edge in this tile
+    /// which affect any of the given access modes.
+    ///
+    /// Valhalla stores each restriction twice.
+    /// When `forward` is true, this searches the forward list,
+    /// which holds restrictions ending on the edge (for forward path expansion).
+    /// Otherwise it searches the reverse list, which holds restrictions starting on the edge.
+    /// Restrictions are only stored in the tile containing the edge they are keyed on.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the restriction lists can't be decoded.
+    fn get_complex_restrictions(
+        &self,
+        edge_id: GraphId,
+        forward: bool,
+        access_modes: EnumSet<Access>,
+    ) -> Result<Vec<ComplexRestriction>, GraphTileDecodingError>;
+
     /// Gets predicted speed information for a directed edge.
     ///
     /// `seconds_from_start_of_week` is measured from midnight Sunday **local time**.
@@ -204,6 +327,52 @@ pub trait GraphTile {
         seconds_from_start_of_week: u32,
     ) -> Option<f32>;
 
+    /// Gets the predicted speeds for every 5-minute bucket of the week for a directed edge.
+    ///
+    /// Buckets start at midnight Sunday **local time**.
+    /// The output is measured in kilometers per hour.
+    /// Returns `None` if the edge at this index does not have predicted speed information.
+    ///
+    /// This is much faster than calling [`GraphTile::get_predicted_speed`] for every bucket.
+    fn get_predicted_weekly_profile(
+        &self,
+        directed_edge_index: usize,
+    ) -> Option<[f32; BUCKETS_PER_WEEK]>;
+
+    /// Gets the speed to use for a directed edge in this tile, falling back through the sources
+    /// in `flow_mask` in order of precedence:
+    /// live traffic, predicted speeds, constrained or free flow (depending on the time of day),
+    /// and finally the edge's default speed.
+    ///
+    /// `seconds_of_week` is measured from midnight Sunday **local time**.
+    /// When it is `None`, predicted speeds are skipped,
+    /// and free flow is preferred over constrained flow.
+    /// `live` is the edge's speed from a traffic tile, if any
+    /// (see [`TrafficTileProvider`](crate::tile_provider::TrafficTileProvider)).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the graph ID cannot be contained in this tile
+    /// or the index is invalid.
+    fn speed_for_edge(
+        &self,
+        edge_id: GraphId,
+        seconds_of_week: Option<u32>,
+        live: Option<TrafficSpeed>,
+        flow_mask: EnumSet<SpeedSource>,
+    ) -> Result<EdgeSpeed, LookupError> {
+        let edge = self.get_directed_edge(edge_id)?;
+        let index =
+            usize::try_from(edge_id.feature_index()).map_err(|_| LookupError::InvalidIndex)?;
+        Ok(speed::select_speed(
+            edge,
+            seconds_of_week,
+            live,
+            flow_mask,
+            |seconds| self.get_predicted_speed(index, seconds),
+        ))
+    }
+
     /// Gets edge info for a directed edge.
     ///
     /// Note that this is NOT a zero-cost operation.
@@ -231,9 +400,65 @@ pub trait GraphTile {
     /// A raw slice of the tile's nodes (i.e. for iteration).
     fn nodes(&self) -> &[NodeInfo];
 
+    /// Iterates over all directed edges in the tile, along with their graph IDs.
+    #[inline]
+    fn edges_with_ids(&self) -> impl Iterator<Item = (GraphId, &DirectedEdge)> {
+        let base_id = self.graph_id().tile_base_id();
+        self.directed_edges()
+            .iter()
+            .enumerate()
+            // The header limits the edge count to the valid index range, so this never skips.
+            .filter_map(move |(index, edge)| {
+                Some((base_id.with_feature_index(index as u64).ok()?, edge))
+            })
+    }
+
+    /// Iterates over all nodes in the tile, along with their graph IDs.
+    #[inline]
+    fn nodes_with_ids(&self) -> impl Iterator<Item = GraphNode<'_>> {
+        let base_id = self.graph_id().tile_base_id();
+        self.nodes()
+            .iter()
+            .enumerate()
+            // The header limits the node count to the valid index range, so this never skips.
+            .filter_map(move |(index, node_info)| {
+                Some(GraphNode {
+                    node_id: base_id.with_feature_index(index as u64).ok()?,
+                    node_info,
+                })
+            })
+    }
+
+    /// Iterates over the outbound edges from a node, along with their graph IDs.
+    ///
+    /// # Correctness
+    ///
+    /// The same caveats apply as for [`GraphTile::get_outbound_edges_from_node`].
+    #[inline]
+    fn outbound_edges_with_ids(
+        &self,
+        node_info: &NodeInfo,
+    ) -> impl Iterator<Item = (GraphId, &DirectedEdge)> {
+        let base_id = self.graph_id().tile_base_id();
+        let start = u64::from(node_info.edge_index());
+        self.get_outbound_edges_from_node(node_info)
+            .iter()
+            .zip(start..)
+            .filter_map(move |(edge, index)| Some((base_id.with_feature_index(index).ok()?, edge)))
+    }
+
     /// Administrative regions covered in this tile.
     fn admins(&self) -> &[Admin];
 
+    /// Gets the administrative region containing a node,
+    /// with its names resolved from the tile's text list.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the node ID cannot be contained in this tile
+    /// or either the node or admin index is invalid.
+    fn get_admin_for_node(&self, node_id: GraphId) -> Result<AdminInfo<'_>, LookupError>;
+
     /// Returns the list of edge IDs contained in the specified bin.
     ///
     /// The bin contents are stored as a single concatenated array with prefix offsets
@@ -256,6 +481,66 @@ pub trait GraphTile {
     /// (specifically, they must each be <= the square root of [`crate::BIN_COUNT`]).
     fn bin_index_xy(&self, x: usize, y: usize) -> usize;
 
+    /// Checks the internal consistency of the tile.
+    ///
+    /// This verifies the references between the tile's data structures
+    /// (edge and transition ranges, end nodes and opposing edges within the tile,
+    /// edge info and text offsets, restriction ordering, edge bins, etc.).
+    /// Lookup methods trust these references, and may panic if they are broken,
+    /// so this is useful for catching corrupt extracts before they are put into service.
+    fn validate(&self) -> ValidationReport;
+
+    /// Gets the candidate edges from all bins in this tile which overlap the given bounding box.
+    ///
+    /// Valhalla only populates the edge bins in tiles on the lowest (local) level,
+    /// but each bin lists the edges from _every_ level (and from neighboring tiles)
+    /// whose shapes pass through it.
+    /// So the returned IDs may refer to edges in other tiles.
+    /// Tiles without bins (or which don't overlap the bbox) yield no candidates.
+    ///
+    /// This is a candidate search; the edges may not actually intersect the bbox.
+    /// Each edge is returned at most once, in the order it was first encountered
+    /// (bins are scanned in row-major order).
+    ///
+    /// # Panics
+    ///
+    /// This function assumes that the level in the graph tile header is a standard Valhalla level.
+    /// Non-standard values will cause a panic.
+    fn edges_in_bbox<F: CoordFloat + FromPrimitive>(&self, bbox: Rect<F>) -> Vec<GraphId> {
+        let level = self.header().graph_id().level();
+        let tiling_system = &crate::tile_hierarchy::STANDARD_LEVELS
+            .iter()
+            .find(|lvl| lvl.level == level)
+            .expect("Only Valhalla standard tile levels are supported")
+            .tiling_system;
+        let n_subdivisions = f64::from(tiling_system.n_subdivisions);
+        let bin_size = f64::from(tiling_system.tile_size) / n_subdivisions;
+        let sw = self.header().sw_corner();
+
+        // Bin coordinates (relative to the SW corner of the tile), as floating point values
+        let to_bin = |value: F, origin: f32| {
+            (value.to_f64().expect("Unable to convert floating point") - f64::from(origin))
+                / bin_size
+        };
+        let (min, max) = (bbox.min(), bbox.max());
+        let (min_x, max_x) = (to_bin(min.x, sw.x), to_bin(max.x, sw.x));
+        let (min_y, max_y) = (to_bin(min.y, sw.y), to_bin(max.y, sw.y));
+        if max_x < 0.0 || max_y < 0.0 || min_x >= n_subdivisions || min_y >= n_subdivisions {
+            return Vec::new();
+        }
+
+        // The values are clamped to [0, n_subdivisions), so the casts are lossless
+        #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let clamp = |value: f64| value.floor().clamp(0.0, n_subdivisions - 1.0) as usize;
+        let mut seen = HashSet::new();
+        (clamp(min_y)..=clamp(max_y))
+            .flat_map(|y| (clamp(min_x)..=clamp(max_x)).map(move |x| (x, y)))
+            .flat_map(|(x, y)| self.edges_in_bin(self.bin_index_xy(x, y)))
+            .filter(|edge_id| seen.insert(**edge_id))
+            .copied()
+            .collect()
+    }
+
     /// Returns an iterator over all nodes near a specified point,
     /// within this tile, including the approximate distance from the point.
     ///
@@ -292,10 +577,7 @@ pub trait GraphTile {
         center: Point<F>,
         radius_in_meters: F,
     ) -> impl Iterator<Item = (GraphNode<'_>, F)> {
-        let header = self.header();
-        let level = header.graph_id().level();
-        let tile_id = header.graph_id().tile_id();
-        let sw = header.sw_corner();
+        let sw = self.header().sw_corner();
 
         // Set up an approximator based on square distance.
         // This will always over-estimate (by about 5% for smaller distances).
@@ -305,22 +587,18 @@ pub trait GraphTile {
         let approximator = DistanceApproximator::new(center.into());
         let radius_squared = radius_in_meters * radius_in_meters;
 
-        self.nodes()
-            .into_iter()
-            .enumerate()
-            .filter_map(move |(idx, node_info)| {
-                let node_id = GraphId::try_from_components(level, tile_id, idx as u64).ok()?;
-                let nc = node_info.coordinate(sw);
-
-                let nc_lon = F::from(nc.x).expect("Unable to convert floating point");
-                let nc_lat = F::from(nc.y).expect("Unable to convert floating point");
-                let sq_dist = approximator.distance_squared(coord! {x: nc_lon, y: nc_lat});
-                if sq_dist <= radius_squared {
-                    Some((GraphNode { node_id, node_info }, sq_dist.sqrt()))
-                } else {
-                    None
-                }
-            })
+        self.nodes_with_ids().filter_map(move |node| {
+            let nc = node.node_info.coordinate(sw);
+
+            let nc_lon = F::from(nc.x).expect("Unable to convert floating point");
+            let nc_lat = F::from(nc.y).expect("Unable to convert floating point");
+            let sq_dist = approximator.distance_squared(coord! {x: nc_lon, y: nc_lat});
+            if sq_dist <= radius_squared {
+                Some((node, sq_dist.sqrt()))
+            } else {
+                None
+            }
+        })
     }
 }
 
@@ -348,6 +626,7 @@ self_cell! {
 ///
 /// This provides a single type that can "own" the backing bytes for tile memory,
 /// which we leverage in the `self_cell` to create a zero-copy view.
+#[cfg(feature = "fs")]
 pub struct MmapTilePointer {
     /// A handle to the memory map that can be shared across threads.
     pub(crate) mmap: Arc<MmapRaw>,
@@ -355,6 +634,7 @@ pub struct MmapTilePointer {
     pub(crate) offsets: TileOffset,
 }
 
+#[cfg(feature = "fs")]
 impl MmapTilePointer {
     /// Returns a slice view over the data mapped in memory.
     ///
@@ -420,9 +700,7 @@ impl MmapTilePointer {
     pub unsafe fn read_volatile<T>(&self) -> T {
         assert_eq!(
             size_of::<T>(),
-            self.offsets
-                .size
-                .try_into()
+            usize::try_from(self.offsets.size)
                 .expect("u32 does not fit into usize... that's unexpected!"),
             "You can't try an unsafe cast on a byte range of the wrong size!"
         );
@@ -503,6 +781,7 @@ impl MmapTilePointer {
 }
 
 /// A tile offset, used for internal storage out of the parsed index.
+#[cfg(any(feature = "fs", feature = "http"))]
 #[derive(Copy, Clone)]
 pub(crate) struct TileOffset {
     /// Byte offset from the beginning of the tar
@@ -568,6 +847,33 @@ impl GraphTile for OwnedGraphTileHandle {
             .get_access_restrictions(directed_edge_index, access_modes)
     }
 
+    #[inline]
+    fn get_turn_lanes(
+        &self,
+        directed_edge_index: u32,
+    ) -> Result<Vec<EnumSet<TurnLaneDirection>>, GraphTileDecodingError> {
+        self.borrow_dependent().get_turn_lanes(directed_edge_index)
+    }
+
+    #[inline]
+    fn get_edge_signs(
+        &self,
+        directed_edge_index: u32,
+    ) -> Result<Vec<(SignType, Cow<'_, str>)>, GraphTileDecodingError> {
+        self.borrow_dependent().get_edge_signs(directed_edge_index)
+    }
+
+    #[inline]
+    fn get_complex_restrictions(
+        &self,
+        edge_id: GraphId,
+        forward: bool,
+        access_modes: EnumSet<Access>,
+    ) -> Result<Vec<ComplexRestriction>, GraphTileDecodingError> {
+        self.borrow_dependent()
+            .get_complex_restrictions(edge_id, forward, access_modes)
+    }
+
     #[inline]
     fn get_predicted_speed(
         &self,
@@ -578,6 +884,15 @@ impl GraphTile for OwnedGraphTileHandle {
             .get_predicted_speed(directed_edge_index, seconds_from_start_of_week)
     }
 
+    #[inline]
+    fn get_predicted_weekly_profile(
+        &self,
+        directed_edge_index: usize,
+    ) -> Option<[f32; BUCKETS_PER_WEEK]> {
+        self.borrow_dependent()
+            .get_predicted_weekly_profile(directed_edge_index)
+    }
+
     #[inline]
     fn get_edge_info(
         &self,
@@ -601,6 +916,11 @@ impl GraphTile for OwnedGraphTileHandle {
         self.borrow_dependent().admins()
     }
 
+    #[inline]
+    fn get_admin_for_node(&self, node_id: GraphId) -> Result<AdminInfo<'_>, LookupError> {
+        self.borrow_dependent().get_admin_for_node(node_id)
+    }
+
     #[inline]
     fn edges_in_bin(&self, bin_index: usize) -> &[GraphId] {
         self.borrow_dependent().edges_in_bin(bin_index)
@@ -610,6 +930,10 @@ impl GraphTile for OwnedGraphTileHandle {
     fn bin_index_xy(&self, x: usize, y: usize) -> usize {
         self.borrow_dependent().bin_index_xy(x, y)
     }
+
+    fn validate(&self) -> ValidationReport {
+        self.borrow_dependent().validate()
+    }
 }
 
 impl TryFrom<Vec<u8>> for OwnedGraphTileHandle {
@@ -750,6 +1074,81 @@ impl GraphTile for GraphTileView<'_> {
             .collect()
     }
 
+    fn get_turn_lanes(
+        &self,
+        directed_edge_index: u32,
+    ) -> Result<Vec<EnumSet<TurnLaneDirection>>, GraphTileDecodingError> {
+        // Like access restrictions, turn lanes are sorted by edge index
+        let index = self
+            .turn_lanes
+            .partition_point(|lane| lane.directed_edge_index() < directed_edge_index);
+        let Some(turn_lane) = self
+            .turn_lanes
+            .get(index)
+            .filter(|lane| lane.directed_edge_index() == directed_edge_index)
+        else {
+            return Ok(Vec::new());
+        };
+
+        let text = self
+            .text_memory
+            .get(turn_lane.text_offset.get() as usize..)
+            .ok_or(GraphTileDecodingError::SliceLength)?;
+        decode_turn_lanes(&text.as_cow_str())
+    }
+
+    fn get_edge_signs(
+        &self,
+        directed_edge_index: u32,
+    ) -> Result<Vec<(SignType, Cow<'_, str>)>, GraphTileDecodingError> {
+        // Signs are sorted by edge (or node) index
+        let start = self
+            .signs
+            .partition_point(|sign| sign.edge_or_node_index() < directed_edge_index);
+        self.signs[start..]
+            .iter()
+            .take_while(|sign| sign.edge_or_node_index() == directed_edge_index)
+            .filter(|sign| {
+                let sign_type = sign.sign_type();
+                !sign_type.is_node_sign()
+                    && sign_type != SignType::Linguistic
+                    && !sign.is_text_tagged()
+            })
+            .map(|sign| {
+                let text = self
+                    .text_memory
+                    .get(sign.text_offset.get() as usize..)
+                    .ok_or(GraphTileDecodingError::SliceLength)?;
+                Ok((sign.sign_type(), text.as_cow_str()))
+            })
+            .collect()
+    }
+
+    fn get_complex_restrictions(
+        &self,
+        edge_id: GraphId,
+        forward: bool,
+        access_modes: EnumSet<Access>,
+    ) -> Result<Vec<ComplexRestriction>, GraphTileDecodingError> {
+        // The lists aren't sorted, so this is a linear scan (like in Valhalla)
+        let memory = if forward {
+            self.complex_forward_restrictions_memory
+        } else {
+            self.complex_reverse_restrictions_memory
+        };
+        let mut restrictions = decode_complex_restrictions(memory)?;
+        restrictions.retain(|restriction| {
+            let key = if forward {
+                restriction.to_edge_id
+            } else {
+                restriction.from_edge_id
+            };
+            key == edge_id && !restriction.affected_access_modes.is_disjoint(access_modes)
+        });
+
+        Ok(restrictions)
+    }
+
     fn get_predicted_speed(
         &self,
         directed_edge_index: usize,
@@ -767,6 +1166,18 @@ impl GraphTile for GraphTileView<'_> {
         }
     }
 
+    fn get_predicted_weekly_profile(
+        &self,
+        directed_edge_index: usize,
+    ) -> Option<[f32; BUCKETS_PER_WEEK]> {
+        let ps = self.predicted_speeds.as_ref()?;
+        if self.directed_edges[directed_edge_index].has_predicted_speed() {
+            ps.weekly_profile(directed_edge_index)
+        } else {
+            None
+        }
+    }
+
     fn get_edge_info(
         &self,
         directed_edge: &DirectedEdge,
@@ -791,6 +1202,28 @@ impl GraphTile for GraphTileView<'_> {
         self.admins
     }
 
+    fn get_admin_for_node(&self, node_id: GraphId) -> Result<AdminInfo<'_>, LookupError> {
+        let node = self.get_node(node_id)?;
+        let admin = self
+            .admins
+            .get(usize::from(node.admin_index()))
+            .ok_or(LookupError::InvalidIndex)?;
+
+        let text_at = |offset: u32| {
+            self.text_memory
+                .get(offset as usize..)
+                .map(AsCowStr::as_cow_str)
+                .ok_or(LookupError::InvalidIndex)
+        };
+
+        Ok(AdminInfo {
+            country_iso: admin.country_iso(),
+            principal_subdivision_iso: admin.principal_subdivision_iso(),
+            country_name: text_at(admin.country_name_offset.get())?,
+            principal_subdivision_name: text_at(admin.principal_subdivision_offset.get())?,
+        })
+    }
+
     #[inline]
     fn edges_in_bin(&self, bin_index: usize) -> &[GraphId] {
         if self.edge_bins.is_empty() {
@@ -824,6 +1257,10 @@ impl GraphTile for GraphTileView<'_> {
             .expect("Only Valhalla standard tile levels are supported");
         y * n_subdivisions + x
     }
+
+    fn validate(&self) -> ValidationReport {
+        GraphTileView::validate(self)
+    }
 }
 
 impl<'a> TryFrom<&'a [u8]> for GraphTileView<'a> {
@@ -1155,7 +1592,10 @@ mod tests {
         GraphTile, TEST_GRAPH_TILE_ID_L0, TEST_GRAPH_TILE_L0, TEST_GRAPH_TILE_L2,
         TEST_GRAPH_TILE_WITH_FLOW,
     };
+    use crate::traffic_tile::{SegmentTrafficInfo, SpeedValue, TrafficSpeed, TrafficSpeedBuilder};
     use enumset::{EnumSet, enum_set};
+    use geo::{Haversine, Length, LineString};
+    use std::collections::HashMap;
 
     #[test]
     fn test_get_opp_edge_index() {
@@ -1249,6 +1689,7 @@ mod tests {
         );
 
         // insta internally does a fork operation, which is not supported under Miri
+        #[cfg(feature = "serde")]
         if !cfg!(miri) {
             insta::assert_yaml_snapshot!(tile_view.edge_bins);
         }
@@ -1287,6 +1728,94 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_edges_in_bbox() {
+        let tile = &*TEST_GRAPH_TILE_L2;
+        let sw = tile.header().sw_corner();
+        let tile_size = 0.25;
+
+        // The whole tile covers all bins
+        let all_binned: HashSet<_> = (0..crate::BIN_COUNT)
+            .flat_map(|i| tile.edges_in_bin(i).iter().copied())
+            .collect();
+        let whole_tile = Rect::new(sw, coord! {x: sw.x + tile_size, y: sw.y + tile_size});
+        let all_edges = tile.edges_in_bbox(whole_tile);
+        assert_eq!(all_edges.len(), all_binned.len());
+        assert_eq!(
+            all_edges.iter().copied().collect::<HashSet<_>>(),
+            all_binned
+        );
+        // Bins include edges on other levels, and in neighboring tiles
+        assert!(all_edges.iter().any(|id| id.level() != 2));
+        assert!(
+            all_edges
+                .iter()
+                .any(|id| id.level() == 2 && id.tile_base_id() != tile.graph_id())
+        );
+
+        // A small bbox inside a single bin
+        let small = Rect::new(
+            coord! {x: sw.x + 0.06, y: sw.y + 0.21},
+            coord! {x: sw.x + 0.07, y: sw.y + 0.22},
+        );
+        let small_edges = tile.edges_in_bbox(small);
+        let (x, y) = (1, 4);
+        assert_eq!(small_edges.len(), {
+            let bin = tile.edges_in_bin(tile.bin_index_xy(x, y));
+            bin.iter().collect::<HashSet<_>>().len()
+        });
+        assert!(small_edges.iter().all(|id| all_binned.contains(id)));
+
+        // Disjoint bbox
+        let outside = Rect::new(coord! {x: 10.0, y: 10.0}, coord! {x: 11.0, y: 11.0});
+        assert!(tile.edges_in_bbox(outside).is_empty());
+
+        // Tiles without bins yield no candidates
+        let l0 = &*TEST_GRAPH_TILE_L0;
+        let l0_sw = l0.header().sw_corner();
+        let l0_tile = Rect::new(l0_sw, coord! {x: l0_sw.x + 4.0, y: l0_sw.y + 4.0});
+        assert!(l0.edges_in_bbox(l0_tile).is_empty());
+    }
+
+    #[test]
+    fn test_edges_with_ids() {
+        let tile = &*TEST_GRAPH_TILE_L0;
+
+        let edges: Vec<_> = tile.edges_with_ids().collect();
+        assert_eq!(edges.len(), tile.directed_edges().len());
+        for (edge_id, edge) in edges {
+            assert!(std::ptr::eq(tile.get_directed_edge(edge_id).unwrap(), edge));
+        }
+
+        let node = &tile.nodes()[10];
+        let outbound: Vec<_> = tile.outbound_edges_with_ids(node).collect();
+        assert_eq!(outbound.len(), usize::from(node.edge_count()));
+        for (edge_id, edge) in outbound {
+            assert_eq!(edge_id.tile_base_id(), TEST_GRAPH_TILE_ID_L0);
+            assert!(std::ptr::eq(tile.get_directed_edge(edge_id).unwrap(), edge));
+        }
+    }
+
+    #[test]
+    fn test_nodes_with_ids() {
+        let tile = &*TEST_GRAPH_TILE_L0;
+
+        let mut traffic_signal_count = 0;
+        let mut node_count = 0;
+        for GraphNode { node_id, node_info } in tile.nodes_with_ids() {
+            assert_eq!(node_id.tile_base_id(), TEST_GRAPH_TILE_ID_L0);
+            assert!(std::ptr::eq(tile.get_node(node_id).unwrap(), node_info));
+            node_count += 1;
+            if node_info.is_traffic_signal() {
+                traffic_signal_count += 1;
+            }
+        }
+
+        assert_eq!(node_count, tile.nodes().len());
+        // Hard-coded based on the test fixture
+        assert_eq!(traffic_signal_count, 1);
+    }
+
     #[test]
     fn test_edge_info() {
         let tile = &*TEST_GRAPH_TILE_L0;
@@ -1336,6 +1865,178 @@ mod tests {
         assert_eq!(other_edge_info.way_id(), 28833880);
     }
 
+    #[test]
+    fn test_edge_info_tagged_values() {
+        let tile = &*TEST_GRAPH_TILE_L2;
+        let mut value_type_counts = std::collections::BTreeMap::new();
+        for edge in tile.directed_edges() {
+            let edge_info = tile.get_edge_info(edge).expect("Unable to get edge info.");
+            for value in edge_info.tagged_values() {
+                *value_type_counts
+                    .entry(format!("{:?}", value.value_type()))
+                    .or_insert(0) += 1;
+
+                // Tagged values are never mixed in with the regular names
+                if let TaggedValue::Level(level) = value {
+                    assert_eq!(level, "-1");
+                    assert!(!edge_info.get_names().contains(&level));
+                }
+            }
+        }
+
+        if !cfg!(miri) {
+            insta::assert_debug_snapshot!(value_type_counts);
+        }
+    }
+
+    #[test]
+    fn test_get_edge_signs() {
+        let tile = &*TEST_GRAPH_TILE_L2;
+        // The fixture tiles don't have signs, so add some
+        let tile = OwnedGraphTileHandle::try_from(
+            GraphTileBuilder::from(tile)
+                .with_edge_sign(5, SignType::ExitToward, "Encamp")
+                .unwrap()
+                .with_edge_sign(3, SignType::ExitNumber, "12")
+                .unwrap()
+                .with_edge_sign(5, SignType::ExitNumber, "14")
+                .unwrap()
+                .into_bytes()
+                .unwrap(),
+        )
+        .unwrap();
+
+        let signs = |index| {
+            tile.get_edge_signs(index)
+                .unwrap()
+                .into_iter()
+                .map(|(sign_type, text)| (sign_type, text.into_owned()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(signs(3), [(SignType::ExitNumber, "12".to_string())]);
+        assert_eq!(
+            signs(5),
+            [
+                (SignType::ExitToward, "Encamp".to_string()),
+                (SignType::ExitNumber, "14".to_string())
+            ]
+        );
+        assert!(signs(4).is_empty());
+        assert!(tile.directed_edges()[5].has_exit_signs());
+        assert!(!tile.directed_edges()[4].has_exit_signs());
+
+        // Node signs don't belong to edges
+        assert!(
+            GraphTileBuilder::from(&tile)
+                .with_edge_sign(5, SignType::JunctionName, "Plaça")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_edge_info_headings() {
+        let tile = &*TEST_GRAPH_TILE_L2;
+        let angle_diff = |a: f64, b: f64| {
+            let diff = (a - b).rem_euclid(360.0);
+            diff.min(360.0 - diff)
+        };
+
+        // Opposing edges share edge info, so their headings should mirror each other
+        let mut edges_by_info: HashMap<u32, Vec<&DirectedEdge>> = HashMap::new();
+        for edge in tile.directed_edges() {
+            edges_by_info
+                .entry(edge.edge_info_offset())
+                .or_default()
+                .push(edge);
+        }
+
+        let mut checked = 0;
+        for edges in edges_by_info.values() {
+            let (Some(forward), Some(reverse)) = (
+                edges.iter().find(|e| e.edge_info_is_forward()),
+                edges.iter().find(|e| !e.edge_info_is_forward()),
+            ) else {
+                continue;
+            };
+
+            let edge_info = tile.get_edge_info(forward).unwrap();
+            let Some((begin, end)) = edge_info.headings(true).unwrap() else {
+                continue;
+            };
+            let (reverse_begin, reverse_end) = tile
+                .get_edge_info(reverse)
+                .unwrap()
+                .headings(false)
+                .unwrap()
+                .unwrap();
+
+            assert!((0.0..360.0).contains(&begin));
+            assert!(angle_diff(begin, reverse_end + 180.0) < 1e-6);
+            assert!(angle_diff(end, reverse_begin + 180.0) < 1e-6);
+            checked += 1;
+        }
+
+        assert!(checked > 0);
+    }
+
+    #[test]
+    fn test_edge_info_traffic_segment_shapes() {
+        let tile = &*TEST_GRAPH_TILE_L2;
+        let edge = tile
+            .directed_edges()
+            .iter()
+            .find(|edge| edge.length() > 100)
+            .expect("No long edges in the fixture");
+        let edge_info = tile.get_edge_info(edge).unwrap();
+        let mut shape = edge_info.decode_raw_shape::<f64>().unwrap();
+        if !edge.edge_info_is_forward() {
+            shape.reverse();
+        }
+
+        // Fast, then closed, then slow
+        let length = edge.length();
+        let speed = TrafficSpeedBuilder::with_edge_length(length)
+            .with_speed_segment(SpeedValue::try_new(80).unwrap(), None, length / 4)
+            .unwrap()
+            .with_closed_segment(length / 4)
+            .unwrap()
+            .with_speed_segment(SpeedValue::try_new(20).unwrap(), None, length - length / 2)
+            .unwrap()
+            .build()
+            .unwrap();
+        let segments = edge_info
+            .traffic_segment_shapes(speed, edge.edge_info_is_forward())
+            .unwrap();
+        assert_eq!(segments.len(), 3);
+        assert!(matches!(segments[1].0, SegmentTrafficInfo::Closed { .. }));
+
+        // The segments are contiguous, and cover the whole shape
+        assert_eq!(segments[0].1.0[0], shape[0]);
+        for pair in segments.windows(2) {
+            assert_eq!(pair[0].1.0.last(), pair[1].1.0.first());
+        }
+        let last = segments[2].1.0.last().unwrap();
+        assert!((last.x - shape.last().unwrap().x).abs() < 1e-9);
+        assert!((last.y - shape.last().unwrap().y).abs() < 1e-9);
+
+        // Segment lengths are proportional to the breakpoints
+        let total_length = Haversine.length(&LineString::new(shape));
+        let closed_length = Haversine.length(&segments[1].1);
+        assert!(
+            (closed_length / total_length - 0.25).abs() < 0.01,
+            "Unexpected closed fraction: {}",
+            closed_length / total_length
+        );
+
+        // No data means no segments
+        assert!(
+            edge_info
+                .traffic_segment_shapes(TrafficSpeed::new(), true)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
     #[test]
     fn test_predicted_speed_access_when_absent_in_tile() {
         let tile = &*TEST_GRAPH_TILE_L0;
@@ -1423,11 +2124,8 @@ mod tests {
         assert_eq!(edge.has_predicted_speed(), true);
 
         // Reconstruct the entire array of speeds.
-        let speeds: [i64; BUCKETS_PER_WEEK] = std::array::from_fn(|i| {
-            tile_view
-                .get_predicted_speed(EDGE_INDEX, (i as u32) * 300)
-                .unwrap() as i64
-        });
+        let profile = tile_view.get_predicted_weekly_profile(EDGE_INDEX).unwrap();
+        let speeds: [i64; BUCKETS_PER_WEEK] = profile.map(|s| s as i64);
 
         // Laying it all out, this highlights that the encoding is indeed quite.... lossy ;)
         // This was originally a series going from 0 to 100 in a loop, sawtooth fashion.
@@ -1,4 +1,5 @@
 use crate::Access;
+use crate::graph_tile::{GraphTileBuildError, TimeDomain};
 use bitfield_struct::bitfield;
 use enumset::EnumSet;
 use zerocopy::{LE, U16, U32, U64};
@@ -72,6 +73,27 @@ pub struct AccessRestriction {
 }
 
 impl AccessRestriction {
+    /// Creates a new access restriction.
+    ///
+    /// The meaning of the value depends on the restriction type.
+    /// Like Valhalla, dimension and weight limits are stored in hundredths
+    /// (ex: 350 for a 3.5 tonne weight limit).
+    ///
+    /// The restriction is attached to a directed edge when it is added to a tile
+    /// (see [`GraphTileBuilder::with_access_restriction`](crate::graph_tile::GraphTileBuilder::with_access_restriction)).
+    pub fn new(
+        restriction_type: AccessRestrictionType,
+        affected_access_modes: EnumSet<Access>,
+        value: u64,
+    ) -> Self {
+        Self {
+            bitfield: AccessRestrictionBitField::new()
+                .with_restriction_type(restriction_type)
+                .with_modes(affected_access_modes.as_repr().into()),
+            value: value.into(),
+        }
+    }
+
     /// Gets the edge index (within the tile) to which the restriction applies.
     #[inline]
     pub fn edge_index(&self) -> u32 {
@@ -91,6 +113,39 @@ impl AccessRestriction {
         // SAFETY: The access bits are length 12, so invalid representations are impossible.
         unsafe { EnumSet::from_repr_unchecked(self.bitfield.modes().get()) }
     }
+
+    /// The value of the restriction (ex: the maximum weight).
+    ///
+    /// See [`AccessRestriction::new`] for the units.
+    #[inline]
+    pub fn value(&self) -> u64 {
+        self.value.get()
+    }
+
+    /// The time domain of a timed restriction ([`AccessRestrictionType::TimedAllowed`]
+    /// or [`AccessRestrictionType::TimedDenied`]).
+    #[inline]
+    pub fn time_domain(&self) -> Option<TimeDomain> {
+        matches!(
+            self.restriction_type(),
+            AccessRestrictionType::TimedAllowed | AccessRestrictionType::TimedDenied
+        )
+        .then(|| TimeDomain::from_raw(self.value()))
+    }
+
+    /// Attaches the restriction to a directed edge.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the edge index does not fit in 22 bits.
+    pub(crate) fn set_edge_index(&mut self, edge_index: u32) -> Result<(), GraphTileBuildError> {
+        self.bitfield
+            .set_edge_index_checked(edge_index.into())
+            .map_err(|()| GraphTileBuildError::BitfieldOverflow {
+                field: "edge_index".to_string(),
+                value: edge_index as usize,
+            })
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,7 @@
+use super::GraphTileBuildError;
 use crate::AsCowStr;
+#[cfg(feature = "serde")]
+use serde::Serialize;
 use std::borrow::Cow;
 use zerocopy::{LE, U32};
 use zerocopy_derive::{FromBytes, Immutable, IntoBytes, Unaligned};
@@ -17,6 +20,46 @@ pub struct Admin {
 }
 
 impl Admin {
+    /// Creates a new admin record.
+    ///
+    /// The ISO codes are stored in fixed-size fields (2 and 3 bytes),
+    /// and shorter codes are padded with nulls.
+    /// Like Valhalla, the subdivision code should not include the country prefix
+    /// (e.g. `CT` rather than `ES-CT`).
+    ///
+    /// # Errors
+    ///
+    /// Fails if either ISO code is too long.
+    pub(crate) fn try_new(
+        country_iso: &str,
+        principal_subdivision_iso: &str,
+        country_name_offset: u32,
+        principal_subdivision_offset: u32,
+    ) -> Result<Self, GraphTileBuildError> {
+        fn pad<const N: usize>(field: &str, code: &str) -> Result<[u8; N], GraphTileBuildError> {
+            let mut out = [0; N];
+            out.get_mut(..code.len())
+                .ok_or_else(|| {
+                    GraphTileBuildError::InvalidFeature(format!(
+                        "The {field} {code:?} is longer than {N} bytes"
+                    ))
+                })?
+                .copy_from_slice(code.as_bytes());
+            Ok(out)
+        }
+
+        Ok(Self {
+            country_name_offset: country_name_offset.into(),
+            principal_subdivision_offset: principal_subdivision_offset.into(),
+            country_iso: pad("country ISO code", country_iso)?,
+            principal_subdivision_iso: pad(
+                "principal subdivision ISO code",
+                principal_subdivision_iso,
+            )?,
+            _spare: [0; 3],
+        })
+    }
+
     /// Gets the ISO 3166-1 country code
     pub fn country_iso(&self) -> Cow<'_, str> {
         self.country_iso.as_cow_str()
@@ -28,9 +71,28 @@ impl Admin {
     }
 }
 
+/// A fully resolved administrative region.
+///
+/// Unlike the raw [`Admin`] record, the names have already been looked up in the tile's text list.
+/// Get one via [`GraphTile::get_admin_for_node`](super::GraphTile::get_admin_for_node).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct AdminInfo<'a> {
+    /// The ISO 3166-1 country code.
+    pub country_iso: Cow<'a, str>,
+    /// The ISO 3166-2 principal subdivision (state, province, etc.) code.
+    ///
+    /// This may be empty if the region has no principal subdivisions.
+    pub principal_subdivision_iso: Cow<'a, str>,
+    /// The name of the country.
+    pub country_name: Cow<'a, str>,
+    /// The name of the principal subdivision.
+    pub principal_subdivision_name: Cow<'a, str>,
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::graph_tile::{GraphTile, TEST_GRAPH_TILE_L0};
+    use crate::graph_tile::{GraphTile, TEST_GRAPH_TILE_ID_L0, TEST_GRAPH_TILE_L0};
 
     #[test]
     fn test_parse_admin_count() {
@@ -61,4 +123,28 @@ mod tests {
             insta::assert_debug_snapshot!("principal_subdivision_iso", principal_subdivision_isos);
         }
     }
+
+    #[test]
+    fn test_get_admin_for_node() {
+        let tile = &*TEST_GRAPH_TILE_L0;
+
+        for (idx, node) in tile.nodes().iter().enumerate() {
+            let node_id = TEST_GRAPH_TILE_ID_L0
+                .with_feature_index(idx as u64)
+                .unwrap();
+            let admin_info = tile.get_admin_for_node(node_id).unwrap();
+            let admin = &tile.admins()[usize::from(node.admin_index())];
+
+            assert_eq!(admin_info.country_iso, admin.country_iso());
+            assert_eq!(
+                admin_info.principal_subdivision_iso,
+                admin.principal_subdivision_iso()
+            );
+        }
+
+        let admin_info = tile.get_admin_for_node(TEST_GRAPH_TILE_ID_L0).unwrap();
+        if !cfg!(miri) {
+            insta::assert_debug_snapshot!("first_node_admin_info", admin_info);
+        }
+    }
 }
@@ -1,18 +1,29 @@
 use super::{
-    AccessRestriction, Admin, DirectedEdge, DirectedEdgeExt, GraphTileBuildError, GraphTileHeader,
-    GraphTileView, NodeInfo, NodeTransition, OwnedGraphTileHandle, Sign, TransitDeparture,
-    TransitRoute, TransitSchedule, TransitStop, TransitTransfer, TurnLane,
+    AccessRestriction, Admin, AdminInfo, ComplexRestriction, DirectedEdge, DirectedEdgeExt,
+    EdgeInfo, GraphTileBuildError, GraphTileView, NodeInfo, NodeTransition, OwnedGraphTileHandle,
+    Sign, SignType, TransitDeparture, TransitRoute, TransitSchedule, TransitStop, TransitTransfer,
+    TurnLane, TurnLaneDirection,
+};
+use crate::graph_tile::edge_info::{
+    edge_info_text_references, encode_edge_info, remap_edge_info_text, replace_edge_info_names,
 };
-use crate::GraphId;
 use crate::graph_tile::header::{GraphTileHeaderBuilder, VERSION_LEN};
 use crate::graph_tile::predicted_speeds::{
     BUCKETS_PER_WEEK, COEFFICIENT_COUNT, compress_speed_buckets, decode_base64_speed_coefficients,
 };
+use crate::graph_tile::turn_lane::encode_turn_lanes;
+use crate::shape_codec::encode_shape;
+use crate::tile_hierarchy::{STANDARD_LEVELS, TilingSystem};
+use crate::{Access, BIN_COUNT, GraphId, RoadClass, RoadUse};
 use chrono::{DateTime, Utc};
-use geo::Coord;
+use enumset::EnumSet;
+use geo::{
+    BoundingRect, Coord, Distance, Haversine, Intersects, Line, LineString, Point, Rect, coord,
+};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use std::borrow::Cow;
-use zerocopy::{I16, Immutable, IntoBytes, LE, U32};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use zerocopy::{FromZeros, I16, Immutable, IntoBytes, LE, U32};
 
 /// The writer version.
 ///
@@ -37,6 +48,9 @@ const DEFAULT_WRITER_VERSION: [u8; VERSION_LEN] = {
     out
 };
 
+/// The largest admin index that nodes can reference (it is stored in 12 bits).
+const MAX_ADMIN_INDEX: u16 = (1 << 12) - 1;
+
 fn writer_version_to_bytes(version: &str) -> Option<[u8; 16]> {
     let bytes = version.as_bytes();
     if bytes.len() <= 16 {
@@ -66,14 +80,22 @@ fn writer_version_to_bytes(version: &str) -> Option<[u8; 16]> {
 ///
 /// Things like headers are not computed every time.
 /// Rather, we keep enough information around to derive what the header fields should be.
+///
+/// ## Append-only graph edits
+///
+/// New nodes (and their outbound edges) are always appended to the end of the tile.
+/// This keeps the IDs of existing features stable,
+/// so references from other tiles remain valid.
 pub struct GraphTileBuilder<'a> {
     writer_version: [u8; VERSION_LEN],
     graph_id: GraphId,
     dataset_id: u64,
     sw_corner: Coord<f32>,
     create_date: DateTime<Utc>,
-    // TODO: Remove this eventually. Currently referenced for a few properties like binning which we don't edit yet anyways.
-    remove_me_header: GraphTileHeader,
+    density: u8,
+    has_elevation: bool,
+    /// The (exclusive) end offset of each edge bin in `edge_bins`.
+    bin_offsets: [u32; BIN_COUNT],
     nodes: Cow<'a, [NodeInfo]>,
     transitions: Cow<'a, [NodeTransition]>,
     directed_edges: Cow<'a, [DirectedEdge]>,
@@ -96,6 +118,75 @@ pub struct GraphTileBuilder<'a> {
     predicted_speed_offsets: Cow<'a, [U32<LE>]>,
     /// Raw profile memory (`n_profiles x COEFFICIENT_COUNT` entries back to back)
     predicted_speed_profile_memory: Cow<'a, [I16<LE>]>,
+    /// Offsets of the strings in the text list, for deduplication.
+    ///
+    /// This is lazily indexed the first time that text is added.
+    text_offsets: Option<HashMap<String, u32>>,
+    /// Offsets of the edge info records added by this builder, for sharing between opposing edges.
+    edge_info_offsets: HashMap<EdgeInfoKey, u32>,
+    /// Whether edges have been added since the edge bins were last built.
+    bins_are_stale: bool,
+    /// Whether edges have been renamed since the text list was last compacted.
+    text_is_stale: bool,
+}
+
+/// The fields which determine whether two directed edges can share an edge info record.
+#[derive(PartialEq, Eq, Hash)]
+struct EdgeInfoKey {
+    way_id: u64,
+    speed_limit: u8,
+    names: Vec<String>,
+    encoded_shape: Vec<u8>,
+}
+
+/// A new directed edge to add to a tile (see [`GraphTileBuilder::with_node`]).
+///
+/// Valhalla graphs always include the opposing edge,
+/// so every edge needs a counterpart in the other direction,
+/// even if access is only allowed one way.
+#[derive(Debug, Clone)]
+pub struct NewDirectedEdge {
+    /// The ID of the node at the end of the edge.
+    ///
+    /// This may be in another tile.
+    pub end_node_id: GraphId,
+    /// The index of the opposing edge among the outbound edges of the end node.
+    pub opposing_edge_index: u32,
+    /// The shape of the edge, in the direction of travel.
+    ///
+    /// The first and last coordinates should match the start and end nodes.
+    /// The edge length is computed from this.
+    pub shape: Vec<Coord<f64>>,
+    /// The names of the edge (e.g. the street name).
+    pub names: Vec<String>,
+    /// The OSM way ID.
+    pub way_id: u64,
+    /// The speed of the edge (in kph).
+    pub speed: u8,
+    /// The posted speed limit (in kph); zero if unknown.
+    pub speed_limit: u8,
+    pub classification: RoadClass,
+    pub road_use: RoadUse,
+    /// Access in the direction of travel.
+    pub forward_access: EnumSet<Access>,
+    /// Access in the opposite direction.
+    pub reverse_access: EnumSet<Access>,
+}
+
+/// A new node to add to a tile (see [`GraphTileBuilder::with_node`]).
+#[derive(Debug, Clone)]
+pub struct NewNode {
+    pub coordinate: Coord<f64>,
+    pub access: EnumSet<Access>,
+    /// The index of the node's admin region in the tile's admin list.
+    pub admin_index: u16,
+    pub is_traffic_signal: bool,
+    pub drive_on_right: bool,
+    /// The outbound edges from this node.
+    ///
+    /// Valhalla stores headings for the first 8 edges,
+    /// so these should be listed first if there are more.
+    pub edges: Vec<NewDirectedEdge>,
 }
 
 impl<'a> From<&'a OwnedGraphTileHandle> for GraphTileBuilder<'a> {
@@ -138,7 +229,9 @@ impl<'a> From<&'a OwnedGraphTileHandle> for GraphTileBuilder<'a> {
             dataset_id: header.dataset_id.get(),
             sw_corner: header.sw_corner(),
             create_date: header.create_date(),
-            remove_me_header: (*header).clone(),
+            density: header.density(),
+            has_elevation: header.has_elevation(),
+            bin_offsets: header.bin_offsets.map(U32::get),
             nodes: Cow::Borrowed(nodes),
             transitions: Cow::Borrowed(transitions),
             directed_edges: Cow::Borrowed(directed_edges),
@@ -160,11 +253,77 @@ impl<'a> From<&'a OwnedGraphTileHandle> for GraphTileBuilder<'a> {
             lane_connectivity_memory: Cow::Borrowed(lane_connectivity_memory),
             predicted_speed_offsets,
             predicted_speed_profile_memory,
+            text_offsets: None,
+            edge_info_offsets: HashMap::new(),
+            bins_are_stale: false,
+            text_is_stale: false,
         }
     }
 }
 
 impl GraphTileBuilder<'_> {
+    /// Creates a builder for an empty tile.
+    ///
+    /// The tile starts with a single empty admin region (index 0),
+    /// which matches what Valhalla uses for nodes without admin info.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the graph ID is not a tile in one of the standard hierarchy levels.
+    pub fn new(graph_id: GraphId) -> Result<Self, GraphTileBuildError> {
+        let graph_id = graph_id.tile_base_id();
+        let level = STANDARD_LEVELS
+            .iter()
+            .find(|level| level.level == graph_id.level())
+            .ok_or(GraphTileBuildError::UnsupportedLevel(graph_id.level()))?;
+        let tile_id = graph_id.tile_id();
+        if tile_id >= u64::from(level.tiling_system.tile_count()) {
+            return Err(GraphTileBuildError::InvalidIndex(format!(
+                "Tile ID {tile_id} is out of range for level {}, which has {} tiles",
+                level.level,
+                level.tiling_system.tile_count()
+            )));
+        }
+        let sw_corner = tile_sw_corner(&level.tiling_system, tile_id)?;
+
+        Ok(Self {
+            writer_version: DEFAULT_WRITER_VERSION,
+            graph_id,
+            dataset_id: 0,
+            sw_corner,
+            create_date: Utc::now(),
+            density: 0,
+            has_elevation: false,
+            bin_offsets: [0; BIN_COUNT],
+            nodes: Cow::default(),
+            transitions: Cow::default(),
+            directed_edges: Cow::default(),
+            ext_directed_edges: Cow::default(),
+            access_restrictions: Cow::default(),
+            transit_departures: Cow::default(),
+            transit_stops: Cow::default(),
+            transit_routes: Cow::default(),
+            transit_schedules: Cow::default(),
+            transit_transfers: Cow::default(),
+            signs: Cow::default(),
+            turn_lanes: Cow::default(),
+            admins: Cow::Owned(vec![Admin::new_zeroed()]),
+            edge_bins: Cow::default(),
+            complex_forward_restrictions_memory: Cow::default(),
+            complex_reverse_restrictions_memory: Cow::default(),
+            edge_info_memory: Cow::default(),
+            // The empty string lives at offset zero
+            text_memory: Cow::Owned(vec![0]),
+            lane_connectivity_memory: Cow::default(),
+            predicted_speed_offsets: Cow::default(),
+            predicted_speed_profile_memory: Cow::default(),
+            text_offsets: None,
+            edge_info_offsets: HashMap::new(),
+            bins_are_stale: false,
+            text_is_stale: false,
+        })
+    }
+
     /// The Graph ID for the tile.
     ///
     /// This is guaranteed to always be a base ID.
@@ -172,6 +331,380 @@ impl GraphTileBuilder<'_> {
         self.graph_id
     }
 
+    /// The ID that the next node added with [`with_node`](GraphTileBuilder::with_node) will get.
+    ///
+    /// This is useful for wiring up edges between new nodes.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the tile is already full.
+    pub fn next_node_id(&self) -> Result<GraphId, GraphTileBuildError> {
+        Ok(self
+            .graph_id
+            .with_feature_index(u64::try_from(self.nodes.len())?)?)
+    }
+
+    /// Adds a new node, along with its outbound directed edges.
+    ///
+    /// The node is appended after all existing nodes (see [`next_node_id`](GraphTileBuilder::next_node_id)),
+    /// and its edges after all existing directed edges.
+    /// Edge info and names are stored automatically;
+    /// opposing edges with the same way ID, names, and (reversed) shape share a single edge info record.
+    /// The edge bins are rebuilt when the tile is serialized
+    /// (see [`rebuild_bins`](GraphTileBuilder::rebuild_bins)).
+    ///
+    /// References to other nodes are not checked,
+    /// since the end nodes may not have been added yet (or may be in other tiles).
+    /// Use [`GraphTile::validate`](super::GraphTile::validate) on the finished tile
+    /// to check that everything lines up.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the node is outside the tile,
+    /// an edge shape has fewer than two coordinates or contains invalid coordinates,
+    /// or any value is too large to fit in the tile format
+    /// (e.g. more than 127 edges, or edges longer than 16,777km).
+    pub fn with_node(self, node: &NewNode) -> Result<Self, GraphTileBuildError> {
+        let mut result = self;
+
+        let edge_index = u32::try_from(result.directed_edges.len())?;
+        let node_info = NodeInfo::try_new(node, result.sw_corner, edge_index)?;
+        for (local_edge_index, edge) in node.edges.iter().enumerate() {
+            result.push_directed_edge(edge, u8::try_from(local_edge_index)?)?;
+        }
+        result.nodes.to_mut().push(node_info);
+
+        Ok(result)
+    }
+
+    fn push_directed_edge(
+        &mut self,
+        edge: &NewDirectedEdge,
+        local_edge_index: u8,
+    ) -> Result<(), GraphTileBuildError> {
+        let is_valid_coord =
+            |c: &Coord<f64>| (-180.0..=180.0).contains(&c.x) && (-90.0..=90.0).contains(&c.y);
+        if edge.shape.len() < 2 || !edge.shape.iter().all(is_valid_coord) {
+            return Err(GraphTileBuildError::InvalidFeature(format!(
+                "The shape of the edge to {} must have at least two valid coordinates",
+                edge.end_node_id
+            )));
+        }
+
+        let length: f64 = edge
+            .shape
+            .windows(2)
+            .map(|pair| Haversine.distance(Point(pair[0]), Point(pair[1])))
+            .sum();
+        // Overly long edges saturate, and are caught by the bitfield check
+        #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let length = length.round() as u32;
+
+        let (edge_info_offset, edge_info_is_forward) = self.add_edge_info(edge)?;
+        let leaves_tile = edge.end_node_id.tile_base_id() != self.graph_id;
+        let directed_edge = DirectedEdge::try_new(
+            edge,
+            length,
+            edge_info_offset,
+            edge_info_is_forward,
+            leaves_tile,
+            local_edge_index,
+        )?;
+
+        self.directed_edges.to_mut().push(directed_edge);
+        if !self.ext_directed_edges.is_empty() {
+            self.ext_directed_edges
+                .to_mut()
+                .push(DirectedEdgeExt::new_zeroed());
+        }
+
+        self.bins_are_stale = true;
+
+        Ok(())
+    }
+
+    /// Finds or adds the edge info for an edge.
+    ///
+    /// Returns the offset of the edge info, and whether it is stored in the direction of travel.
+    fn add_edge_info(
+        &mut self,
+        edge: &NewDirectedEdge,
+    ) -> Result<(u32, bool), GraphTileBuildError> {
+        let reversed_shape: Vec<_> = edge.shape.iter().rev().copied().collect();
+        let reverse_key = EdgeInfoKey {
+            way_id: edge.way_id,
+            speed_limit: edge.speed_limit,
+            names: edge.names.clone(),
+            encoded_shape: encode_shape(&reversed_shape),
+        };
+        if let Some(&offset) = self.edge_info_offsets.get(&reverse_key) {
+            return Ok((offset, false));
+        }
+
+        let key = EdgeInfoKey {
+            encoded_shape: encode_shape(&edge.shape),
+            ..reverse_key
+        };
+        if let Some(&offset) = self.edge_info_offsets.get(&key) {
+            return Ok((offset, true));
+        }
+
+        let name_offsets = edge
+            .names
+            .iter()
+            .map(|name| self.add_text(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let bytes = encode_edge_info(
+            key.way_id,
+            key.speed_limit,
+            &name_offsets,
+            &key.encoded_shape,
+        )?;
+
+        let offset = u32::try_from(self.edge_info_memory.len())?;
+        self.edge_info_memory.to_mut().extend_from_slice(&bytes);
+        self.edge_info_offsets.insert(key, offset);
+
+        Ok((offset, true))
+    }
+
+    /// Adds a string to the text list (if it isn't already there), returning its offset.
+    fn add_text(&mut self, text: &str) -> Result<u32, GraphTileBuildError> {
+        if text.contains('\0') {
+            return Err(GraphTileBuildError::InvalidFeature(format!(
+                "Text may not contain null characters: {text:?}"
+            )));
+        }
+
+        let text_memory = &self.text_memory;
+        let text_offsets = self.text_offsets.get_or_insert_with(|| {
+            // Index the existing (null-terminated) strings
+            let mut offsets = HashMap::new();
+            let mut start = 0;
+            for (end, _) in text_memory.iter().enumerate().filter(|&(_, &b)| b == 0) {
+                if let (Ok(text), Ok(offset)) = (
+                    std::str::from_utf8(&text_memory[start..end]),
+                    u32::try_from(start),
+                ) {
+                    offsets.entry(text.to_string()).or_insert(offset);
+                }
+                start = end + 1;
+            }
+            offsets
+        });
+        if let Some(&offset) = text_offsets.get(text) {
+            return Ok(offset);
+        }
+
+        let offset = u32::try_from(self.text_memory.len())?;
+        let text_memory = self.text_memory.to_mut();
+        text_memory.extend_from_slice(text.as_bytes());
+        text_memory.push(0);
+        text_offsets.insert(text.to_string(), offset);
+
+        Ok(offset)
+    }
+
+    /// Rebuilds the spatial edge bins from the shapes of the tile's edges.
+    ///
+    /// Local level tiles are divided into a 5x5 grid of bins,
+    /// each listing the edges whose shapes pass through it,
+    /// which is what candidate search uses to find nearby edges.
+    /// Like Valhalla, transit lines and shortcuts are skipped,
+    /// and only the first edge of each opposing pair (i.e. sharing an edge info record) is binned.
+    ///
+    /// Bins also list edges from other tiles (including other hierarchy levels)
+    /// whose shapes pass through this tile.
+    /// Their shapes aren't available here, so those entries are kept as-is,
+    /// after this tile's own edges.
+    /// See [`cross_tile_bins`](GraphTileBuilder::cross_tile_bins) for keeping them up to date.
+    ///
+    /// This happens automatically when serializing a tile which has new edges,
+    /// so you rarely need to call it directly.
+    /// Tiles outside the local level have no bins, so this is a no-op for them.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the edge info or shape of an edge can't be decoded.
+    pub fn rebuild_bins(self) -> Result<Self, GraphTileBuildError> {
+        let mut result = self;
+        result.bins_are_stale = false;
+        if !is_local_level(result.graph_id) {
+            return Ok(result);
+        }
+
+        let mut bins: [Vec<GraphId>; BIN_COUNT] = Default::default();
+        for (edge_id, shape) in result.binnable_edge_shapes()? {
+            if let Some(tile_bins) = bins_intersecting_shape(&shape.0)?.get(&result.graph_id) {
+                for &bin_index in tile_bins {
+                    bins[bin_index].push(edge_id);
+                }
+            }
+        }
+
+        for (bin, old_bin) in bins.iter_mut().zip(result.bins()?) {
+            bin.extend(
+                old_bin
+                    .into_iter()
+                    .filter(|edge_id| edge_id.tile_base_id() != result.graph_id),
+            );
+        }
+        result.set_bins(bins)?;
+
+        Ok(result)
+    }
+
+    /// Finds the edges of this tile which need to be binned in other tiles.
+    ///
+    /// Edges are binned in every local level tile that their shapes pass through.
+    /// This includes all edges from the other hierarchy levels,
+    /// and local level edges which cross a tile boundary.
+    ///
+    /// The result is keyed by the (base) ID of the local level tile,
+    /// and lists the edges to add to each of its bins.
+    /// Pass these to [`with_cross_tile_bins`](GraphTileBuilder::with_cross_tile_bins)
+    /// on a builder for that tile.
+    ///
+    /// Valhalla rasterizes shapes slightly differently near tile boundaries,
+    /// so this may include a few edges which only barely clip a bin,
+    /// which Valhalla leaves out.
+    /// This is harmless for candidate search, which checks the actual distance to each edge.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the edge info or shape of an edge can't be decoded.
+    pub fn cross_tile_bins(
+        &self,
+    ) -> Result<BTreeMap<GraphId, [Vec<GraphId>; BIN_COUNT]>, GraphTileBuildError> {
+        let mut result: BTreeMap<GraphId, [Vec<GraphId>; BIN_COUNT]> = BTreeMap::new();
+        for (edge_id, shape) in self.binnable_edge_shapes()? {
+            for (tile_id, bins) in bins_intersecting_shape(&shape.0)? {
+                if tile_id == self.graph_id {
+                    continue;
+                }
+                let tile_bins = result.entry(tile_id).or_default();
+                for bin_index in bins {
+                    tile_bins[bin_index].push(edge_id);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Sets the entries in this tile's edge bins for edges from another tile.
+    ///
+    /// This replaces any existing entries from the source tile,
+    /// and is typically used with the output of [`cross_tile_bins`](GraphTileBuilder::cross_tile_bins)
+    /// from a builder for the source tile.
+    /// The entries are stored after those for this tile's own edges.
+    ///
+    /// # Errors
+    ///
+    /// Fails if this is not a local level tile,
+    /// the source tile is this tile,
+    /// or any of the edges are not in the source tile.
+    pub fn with_cross_tile_bins(
+        self,
+        source_tile_id: GraphId,
+        bins: &[Vec<GraphId>; BIN_COUNT],
+    ) -> Result<Self, GraphTileBuildError> {
+        let mut result = self;
+        let source_tile_id = source_tile_id.tile_base_id();
+        if !is_local_level(result.graph_id) {
+            return Err(GraphTileBuildError::InvalidFeature(format!(
+                "Only local level tiles have edge bins, but {} is on level {}",
+                result.graph_id,
+                result.graph_id.level()
+            )));
+        }
+        if source_tile_id == result.graph_id {
+            return Err(GraphTileBuildError::InvalidFeature(
+                "Edges from this tile are binned with rebuild_bins".to_string(),
+            ));
+        }
+        if let Some(edge_id) = bins
+            .iter()
+            .flatten()
+            .find(|edge_id| edge_id.tile_base_id() != source_tile_id)
+        {
+            return Err(GraphTileBuildError::InvalidFeature(format!(
+                "Edge {edge_id} is not in the source tile {source_tile_id}"
+            )));
+        }
+
+        let mut new_bins = result.bins()?;
+        for (bin, source_bin) in new_bins.iter_mut().zip(bins) {
+            bin.retain(|edge_id| edge_id.tile_base_id() != source_tile_id);
+            bin.extend_from_slice(source_bin);
+        }
+        result.set_bins(new_bins)?;
+
+        Ok(result)
+    }
+
+    /// Decodes the shapes of the edges which Valhalla puts in the edge bins.
+    fn binnable_edge_shapes(&self) -> Result<Vec<(GraphId, LineString)>, GraphTileBuildError> {
+        let mut seen_offsets = HashSet::new();
+        let mut shapes = Vec::new();
+        for (index, edge) in self.directed_edges.iter().enumerate() {
+            // Opposing edges share the same edge info (and shape), so only the first is binned
+            if edge.is_transit_line() || !seen_offsets.insert(edge.edge_info_offset()) {
+                continue;
+            }
+
+            let decoding_error = |description: String| {
+                GraphTileBuildError::InvalidFeature(format!(
+                    "Unable to decode the shape of directed edge {index}: {description}"
+                ))
+            };
+            let bytes = self
+                .edge_info_memory
+                .get(edge.edge_info_offset() as usize..)
+                .ok_or_else(|| decoding_error("edge info offset out of bounds".to_string()))?;
+            let shape = EdgeInfo::try_from((bytes, &self.text_memory[..]))
+                .map_err(|e| decoding_error(e.to_string()))?
+                .decode_raw_shape::<f64>()
+                .map_err(|e| decoding_error(e.to_string()))?;
+            shapes.push((
+                self.graph_id.with_feature_index(u64::try_from(index)?)?,
+                LineString(shape),
+            ));
+        }
+
+        Ok(shapes)
+    }
+
+    /// Splits the edge bins into a list per bin.
+    fn bins(&self) -> Result<[Vec<GraphId>; BIN_COUNT], GraphTileBuildError> {
+        let mut bins: [Vec<GraphId>; BIN_COUNT] = Default::default();
+        let mut start = 0;
+        for (bin_index, bin) in bins.iter_mut().enumerate() {
+            let end = self.bin_offsets[bin_index] as usize;
+            bin.extend_from_slice(self.edge_bins.get(start..end).ok_or_else(|| {
+                GraphTileBuildError::InvalidIndex(format!(
+                    "Edge bin {bin_index} spans {start}..{end}, but there are only {} edge bin entries",
+                    self.edge_bins.len()
+                ))
+            })?);
+            start = end;
+        }
+
+        Ok(bins)
+    }
+
+    /// Replaces the edge bins, storing them back to back.
+    fn set_bins(&mut self, bins: [Vec<GraphId>; BIN_COUNT]) -> Result<(), GraphTileBuildError> {
+        let mut edge_bins = Vec::with_capacity(bins.iter().map(Vec::len).sum());
+        for (bin_index, bin) in bins.into_iter().enumerate() {
+            edge_bins.extend(bin);
+            self.bin_offsets[bin_index] = u32::try_from(edge_bins.len())?;
+        }
+        self.edge_bins = Cow::Owned(edge_bins);
+
+        Ok(())
+    }
+
     /// Sets the version string to encode in the graph tile.
     ///
     /// This is purely metadata and is not used by Valhalla to determine compatibility.
@@ -190,6 +723,52 @@ impl GraphTileBuilder<'_> {
         })
     }
 
+    /// Sets the dataset ID to stamp in the tile header.
+    ///
+    /// Canonically, this is the ID of the last OSM changeset included in the data,
+    /// which lets consumers tell which OSM snapshot a tileset was built from.
+    /// Tiles built from an existing tile keep its dataset ID unless this is set.
+    #[must_use]
+    pub fn with_dataset_id(self, dataset_id: u64) -> Self {
+        Self { dataset_id, ..self }
+    }
+
+    /// Sets the creation date to stamp in the tile header.
+    ///
+    /// Tiles only store the date (as a day count since 2014-01-01),
+    /// so the time of day is dropped, and earlier dates are clamped to the start of 2014.
+    /// New tiles default to the current time,
+    /// and tiles built from an existing tile keep its creation date unless this is set.
+    #[must_use]
+    pub fn with_create_date(self, create_date: DateTime<Utc>) -> Self {
+        Self {
+            create_date,
+            ..self
+        }
+    }
+
+    /// Sets the SW corner of the tile, which node coordinates are stored relative to.
+    ///
+    /// The SW corner is normally determined by the tile ID,
+    /// so this is only needed for non-standard tilesets.
+    /// Existing nodes keep their absolute position (their offsets are re-encoded).
+    ///
+    /// # Errors
+    ///
+    /// Fails if any existing node would end up south or west of the new corner,
+    /// or too far away from it to fit in the tile format.
+    pub fn with_sw_corner(self, sw_corner: Coord<f32>) -> Result<Self, GraphTileBuildError> {
+        let mut result = self;
+        if result.sw_corner != sw_corner {
+            for node in result.nodes.to_mut() {
+                node.move_sw_corner(result.sw_corner, sw_corner)?;
+            }
+            result.sw_corner = sw_corner;
+        }
+
+        Ok(result)
+    }
+
     /// Adds historical average (coarse granularity) speed information to a directed edge.
     ///
     /// Zero indicates that no data is available.
@@ -211,9 +790,591 @@ impl GraphTileBuilder<'_> {
             )));
         }
 
-        let edge = &mut result.directed_edges.to_mut()[directed_edge_index];
-        edge.set_free_flow_speed(free_flow_speed);
-        edge.set_constrained_speed(constrained_speed);
+        let edge = &mut result.directed_edges.to_mut()[directed_edge_index];
+        edge.set_free_flow_speed(free_flow_speed);
+        edge.set_constrained_speed(constrained_speed);
+
+        Ok(result)
+    }
+
+    /// Adds an access restriction (ex: a weight limit) to a directed edge.
+    ///
+    /// Restrictions are kept sorted by edge index, which lookups rely on.
+    /// The new restriction goes after any existing restrictions for the same edge.
+    /// The edge's set of restricted access modes is also updated,
+    /// since Valhalla only looks up restrictions for modes in this set.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the directed edge index is out of bounds.
+    pub fn with_access_restriction(
+        self,
+        directed_edge_index: usize,
+        restriction: AccessRestriction,
+    ) -> Result<Self, GraphTileBuildError> {
+        let mut result = self;
+        if directed_edge_index >= result.directed_edges.len() {
+            return Err(GraphTileBuildError::InvalidIndex(format!(
+                "Attempted to add an access restriction to directed edge index {directed_edge_index}, but tile only has {} edges",
+                result.directed_edges.len()
+            )));
+        }
+
+        let edge_index = u32::try_from(directed_edge_index)?;
+        let mut restriction = restriction;
+        restriction.set_edge_index(edge_index)?;
+        let edge = &mut result.directed_edges.to_mut()[directed_edge_index];
+        edge.set_access_restriction_modes(
+            edge.access_restriction_modes() | restriction.affected_access_modes(),
+        );
+
+        let index = result
+            .access_restrictions
+            .partition_point(|r| r.edge_index() <= edge_index);
+        result
+            .access_restrictions
+            .to_mut()
+            .insert(index, restriction);
+
+        Ok(result)
+    }
+
+    /// Removes the access restrictions on a directed edge which match a predicate.
+    ///
+    /// For example, `|_| true` removes all of the edge's restrictions.
+    /// The order of the remaining restrictions is preserved,
+    /// and the edge's set of restricted access modes is updated to match.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the directed edge index is out of bounds.
+    pub fn without_access_restrictions<F: Fn(&AccessRestriction) -> bool>(
+        self,
+        directed_edge_index: usize,
+        predicate: F,
+    ) -> Result<Self, GraphTileBuildError> {
+        let mut result = self;
+        if directed_edge_index >= result.directed_edges.len() {
+            return Err(GraphTileBuildError::InvalidIndex(format!(
+                "Attempted to remove access restrictions from directed edge index {directed_edge_index}, but tile only has {} edges",
+                result.directed_edges.len()
+            )));
+        }
+
+        let edge_index = u32::try_from(directed_edge_index)?;
+        let start = result
+            .access_restrictions
+            .partition_point(|r| r.edge_index() < edge_index);
+        let end = result
+            .access_restrictions
+            .partition_point(|r| r.edge_index() <= edge_index);
+        if !result.access_restrictions[start..end]
+            .iter()
+            .any(&predicate)
+        {
+            return Ok(result);
+        }
+
+        let access_restrictions = result.access_restrictions.to_mut();
+        let remaining: Vec<_> = access_restrictions
+            .drain(start..end)
+            .filter(|r| !predicate(r))
+            .collect();
+        let modes = remaining.iter().fold(EnumSet::empty(), |modes, r| {
+            modes | r.affected_access_modes()
+        });
+        access_restrictions.splice(start..start, remaining);
+        result.directed_edges.to_mut()[directed_edge_index].set_access_restriction_modes(modes);
+
+        Ok(result)
+    }
+
+    /// Adds a complex (multi-edge) restriction.
+    ///
+    /// Valhalla stores each restriction twice:
+    /// in the forward list of the tile containing the last (to) edge,
+    /// and in the reverse list of the tile containing the first (from) edge.
+    /// This adds the restriction to whichever of those lists belong to this tile
+    /// (both if the edges are in the same tile),
+    /// so restrictions spanning several tiles need to be added to each of them.
+    /// The restriction modes of the from and to edges are updated to match,
+    /// and every edge of the restriction in this tile is marked as part of a complex restriction.
+    ///
+    /// # Errors
+    ///
+    /// Fails if neither the from nor the to edge is in this tile,
+    /// an edge in this tile is out of bounds,
+    /// or the restriction can't be encoded (e.g. it has more than 31 via edges).
+    pub fn with_complex_restriction(
+        self,
+        restriction: &ComplexRestriction,
+    ) -> Result<Self, GraphTileBuildError> {
+        let mut result = self;
+        let base_id = result.graph_id;
+        let local_index = |edge_id: GraphId| -> Result<Option<usize>, GraphTileBuildError> {
+            if edge_id.tile_base_id() != base_id {
+                return Ok(None);
+            }
+            let index = usize::try_from(edge_id.feature_index())?;
+            if index >= result.directed_edges.len() {
+                return Err(GraphTileBuildError::InvalidIndex(format!(
+                    "Complex restriction references directed edge {edge_id}, but tile only has {} edges",
+                    result.directed_edges.len()
+                )));
+            }
+            Ok(Some(index))
+        };
+
+        let from_index = local_index(restriction.from_edge_id)?;
+        let to_index = local_index(restriction.to_edge_id)?;
+        if from_index.is_none() && to_index.is_none() {
+            return Err(GraphTileBuildError::InvalidFeature(format!(
+                "Neither end of the complex restriction from {} to {} is in tile {base_id}",
+                restriction.from_edge_id, restriction.to_edge_id
+            )));
+        }
+        let via_indices = restriction
+            .via_edge_ids
+            .iter()
+            .filter_map(|&edge_id| local_index(edge_id).transpose())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let bytes = restriction.encode()?;
+        let directed_edges = result.directed_edges.to_mut();
+        let modes = restriction.affected_access_modes;
+        if let Some(index) = from_index {
+            let edge = &mut directed_edges[index];
+            edge.set_complex_restriction_modes(
+                edge.start_restriction_modes() | modes,
+                edge.end_restriction_modes(),
+            );
+            result
+                .complex_reverse_restrictions_memory
+                .to_mut()
+                .extend_from_slice(&bytes);
+        }
+        if let Some(index) = to_index {
+            let edge = &mut directed_edges[index];
+            edge.set_complex_restriction_modes(
+                edge.start_restriction_modes(),
+                edge.end_restriction_modes() | modes,
+            );
+            result
+                .complex_forward_restrictions_memory
+                .to_mut()
+                .extend_from_slice(&bytes);
+        }
+        for index in from_index.into_iter().chain(to_index).chain(via_indices) {
+            directed_edges[index].set_part_of_complex_restriction(true);
+        }
+
+        Ok(result)
+    }
+
+    /// Sets the turn lanes at the end of a directed edge, from left to right.
+    ///
+    /// Each lane is the set of directions that it allows
+    /// (see [`parse_osm_turn_lanes`](crate::graph_tile::parse_osm_turn_lanes) for OSM tags).
+    /// This replaces any existing turn lanes for the edge,
+    /// and an empty list removes them.
+    /// The lane text is added to the tile's text list (if it isn't already there).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the directed edge index is out of bounds.
+    pub fn with_turn_lanes(
+        self,
+        directed_edge_index: usize,
+        lanes: &[EnumSet<TurnLaneDirection>],
+    ) -> Result<Self, GraphTileBuildError> {
+        let mut result = self;
+        if directed_edge_index >= result.directed_edges.len() {
+            return Err(GraphTileBuildError::InvalidIndex(format!(
+                "Attempted to set turn lanes for directed edge index {directed_edge_index}, but tile only has {} edges",
+                result.directed_edges.len()
+            )));
+        }
+
+        // Turn lanes are sorted by edge index, with at most one per edge
+        let edge_index = u32::try_from(directed_edge_index)?;
+        let start = result
+            .turn_lanes
+            .partition_point(|lane| lane.directed_edge_index() < edge_index);
+        let end = result
+            .turn_lanes
+            .partition_point(|lane| lane.directed_edge_index() <= edge_index);
+        let turn_lane = if lanes.is_empty() {
+            None
+        } else {
+            let text_offset = result.add_text(&encode_turn_lanes(lanes))?;
+            Some(TurnLane::try_new(edge_index, text_offset)?)
+        };
+
+        result.directed_edges.to_mut()[directed_edge_index].set_has_turn_lanes(turn_lane.is_some());
+        result.turn_lanes.to_mut().splice(start..end, turn_lane);
+
+        Ok(result)
+    }
+
+    /// Adds a sign (ex: an exit number) to a directed edge.
+    ///
+    /// Signs are kept sorted by edge index, which lookups rely on.
+    /// The new sign goes after any existing signs for the same edge,
+    /// and the edge is flagged as having exit signs.
+    /// The text is added to the tile's text list (if it isn't already there).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the directed edge index is out of bounds,
+    /// or the sign type belongs to nodes (see [`SignType::is_node_sign`]).
+    pub fn with_edge_sign(
+        self,
+        directed_edge_index: usize,
+        sign_type: SignType,
+        text: &str,
+    ) -> Result<Self, GraphTileBuildError> {
+        let mut result = self;
+        if directed_edge_index >= result.directed_edges.len() {
+            return Err(GraphTileBuildError::InvalidIndex(format!(
+                "Attempted to add a sign to directed edge index {directed_edge_index}, but tile only has {} edges",
+                result.directed_edges.len()
+            )));
+        }
+        if sign_type.is_node_sign() || sign_type == SignType::Linguistic {
+            return Err(GraphTileBuildError::InvalidFeature(format!(
+                "{sign_type:?} signs can't be added to directed edges"
+            )));
+        }
+
+        let edge_index = u32::try_from(directed_edge_index)?;
+        let text_offset = result.add_text(text)?;
+        let sign = Sign::try_new(edge_index, sign_type, text_offset)?;
+        result.directed_edges.to_mut()[directed_edge_index].set_has_exit_signs(true);
+        let index = result
+            .signs
+            .partition_point(|sign| sign.edge_or_node_index() <= edge_index);
+        result.signs.to_mut().insert(index, sign);
+
+        Ok(result)
+    }
+
+    /// The index that the next admin added with [`with_admin`](GraphTileBuilder::with_admin) will get.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the admin list is already full.
+    pub fn next_admin_index(&self) -> Result<u16, GraphTileBuildError> {
+        Ok(u16::try_from(self.admins.len())?)
+    }
+
+    /// Appends an administrative region to the tile's admin list.
+    ///
+    /// The admin gets the index returned by [`next_admin_index`](GraphTileBuilder::next_admin_index),
+    /// and can then be assigned to nodes with [`with_node_admin`](GraphTileBuilder::with_node_admin).
+    /// The names are added to the tile's text list (if they aren't already there).
+    /// Like Valhalla, the subdivision ISO code should not include the country prefix
+    /// (e.g. `CT` rather than `ES-CT`).
+    ///
+    /// # Errors
+    ///
+    /// Fails if an ISO code is too long (2 bytes for countries and 3 for subdivisions),
+    /// or the tile already has the maximum number of admins (4,096).
+    pub fn with_admin(self, admin: &AdminInfo<'_>) -> Result<Self, GraphTileBuildError> {
+        let mut result = self;
+        let admin_index = result.next_admin_index()?;
+        if admin_index > MAX_ADMIN_INDEX {
+            return Err(GraphTileBuildError::BitfieldOverflow {
+                field: "admin_index".to_string(),
+                value: usize::from(admin_index),
+            });
+        }
+
+        let country_name_offset = result.add_text(&admin.country_name)?;
+        let principal_subdivision_offset = result.add_text(&admin.principal_subdivision_name)?;
+        let admin = Admin::try_new(
+            &admin.country_iso,
+            &admin.principal_subdivision_iso,
+            country_name_offset,
+            principal_subdivision_offset,
+        )?;
+        result.admins.to_mut().push(admin);
+
+        Ok(result)
+    }
+
+    /// Assigns a node to an administrative region in the tile's admin list.
+    ///
+    /// The country crossing flags of the node's edges (in both directions) are updated to match,
+    /// as long as the node at the other end is in this tile.
+    /// Edges which leave the tile are left alone,
+    /// since the neighboring tile is needed to check them.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the node or admin index is out of bounds.
+    pub fn with_node_admin(
+        self,
+        node_index: usize,
+        admin_index: u16,
+    ) -> Result<Self, GraphTileBuildError> {
+        let mut result = self;
+        if node_index >= result.nodes.len() {
+            return Err(GraphTileBuildError::InvalidIndex(format!(
+                "Attempted to set the admin of node index {node_index}, but tile only has {} nodes",
+                result.nodes.len()
+            )));
+        }
+        if usize::from(admin_index) >= result.admins.len() {
+            return Err(GraphTileBuildError::InvalidIndex(format!(
+                "Attempted to assign admin index {admin_index} to a node, but tile only has {} admins",
+                result.admins.len()
+            )));
+        }
+
+        result.nodes.to_mut()[node_index].set_admin_index(admin_index)?;
+
+        // Opposing edges start at the other node, so they can be found without a full scan
+        let base_id = result.graph_id;
+        let country_iso = |nodes: &[NodeInfo], admins: &[Admin], index: usize| {
+            admins[usize::from(nodes[index].admin_index())]
+                .country_iso()
+                .into_owned()
+        };
+        let node = &result.nodes[node_index];
+        let edge_range =
+            node.edge_index() as usize..node.edge_index() as usize + usize::from(node.edge_count());
+        let country = country_iso(&result.nodes, &result.admins, node_index);
+        for edge_index in edge_range {
+            let Some(edge) = result.directed_edges.get(edge_index) else {
+                continue;
+            };
+            let end_node_id = edge.end_node_id();
+            if end_node_id.tile_base_id() != base_id {
+                continue;
+            }
+            let Some(end_node) = usize::try_from(end_node_id.feature_index())
+                .ok()
+                .filter(|&index| index < result.nodes.len())
+            else {
+                continue;
+            };
+
+            let crosses = country_iso(&result.nodes, &result.admins, end_node) != country;
+            let opposing_edge_index =
+                result.nodes[end_node].edge_index() as usize + edge.opposing_edge_index() as usize;
+            let directed_edges = result.directed_edges.to_mut();
+            directed_edges[edge_index].set_country_crossing(crosses);
+            if let Some(opposing_edge) = directed_edges.get_mut(opposing_edge_index) {
+                opposing_edge.set_country_crossing(crosses);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Replaces the names of a directed edge.
+    ///
+    /// Opposing edges share their edge info, so this renames both directions.
+    /// Tagged values (tunnel names, layers, etc.) are left alone.
+    /// Each new name keeps the route number flag of the old name in the same position.
+    /// Names are reused if they are already in the tile's text list,
+    /// and appended otherwise.
+    /// Old names which nothing else references are removed from the text list
+    /// when the tile is serialized (see [`compact_text_list`](GraphTileBuilder::compact_text_list)).
+    ///
+    /// The edge info record is rewritten in place,
+    /// and the offsets of every later record are shifted to match.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the directed edge index is out of bounds,
+    /// the edge info can't be decoded,
+    /// or there are too many names (at most 15 names and tagged values are allowed per edge).
+    pub fn with_edge_names(
+        self,
+        directed_edge_index: usize,
+        names: &[&str],
+    ) -> Result<Self, GraphTileBuildError> {
+        let mut result = self;
+        let Some(edge) = result.directed_edges.get(directed_edge_index) else {
+            return Err(GraphTileBuildError::InvalidIndex(format!(
+                "Attempted to set names for directed edge index {directed_edge_index}, but tile only has {} edges",
+                result.directed_edges.len()
+            )));
+        };
+
+        // Records are stored back to back, so this one ends where the next one starts
+        let start = edge.edge_info_offset();
+        let end = result
+            .directed_edges
+            .iter()
+            .map(DirectedEdge::edge_info_offset)
+            .filter(|&offset| offset > start)
+            .min()
+            .unwrap_or(u32::try_from(result.edge_info_memory.len())?);
+        let name_offsets = names
+            .iter()
+            .map(|name| result.add_text(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let record = result
+            .edge_info_memory
+            .get(start as usize..end as usize)
+            .ok_or_else(|| {
+                GraphTileBuildError::InvalidIndex(format!(
+                    "The edge info offset {start} for directed edge {directed_edge_index} is out of bounds"
+                ))
+            })?;
+
+        let new_record = replace_edge_info_names(record, &name_offsets)?;
+
+        let new_end = start + u32::try_from(new_record.len())?;
+        result
+            .edge_info_memory
+            .to_mut()
+            .splice(start as usize..end as usize, new_record);
+
+        for edge in result.directed_edges.to_mut() {
+            let offset = edge.edge_info_offset();
+            if offset == start {
+                edge.set_is_named(!names.is_empty());
+            } else if offset > start {
+                edge.set_edge_info_offset(offset - end + new_end)?;
+            }
+        }
+
+        // The renamed record can no longer be shared by new edges with the old names
+        result
+            .edge_info_offsets
+            .retain(|_, offset| *offset != start);
+        for offset in result.edge_info_offsets.values_mut() {
+            if *offset > start {
+                *offset = *offset - end + new_end;
+            }
+        }
+        result.text_is_stale = true;
+
+        Ok(result)
+    }
+
+    /// Removes strings which nothing references from the text list.
+    ///
+    /// Renaming edges leaves their old names behind,
+    /// which would defeat the purpose when anonymizing a tile.
+    /// This rebuilds the text list from the strings which are still referenced
+    /// by edge info records, signs, turn lanes, and admins (keeping their order),
+    /// and updates every offset to match.
+    ///
+    /// This happens automatically when serializing a tile whose edges were renamed,
+    /// so you rarely need to call it directly.
+    /// Transit stops and routes also reference the text list, but aren't decoded yet,
+    /// so tiles with transit data are left as-is.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an edge info record can't be decoded,
+    /// or something references text outside the text list.
+    pub fn compact_text_list(self) -> Result<Self, GraphTileBuildError> {
+        let mut result = self;
+        result.text_is_stale = false;
+        if !result.transit_stops.is_empty() || !result.transit_routes.is_empty() {
+            return Ok(result);
+        }
+
+        // Records are stored back to back, so each one ends where the next one starts
+        let mut record_starts = result
+            .directed_edges
+            .iter()
+            .map(|edge| edge.edge_info_offset() as usize)
+            .collect::<Vec<_>>();
+        record_starts.sort_unstable();
+        record_starts.dedup();
+        let record_ranges = record_starts
+            .iter()
+            .enumerate()
+            .map(|(index, &start)| {
+                let end = record_starts
+                    .get(index + 1)
+                    .copied()
+                    .unwrap_or(result.edge_info_memory.len());
+                start..end
+            })
+            .collect::<Vec<_>>();
+
+        // The number of null-terminated strings to keep at each referenced offset
+        let mut references = BTreeMap::new();
+        if !result.text_memory.is_empty() {
+            // Valhalla always starts the text list with an empty string
+            references.insert(0, 1);
+        }
+        for range in &record_ranges {
+            let record = result.edge_info_memory.get(range.clone()).ok_or_else(|| {
+                GraphTileBuildError::InvalidIndex(format!(
+                    "The edge info offset {} is out of bounds",
+                    range.start
+                ))
+            })?;
+            for (offset, count) in edge_info_text_references(record)? {
+                let entry = references.entry(offset).or_insert(count);
+                *entry = (*entry).max(count);
+            }
+        }
+        let other_offsets = result
+            .signs
+            .iter()
+            .map(|sign| sign.text_offset.get())
+            .chain(result.turn_lanes.iter().map(|lane| lane.text_offset.get()))
+            .chain(result.admins.iter().flat_map(|admin| {
+                [
+                    admin.country_name_offset.get(),
+                    admin.principal_subdivision_offset.get(),
+                ]
+            }));
+        for offset in other_offsets {
+            references.entry(offset).or_insert(1);
+        }
+
+        let mut text_memory = Vec::with_capacity(result.text_memory.len());
+        let mut new_offsets = HashMap::with_capacity(references.len());
+        for (offset, count) in references {
+            let start = offset as usize;
+            let end = result
+                .text_memory
+                .get(start..)
+                .and_then(|text| {
+                    text.iter()
+                        .enumerate()
+                        .filter(|&(_, &b)| b == 0)
+                        .nth(count - 1)
+                })
+                .map(|(length, _)| start + length + 1)
+                .ok_or_else(|| {
+                    GraphTileBuildError::InvalidFeature(format!(
+                        "Text offset {offset} is outside of the text list"
+                    ))
+                })?;
+            new_offsets.insert(offset, u32::try_from(text_memory.len())?);
+            text_memory.extend_from_slice(&result.text_memory[start..end]);
+        }
+
+        // Every offset was found above
+        let new_offset = |offset: u32| new_offsets[&offset];
+        let edge_info_memory = result.edge_info_memory.to_mut();
+        for range in record_ranges {
+            remap_edge_info_text(&mut edge_info_memory[range], new_offset)?;
+        }
+        for sign in result.signs.to_mut() {
+            sign.text_offset = new_offset(sign.text_offset.get()).into();
+        }
+        for lane in result.turn_lanes.to_mut() {
+            lane.text_offset = new_offset(lane.text_offset.get()).into();
+        }
+        for admin in result.admins.to_mut() {
+            admin.country_name_offset = new_offset(admin.country_name_offset.get()).into();
+            admin.principal_subdivision_offset =
+                new_offset(admin.principal_subdivision_offset.get()).into();
+        }
+        result.text_memory = Cow::Owned(text_memory);
+        result.text_offsets = None;
 
         Ok(result)
     }
@@ -310,6 +1471,105 @@ impl GraphTileBuilder<'_> {
         self.with_predicted_speed_coefficients(directed_edge_index, &compress_speed_buckets(speeds))
     }
 
+    /// Removes the predicted speeds from a directed edge.
+    ///
+    /// This clears the edge's predicted speed flag and offset,
+    /// but leaves the speed profile itself in the tile.
+    /// Use [`without_orphaned_predicted_speeds`](GraphTileBuilder::without_orphaned_predicted_speeds)
+    /// to reclaim the space once you're done removing speeds.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the directed edge index is out of bounds.
+    pub fn without_predicted_speeds(
+        self,
+        directed_edge_index: usize,
+    ) -> Result<Self, GraphTileBuildError> {
+        let mut result = self;
+        if directed_edge_index >= result.directed_edges.len() {
+            return Err(GraphTileBuildError::InvalidIndex(format!(
+                "Attempted to remove predicted speeds from directed edge index {directed_edge_index}, but tile only has {} edges",
+                result.directed_edges.len()
+            )));
+        }
+
+        result.directed_edges.to_mut()[directed_edge_index].set_has_predicted_speed(false);
+        // Edges added since the offsets were last grown don't have an offset yet
+        if let Some(offset) = result
+            .predicted_speed_offsets
+            .to_mut()
+            .get_mut(directed_edge_index)
+        {
+            *offset = 0.into();
+        }
+
+        Ok(result)
+    }
+
+    /// Drops any speed profiles which are no longer used by a directed edge.
+    ///
+    /// The remaining profiles are kept in their original order, and edge offsets are updated to match.
+    /// If no edges have predicted speeds left, the predicted speed sections are removed entirely,
+    /// so the tile is the same as if it never had any.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an edge with predicted speeds has no speed profile (this is a corrupt tile).
+    pub fn without_orphaned_predicted_speeds(self) -> Result<Self, GraphTileBuildError> {
+        let mut result = self;
+
+        // Old offset -> new offset, in the original order
+        let mut used_offsets = BTreeMap::new();
+        for (edge_index, edge) in result.directed_edges.iter().enumerate() {
+            if !edge.has_predicted_speed() {
+                continue;
+            }
+            let offset = result
+                .predicted_speed_offsets
+                .get(edge_index)
+                .map(|offset| offset.get() as usize)
+                .filter(|offset| {
+                    offset + COEFFICIENT_COUNT <= result.predicted_speed_profile_memory.len()
+                })
+                .ok_or_else(|| {
+                    GraphTileBuildError::InvalidIndex(format!(
+                        "Directed edge {edge_index} has predicted speeds, but no speed profile"
+                    ))
+                })?;
+            used_offsets.insert(offset, 0);
+        }
+
+        if used_offsets.is_empty() {
+            result.predicted_speed_offsets = Cow::default();
+            result.predicted_speed_profile_memory = Cow::default();
+            return Ok(result);
+        }
+
+        let mut profiles = Vec::with_capacity(used_offsets.len() * COEFFICIENT_COUNT);
+        for (&old_offset, new_offset) in &mut used_offsets {
+            *new_offset = u32::try_from(profiles.len())?;
+            profiles.extend_from_slice(
+                &result.predicted_speed_profile_memory[old_offset..old_offset + COEFFICIENT_COUNT],
+            );
+        }
+
+        let mut result = result.grow_predicted_speeds_if_needed(true);
+        let predicted_speed_offsets = result.predicted_speed_offsets.to_mut();
+        for (offset, edge) in predicted_speed_offsets
+            .iter_mut()
+            .zip(result.directed_edges.iter())
+        {
+            *offset = if edge.has_predicted_speed() {
+                used_offsets[&(offset.get() as usize)].into()
+            } else {
+                0.into()
+            };
+        }
+        result.predicted_speed_profile_memory = Cow::Owned(profiles);
+
+        Ok(result)
+    }
+
     fn grow_predicted_speeds_if_needed(self, force_create_offsets_array: bool) -> Self {
         assert!(
             self.predicted_speed_offsets.len() <= self.directed_edges.len(),
@@ -363,8 +1623,18 @@ impl GraphTileBuilder<'_> {
     ///
     /// If you hit any of these, it is a bug in Valinor, not your code.
     pub fn into_byte_iter(self) -> Result<impl Iterator<Item = Box<[u8]>>, GraphTileBuildError> {
+        let intermediate = if self.bins_are_stale {
+            self.rebuild_bins()?
+        } else {
+            self
+        };
+        let intermediate = if intermediate.text_is_stale {
+            intermediate.compact_text_list()?
+        } else {
+            intermediate
+        };
         // Validate and finalize predicted speeds arrays (sizes and counts)
-        let intermediate = self.grow_predicted_speeds_if_needed(false);
+        let intermediate = intermediate.grow_predicted_speeds_if_needed(false);
         if intermediate.predicted_speed_profile_memory.is_empty() {
             assert!(
                 intermediate.predicted_speed_offsets.is_empty(),
@@ -389,8 +1659,8 @@ impl GraphTileBuilder<'_> {
         let header = GraphTileHeaderBuilder {
             version: intermediate.writer_version,
             graph_id: intermediate.graph_id,
-            density: intermediate.remove_me_header.density(),
-            has_elevation: intermediate.remove_me_header.has_elevation(),
+            density: intermediate.density,
+            has_elevation: intermediate.has_elevation,
             has_ext_directed_edges: !intermediate.ext_directed_edges.is_empty(),
             sw_corner: intermediate.sw_corner,
             dataset_id: intermediate.dataset_id,
@@ -409,7 +1679,7 @@ impl GraphTileBuilder<'_> {
             access_restriction_count: intermediate.access_restrictions.len(),
             admin_count: intermediate.admins.len(),
             create_date: intermediate.create_date,
-            bin_offsets: intermediate.remove_me_header.bin_offsets.map(Into::into),
+            bin_offsets: intermediate.bin_offsets,
             complex_forward_restrictions_size: intermediate
                 .complex_forward_restrictions_memory
                 .len(),
@@ -464,6 +1734,79 @@ impl GraphTileBuilder<'_> {
     }
 }
 
+/// Is the tile on the local level (the only one with edge bins)?
+fn is_local_level(graph_id: GraphId) -> bool {
+    STANDARD_LEVELS
+        .last()
+        .is_some_and(|level| level.level == graph_id.level())
+}
+
+/// Computes the south-west corner of a tile.
+fn tile_sw_corner(
+    tiling_system: &TilingSystem,
+    tile_id: u64,
+) -> Result<Coord<f32>, GraphTileBuildError> {
+    // The tile count fits in a u32, so the row and column do too
+    let n_cols = u64::from(tiling_system.n_cols);
+    let row = u32::try_from(tile_id / n_cols)?;
+    let col = u32::try_from(tile_id % n_cols)?;
+    #[expect(
+        clippy::cast_precision_loss,
+        reason = "The standard tiling systems have far fewer than 2^24 rows and columns."
+    )]
+    let sw_corner = tiling_system.bounding_box.min()
+        + coord! {
+            x: col as f32 * tiling_system.tile_size,
+            y: row as f32 * tiling_system.tile_size,
+        };
+
+    Ok(sw_corner)
+}
+
+/// Finds the local level bins which a shape passes through, keyed by tile.
+///
+/// Bins are closed, so a shape touching the boundary between two bins is in both.
+fn bins_intersecting_shape(
+    shape: &[Coord<f64>],
+) -> Result<BTreeMap<GraphId, BTreeSet<usize>>, GraphTileBuildError> {
+    let mut result: BTreeMap<GraphId, BTreeSet<usize>> = BTreeMap::new();
+    let Some(local_level) = STANDARD_LEVELS.last() else {
+        return Ok(result);
+    };
+    let tiling_system = &local_level.tiling_system;
+    let n_subdivisions = tiling_system.n_subdivisions;
+    let bin_size = f64::from(tiling_system.tile_size) / f64::from(n_subdivisions);
+
+    for segment in shape.windows(2) {
+        let line = Line::new(segment[0], segment[1]);
+        let bbox = line.bounding_rect();
+        for tile_id in local_level.tiles_intersecting_bbox(
+            bbox.max().y,
+            bbox.max().x,
+            bbox.min().y,
+            bbox.min().x,
+        ) {
+            let sw_corner = tile_sw_corner(tiling_system, tile_id.tile_id())?;
+            for row in 0..n_subdivisions {
+                for col in 0..n_subdivisions {
+                    let min = coord! {
+                        x: f64::from(sw_corner.x) + f64::from(col) * bin_size,
+                        y: f64::from(sw_corner.y) + f64::from(row) * bin_size,
+                    };
+                    let bin = Rect::new(min, min + coord! { x: bin_size, y: bin_size });
+                    if line.intersects(&bin) {
+                        result.entry(tile_id).or_default().insert(
+                            usize::from(row) * usize::from(n_subdivisions) + usize::from(col),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(result)
+}
+
 /// An iterator that lazily produces a full graph tile without too many extra allocations.
 ///
 /// Specifically, while this iterator is not zero-cost,
@@ -624,9 +1967,21 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::graph_tile::{GraphTileBuilder, GraphTileHeader, OwnedGraphTileHandle};
+    use crate::graph_tile::{
+        AccessRestriction, AccessRestrictionType, AdminInfo, ComplexRestriction, DirectedEdge,
+        GraphTile, GraphTileBuildError, GraphTileBuilder, GraphTileHeader, HEADING_SAMPLE_DISTANCE,
+        NewDirectedEdge, NewNode, OwnedGraphTileHandle, RestrictionType, TEST_GRAPH_TILE_L0,
+        TEST_GRAPH_TILE_L2, TimeDomain, TurnLaneDirection,
+    };
+    use crate::spatial::heading_along_line;
+    use crate::{Access, GraphId, RoadClass, RoadUse};
+    use chrono::DateTime;
+    use enumset::EnumSet;
+    use geo::{Coord, Distance, Haversine, Point, coord};
+    use std::collections::{HashMap, HashSet};
     use std::path::Path;
     use walkdir::WalkDir;
+    use zerocopy::IntoBytes;
 
     fn assert_round_trip_unmodified_equals_original(path: &Path) {
         const HEADER_SIZE: usize = size_of::<GraphTileHeader>();
@@ -755,4 +2110,819 @@ mod tests {
 
         assert_eq!(out_bytes, expected_out_bytes);
     }
+
+    #[test]
+    fn set_header_metadata() {
+        let original = &*TEST_GRAPH_TILE_L2;
+        let original_corner = original.header().sw_corner();
+        let sw_corner = coord! { x: original_corner.x - 0.5, y: original_corner.y - 0.25 };
+        let create_date = DateTime::parse_from_rfc3339("2024-06-15T12:34:56Z")
+            .unwrap()
+            .to_utc();
+        let tile = OwnedGraphTileHandle::try_from(
+            GraphTileBuilder::from(original)
+                .with_dataset_id(123_456_789)
+                .with_create_date(create_date)
+                .with_sw_corner(sw_corner)
+                .unwrap()
+                .into_bytes()
+                .unwrap(),
+        )
+        .unwrap();
+
+        let header = tile.header();
+        assert_eq!(header.dataset_id.get(), 123_456_789);
+        assert_eq!(header.create_date().date_naive(), create_date.date_naive());
+        assert_eq!(header.sw_corner(), sw_corner);
+        assert_eq!(tile.validate().issues, []);
+        for (node, original_node) in tile.nodes().iter().zip(original.nodes()) {
+            let coordinate = node.coordinate(sw_corner);
+            let original_coordinate = original_node.coordinate(original_corner);
+            assert!((coordinate.x - original_coordinate.x).abs() < 1e-5);
+            assert!((coordinate.y - original_coordinate.y).abs() < 1e-5);
+        }
+
+        // Nodes can't end up outside the tile
+        assert!(matches!(
+            GraphTileBuilder::from(original)
+                .with_sw_corner(coord! { x: original_corner.x, y: original_corner.y + 0.25 }),
+            Err(GraphTileBuildError::InvalidFeature(_))
+        ));
+    }
+
+    #[test]
+    fn remove_predicted_speeds() {
+        let fixtures = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let tile_path = std::path::Path::new("0").join("003").join("015.gph");
+        let read_tile = |dir: &str| {
+            OwnedGraphTileHandle::try_from(
+                std::fs::read(fixtures.join(dir).join(&tile_path)).expect("Unable to read file"),
+            )
+            .expect("Unable to get tile handle")
+        };
+        let tile = read_tile("andorra-tiles-with-traffic");
+        let speeds = |tile: &OwnedGraphTileHandle, index| {
+            (0..7 * 24 * 3600)
+                .step_by(3600)
+                .map(|seconds| tile.get_predicted_speed(index, seconds))
+                .collect::<Vec<_>>()
+        };
+
+        // Removing one edge keeps the other edge's profile intact
+        let partial = OwnedGraphTileHandle::try_from(
+            GraphTileBuilder::from(&tile)
+                .without_predicted_speeds(7)
+                .unwrap()
+                .without_orphaned_predicted_speeds()
+                .unwrap()
+                .into_bytes()
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(!partial.directed_edges()[7].has_predicted_speed());
+        assert_eq!(partial.get_predicted_speed(7, 0), None);
+        assert_eq!(speeds(&partial, 42), speeds(&tile, 42));
+        assert_eq!(
+            partial.header().predicted_speeds_count(),
+            tile.header().predicted_speeds_count() - 1
+        );
+
+        // Removing all of them gives the same tile as never having any
+        let stripped = GraphTileBuilder::from(&partial)
+            .without_predicted_speeds(42)
+            .unwrap()
+            .without_orphaned_predicted_speeds()
+            .unwrap()
+            .into_bytes()
+            .unwrap();
+        let original = read_tile("andorra-tiles");
+        let expected = GraphTileBuilder::from(&original)
+            .with_average_speeds(0, 50, 40)
+            .unwrap()
+            .with_average_speeds(42, 100, 42)
+            .unwrap()
+            .with_average_speeds(7, 12, 34)
+            .unwrap()
+            .into_bytes()
+            .unwrap();
+        assert_eq!(stripped, expected);
+
+        assert!(matches!(
+            GraphTileBuilder::from(&tile).without_predicted_speeds(tile.directed_edges().len()),
+            Err(GraphTileBuildError::InvalidIndex(_))
+        ));
+    }
+
+    fn new_edge(end_node_id: GraphId, shape: &[Coord<f64>]) -> NewDirectedEdge {
+        NewDirectedEdge {
+            end_node_id,
+            opposing_edge_index: 0,
+            shape: shape.to_vec(),
+            names: vec!["Carrer Major".to_string()],
+            // Exercises all of the extended way ID bytes
+            way_id: 0x0102_0304_0506_0708,
+            speed: 40,
+            speed_limit: 50,
+            classification: RoadClass::Residential,
+            road_use: RoadUse::Road,
+            forward_access: EnumSet::all(),
+            reverse_access: EnumSet::all(),
+        }
+    }
+
+    fn new_node(coordinate: Coord<f64>, edges: Vec<NewDirectedEdge>) -> NewNode {
+        NewNode {
+            coordinate,
+            access: EnumSet::all(),
+            admin_index: 0,
+            is_traffic_signal: false,
+            drive_on_right: true,
+            edges,
+        }
+    }
+
+    /// Adds two new nodes, connected by a pair of opposing edges.
+    fn with_edge_pair<'a>(
+        builder: GraphTileBuilder<'a>,
+        shape: &[Coord<f64>],
+    ) -> (GraphTileBuilder<'a>, GraphId) {
+        let start_id = builder.next_node_id().unwrap();
+        let end_id = start_id
+            .with_feature_index(start_id.feature_index() + 1)
+            .unwrap();
+        let reversed: Vec<_> = shape.iter().rev().copied().collect();
+        let builder = builder
+            .with_node(&new_node(shape[0], vec![new_edge(end_id, shape)]))
+            .unwrap()
+            .with_node(&new_node(reversed[0], vec![new_edge(start_id, &reversed)]))
+            .unwrap();
+        (builder, start_id)
+    }
+
+    #[test]
+    fn build_tile_from_scratch() {
+        let graph_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+        let shape = [
+            coord! { x: 1.31, y: 42.31 },
+            coord! { x: 1.32, y: 42.315 },
+            coord! { x: 1.36, y: 42.32 },
+        ];
+        let (builder, start_id) = with_edge_pair(GraphTileBuilder::new(graph_id).unwrap(), &shape);
+        let tile = OwnedGraphTileHandle::try_from(builder.into_bytes().unwrap()).unwrap();
+
+        assert_eq!(tile.validate().issues, []);
+        assert_eq!(tile.graph_id(), graph_id);
+        assert_eq!(tile.header().node_count(), 2);
+        assert_eq!(tile.header().directed_edge_count(), 2);
+        assert_eq!(
+            tile.header().sw_corner(),
+            TEST_GRAPH_TILE_L2.header().sw_corner()
+        );
+
+        let node = tile.get_node(start_id).unwrap();
+        let coordinate = node.coordinate(tile.header().sw_corner());
+        // Coordinates are returned as f32, which has a resolution of a few 1e-6 degrees here
+        assert!((f64::from(coordinate.x) - 1.31).abs() < 1e-5);
+        assert!((f64::from(coordinate.y) - 42.31).abs() < 1e-5);
+        let heading = heading_along_line(&shape, HEADING_SAMPLE_DISTANCE).unwrap();
+        assert!((f64::from(node.heading(0).unwrap()) - heading).abs() <= 2.0);
+
+        // The opposing edges share a single edge info record
+        let [forward, reverse] = tile.directed_edges() else {
+            panic!("Expected exactly two edges");
+        };
+        assert_eq!(forward.edge_info_offset(), reverse.edge_info_offset());
+        assert!(forward.edge_info_is_forward());
+        assert!(!reverse.edge_info_is_forward());
+        assert!(!forward.leaves_tile());
+        assert_eq!(forward.length(), reverse.length());
+        let length: f64 = shape
+            .windows(2)
+            .map(|pair| Haversine.distance(Point(pair[0]), Point(pair[1])))
+            .sum();
+        assert!((f64::from(forward.length()) - length).abs() <= 0.5);
+
+        let edge_info = tile.get_edge_info(forward).unwrap();
+        assert_eq!(edge_info.way_id(), 0x0102_0304_0506_0708);
+        assert_eq!(edge_info.speed_limit(), 50);
+        assert_eq!(edge_info.get_names(), ["Carrer Major"]);
+        let decoded = edge_info.decode_raw_shape::<f64>().unwrap();
+        assert_eq!(decoded.len(), shape.len());
+        for (a, b) in decoded.iter().zip(shape) {
+            assert!((a.x - b.x).abs() < 1e-6 && (a.y - b.y).abs() < 1e-6);
+        }
+
+        // Only the forward edge is binned, once in each bin it passes through
+        let forward_id = graph_id.with_feature_index(0).unwrap();
+        assert_eq!(tile.edges_in_bin(6), [forward_id]);
+        assert_eq!(tile.edges_in_bin(7), [forward_id]);
+        assert_eq!(
+            (0..crate::BIN_COUNT)
+                .map(|bin| tile.edges_in_bin(bin).len())
+                .sum::<usize>(),
+            2
+        );
+    }
+
+    #[test]
+    fn append_nodes_to_existing_tile() {
+        let original = &*TEST_GRAPH_TILE_L2;
+        let shape = [coord! { x: 1.46, y: 42.46 }, coord! { x: 1.461, y: 42.461 }];
+        let (builder, start_id) = with_edge_pair(GraphTileBuilder::from(original), &shape);
+        let tile = OwnedGraphTileHandle::try_from(builder.into_bytes().unwrap()).unwrap();
+
+        assert_eq!(tile.validate().issues, []);
+        let node_count = original.nodes().len();
+        let edge_count = original.directed_edges().len();
+        assert_eq!(start_id.feature_index(), node_count as u64);
+        assert_eq!(tile.nodes().len(), node_count + 2);
+        assert_eq!(tile.directed_edges().len(), edge_count + 2);
+        assert_eq!(tile.admins().len(), original.admins().len());
+
+        // Existing features are untouched
+        assert_eq!(
+            tile.nodes()[..node_count].as_bytes(),
+            original.nodes().as_bytes()
+        );
+        assert_eq!(
+            tile.directed_edges()[..edge_count].as_bytes(),
+            original.directed_edges().as_bytes()
+        );
+
+        let edge = &tile.directed_edges()[edge_count];
+        assert_eq!(
+            tile.get_edge_info(edge).unwrap().get_names(),
+            ["Carrer Major"]
+        );
+        let bin_entry_count = |tile: &OwnedGraphTileHandle| {
+            (0..crate::BIN_COUNT)
+                .map(|bin| tile.edges_in_bin(bin).len())
+                .sum::<usize>()
+        };
+        assert_eq!(bin_entry_count(&tile), bin_entry_count(original) + 1);
+    }
+
+    #[test]
+    fn reject_invalid_features() {
+        let graph_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+        let builder = || GraphTileBuilder::new(graph_id).unwrap();
+        let end_id = graph_id.with_feature_index(1).unwrap();
+
+        let point = coord! { x: 1.30, y: 42.30 };
+        let result = builder().with_node(&new_node(point, vec![new_edge(end_id, &[point])]));
+        assert!(matches!(
+            result,
+            Err(GraphTileBuildError::InvalidFeature(_))
+        ));
+
+        // Outside the tile
+        let point = coord! { x: 1.20, y: 42.30 };
+        let result = builder().with_node(&new_node(point, vec![]));
+        assert!(matches!(
+            result,
+            Err(GraphTileBuildError::InvalidFeature(_))
+        ));
+
+        let transit_id = GraphId::try_from_components(3, 762_485, 0).unwrap();
+        assert!(matches!(
+            GraphTileBuilder::new(transit_id),
+            Err(GraphTileBuildError::UnsupportedLevel(3))
+        ));
+    }
+
+    #[test]
+    fn rebuild_bins() {
+        let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles");
+        let load = |graph_id: GraphId| {
+            let path = base_dir.join(graph_id.file_path("gph").unwrap());
+            OwnedGraphTileHandle::try_from(std::fs::read(path).unwrap()).unwrap()
+        };
+        let original = &*TEST_GRAPH_TILE_L2;
+        let bins = |tile: &OwnedGraphTileHandle| {
+            (0..crate::BIN_COUNT)
+                .map(|bin| tile.edges_in_bin(bin).to_vec())
+                .collect::<Vec<_>>()
+        };
+
+        // Rebuilding from the shapes reproduces the bins written by Valhalla
+        let rebuilt = OwnedGraphTileHandle::try_from(
+            GraphTileBuilder::from(original)
+                .rebuild_bins()
+                .unwrap()
+                .into_bytes()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(bins(&rebuilt), bins(original));
+
+        // Recompute the entries for edges from the other levels and the neighboring tile
+        let mut builder = GraphTileBuilder::from(original).rebuild_bins().unwrap();
+        let mut sources = HashMap::new();
+        for (level, tile_id) in [(0, 3015), (1, 47_701), (2, 762_486)] {
+            let source = load(GraphId::try_from_components(level, tile_id, 0).unwrap());
+            let cross_tile_bins = GraphTileBuilder::from(&source).cross_tile_bins().unwrap();
+            builder = builder
+                .with_cross_tile_bins(source.graph_id(), &cross_tile_bins[&original.graph_id()])
+                .unwrap();
+            sources.insert(source.graph_id(), source);
+        }
+        let tile = OwnedGraphTileHandle::try_from(builder.into_bytes().unwrap()).unwrap();
+        // Opposing edges share a shape, so compare by edge info record.
+        // Valhalla occasionally bins the other edge of a pair,
+        // and skips bins which a shape only barely clips near tile boundaries,
+        // so every entry it wrote should be covered, but there may be a few extras.
+        let records = |tile: &OwnedGraphTileHandle, bin: usize| {
+            tile.edges_in_bin(bin)
+                .iter()
+                .filter(|edge_id| edge_id.tile_base_id() != original.graph_id())
+                .map(|edge_id| {
+                    let source = &sources[&edge_id.tile_base_id()];
+                    let edge = source.get_directed_edge(*edge_id).unwrap();
+                    (source.graph_id(), edge.edge_info_offset())
+                })
+                .collect::<HashSet<_>>()
+        };
+        for bin in 0..crate::BIN_COUNT {
+            assert!(records(original, bin).is_subset(&records(&tile, bin)));
+        }
+
+        // Replacing the entries from a source tile
+        let source_id = GraphId::try_from_components(0, 3015, 0).unwrap();
+        let tile = OwnedGraphTileHandle::try_from(
+            GraphTileBuilder::from(&tile)
+                .with_cross_tile_bins(source_id, &Default::default())
+                .unwrap()
+                .into_bytes()
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(
+            (0..crate::BIN_COUNT)
+                .flat_map(|bin| tile.edges_in_bin(bin))
+                .all(|edge_id| edge_id.tile_base_id() != source_id)
+        );
+
+        assert!(matches!(
+            GraphTileBuilder::from(original)
+                .with_cross_tile_bins(original.graph_id(), &Default::default()),
+            Err(GraphTileBuildError::InvalidFeature(_))
+        ));
+        assert!(matches!(
+            GraphTileBuilder::from(&*TEST_GRAPH_TILE_L0)
+                .with_cross_tile_bins(original.graph_id(), &Default::default()),
+            Err(GraphTileBuildError::InvalidFeature(_))
+        ));
+    }
+
+    #[test]
+    fn add_and_remove_access_restrictions() {
+        let original = &*TEST_GRAPH_TILE_L0;
+        let restricted_index =
+            original.borrow_dependent().access_restrictions[0].edge_index() as usize;
+        let existing = original
+            .get_access_restrictions(u32::try_from(restricted_index).unwrap(), EnumSet::all())
+            .len();
+        let (free_index, _) = original
+            .directed_edges()
+            .iter()
+            .enumerate()
+            .find(|(_, edge)| edge.access_restriction_modes().is_empty())
+            .expect("Expected an edge without restrictions");
+
+        let weight_limit =
+            || AccessRestriction::new(AccessRestrictionType::MaxWeight, Access::Truck.into(), 750);
+        let height_limit = || {
+            AccessRestriction::new(
+                AccessRestrictionType::MaxHeight,
+                Access::Truck | Access::Bus,
+                400,
+            )
+        };
+        let tile = OwnedGraphTileHandle::try_from(
+            GraphTileBuilder::from(original)
+                .with_access_restriction(free_index, weight_limit())
+                .unwrap()
+                .with_access_restriction(free_index, height_limit())
+                .unwrap()
+                .with_access_restriction(restricted_index, weight_limit())
+                .unwrap()
+                .into_bytes()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(tile.validate().issues, []);
+        assert_eq!(
+            tile.header().access_restriction_count(),
+            original.header().access_restriction_count() + 3
+        );
+
+        let restrictions = |tile: &OwnedGraphTileHandle, index: usize, modes| {
+            tile.get_access_restrictions(u32::try_from(index).unwrap(), modes)
+                .into_iter()
+                .map(|r| (r.restriction_type(), r.value()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            restrictions(&tile, free_index, EnumSet::all()),
+            [
+                (AccessRestrictionType::MaxWeight, 750),
+                (AccessRestrictionType::MaxHeight, 400)
+            ]
+        );
+        assert_eq!(
+            restrictions(&tile, free_index, Access::Bus.into()),
+            [(AccessRestrictionType::MaxHeight, 400)]
+        );
+        assert_eq!(
+            tile.directed_edges()[free_index].access_restriction_modes(),
+            Access::Truck | Access::Bus
+        );
+        // New restrictions go after the existing ones
+        let all = restrictions(&tile, restricted_index, EnumSet::all());
+        assert_eq!(all.len(), existing + 1);
+        assert_eq!(all.last(), Some(&(AccessRestrictionType::MaxWeight, 750)));
+
+        let tile = OwnedGraphTileHandle::try_from(
+            GraphTileBuilder::from(&tile)
+                .without_access_restrictions(free_index, |r| {
+                    r.restriction_type() == AccessRestrictionType::MaxHeight
+                })
+                .unwrap()
+                .without_access_restrictions(restricted_index, |_| true)
+                .unwrap()
+                .into_bytes()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(tile.validate().issues, []);
+        assert_eq!(
+            restrictions(&tile, free_index, EnumSet::all()),
+            [(AccessRestrictionType::MaxWeight, 750)]
+        );
+        assert_eq!(
+            tile.directed_edges()[free_index].access_restriction_modes(),
+            Access::Truck
+        );
+        assert!(restrictions(&tile, restricted_index, EnumSet::all()).is_empty());
+        assert!(
+            tile.directed_edges()[restricted_index]
+                .access_restriction_modes()
+                .is_empty()
+        );
+
+        let edge_count = original.directed_edges().len();
+        assert!(matches!(
+            GraphTileBuilder::from(original).with_access_restriction(edge_count, weight_limit()),
+            Err(GraphTileBuildError::InvalidIndex(_))
+        ));
+        assert!(matches!(
+            GraphTileBuilder::from(original).without_access_restrictions(edge_count, |_| true),
+            Err(GraphTileBuildError::InvalidIndex(_))
+        ));
+    }
+
+    #[test]
+    fn add_complex_restrictions() {
+        let original = &*TEST_GRAPH_TILE_L2;
+        let base_id = original.graph_id();
+        let edge = |index| base_id.with_feature_index(index).unwrap();
+        let local = ComplexRestriction {
+            from_edge_id: edge(0),
+            to_edge_id: edge(2),
+            via_edge_ids: vec![edge(1)],
+            restriction_type: RestrictionType::NoUTurn,
+            affected_access_modes: Access::Auto | Access::Bus,
+            probability: 0,
+            time_domain: None,
+        };
+        // Starts in this tile, but ends in the neighboring one
+        let outbound = ComplexRestriction {
+            from_edge_id: edge(10),
+            to_edge_id: GraphId::try_from_components(2, 762_486, 0).unwrap(),
+            via_edge_ids: vec![],
+            restriction_type: RestrictionType::NoEntry,
+            affected_access_modes: Access::Truck.into(),
+            probability: 0,
+            time_domain: Some(TimeDomain {
+                dow_mask: 0b011_1110,
+                begin_hours: 7,
+                end_hours: 9,
+                ..TimeDomain::default()
+            }),
+        };
+
+        let tile = OwnedGraphTileHandle::try_from(
+            GraphTileBuilder::from(original)
+                .with_complex_restriction(&local)
+                .unwrap()
+                .with_complex_restriction(&outbound)
+                .unwrap()
+                .into_bytes()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(tile.validate().issues, []);
+        assert_eq!(tile.header().complex_forward_restrictions_size(), 32);
+        assert_eq!(tile.header().complex_reverse_restrictions_size(), 32 + 24);
+
+        assert_eq!(
+            tile.get_complex_restrictions(edge(2), true, Access::Auto.into())
+                .unwrap(),
+            std::slice::from_ref(&local)
+        );
+        assert_eq!(
+            tile.get_complex_restrictions(edge(0), false, EnumSet::all())
+                .unwrap(),
+            [local]
+        );
+        assert_eq!(
+            tile.get_complex_restrictions(edge(10), false, Access::Truck.into())
+                .unwrap(),
+            std::slice::from_ref(&outbound)
+        );
+        // Different modes, and the wrong direction
+        assert!(
+            tile.get_complex_restrictions(edge(10), false, Access::Auto.into())
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            tile.get_complex_restrictions(edge(10), true, EnumSet::all())
+                .unwrap()
+                .is_empty()
+        );
+
+        let edges = tile.directed_edges();
+        assert_eq!(
+            edges[0].start_restriction_modes(),
+            Access::Auto | Access::Bus
+        );
+        assert_eq!(edges[2].end_restriction_modes(), Access::Auto | Access::Bus);
+        assert_eq!(edges[10].start_restriction_modes(), Access::Truck);
+        assert!(edges[10].end_restriction_modes().is_empty());
+        for index in [0, 1, 2, 10] {
+            assert!(edges[index].is_part_of_complex_restriction());
+        }
+        assert!(!edges[3].is_part_of_complex_restriction());
+
+        // The restriction has to start or end in the tile
+        let foreign = ComplexRestriction {
+            from_edge_id: outbound.to_edge_id,
+            to_edge_id: outbound.to_edge_id,
+            ..outbound
+        };
+        assert!(matches!(
+            GraphTileBuilder::from(original).with_complex_restriction(&foreign),
+            Err(GraphTileBuildError::InvalidFeature(_))
+        ));
+    }
+
+    #[test]
+    fn set_turn_lanes() {
+        let original = &*TEST_GRAPH_TILE_L0;
+        let lanes = [
+            TurnLaneDirection::Left.into(),
+            TurnLaneDirection::Through | TurnLaneDirection::Right,
+        ];
+        // One edge before and one after the existing turn lanes, and one which already has some
+        let last_index = original.directed_edges().len() - 1;
+        let tile = OwnedGraphTileHandle::try_from(
+            GraphTileBuilder::from(original)
+                .with_turn_lanes(0, &lanes)
+                .unwrap()
+                .with_turn_lanes(last_index, &lanes)
+                .unwrap()
+                .with_turn_lanes(1710, &[])
+                .unwrap()
+                .into_bytes()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(tile.validate().issues, []);
+        assert_eq!(
+            tile.header().turn_lane_count(),
+            original.header().turn_lane_count() + 1
+        );
+
+        for index in [0, last_index] {
+            assert!(tile.directed_edges()[index].has_turn_lanes());
+            assert_eq!(
+                tile.get_turn_lanes(u32::try_from(index).unwrap()).unwrap(),
+                lanes
+            );
+        }
+        assert!(!tile.directed_edges()[1710].has_turn_lanes());
+        assert!(tile.get_turn_lanes(1710).unwrap().is_empty());
+        // The other turn lanes are untouched
+        assert_eq!(
+            tile.get_turn_lanes(1728).unwrap(),
+            original.get_turn_lanes(1728).unwrap()
+        );
+
+        // Replacing existing lanes
+        let tile = OwnedGraphTileHandle::try_from(
+            GraphTileBuilder::from(&tile)
+                .with_turn_lanes(0, &[TurnLaneDirection::Reverse.into()])
+                .unwrap()
+                .into_bytes()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            tile.get_turn_lanes(0).unwrap(),
+            [EnumSet::from(TurnLaneDirection::Reverse)]
+        );
+        assert_eq!(
+            tile.header().turn_lane_count(),
+            original.header().turn_lane_count() + 1
+        );
+
+        assert!(matches!(
+            GraphTileBuilder::from(original).with_turn_lanes(last_index + 1, &lanes),
+            Err(GraphTileBuildError::InvalidIndex(_))
+        ));
+    }
+
+    #[test]
+    fn set_node_admins() {
+        let original = &*TEST_GRAPH_TILE_L0;
+        let base_id = original.graph_id();
+        // A node whose edges all stay in the tile
+        let node_index = original
+            .nodes()
+            .iter()
+            .position(|node| {
+                node.edge_count() > 0
+                    && original
+                        .get_outbound_edges_from_node(node)
+                        .iter()
+                        .all(|edge| edge.end_node_id().tile_base_id() == base_id)
+            })
+            .unwrap();
+        let node_id = base_id.with_feature_index(node_index as u64).unwrap();
+        let admin = AdminInfo {
+            country_iso: "XX".into(),
+            principal_subdivision_iso: "A".into(),
+            country_name: "Testland".into(),
+            principal_subdivision_name: "Andorra".into(),
+        };
+
+        let builder = GraphTileBuilder::from(original);
+        let admin_index = builder.next_admin_index().unwrap();
+        assert_eq!(usize::from(admin_index), original.admins().len());
+        let tile = OwnedGraphTileHandle::try_from(
+            builder
+                .with_admin(&admin)
+                .unwrap()
+                .with_node_admin(node_index, admin_index)
+                .unwrap()
+                .into_bytes()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(tile.validate().issues, []);
+        assert_eq!(tile.get_admin_for_node(node_id).unwrap(), admin);
+
+        // Every edge into or out of the node now crosses a border
+        let node = &tile.nodes()[node_index];
+        for edge in tile.get_outbound_edges_from_node(node) {
+            assert!(edge.country_crossing());
+            let end_node = tile.get_node(edge.end_node_id()).unwrap();
+            let opposing_edge =
+                &tile.get_outbound_edges_from_node(end_node)[edge.opposing_edge_index() as usize];
+            assert!(opposing_edge.country_crossing());
+        }
+
+        // Moving the node back clears the flags again
+        let original_admin_index = original.nodes()[node_index].admin_index();
+        let tile = OwnedGraphTileHandle::try_from(
+            GraphTileBuilder::from(&tile)
+                .with_node_admin(node_index, original_admin_index)
+                .unwrap()
+                .into_bytes()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            tile.directed_edges()
+                .iter()
+                .map(DirectedEdge::country_crossing)
+                .collect::<Vec<_>>(),
+            original
+                .directed_edges()
+                .iter()
+                .map(DirectedEdge::country_crossing)
+                .collect::<Vec<_>>()
+        );
+
+        assert!(matches!(
+            GraphTileBuilder::from(original).with_node_admin(node_index, admin_index),
+            Err(GraphTileBuildError::InvalidIndex(_))
+        ));
+        assert!(matches!(
+            GraphTileBuilder::from(original).with_node_admin(original.nodes().len(), 0),
+            Err(GraphTileBuildError::InvalidIndex(_))
+        ));
+        assert!(matches!(
+            GraphTileBuilder::from(original).with_admin(&AdminInfo {
+                country_iso: "XXX".into(),
+                ..admin
+            }),
+            Err(GraphTileBuildError::InvalidFeature(_))
+        ));
+    }
+
+    #[test]
+    fn rename_edges() {
+        let original = &*TEST_GRAPH_TILE_L2;
+        let edges = original.directed_edges();
+        // Pick a named edge from the middle of the tile, so later records need to shift,
+        // whose names no other edge uses, so they should leave the text list
+        let (index, edge) = edges
+            .iter()
+            .enumerate()
+            .skip(edges.len() / 2)
+            .find(|(_, edge)| {
+                let info = original.get_edge_info(edge).unwrap();
+                let names = info.get_names();
+                !names.is_empty()
+                    && edges
+                        .iter()
+                        .filter(|other| other.edge_info_offset() != edge.edge_info_offset())
+                        .all(|other| {
+                            let other_info = original.get_edge_info(other).unwrap();
+                            !other_info
+                                .get_names()
+                                .iter()
+                                .any(|name| names.contains(name))
+                        })
+            })
+            .expect("Expected an edge with unique names");
+        let offset = edge.edge_info_offset();
+
+        let tile = OwnedGraphTileHandle::try_from(
+            GraphTileBuilder::from(original)
+                .with_edge_names(index, &["Carrer Nou", "Carrer Major"])
+                .unwrap()
+                .into_bytes()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(tile.validate().issues, []);
+
+        for (old, new) in edges.iter().zip(tile.directed_edges()) {
+            let old_info = original.get_edge_info(old).unwrap();
+            let new_info = tile.get_edge_info(new).unwrap();
+            // Both directions are renamed; everything else is untouched
+            if old.edge_info_offset() == offset {
+                assert_eq!(new_info.get_names(), ["Carrer Nou", "Carrer Major"]);
+            } else {
+                assert_eq!(new_info.get_names(), old_info.get_names());
+            }
+            assert_eq!(new_info.way_id(), old_info.way_id());
+            assert_eq!(new_info.encoded_shape, old_info.encoded_shape);
+            assert_eq!(
+                new_info.tagged_values().collect::<Vec<_>>(),
+                old_info.tagged_values().collect::<Vec<_>>()
+            );
+        }
+        for node in 0..original.nodes().len() {
+            let node_id = original.graph_id().with_feature_index(node as u64).unwrap();
+            assert_eq!(
+                tile.get_admin_for_node(node_id).unwrap(),
+                original.get_admin_for_node(node_id).unwrap()
+            );
+        }
+
+        // The old names are dropped from the text list
+        let text_list = tile
+            .borrow_dependent()
+            .text_memory
+            .split(|&b| b == 0)
+            .collect::<HashSet<_>>();
+        assert!(text_list.contains("Carrer Nou".as_bytes()));
+        for name in original.get_edge_info(edge).unwrap().get_names() {
+            assert!(!text_list.contains(name.as_bytes()), "{name}");
+        }
+
+        // Removing all names
+        let tile = OwnedGraphTileHandle::try_from(
+            GraphTileBuilder::from(&tile)
+                .with_edge_names(index, &[])
+                .unwrap()
+                .into_bytes()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(tile.validate().issues, []);
+        let edge = &tile.directed_edges()[index];
+        assert!(tile.get_edge_info(edge).unwrap().get_names().is_empty());
+
+        assert!(matches!(
+            GraphTileBuilder::from(&tile).with_edge_names(edges.len(), &["Nowhere"]),
+            Err(GraphTileBuildError::InvalidIndex(_))
+        ));
+    }
 }
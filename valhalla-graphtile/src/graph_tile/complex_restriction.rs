@@ -0,0 +1,583 @@
+use crate::graph_tile::{GraphTileBuildError, GraphTileDecodingError};
+use crate::{Access, GraphId};
+use bitfield_struct::bitfield;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike, Weekday};
+use enumset::EnumSet;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use zerocopy::{FromBytes, IntoBytes, LE, U16, U64};
+use zerocopy_derive::{FromBytes, Immutable, IntoBytes, KnownLayout, Unaligned};
+
+/// The maximum number of via edges in a complex restriction (the count is stored in 5 bits).
+const MAX_VIA_COUNT: usize = (1 << 5) - 1;
+
+/// Types of turn restrictions.
+///
+/// These match the OSM `restriction` tag values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[repr(u8)]
+pub enum RestrictionType {
+    NoLeftTurn = 0,
+    NoRightTurn = 1,
+    NoStraightOn = 2,
+    NoUTurn = 3,
+    OnlyRightTurn = 4,
+    OnlyLeftTurn = 5,
+    OnlyStraightOn = 6,
+    NoEntry = 7,
+    NoExit = 8,
+    NoTurn = 9,
+}
+
+/// A date/time range during which a restriction applies.
+///
+/// This covers the subset of the OSM `opening_hours` syntax that Valhalla supports
+/// (ex: `Mo-Fr 07:00-09:00` or `Oct Su[-1]-Mar Su[-1]`).
+/// Zero means that a field is not set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct TimeDomain {
+    /// If true, `begin_day_dow` and `end_day_dow` are days of the week
+    /// (with the week of the month in `begin_week` and `end_week`);
+    /// otherwise they are days of the month.
+    pub is_nth_day_of_week: bool,
+    /// Day of week mask, starting from Sunday (ex: `0b0111110` for Mo-Fr).
+    pub dow_mask: u8,
+    /// Begin month, from 1 (January) to 12 (December).
+    pub begin_month: u8,
+    /// Begin day of the month, or day of the week (1 = Sunday).
+    pub begin_day_dow: u8,
+    /// Which week of the month the range begins (ex: 1 for the first Sunday in October).
+    pub begin_week: u8,
+    pub begin_hours: u8,
+    pub begin_minutes: u8,
+    /// End month, from 1 (January) to 12 (December).
+    pub end_month: u8,
+    /// End day of the month, or day of the week (1 = Sunday).
+    pub end_day_dow: u8,
+    /// Which week of the month the range ends (ex: 5 for the last Sunday in March).
+    pub end_week: u8,
+    pub end_hours: u8,
+    pub end_minutes: u8,
+}
+
+#[bitfield(u64,
+    repr = U64<LE>,
+    from = bit_twiddling_helpers::conv_u64le::from_inner,
+    into = bit_twiddling_helpers::conv_u64le::into_inner
+)]
+#[derive(FromBytes, IntoBytes, Immutable, Unaligned, KnownLayout)]
+struct TimeDomainBitfield {
+    #[bits(1)]
+    is_nth_day_of_week: u8,
+    #[bits(7)]
+    dow_mask: u8,
+    #[bits(4)]
+    begin_month: u8,
+    #[bits(5)]
+    begin_day_dow: u8,
+    #[bits(3)]
+    begin_week: u8,
+    #[bits(5)]
+    begin_hours: u8,
+    #[bits(6)]
+    begin_minutes: u8,
+    #[bits(4)]
+    end_month: u8,
+    #[bits(5)]
+    end_day_dow: u8,
+    #[bits(3)]
+    end_week: u8,
+    #[bits(5)]
+    end_hours: u8,
+    #[bits(6)]
+    end_minutes: u8,
+    #[bits(10)]
+    _spare: U16<LE>,
+}
+
+impl TimeDomain {
+    /// Decodes a time domain from its packed representation
+    /// (ex: the value of a timed [`AccessRestriction`](super::AccessRestriction)).
+    pub fn from_raw(value: u64) -> Self {
+        Self::from_bitfield(TimeDomainBitfield::from_bits(value.into()))
+    }
+
+    /// Packs the time domain into the representation used in tiles.
+    ///
+    /// # Errors
+    ///
+    /// Fails if any field is out of range for its bitfield.
+    pub fn try_to_raw(&self) -> Result<u64, GraphTileBuildError> {
+        Ok(self.try_to_bitfield()?.into_bits().get())
+    }
+
+    /// Does the time domain include a (local) date and time?
+    ///
+    /// This follows Valhalla's interpretation:
+    /// the day of week mask, date range, and time range must all match (when set).
+    /// Time ranges which end before they begin continue past midnight,
+    /// and date ranges which end before they begin continue into the next year.
+    pub fn contains(&self, local: NaiveDateTime) -> bool {
+        let minute_of_day = local.hour() * 60 + local.minute();
+        let begin = u32::from(self.begin_hours) * 60 + u32::from(self.begin_minutes);
+        let end = u32::from(self.end_hours) * 60 + u32::from(self.end_minutes);
+
+        // An overnight range belongs to the day it started on
+        let mut date = local.date();
+        if begin != end {
+            if begin < end {
+                if minute_of_day < begin || minute_of_day >= end {
+                    return false;
+                }
+            } else if minute_of_day < end {
+                date = date.pred_opt().unwrap_or(date);
+            } else if minute_of_day < begin {
+                return false;
+            }
+        }
+
+        let dow_bit = 1 << date.weekday().num_days_from_sunday();
+        if self.dow_mask != 0 && self.dow_mask & dow_bit == 0 {
+            return false;
+        }
+
+        self.contains_date(date)
+    }
+
+    /// Is the date within the date range (if any)?
+    fn contains_date(&self, date: NaiveDate) -> bool {
+        if self.begin_month == 0 || self.end_month == 0 {
+            return true;
+        }
+
+        let year = date.year();
+        let (Some(begin), Some(end)) = (
+            self.range_date(
+                year,
+                self.begin_month,
+                self.begin_day_dow,
+                self.begin_week,
+                false,
+            ),
+            self.range_date(year, self.end_month, self.end_day_dow, self.end_week, true),
+        ) else {
+            // Invalid dates (ex: February 30th) never match
+            return false;
+        };
+
+        if begin <= end {
+            begin <= date && date <= end
+        } else {
+            date >= begin || date <= end
+        }
+    }
+
+    /// Resolves one end of the date range in a year.
+    ///
+    /// Ranges without a day cover whole months.
+    fn range_date(
+        &self,
+        year: i32,
+        month: u8,
+        day_dow: u8,
+        week: u8,
+        is_end: bool,
+    ) -> Option<NaiveDate> {
+        let month = u32::from(month);
+        if self.is_nth_day_of_week && day_dow != 0 && week != 0 {
+            let weekday = Weekday::try_from((day_dow + 5) % 7).ok()?;
+            // Week 5 is the last week of the month, even when it only has four
+            return NaiveDate::from_weekday_of_month_opt(year, month, weekday, week).or_else(
+                || {
+                    (week == 5)
+                        .then(|| NaiveDate::from_weekday_of_month_opt(year, month, weekday, 4))
+                        .flatten()
+                },
+            );
+        }
+
+        match (day_dow, is_end) {
+            (0, false) => NaiveDate::from_ymd_opt(year, month, 1),
+            (0, true) => NaiveDate::from_ymd_opt(year, month, 1)?
+                .checked_add_months(chrono::Months::new(1))?
+                .pred_opt(),
+            (day, _) => NaiveDate::from_ymd_opt(year, month, u32::from(day)),
+        }
+    }
+
+    fn try_to_bitfield(&self) -> Result<TimeDomainBitfield, GraphTileBuildError> {
+        let overflow = |field: &'static str, value: u8| {
+            move |()| GraphTileBuildError::BitfieldOverflow {
+                field: field.to_string(),
+                value: usize::from(value),
+            }
+        };
+
+        TimeDomainBitfield::new()
+            .with_is_nth_day_of_week(self.is_nth_day_of_week.into())
+            .with_dow_mask_checked(self.dow_mask)
+            .map_err(overflow("dow_mask", self.dow_mask))?
+            .with_begin_month_checked(self.begin_month)
+            .map_err(overflow("begin_month", self.begin_month))?
+            .with_begin_day_dow_checked(self.begin_day_dow)
+            .map_err(overflow("begin_day_dow", self.begin_day_dow))?
+            .with_begin_week_checked(self.begin_week)
+            .map_err(overflow("begin_week", self.begin_week))?
+            .with_begin_hours_checked(self.begin_hours)
+            .map_err(overflow("begin_hours", self.begin_hours))?
+            .with_begin_minutes_checked(self.begin_minutes)
+            .map_err(overflow("begin_minutes", self.begin_minutes))?
+            .with_end_month_checked(self.end_month)
+            .map_err(overflow("end_month", self.end_month))?
+            .with_end_day_dow_checked(self.end_day_dow)
+            .map_err(overflow("end_day_dow", self.end_day_dow))?
+            .with_end_week_checked(self.end_week)
+            .map_err(overflow("end_week", self.end_week))?
+            .with_end_hours_checked(self.end_hours)
+            .map_err(overflow("end_hours", self.end_hours))?
+            .with_end_minutes_checked(self.end_minutes)
+            .map_err(overflow("end_minutes", self.end_minutes))
+    }
+
+    fn from_bitfield(bitfield: TimeDomainBitfield) -> Self {
+        Self {
+            is_nth_day_of_week: bitfield.is_nth_day_of_week() != 0,
+            dow_mask: bitfield.dow_mask(),
+            begin_month: bitfield.begin_month(),
+            begin_day_dow: bitfield.begin_day_dow(),
+            begin_week: bitfield.begin_week(),
+            begin_hours: bitfield.begin_hours(),
+            begin_minutes: bitfield.begin_minutes(),
+            end_month: bitfield.end_month(),
+            end_day_dow: bitfield.end_day_dow(),
+            end_week: bitfield.end_week(),
+            end_hours: bitfield.end_hours(),
+            end_minutes: bitfield.end_minutes(),
+        }
+    }
+}
+
+#[bitfield(u64,
+    repr = U64<LE>,
+    from = bit_twiddling_helpers::conv_u64le::from_inner,
+    into = bit_twiddling_helpers::conv_u64le::into_inner
+)]
+#[derive(FromBytes, IntoBytes, Immutable, Unaligned, KnownLayout)]
+struct FromEdgeBitfield {
+    #[bits(46, from = bit_twiddling_helpers::conv_u64le::from_inner, into = bit_twiddling_helpers::conv_u64le::into_inner)]
+    from_edge_id: U64<LE>,
+    #[bits(5)]
+    via_count: u8,
+    #[bits(7)]
+    probability: u8,
+    #[bits(6)]
+    _spare: u8,
+}
+
+#[bitfield(u64,
+    repr = U64<LE>,
+    from = bit_twiddling_helpers::conv_u64le::from_inner,
+    into = bit_twiddling_helpers::conv_u64le::into_inner
+)]
+#[derive(FromBytes, IntoBytes, Immutable, Unaligned, KnownLayout)]
+struct ToEdgeBitfield {
+    #[bits(46, from = bit_twiddling_helpers::conv_u64le::from_inner, into = bit_twiddling_helpers::conv_u64le::into_inner)]
+    to_edge_id: U64<LE>,
+    #[bits(4)]
+    restriction_type: u8,
+    #[bits(12, from = bit_twiddling_helpers::conv_u16le::from_inner, into = bit_twiddling_helpers::conv_u16le::into_inner)]
+    modes: U16<LE>,
+    #[bits(1)]
+    has_time_domain: u8,
+    #[bits(1)]
+    _spare: u8,
+}
+
+/// The fixed-size part of a complex restriction record.
+///
+/// On disk, this is immediately followed by the via edge IDs.
+#[derive(FromBytes, IntoBytes, Immutable, Unaligned, KnownLayout)]
+#[repr(C)]
+struct ComplexRestrictionHeader {
+    from: FromEdgeBitfield,
+    to: ToEdgeBitfield,
+    time_domain: TimeDomainBitfield,
+}
+
+/// A turn restriction which spans multiple edges (ex: a `via` way in OSM).
+///
+/// Simple restrictions between two edges at a node are stored directly on the node's edges,
+/// but longer restrictions (and time dependent ones) are stored separately in the tile.
+/// See [`GraphTile::get_complex_restrictions`](crate::graph_tile::GraphTile::get_complex_restrictions).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComplexRestriction {
+    /// The first edge of the restricted path.
+    pub from_edge_id: GraphId,
+    /// The last edge of the restricted path.
+    pub to_edge_id: GraphId,
+    /// The edges between the from and to edges, in order (at most 31).
+    pub via_edge_ids: Vec<GraphId>,
+    pub restriction_type: RestrictionType,
+    /// The access modes affected by this restriction.
+    pub affected_access_modes: EnumSet<Access>,
+    /// The probability (as a percentage) that the restriction applies,
+    /// for restrictions which are inferred from traffic data.
+    /// Zero means that this is a regular restriction, which always applies.
+    pub probability: u8,
+    /// When the restriction applies (`None` means always).
+    pub time_domain: Option<TimeDomain>,
+}
+
+impl ComplexRestriction {
+    /// Encodes the restriction in Valhalla's binary format.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there are too many via edges, or a value is too large for its field.
+    pub(crate) fn encode(&self) -> Result<Vec<u8>, GraphTileBuildError> {
+        let via_count = self.via_edge_ids.len();
+        if via_count > MAX_VIA_COUNT {
+            return Err(GraphTileBuildError::BitfieldOverflow {
+                field: "via_count".to_string(),
+                value: via_count,
+            });
+        }
+
+        let header = ComplexRestrictionHeader {
+            from: FromEdgeBitfield::new()
+                .with_from_edge_id(self.from_edge_id.value().into())
+                .with_via_count(u8::try_from(via_count)?)
+                .with_probability_checked(self.probability)
+                .map_err(|()| GraphTileBuildError::BitfieldOverflow {
+                    field: "probability".to_string(),
+                    value: usize::from(self.probability),
+                })?,
+            to: ToEdgeBitfield::new()
+                .with_to_edge_id(self.to_edge_id.value().into())
+                .with_restriction_type(self.restriction_type.into())
+                .with_modes(self.affected_access_modes.as_repr().into())
+                .with_has_time_domain(self.time_domain.is_some().into()),
+            time_domain: self
+                .time_domain
+                .map(|time_domain| time_domain.try_to_bitfield())
+                .transpose()?
+                .unwrap_or_default(),
+        };
+
+        let mut bytes = header.as_bytes().to_vec();
+        for via_edge_id in &self.via_edge_ids {
+            bytes.extend_from_slice(&via_edge_id.value().to_le_bytes());
+        }
+
+        Ok(bytes)
+    }
+}
+
+/// Decodes a list of complex restrictions (either the forward or reverse list from a tile).
+pub(crate) fn decode_complex_restrictions(
+    bytes: &[u8],
+) -> Result<Vec<ComplexRestriction>, GraphTileDecodingError> {
+    let graph_id = |field: &str, value: u64| {
+        GraphId::try_from_id(value).map_err(|e| GraphTileDecodingError::CastError {
+            field: field.to_string(),
+            error_description: e.to_string(),
+        })
+    };
+
+    let mut restrictions = Vec::new();
+    let mut bytes = bytes;
+    while !bytes.is_empty() {
+        let (header, rest) = ComplexRestrictionHeader::ref_from_prefix(bytes).map_err(|e| {
+            GraphTileDecodingError::CastError {
+                field: "complex_restriction".to_string(),
+                error_description: e.to_string(),
+            }
+        })?;
+        let (via_edge_ids, rest) =
+            <[U64<LE>]>::ref_from_prefix_with_elems(rest, usize::from(header.from.via_count()))
+                .map_err(|e| GraphTileDecodingError::CastError {
+                    field: "via_edge_ids".to_string(),
+                    error_description: e.to_string(),
+                })?;
+        bytes = rest;
+
+        let restriction_type =
+            RestrictionType::try_from(header.to.restriction_type()).map_err(|e| {
+                GraphTileDecodingError::CastError {
+                    field: "restriction_type".to_string(),
+                    error_description: e.to_string(),
+                }
+            })?;
+        restrictions.push(ComplexRestriction {
+            from_edge_id: graph_id("from_edge_id", header.from.from_edge_id().get())?,
+            to_edge_id: graph_id("to_edge_id", header.to.to_edge_id().get())?,
+            via_edge_ids: via_edge_ids
+                .iter()
+                .map(|id| graph_id("via_edge_id", id.get()))
+                .collect::<Result<_, _>>()?,
+            restriction_type,
+            // SAFETY: The access bits are length 12, so invalid representations are impossible.
+            affected_access_modes: unsafe { EnumSet::from_repr_unchecked(header.to.modes().get()) },
+            probability: header.from.probability(),
+            time_domain: (header.to.has_time_domain() != 0)
+                .then(|| TimeDomain::from_bitfield(header.time_domain)),
+        });
+    }
+
+    Ok(restrictions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ComplexRestriction, RestrictionType, TimeDomain, decode_complex_restrictions};
+    use crate::graph_tile::GraphTileBuildError;
+    use crate::{Access, GraphId};
+    use chrono::{NaiveDate, NaiveDateTime};
+    use enumset::EnumSet;
+
+    fn edge(index: u64) -> GraphId {
+        GraphId::try_from_components(2, 762_485, index).unwrap()
+    }
+
+    #[test]
+    fn test_complex_restriction_round_trip() {
+        let restrictions = [
+            ComplexRestriction {
+                from_edge_id: edge(1),
+                to_edge_id: edge(4),
+                via_edge_ids: vec![edge(2), edge(3)],
+                restriction_type: RestrictionType::NoUTurn,
+                affected_access_modes: Access::Auto | Access::Truck,
+                probability: 0,
+                time_domain: None,
+            },
+            ComplexRestriction {
+                from_edge_id: edge(5),
+                to_edge_id: GraphId::try_from_components(2, 762_486, 10).unwrap(),
+                via_edge_ids: vec![],
+                restriction_type: RestrictionType::OnlyStraightOn,
+                affected_access_modes: EnumSet::all(),
+                probability: 75,
+                // Mo-Fr 07:00-09:30
+                time_domain: Some(TimeDomain {
+                    dow_mask: 0b011_1110,
+                    begin_hours: 7,
+                    end_hours: 9,
+                    end_minutes: 30,
+                    ..TimeDomain::default()
+                }),
+            },
+        ];
+
+        let mut bytes = Vec::new();
+        for restriction in &restrictions {
+            let encoded = restriction.encode().unwrap();
+            assert_eq!(encoded.len(), 24 + 8 * restriction.via_edge_ids.len());
+            bytes.extend(encoded);
+        }
+        assert_eq!(decode_complex_restrictions(&bytes).unwrap(), restrictions);
+
+        // Truncated records are an error
+        assert!(decode_complex_restrictions(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_complex_restriction_limits() {
+        let restriction = ComplexRestriction {
+            from_edge_id: edge(1),
+            to_edge_id: edge(2),
+            via_edge_ids: vec![edge(3); 32],
+            restriction_type: RestrictionType::NoEntry,
+            affected_access_modes: Access::Auto.into(),
+            probability: 0,
+            time_domain: None,
+        };
+        assert!(matches!(
+            restriction.encode(),
+            Err(GraphTileBuildError::BitfieldOverflow { .. })
+        ));
+
+        let restriction = ComplexRestriction {
+            via_edge_ids: vec![],
+            time_domain: Some(TimeDomain {
+                begin_hours: 32,
+                ..TimeDomain::default()
+            }),
+            ..restriction
+        };
+        assert!(matches!(
+            restriction.encode(),
+            Err(GraphTileBuildError::BitfieldOverflow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_time_domain_contains() {
+        let at = |month, day, hour, minute| -> NaiveDateTime {
+            NaiveDate::from_ymd_opt(2025, month, day)
+                .unwrap()
+                .and_hms_opt(hour, minute, 0)
+                .unwrap()
+        };
+
+        // Mo-Fr 07:00-09:00
+        let rush_hour = TimeDomain {
+            dow_mask: 0b011_1110,
+            begin_hours: 7,
+            end_hours: 9,
+            ..TimeDomain::default()
+        };
+        assert_eq!(
+            TimeDomain::from_raw(rush_hour.try_to_raw().unwrap()),
+            rush_hour
+        );
+        // 2025-06-02 is a Monday
+        assert!(rush_hour.contains(at(6, 2, 7, 0)));
+        assert!(rush_hour.contains(at(6, 2, 8, 59)));
+        assert!(!rush_hour.contains(at(6, 2, 9, 0)));
+        assert!(!rush_hour.contains(at(6, 2, 6, 59)));
+        assert!(!rush_hour.contains(at(6, 1, 8, 0)));
+
+        // Sa 22:00-06:00 continues into Sunday morning
+        let overnight = TimeDomain {
+            dow_mask: 0b100_0000,
+            begin_hours: 22,
+            end_hours: 6,
+            ..TimeDomain::default()
+        };
+        assert!(overnight.contains(at(5, 31, 23, 0)));
+        assert!(overnight.contains(at(6, 1, 5, 0)));
+        assert!(!overnight.contains(at(6, 1, 23, 0)));
+        assert!(!overnight.contains(at(5, 31, 5, 0)));
+
+        // Nov-Mar wraps around the new year
+        let winter = TimeDomain {
+            begin_month: 11,
+            end_month: 3,
+            ..TimeDomain::default()
+        };
+        assert!(winter.contains(at(1, 15, 12, 0)));
+        assert!(winter.contains(at(3, 31, 12, 0)));
+        assert!(winter.contains(at(11, 1, 0, 0)));
+        assert!(!winter.contains(at(6, 2, 12, 0)));
+
+        // Oct Su[-1]-Mar Su[-1] (the last Sundays of October and March)
+        let last_sundays = TimeDomain {
+            is_nth_day_of_week: true,
+            begin_month: 10,
+            begin_day_dow: 1,
+            begin_week: 5,
+            end_month: 3,
+            end_day_dow: 1,
+            end_week: 5,
+            ..TimeDomain::default()
+        };
+        assert!(last_sundays.contains(at(3, 30, 12, 0)));
+        assert!(!last_sundays.contains(at(3, 31, 12, 0)));
+        assert!(!last_sundays.contains(at(10, 25, 12, 0)));
+        assert!(last_sundays.contains(at(10, 26, 12, 0)));
+    }
+}
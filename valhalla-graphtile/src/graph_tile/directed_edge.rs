@@ -1,10 +1,11 @@
-use crate::{Access, GraphId, RoadClass, RoadUse, Surface};
+use super::{GraphTileBuildError, NewDirectedEdge};
+use crate::{Access, CycleLane, GraphId, RoadClass, RoadUse, Surface};
 use bitfield_struct::bitfield;
 use enumset::EnumSet;
 #[cfg(feature = "serde")]
 use serde::{Serialize, Serializer, ser::SerializeStruct};
 use std::fmt::{Debug, Formatter};
-use zerocopy::{LE, U16, U32, U64};
+use zerocopy::{FromZeros, LE, U16, U32, U64};
 use zerocopy_derive::{FromBytes, Immutable, IntoBytes, Unaligned};
 
 #[bitfield(u64,
@@ -112,7 +113,7 @@ struct FourthBitfield {
     #[bits(3)]
     sac_scale: u8,
     #[bits(2)]
-    cycle_lane: u8,
+    cycle_lane: CycleLane,
     // Booleans represented this way for infailability.
     // See comment in node_info.rs for details.
     #[bits(1)]
@@ -261,6 +262,70 @@ pub struct DirectedEdge {
 }
 
 impl DirectedEdge {
+    /// Creates a new directed edge.
+    ///
+    /// The edge info (shape, names, etc.) must already be in the tile at `edge_info_offset`;
+    /// `edge_info_is_forward` indicates whether it is stored in the direction of travel.
+    /// `local_edge_index` is the index of the edge among its start node's outbound edges.
+    pub(crate) fn try_new(
+        edge: &NewDirectedEdge,
+        length: u32,
+        edge_info_offset: u32,
+        edge_info_is_forward: bool,
+        leaves_tile: bool,
+        local_edge_index: u8,
+    ) -> Result<Self, GraphTileBuildError> {
+        let mut result = Self::new_zeroed();
+
+        result.first_bitfield = FirstBitfield::new()
+            .with_end_node(edge.end_node_id.value().into())
+            .with_opposing_edge_index_checked(edge.opposing_edge_index.into())
+            .map_err(|()| GraphTileBuildError::BitfieldOverflow {
+                field: "opposing_edge_index".to_string(),
+                value: edge.opposing_edge_index as usize,
+            })?
+            .with_is_edge_info_forward(edge_info_is_forward.into())
+            .with_leaves_tile(leaves_tile.into());
+
+        result.second_bitfield = SecondBitfield::new()
+            .with_edge_info_offset_checked(edge_info_offset.into())
+            .map_err(|()| GraphTileBuildError::BitfieldOverflow {
+                field: "edge_info_offset".to_string(),
+                value: edge_info_offset as usize,
+            })?;
+
+        result.third_bitfield = ThirdBitfield::new()
+            .with_speed(edge.speed)
+            .with_road_use(edge.road_use)
+            .with_classification(edge.classification);
+
+        result.fourth_bitfield = FourthBitfield::new()
+            .with_forward_access(edge.forward_access.as_repr().into())
+            .with_reverse_access(edge.reverse_access.as_repr().into());
+
+        result.fifth_bitfield = FifthBitfield::new()
+            .with_length_checked(length.into())
+            .map_err(|()| GraphTileBuildError::BitfieldOverflow {
+                field: "length".to_string(),
+                value: length as usize,
+            })?;
+
+        // Opposing edge indices are always local to the end node,
+        // which is all that the builder supports.
+        #[expect(clippy::cast_possible_truncation, reason = "Checked above (7 bits).")]
+        let opposing_local_index = edge.opposing_edge_index as u8;
+        result.seventh_bitfield = SeventhBitField::new()
+            .with_local_level_edge_index_checked(local_edge_index)
+            .map_err(|()| GraphTileBuildError::BitfieldOverflow {
+                field: "local_level_edge_index".to_string(),
+                value: usize::from(local_edge_index),
+            })?
+            .with_local_level_opp_edge_index(opposing_local_index)
+            .with_is_named((!edge.names.is_empty()).into());
+
+        Ok(result)
+    }
+
     // TODO: Dozens of access helpers :)
 
     /// The end node ID for this directed edge.
@@ -483,6 +548,74 @@ impl DirectedEdge {
         self.third_bitfield.set_has_predicted_speed(value.into());
     }
 
+    /// Points the edge at a different edge info record.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the offset does not fit in 25 bits.
+    pub(crate) fn set_edge_info_offset(&mut self, offset: u32) -> Result<(), GraphTileBuildError> {
+        self.second_bitfield
+            .set_edge_info_offset_checked(offset.into())
+            .map_err(|()| GraphTileBuildError::BitfieldOverflow {
+                field: "edge_info_offset".to_string(),
+                value: offset as usize,
+            })
+    }
+
+    /// Sets the access modes which have restrictions on this edge.
+    ///
+    /// This should always match the modes affected by the tile's access restrictions for the edge.
+    #[inline]
+    pub(crate) fn set_access_restriction_modes(&mut self, modes: EnumSet<Access>) {
+        self.second_bitfield
+            .set_access_restrictions(modes.as_repr().into());
+    }
+
+    /// Sets whether the edge crosses into a different country.
+    #[inline]
+    pub(crate) fn set_country_crossing(&mut self, value: bool) {
+        self.first_bitfield.set_country_crossing(value.into());
+    }
+
+    /// Sets the access modes with complex restrictions starting and ending on this edge.
+    #[inline]
+    pub(crate) fn set_complex_restriction_modes(
+        &mut self,
+        start_modes: EnumSet<Access>,
+        end_modes: EnumSet<Access>,
+    ) {
+        self.second_bitfield
+            .set_start_restriction(start_modes.as_repr().into());
+        self.second_bitfield
+            .set_end_restriction(end_modes.as_repr().into());
+    }
+
+    /// Sets whether the edge is part of a complex restriction.
+    #[inline]
+    pub(crate) fn set_part_of_complex_restriction(&mut self, value: bool) {
+        self.second_bitfield.set_complex_restriction(value.into());
+    }
+
+    /// Sets whether the edge has turn lanes in the tile's turn lane list.
+    #[inline]
+    pub(crate) fn set_has_turn_lanes(&mut self, value: bool) {
+        self.fourth_bitfield.set_has_turn_lanes(value.into());
+    }
+
+    /// Sets whether the edge has exit signs in the tile's sign list.
+    #[inline]
+    pub(crate) fn set_has_exit_signs(&mut self, value: bool) {
+        self.fourth_bitfield.set_has_exit_signs(value.into());
+    }
+
+    /// Sets whether the edge has any names.
+    ///
+    /// This should always match the (untagged) names in the edge info.
+    #[inline]
+    pub(crate) fn set_is_named(&mut self, value: bool) {
+        self.seventh_bitfield.set_is_named(value.into());
+    }
+
     /// Is this edge an internal intersection?
     ///
     /// TODO: Figure out what this affects in Valhalla
@@ -491,6 +624,22 @@ impl DirectedEdge {
         self.fourth_bitfield.is_intersection_internal() != 0
     }
 
+    /// Does this edge have turn lanes?
+    ///
+    /// See [`GraphTile::get_turn_lanes`](crate::graph_tile::GraphTile::get_turn_lanes).
+    #[inline]
+    pub const fn has_turn_lanes(&self) -> bool {
+        self.fourth_bitfield.has_turn_lanes() != 0
+    }
+
+    /// Does this edge have exit signs?
+    ///
+    /// See [`GraphTile::get_edge_signs`](crate::graph_tile::GraphTile::get_edge_signs).
+    #[inline]
+    pub const fn has_exit_signs(&self) -> bool {
+        self.fourth_bitfield.has_exit_signs() != 0
+    }
+
     /// Does this lead to (or come out from) a bike share station?
     ///
     /// TODO: Figure out what this affects in Valhalla
@@ -499,6 +648,21 @@ impl DirectedEdge {
         self.fourth_bitfield.has_bss_connection() != 0
     }
 
+    /// The type of cycle lane along this edge.
+    #[inline]
+    pub const fn cycle_lane(&self) -> CycleLane {
+        self.fourth_bitfield.cycle_lane()
+    }
+
+    /// Is this edge part of a bicycle network?
+    ///
+    /// See [`EdgeInfo::bicycle_network`](crate::graph_tile::EdgeInfo::bicycle_network)
+    /// for the specific networks.
+    #[inline]
+    pub const fn is_bike_network(&self) -> bool {
+        self.fourth_bitfield.is_bike_network() != 0
+    }
+
     /// Gets the set of access modes allowed to traverse this edge forward.
     #[inline]
     pub fn forward_access(&self) -> EnumSet<Access> {
@@ -513,11 +677,125 @@ impl DirectedEdge {
         unsafe { EnumSet::from_repr_unchecked(self.fourth_bitfield.reverse_access().get()) }
     }
 
+    /// Gets the set of access modes which have access restrictions on this edge.
+    ///
+    /// The restrictions themselves are stored in the tile
+    /// (see [`GraphTile::get_access_restrictions`](crate::graph_tile::GraphTile::get_access_restrictions)).
+    #[inline]
+    pub fn access_restriction_modes(&self) -> EnumSet<Access> {
+        // SAFETY: The access bits are length 12, so invalid representations are impossible.
+        unsafe { EnumSet::from_repr_unchecked(self.second_bitfield.access_restrictions().get()) }
+    }
+
+    /// Gets the set of access modes with complex restrictions starting on this edge.
+    ///
+    /// See [`GraphTile::get_complex_restrictions`](crate::graph_tile::GraphTile::get_complex_restrictions).
+    #[inline]
+    pub fn start_restriction_modes(&self) -> EnumSet<Access> {
+        // SAFETY: The access bits are length 12, so invalid representations are impossible.
+        unsafe { EnumSet::from_repr_unchecked(self.second_bitfield.start_restriction().get()) }
+    }
+
+    /// Gets the set of access modes with complex restrictions ending on this edge.
+    ///
+    /// See [`GraphTile::get_complex_restrictions`](crate::graph_tile::GraphTile::get_complex_restrictions).
+    #[inline]
+    pub fn end_restriction_modes(&self) -> EnumSet<Access> {
+        // SAFETY: The access bits are length 12, so invalid representations are impossible.
+        unsafe { EnumSet::from_repr_unchecked(self.second_bitfield.end_restriction().get()) }
+    }
+
+    /// Is this edge part of a complex restriction?
+    #[inline]
+    pub const fn is_part_of_complex_restriction(&self) -> bool {
+        self.second_bitfield.complex_restriction() != 0
+    }
+
     /// The length of the edge (in meters)
     #[inline]
     pub const fn length(&self) -> u32 {
         self.fifth_bitfield.length().get()
     }
+
+    /// The index of this edge among the edges leaving its start node, on the local level.
+    ///
+    /// This is the index used by node-level attributes like
+    /// [`NodeInfo::heading`](super::NodeInfo::heading).
+    #[inline]
+    pub const fn local_edge_index(&self) -> u8 {
+        self.seventh_bitfield.local_level_edge_index()
+    }
+
+    /// The local index of the opposing edge at the end node.
+    ///
+    /// The per-transition attributes of the edges leaving the end node
+    /// (ex: [`Self::stop_impact`]) are indexed by this value.
+    #[inline]
+    pub const fn opposing_local_edge_index(&self) -> u8 {
+        self.seventh_bitfield.local_level_opp_edge_index()
+    }
+
+    /// The relative delay (0-7) when transitioning onto this edge
+    /// from the edge with the given local index at the start node.
+    ///
+    /// Stop impact accounts for traffic control (signals, stop signs, etc.)
+    /// and the importance of the crossing roads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `local_edge_index` is greater than 7.
+    #[inline]
+    pub const fn stop_impact(&self, local_edge_index: u8) -> u8 {
+        assert!(local_edge_index < 8, "Invalid input (must be 0-7)");
+        // SAFETY: Transit lines are the only edges which use the line ID,
+        // and those are never part of a transition, so the worst case is a garbage value.
+        let impacts = unsafe { self.stop_impact_or_line.stop_impact.impact_between_edges() };
+        ((impacts.get() >> (local_edge_index * 3)) & 0b111) as u8
+    }
+
+    /// Is there an edge to the left of the transition onto this edge
+    /// from the edge with the given local index at the start node?
+    ///
+    /// # Panics
+    ///
+    /// Panics if `local_edge_index` is greater than 7.
+    #[inline]
+    pub const fn edge_to_left(&self, local_edge_index: u8) -> bool {
+        assert!(local_edge_index < 8, "Invalid input (must be 0-7)");
+        self.fifth_bitfield.edge_to_left() & (1 << local_edge_index) != 0
+    }
+
+    /// Is there an edge to the right of the transition onto this edge
+    /// from the edge with the given local index at the start node?
+    ///
+    /// # Panics
+    ///
+    /// Panics if `local_edge_index` is greater than 7.
+    #[inline]
+    pub const fn edge_to_right(&self, local_edge_index: u8) -> bool {
+        assert!(local_edge_index < 8, "Invalid input (must be 0-7)");
+        // SAFETY: See [`Self::stop_impact`].
+        let edges = unsafe { self.stop_impact_or_line.stop_impact.edge_to_right() };
+        edges & (1 << local_edge_index) != 0
+    }
+
+    /// Is there a traffic signal at the end of this edge?
+    #[inline]
+    pub const fn has_traffic_signal(&self) -> bool {
+        self.fourth_bitfield.has_traffic_signal() != 0
+    }
+
+    /// Is there a stop sign at the end of this edge?
+    #[inline]
+    pub const fn has_stop_sign(&self) -> bool {
+        self.fourth_bitfield.has_stop_sign() != 0
+    }
+
+    /// Is there a yield (give way) sign at the end of this edge?
+    #[inline]
+    pub const fn has_yield_sign(&self) -> bool {
+        self.fourth_bitfield.has_yield_sign() != 0
+    }
 }
 
 // The bitfield struct macros break serde field attributes, so we roll our own for now.
@@ -602,7 +880,8 @@ pub struct DirectedEdgeExt(U64<LE>);
 
 #[cfg(test)]
 mod test {
-    use crate::graph_tile::{GraphTile, TEST_GRAPH_TILE_L0};
+    use crate::CycleLane;
+    use crate::graph_tile::{GraphTile, TEST_GRAPH_TILE_L0, TEST_GRAPH_TILE_L2};
 
     #[test]
     fn test_parse_directed_edges_count() {
@@ -629,4 +908,136 @@ mod test {
 
         // TODO: Other sanity checks after we add some more advanced methods
     }
+
+    #[test]
+    fn test_bicycle_attributes() {
+        let tile = &*TEST_GRAPH_TILE_L2;
+
+        let cycle_lanes = [
+            CycleLane::None,
+            CycleLane::Shared,
+            CycleLane::Dedicated,
+            CycleLane::Separated,
+        ]
+        .map(|lane| {
+            let count = tile
+                .directed_edges()
+                .iter()
+                .filter(|edge| edge.cycle_lane() == lane)
+                .count();
+            (lane, count)
+        });
+        if !cfg!(miri) {
+            insta::assert_debug_snapshot!(cycle_lanes);
+        }
+
+        // Network membership details are stored in the edge info
+        for edge in tile.directed_edges() {
+            let networks = tile.get_edge_info(edge).unwrap().bicycle_network();
+            assert_eq!(edge.is_bike_network(), !networks.is_empty());
+        }
+    }
+
+    /// Compares every directed edge against a reference dump of Valhalla's fields
+    /// (see `fixtures/tools/README.md` for how it's generated).
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_directed_edges_match_valhalla() {
+        use crate::GraphId;
+        use crate::tile_provider::{DirectoryGraphTileProvider, OwnedGraphTileProvider};
+        use std::num::NonZeroUsize;
+        use std::path::PathBuf;
+
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let reference = std::fs::read_to_string(fixtures.join("andorra-directed-edges.jsonl"))
+            .expect("Unable to read the reference fixture");
+        let provider =
+            DirectoryGraphTileProvider::new(fixtures.join("andorra-tiles"), NonZeroUsize::MIN);
+
+        let mut checked = 0;
+        for line in reference.lines().filter(|line| !line.trim().is_empty()) {
+            let expected: serde_json::Value = serde_json::from_str(line).unwrap();
+            let field = |name: &str| {
+                expected[name]
+                    .as_u64()
+                    .unwrap_or_else(|| panic!("Missing field {name} in {line}"))
+            };
+
+            // Tiles are written as `level/tile_id`
+            let tile_id: GraphId = format!("{}/0", expected["tile"].as_str().unwrap())
+                .parse()
+                .unwrap();
+            let tile = provider.get_handle_for_tile_containing(tile_id).unwrap();
+            let edge = &tile.directed_edges()[usize::try_from(field("index")).unwrap()];
+
+            let actual = [
+                ("end_node", edge.end_node_id().value()),
+                ("opp_index", u64::from(edge.opposing_edge_index())),
+                ("edgeinfo_offset", u64::from(edge.edge_info_offset())),
+                ("forward", u64::from(edge.edge_info_is_forward())),
+                ("leaves_tile", u64::from(edge.leaves_tile())),
+                ("length", u64::from(edge.length())),
+                ("speed", u64::from(edge.speed())),
+                ("free_flow_speed", u64::from(edge.free_flow_speed())),
+                (
+                    "constrained_flow_speed",
+                    u64::from(edge.constrained_flow_speed()),
+                ),
+                ("truck_speed", u64::from(edge.truck_speed())),
+                ("use", edge.road_use() as u64),
+                ("classification", edge.classification() as u64),
+                ("surface", edge.surface() as u64),
+                ("lanecount", u64::from(edge.lane_count())),
+                ("density", u64::from(edge.density())),
+                ("toll", u64::from(edge.toll())),
+                ("roundabout", u64::from(edge.roundabout())),
+                ("truck_route", u64::from(edge.truck_route())),
+                ("has_predicted_speed", u64::from(edge.has_predicted_speed())),
+                ("internal", u64::from(edge.is_intersection_internal())),
+                ("is_shortcut", u64::from(edge.is_shortcut())),
+                // Valhalla returns the raw mask, with one bit set for the superseding shortcut
+                (
+                    "superseded",
+                    edge.superseded_index().map_or(0, |index| 1 << index),
+                ),
+                ("forwardaccess", u64::from(edge.forward_access().as_repr())),
+                ("reverseaccess", u64::from(edge.reverse_access().as_repr())),
+                ("cyclelane", edge.cycle_lane() as u64),
+                ("bike_network", u64::from(edge.is_bike_network())),
+                ("destonly", u64::from(edge.dest_only())),
+                ("not_thru", u64::from(edge.no_thru())),
+                ("ctry_crossing", u64::from(edge.country_crossing())),
+                ("bss_connection", u64::from(edge.has_bss_connection())),
+                ("localedgeidx", u64::from(edge.local_edge_index())),
+                ("opp_local_idx", u64::from(edge.opposing_local_edge_index())),
+                (
+                    "stopimpact",
+                    (0..8).fold(0, |packed, idx| {
+                        packed | (u64::from(edge.stop_impact(idx)) << (idx * 3))
+                    }),
+                ),
+                (
+                    "edge_to_left",
+                    (0..8).fold(0, |packed, idx| {
+                        packed | (u64::from(edge.edge_to_left(idx)) << idx)
+                    }),
+                ),
+                (
+                    "edge_to_right",
+                    (0..8).fold(0, |packed, idx| {
+                        packed | (u64::from(edge.edge_to_right(idx)) << idx)
+                    }),
+                ),
+                ("traffic_signal", u64::from(edge.has_traffic_signal())),
+                ("stop_sign", u64::from(edge.has_stop_sign())),
+                ("yield_sign", u64::from(edge.has_yield_sign())),
+            ];
+            for (name, value) in actual {
+                assert_eq!(value, field(name), "Mismatched {name} for {line}");
+            }
+            checked += 1;
+        }
+
+        assert!(checked > 0, "The reference fixture is empty");
+    }
 }
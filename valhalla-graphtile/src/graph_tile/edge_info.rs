@@ -1,24 +1,33 @@
 use crate::{
-    AsCowStr, BicycleNetwork, graph_tile::GraphTileDecodingError, shape_codec::decode_shape,
+    AsCowStr, BicycleNetwork,
+    graph_tile::{GraphTileBuildError, GraphTileDecodingError},
+    shape_codec::decode_shape,
+    spatial::heading_along_line,
+    traffic_tile::{SegmentTrafficInfo, TrafficSpeed},
 };
 use bitfield_struct::bitfield;
 use enumset::EnumSet;
-use geo::{Coord, CoordFloat};
+use geo::{Coord, CoordFloat, LineString};
 use num_traits::FromPrimitive;
 use std::borrow::Cow;
-use zerocopy::{FromBytes, LE, U16, U32};
-use zerocopy_derive::{FromBytes, Immutable, KnownLayout, Unaligned};
+use zerocopy::{FromBytes, IntoBytes, LE, U16, U32};
+use zerocopy_derive::{FromBytes, Immutable, IntoBytes, KnownLayout, Unaligned};
 
 use crate::shape_codec::decode_first_coordinate;
 #[cfg(feature = "serde")]
 use serde::{Serialize, Serializer, ser::SerializeStruct};
 
+/// The distance (in meters) along an edge shape used to compute headings.
+///
+/// See [`EdgeInfo::headings`].
+pub const HEADING_SAMPLE_DISTANCE: f64 = 30.0;
+
 #[bitfield(u32,
     repr = U32<LE>,
     from = bit_twiddling_helpers::conv_u32le::from_inner,
     into = bit_twiddling_helpers::conv_u32le::into_inner
 )]
-#[derive(FromBytes, Immutable, Unaligned)]
+#[derive(FromBytes, IntoBytes, Immutable, Unaligned)]
 pub struct NameInfo {
     #[bits(24, from = bit_twiddling_helpers::conv_u32le::from_inner, into = bit_twiddling_helpers::conv_u32le::into_inner)]
     name_offset: U32<LE>,
@@ -43,7 +52,7 @@ pub struct NameInfo {
     from = bit_twiddling_helpers::conv_u32le::from_inner,
     into = bit_twiddling_helpers::conv_u32le::into_inner
 )]
-#[derive(FromBytes, Immutable, Unaligned, KnownLayout)]
+#[derive(FromBytes, IntoBytes, Immutable, Unaligned, KnownLayout)]
 struct FirstInnerBitfield {
     #[bits(12, from = bit_twiddling_helpers::conv_u16le::from_inner, into = bit_twiddling_helpers::conv_u16le::into_inner)]
     mean_elevation: U16<LE>,
@@ -60,7 +69,7 @@ struct FirstInnerBitfield {
     from = bit_twiddling_helpers::conv_u32le::from_inner,
     into = bit_twiddling_helpers::conv_u32le::into_inner
 )]
-#[derive(FromBytes, Immutable, Unaligned, KnownLayout)]
+#[derive(FromBytes, IntoBytes, Immutable, Unaligned, KnownLayout)]
 struct SecondInnerBitfield {
     #[bits(4)]
     name_count: u8,
@@ -76,7 +85,113 @@ struct SecondInnerBitfield {
     _spare: u8,
 }
 
-#[derive(Debug, FromBytes, Immutable, Unaligned, KnownLayout)]
+/// The kind of a tagged value in the edge info text list.
+///
+/// Tagged strings use their first byte to indicate the type of value that follows.
+/// These discriminants match Valhalla's `TaggedValue` enum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[repr(u8)]
+pub enum TaggedValueType {
+    Layer = 1,
+    Linguistic = 2,
+    BssInfo = 3,
+    Level = 4,
+    LevelRef = 5,
+    Landmark = 6,
+    ConditionalSpeedLimits = 7,
+    Levels = 8,
+    // NOTE: Valhalla historically encoded these as ASCII digits rather than the raw values,
+    // and this is preserved for compatibility.
+    Tunnel = b'1',
+    Bridge = b'2',
+}
+
+impl TaggedValueType {
+    /// Gets the tagged value type from its on-disk representation (if known).
+    pub const fn from_repr(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(Self::Layer),
+            2 => Some(Self::Linguistic),
+            3 => Some(Self::BssInfo),
+            4 => Some(Self::Level),
+            5 => Some(Self::LevelRef),
+            6 => Some(Self::Landmark),
+            7 => Some(Self::ConditionalSpeedLimits),
+            8 => Some(Self::Levels),
+            b'1' => Some(Self::Tunnel),
+            b'2' => Some(Self::Bridge),
+            _ => None,
+        }
+    }
+}
+
+/// A decoded tagged value from the edge info.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum TaggedValue<'a> {
+    /// The name of the tunnel which this edge passes through.
+    Tunnel(Cow<'a, str>),
+    /// The name of the bridge which this edge passes over.
+    Bridge(Cow<'a, str>),
+    /// The OSM `layer` of the edge (relative vertical position).
+    Layer(i8),
+    /// The OSM `level` (e.g. the floor of a building) in its textual form.
+    Level(Cow<'a, str>),
+    /// The OSM `level:ref`, which is a human-readable label for the level.
+    LevelRef(Cow<'a, str>),
+    /// A tagged value which is not decoded (yet).
+    ///
+    /// This covers binary encodings like linguistic data, landmarks,
+    /// conditional speed limits, and level ranges.
+    /// The raw bytes run up to the first NUL,
+    /// so they may be truncated for encodings which can contain NUL bytes.
+    Raw {
+        value_type: TaggedValueType,
+        bytes: &'a [u8],
+    },
+    /// A tagged value with an unrecognized type tag.
+    Unknown { tag: u8, bytes: &'a [u8] },
+}
+
+impl<'a> TaggedValue<'a> {
+    /// Decodes a tagged value from the text list memory, starting with the type tag byte.
+    fn decode(bytes: &'a [u8]) -> Option<Self> {
+        let (&tag, value) = bytes.split_first()?;
+        let raw = value.split(|&b| b == 0).next().unwrap_or_default();
+        let Some(value_type) = TaggedValueType::from_repr(tag) else {
+            return Some(Self::Unknown { tag, bytes: raw });
+        };
+
+        Some(match value_type {
+            TaggedValueType::Tunnel => Self::Tunnel(value.as_cow_str()),
+            TaggedValueType::Bridge => Self::Bridge(value.as_cow_str()),
+            TaggedValueType::Level => Self::Level(value.as_cow_str()),
+            TaggedValueType::LevelRef => Self::LevelRef(value.as_cow_str()),
+            #[expect(clippy::cast_possible_wrap)]
+            TaggedValueType::Layer => Self::Layer(*value.first()? as i8),
+            value_type => Self::Raw {
+                value_type,
+                bytes: raw,
+            },
+        })
+    }
+
+    /// The type of this tagged value, if known.
+    pub const fn value_type(&self) -> Option<TaggedValueType> {
+        match self {
+            Self::Tunnel(_) => Some(TaggedValueType::Tunnel),
+            Self::Bridge(_) => Some(TaggedValueType::Bridge),
+            Self::Layer(_) => Some(TaggedValueType::Layer),
+            Self::Level(_) => Some(TaggedValueType::Level),
+            Self::LevelRef(_) => Some(TaggedValueType::LevelRef),
+            Self::Raw { value_type, .. } => Some(*value_type),
+            Self::Unknown { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, FromBytes, IntoBytes, Immutable, Unaligned, KnownLayout)]
 #[repr(C)]
 struct EdgeInfoInner {
     // The first part of the OSM way ID
@@ -168,6 +283,66 @@ impl EdgeInfo<'_> {
         decode_first_coordinate(self.encoded_shape)
     }
 
+    /// Computes the headings at the start and end of a directed edge using this edge info.
+    ///
+    /// Both headings are in degrees clockwise from north, in the direction of travel.
+    /// So the end heading is the direction you are facing when arriving at the end node
+    /// (not the direction leaving it).
+    /// Each is measured over (up to) the first or last [`HEADING_SAMPLE_DISTANCE`] meters of the shape.
+    ///
+    /// `is_forward` should come from [`DirectedEdge::edge_info_is_forward`](crate::graph_tile::DirectedEdge::edge_info_is_forward),
+    /// since opposing edges share the same edge info (and therefore the same shape).
+    ///
+    /// Returns `None` for degenerate shapes with no length.
+    ///
+    /// # Errors
+    ///
+    /// See [`decode_shape`] for a description of possible errors.
+    ///
+    /// # Performance
+    ///
+    /// This decodes the entire shape,
+    /// so prefer [`heading_along_line`] if you already have it on hand.
+    pub fn headings(&self, is_forward: bool) -> std::io::Result<Option<(f64, f64)>> {
+        let mut shape = self.decode_raw_shape::<f64>()?;
+        if !is_forward {
+            shape.reverse();
+        }
+
+        let Some(begin) = heading_along_line(&shape, HEADING_SAMPLE_DISTANCE) else {
+            return Ok(None);
+        };
+        // Measure backwards from the end, then flip it around to face the direction of travel
+        shape.reverse();
+        let Some(end) = heading_along_line(&shape, HEADING_SAMPLE_DISTANCE) else {
+            return Ok(None);
+        };
+
+        Ok(Some((begin, (end + 180.0).rem_euclid(360.0))))
+    }
+
+    /// Splits the shape of a directed edge using this edge info into its live traffic segments.
+    ///
+    /// `is_forward` should come from [`DirectedEdge::edge_info_is_forward`](crate::graph_tile::DirectedEdge::edge_info_is_forward),
+    /// and `speed` is the live traffic speed of the directed edge.
+    /// See [`TrafficSpeed::segment_shapes`] for details.
+    ///
+    /// # Errors
+    ///
+    /// See [`decode_shape`] for a description of possible errors.
+    pub fn traffic_segment_shapes(
+        &self,
+        speed: TrafficSpeed,
+        is_forward: bool,
+    ) -> std::io::Result<Vec<(SegmentTrafficInfo, LineString<f64>)>> {
+        let mut shape = self.decode_raw_shape::<f64>()?;
+        if !is_forward {
+            shape.reverse();
+        }
+
+        Ok(speed.segment_shapes(&shape))
+    }
+
     // TODO: Other filters (tagged and linguistic filters)
     /// Gets all names for this edge.
     ///
@@ -191,6 +366,28 @@ impl EdgeInfo<'_> {
             .collect()
     }
 
+    /// The offsets into the tile text list of every name (including tagged values).
+    pub(crate) fn name_offsets(&self) -> impl Iterator<Item = u32> + '_ {
+        self.name_info_list.iter().map(|ni| ni.name_offset().get())
+    }
+
+    /// Gets the tagged values (tunnel and bridge names, layers, levels, etc.) for this edge.
+    ///
+    /// These share the name list with regular names,
+    /// but are excluded from [`EdgeInfo::get_names`].
+    /// Entries which point outside the text list are skipped.
+    pub fn tagged_values(&self) -> impl Iterator<Item = TaggedValue<'_>> {
+        self.name_info_list.iter().filter_map(|ni| {
+            if ni.is_tagged() == 0 {
+                None
+            } else {
+                self.text_list_memory
+                    .get(ni.name_offset().get() as usize..)
+                    .and_then(TaggedValue::decode)
+            }
+        })
+    }
+
     /// The bicycle network membership mask for this edge.
     #[inline]
     pub fn bicycle_network(&self) -> EnumSet<BicycleNetwork> {
@@ -210,6 +407,188 @@ impl EdgeInfo<'_> {
     }
 }
 
+/// Serializes a new edge info record, in the same layout that [`EdgeInfo`] reads.
+///
+/// The names are given as offsets into the tile text list,
+/// and the shape should already be encoded with [`encode_shape`](crate::shape_codec::encode_shape).
+pub(crate) fn encode_edge_info(
+    way_id: u64,
+    speed_limit: u8,
+    name_offsets: &[u32],
+    encoded_shape: &[u8],
+) -> Result<Vec<u8>, GraphTileBuildError> {
+    let overflow = |field: &str, value: usize| GraphTileBuildError::BitfieldOverflow {
+        field: field.to_string(),
+        value,
+    };
+
+    // The way ID is split across several fields; see EdgeInfo::way_id
+    let [b0, b1, b2, b3, b4, b5, b6, b7] = way_id.to_le_bytes();
+    let extended_way_id_size = match (b6, b7) {
+        (0, 0) => 0,
+        (_, 0) => 1,
+        _ => 2,
+    };
+
+    let inner = EdgeInfoInner {
+        way_id: U32::from_bytes([b0, b1, b2, b3]),
+        first_inner_bitfield: FirstInnerBitfield::new()
+            .with_speed_limit(speed_limit)
+            .with_extended_way_id(b4),
+        second_inner_bitfield: SecondInnerBitfield::new()
+            .with_name_count_checked(
+                u8::try_from(name_offsets.len())
+                    .map_err(|_| overflow("name_count", name_offsets.len()))?,
+            )
+            .map_err(|()| overflow("name_count", name_offsets.len()))?
+            .with_encoded_shape_size(
+                u16::try_from(encoded_shape.len())
+                    .map_err(|_| overflow("encoded_shape_size", encoded_shape.len()))?
+                    .into(),
+            )
+            .with_extended_way_id(b5)
+            .with_extended_way_id_size(extended_way_id_size),
+    };
+
+    let mut bytes = inner.as_bytes().to_vec();
+    for &offset in name_offsets {
+        let name_info = NameInfo::new()
+            .with_name_offset_checked(offset.into())
+            .map_err(|()| overflow("name_offset", offset as usize))?;
+        bytes.extend_from_slice(name_info.as_bytes());
+    }
+    bytes.extend_from_slice(encoded_shape);
+    bytes.extend_from_slice(&[b6, b7][..usize::from(extended_way_id_size)]);
+
+    Ok(bytes)
+}
+
+/// Replaces the (untagged) names in a serialized edge info record.
+///
+/// Tagged values are kept, and everything following the name list (the shape, etc.)
+/// is copied over as-is.
+/// Each new name keeps the route number flag of the old name in the same position.
+pub(crate) fn replace_edge_info_names(
+    record: &[u8],
+    name_offsets: &[u32],
+) -> Result<Vec<u8>, GraphTileBuildError> {
+    let overflow = |field: &str, value: usize| GraphTileBuildError::BitfieldOverflow {
+        field: field.to_string(),
+        value,
+    };
+    let invalid = |field: &str| {
+        GraphTileBuildError::InvalidFeature(format!("Unable to decode the edge info {field}"))
+    };
+
+    let (inner, rest) = EdgeInfoInner::read_from_prefix(record).map_err(|_| invalid("header"))?;
+    let (name_info_list, rest) = <[NameInfo]>::ref_from_prefix_with_elems(
+        rest,
+        usize::from(inner.second_inner_bitfield.name_count()),
+    )
+    .map_err(|_| invalid("name list"))?;
+
+    let mut old_names = name_info_list
+        .iter()
+        .filter(|name_info| name_info.is_tagged() == 0);
+    let mut new_name_infos = Vec::with_capacity(name_offsets.len() + name_info_list.len());
+    for &offset in name_offsets {
+        let is_route_num = old_names.next().map_or(0, NameInfo::is_route_num);
+        new_name_infos.push(
+            NameInfo::new()
+                .with_name_offset_checked(offset.into())
+                .map_err(|()| overflow("name_offset", offset as usize))?
+                .with_is_route_num(is_route_num),
+        );
+    }
+    new_name_infos.extend(
+        name_info_list
+            .iter()
+            .filter(|name_info| name_info.is_tagged() != 0)
+            .map(|name_info| NameInfo::from_bits(name_info.into_bits())),
+    );
+
+    let name_count = new_name_infos.len();
+    let inner = EdgeInfoInner {
+        second_inner_bitfield: inner
+            .second_inner_bitfield
+            .with_name_count_checked(
+                u8::try_from(name_count).map_err(|_| overflow("name_count", name_count))?,
+            )
+            .map_err(|()| overflow("name_count", name_count))?,
+        ..inner
+    };
+
+    let mut bytes = inner.as_bytes().to_vec();
+    for name_info in &new_name_infos {
+        bytes.extend_from_slice(name_info.as_bytes());
+    }
+    bytes.extend_from_slice(rest);
+
+    Ok(bytes)
+}
+
+/// Lists the text list entries referenced by the names (including tagged values)
+/// of a serialized edge info record.
+///
+/// Each entry is an offset and the number of null-terminated strings stored there
+/// (names may be followed by additional fields).
+pub(crate) fn edge_info_text_references(
+    record: &[u8],
+) -> Result<Vec<(u32, usize)>, GraphTileBuildError> {
+    let (inner, rest) = EdgeInfoInner::ref_from_prefix(record).map_err(|_| {
+        GraphTileBuildError::InvalidFeature("Unable to decode the edge info header".to_string())
+    })?;
+    let (name_info_list, _) = <[NameInfo]>::ref_from_prefix_with_elems(
+        rest,
+        usize::from(inner.second_inner_bitfield.name_count()),
+    )
+    .map_err(|_| {
+        GraphTileBuildError::InvalidFeature("Unable to decode the edge info name list".to_string())
+    })?;
+
+    Ok(name_info_list
+        .iter()
+        .map(|name_info| {
+            (
+                name_info.name_offset().get(),
+                1 + usize::from(name_info.additional_fields()),
+            )
+        })
+        .collect())
+}
+
+/// Rewrites the text list offsets of the names (including tagged values)
+/// in a serialized edge info record, in place.
+///
+/// `new_offset` maps each old offset to its new one.
+pub(crate) fn remap_edge_info_text(
+    record: &mut [u8],
+    new_offset: impl Fn(u32) -> u32,
+) -> Result<(), GraphTileBuildError> {
+    let (inner, rest) = EdgeInfoInner::mut_from_prefix(record).map_err(|_| {
+        GraphTileBuildError::InvalidFeature("Unable to decode the edge info header".to_string())
+    })?;
+    let (name_info_list, _) = <[NameInfo]>::mut_from_prefix_with_elems(
+        rest,
+        usize::from(inner.second_inner_bitfield.name_count()),
+    )
+    .map_err(|_| {
+        GraphTileBuildError::InvalidFeature("Unable to decode the edge info name list".to_string())
+    })?;
+
+    for name_info in name_info_list {
+        let offset = new_offset(name_info.name_offset().get());
+        name_info
+            .set_name_offset_checked(offset.into())
+            .map_err(|()| GraphTileBuildError::BitfieldOverflow {
+                field: "name_offset".to_string(),
+                value: offset as usize,
+            })?;
+    }
+
+    Ok(())
+}
+
 // TODO: Feels like this could be a macro
 impl<'a> TryFrom<(&'a [u8], &'a [u8])> for EdgeInfo<'a> {
     type Error = GraphTileDecodingError;
@@ -234,21 +613,36 @@ impl<'a> TryFrom<(&'a [u8], &'a [u8])> for EdgeInfo<'a> {
         let offset = 0;
         let (encoded_shape, offset) = {
             let end = offset + inner.second_inner_bitfield.encoded_shape_size().get() as usize;
-            (&bytes[offset..end], end)
+            (
+                bytes
+                    .get(offset..end)
+                    .ok_or(GraphTileDecodingError::SliceLength)?,
+                end,
+            )
         };
 
         // Maybe read a byte; the data structure on disk is tightly packed
         // and drops bytes when possible in exchange for bits that are otherwise unused.
         let (extended_way_id_2, offset) = if inner.second_inner_bitfield.extended_way_id_size() > 0
         {
-            (bytes[offset], offset + 1)
+            (
+                *bytes
+                    .get(offset)
+                    .ok_or(GraphTileDecodingError::SliceLength)?,
+                offset + 1,
+            )
         } else {
             (0, offset)
         };
 
         let (extended_way_id_3, _offset) = if inner.second_inner_bitfield.extended_way_id_size() > 1
         {
-            (bytes[offset], offset + 1)
+            (
+                *bytes
+                    .get(offset)
+                    .ok_or(GraphTileDecodingError::SliceLength)?,
+                offset + 1,
+            )
         } else {
             (0, offset)
         };
@@ -313,3 +707,30 @@ impl Serialize for EdgeInfo<'_> {
         state.end()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{EdgeInfoInner, NameInfo, encode_edge_info, replace_edge_info_names};
+    use zerocopy::FromBytes;
+
+    fn name_infos(record: &mut [u8]) -> &mut [NameInfo] {
+        let (inner, rest) = EdgeInfoInner::mut_from_prefix(record).unwrap();
+        let name_count = usize::from(inner.second_inner_bitfield.name_count());
+        <[NameInfo]>::mut_from_prefix_with_elems(rest, name_count)
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn replace_names_keeps_route_numbers() {
+        let mut record = encode_edge_info(1, 0, &[1, 5], &[]).unwrap();
+        name_infos(&mut record)[0].set_is_route_num(1);
+
+        let mut record = replace_edge_info_names(&record, &[9, 13, 17]).unwrap();
+        let names = name_infos(&mut record)
+            .iter()
+            .map(|name_info| (name_info.name_offset().get(), name_info.is_route_num()))
+            .collect::<Vec<_>>();
+        assert_eq!(names, [(9, 1), (13, 0), (17, 0)]);
+    }
+}
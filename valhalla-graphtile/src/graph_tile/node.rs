@@ -1,4 +1,5 @@
-use crate::{Access, GraphId};
+use super::{GraphTileBuildError, HEADING_SAMPLE_DISTANCE, NewNode};
+use crate::{Access, GraphId, spatial::heading_along_line};
 use bitfield_struct::bitfield;
 use enumset::EnumSet;
 use geo::{Coord, coord};
@@ -10,6 +11,8 @@ const MIN_ELEVATION: f32 = -500.0;
 // Max number of edges on the local level
 const MAX_LOCAL_EDGE_INDEX: u8 = 7;
 const HEADING_EXPAND_FACTOR: f32 = 359f32 / 255f32;
+// Node coordinates are stored as offsets from the tile corner in units of 1e-7 degrees
+const COORDINATE_UNITS_PER_DEGREE: f64 = 1e7;
 
 #[bitfield(u64,
     repr = U64<LE>,
@@ -157,6 +160,160 @@ impl NodeTransition {
 }
 
 impl NodeInfo {
+    /// Creates a new node for a tile with the given SW corner.
+    ///
+    /// The node's outbound edges must be stored contiguously starting at `edge_index`.
+    pub(crate) fn try_new(
+        node: &NewNode,
+        sw_corner: Coord<f32>,
+        edge_index: u32,
+    ) -> Result<Self, GraphTileBuildError> {
+        let to_units = |value: f64, origin: f32| {
+            ((value - f64::from(origin)) * COORDINATE_UNITS_PER_DEGREE).round()
+        };
+        let first_bit_field = FirstBitfield::new().with_access(node.access.as_repr().into());
+
+        let edge_count = node.edges.len();
+        let second_bit_field = SecondBitfield::new()
+            .with_edge_index_checked(edge_index.into())
+            .map_err(|()| GraphTileBuildError::BitfieldOverflow {
+                field: "edge_index".to_string(),
+                value: edge_index as usize,
+            })?
+            .with_edge_count_checked(u8::try_from(edge_count)?)
+            .map_err(|()| GraphTileBuildError::BitfieldOverflow {
+                field: "edge_count".to_string(),
+                value: edge_count,
+            })?
+            .with_admin_index_checked(node.admin_index.into())
+            .map_err(|()| GraphTileBuildError::BitfieldOverflow {
+                field: "admin_index".to_string(),
+                value: usize::from(node.admin_index),
+            })?
+            .with_is_traffic_signal(node.is_traffic_signal.into());
+
+        // The local edge count is stored minus one (see local_edge_count)
+        let local_edge_count = edge_count.clamp(1, usize::from(MAX_LOCAL_EDGE_INDEX) + 1) - 1;
+        let third_bit_field = ThirdBitfield::new()
+            .with_local_edge_count(u8::try_from(local_edge_count)?)
+            .with_drive_on_right(node.drive_on_right.into());
+
+        // Headings are stored in a single byte each, for up to the first 8 edges
+        let mut headings = 0u64;
+        for (index, edge) in node
+            .edges
+            .iter()
+            .take(usize::from(MAX_LOCAL_EDGE_INDEX) + 1)
+            .enumerate()
+        {
+            let heading = heading_along_line(&edge.shape, HEADING_SAMPLE_DISTANCE).unwrap_or(0.0);
+            #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let compressed = (heading.rem_euclid(360.0) / f64::from(HEADING_EXPAND_FACTOR))
+                .round()
+                .min(255.0) as u64;
+            headings |= compressed << (index * 8);
+        }
+
+        let mut result = Self {
+            first_bit_field,
+            second_bit_field,
+            third_bit_field,
+            headings: headings.into(),
+        };
+        result.set_coordinate_units(
+            to_units(node.coordinate.y, sw_corner.y),
+            to_units(node.coordinate.x, sw_corner.x),
+        )?;
+
+        Ok(result)
+    }
+
+    /// Moves the origin that the node's coordinate is stored relative to,
+    /// keeping the absolute position of the node the same.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the node would be south or west of the new origin,
+    /// or too far away from it to fit in the tile format.
+    pub(crate) fn move_sw_corner(
+        &mut self,
+        old_sw_corner: Coord<f32>,
+        new_sw_corner: Coord<f32>,
+    ) -> Result<(), GraphTileBuildError> {
+        let units = |offset: u32, offset7: u8, old: f32, new: f32| {
+            f64::from(offset) * 10.0
+                + f64::from(offset7)
+                + ((f64::from(old) - f64::from(new)) * COORDINATE_UNITS_PER_DEGREE).round()
+        };
+        self.set_coordinate_units(
+            units(
+                self.first_bit_field.lat_offset().get(),
+                self.first_bit_field.lat_offset7(),
+                old_sw_corner.y,
+                new_sw_corner.y,
+            ),
+            units(
+                self.first_bit_field.lon_offset().get(),
+                self.first_bit_field.lon_offset7(),
+                old_sw_corner.x,
+                new_sw_corner.x,
+            ),
+        )
+    }
+
+    /// Sets the node's offsets from the tile origin, in (whole) units of 1e-7 degrees.
+    fn set_coordinate_units(
+        &mut self,
+        lat_units: f64,
+        lon_units: f64,
+    ) -> Result<(), GraphTileBuildError> {
+        // Offsets are split into a 1e-6 degree part and a 1e-7 degree remainder
+        let to_offset = |field: &str, units: f64| {
+            if !(0.0..=f64::from(u32::MAX)).contains(&units) {
+                return Err(GraphTileBuildError::InvalidFeature(format!(
+                    "The node {field} offset {} is outside the tile",
+                    units / COORDINATE_UNITS_PER_DEGREE
+                )));
+            }
+            #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let units = units as u32;
+            Ok((units / 10, u8::try_from(units % 10)?))
+        };
+        let (lat_offset, lat_offset7) = to_offset("latitude", lat_units)?;
+        let (lon_offset, lon_offset7) = to_offset("longitude", lon_units)?;
+
+        self.first_bit_field = self
+            .first_bit_field
+            .with_lat_offset_checked(lat_offset.into())
+            .map_err(|()| GraphTileBuildError::BitfieldOverflow {
+                field: "lat_offset".to_string(),
+                value: lat_offset as usize,
+            })?
+            .with_lat_offset7(lat_offset7)
+            .with_lon_offset_checked(lon_offset.into())
+            .map_err(|()| GraphTileBuildError::BitfieldOverflow {
+                field: "lon_offset".to_string(),
+                value: lon_offset as usize,
+            })?
+            .with_lon_offset7(lon_offset7);
+
+        Ok(())
+    }
+
+    /// Sets the index of the admin region containing this node (in the tile's admin list).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the index does not fit in 12 bits.
+    pub(crate) fn set_admin_index(&mut self, admin_index: u16) -> Result<(), GraphTileBuildError> {
+        self.second_bit_field
+            .set_admin_index_checked(admin_index.into())
+            .map_err(|()| GraphTileBuildError::BitfieldOverflow {
+                field: "admin_index".to_string(),
+                value: usize::from(admin_index),
+            })
+    }
+
     /// Gets the coordinate of the node.
     /// The data is stored as a relative offset internally,
     /// so a reference coordinate (namely the SW corner of the tile)
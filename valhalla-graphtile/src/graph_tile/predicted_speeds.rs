@@ -19,6 +19,9 @@ use std::sync::LazyLock;
 use thiserror::Error;
 use zerocopy::{I16, LE, U32};
 
+#[cfg(feature = "fft")]
+mod dct;
+
 /// The size of each interval.
 ///
 /// The week is broken into fixed-size buckets.
@@ -34,6 +37,17 @@ pub const COEFFICIENT_COUNT: usize = 200;
 /// Each value (`i16`) is encoded as 2 bytes in big-endian order.
 const DECODED_SPEED_SIZE: usize = 2 * COEFFICIENT_COUNT;
 
+/// Normalization factor for the orthonormal DCT-II / DCT-III.
+///
+/// Uses the `trig_const` crate to precompute this at compile time within an acceptable range of error.
+/// If sqrt is ever made stable in const contexts, we can drop this dependency.
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    reason = "This value is guaranteed to be small, since BUCKETS_PER_WEEK is small."
+)]
+const SPEED_NORM: f32 = const { trig_const::sqrt(2.0 / BUCKETS_PER_WEEK as f64) as f32 };
+
 /// Lazily initialized cosine lookup table.
 ///
 /// We pre-scale the table as an additional optimization from the Valhalla version.
@@ -55,15 +69,6 @@ static COS_TABLE: LazyLock<Box<[[f32; COEFFICIENT_COUNT]]>> = LazyLock::new(|| {
     )]
     const PI_BUCKET_CONST: f32 = std::f32::consts::PI / BUCKETS_PER_WEEK as f32;
 
-    // Uses the trig_const crate to precompute this at compile time within an acceptable range of error.
-    // If sqrt is ever made stable in const contexts, we can drop this dependency.
-    #[expect(
-        clippy::cast_possible_truncation,
-        clippy::cast_precision_loss,
-        reason = "This value is guaranteed to be small, since BUCKETS_PER_WEEK is small."
-    )]
-    const SPEED_NORM: f32 = const { trig_const::sqrt(2.0 / BUCKETS_PER_WEEK as f64) as f32 };
-
     const {
         assert!(BUCKETS_PER_WEEK < 2usize.pow(24));
     }
@@ -114,17 +119,14 @@ pub enum PredictedSpeedCodecError {
 /// Compress a full week of speed buckets by truncating its DCT-II.
 ///
 /// Speeds are expected to be specified in kilometers per hour.
+/// With the `fft` feature enabled, the transform is computed via an FFT
+/// rather than directly against the cosine table.
 #[inline]
 pub fn compress_speed_buckets(speeds: &[f32; BUCKETS_PER_WEEK]) -> [i16; COEFFICIENT_COUNT] {
-    let mut acc = [0f32; COEFFICIENT_COUNT];
-
-    // DCT-II accumulation (bucket-major) using the precomputed, scaled cosines.
-    for (bucket, &speed) in speeds.iter().enumerate() {
-        let row = cos_row(bucket);
-        for (a, &basis) in acc.iter_mut().zip(row.iter()) {
-            *a += speed * basis;
-        }
-    }
+    #[cfg(feature = "fft")]
+    let acc = dct::dct_ii(speeds);
+    #[cfg(not(feature = "fft"))]
+    let acc = dct_ii_scalar(speeds);
 
     // Quantize (round) directly to i16
     let mut result = [0i16; COEFFICIENT_COUNT];
@@ -138,6 +140,22 @@ pub fn compress_speed_buckets(speeds: &[f32; BUCKETS_PER_WEEK]) -> [i16; COEFFIC
     result
 }
 
+/// Truncated DCT-II (bucket-major) using the precomputed, scaled cosines.
+#[cfg_attr(
+    all(feature = "fft", not(test)),
+    expect(dead_code, reason = "Replaced by the FFT implementation")
+)]
+fn dct_ii_scalar(speeds: &[f32; BUCKETS_PER_WEEK]) -> [f32; COEFFICIENT_COUNT] {
+    let mut acc = [0f32; COEFFICIENT_COUNT];
+    for (bucket, &speed) in speeds.iter().enumerate() {
+        let row = cos_row(bucket);
+        for (a, &basis) in acc.iter_mut().zip(row.iter()) {
+            *a += speed * basis;
+        }
+    }
+    acc
+}
+
 /// Recover a single bucket’s speed from the compressed coefficients.
 ///
 /// `bucket_idx` must be in [0, [`BUCKETS_PER_WEEK`]).
@@ -156,6 +174,42 @@ pub fn decompress_speed_bucket(coefficients: &[i16; COEFFICIENT_COUNT], bucket_i
     s
 }
 
+/// Recover every bucket's speed from the compressed coefficients.
+///
+/// This is equivalent to calling [`decompress_speed_bucket`] for each bucket,
+/// writing the speeds (in kilometers per hour) into `speeds`.
+/// With the `fft` feature enabled, the inverse transform is computed via an FFT,
+/// so results may differ from [`decompress_speed_bucket`] by floating point error.
+#[inline]
+pub fn decompress_speed_buckets(
+    coefficients: &[i16; COEFFICIENT_COUNT],
+    speeds: &mut [f32; BUCKETS_PER_WEEK],
+) {
+    #[cfg(feature = "fft")]
+    dct::dct_iii(coefficients, speeds);
+    #[cfg(not(feature = "fft"))]
+    dct_iii_scalar(coefficients, speeds);
+}
+
+/// DCT-III reconstruction of every bucket using the precomputed, scaled cosines.
+#[cfg_attr(
+    all(feature = "fft", not(test)),
+    expect(dead_code, reason = "Replaced by the FFT implementation")
+)]
+fn dct_iii_scalar(coefficients: &[i16; COEFFICIENT_COUNT], speeds: &mut [f32; BUCKETS_PER_WEEK]) {
+    // Widen once up front rather than once per bucket
+    let coefficients = coefficients.map(f32::from);
+    for (bucket, speed) in speeds.iter_mut().enumerate() {
+        let row = cos_row(bucket);
+        // See decompress_speed_bucket re: the indexed loop
+        let mut s = 0.0f32;
+        for i in 0..COEFFICIENT_COUNT {
+            s = row[i].mul_add(coefficients[i], s);
+        }
+        *speed = s;
+    }
+}
+
 /// Pack transformed speed values into a base64 string.
 /// Each i16 is serialized big-endian to match the C++.
 pub fn encode_compressed_speeds(coefficients: &[i16; COEFFICIENT_COUNT]) -> String {
@@ -242,6 +296,40 @@ impl<'a> PredictedSpeeds<'a> {
         if bucket >= BUCKETS_PER_WEEK {
             return None;
         }
+        let coeffs = self.coefficients(directed_edge_index)?;
+
+        Some(decompress_speed_bucket(&coeffs, bucket))
+    }
+
+    /// Get the predicted speeds (kph) for every bucket of the week for a given directed-edge index.
+    ///
+    /// This decodes the whole profile in one pass,
+    /// which is much cheaper than calling [`PredictedSpeeds::speed`] for each bucket.
+    /// Returns `None` if the offset is invalid.
+    ///
+    /// The same caveats as [`PredictedSpeeds::speed`] apply regarding edges without traffic data.
+    pub fn weekly_profile(&self, directed_edge_index: usize) -> Option<[f32; BUCKETS_PER_WEEK]> {
+        let mut speeds = [0f32; BUCKETS_PER_WEEK];
+        self.weekly_profile_into(directed_edge_index, &mut speeds)?;
+        Some(speeds)
+    }
+
+    /// Like [`PredictedSpeeds::weekly_profile`], but writes into a caller-provided buffer.
+    ///
+    /// This is useful when decoding many profiles, as the buffer can be reused.
+    /// Returns `None` (leaving `speeds` untouched) if the offset is invalid.
+    pub fn weekly_profile_into(
+        &self,
+        directed_edge_index: usize,
+        speeds: &mut [f32; BUCKETS_PER_WEEK],
+    ) -> Option<()> {
+        let coeffs = self.coefficients(directed_edge_index)?;
+        decompress_speed_buckets(&coeffs, speeds);
+        Some(())
+    }
+
+    /// Looks up the compressed coefficients for a directed-edge index.
+    fn coefficients(&self, directed_edge_index: usize) -> Option<[i16; COEFFICIENT_COUNT]> {
         let start = self.offsets.get(directed_edge_index)?.get() as usize;
 
         // View the profiles as fixed-size chunks
@@ -256,9 +344,7 @@ impl<'a> PredictedSpeeds<'a> {
         );
 
         let chunk_idx = start / COEFFICIENT_COUNT;
-        let coeffs = chunks.get(chunk_idx)?.map(I16::get);
-
-        Some(decompress_speed_bucket(&coeffs, bucket))
+        chunks.get(chunk_idx).map(|chunk| chunk.map(I16::get))
     }
 
     /// Returns the raw borrowed slices for the offsets and profiles (in order).
@@ -293,9 +379,7 @@ mod tests {
             // Compress then decode all buckets
             let coeffs = compress_speed_buckets(&speeds);
             let mut recon = [0f32; BUCKETS_PER_WEEK];
-            for i in 0..BUCKETS_PER_WEEK {
-                recon[i] = decompress_speed_bucket(&coeffs, i);
-            }
+            decompress_speed_buckets(&coeffs, &mut recon);
 
             for (i, &s) in recon.iter().enumerate() {
                 prop_assert!(s >= -0.5, "negative speed at bucket {i}: {s}");
@@ -347,9 +431,7 @@ mod tests {
             // Compress and reconstruct
             let coeffs = compress_speed_buckets(&speeds);
             let mut recon = [0f32; BUCKETS_PER_WEEK];
-            for i in 0..BUCKETS_PER_WEEK {
-                recon[i] = decompress_speed_bucket(&coeffs, i);
-            }
+            decompress_speed_buckets(&coeffs, &mut recon);
 
             let (mae, maxe) = mae_and_max(&speeds, &recon);
 
@@ -508,10 +590,9 @@ mod tests {
         let profiles: Vec<I16<LE>> = coeffs.into();
 
         let ps = PredictedSpeeds::new(&offsets, &profiles);
+        let profile = ps.weekly_profile(0).expect("profile");
 
-        for (i, &exp) in SPEEDS.iter().enumerate() {
-            let secs = (i as u32) * SPEED_BUCKET_SIZE_SECONDS;
-            let s = ps.speed(0, secs).expect("speed");
+        for (i, (&exp, &s)) in SPEEDS.iter().zip(profile.iter()).enumerate() {
             // The C++ codebase had a rather strange way of comparing these.
             // Presumably it was to account for theoretical differences that could arise
             // due to floating point imprecision?
@@ -522,6 +603,43 @@ mod tests {
         }
     }
 
+    #[test]
+    fn weekly_profile_matches_speed() {
+        let mut speeds = [0f32; BUCKETS_PER_WEEK];
+        for (i, speed) in (0u16..).zip(speeds.iter_mut()) {
+            *speed = 40.0 + 20.0 * (f32::from(i) / 50.0).sin();
+        }
+        let offsets: [U32<LE>; 2] = [0.into(), (COEFFICIENT_COUNT as u32).into()];
+        let profiles: Vec<I16<LE>> = compress_speed_buckets(&[10.0; BUCKETS_PER_WEEK])
+            .into_iter()
+            .chain(compress_speed_buckets(&speeds))
+            .map(I16::from)
+            .collect();
+        let ps = PredictedSpeeds::new(&offsets, &profiles);
+
+        let mut buffer = [0f32; BUCKETS_PER_WEEK];
+        for edge in 0..offsets.len() {
+            let profile = ps.weekly_profile(edge).expect("profile");
+            assert_eq!(ps.weekly_profile_into(edge, &mut buffer), Some(()));
+            assert_eq!(profile.map(f32::to_bits), buffer.map(f32::to_bits));
+            for (i, &s) in profile.iter().enumerate() {
+                let secs = (i as u32) * SPEED_BUCKET_SIZE_SECONDS;
+                let expected = ps.speed(edge, secs).expect("speed");
+                // The FFT backend may differ by floating point error
+                assert!((expected - s).abs() < 0.01, "Mismatch at bucket {i}");
+            }
+        }
+
+        // Out of range edge indexes leave the buffer untouched
+        assert_eq!(ps.weekly_profile(2), None);
+        assert_eq!(ps.weekly_profile_into(2, &mut buffer), None);
+        assert_eq!(
+            Some(buffer.map(f32::to_bits)),
+            ps.weekly_profile(1)
+                .map(|profile| profile.map(f32::to_bits))
+        );
+    }
+
     fn normalized_l1_norm(vec: &[f32]) -> f32 {
         assert!(!vec.is_empty());
         let sum: f32 = vec.iter().map(|v| v.abs()).sum();
@@ -680,9 +798,7 @@ mod tests {
             let profiles: Vec<I16<LE>> = coeffs.into();
             let ps = PredictedSpeeds::new(&offsets, &profiles);
 
-            for i in 0..BUCKETS_PER_WEEK {
-                let secs = (i as u32) * SPEED_BUCKET_SIZE_SECONDS;
-                let s = ps.speed(0, secs).expect("speed");
+            for (i, &s) in ps.weekly_profile(0).expect("profile").iter().enumerate() {
                 assert!(s >= 0.0, "Unexpected negative speed at bucket {i}");
             }
         }
@@ -700,9 +816,7 @@ mod tests {
 
             // decompress all buckets
             let mut recon = [0f32; BUCKETS_PER_WEEK];
-            for i in 0..BUCKETS_PER_WEEK {
-                recon[i] = decompress_speed_bucket(&coeffs, i);
-            }
+            decompress_speed_buckets(&coeffs, &mut recon);
 
             // diffs
             let mut diffs = [0f32; BUCKETS_PER_WEEK];
@@ -0,0 +1,83 @@
+//! FFT-backed DCTs for compressing and decompressing whole weeks of speeds.
+//!
+//! The scalar implementations cost `O(BUCKETS_PER_WEEK * COEFFICIENT_COUNT)` per profile,
+//! which adds up quickly when building tiles with predicted speeds for every edge.
+//! These compute the full-length transform in `O(BUCKETS_PER_WEEK * log(BUCKETS_PER_WEEK))`,
+//! and agree with the scalar path to within floating point error.
+use super::{BUCKETS_PER_WEEK, COEFFICIENT_COUNT, SPEED_NORM};
+use rustdct::{DctPlanner, TransformType2And3};
+use std::f32::consts::{FRAC_1_SQRT_2, SQRT_2};
+use std::sync::{Arc, LazyLock};
+
+/// A single plan handles both directions, since the DCT-III is the inverse of the DCT-II.
+static PLAN: LazyLock<Arc<dyn TransformType2And3<f32>>> =
+    LazyLock::new(|| DctPlanner::new().plan_dct2(BUCKETS_PER_WEEK));
+
+/// Truncated orthonormal DCT-II of a week of speeds.
+pub(super) fn dct_ii(speeds: &[f32; BUCKETS_PER_WEEK]) -> [f32; COEFFICIENT_COUNT] {
+    let mut buffer = *speeds;
+    PLAN.process_dct2(&mut buffer);
+
+    // rustdct does not normalize, so apply the same scaling as the cosine table
+    let mut acc = [0f32; COEFFICIENT_COUNT];
+    for (a, &raw) in acc.iter_mut().zip(buffer.iter()) {
+        *a = raw * SPEED_NORM;
+    }
+    acc[0] *= FRAC_1_SQRT_2;
+    acc
+}
+
+/// Orthonormal DCT-III of the (zero-padded) coefficients, recovering every bucket.
+pub(super) fn dct_iii(
+    coefficients: &[i16; COEFFICIENT_COUNT],
+    speeds: &mut [f32; BUCKETS_PER_WEEK],
+) {
+    speeds.fill(0.0);
+    for (s, &c) in speeds.iter_mut().zip(coefficients.iter()) {
+        *s = f32::from(c) * SPEED_NORM;
+    }
+    // rustdct halves the DC term, whereas the orthonormal transform scales it by 1/sqrt(2)
+    speeds[0] *= SQRT_2;
+    PLAN.process_dct3(speeds);
+}
+
+// Temporarily not run under miri, since the trig ops are REALLY slow
+#[cfg(all(test, not(miri)))]
+mod tests {
+    use super::super::{dct_ii_scalar, dct_iii_scalar};
+    use super::{BUCKETS_PER_WEEK, COEFFICIENT_COUNT, dct_ii, dct_iii};
+    use proptest::prop_assert;
+
+    proptest::proptest! {
+        #[test]
+        fn prop_dct_ii_matches_scalar(
+            speeds in proptest::collection::vec(0.0f32..250.0f32, BUCKETS_PER_WEEK)
+        ) {
+            let speeds: [f32; BUCKETS_PER_WEEK] = speeds.try_into().expect("exact length");
+
+            let fast = dct_ii(&speeds);
+            let scalar = dct_ii_scalar(&speeds);
+            for (i, (f, s)) in fast.iter().zip(scalar.iter()).enumerate() {
+                // Coefficients are rounded to integers, so this is well within tolerance
+                prop_assert!((f - s).abs() < 0.05, "Coefficient {i} differs: {f} vs {s}");
+            }
+        }
+
+        #[test]
+        fn prop_dct_iii_matches_scalar(
+            coefficients in proptest::collection::vec(-2000i16..2000i16, COEFFICIENT_COUNT)
+        ) {
+            let coefficients: [i16; COEFFICIENT_COUNT] =
+                coefficients.try_into().expect("exact length");
+
+            let mut fast = [0f32; BUCKETS_PER_WEEK];
+            let mut scalar = [0f32; BUCKETS_PER_WEEK];
+            dct_iii(&coefficients, &mut fast);
+            dct_iii_scalar(&coefficients, &mut scalar);
+            for (i, (f, s)) in fast.iter().zip(scalar.iter()).enumerate() {
+                // Both accumulate in f32, so allow for some rounding error
+                prop_assert!((f - s).abs() < 0.05, "Bucket {i} differs: {f} vs {s}");
+            }
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::graph_tile::GraphTileBuildError;
 use bitfield_struct::bitfield;
 use zerocopy::{LE, U32};
 use zerocopy_derive::{FromBytes, Immutable, IntoBytes, TryFromBytes, Unaligned};
@@ -8,7 +9,7 @@ use zerocopy_derive::{FromBytes, Immutable, IntoBytes, TryFromBytes, Unaligned};
 /// and the offset is stored within the sign.
 /// The directed edge index within the tile is also stored
 /// so that signs can be found via either the directed edge or node index.
-#[derive(TryFromBytes, Immutable, Unaligned, Debug)]
+#[derive(TryFromBytes, Immutable, Unaligned, Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum SignType {
     ExitNumber,
@@ -25,6 +26,11 @@ pub enum SignType {
 }
 
 impl SignType {
+    /// Does this type of sign belong to a node (rather than a directed edge)?
+    pub const fn is_node_sign(self) -> bool {
+        matches!(self, Self::JunctionName | Self::TollName)
+    }
+
     const fn into_bits(self) -> u8 {
         self as _
     }
@@ -103,6 +109,30 @@ impl Sign {
     pub const fn is_text_tagged(&self) -> bool {
         self.bitfield.is_text_tagged() != 0
     }
+
+    /// Creates a sign with untagged text for a directed edge or node.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the index does not fit in 22 bits.
+    pub(crate) fn try_new(
+        edge_or_node_index: u32,
+        sign_type: SignType,
+        text_offset: u32,
+    ) -> Result<Self, GraphTileBuildError> {
+        let mut bitfield = SignBitField::new().with_sign_type(sign_type);
+        bitfield
+            .set_edge_or_node_index_checked(edge_or_node_index.into())
+            .map_err(|()| GraphTileBuildError::BitfieldOverflow {
+                field: "edge_or_node_index".to_string(),
+                value: edge_or_node_index as usize,
+            })?;
+
+        Ok(Self {
+            bitfield,
+            text_offset: text_offset.into(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,284 @@
+//! Edge speed selection.
+//!
+//! An edge can have several speeds: live traffic, a predicted (historical) weekly profile,
+//! typical daytime (constrained flow) and night time (free flow) speeds, and a default speed.
+//! This module implements the precedence rules for picking between them,
+//! ported from `GraphTile::GetSpeed` in Valhalla,
+//! so that costing models don't each have to reimplement them.
+
+use super::DirectedEdge;
+use crate::traffic_tile::{SegmentTrafficInfo, TrafficSpeed};
+use enumset::{EnumSet, EnumSetType};
+
+const SECONDS_PER_DAY: u32 = 24 * 60 * 60;
+/// Constrained (daytime) flow starts at 7 AM.
+const CONSTRAINED_FLOW_SECOND_OF_DAY: u32 = 7 * 60 * 60;
+/// Free (night time) flow starts at 7 PM.
+const FREE_FLOW_SECOND_OF_DAY: u32 = 19 * 60 * 60;
+
+/// A source of edge speed information.
+///
+/// Sources are listed in order of precedence.
+#[derive(Debug, EnumSetType)]
+pub enum SpeedSource {
+    /// Live traffic (from a traffic tile).
+    Live,
+    /// The predicted speed profile for the time of the week.
+    Predicted,
+    /// The typical speed during the day (7 AM to 7 PM), when there is traffic.
+    ConstrainedFlow,
+    /// The typical speed at night (7 PM to 7 AM), when there is no traffic.
+    FreeFlow,
+    /// The edge's default speed.
+    ///
+    /// This is always used as a last resort, regardless of the mask.
+    Default,
+}
+
+/// The speed selected for an edge, along with where it came from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeSpeed {
+    /// The speed, in kph.
+    pub kph: f32,
+    /// The sources which contributed to the speed.
+    ///
+    /// This has more than one member when live traffic only covers part of the edge,
+    /// in which case the remainder is blended in from the next available source.
+    pub sources: EnumSet<SpeedSource>,
+}
+
+impl EdgeSpeed {
+    fn new(kph: f32, source: SpeedSource) -> Self {
+        Self {
+            kph,
+            sources: EnumSet::<SpeedSource>::only(source),
+        }
+    }
+}
+
+/// Selects a speed for `edge` by falling back through the sources in `flow_mask`.
+///
+/// `predicted` looks up the predicted speed for the edge at a time of the week.
+pub(super) fn select_speed(
+    edge: &DirectedEdge,
+    seconds_of_week: Option<u32>,
+    live: Option<TrafficSpeed>,
+    flow_mask: EnumSet<SpeedSource>,
+    predicted: impl FnOnce(u32) -> Option<f32>,
+) -> EdgeSpeed {
+    let fallback = |predicted| select_historical_speed(edge, seconds_of_week, flow_mask, predicted);
+
+    let Some(live) = live.filter(|_| flow_mask.contains(SpeedSource::Live)) else {
+        return fallback(predicted);
+    };
+    if live.is_completely_closed() {
+        return EdgeSpeed::new(0.0, SpeedSource::Live);
+    }
+    let Some((live_kph, coverage)) = live_speed_and_coverage(live) else {
+        return fallback(predicted);
+    };
+    if coverage >= 1.0 {
+        return EdgeSpeed::new(live_kph, SpeedSource::Live);
+    }
+
+    // Live traffic only covers part of the edge, so fill in the rest
+    let rest = fallback(predicted);
+    EdgeSpeed {
+        kph: live_kph.mul_add(coverage, rest.kph * (1.0 - coverage)),
+        sources: rest.sources | SpeedSource::Live,
+    }
+}
+
+/// Selects a speed without considering live traffic.
+fn select_historical_speed(
+    edge: &DirectedEdge,
+    seconds_of_week: Option<u32>,
+    flow_mask: EnumSet<SpeedSource>,
+    predicted: impl FnOnce(u32) -> Option<f32>,
+) -> EdgeSpeed {
+    if flow_mask.contains(SpeedSource::Predicted)
+        && edge.has_predicted_speed()
+        && let Some(seconds) = seconds_of_week
+        && let Some(kph) = predicted(seconds).filter(|&kph| kph > 0.0)
+    {
+        return EdgeSpeed::new(kph, SpeedSource::Predicted);
+    }
+
+    // Without a time, either of the typical speeds will do
+    let is_night = seconds_of_week.map(|seconds| {
+        let second_of_day = seconds % SECONDS_PER_DAY;
+        !(CONSTRAINED_FLOW_SECOND_OF_DAY..FREE_FLOW_SECOND_OF_DAY).contains(&second_of_day)
+    });
+    if flow_mask.contains(SpeedSource::FreeFlow)
+        && is_night != Some(false)
+        && edge.free_flow_speed() > 0
+    {
+        return EdgeSpeed::new(f32::from(edge.free_flow_speed()), SpeedSource::FreeFlow);
+    }
+    if flow_mask.contains(SpeedSource::ConstrainedFlow)
+        && is_night != Some(true)
+        && edge.constrained_flow_speed() > 0
+    {
+        return EdgeSpeed::new(
+            f32::from(edge.constrained_flow_speed()),
+            SpeedSource::ConstrainedFlow,
+        );
+    }
+
+    EdgeSpeed::new(f32::from(edge.speed()), SpeedSource::Default)
+}
+
+/// Computes the average live speed over the segments with data,
+/// and the fraction of the edge which they cover.
+///
+/// Closed segments count as covered, with a speed of zero.
+/// Returns `None` if no segment has data.
+fn live_speed_and_coverage(live: TrafficSpeed) -> Option<(f32, f32)> {
+    if !live.has_valid_speed() {
+        return None;
+    }
+
+    let mut weighted_kph = 0.0;
+    let mut coverage = 0.0;
+    let mut segment_start = 0;
+    for segment in 0..3 {
+        let (breakpoint, kph) = match live.segment_info(segment) {
+            SegmentTrafficInfo::NoData { breakpoint } => (breakpoint, None),
+            SegmentTrafficInfo::Closed { breakpoint } => (breakpoint, Some(0)),
+            SegmentTrafficInfo::Speed {
+                speed_kph,
+                breakpoint,
+                ..
+            } => (breakpoint, Some(speed_kph)),
+        };
+        if breakpoint <= segment_start {
+            break;
+        }
+        let fraction = f32::from(breakpoint - segment_start) / 255.0;
+        if let Some(kph) = kph {
+            weighted_kph = f32::from(kph).mul_add(fraction, weighted_kph);
+            coverage += fraction;
+        }
+        if breakpoint == 255 {
+            break;
+        }
+        segment_start = breakpoint;
+    }
+
+    (coverage > 0.0).then(|| (weighted_kph / coverage, coverage))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EdgeSpeed, SpeedSource};
+    use crate::graph_tile::{GraphTile, TEST_GRAPH_TILE_WITH_FLOW};
+    use crate::traffic_tile::{SpeedValue, TrafficSpeed, TrafficSpeedBuilder};
+    use enumset::EnumSet;
+
+    const EDGE_INDEX: u64 = 42;
+    const MIDNIGHT: u32 = 0;
+    const NOON: u32 = 12 * 60 * 60;
+
+    fn speed(
+        seconds_of_week: Option<u32>,
+        live: Option<TrafficSpeed>,
+        flow_mask: EnumSet<SpeedSource>,
+    ) -> EdgeSpeed {
+        let tile = &*TEST_GRAPH_TILE_WITH_FLOW;
+        let edge_id = tile.graph_id().with_feature_index(EDGE_INDEX).unwrap();
+        tile.speed_for_edge(edge_id, seconds_of_week, live, flow_mask)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_historical_fallbacks() {
+        let all = EnumSet::all();
+
+        // Predicted speeds win when there is a time
+        let predicted = speed(Some(MIDNIGHT), None, all);
+        assert_eq!(predicted.sources, SpeedSource::Predicted);
+        assert!((predicted.kph - 36.0).abs() < 0.5);
+
+        // Free flow at night and constrained flow during the day
+        let mask = all - SpeedSource::Predicted;
+        assert_eq!(
+            speed(Some(MIDNIGHT), None, mask),
+            EdgeSpeed::new(100.0, SpeedSource::FreeFlow)
+        );
+        assert_eq!(
+            speed(Some(NOON), None, mask),
+            EdgeSpeed::new(42.0, SpeedSource::ConstrainedFlow)
+        );
+        // Days wrap around
+        assert_eq!(
+            speed(Some(NOON + 3 * 24 * 60 * 60), None, mask),
+            EdgeSpeed::new(42.0, SpeedSource::ConstrainedFlow)
+        );
+
+        // Without a time, predicted speeds can't be used
+        assert_eq!(
+            speed(None, None, all),
+            EdgeSpeed::new(100.0, SpeedSource::FreeFlow)
+        );
+        assert_eq!(
+            speed(None, None, SpeedSource::ConstrainedFlow.into()),
+            EdgeSpeed::new(42.0, SpeedSource::ConstrainedFlow)
+        );
+
+        // The default speed is the last resort
+        let tile = &*TEST_GRAPH_TILE_WITH_FLOW;
+        let default_kph = f32::from(tile.directed_edges()[42].speed());
+        assert_eq!(
+            speed(Some(MIDNIGHT), None, SpeedSource::ConstrainedFlow.into()),
+            EdgeSpeed::new(default_kph, SpeedSource::Default)
+        );
+        assert_eq!(
+            speed(Some(NOON), None, EnumSet::empty()),
+            EdgeSpeed::new(default_kph, SpeedSource::Default)
+        );
+    }
+
+    #[test]
+    fn test_live_speeds() {
+        let all = EnumSet::all();
+        let live = TrafficSpeed::single_speed(SpeedValue::try_new(64).unwrap(), None);
+        assert_eq!(
+            speed(Some(NOON), Some(live), all),
+            EdgeSpeed::new(64.0, SpeedSource::Live)
+        );
+        assert_eq!(
+            speed(None, Some(TrafficSpeed::closed()), all),
+            EdgeSpeed::new(0.0, SpeedSource::Live)
+        );
+
+        // Live speeds are ignored unless requested, or if they are invalid
+        let mask = all - SpeedSource::Live - SpeedSource::Predicted;
+        assert_eq!(
+            speed(Some(NOON), Some(live), mask),
+            EdgeSpeed::new(42.0, SpeedSource::ConstrainedFlow)
+        );
+        assert_eq!(
+            speed(
+                Some(NOON),
+                Some(TrafficSpeed::new()),
+                all - SpeedSource::Predicted
+            ),
+            EdgeSpeed::new(42.0, SpeedSource::ConstrainedFlow)
+        );
+
+        // Partial coverage is blended with the next available source
+        let partial = TrafficSpeedBuilder::with_edge_length(100)
+            .with_speed_segment(SpeedValue::try_new(20).unwrap(), None, 50)
+            .unwrap()
+            .with_unknown_segment(50)
+            .unwrap()
+            .build()
+            .unwrap();
+        let blended = speed(Some(NOON), Some(partial), all - SpeedSource::Predicted);
+        assert_eq!(
+            blended.sources,
+            SpeedSource::Live | SpeedSource::ConstrainedFlow
+        );
+        assert!((blended.kph - 31.0).abs() < 0.5, "{}", blended.kph);
+    }
+}
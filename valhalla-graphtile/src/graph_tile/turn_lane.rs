@@ -1,4 +1,10 @@
+use crate::graph_tile::{GraphTileBuildError, GraphTileDecodingError};
 use bitfield_struct::bitfield;
+use enumset::{EnumSet, EnumSetType};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use thiserror::Error;
 use zerocopy::{LE, U32};
 use zerocopy_derive::{FromBytes, Immutable, IntoBytes, Unaligned};
 
@@ -34,11 +40,132 @@ impl TurnLane {
     pub const fn directed_edge_index(&self) -> u32 {
         self.edge_index.edge_index().get()
     }
+
+    /// Creates a turn lane record for a directed edge.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the edge index does not fit in 22 bits.
+    pub(crate) fn try_new(edge_index: u32, text_offset: u32) -> Result<Self, GraphTileBuildError> {
+        let mut bitfield = EdgeIndex::new();
+        bitfield
+            .set_edge_index_checked(edge_index.into())
+            .map_err(|()| GraphTileBuildError::BitfieldOverflow {
+                field: "edge_index".to_string(),
+                value: edge_index as usize,
+            })?;
+
+        Ok(Self {
+            edge_index: bitfield,
+            text_offset: text_offset.into(),
+        })
+    }
+}
+
+/// A direction which a turn lane allows.
+///
+/// A lane is described by the set of directions it allows
+/// (an empty set means the lane has no markings).
+/// These match the values of the OSM `turn:lanes` tag.
+#[derive(Debug, EnumSetType)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[enumset(repr = "u16")]
+pub enum TurnLaneDirection {
+    None,
+    Through,
+    SharpLeft,
+    Left,
+    SlightLeft,
+    SlightRight,
+    Right,
+    SharpRight,
+    Reverse,
+    MergeToLeft,
+    MergeToRight,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Unrecognized turn lane direction: {0}")]
+pub struct UnknownTurnLaneDirectionError(String);
+
+impl FromStr for TurnLaneDirection {
+    type Err = UnknownTurnLaneDirectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "none" => Ok(Self::None),
+            "through" => Ok(Self::Through),
+            "sharp_left" => Ok(Self::SharpLeft),
+            "left" => Ok(Self::Left),
+            "slight_left" => Ok(Self::SlightLeft),
+            "slight_right" => Ok(Self::SlightRight),
+            "right" => Ok(Self::Right),
+            "sharp_right" => Ok(Self::SharpRight),
+            "reverse" => Ok(Self::Reverse),
+            "merge_to_left" => Ok(Self::MergeToLeft),
+            "merge_to_right" => Ok(Self::MergeToRight),
+            s => Err(UnknownTurnLaneDirectionError(s.to_string())),
+        }
+    }
+}
+
+/// Parses the value of an OSM `turn:lanes` tag (ex: `left|through;right|`).
+///
+/// The lanes are listed from left to right.
+/// Lanes without markings (empty values) become empty sets.
+///
+/// # Errors
+///
+/// Fails if any of the directions are not recognized.
+pub fn parse_osm_turn_lanes(
+    value: &str,
+) -> Result<Vec<EnumSet<TurnLaneDirection>>, UnknownTurnLaneDirectionError> {
+    value
+        .split('|')
+        .map(|lane| {
+            lane.split(';')
+                .filter(|direction| !direction.trim().is_empty())
+                .map(str::parse::<TurnLaneDirection>)
+                .collect()
+        })
+        .collect()
+}
+
+/// Encodes turn lanes the way Valhalla stores them in the text list
+/// (the lane bit masks as decimal numbers, separated by `|`).
+pub(crate) fn encode_turn_lanes(lanes: &[EnumSet<TurnLaneDirection>]) -> String {
+    lanes
+        .iter()
+        .map(|lane| lane.as_repr().to_string())
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Decodes turn lanes from the text list (see [`encode_turn_lanes`]).
+pub(crate) fn decode_turn_lanes(
+    text: &str,
+) -> Result<Vec<EnumSet<TurnLaneDirection>>, GraphTileDecodingError> {
+    text.split('|')
+        .map(|mask| {
+            mask.parse()
+                .ok()
+                .and_then(EnumSet::try_from_repr)
+                .ok_or_else(|| GraphTileDecodingError::CastError {
+                    field: "turn_lanes".to_string(),
+                    error_description: format!("Invalid turn lane mask {mask:?} in {text:?}"),
+                })
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{
+        TurnLaneDirection, UnknownTurnLaneDirectionError, decode_turn_lanes, encode_turn_lanes,
+        parse_osm_turn_lanes,
+    };
     use crate::graph_tile::{GraphTile, TEST_GRAPH_TILE_L0};
+    use enumset::EnumSet;
 
     #[test]
     fn test_parse_turn_lane_count() {
@@ -61,4 +188,47 @@ mod tests {
             insta::assert_debug_snapshot!(tile_view.turn_lanes);
         }
     }
+
+    #[test]
+    fn test_get_turn_lanes() {
+        let tile = &*TEST_GRAPH_TILE_L0;
+        let tile_view = tile.borrow_dependent();
+
+        for turn_lane in tile_view.turn_lanes {
+            let edge_index = turn_lane.directed_edge_index();
+            assert!(tile.directed_edges()[edge_index as usize].has_turn_lanes());
+            assert!(!tile.get_turn_lanes(edge_index).unwrap().is_empty());
+        }
+        assert_eq!(
+            tile.get_turn_lanes(1710).unwrap(),
+            [
+                EnumSet::empty(),
+                EnumSet::empty(),
+                TurnLaneDirection::MergeToLeft.into()
+            ]
+        );
+        assert!(tile.get_turn_lanes(0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_osm_turn_lanes() {
+        let lanes = parse_osm_turn_lanes("left|through;right|").unwrap();
+        assert_eq!(
+            lanes,
+            [
+                TurnLaneDirection::Left.into(),
+                TurnLaneDirection::Through | TurnLaneDirection::Right,
+                EnumSet::empty()
+            ]
+        );
+        assert_eq!(encode_turn_lanes(&lanes), "8|66|0");
+        assert_eq!(decode_turn_lanes("8|66|0").unwrap(), lanes);
+
+        assert_eq!(
+            parse_osm_turn_lanes("left|sideways"),
+            Err(UnknownTurnLaneDirectionError("sideways".to_string()))
+        );
+        assert!(decode_turn_lanes("8|x").is_err());
+        assert!(decode_turn_lanes("4096").is_err());
+    }
 }
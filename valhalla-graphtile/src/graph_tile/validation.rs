@@ -0,0 +1,379 @@
+//! Structural validation of graph tiles.
+//!
+//! Decoding a tile only checks that the sections add up to the right size.
+//! The references between sections (edge ranges, end nodes, text offsets, etc.)
+//! are trusted at lookup time, and a corrupt tile can cause panics or garbage results
+//! long after it was loaded.
+//! Validation checks these invariants up front so that bad extracts can be caught early.
+
+use super::{EdgeInfo, GraphTileView};
+use crate::{BIN_COUNT, GraphId};
+use std::collections::HashSet;
+use thiserror::Error;
+
+/// A single broken invariant found while validating a tile.
+///
+/// Indices refer to positions within the tile's own lists.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ValidationIssue {
+    #[error(
+        "Node {node_index} has edges {edge_index}..{edge_index}+{edge_count}, but the tile only has {directed_edge_count} directed edges."
+    )]
+    NodeEdgesOutOfBounds {
+        node_index: usize,
+        edge_index: u32,
+        edge_count: u8,
+        directed_edge_count: usize,
+    },
+    #[error(
+        "Node {node_index} has transitions {transition_index}..{transition_index}+{transition_count}, but the tile only has {total_transition_count} transitions."
+    )]
+    NodeTransitionsOutOfBounds {
+        node_index: usize,
+        transition_index: u32,
+        transition_count: u8,
+        total_transition_count: usize,
+    },
+    #[error("Node {node_index} references admin {admin_index}, which does not exist.")]
+    NodeAdminOutOfBounds { node_index: usize, admin_index: u16 },
+    #[error("Directed edge {edge_index} ends at node {end_node_id}, which does not exist.")]
+    EndNodeOutOfBounds {
+        edge_index: usize,
+        end_node_id: GraphId,
+    },
+    #[error(
+        "Directed edge {edge_index} has opposing edge index {opposing_edge_index}, but its end node only has {edge_count} edges."
+    )]
+    OpposingEdgeOutOfBounds {
+        edge_index: usize,
+        opposing_edge_index: u32,
+        edge_count: u8,
+    },
+    #[error(
+        "Directed edge {edge_index} has edge info offset {offset}, which is past the end of the edge info ({size} bytes)."
+    )]
+    EdgeInfoOutOfBounds {
+        edge_index: usize,
+        offset: u32,
+        size: usize,
+    },
+    #[error("Directed edge {edge_index} has edge info which can't be decoded: {description}")]
+    InvalidEdgeInfo {
+        edge_index: usize,
+        description: String,
+    },
+    #[error("The text offset {offset} for {field} {index} is past the end of the text list.")]
+    TextOffsetOutOfBounds {
+        field: &'static str,
+        index: usize,
+        offset: u32,
+    },
+    #[error("The text at offset {offset} for {field} {index} is not null-terminated.")]
+    UnterminatedText {
+        field: &'static str,
+        index: usize,
+        offset: u32,
+    },
+    #[error(
+        "Access restriction {index} is out of order (the list must be sorted by directed edge index)."
+    )]
+    UnsortedAccessRestrictions { index: usize },
+    #[error("{field} {index} references directed edge {edge_index}, which does not exist.")]
+    DirectedEdgeOutOfBounds {
+        field: &'static str,
+        index: usize,
+        edge_index: u32,
+    },
+    #[error(
+        "Edge bin {bin_index} spans {start}..{end}, but there are only {len} edge bin entries."
+    )]
+    EdgeBinOutOfBounds {
+        bin_index: usize,
+        start: usize,
+        end: usize,
+        len: usize,
+    },
+}
+
+/// The result of validating a graph tile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// The base ID of the validated tile.
+    pub graph_id: GraphId,
+    /// Every issue found, in the order they were checked.
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Returns true if no issues were found.
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl GraphTileView<'_> {
+    /// Checks the internal invariants of the tile.
+    ///
+    /// References which point into other tiles (e.g. end nodes of edges which leave the tile)
+    /// cannot be checked without loading those tiles, so they are skipped.
+    pub(crate) fn validate(&self) -> ValidationReport {
+        let mut issues = Vec::new();
+
+        self.validate_nodes(&mut issues);
+        self.validate_directed_edges(&mut issues);
+        self.validate_edge_info(&mut issues);
+        self.validate_restrictions_and_lanes(&mut issues);
+        self.validate_text(&mut issues);
+        self.validate_edge_bins(&mut issues);
+
+        ValidationReport {
+            graph_id: self.header.graph_id(),
+            issues,
+        }
+    }
+
+    fn validate_nodes(&self, issues: &mut Vec<ValidationIssue>) {
+        for (node_index, node) in self.nodes.iter().enumerate() {
+            let edge_end = node.edge_index() as usize + usize::from(node.edge_count());
+            if edge_end > self.directed_edges.len() {
+                issues.push(ValidationIssue::NodeEdgesOutOfBounds {
+                    node_index,
+                    edge_index: node.edge_index(),
+                    edge_count: node.edge_count(),
+                    directed_edge_count: self.directed_edges.len(),
+                });
+            }
+
+            let transition_end =
+                node.transition_index() as usize + usize::from(node.transition_count());
+            if transition_end > self.transitions.len() {
+                issues.push(ValidationIssue::NodeTransitionsOutOfBounds {
+                    node_index,
+                    transition_index: node.transition_index(),
+                    transition_count: node.transition_count(),
+                    total_transition_count: self.transitions.len(),
+                });
+            }
+
+            if usize::from(node.admin_index()) >= self.admins.len() {
+                issues.push(ValidationIssue::NodeAdminOutOfBounds {
+                    node_index,
+                    admin_index: node.admin_index(),
+                });
+            }
+        }
+    }
+
+    fn validate_directed_edges(&self, issues: &mut Vec<ValidationIssue>) {
+        let base_id = self.header.graph_id().tile_base_id();
+        for (edge_index, edge) in self.directed_edges.iter().enumerate() {
+            let end_node_id = edge.end_node_id();
+            // Edges which leave the tile can only be checked against the neighboring tile
+            if end_node_id.tile_base_id() != base_id {
+                continue;
+            }
+
+            let end_node = usize::try_from(end_node_id.feature_index())
+                .ok()
+                .and_then(|index| self.nodes.get(index));
+            let Some(end_node) = end_node else {
+                issues.push(ValidationIssue::EndNodeOutOfBounds {
+                    edge_index,
+                    end_node_id,
+                });
+                continue;
+            };
+
+            if edge.opposing_edge_index() >= u32::from(end_node.edge_count()) {
+                issues.push(ValidationIssue::OpposingEdgeOutOfBounds {
+                    edge_index,
+                    opposing_edge_index: edge.opposing_edge_index(),
+                    edge_count: end_node.edge_count(),
+                });
+            }
+        }
+    }
+
+    fn validate_edge_info(&self, issues: &mut Vec<ValidationIssue>) {
+        // Opposing edges share their edge info, so each one only needs to be checked once
+        let mut seen_offsets = HashSet::new();
+        for (edge_index, edge) in self.directed_edges.iter().enumerate() {
+            let offset = edge.edge_info_offset();
+            if !seen_offsets.insert(offset) {
+                continue;
+            }
+
+            let Some(bytes) = self.edge_info_memory.get(offset as usize..) else {
+                issues.push(ValidationIssue::EdgeInfoOutOfBounds {
+                    edge_index,
+                    offset,
+                    size: self.edge_info_memory.len(),
+                });
+                continue;
+            };
+
+            let edge_info = match EdgeInfo::try_from((bytes, self.text_memory)) {
+                Ok(edge_info) => edge_info,
+                Err(e) => {
+                    issues.push(ValidationIssue::InvalidEdgeInfo {
+                        edge_index,
+                        description: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            if let Err(e) = edge_info.decode_raw_shape::<f64>() {
+                issues.push(ValidationIssue::InvalidEdgeInfo {
+                    edge_index,
+                    description: format!("Invalid shape: {e}"),
+                });
+            }
+
+            for name_offset in edge_info.name_offsets() {
+                self.check_text(
+                    issues,
+                    "edge name of directed edge",
+                    edge_index,
+                    name_offset,
+                );
+            }
+        }
+    }
+
+    fn validate_restrictions_and_lanes(&self, issues: &mut Vec<ValidationIssue>) {
+        let edge_count = self.directed_edges.len();
+
+        // Lookups use a binary search, which silently misses restrictions if this isn't sorted
+        for (index, pair) in self.access_restrictions.windows(2).enumerate() {
+            if pair[0].edge_index() > pair[1].edge_index() {
+                issues.push(ValidationIssue::UnsortedAccessRestrictions { index: index + 1 });
+            }
+        }
+        for (index, restriction) in self.access_restrictions.iter().enumerate() {
+            if restriction.edge_index() as usize >= edge_count {
+                issues.push(ValidationIssue::DirectedEdgeOutOfBounds {
+                    field: "Access restriction",
+                    index,
+                    edge_index: restriction.edge_index(),
+                });
+            }
+        }
+
+        for (index, turn_lane) in self.turn_lanes.iter().enumerate() {
+            if turn_lane.directed_edge_index() as usize >= edge_count {
+                issues.push(ValidationIssue::DirectedEdgeOutOfBounds {
+                    field: "Turn lane",
+                    index,
+                    edge_index: turn_lane.directed_edge_index(),
+                });
+            }
+        }
+    }
+
+    fn validate_text(&self, issues: &mut Vec<ValidationIssue>) {
+        for (index, sign) in self.signs.iter().enumerate() {
+            self.check_text(issues, "sign", index, sign.text_offset.get());
+        }
+        for (index, turn_lane) in self.turn_lanes.iter().enumerate() {
+            self.check_text(issues, "turn lane", index, turn_lane.text_offset.get());
+        }
+        for (index, admin) in self.admins.iter().enumerate() {
+            self.check_text(
+                issues,
+                "country name of admin",
+                index,
+                admin.country_name_offset.get(),
+            );
+            self.check_text(
+                issues,
+                "principal subdivision name of admin",
+                index,
+                admin.principal_subdivision_offset.get(),
+            );
+        }
+    }
+
+    fn check_text(
+        &self,
+        issues: &mut Vec<ValidationIssue>,
+        field: &'static str,
+        index: usize,
+        offset: u32,
+    ) {
+        match self.text_memory.get(offset as usize..) {
+            None => issues.push(ValidationIssue::TextOffsetOutOfBounds {
+                field,
+                index,
+                offset,
+            }),
+            Some(text) if !text.contains(&0) => issues.push(ValidationIssue::UnterminatedText {
+                field,
+                index,
+                offset,
+            }),
+            Some(_) => {}
+        }
+    }
+
+    fn validate_edge_bins(&self, issues: &mut Vec<ValidationIssue>) {
+        // Only level 2 tiles have edge bins
+        if self.edge_bins.is_empty() {
+            return;
+        }
+
+        for bin_index in 0..BIN_COUNT {
+            let (start, end) = self.header.edge_bin_offsets(bin_index);
+            if start > end || end > self.edge_bins.len() {
+                issues.push(ValidationIssue::EdgeBinOutOfBounds {
+                    bin_index,
+                    start,
+                    end,
+                    len: self.edge_bins.len(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ValidationIssue;
+    use crate::graph_tile::{GraphTile, GraphTileView, TEST_GRAPH_TILE_L0, TEST_GRAPH_TILE_L2};
+
+    #[test]
+    fn test_fixtures_are_valid() {
+        for tile in [&*TEST_GRAPH_TILE_L0, &*TEST_GRAPH_TILE_L2] {
+            let report = tile.validate();
+            assert_eq!(report.graph_id, tile.graph_id());
+            assert_eq!(report.issues, []);
+            assert!(report.is_valid());
+        }
+    }
+
+    #[test]
+    fn test_detects_broken_references() {
+        let tile = TEST_GRAPH_TILE_L2.borrow_dependent();
+        let view = GraphTileView {
+            // Drop the last node, and all but the first byte of the text list
+            nodes: &tile.nodes[..tile.nodes.len() - 1],
+            text_memory: &tile.text_memory[..1],
+            predicted_speeds: None,
+            ..*tile
+        };
+
+        let report = view.validate();
+        assert!(!report.is_valid());
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            ValidationIssue::EndNodeOutOfBounds { end_node_id, .. }
+                if end_node_id.feature_index() == tile.nodes.len() as u64 - 1
+        )));
+        assert!(
+            report
+                .issues
+                .iter()
+                .any(|issue| matches!(issue, ValidationIssue::TextOffsetOutOfBounds { .. }))
+        );
+    }
+}
@@ -1,7 +1,9 @@
-#![doc = include_str!("../README.md")]
+// The README example reads tiles from a directory
+#![cfg_attr(feature = "fs", doc = include_str!("../README.md"))]
 
 mod graph_id;
 pub mod graph_tile;
+pub mod route_diff;
 pub mod shape_codec;
 pub mod spatial;
 pub mod tile_hierarchy;
@@ -19,7 +21,9 @@ use serde::{Deserialize, Serialize};
 // The implementations are sufficiently complex that we want to have lots of files,
 // But many of those only have one or two useful definitions to re-export,
 // so this flattens things for better ergonomics.
-pub use graph_id::GraphId;
+#[cfg(feature = "serde")]
+pub use graph_id::hierarchical_graph_id;
+pub use graph_id::{GraphId, GraphIdListError, GraphIdParseError, InvalidGraphIdError};
 
 /// Road class; broad hierarchies of relative (and sometimes locally specific) importance.
 ///
@@ -28,7 +32,7 @@ pub use graph_id::GraphId;
 /// - For estimating speeds when better data is not available
 /// - To determine preference / avoidance for roads
 #[repr(u8)]
-#[derive(TryFromBytes, Debug, Eq, PartialEq, PartialOrd)]
+#[derive(TryFromBytes, Debug, Clone, Copy, Eq, PartialEq, PartialOrd)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub enum RoadClass {
     Motorway,
@@ -111,7 +115,7 @@ impl Surface {
 }
 
 /// Sub-categorization of roads based on specialized usage.
-#[derive(TryFromBytes, Debug, Eq, PartialEq)]
+#[derive(TryFromBytes, Debug, Clone, Copy, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[repr(u8)]
 pub enum RoadUse {
@@ -321,6 +325,36 @@ impl BicycleNetwork {
     }
 }
 
+/// The type of cycle lane (if any) along an edge, ordered from least to most separated.
+#[repr(u8)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub enum CycleLane {
+    /// No specific cycle lane.
+    None,
+    /// Shared with other traffic (e.g. marked with sharrows).
+    Shared,
+    /// A dedicated (painted) lane.
+    Dedicated,
+    /// A lane which is physically separated from other traffic.
+    Separated,
+}
+
+impl CycleLane {
+    const fn into_bits(self) -> u8 {
+        self as _
+    }
+    const fn from_bits(value: u8) -> Self {
+        // Only 2 bits are allocated in the tile, so every value is valid
+        match value & 0b11 {
+            0 => Self::None,
+            1 => Self::Shared,
+            2 => Self::Dedicated,
+            _ => Self::Separated,
+        }
+    }
+}
+
 /// The number of subdivisions in each graph tile for edge binning.
 ///
 /// This is a fixed value.
@@ -0,0 +1,348 @@
+//! Comparison of two routes over the same graph.
+//!
+//! This is useful for A/B testing costing changes
+//! (how much did the route actually change, and where?),
+//! and for filtering alternate routes which are too similar to the primary route.
+
+use crate::GraphId;
+use std::collections::HashMap;
+
+/// A directed edge traversed by a route.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RouteEdge {
+    pub edge_id: GraphId,
+    /// The length of the traversed portion of the edge, in meters.
+    pub length: f64,
+    /// The time taken to traverse the edge (including any transition costs), in seconds.
+    pub duration: f64,
+}
+
+/// A stretch where the two routes take different paths.
+///
+/// Indices refer to positions in the edge sequences passed to [`diff_routes`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    /// The indices of the last shared edge before the routes split,
+    /// as `(index in a, index in b)`.
+    ///
+    /// This is `None` when the routes differ from the very first edge.
+    pub diverge_after: Option<(usize, usize)>,
+    /// The indices of the first shared edge after the routes come back together,
+    /// as `(index in a, index in b)`.
+    ///
+    /// This is `None` when the routes never rejoin (e.g. they end on different edges).
+    pub rejoin_at: Option<(usize, usize)>,
+    /// The length of route `a` between the divergence and rejoin points, in meters.
+    pub length_a: f64,
+    /// The length of route `b` between the divergence and rejoin points, in meters.
+    pub length_b: f64,
+    /// The time spent on route `a` between the divergence and rejoin points, in seconds.
+    pub duration_a: f64,
+    /// The time spent on route `b` between the divergence and rejoin points, in seconds.
+    pub duration_b: f64,
+}
+
+impl Divergence {
+    /// The extra time route `b` spends on this stretch compared to route `a`, in seconds.
+    ///
+    /// Negative values mean that route `b` is faster.
+    pub fn duration_delta(&self) -> f64 {
+        self.duration_b - self.duration_a
+    }
+}
+
+/// A summary of the differences between two routes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteDiff {
+    /// The total length of route `a`, in meters.
+    pub length_a: f64,
+    /// The total length of route `b`, in meters.
+    pub length_b: f64,
+    /// The total duration of route `a`, in seconds.
+    pub duration_a: f64,
+    /// The total duration of route `b`, in seconds.
+    pub duration_b: f64,
+    /// The length of the edges which both routes traverse in the same order, in meters.
+    ///
+    /// Lengths are taken from route `a`.
+    pub shared_length: f64,
+    /// Every stretch where the routes differ, in route order.
+    pub divergences: Vec<Divergence>,
+}
+
+impl RouteDiff {
+    /// Returns true if both routes traverse exactly the same edges.
+    pub fn is_identical(&self) -> bool {
+        self.divergences.is_empty()
+    }
+
+    /// The fraction of route `a` (by length) which is shared with route `b`.
+    ///
+    /// This is in the range `[0, 1]`; empty routes are considered fully shared.
+    pub fn shared_fraction_of_a(&self) -> f64 {
+        shared_fraction(self.shared_length, self.length_a)
+    }
+
+    /// The fraction of route `b` (by length) which is shared with route `a`.
+    ///
+    /// This is in the range `[0, 1]`; empty routes are considered fully shared.
+    pub fn shared_fraction_of_b(&self) -> f64 {
+        shared_fraction(self.shared_length, self.length_b)
+    }
+
+    /// The change in total travel time from route `a` to route `b`, in seconds.
+    ///
+    /// Negative values mean that route `b` is faster.
+    pub fn duration_delta(&self) -> f64 {
+        self.duration_b - self.duration_a
+    }
+
+    /// The change in total length from route `a` to route `b`, in meters.
+    pub fn length_delta(&self) -> f64 {
+        self.length_b - self.length_a
+    }
+}
+
+/// Finds the pairs of indexes `(index_a, index_b)` of a longest common subsequence of edge IDs.
+fn longest_common_subsequence(a: &[RouteEdge], b: &[RouteEdge]) -> Vec<(usize, usize)> {
+    // Positions of each edge in route b
+    let mut positions_in_b: HashMap<GraphId, Vec<usize>> = HashMap::new();
+    for (index, edge) in b.iter().enumerate() {
+        positions_in_b.entry(edge.edge_id).or_default().push(index);
+    }
+
+    // Every pair of equal edges, in order of a.
+    // The positions in b are reversed so that no chain can use the same edge of a twice.
+    let pairs: Vec<(usize, usize)> = a
+        .iter()
+        .enumerate()
+        .filter_map(|(index_a, edge)| Some((index_a, positions_in_b.get(&edge.edge_id)?)))
+        .flat_map(|(index_a, positions)| {
+            positions
+                .iter()
+                .rev()
+                .map(move |&index_b| (index_a, index_b))
+        })
+        .collect();
+
+    // The common subsequence is the longest chain of pairs with strictly increasing b indexes.
+    // chain_ends[k] is the pair ending the best chain of length k + 1 found so far
+    // (the one with the lowest b index).
+    let mut chain_ends: Vec<usize> = Vec::new();
+    let mut predecessors: Vec<Option<usize>> = Vec::with_capacity(pairs.len());
+    for (pair, &(_, index_b)) in pairs.iter().enumerate() {
+        let length = chain_ends.partition_point(|&end| pairs[end].1 < index_b);
+        predecessors.push(length.checked_sub(1).map(|previous| chain_ends[previous]));
+        if length == chain_ends.len() {
+            chain_ends.push(pair);
+        } else {
+            chain_ends[length] = pair;
+        }
+    }
+
+    let mut matches = Vec::with_capacity(chain_ends.len());
+    let mut next = chain_ends.last().copied();
+    while let Some(pair) = next {
+        matches.push(pairs[pair]);
+        next = predecessors[pair];
+    }
+    matches.reverse();
+    matches
+}
+
+fn shared_fraction(shared_length: f64, total_length: f64) -> f64 {
+    if total_length > 0.0 {
+        (shared_length / total_length).clamp(0.0, 1.0)
+    } else {
+        1.0
+    }
+}
+
+/// Compares two routes, reporting where they diverge and how much they share.
+///
+/// The routes are aligned by edge ID in order,
+/// so an edge only counts as shared if both routes reach it in the same sequence
+/// (a route which loops back over an earlier edge of the other route does not "rejoin" it).
+/// The alignment is a longest common subsequence of edges,
+/// found with the Hunt-Szymanski algorithm.
+/// This takes `O((n + r) log n)` time, where `r` is the number of pairs of equal edges,
+/// so it stays close to linear for routes which rarely repeat edges.
+pub fn diff_routes(a: &[RouteEdge], b: &[RouteEdge]) -> RouteDiff {
+    let matches = longest_common_subsequence(a, b);
+
+    let sum = |edges: &[RouteEdge]| -> (f64, f64) {
+        edges.iter().fold((0.0, 0.0), |(length, duration), edge| {
+            (length + edge.length, duration + edge.duration)
+        })
+    };
+
+    let mut divergences = Vec::new();
+    let mut previous: Option<(usize, usize)> = None;
+    for next in matches.iter().copied().map(Some).chain([None]) {
+        // The half-open ranges strictly between the previous and next matches
+        let start = previous.map_or((0, 0), |(i, j)| (i + 1, j + 1));
+        let end = next.unwrap_or((a.len(), b.len()));
+        if start == end {
+            previous = next;
+            continue;
+        }
+
+        let (length_a, duration_a) = sum(&a[start.0..end.0]);
+        let (length_b, duration_b) = sum(&b[start.1..end.1]);
+        divergences.push(Divergence {
+            diverge_after: previous,
+            rejoin_at: next,
+            length_a,
+            length_b,
+            duration_a,
+            duration_b,
+        });
+        previous = next;
+    }
+
+    let (length_a, duration_a) = sum(a);
+    let (length_b, duration_b) = sum(b);
+    let shared_length = matches.iter().map(|&(index_a, _)| a[index_a].length).sum();
+
+    RouteDiff {
+        length_a,
+        length_b,
+        duration_a,
+        duration_b,
+        shared_length,
+        divergences,
+    }
+}
+
+#[cfg(test)]
+// All of the lengths and durations in these tests are small integers,
+// so the sums are exact.
+#[expect(clippy::float_cmp)]
+mod test {
+    use super::{RouteEdge, diff_routes};
+    use crate::GraphId;
+
+    fn route(edges: &[(u64, f64, f64)]) -> Vec<RouteEdge> {
+        edges
+            .iter()
+            .map(|&(index, length, duration)| RouteEdge {
+                edge_id: GraphId::try_from_components(2, 762_485, index).unwrap(),
+                length,
+                duration,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn identical_routes() {
+        let a = route(&[(1, 100.0, 10.0), (2, 200.0, 20.0)]);
+        let diff = diff_routes(&a, &a);
+
+        assert!(diff.is_identical());
+        assert_eq!(diff.shared_length, 300.0);
+        assert_eq!(diff.shared_fraction_of_a(), 1.0);
+        assert_eq!(diff.shared_fraction_of_b(), 1.0);
+        assert_eq!(diff.duration_delta(), 0.0);
+    }
+
+    #[test]
+    fn detour_in_the_middle() {
+        let a = route(&[
+            (1, 100.0, 10.0),
+            (2, 200.0, 20.0),
+            (3, 300.0, 30.0),
+            (4, 100.0, 10.0),
+        ]);
+        let b = route(&[
+            (1, 100.0, 10.0),
+            (5, 250.0, 15.0),
+            (6, 250.0, 15.0),
+            (7, 100.0, 5.0),
+            (4, 100.0, 10.0),
+        ]);
+        let diff = diff_routes(&a, &b);
+
+        assert_eq!(diff.divergences.len(), 1);
+        let divergence = &diff.divergences[0];
+        assert_eq!(divergence.diverge_after, Some((0, 0)));
+        assert_eq!(divergence.rejoin_at, Some((3, 4)));
+        assert_eq!(divergence.length_a, 500.0);
+        assert_eq!(divergence.length_b, 600.0);
+        assert_eq!(divergence.duration_delta(), -15.0);
+
+        assert_eq!(diff.shared_length, 200.0);
+        assert_eq!(diff.shared_fraction_of_a(), 200.0 / 700.0);
+        assert_eq!(diff.shared_fraction_of_b(), 200.0 / 800.0);
+        assert_eq!(diff.duration_delta(), -15.0);
+        assert_eq!(diff.length_delta(), 100.0);
+    }
+
+    #[test]
+    fn different_start_and_end() {
+        let a = route(&[(1, 100.0, 10.0), (2, 200.0, 20.0), (3, 100.0, 10.0)]);
+        let b = route(&[(4, 150.0, 10.0), (2, 200.0, 20.0), (5, 50.0, 5.0)]);
+        let diff = diff_routes(&a, &b);
+
+        assert_eq!(diff.divergences.len(), 2);
+        assert_eq!(diff.divergences[0].diverge_after, None);
+        assert_eq!(diff.divergences[0].rejoin_at, Some((1, 1)));
+        assert_eq!(diff.divergences[1].diverge_after, Some((1, 1)));
+        assert_eq!(diff.divergences[1].rejoin_at, None);
+        assert_eq!(diff.divergences[1].duration_delta(), -5.0);
+        assert_eq!(diff.shared_length, 200.0);
+    }
+
+    #[test]
+    fn moved_edge() {
+        // Greedily matching edge 9 first would hide the three edges the routes share
+        let a = route(&[
+            (9, 50.0, 5.0),
+            (1, 100.0, 10.0),
+            (2, 200.0, 20.0),
+            (3, 300.0, 30.0),
+        ]);
+        let b = route(&[
+            (1, 100.0, 10.0),
+            (2, 200.0, 20.0),
+            (3, 300.0, 30.0),
+            (9, 50.0, 5.0),
+        ]);
+        let diff = diff_routes(&a, &b);
+
+        assert_eq!(diff.shared_length, 600.0);
+        assert_eq!(diff.divergences.len(), 2);
+        assert_eq!(diff.divergences[0].diverge_after, None);
+        assert_eq!(diff.divergences[0].rejoin_at, Some((1, 0)));
+        assert_eq!(diff.divergences[0].length_a, 50.0);
+        assert_eq!(diff.divergences[1].diverge_after, Some((3, 2)));
+        assert_eq!(diff.divergences[1].rejoin_at, None);
+        assert_eq!(diff.divergences[1].length_b, 50.0);
+    }
+
+    #[test]
+    fn repeated_edges() {
+        let a = route(&[(1, 100.0, 10.0), (2, 100.0, 10.0), (1, 100.0, 10.0)]);
+        let b = route(&[(1, 100.0, 10.0), (1, 100.0, 10.0)]);
+        let diff = diff_routes(&a, &b);
+
+        assert_eq!(diff.shared_length, 200.0);
+        assert_eq!(diff.divergences.len(), 1);
+        assert_eq!(diff.divergences[0].diverge_after, Some((0, 0)));
+        assert_eq!(diff.divergences[0].rejoin_at, Some((2, 1)));
+    }
+
+    #[test]
+    fn disjoint_and_empty_routes() {
+        let a = route(&[(1, 100.0, 10.0)]);
+        let b = route(&[(2, 100.0, 12.0)]);
+        let diff = diff_routes(&a, &b);
+        assert_eq!(diff.divergences.len(), 1);
+        assert_eq!(diff.divergences[0].diverge_after, None);
+        assert_eq!(diff.divergences[0].rejoin_at, None);
+        assert_eq!(diff.shared_fraction_of_a(), 0.0);
+
+        let diff = diff_routes(&[], &[]);
+        assert!(diff.is_identical());
+        assert_eq!(diff.shared_fraction_of_a(), 1.0);
+    }
+}
@@ -5,12 +5,18 @@
 //!
 //! See Google's [protobuf docs](https://protobuf.dev/programming-guides/encoding/)
 //! for info on varint encoding generally.
+//!
+//! Shapes in API responses are instead encoded as
+//! [Google polylines](https://developers.google.com/maps/documentation/utilities/polylinealgorithm),
+//! typically with 6 digits of precision (polyline6), or 5 for compatibility with other tools.
 
 use geo::{Coord, CoordFloat, coord};
-use integer_encoding::VarIntReader;
+use integer_encoding::{VarIntReader, VarIntWriter};
 use num_traits::FromPrimitive;
+use thiserror::Error;
 
 const DECODE_PRECISION: f64 = 1e-6;
+const ENCODE_PRECISION: f64 = 1e6;
 
 /// Decodes a Valhalla encoded shape from a byte buffer of exact size.
 ///
@@ -62,3 +68,314 @@ pub fn decode_first_coordinate<T: CoordFloat + FromPrimitive>(
         y: T::from(lat).expect("Conversion from i32 to float should not fail") * prec,
     })
 }
+
+/// Encodes a shape using Valhalla's varint delta encoding.
+///
+/// This is the inverse of [`decode_shape`].
+/// Coordinates are rounded to 6 decimal places (roughly 10cm),
+/// which is the precision Valhalla stores in tiles.
+///
+/// # Panics
+///
+/// Coordinates must be valid longitudes and latitudes (in degrees).
+/// This panics on non-finite values.
+pub fn encode_shape<T: CoordFloat>(coords: &[Coord<T>]) -> Vec<u8> {
+    // Most deltas will fit in 2-3 bytes, so this usually avoids reallocation.
+    let mut bytes = Vec::with_capacity(coords.len() * 6);
+
+    let to_fixed = |value: T| {
+        let value = value
+            .to_f64()
+            .expect("Conversion from float to f64 should not fail");
+        assert!(value.is_finite(), "Coordinates must be finite");
+        // Valid coordinates are at most 180 * 1e6 in magnitude, so this cannot truncate
+        #[expect(clippy::cast_possible_truncation)]
+        let fixed = (value * ENCODE_PRECISION).round() as i32;
+        fixed
+    };
+
+    let mut prev_lat = 0;
+    let mut prev_lon = 0;
+    for coord in coords {
+        let lat = to_fixed(coord.y);
+        let lon = to_fixed(coord.x);
+        // Writing to a Vec can't fail
+        bytes
+            .write_varint(lat - prev_lat)
+            .expect("Writing to a Vec should not fail");
+        bytes
+            .write_varint(lon - prev_lon)
+            .expect("Writing to a Vec should not fail");
+        prev_lat = lat;
+        prev_lon = lon;
+    }
+
+    bytes
+}
+
+/// An error decoding a Google polyline string.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PolylineDecodeError {
+    #[error("Invalid character in polyline at byte {0}")]
+    InvalidCharacter(usize),
+    #[error("The polyline ended in the middle of a value")]
+    Truncated,
+    #[error("The polyline contains a value which is too large")]
+    Overflow,
+}
+
+/// Encodes coordinates as a Google polyline string with the given number of decimal digits
+/// (5 for polyline5, 6 for polyline6).
+///
+/// # Panics
+///
+/// Coordinates must be valid longitudes and latitudes (in degrees).
+/// This panics on non-finite values.
+pub fn encode_polyline<T: CoordFloat>(coords: &[Coord<T>], precision: u8) -> String {
+    let factor = 10_f64.powi(i32::from(precision));
+    let to_fixed = |value: T| {
+        let value = value
+            .to_f64()
+            .expect("Conversion from float to f64 should not fail");
+        assert!(value.is_finite(), "Coordinates must be finite");
+        // Valid coordinates are small enough that this cannot truncate for sane precisions
+        #[expect(clippy::cast_possible_truncation)]
+        let fixed = (value * factor).round() as i64;
+        fixed
+    };
+
+    let mut out = String::with_capacity(coords.len() * 8);
+    let mut prev_lat = 0;
+    let mut prev_lon = 0;
+    for coord in coords {
+        let lat = to_fixed(coord.y);
+        let lon = to_fixed(coord.x);
+        push_polyline_value(&mut out, lat - prev_lat);
+        push_polyline_value(&mut out, lon - prev_lon);
+        prev_lat = lat;
+        prev_lon = lon;
+    }
+
+    out
+}
+
+// Every pushed value is at most 0x3f + 63, which is a printable ASCII character
+#[expect(clippy::cast_possible_truncation)]
+fn push_polyline_value(out: &mut String, value: i64) {
+    // Zigzag encoding, so that small negative values stay small
+    #[expect(clippy::cast_sign_loss)]
+    let mut value = ((value << 1) ^ (value >> 63)) as u64;
+    while value >= 0x20 {
+        out.push(char::from(((value & 0x1f) | 0x20) as u8 + 63));
+        value >>= 5;
+    }
+    out.push(char::from(value as u8 + 63));
+}
+
+/// Decodes a Google polyline string with the given number of decimal digits
+/// (5 for polyline5, 6 for polyline6).
+///
+/// # Errors
+///
+/// Fails if the string contains characters outside the polyline alphabet,
+/// ends partway through a coordinate,
+/// or accumulates a coordinate too large for a 64-bit integer.
+///
+/// # Panics
+///
+/// This only panics if `T` cannot represent the coordinates,
+/// which is never the case for `f32` or `f64`.
+pub fn decode_polyline<T: CoordFloat + FromPrimitive>(
+    polyline: &str,
+    precision: u8,
+) -> Result<Vec<Coord<T>>, PolylineDecodeError> {
+    let factor = 10_f64.powi(i32::from(precision));
+    let bytes = polyline.as_bytes();
+    let mut coords = Vec::with_capacity(bytes.len() / 8);
+
+    let mut index = 0;
+    let mut lat = 0_i64;
+    let mut lon = 0_i64;
+    while index < bytes.len() {
+        lat = lat
+            .checked_add(next_polyline_value(bytes, &mut index)?)
+            .ok_or(PolylineDecodeError::Overflow)?;
+        if index >= bytes.len() {
+            return Err(PolylineDecodeError::Truncated);
+        }
+        lon = lon
+            .checked_add(next_polyline_value(bytes, &mut index)?)
+            .ok_or(PolylineDecodeError::Overflow)?;
+
+        // Values are bounded by the 64-bit accumulator; precision loss here is irrelevant
+        #[expect(clippy::cast_precision_loss)]
+        coords.push(coord! {
+            x: T::from_f64(lon as f64 / factor).expect("Conversion from f64 to float should not fail"),
+            y: T::from_f64(lat as f64 / factor).expect("Conversion from f64 to float should not fail"),
+        });
+    }
+
+    Ok(coords)
+}
+
+fn next_polyline_value(bytes: &[u8], index: &mut usize) -> Result<i64, PolylineDecodeError> {
+    let mut result = 0_u64;
+    let mut shift = 0;
+    loop {
+        let Some(&byte) = bytes.get(*index) else {
+            return Err(PolylineDecodeError::Truncated);
+        };
+        if !(63..=126).contains(&byte) {
+            return Err(PolylineDecodeError::InvalidCharacter(*index));
+        }
+        if shift > 60 {
+            return Err(PolylineDecodeError::Overflow);
+        }
+
+        let chunk = u64::from(byte - 63);
+        result |= (chunk & 0x1f) << shift;
+        shift += 5;
+        *index += 1;
+        if chunk < 0x20 {
+            break;
+        }
+    }
+
+    // Undo the zigzag encoding
+    #[expect(clippy::cast_possible_wrap)]
+    Ok(((result >> 1) as i64) ^ -((result & 1) as i64))
+}
+
+/// Converts a Valhalla encoded shape (as stored in tiles) directly into a polyline string.
+///
+/// # Errors
+///
+/// See [`decode_shape`] for a description of possible errors.
+pub fn shape_to_polyline(bytes: &[u8], precision: u8) -> std::io::Result<String> {
+    Ok(encode_polyline(&decode_shape::<f64>(bytes)?, precision))
+}
+
+/// Converts a polyline string into a Valhalla encoded shape (as stored in tiles).
+///
+/// # Errors
+///
+/// See [`decode_polyline`] for a description of possible errors.
+pub fn polyline_to_shape(polyline: &str, precision: u8) -> Result<Vec<u8>, PolylineDecodeError> {
+    Ok(encode_shape(&decode_polyline::<f64>(polyline, precision)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "fs")]
+    use crate::{
+        GraphId,
+        graph_tile::GraphTile,
+        tile_provider::{DirectoryGraphTileProvider, OwnedGraphTileProvider},
+    };
+    #[cfg(feature = "fs")]
+    use std::{num::NonZeroUsize, path::PathBuf};
+
+    #[test]
+    fn round_trip_small_shape() {
+        let coords = [
+            coord! {x: 1.521_234, y: 42.505_678},
+            coord! {x: 1.521_3, y: 42.505_6},
+            coord! {x: -179.999_999, y: -89.999_999},
+        ];
+        let bytes = encode_shape(&coords);
+        let decoded = decode_shape::<f64>(&bytes).unwrap();
+        assert_eq!(decoded.len(), coords.len());
+        for (actual, expected) in decoded.iter().zip(&coords) {
+            assert!((actual.x - expected.x).abs() < 1e-9);
+            assert!((actual.y - expected.y).abs() < 1e-9);
+        }
+        assert_eq!(decode_first_coordinate::<f64>(&bytes).unwrap(), decoded[0]);
+
+        assert!(encode_shape::<f64>(&[]).is_empty());
+    }
+
+    #[test]
+    fn polyline_reference() {
+        // The example from Google's polyline algorithm docs
+        let coords = [
+            coord! {x: -120.2, y: 38.5},
+            coord! {x: -120.95, y: 40.7},
+            coord! {x: -126.453, y: 43.252},
+        ];
+        let polyline = "_p~iF~ps|U_ulLnnqC_mqNvxq`@";
+        assert_eq!(encode_polyline(&coords, 5), polyline);
+
+        let decoded = decode_polyline::<f64>(polyline, 5).unwrap();
+        assert_eq!(decoded.len(), coords.len());
+        for (actual, expected) in decoded.iter().zip(&coords) {
+            assert!((actual.x - expected.x).abs() < 1e-9);
+            assert!((actual.y - expected.y).abs() < 1e-9);
+        }
+
+        // The same coordinates round trip through polyline6 as well
+        let polyline6 = encode_polyline(&coords, 6);
+        assert_eq!(decode_polyline::<f64>(&polyline6, 6).unwrap(), decoded);
+    }
+
+    #[test]
+    fn polyline_errors() {
+        assert_eq!(decode_polyline::<f64>("", 6), Ok(vec![]));
+        assert_eq!(
+            decode_polyline::<f64>("_p~iF", 5),
+            Err(PolylineDecodeError::Truncated)
+        );
+        assert_eq!(
+            decode_polyline::<f64>("_p~i", 5),
+            Err(PolylineDecodeError::Truncated)
+        );
+        assert_eq!(
+            decode_polyline::<f64>("_p~iF ps|U", 5),
+            Err(PolylineDecodeError::InvalidCharacter(5))
+        );
+        assert_eq!(
+            decode_polyline::<f64>("~~~~~~~~~~~~~~~~~", 5),
+            Err(PolylineDecodeError::Overflow)
+        );
+
+        // Each delta fits, but their sum doesn't
+        let mut polyline = String::new();
+        for value in [i64::MAX, 0, i64::MAX, 0] {
+            push_polyline_value(&mut polyline, value);
+        }
+        assert_eq!(
+            decode_polyline::<f64>(&polyline, 6),
+            Err(PolylineDecodeError::Overflow)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn round_trip_fixture_shapes() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(1).unwrap());
+
+        for tile_id in [
+            GraphId::try_from_components(0, 3015, 0).unwrap(),
+            GraphId::try_from_components(2, 762_485, 0).unwrap(),
+        ] {
+            let tile = provider.get_handle_for_tile_containing(tile_id).unwrap();
+            for edge in tile.directed_edges() {
+                let edge_info = tile.get_edge_info(edge).unwrap();
+                let coords = decode_shape::<f64>(edge_info.encoded_shape).unwrap();
+
+                // Re-encoding must reproduce Valhalla's output byte for byte
+                assert_eq!(encode_shape(&coords), edge_info.encoded_shape);
+
+                // Tile shapes have 6 digits of precision, so polyline6 is lossless
+                let polyline = shape_to_polyline(edge_info.encoded_shape, 6).unwrap();
+                assert_eq!(
+                    polyline_to_shape(&polyline, 6).unwrap(),
+                    edge_info.encoded_shape
+                );
+            }
+        }
+    }
+}
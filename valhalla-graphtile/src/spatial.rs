@@ -1,6 +1,6 @@
 //! # Spatial utilities useful for routing
 
-use geo::{Coord, CoordFloat, Destination, Haversine, Point};
+use geo::{Bearing, Coord, CoordFloat, Destination, Distance, Haversine, Point};
 use num_traits::FromPrimitive;
 
 const METERS_PER_DEGREE_LAT: f64 = 111_132.954;
@@ -99,12 +99,230 @@ impl<F: CoordFloat + FromPrimitive> DistanceApproximator<F> {
     }
 }
 
+/// Finds the point on a line string which is closest to `point`.
+///
+/// Returns the snapped coordinate, and the fraction of the line's length (from 0 to 1)
+/// at which it lies.
+/// Distances are measured in a local equirectangular projection centered on `point`,
+/// which is plenty accurate over the length of a typical edge.
+///
+/// Returns `None` if the line string is empty.
+pub fn closest_point_on_line(point: Coord<f64>, line: &[Coord<f64>]) -> Option<(Coord<f64>, f64)> {
+    let meters_per_lon_degree = point.y.to_radians().cos() * METERS_PER_DEGREE_LAT;
+    let project = |c: Coord<f64>| Coord {
+        x: (c.x - point.x) * meters_per_lon_degree,
+        y: (c.y - point.y) * METERS_PER_DEGREE_LAT,
+    };
+
+    let (first, rest) = line.split_first()?;
+    // (distance from the point, snapped coordinate, distance along the line)
+    let mut best = (project(*first).x.hypot(project(*first).y), *first, 0.0);
+    let mut length = 0.0;
+    let mut prev = *first;
+    for &next in rest {
+        let (a, b) = (project(prev), project(next));
+        let segment = b - a;
+        let segment_length = segment.x.hypot(segment.y);
+        // Project the origin (the search point) onto the segment
+        let t = if segment_length > 0.0 {
+            (-(a.x * segment.x + a.y * segment.y) / (segment_length * segment_length))
+                .clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let snapped = a + segment * t;
+        let distance = snapped.x.hypot(snapped.y);
+        if distance < best.0 {
+            best = (
+                distance,
+                prev + (next - prev) * t,
+                length + segment_length * t,
+            );
+        }
+
+        length += segment_length;
+        prev = next;
+    }
+
+    let fraction = if length > 0.0 { best.2 / length } else { 0.0 };
+    Some((best.1, fraction))
+}
+
+/// Computes the heading along a line, looking `distance` meters ahead from its first coordinate.
+///
+/// The heading is in degrees clockwise from north, in the range `[0, 360)`.
+/// Measuring over some distance (rather than using the first segment)
+/// smooths out noise from short segments near intersections.
+/// If the line is shorter than `distance`, the heading to its last coordinate is used.
+///
+/// Returns `None` if the line has no length.
+pub fn heading_along_line(line: &[Coord<f64>], distance: f64) -> Option<f64> {
+    let (&start, rest) = line.split_first()?;
+    let mut target = None;
+    let mut travelled = 0.0;
+    let mut prev = start;
+    for &next in rest {
+        let segment_length = Haversine.distance(Point(prev), Point(next));
+        if travelled + segment_length >= distance && segment_length > 0.0 {
+            let t = (distance - travelled) / segment_length;
+            target = Some(prev + (next - prev) * t);
+            break;
+        }
+
+        travelled += segment_length;
+        prev = next;
+        if segment_length > 0.0 {
+            target = Some(next);
+        }
+    }
+
+    target.filter(|target| *target != start).map(|target| {
+        Haversine
+            .bearing(Point(start), Point(target))
+            .rem_euclid(360.0)
+    })
+}
+
+/// Extracts the part of a line string between two fractions of its length (each from 0 to 1).
+///
+/// Lengths are measured with the haversine formula.
+/// The result starts and ends with interpolated points (ex: partway along a segment),
+/// and includes every vertex in between.
+/// Fractions outside `[0, 1]` are clamped, and `start` must not be greater than `end`.
+///
+/// Returns an empty vector if the line string is empty.
+pub fn line_substring(line: &[Coord<f64>], start: f64, end: f64) -> Vec<Coord<f64>> {
+    let segment_lengths: Vec<f64> = line
+        .windows(2)
+        .map(|pair| Haversine.distance(Point(pair[0]), Point(pair[1])))
+        .collect();
+    let total_length: f64 = segment_lengths.iter().sum();
+    let (Some(&first), Some(&last)) = (line.first(), line.last()) else {
+        return Vec::new();
+    };
+    if total_length == 0.0 {
+        return vec![first, last];
+    }
+
+    let start_distance = start.clamp(0.0, 1.0) * total_length;
+    let end_distance = end.clamp(0.0, 1.0) * total_length;
+    let mut result = Vec::new();
+    let mut travelled = 0.0;
+    for (pair, &segment_length) in line.windows(2).zip(&segment_lengths) {
+        let (a, b) = (pair[0], pair[1]);
+        let segment_end = travelled + segment_length;
+        let interpolate = |distance: f64| {
+            if segment_length > 0.0 {
+                a + (b - a) * ((distance - travelled) / segment_length)
+            } else {
+                a
+            }
+        };
+
+        if result.is_empty() && start_distance <= segment_end {
+            result.push(interpolate(start_distance));
+        }
+        if !result.is_empty() {
+            if end_distance <= segment_end {
+                result.push(interpolate(end_distance));
+                return result;
+            }
+            result.push(b);
+        }
+
+        travelled = segment_end;
+    }
+
+    // Floating point error can leave the end just past the last segment
+    if result.is_empty() {
+        result.push(last);
+    }
+    if result.last() != Some(&last) {
+        result.push(last);
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use geo::{Distance, coord};
     use proptest::{prop_assert, proptest};
 
+    #[test]
+    fn heading_along_line_distance() {
+        // East, then north
+        let line = [
+            coord! {x: 1.0, y: 42.0},
+            coord! {x: 1.0001, y: 42.0},
+            coord! {x: 1.0001, y: 42.01},
+        ];
+
+        let first_segment = heading_along_line(&line, 5.0).unwrap();
+        assert!((first_segment - 90.0).abs() < 0.1, "{first_segment}");
+
+        // Looking further ahead, the line is mostly heading north
+        let overall = heading_along_line(&line, 500.0).unwrap();
+        assert!(overall < 2.0, "{overall}");
+
+        assert_eq!(heading_along_line(&line[..1], 30.0), None);
+        assert_eq!(heading_along_line(&[line[0], line[0]], 30.0), None);
+    }
+
+    #[test]
+    fn line_substring_interpolates() {
+        // Two segments of (roughly) equal length
+        let line = [
+            coord! {x: 1.0, y: 42.0},
+            coord! {x: 1.0, y: 42.001},
+            coord! {x: 1.0, y: 42.002},
+        ];
+
+        let start = line_substring(&line, 0.0, 0.4);
+        assert_eq!(start.len(), 2);
+        assert_eq!(start[0], line[0]);
+        assert!((start[1].y - 42.0008).abs() < 1e-9);
+
+        let middle = line_substring(&line, 0.25, 0.75);
+        assert_eq!(middle.len(), 3);
+        assert!((middle[0].y - 42.0005).abs() < 1e-9);
+        assert_eq!(middle[1], line[1]);
+        assert!((middle[2].y - 42.0015).abs() < 1e-9);
+
+        let whole = line_substring(&line, 0.0, 1.0);
+        assert_eq!(whole.len(), 3);
+        assert!((whole[2].y - line[2].y).abs() < 1e-9);
+
+        assert!(line_substring(&[], 0.0, 1.0).is_empty());
+    }
+
+    #[test]
+    fn closest_point_on_line_midpoint() {
+        let line = [
+            coord! {x: 1.0, y: 42.0},
+            coord! {x: 1.001, y: 42.0},
+            coord! {x: 1.001, y: 42.002},
+        ];
+
+        let (snapped, fraction) =
+            closest_point_on_line(coord! {x: 1.0005, y: 42.0001}, &line).unwrap();
+        assert!((snapped.x - 1.0005).abs() < 1e-9);
+        assert!((snapped.y - 42.0).abs() < 1e-9);
+        // The first segment is ~83m long, and the second ~222m
+        assert!(
+            (fraction - 0.136).abs() < 0.01,
+            "Unexpected fraction: {fraction}"
+        );
+
+        // Past the end of the line
+        let (snapped, fraction) =
+            closest_point_on_line(coord! {x: 1.001, y: 42.01}, &line).unwrap();
+        assert_eq!(snapped, line[2]);
+        assert!((fraction - 1.0).abs() < 1e-9);
+
+        assert!(closest_point_on_line(coord! {x: 0.0, y: 0.0}, &[]).is_none());
+    }
+
     proptest! {
         #[test]
         fn haversine_oracle_f32(lat in -90.0f32..90.0, lon in -180.0f32..180.0,
@@ -5,7 +5,7 @@
 //! See <https://valhalla.github.io/valhalla/tiles/> for a full writeup.
 
 use super::{GraphId, RoadClass};
-use geo::{CoordFloat, Rect, coord};
+use geo::{CoordFloat, Point, Rect, coord};
 use num_traits::FromPrimitive;
 use std::sync::LazyLock;
 
@@ -76,21 +76,75 @@ impl TileLevel {
     /// whose geographic extent intersects the axis-aligned bbox:
     /// `lon ∈ [west, east], lat ∈ [south, north]`.
     ///
-    /// Longitudes are expected in degrees, in the `[-180, 180]` range.
+    /// Longitudes are expected in degrees.
     /// If `west > east`, the bbox is assumed to wrap across the antimeridian
     /// and is treated as the union of `[left, 180] ∪ [-180, right]`
     ///
+    /// Longitudes beyond the antimeridian wrap around the world (when the tiling system wraps),
+    /// the same as in [`TileLevel::tile_containing`],
+    /// so a bbox covering a single point includes the tile containing that point.
+    /// Latitudes beyond the poles are clamped to the edge of the world.
+    /// Non-finite values yield no tiles.
+    ///
     /// # Correctness
     ///
-    /// This function does not sanity check the input.
-    /// It is the responsibility of the caller to ensure that the coordinates are in the range for
-    /// latitude and longitude, and that they describe a valid bounding box.
+    /// Beyond the above, this function does not sanity check the input.
+    /// It is the responsibility of the caller to ensure that the coordinates describe
+    /// a valid bounding box (e.g. `north >= south`).
     pub fn tiles_intersecting_bbox<N: CoordFloat + FromPrimitive>(
         &self,
         north: N,
         east: N,
         south: N,
         west: N,
+    ) -> Vec<GraphId> {
+        // These conversions cannot fail and are exercised by unit tests
+        let n_180 = N::from(180).unwrap();
+        let n_360 = N::from(360).unwrap();
+
+        if ![north, east, south, west]
+            .iter()
+            .all(|value| value.is_finite())
+        {
+            return Vec::new();
+        }
+
+        let (west, east) = if self.tiling_system.wrap_x {
+            // Move west into [-180, 180), keeping the width of the bbox
+            let width = if west > east {
+                east - west + n_360
+            } else {
+                east - west
+            };
+            if width >= n_360 {
+                (-n_180, n_180)
+            } else {
+                let west = (west + n_180) % n_360;
+                let west = if west < N::zero() { west + n_360 } else { west } - n_180;
+                (west, west + width)
+            }
+        } else {
+            (west, east)
+        };
+
+        if self.tiling_system.wrap_x && east > n_180 {
+            // Wrap across the antimeridian: [west, 180] ∪ [-180, east].
+            self.tiles_intersecting_lon_range(north, n_180, south, west)
+                .into_iter()
+                .chain(self.tiles_intersecting_lon_range(north, east - n_360, south, -n_180))
+                .collect()
+        } else {
+            self.tiles_intersecting_lon_range(north, east, south, west)
+        }
+    }
+
+    /// Tiles intersecting a bbox which doesn't cross the antimeridian (`west <= east`).
+    fn tiles_intersecting_lon_range<N: CoordFloat + FromPrimitive>(
+        &self,
+        north: N,
+        east: N,
+        south: N,
+        west: N,
     ) -> Vec<GraphId> {
         // These conversions cannot fail and are exercised by unit tests
         let size = N::from(self.tiling_system.tile_size).unwrap();
@@ -100,53 +154,116 @@ impl TileLevel {
         let n_90 = N::from(90).unwrap();
         let n_180 = N::from(180).unwrap();
 
-        if west > east {
-            // Wrap across the antimeridian: [west, 180] ∪ [-180, east].
-            self.tiles_intersecting_bbox(north, n_180, south, west)
-                .into_iter()
-                .chain(self.tiles_intersecting_bbox(north, east, south, -n_180))
-                .collect()
-        } else {
-            // At this point we have a non-wrapping lon interval [w, e] with w <= e.
-
-            // Map lon from [-180, 180] to [0, 360] and then to tile index.
-            let min_x = (((west + n_180) / size)
-                .floor()
-                .to_i64()
-                .expect("Unable to convert value to i64"))
-            .clamp(0, width - 1);
-            let max_x = (((east + n_180) / size)
-                .floor()
-                .to_i64()
-                .expect("Unable to convert value to i64"))
-            .clamp(0, width - 1);
-
-            // Map lat from [-90, 90] to [0, 180] and then to tile index.
-            let min_y = (((south + n_90) / size)
-                .floor()
-                .to_i64()
-                .expect("Unable to convert value to i64"))
-            .clamp(0, height - 1);
-            let max_y = (((north + n_90) / size)
-                .floor()
-                .to_i64()
-                .expect("Unable to convert value to i64"))
-            .clamp(0, height - 1);
-
-            // Iterate row-major: for each y, for each x, compute tile_index.
-            (min_y..=max_y)
-                .flat_map(move |y| {
-                    (min_x..=max_x).map(move |x| {
-                        let tile_index = (y * width + x) as u64;
-                        GraphId::try_from_components(self.level, tile_index, 0)
-                            .expect("valid base id")
-                    })
+        // Map lon from [-180, 180] to [0, 360] and then to tile index.
+        let min_x = (((west + n_180) / size)
+            .floor()
+            .to_i64()
+            .expect("Unable to convert value to i64"))
+        .clamp(0, width - 1);
+        let max_x = (((east + n_180) / size)
+            .floor()
+            .to_i64()
+            .expect("Unable to convert value to i64"))
+        .clamp(0, width - 1);
+
+        // Map lat from [-90, 90] to [0, 180] and then to tile index.
+        let min_y = (((south + n_90) / size)
+            .floor()
+            .to_i64()
+            .expect("Unable to convert value to i64"))
+        .clamp(0, height - 1);
+        let max_y = (((north + n_90) / size)
+            .floor()
+            .to_i64()
+            .expect("Unable to convert value to i64"))
+        .clamp(0, height - 1);
+
+        // Iterate row-major: for each y, for each x, compute tile_index.
+        (min_y..=max_y)
+            .flat_map(move |y| {
+                (min_x..=max_x).map(move |x| {
+                    let tile_index = (y * width + x) as u64;
+                    GraphId::try_from_components(self.level, tile_index, 0).expect("valid base id")
                 })
-                .collect()
+            })
+            .collect()
+    }
+
+    /// Gets the base graph ID of the tile in this level which contains the point.
+    ///
+    /// Tiles are half-open intervals, so points on a boundary between tiles
+    /// belong to the tile to the north and/or east.
+    /// The exceptions are the edges of the world:
+    ///
+    /// - Longitudes wrap around the antimeridian (when the tiling system wraps),
+    ///   so 180 is the same as -180 (and 181 is the same as -179).
+    /// - Points on the north pole belong to the top row of tiles.
+    ///
+    /// Returns `None` if the point is non-finite or outside the tiling system.
+    pub fn tile_containing<N: CoordFloat + FromPrimitive>(
+        &self,
+        point: Point<N>,
+    ) -> Option<GraphId> {
+        let tiling_system = &self.tiling_system;
+        let size = f64::from(tiling_system.tile_size);
+        let min = tiling_system.bounding_box.min();
+        let max = tiling_system.bounding_box.max();
+        let (min_x, min_y) = (f64::from(min.x), f64::from(min.y));
+        let (max_x, max_y) = (f64::from(max.x), f64::from(max.y));
+
+        let mut lon = point.x().to_f64()?;
+        let lat = point.y().to_f64()?;
+        if !lon.is_finite() || !lat.is_finite() || lat < min_y || lat > max_y {
+            return None;
+        }
+
+        if tiling_system.wrap_x {
+            lon = min_x + (lon - min_x).rem_euclid(max_x - min_x);
+        } else if lon < min_x || lon > max_x {
+            return None;
         }
+
+        // The clamps take care of the upper edges of the world (and float rounding near them).
+        // The values are also non-negative, so the casts are lossless.
+        #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let to_index = |value: f64, origin: f64, count: u32| {
+            (((value - origin) / size).floor() as u64).min(u64::from(count) - 1)
+        };
+        let x = to_index(lon, min_x, tiling_system.n_cols);
+        let y = to_index(lat, min_y, tiling_system.n_rows);
+
+        GraphId::try_from_components(self.level, y * u64::from(tiling_system.n_cols) + x, 0).ok()
     }
 }
 
+/// Returns the base graph IDs of all tiles (across every standard level)
+/// which intersect the bounding box.
+///
+/// See [`TileLevel::tiles_intersecting_bbox`] for details on how the bbox is interpreted.
+pub fn tiles_for_bbox<N: CoordFloat + FromPrimitive>(
+    north: N,
+    east: N,
+    south: N,
+    west: N,
+) -> Vec<GraphId> {
+    STANDARD_LEVELS
+        .iter()
+        .flat_map(|level| level.tiles_intersecting_bbox(north, east, south, west))
+        .collect()
+}
+
+/// Returns the base graph ID of the tile containing the point at every standard level
+/// (in level order).
+///
+/// See [`TileLevel::tile_containing`] for details on how boundaries are handled.
+/// The result is empty if the point is invalid.
+pub fn tiles_for_point<N: CoordFloat + FromPrimitive>(point: Point<N>) -> Vec<GraphId> {
+    STANDARD_LEVELS
+        .iter()
+        .filter_map(|level| level.tile_containing(point))
+        .collect()
+}
+
 /// A concrete instantiation of the standard Valhalla tile system.
 ///
 /// While other systems are technically possible, you should probably stick to the canonical one.
@@ -208,6 +325,7 @@ pub static TRANSIT_LEVEL: LazyLock<TileLevel> = LazyLock::new(|| TileLevel {
 mod tests {
     use super::*;
     use crate::tile_hierarchy::STANDARD_LEVELS;
+    use geo::point;
 
     /// Helper to compute the base tile GraphId at (x, y) for a given level.
     fn base_tile_id(level: &TileLevel, x: i64, y: i64) -> GraphId {
@@ -338,6 +456,110 @@ mod tests {
         assert!(ids.contains(&base_tile_id(level, 1, 1)));
     }
 
+    #[test]
+    fn bbox_wraps_longitudes_like_tile_containing() {
+        for level in STANDARD_LEVELS.iter() {
+            // A bbox around a single point only covers the tile containing the point,
+            // including beyond the antimeridian
+            for lon in [
+                -540.0, -181.0, -180.0, -179.9, 0.1, 179.9, 180.0, 181.0, 540.0,
+            ] {
+                let expected = level.tile_containing(point!(x: lon, y: 10.1)).unwrap();
+                assert_eq!(
+                    level.tiles_intersecting_bbox(10.1, lon, 10.1, lon),
+                    vec![expected],
+                    "lon {lon} at level {}",
+                    level.level
+                );
+            }
+
+            // Bboxes which extend past the antimeridian wrap around to the other side
+            let sorted = |mut tiles: Vec<GraphId>| {
+                tiles.sort_by_key(GraphId::value);
+                tiles
+            };
+            let crossing = sorted(level.tiles_intersecting_bbox(10.1, -179.0, 10.0, 179.0));
+            assert_eq!(
+                sorted(level.tiles_intersecting_bbox(10.1, 181.0, 10.0, 179.0)),
+                crossing
+            );
+            assert_eq!(
+                sorted(level.tiles_intersecting_bbox(10.1, -179.0, 10.0, -181.0)),
+                crossing
+            );
+
+            // Bboxes at least as wide as the world cover every column once
+            let row = level.tiles_intersecting_bbox(10.1, 200.0, 10.0, -200.0);
+            assert_eq!(row.len(), level.tiling_system.n_cols as usize);
+        }
+    }
+
+    #[test]
+    fn tile_containing_point() {
+        let level = &STANDARD_LEVELS[2];
+        let width = i64::from(level.tiling_system.n_cols);
+        let height = i64::from(level.tiling_system.n_rows);
+
+        // Andorra
+        assert_eq!(
+            level.tile_containing(point!(x: 1.52, y: 42.46)),
+            Some(GraphId::try_from_components(2, 762_486, 0).unwrap())
+        );
+
+        // Boundaries belong to the tile to the north and east
+        assert_eq!(
+            level.tile_containing(point!(x: 1.5, y: 42.5)),
+            Some(base_tile_id(level, 726, 530))
+        );
+
+        // Edges of the world
+        assert_eq!(
+            level.tile_containing(point!(x: -180.0, y: -90.0)),
+            Some(base_tile_id(level, 0, 0))
+        );
+        assert_eq!(
+            level.tile_containing(point!(x: 180.0, y: 90.0)),
+            Some(base_tile_id(level, 0, height - 1))
+        );
+        assert_eq!(
+            level.tile_containing(point!(x: 179.999_999, y: 0.0)),
+            Some(base_tile_id(level, width - 1, height / 2))
+        );
+        assert_eq!(
+            level.tile_containing(point!(x: 181.0, y: 0.0)),
+            level.tile_containing(point!(x: -179.0, y: 0.0))
+        );
+
+        // Invalid inputs
+        assert_eq!(level.tile_containing(point!(x: 0.0, y: 90.1)), None);
+        assert_eq!(level.tile_containing(point!(x: f64::NAN, y: 0.0)), None);
+    }
+
+    #[test]
+    fn tiles_for_all_levels() {
+        let tiles = tiles_for_point(point!(x: 1.52_f32, y: 42.46));
+        assert_eq!(
+            tiles,
+            vec![
+                GraphId::try_from_components(0, 3015, 0).unwrap(),
+                GraphId::try_from_components(1, 47_701, 0).unwrap(),
+                GraphId::try_from_components(2, 762_486, 0).unwrap(),
+            ]
+        );
+
+        // Every tile containing a point in the bbox is included
+        let tiles = tiles_for_bbox(42.47, 1.52, 42.45, 1.48);
+        assert_eq!(tiles.len(), 4);
+        for point in [point!(x: 1.48, y: 42.45), point!(x: 1.52, y: 42.47)] {
+            for tile in tiles_for_point(point) {
+                assert!(tiles.contains(&tile));
+            }
+        }
+
+        assert!(tiles_for_bbox(f64::NAN, 1.0, 0.0, 0.0).is_empty());
+        assert!(tiles_for_point(point!(x: 0.0, y: f64::INFINITY)).is_empty());
+    }
+
     #[test]
     fn test_base_tile_id() {
         // Test the base_tile_id function
@@ -0,0 +1,107 @@
+use super::{GraphTileProviderError, OwnedGraphTileProvider};
+use crate::GraphId;
+use crate::graph_tile::{GraphTileView, OwnedGraphTileHandle};
+use std::sync::Arc;
+
+/// An asynchronous source of graph tiles.
+///
+/// Tile lookups may hit the disk or the network,
+/// so calling a synchronous provider directly from an async task can stall the executor.
+/// This trait lets async code (ex: request handlers in a tokio-based service) await tiles instead.
+///
+/// Any synchronous [`OwnedGraphTileProvider`] wrapped in an [`Arc`] implements this trait,
+/// running lookups on tokio's blocking thread pool via [`tokio::task::spawn_blocking`].
+/// Natively async providers can implement it directly.
+pub trait AsyncGraphTileProvider: Send + Sync {
+    /// Gets a tile containing the given graph ID.
+    ///
+    /// See [`OwnedGraphTileProvider::get_handle_for_tile_containing`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the tile does not exist or could not be loaded.
+    fn get_handle_for_tile_containing(
+        &self,
+        graph_id: GraphId,
+    ) -> impl Future<Output = Result<Arc<OwnedGraphTileHandle>, GraphTileProviderError>> + Send;
+
+    /// Performs an operation against a graph tile.
+    ///
+    /// The operation runs on the calling task once the tile is available,
+    /// so it should be quick (no further I/O).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the tile does not exist or could not be loaded.
+    fn with_tile_containing<F, T>(
+        &self,
+        graph_id: GraphId,
+        process: F,
+    ) -> impl Future<Output = Result<T, GraphTileProviderError>> + Send
+    where
+        F: FnOnce(&GraphTileView) -> T + Send,
+        Self: Sized,
+    {
+        async move {
+            let tile = self.get_handle_for_tile_containing(graph_id).await?;
+            Ok(process(tile.borrow_dependent()))
+        }
+    }
+}
+
+impl<P> AsyncGraphTileProvider for Arc<P>
+where
+    P: OwnedGraphTileProvider + Send + Sync + 'static,
+{
+    async fn get_handle_for_tile_containing(
+        &self,
+        graph_id: GraphId,
+    ) -> Result<Arc<OwnedGraphTileHandle>, GraphTileProviderError> {
+        let provider = Arc::clone(self);
+        // Call the sync provider explicitly; method resolution on the Arc would find this impl
+        tokio::task::spawn_blocking(move || P::get_handle_for_tile_containing(&provider, graph_id))
+            .await
+            .map_err(|e| GraphTileProviderError::BlockingTaskFailed(e.to_string()))?
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AsyncGraphTileProvider;
+    use crate::GraphId;
+    use crate::graph_tile::GraphTile;
+    use crate::tile_provider::{DirectoryGraphTileProvider, GraphTileProviderError};
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_sync_provider_adapter() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles");
+        let provider = Arc::new(DirectoryGraphTileProvider::new(
+            base,
+            NonZeroUsize::new(1).unwrap(),
+        ));
+
+        let graph_id = GraphId::try_from_components(0, 3015, 0).unwrap();
+        let tile = provider
+            .get_handle_for_tile_containing(graph_id)
+            .await
+            .unwrap();
+        assert_eq!(tile.graph_id(), graph_id);
+
+        let node_count = provider
+            .with_tile_containing(graph_id, |tile| tile.header().node_count())
+            .await
+            .unwrap();
+        assert_eq!(node_count, tile.header().node_count());
+
+        let missing = GraphId::try_from_components(0, 0, 0).unwrap();
+        assert!(matches!(
+            provider.get_handle_for_tile_containing(missing).await,
+            Err(GraphTileProviderError::TileDoesNotExist)
+        ));
+    }
+}
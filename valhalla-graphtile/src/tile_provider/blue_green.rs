@@ -0,0 +1,343 @@
+#[cfg(feature = "spatial-index")]
+use super::EdgeSpatialIndex;
+use super::{GraphTileProvider, GraphTileProviderError, OwnedGraphTileProvider};
+use crate::GraphId;
+use crate::graph_tile::{GraphTileView, OwnedGraphTileHandle};
+use geo::{CoordFloat, Point};
+use num_traits::FromPrimitive;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use thiserror::Error;
+
+/// The name of the HTTP header which selects a tileset for a request.
+///
+/// See [`BlueGreenTileProvider::select_by_header`].
+pub const TILESET_HEADER: &str = "x-valinor-tileset";
+
+/// Which of the two tilesets in a [`BlueGreenTileProvider`] to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Tileset {
+    /// The tileset currently serving production traffic.
+    #[default]
+    Current,
+    /// The tileset being evaluated (e.g. a fresh build).
+    Candidate,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Unrecognized tileset: {0} (expected `current` or `candidate`)")]
+pub struct UnknownTilesetError(String);
+
+impl FromStr for Tileset {
+    type Err = UnknownTilesetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            s if s.eq_ignore_ascii_case("current") => Ok(Self::Current),
+            s if s.eq_ignore_ascii_case("candidate") => Ok(Self::Candidate),
+            s => Err(UnknownTilesetError(s.to_string())),
+        }
+    }
+}
+
+impl Display for Tileset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Current => write!(f, "current"),
+            Self::Candidate => write!(f, "candidate"),
+        }
+    }
+}
+
+/// A pair of tile providers for blue/green dataset switching.
+///
+/// This holds the current (production) tileset alongside a candidate,
+/// and lets each request pick which one it runs against.
+/// This makes it possible to shadow test a new tile build with production traffic
+/// before switching over.
+///
+/// Both providers should be the same type (e.g. two tarballs),
+/// so that the choice doesn't leak into the rest of the routing code:
+/// [`BlueGreenTileProvider::select`] simply hands back the selected provider.
+///
+/// The pair is also a [`GraphTileProvider`] in its own right, which reads from the current tileset.
+/// [`BlueGreenTileProvider::swap`] switches tilesets while in use;
+/// lookups which are already in flight finish against the tileset they started with.
+/// Each lookup picks the current tileset afresh, so hold on to [`BlueGreenTileProvider::current`]
+/// for operations which must see a consistent tileset (ex: a single route computation).
+pub struct BlueGreenTileProvider<P> {
+    providers: [P; 2],
+    /// Whether the tilesets have been swapped (so the second provider is the current one).
+    swapped: AtomicBool,
+}
+
+impl<P> BlueGreenTileProvider<P> {
+    pub fn new(current: P, candidate: P) -> Self {
+        Self {
+            providers: [current, candidate],
+            swapped: AtomicBool::new(false),
+        }
+    }
+
+    /// Gets the provider for the given tileset.
+    pub fn select(&self, tileset: Tileset) -> &P {
+        let (current, candidate) = self.current_and_candidate();
+        match tileset {
+            Tileset::Current => current,
+            Tileset::Candidate => candidate,
+        }
+    }
+
+    /// Gets the provider selected by the value of the [`TILESET_HEADER`] header (if any).
+    ///
+    /// Missing or unrecognized values fall back to the current tileset,
+    /// so a typo never routes production traffic to the candidate.
+    pub fn select_by_header(&self, value: Option<&str>) -> &P {
+        let tileset = value
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default();
+        self.select(tileset)
+    }
+
+    /// The tileset currently serving production traffic.
+    pub fn current(&self) -> &P {
+        self.select(Tileset::Current)
+    }
+
+    /// The tileset being evaluated.
+    pub fn candidate(&self) -> &P {
+        self.select(Tileset::Candidate)
+    }
+
+    /// The current and candidate tilesets, as of the same moment.
+    ///
+    /// Calling [`BlueGreenTileProvider::current`] and [`BlueGreenTileProvider::candidate`]
+    /// separately may see a swap in between (and return the same provider twice).
+    pub fn current_and_candidate(&self) -> (&P, &P) {
+        let [first, second] = &self.providers;
+        if self.swapped.load(Ordering::Acquire) {
+            (second, first)
+        } else {
+            (first, second)
+        }
+    }
+
+    /// Swaps the current and candidate tilesets.
+    ///
+    /// This promotes the candidate to production,
+    /// while keeping the previous tileset around as a candidate for a quick rollback.
+    /// It is safe to swap while other threads are reading from either tileset.
+    pub fn swap(&self) {
+        self.swapped.fetch_xor(true, Ordering::AcqRel);
+    }
+
+    /// Consumes the pair, returning the current and candidate providers.
+    pub fn into_inner(self) -> (P, P) {
+        let [first, second] = self.providers;
+        if self.swapped.into_inner() {
+            (second, first)
+        } else {
+            (first, second)
+        }
+    }
+}
+
+impl<P: GraphTileProvider> GraphTileProvider for BlueGreenTileProvider<P> {
+    #[inline]
+    fn with_tile_containing<F, T>(
+        &self,
+        graph_id: GraphId,
+        process: F,
+    ) -> Result<T, GraphTileProviderError>
+    where
+        F: FnOnce(&GraphTileView) -> T,
+    {
+        self.current().with_tile_containing(graph_id, process)
+    }
+
+    fn enumerate_tiles_within_radius<N: CoordFloat + FromPrimitive>(
+        &self,
+        center: Point<N>,
+        radius: N,
+    ) -> Vec<GraphId> {
+        self.current().enumerate_tiles_within_radius(center, radius)
+    }
+
+    #[cfg(feature = "spatial-index")]
+    fn edge_spatial_index(
+        &self,
+        graph_id: GraphId,
+    ) -> Result<Option<Arc<EdgeSpatialIndex>>, GraphTileProviderError> {
+        self.current().edge_spatial_index(graph_id)
+    }
+
+    fn available_tiles(&self) -> Result<Vec<GraphId>, GraphTileProviderError> {
+        self.current().available_tiles()
+    }
+}
+
+impl<P: OwnedGraphTileProvider> OwnedGraphTileProvider for BlueGreenTileProvider<P> {
+    fn get_handle_for_tile_containing(
+        &self,
+        graph_id: GraphId,
+    ) -> Result<Arc<OwnedGraphTileHandle>, GraphTileProviderError> {
+        self.current().get_handle_for_tile_containing(graph_id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Tileset, UnknownTilesetError};
+    #[cfg(feature = "fs")]
+    use crate::{
+        GraphId,
+        graph_tile::GraphTile,
+        tile_provider::{
+            BlueGreenTileProvider, DirectoryGraphTileProvider, GraphTileProvider,
+            TarballTileProvider,
+        },
+    };
+    #[cfg(feature = "fs")]
+    use std::num::NonZeroUsize;
+    #[cfg(feature = "fs")]
+    use std::path::PathBuf;
+    #[cfg(feature = "fs")]
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn test_parse_tileset() {
+        assert_eq!("current".parse(), Ok(Tileset::Current));
+        assert_eq!(" Candidate ".parse(), Ok(Tileset::Candidate));
+        assert_eq!(
+            "green".parse::<Tileset>(),
+            Err(UnknownTilesetError("green".to_string()))
+        );
+        assert_eq!(
+            Tileset::Candidate.to_string().parse(),
+            Ok(Tileset::Candidate)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_select() {
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let providers = BlueGreenTileProvider::new(
+            DirectoryGraphTileProvider::new(
+                fixtures.join("andorra-tiles"),
+                NonZeroUsize::new(1).unwrap(),
+            ),
+            DirectoryGraphTileProvider::new(
+                fixtures.join("nonexistent-tiles"),
+                NonZeroUsize::new(1).unwrap(),
+            ),
+        );
+        let graph_id = GraphId::try_from_components(0, 3015, 0).unwrap();
+
+        // Unknown and missing selections must never fall through to the candidate
+        for header in [None, Some("current"), Some("bogus")] {
+            assert!(
+                providers
+                    .select_by_header(header)
+                    .with_tile_containing(graph_id, |_| ())
+                    .is_ok()
+            );
+        }
+        assert!(
+            providers
+                .select_by_header(Some("candidate"))
+                .with_tile_containing(graph_id, |_| ())
+                .is_err()
+        );
+
+        providers.swap();
+        assert!(
+            providers
+                .select(Tileset::Current)
+                .with_tile_containing(graph_id, |_| ())
+                .is_err()
+        );
+        assert!(
+            providers
+                .candidate()
+                .with_tile_containing(graph_id, |_| ())
+                .is_ok()
+        );
+        // The pair itself reads from whichever tileset is current
+        assert!(providers.with_tile_containing(graph_id, |_| ()).is_err());
+
+        let (current, candidate) = providers.into_inner();
+        assert!(current.with_tile_containing(graph_id, |_| ()).is_err());
+        assert!(candidate.with_tile_containing(graph_id, |_| ()).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_tarball_pair() {
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let tarball =
+            || TarballTileProvider::<false>::new(fixtures.join("andorra-tiles.tar")).unwrap();
+        let providers = BlueGreenTileProvider::new(tarball(), tarball());
+        let graph_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+
+        let count = |tileset| {
+            providers
+                .select(tileset)
+                .with_tile_containing(graph_id, |tile| tile.header().directed_edge_count())
+                .unwrap()
+        };
+        assert_eq!(count(Tileset::Current), count(Tileset::Candidate));
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_swap_while_reading() {
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let directory = |name| {
+            DirectoryGraphTileProvider::new(fixtures.join(name), NonZeroUsize::new(1).unwrap())
+        };
+        let providers =
+            BlueGreenTileProvider::new(directory("andorra-tiles"), directory("andorra-tiles"));
+        let (first, second) = providers.current_and_candidate();
+        let (first, second) = (std::ptr::from_ref(first), std::ptr::from_ref(second));
+        let graph_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+        let edge_count = providers
+            .with_tile_containing(graph_id, |tile| tile.header().directed_edge_count())
+            .unwrap();
+
+        let done = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    while !done.load(Ordering::Relaxed) {
+                        // Reads through the pair never fail mid-swap
+                        let count = providers
+                            .with_tile_containing(graph_id, |tile| {
+                                tile.header().directed_edge_count()
+                            })
+                            .unwrap();
+                        assert_eq!(count, edge_count);
+
+                        // Both sides are read from the same swap
+                        let (current, candidate) = providers.current_and_candidate();
+                        assert!(!std::ptr::eq(current, candidate));
+                    }
+                });
+            }
+
+            // Swapping is cheap, but reading tiles under miri isn't
+            let swaps = if cfg!(miri) { 10 } else { 1000 };
+            for _ in 0..swaps {
+                providers.swap();
+            }
+            providers.swap();
+            done.store(true, Ordering::Relaxed);
+        });
+
+        // An odd number of swaps leaves the original candidate in production
+        assert!(std::ptr::eq(providers.current(), second));
+        assert!(std::ptr::eq(providers.candidate(), first));
+    }
+}
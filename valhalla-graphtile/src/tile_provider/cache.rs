@@ -0,0 +1,218 @@
+use crate::GraphId;
+use crate::graph_tile::OwnedGraphTileHandle;
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+/// How a tile provider decides when to evict cached tiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// Caches up to a fixed number of tiles.
+    MaxTiles(NonZeroUsize),
+    /// Caches tiles up to a total size in bytes.
+    ///
+    /// Tile sizes vary by orders of magnitude (ex: rural vs dense urban tiles),
+    /// so this gives much more predictable memory usage than a tile count.
+    /// A single tile which is larger than the whole budget is still cached
+    /// (until the next tile is loaded).
+    MaxBytes(NonZeroUsize),
+}
+
+/// A snapshot of tile cache usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    /// The number of lookups which were served from the cache.
+    pub hits: u64,
+    /// The number of lookups which had to load a tile.
+    pub misses: u64,
+    /// The number of tiles evicted to make room for others.
+    ///
+    /// This does not include tiles invalidated by writes.
+    pub evictions: u64,
+    /// The number of tiles currently cached.
+    pub cached_tiles: usize,
+    /// The total size of the currently cached tiles, in bytes.
+    pub cached_bytes: usize,
+}
+
+/// An LRU cache of graph tiles, with a choice of eviction policy.
+pub(crate) struct TileCache {
+    policy: CachePolicy,
+    tiles: LruCache<GraphId, Arc<OwnedGraphTileHandle>>,
+    cached_bytes: usize,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl TileCache {
+    pub fn new(policy: CachePolicy) -> Self {
+        let tiles = match policy {
+            CachePolicy::MaxTiles(max_tiles) => LruCache::new(max_tiles),
+            CachePolicy::MaxBytes(_) => LruCache::unbounded(),
+        };
+        Self {
+            policy,
+            tiles,
+            cached_bytes: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    #[cfg(feature = "spatial-index")]
+    pub fn policy(&self) -> CachePolicy {
+        self.policy
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            cached_tiles: self.tiles.len(),
+            cached_bytes: self.cached_bytes,
+        }
+    }
+
+    /// Gets a tile from the cache, or loads and caches it.
+    ///
+    /// Any tiles evicted to make room are appended to `evicted`,
+    /// so that callers can drop derived data (ex: spatial indexes) too.
+    pub fn try_get_or_insert<E>(
+        &mut self,
+        graph_id: GraphId,
+        load: impl FnOnce() -> Result<Arc<OwnedGraphTileHandle>, E>,
+        evicted: &mut Vec<GraphId>,
+    ) -> Result<Arc<OwnedGraphTileHandle>, E> {
+        if let Some(tile) = self.tiles.get(&graph_id) {
+            self.hits += 1;
+            return Ok(tile.clone());
+        }
+
+        self.misses += 1;
+        let tile = load()?;
+        self.cached_bytes += tile.borrow_owner().len();
+        if let Some((evicted_id, evicted_tile)) = self.tiles.push(graph_id, tile.clone()) {
+            self.cached_bytes -= evicted_tile.borrow_owner().len();
+            // Pushing an existing key returns the old value, which isn't an eviction
+            if evicted_id != graph_id {
+                self.evictions += 1;
+                evicted.push(evicted_id);
+            }
+        }
+
+        if let CachePolicy::MaxBytes(max_bytes) = self.policy {
+            // Never evict the tile we just loaded
+            while self.cached_bytes > max_bytes.get() && self.tiles.len() > 1 {
+                let Some((evicted_id, evicted_tile)) = self.tiles.pop_lru() else {
+                    break;
+                };
+                self.cached_bytes -= evicted_tile.borrow_owner().len();
+                self.evictions += 1;
+                evicted.push(evicted_id);
+            }
+        }
+
+        Ok(tile)
+    }
+
+    /// Removes a tile from the cache (ex: after it has been overwritten).
+    pub fn invalidate(&mut self, graph_id: GraphId) {
+        if let Some(tile) = self.tiles.pop(&graph_id) {
+            self.cached_bytes -= tile.borrow_owner().len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CachePolicy, CacheStats, TileCache};
+    use crate::GraphId;
+    use crate::graph_tile::OwnedGraphTileHandle;
+    use std::convert::Infallible;
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    #[expect(
+        clippy::unnecessary_wraps,
+        reason = "Matches the loader signature of TileCache::try_get_or_insert"
+    )]
+    fn load(graph_id: GraphId) -> Result<Arc<OwnedGraphTileHandle>, Infallible> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles")
+            .join(graph_id.file_path("gph").unwrap());
+        let bytes = std::fs::read(path).unwrap();
+        Ok(Arc::new(OwnedGraphTileHandle::try_from(bytes).unwrap()))
+    }
+
+    #[test]
+    fn test_max_bytes_policy() {
+        let l0_id = GraphId::try_from_components(0, 3015, 0).unwrap();
+        let l2_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+        let l0_size = load(l0_id).unwrap().borrow_owner().len();
+        let l2_size = load(l2_id).unwrap().borrow_owner().len();
+
+        // Room for the level 2 tile, but not both
+        let mut cache = TileCache::new(CachePolicy::MaxBytes(NonZeroUsize::new(l2_size).unwrap()));
+        let mut evicted = Vec::new();
+        cache
+            .try_get_or_insert(l2_id, || load(l2_id), &mut evicted)
+            .unwrap();
+        cache
+            .try_get_or_insert::<Infallible>(l2_id, || unreachable!(), &mut evicted)
+            .unwrap();
+        assert!(evicted.is_empty());
+
+        cache
+            .try_get_or_insert(l0_id, || load(l0_id), &mut evicted)
+            .unwrap();
+        assert_eq!(evicted, [l2_id]);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 2,
+                evictions: 1,
+                cached_tiles: 1,
+                cached_bytes: l0_size,
+            }
+        );
+
+        cache.invalidate(l0_id);
+        assert_eq!(cache.stats().cached_bytes, 0);
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn test_oversized_tile_is_still_cached() {
+        let l2_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+        let mut cache = TileCache::new(CachePolicy::MaxBytes(NonZeroUsize::MIN));
+        let mut evicted = Vec::new();
+        cache
+            .try_get_or_insert(l2_id, || load(l2_id), &mut evicted)
+            .unwrap();
+        assert_eq!(cache.stats().cached_tiles, 1);
+        assert!(evicted.is_empty());
+    }
+
+    #[test]
+    fn test_max_tiles_policy() {
+        let l0_id = GraphId::try_from_components(0, 3015, 0).unwrap();
+        let l2_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+        let mut cache = TileCache::new(CachePolicy::MaxTiles(NonZeroUsize::MIN));
+        let mut evicted = Vec::new();
+        cache
+            .try_get_or_insert(l0_id, || load(l0_id), &mut evicted)
+            .unwrap();
+        let l2_tile = cache
+            .try_get_or_insert(l2_id, || load(l2_id), &mut evicted)
+            .unwrap();
+        assert_eq!(evicted, [l0_id]);
+        assert_eq!(cache.stats().cached_tiles, 1);
+        assert_eq!(cache.stats().cached_bytes, l2_tile.borrow_owner().len());
+    }
+}
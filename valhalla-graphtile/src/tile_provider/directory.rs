@@ -3,17 +3,23 @@ use crate::graph_id::InvalidGraphIdError;
 use crate::graph_tile::{GraphTileBuildError, GraphTileBuilder};
 use crate::graph_tile::{GraphTileView, OwnedGraphTileHandle};
 use crate::spatial::bbox_with_center;
-use crate::tile_hierarchy::STANDARD_LEVELS;
+use crate::tile_hierarchy::{STANDARD_LEVELS, tiles_for_bbox};
+#[cfg(feature = "spatial-index")]
+use crate::tile_provider::EdgeSpatialIndex;
+use crate::tile_provider::cache::{CachePolicy, CacheStats, TileCache};
 use crate::tile_provider::{
     GraphTileProvider, GraphTileProviderError, LockTable, OwnedGraphTileProvider,
+    TileProviderMetrics,
 };
 use geo::{CoordFloat, Point};
+#[cfg(feature = "spatial-index")]
 use lru::LruCache;
 use num_traits::FromPrimitive;
 use std::io::{ErrorKind, Write};
 use std::num::NonZeroUsize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use std::{fs::File, io::BufWriter};
 
 /// A graph tile provider that is backed by a directory of tiles.
@@ -22,21 +28,80 @@ use std::{fs::File, io::BufWriter};
 ///
 /// To minimize file handle churn and re-validation of the mapped tile memory,
 /// this includes an internal LRU cache.
-/// This is configurable with a max number of cached tiles.
+/// This is configurable with a max number of cached tiles,
+/// or a max total size (see [`CachePolicy`]).
 /// Any cached tiles will remain in memory.
+///
+/// Edge spatial indexes (with the `spatial-index` feature) are disabled by default,
+/// since they can use a lot of memory relative to the tile itself.
+/// See [`DirectoryGraphTileProvider::with_edge_spatial_index`].
 pub struct DirectoryGraphTileProvider {
     base_directory: PathBuf,
     lock_table: LockTable<GraphId>,
     // TODO: This is a bit hackish for now, but even so it speeds things up MASSIVELY for many workloads!
-    lru_cache: Mutex<LruCache<GraphId, Arc<OwnedGraphTileHandle>>>,
+    lru_cache: Mutex<TileCache>,
+    /// Spatial indexes for (at most) the same tiles as the tile cache.
+    ///
+    /// When this is `None`, spatial indexes are disabled.
+    #[cfg(feature = "spatial-index")]
+    spatial_index_cache: Option<Mutex<LruCache<GraphId, Arc<EdgeSpatialIndex>>>>,
+    metrics: Option<Arc<dyn TileProviderMetrics>>,
 }
 
 impl DirectoryGraphTileProvider {
     pub fn new(base_directory: PathBuf, num_cached_tiles: NonZeroUsize) -> Self {
+        Self::with_cache_policy(base_directory, CachePolicy::MaxTiles(num_cached_tiles))
+    }
+
+    /// Creates a provider with the given tile cache policy
+    /// (ex: `CachePolicy::MaxBytes` to cache up to 2 GiB of tiles).
+    pub fn with_cache_policy(base_directory: PathBuf, cache_policy: CachePolicy) -> Self {
         DirectoryGraphTileProvider {
             base_directory,
             lock_table: LockTable::new(),
-            lru_cache: Mutex::new(LruCache::new(num_cached_tiles)),
+            lru_cache: Mutex::new(TileCache::new(cache_policy)),
+            #[cfg(feature = "spatial-index")]
+            spatial_index_cache: None,
+            metrics: None,
+        }
+    }
+
+    /// Reports cache and fetch activity to the given hooks (see [`TileProviderMetrics`]).
+    #[must_use]
+    pub fn with_metrics(self, metrics: Arc<dyn TileProviderMetrics>) -> Self {
+        Self {
+            metrics: Some(metrics),
+            ..self
+        }
+    }
+
+    /// Returns a snapshot of the tile cache usage (hits, misses, evictions, and current size).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the internal cache lock is poisoned.
+    pub fn cache_stats(&self) -> Result<CacheStats, GraphTileProviderError> {
+        Ok(self
+            .lru_cache
+            .lock()
+            .map_err(|e| GraphTileProviderError::PoisonedCacheLock(e.to_string()))?
+            .stats())
+    }
+
+    /// Enables lazily built edge spatial indexes (see [`EdgeSpatialIndex`]).
+    ///
+    /// Up to one index per cached tile is kept in memory,
+    /// and indexes are dropped when their tile is evicted.
+    #[cfg(feature = "spatial-index")]
+    #[must_use]
+    pub fn with_edge_spatial_index(self) -> Self {
+        let spatial_index_cache = match self.lru_cache.lock().map(|cache| cache.policy()) {
+            Ok(CachePolicy::MaxTiles(max_tiles)) => LruCache::new(max_tiles),
+            Ok(CachePolicy::MaxBytes(_)) | Err(_) => LruCache::unbounded(),
+        };
+        Self {
+            spatial_index_cache: Some(Mutex::new(spatial_index_cache)),
+            ..self
         }
     }
 
@@ -91,11 +156,63 @@ impl DirectoryGraphTileProvider {
         &self,
         graph_tile_builder: GraphTileBuilder<'_>,
     ) -> Result<(), GraphTileBuildError> {
-        let graph_id = graph_tile_builder.graph_id();
+        let lock = self.lock_table.lock_for(graph_tile_builder.graph_id());
+        let _guard = lock.lock();
+
+        self.write_tile(graph_tile_builder)
+    }
 
-        let lock = self.lock_table.lock_for(graph_id);
+    /// Checks out the tile containing the given graph ID as a builder,
+    /// applies `edit` to it, and writes the result back.
+    ///
+    /// Unlike loading a tile and calling [`DirectoryGraphTileProvider::overwrite_tile`] yourself,
+    /// the tile stays locked for the whole operation,
+    /// so concurrent edits to the same tile can't clobber each other
+    /// (readers of this tile block until the edit is committed).
+    /// The write is atomic and invalidates the caches in the same way as `overwrite_tile`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::num::NonZeroUsize;
+    /// # use std::path::PathBuf;
+    /// # use valhalla_graphtile::GraphId;
+    /// # use valhalla_graphtile::tile_provider::DirectoryGraphTileProvider;
+    /// let provider =
+    ///     DirectoryGraphTileProvider::new(PathBuf::from("tiles"), NonZeroUsize::new(4).unwrap());
+    /// let graph_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+    /// provider
+    ///     .update_tile(graph_id, |builder| Ok(builder.with_dataset_id(42)))
+    ///     .unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the tile can't be loaded, if `edit` returns an error
+    /// (in which case nothing is written), or for any of the reasons `overwrite_tile` can fail.
+    pub fn update_tile<F>(&self, graph_id: GraphId, edit: F) -> Result<(), GraphTileBuildError>
+    where
+        F: for<'a> FnOnce(
+            GraphTileBuilder<'a>,
+        ) -> Result<GraphTileBuilder<'a>, GraphTileBuildError>,
+    {
+        let base_graph_id = graph_id.tile_base_id();
+        let lock = self.lock_table.lock_for(base_graph_id);
         let _guard = lock.lock();
 
+        let tile = self.load_tile(base_graph_id)?;
+        let builder = edit(GraphTileBuilder::from(&*tile))?;
+        self.write_tile(builder)
+    }
+
+    /// Atomically writes a tile to disk and invalidates any cached data for it.
+    ///
+    /// The caller must hold the lock for the tile.
+    fn write_tile(
+        &self,
+        graph_tile_builder: GraphTileBuilder<'_>,
+    ) -> Result<(), GraphTileBuildError> {
+        let graph_id = graph_tile_builder.graph_id();
         let path = self.path_for_graph_id(graph_id)?;
 
         // Write to a temp file
@@ -117,11 +234,43 @@ impl DirectoryGraphTileProvider {
             .lock()
             .map_err(|e| GraphTileBuildError::PoisonedCacheLock(e.to_string()))?;
         // Invalidate the cache
-        cache.pop(&graph_id);
+        cache.invalidate(graph_id);
+        drop(cache);
+
+        #[cfg(feature = "spatial-index")]
+        if let Some(spatial_index_cache) = &self.spatial_index_cache {
+            spatial_index_cache
+                .lock()
+                .map_err(|e| GraphTileBuildError::PoisonedCacheLock(e.to_string()))?
+                .pop(&graph_id);
+        }
 
         Ok(())
     }
 
+    /// Parses the graph ID of a tile from its path (the inverse of `path_for_graph_id`).
+    ///
+    /// Returns `None` if the path is not a tile in the standard hierarchy.
+    fn graph_id_for_path(&self, path: &Path) -> Option<GraphId> {
+        if path.extension()? != "gph" {
+            return None;
+        }
+        let relative_path = path.strip_prefix(&self.base_directory).ok()?;
+        let stem = relative_path.with_extension("");
+        let mut components = stem.iter();
+        let level: u8 = components.next()?.to_str()?.parse().ok()?;
+        if usize::from(level) >= STANDARD_LEVELS.len() {
+            return None;
+        }
+        // The tile ID is split into groups of three digits after the level (ex: 2/000/762/485)
+        let digits: String = components
+            .map(|component| component.to_str())
+            .collect::<Option<_>>()?;
+        let graph_id = GraphId::try_from_components(level, digits.parse().ok()?, 0).ok()?;
+        // Reject anything that doesn't round trip (ex: missing zero padding)
+        (graph_id.file_path("gph").ok()? == relative_path).then_some(graph_id)
+    }
+
     /// Computes the path for the given graph ID.
     ///
     /// NOTE: This function assumes that the graph ID is already a base ID.
@@ -149,19 +298,59 @@ impl GraphTileProvider for DirectoryGraphTileProvider {
         center: Point<N>,
         radius: N,
     ) -> Vec<GraphId> {
-        let mut out: Vec<GraphId> = Vec::new();
-
         let (north, east, south, west) = bbox_with_center(center, radius);
 
-        for level in STANDARD_LEVELS.iter() {
-            for gid in level.tiles_intersecting_bbox(north, east, south, west) {
-                if self.get_handle_for_tile_containing(gid).is_ok() {
-                    out.push(gid);
+        tiles_for_bbox(north, east, south, west)
+            .into_iter()
+            .filter(|&gid| self.get_handle_for_tile_containing(gid).is_ok())
+            .collect()
+    }
+
+    #[cfg(feature = "spatial-index")]
+    fn edge_spatial_index(
+        &self,
+        graph_id: GraphId,
+    ) -> Result<Option<Arc<EdgeSpatialIndex>>, GraphTileProviderError> {
+        let Some(spatial_index_cache) = &self.spatial_index_cache else {
+            return Ok(None);
+        };
+        let lock_cache = || {
+            spatial_index_cache
+                .lock()
+                .map_err(|e| GraphTileProviderError::PoisonedCacheLock(e.to_string()))
+        };
+
+        let base_graph_id = graph_id.tile_base_id();
+        let tile = self.get_handle_for_tile_containing(base_graph_id)?;
+        if let Some(index) = lock_cache()?.get(&base_graph_id) {
+            return Ok(Some(index.clone()));
+        }
+
+        // Building an index can take a while, so don't block lookups for other tiles meanwhile.
+        // If another thread got here first, its index wins (so everyone shares the same one).
+        let index = Arc::new(EdgeSpatialIndex::new(&*tile)?);
+        let index = lock_cache()?.get_or_insert(base_graph_id, || index).clone();
+
+        Ok(Some(index))
+    }
+
+    fn available_tiles(&self) -> Result<Vec<GraphId>, GraphTileProviderError> {
+        let mut tiles = Vec::new();
+        let mut directories = vec![self.base_directory.clone()];
+        while let Some(directory) = directories.pop() {
+            for entry in std::fs::read_dir(directory)? {
+                let entry = entry?;
+                let path = entry.path();
+                if entry.file_type()?.is_dir() {
+                    directories.push(path);
+                } else if let Some(graph_id) = self.graph_id_for_path(&path) {
+                    tiles.push(graph_id);
                 }
             }
         }
 
-        out
+        tiles.sort_by_key(|graph_id| (graph_id.level(), graph_id.tile_id()));
+        Ok(tiles)
     }
 }
 
@@ -170,42 +359,103 @@ impl OwnedGraphTileProvider for DirectoryGraphTileProvider {
         &self,
         graph_id: GraphId,
     ) -> Result<Arc<OwnedGraphTileHandle>, GraphTileProviderError> {
-        let lock = self.lock_table.lock_for(graph_id);
+        // Writers lock the base ID, so readers must too
+        let base_graph_id = graph_id.tile_base_id();
+        let lock = self.lock_table.lock_for(base_graph_id);
         let _guard = lock.lock();
 
+        self.load_tile(base_graph_id)
+    }
+}
+
+impl DirectoryGraphTileProvider {
+    /// Gets a tile from the cache, or loads it from disk.
+    ///
+    /// The caller must hold the lock for the tile.
+    fn load_tile(
+        &self,
+        base_graph_id: GraphId,
+    ) -> Result<Arc<OwnedGraphTileHandle>, GraphTileProviderError> {
         // Build up the path from the base directory + tile ID components
-        // TODO: Do we want to move the base ID check inside file_path?
-        let base_graph_id = graph_id.tile_base_id();
         let mut cache = self
             .lru_cache
             .lock()
             .map_err(|e| GraphTileProviderError::PoisonedCacheLock(e.to_string()))?;
-        let tile = cache
-            .try_get_or_insert(base_graph_id, || {
-                let path = self.path_for_graph_id(base_graph_id)?;
-                // Open the file and read all bytes into a buffer
-                // NOTE: Does not handle compressed tiles
-                let data = std::fs::read(path).map_err(|e| match e.kind() {
-                    ErrorKind::NotFound => GraphTileProviderError::TileDoesNotExist,
-                    _ => GraphTileProviderError::IoError(e),
-                })?;
-                let tile = OwnedGraphTileHandle::try_from(data)?;
-                Ok::<_, GraphTileProviderError>(Arc::new(tile))
-            })
-            .cloned()?;
-
-        // Construct a graph tile with the bytes
+        let mut evicted = Vec::new();
+        let mut is_hit = true;
+        let tile = cache.try_get_or_insert(
+            base_graph_id,
+            || {
+                is_hit = false;
+                let start = Instant::now();
+                let result = self.read_tile(base_graph_id);
+                if let Some(metrics) = &self.metrics {
+                    metrics.cache_miss(base_graph_id);
+                    match &result {
+                        Ok(tile) => metrics.tile_fetched(
+                            base_graph_id,
+                            tile.borrow_owner().len(),
+                            start.elapsed(),
+                        ),
+                        Err(e) => metrics.fetch_failed(base_graph_id, e, start.elapsed()),
+                    }
+                }
+                result
+            },
+            &mut evicted,
+        )?;
+        drop(cache);
+
+        if let Some(metrics) = &self.metrics {
+            if is_hit {
+                metrics.cache_hit(base_graph_id);
+            }
+            for &graph_id in &evicted {
+                metrics.cache_eviction(graph_id);
+            }
+        }
+
+        #[cfg(feature = "spatial-index")]
+        if let Some(spatial_index_cache) = &self.spatial_index_cache
+            && !evicted.is_empty()
+        {
+            let mut spatial_index_cache = spatial_index_cache
+                .lock()
+                .map_err(|e| GraphTileProviderError::PoisonedCacheLock(e.to_string()))?;
+            for graph_id in evicted {
+                spatial_index_cache.pop(&graph_id);
+            }
+        }
+
         Ok(tile)
     }
+
+    fn read_tile(
+        &self,
+        base_graph_id: GraphId,
+    ) -> Result<Arc<OwnedGraphTileHandle>, GraphTileProviderError> {
+        let path = self.path_for_graph_id(base_graph_id)?;
+        // Open the file and read all bytes into a buffer
+        // NOTE: Does not handle compressed tiles
+        let data = std::fs::read(path).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => GraphTileProviderError::TileDoesNotExist,
+            _ => GraphTileProviderError::IoError(e),
+        })?;
+        let tile = OwnedGraphTileHandle::try_from(data)?;
+        Ok(Arc::new(tile))
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::DirectoryGraphTileProvider;
     use crate::GraphId;
-    use crate::graph_tile::GraphTile;
+    use crate::graph_tile::{GraphTile, GraphTileBuildError};
     use crate::tile_hierarchy::STANDARD_LEVELS;
-    use crate::tile_provider::{GraphTileProvider, OwnedGraphTileProvider};
+    use crate::tile_provider::metrics::testing::CountingMetrics;
+    use crate::tile_provider::{
+        CachePolicy, GraphTileProvider, GraphTileProviderError, OwnedGraphTileProvider,
+    };
     use core::num::NonZeroUsize;
     use rand::{
         distr::{Distribution, Uniform},
@@ -213,6 +463,7 @@ mod test {
     };
     use std::collections::HashSet;
     use std::path::PathBuf;
+    use std::sync::Arc;
 
     #[test]
     fn test_get_tile() {
@@ -230,6 +481,156 @@ mod test {
         assert_eq!(tile.header().graph_id().value(), graph_id.value());
     }
 
+    #[test]
+    fn test_byte_budgeted_cache() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles");
+        let l0_id = GraphId::try_from_components(0, 3015, 0).unwrap();
+        let l2_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+        let l0_size = std::fs::metadata(base.join(l0_id.file_path("gph").unwrap()))
+            .unwrap()
+            .len();
+        let provider = DirectoryGraphTileProvider::with_cache_policy(
+            base,
+            CachePolicy::MaxBytes(NonZeroUsize::new(usize::try_from(l0_size).unwrap()).unwrap()),
+        );
+
+        provider.get_handle_for_tile_containing(l0_id).unwrap();
+        provider.get_handle_for_tile_containing(l0_id).unwrap();
+        provider.get_handle_for_tile_containing(l0_id).unwrap();
+        // The level 2 tile doesn't fit alongside the level 0 tile
+        provider.get_handle_for_tile_containing(l2_id).unwrap();
+
+        let stats = provider.cache_stats().unwrap();
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.misses, 2);
+        assert_eq!(stats.evictions, 1);
+        assert_eq!(stats.cached_tiles, 1);
+    }
+
+    #[test]
+    #[cfg(feature = "spatial-index")]
+    fn test_spatial_indexes_are_evicted_with_their_tile() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles");
+        let l0_id = GraphId::try_from_components(0, 3015, 0).unwrap();
+        let l2_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+        let provider =
+            DirectoryGraphTileProvider::new(base, NonZeroUsize::MIN).with_edge_spatial_index();
+
+        let index = provider.edge_spatial_index(l0_id).unwrap().unwrap();
+        let cached = provider.edge_spatial_index(l0_id).unwrap().unwrap();
+        assert!(Arc::ptr_eq(&index, &cached));
+        // Only one tile fits in the cache
+        provider.get_handle_for_tile_containing(l2_id).unwrap();
+
+        assert!(
+            provider
+                .spatial_index_cache
+                .as_ref()
+                .unwrap()
+                .lock()
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_metrics() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles");
+        let metrics = Arc::new(CountingMetrics::default());
+        let provider =
+            DirectoryGraphTileProvider::new(base, NonZeroUsize::MIN).with_metrics(metrics.clone());
+
+        let l0_id = GraphId::try_from_components(0, 3015, 0).unwrap();
+        let l2_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+        let l0_size = provider
+            .get_handle_for_tile_containing(l0_id)
+            .unwrap()
+            .borrow_owner()
+            .len();
+        provider.get_handle_for_tile_containing(l0_id).unwrap();
+        let l2_size = provider
+            .get_handle_for_tile_containing(l2_id)
+            .unwrap()
+            .borrow_owner()
+            .len();
+        assert!(
+            provider
+                .get_handle_for_tile_containing(GraphId::try_from_components(2, 0, 0).unwrap())
+                .is_err()
+        );
+
+        // Hits, misses, evictions, fetches, failures, and bytes read
+        assert_eq!(metrics.counts(), (1, 3, 1, 2, 1, l0_size + l2_size));
+    }
+
+    /// Copies the fixture tiles to a fresh temporary directory, so they can be modified.
+    #[cfg(not(miri))]
+    fn copy_fixture_tiles(name: &str) -> PathBuf {
+        let source = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles");
+        let target = std::env::temp_dir().join(format!("valinor-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&target);
+        for entry in walkdir::WalkDir::new(&source) {
+            let entry = entry.unwrap();
+            let path = target.join(entry.path().strip_prefix(&source).unwrap());
+            if entry.file_type().is_dir() {
+                std::fs::create_dir_all(path).unwrap();
+            } else {
+                std::fs::copy(entry.path(), path).unwrap();
+            }
+        }
+        target
+    }
+
+    #[test]
+    #[cfg(not(miri))]
+    fn test_update_tile() {
+        let base = copy_fixture_tiles("update-tile");
+        let provider = DirectoryGraphTileProvider::new(base.clone(), NonZeroUsize::new(4).unwrap());
+        let graph_id = GraphId::try_from_components(2, 762_485, 7).unwrap();
+        let original = provider.get_handle_for_tile_containing(graph_id).unwrap();
+
+        provider
+            .update_tile(graph_id, |builder| Ok(builder.with_dataset_id(42)))
+            .unwrap();
+
+        // The cached copy is replaced, and the change is persisted
+        let tile = provider.get_handle_for_tile_containing(graph_id).unwrap();
+        assert_eq!(tile.header().dataset_id.get(), 42);
+        assert_eq!(tile.nodes().len(), original.nodes().len());
+        let reopened = DirectoryGraphTileProvider::new(base.clone(), NonZeroUsize::MIN);
+        let tile = reopened.get_handle_for_tile_containing(graph_id).unwrap();
+        assert_eq!(tile.header().dataset_id.get(), 42);
+
+        // Nothing is written if the edit fails
+        assert!(matches!(
+            provider.update_tile(graph_id, |builder| builder
+                .with_version("x".repeat(17).as_str())),
+            Err(GraphTileBuildError::InvalidVersionString(_))
+        ));
+        let tile = reopened.get_handle_for_tile_containing(graph_id).unwrap();
+        assert_eq!(tile.header().dataset_id.get(), 42);
+
+        assert!(matches!(
+            provider.update_tile(
+                GraphId::try_from_components(2, 0, 0).unwrap(),
+                |builder| Ok(builder)
+            ),
+            Err(GraphTileBuildError::LoadError(
+                GraphTileProviderError::TileDoesNotExist
+            ))
+        ));
+
+        std::fs::remove_dir_all(base).unwrap();
+    }
+
     #[test]
     fn test_get_opp_edge() {
         let mut rng = rng();
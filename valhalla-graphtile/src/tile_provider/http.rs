@@ -0,0 +1,834 @@
+use super::tarball_index::index_tiles;
+use super::{
+    GraphTileProvider, GraphTileProviderError, LockTable, OwnedGraphTileProvider,
+    TileProviderMetrics,
+};
+use crate::GraphId;
+use crate::graph_tile::{GraphTileView, OwnedGraphTileHandle, TileOffset};
+use crate::spatial::bbox_with_center;
+use crate::tile_hierarchy::tiles_for_bbox;
+use geo::{CoordFloat, Point};
+use lru::LruCache;
+use num_traits::FromPrimitive;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::num::NonZeroUsize;
+use std::ops::Range;
+use std::path::Component;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use ureq::Agent;
+use ureq::http::Uri;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// The largest tiles in a planet build are tens of megabytes,
+/// so this leaves plenty of headroom while keeping a misbehaving server from exhausting memory.
+const DEFAULT_MAX_RESPONSE_SIZE: u64 = 256 * 1024 * 1024;
+/// The size of a tar header block (entries are also padded to a multiple of this).
+const TAR_BLOCK_SIZE: u64 = 512;
+
+/// A source of raw tile bytes for an [`HttpTileProvider`].
+///
+/// Implement this to fetch tiles with the HTTP client or object store SDK of your choice
+/// (ex: for authenticated S3 buckets).
+/// [`HttpFetcher`] is the built-in implementation for HTTP(S) servers.
+pub trait TileFetcher {
+    /// Fetches the object at a path relative to the tileset root (ex: `2/000/762/485.gph`).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Ok(None)` if the object does not exist,
+    /// and an error if it could not be fetched.
+    /// Transient failures should be reported as [`GraphTileProviderError::TileFetchError`]
+    /// or an I/O error so that callers can retry.
+    fn fetch(&self, path: &str) -> Result<Option<Vec<u8>>, GraphTileProviderError>;
+
+    /// Fetches a range of bytes from the object at a path relative to the tileset root.
+    ///
+    /// This is how tiles are read out of a remote tarball (see [`HttpTileProvider::from_tarball`]).
+    /// The default implementation doesn't support ranges.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Ok(None)` if the object does not exist,
+    /// and an error if the range could not be fetched
+    /// (including when it extends past the end of the object).
+    fn fetch_range(
+        &self,
+        _path: &str,
+        _range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, GraphTileProviderError> {
+        Err(std::io::Error::new(
+            ErrorKind::Unsupported,
+            "This fetcher doesn't support range requests",
+        )
+        .into())
+    }
+}
+
+/// An HTTP(S) client for fetching tiles from a static file server or public bucket.
+///
+/// Redirects are followed, and `https://` URLs are verified against the Mozilla root certificates.
+/// Responses are limited to 256 MiB by default (see [`HttpFetcher::with_max_response_size`]),
+/// so a misbehaving server can't exhaust memory.
+/// For authentication or other transports, implement [`TileFetcher`] instead.
+#[derive(Debug, Clone)]
+pub struct HttpFetcher {
+    agent: Agent,
+    base_url: String,
+    max_response_size: u64,
+}
+
+impl HttpFetcher {
+    /// Creates a fetcher for tiles under the given base URL (ex: `https://tiles.example.com/planet/`).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the URL is not a valid `http://` or `https://` URL.
+    pub fn new(base_url: &str) -> Result<Self, GraphTileProviderError> {
+        let uri = Uri::try_from(base_url)
+            .map_err(|e| GraphTileProviderError::InvalidUrl(format!("{base_url} ({e})")))?;
+        if !matches!(uri.scheme_str(), Some("http" | "https")) {
+            return Err(GraphTileProviderError::InvalidUrl(format!(
+                "{base_url} (only http:// and https:// URLs are supported; implement TileFetcher for other schemes)"
+            )));
+        }
+        let Some(authority) = uri
+            .authority()
+            .filter(|authority| !authority.host().is_empty())
+        else {
+            return Err(GraphTileProviderError::InvalidUrl(format!(
+                "{base_url} (missing host)"
+            )));
+        };
+        // The URI parser accepts any port, but only reads numeric ones
+        let port = authority
+            .as_str()
+            .rsplit_once(authority.host())
+            .map_or("", |(_, port)| port);
+        if !port.is_empty() && authority.port_u16().is_none() {
+            return Err(GraphTileProviderError::InvalidUrl(format!(
+                "{base_url} (invalid port)"
+            )));
+        }
+
+        Ok(Self {
+            agent: agent_with_timeout(DEFAULT_TIMEOUT),
+            base_url: format!("{}/", base_url.trim_end_matches('/')),
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+        })
+    }
+
+    /// Sets the timeout for each request, including reading the response (defaults to 30 seconds).
+    #[must_use]
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self {
+            agent: agent_with_timeout(timeout),
+            ..self
+        }
+    }
+
+    /// Sets the maximum size of a response body in bytes (defaults to 256 MiB).
+    ///
+    /// Larger responses fail with an I/O error of kind [`ErrorKind::FileTooLarge`].
+    #[must_use]
+    pub fn with_max_response_size(self, max_response_size: u64) -> Self {
+        Self {
+            max_response_size,
+            ..self
+        }
+    }
+
+    /// The URL which tile paths are relative to (always ending with a slash).
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn get(
+        &self,
+        path: &str,
+        range: Option<Range<u64>>,
+    ) -> Result<Option<Vec<u8>>, GraphTileProviderError> {
+        let mut request = self.agent.get(format!("{}{path}", self.base_url));
+        if let Some(range) = &range {
+            if range.is_empty() {
+                return Ok(Some(Vec::new()));
+            }
+            request = request.header("Range", format!("bytes={}-{}", range.start, range.end - 1));
+        }
+
+        let response = request.call().map_err(fetch_error)?;
+        match (response.status().as_u16(), &range) {
+            (200, None) | (206, Some(_)) => {}
+            (404, _) => return Ok(None),
+            (200, Some(_)) => {
+                return Err(std::io::Error::new(
+                    ErrorKind::Unsupported,
+                    format!("The server doesn't support range requests for {path}"),
+                )
+                .into());
+            }
+            (status, _) => {
+                return Err(GraphTileProviderError::TileFetchError(format!(
+                    "HTTP {status} fetching {path}"
+                )));
+            }
+        }
+
+        let body = response
+            .into_body()
+            .with_config()
+            // The limit is exclusive (it fails if there are any bytes left to read after it)
+            .limit(self.max_response_size.saturating_add(1))
+            .read_to_vec()
+            .map_err(fetch_error)?;
+        if let Some(range) = range
+            && body.len() as u64 != range.end - range.start
+        {
+            return Err(GraphTileProviderError::TileFetchError(format!(
+                "Expected {} bytes from {path}, but received {}",
+                range.end - range.start,
+                body.len()
+            )));
+        }
+
+        Ok(Some(body))
+    }
+}
+
+fn agent_with_timeout(timeout: Duration) -> Agent {
+    Agent::config_builder()
+        .timeout_global(Some(timeout))
+        .http_status_as_error(false)
+        .user_agent("valinor")
+        .build()
+        .into()
+}
+
+fn fetch_error(error: ureq::Error) -> GraphTileProviderError {
+    match error {
+        ureq::Error::Io(e) => e.into(),
+        ureq::Error::BodyExceedsLimit(limit) => std::io::Error::new(
+            ErrorKind::FileTooLarge,
+            format!("The response was larger than the {limit} byte limit"),
+        )
+        .into(),
+        e => GraphTileProviderError::TileFetchError(e.to_string()),
+    }
+}
+
+impl TileFetcher for HttpFetcher {
+    fn fetch(&self, path: &str) -> Result<Option<Vec<u8>>, GraphTileProviderError> {
+        self.get(path, None)
+    }
+
+    fn fetch_range(
+        &self,
+        path: &str,
+        range: Range<u64>,
+    ) -> Result<Option<Vec<u8>>, GraphTileProviderError> {
+        self.get(path, Some(range))
+    }
+}
+
+/// Where the tiles of a remote tileset are stored.
+enum TileLayout {
+    /// One object per tile, at its usual relative path (ex: `2/000/762/485.gph`).
+    Directory,
+    /// A single tarball, with an `index.bin` locating each tile.
+    Tarball {
+        path: String,
+        tile_index: HashMap<GraphId, TileOffset>,
+    },
+}
+
+/// Reads the size of the `index.bin` entry from the first tar header of an extract.
+fn index_bin_size(header: &[u8]) -> Result<u64, GraphTileProviderError> {
+    let field = |range: Range<usize>| {
+        let bytes = header.get(range).unwrap_or_default();
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+    };
+
+    if field(0..100) != "index.bin" {
+        return Err(GraphTileProviderError::InvalidTarball(
+            "Expected index.bin at the start of the archive".to_string(),
+        ));
+    }
+    let size = field(124..136);
+    u64::from_str_radix(&size, 8).map_err(|_| {
+        GraphTileProviderError::InvalidTarball(format!("Invalid index.bin size: {size:?}"))
+    })
+}
+
+/// A graph tile provider which fetches tiles from a remote tileset.
+///
+/// Tilesets can either be an unpacked tile directory,
+/// where tiles are fetched by their usual relative path (ex: `2/000/762/485.gph`),
+/// or a tarball extract, where tiles are fetched with range requests
+/// using the extract's `index.bin` (see [`HttpTileProvider::from_tarball`]).
+/// Either way, any static file server or object store bucket will do.
+/// This lets services run without shipping the whole extract to local disk.
+///
+/// # Resource consumption
+///
+/// Every fetch is a network round trip, so this includes an internal LRU cache
+/// with a configurable max number of tiles.
+/// Missing tiles are cached too, so that repeated lookups at the edge of an extract
+/// don't hammer the server.
+/// Concurrent requests for the same tile wait for a single fetch.
+pub struct HttpTileProvider<F = HttpFetcher> {
+    fetcher: F,
+    layout: TileLayout,
+    lock_table: LockTable<GraphId>,
+    lru_cache: Mutex<LruCache<GraphId, Option<Arc<OwnedGraphTileHandle>>>>,
+    metrics: Option<Arc<dyn TileProviderMetrics>>,
+}
+
+impl HttpTileProvider<HttpFetcher> {
+    /// Creates a provider for a tile directory served over HTTP(S) (see [`HttpFetcher`]).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the URL is not a valid `http://` or `https://` URL.
+    pub fn from_base_url(
+        base_url: &str,
+        num_cached_tiles: NonZeroUsize,
+    ) -> Result<Self, GraphTileProviderError> {
+        Ok(Self::new(HttpFetcher::new(base_url)?, num_cached_tiles))
+    }
+
+    /// Creates a provider for a tarball extract served over HTTP(S)
+    /// (ex: `https://tiles.example.com/planet/valhalla_tiles.tar`).
+    ///
+    /// See [`HttpTileProvider::from_tarball`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the URL is not a valid `http://` or `https://` URL,
+    /// or the extract's index can't be fetched.
+    pub fn from_tarball_url(
+        url: &str,
+        num_cached_tiles: NonZeroUsize,
+    ) -> Result<Self, GraphTileProviderError> {
+        let Some((base_url, file_name)) = url.rsplit_once('/').filter(|(_, name)| !name.is_empty())
+        else {
+            return Err(GraphTileProviderError::InvalidUrl(format!(
+                "{url} (expected the URL of a tarball)"
+            )));
+        };
+
+        Self::from_tarball(HttpFetcher::new(base_url)?, file_name, num_cached_tiles)
+    }
+}
+
+impl<F: TileFetcher> HttpTileProvider<F> {
+    /// Creates a provider for a tile directory (see [`TileFetcher::fetch`]).
+    pub fn new(fetcher: F, num_cached_tiles: NonZeroUsize) -> Self {
+        Self {
+            fetcher,
+            layout: TileLayout::Directory,
+            lock_table: LockTable::new(),
+            lru_cache: Mutex::new(LruCache::new(num_cached_tiles)),
+            metrics: None,
+        }
+    }
+
+    /// Creates a provider for a tarball extract at the given path.
+    ///
+    /// Like [`TarballTileProvider`](super::TarballTileProvider), this requires an `index.bin`
+    /// at the start of the archive, which is fetched up front.
+    /// Each tile is then fetched with a range request (see [`TileFetcher::fetch_range`]),
+    /// so the rest of the extract never needs to be downloaded.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the index can't be fetched or is invalid.
+    pub fn from_tarball(
+        fetcher: F,
+        path: &str,
+        num_cached_tiles: NonZeroUsize,
+    ) -> Result<Self, GraphTileProviderError> {
+        let missing = || {
+            GraphTileProviderError::from(std::io::Error::new(
+                ErrorKind::NotFound,
+                format!("{path} does not exist"),
+            ))
+        };
+        let header = fetcher
+            .fetch_range(path, 0..TAR_BLOCK_SIZE)?
+            .ok_or_else(missing)?;
+        let index_size = index_bin_size(&header)?;
+        let index_bytes = fetcher
+            .fetch_range(path, TAR_BLOCK_SIZE..TAR_BLOCK_SIZE + index_size)?
+            .ok_or_else(missing)?;
+
+        Ok(Self {
+            layout: TileLayout::Tarball {
+                path: path.to_string(),
+                tile_index: index_tiles(&index_bytes)?,
+            },
+            ..Self::new(fetcher, num_cached_tiles)
+        })
+    }
+
+    /// Reports cache and fetch activity to the given hooks (see [`TileProviderMetrics`]).
+    ///
+    /// Lookups of missing tiles are reported as failed fetches the first time,
+    /// and as cache hits after that.
+    #[must_use]
+    pub fn with_metrics(self, metrics: Arc<dyn TileProviderMetrics>) -> Self {
+        Self {
+            metrics: Some(metrics),
+            ..self
+        }
+    }
+
+    /// The fetcher used to retrieve tiles.
+    pub fn fetcher(&self) -> &F {
+        &self.fetcher
+    }
+
+    fn fetch_tile(
+        &self,
+        base_graph_id: GraphId,
+    ) -> Result<Option<Arc<OwnedGraphTileHandle>>, GraphTileProviderError> {
+        let data = match &self.layout {
+            TileLayout::Directory => {
+                // Object keys always use forward slashes, regardless of the platform's path separator
+                let path = base_graph_id
+                    .file_path("gph")?
+                    .components()
+                    .filter_map(|component| match component {
+                        Component::Normal(part) => part.to_str(),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("/");
+                self.fetcher.fetch(&path)?
+            }
+            TileLayout::Tarball { path, tile_index } => {
+                let Some(&TileOffset { offset, size }) = tile_index.get(&base_graph_id) else {
+                    return Ok(None);
+                };
+                // The index says the tile exists, so a missing extract is a (hopefully transient) failure
+                let data = self
+                    .fetcher
+                    .fetch_range(path, offset..offset + u64::from(size))?
+                    .ok_or_else(|| {
+                        GraphTileProviderError::TileFetchError(format!("{path} does not exist"))
+                    })?;
+                Some(data)
+            }
+        };
+
+        data.map(|data| Ok(Arc::new(OwnedGraphTileHandle::try_from(data)?)))
+            .transpose()
+    }
+}
+
+impl<F: TileFetcher> GraphTileProvider for HttpTileProvider<F> {
+    #[inline]
+    fn with_tile_containing<Fn, T>(
+        &self,
+        graph_id: GraphId,
+        process: Fn,
+    ) -> Result<T, GraphTileProviderError>
+    where
+        Fn: FnOnce(&GraphTileView) -> T,
+    {
+        let tile = self.get_handle_for_tile_containing(graph_id)?;
+        Ok(process(tile.borrow_dependent()))
+    }
+
+    /// Tarballs are checked against their index, without fetching anything.
+    ///
+    /// Remote tile directories have no index, so this fetches every candidate tile
+    /// which isn't cached yet, one at a time, blocking on the network.
+    /// This can take a while on a cold cache with a large radius,
+    /// but the tiles are cached, so the lookups which usually follow are cheap.
+    fn enumerate_tiles_within_radius<N: CoordFloat + FromPrimitive>(
+        &self,
+        center: Point<N>,
+        radius: N,
+    ) -> Vec<GraphId> {
+        let (north, east, south, west) = bbox_with_center(center, radius);
+
+        let tiles = tiles_for_bbox(north, east, south, west).into_iter();
+        match &self.layout {
+            TileLayout::Directory => tiles
+                .filter(|&gid| self.get_handle_for_tile_containing(gid).is_ok())
+                .collect(),
+            TileLayout::Tarball { tile_index, .. } => {
+                tiles.filter(|gid| tile_index.contains_key(gid)).collect()
+            }
+        }
+    }
+
+    /// Tarballs list the tiles in their index.
+    ///
+    /// Remote tile directories can't be listed, and probing every possible tile ID
+    /// over the network isn't practical, so this always fails for them.
+    fn available_tiles(&self) -> Result<Vec<GraphId>, GraphTileProviderError> {
+        match &self.layout {
+            TileLayout::Directory => Err(std::io::Error::new(
+                ErrorKind::Unsupported,
+                "Remote tile directories can't be listed; enumerate tiles from the source tileset instead",
+            )
+            .into()),
+            TileLayout::Tarball { tile_index, .. } => {
+                let mut tiles: Vec<_> = tile_index.keys().copied().collect();
+                tiles.sort_by_key(|graph_id| (graph_id.level(), graph_id.tile_id()));
+                Ok(tiles)
+            }
+        }
+    }
+}
+
+impl<F: TileFetcher> OwnedGraphTileProvider for HttpTileProvider<F> {
+    fn get_handle_for_tile_containing(
+        &self,
+        graph_id: GraphId,
+    ) -> Result<Arc<OwnedGraphTileHandle>, GraphTileProviderError> {
+        let base_graph_id = graph_id.tile_base_id();
+        let lock = self.lock_table.lock_for(base_graph_id);
+        let _guard = lock.lock();
+
+        let cached = self
+            .lru_cache
+            .lock()
+            .map_err(|e| GraphTileProviderError::PoisonedCacheLock(e.to_string()))?
+            .get(&base_graph_id)
+            .cloned();
+        // Don't hold the cache lock during the fetch, so other tiles can be served in the meantime
+        let tile = if let Some(tile) = cached {
+            if let Some(metrics) = &self.metrics {
+                metrics.cache_hit(base_graph_id);
+            }
+            tile
+        } else {
+            let start = Instant::now();
+            let result = self.fetch_tile(base_graph_id);
+            if let Some(metrics) = &self.metrics {
+                metrics.cache_miss(base_graph_id);
+                match &result {
+                    Ok(Some(tile)) => metrics.tile_fetched(
+                        base_graph_id,
+                        tile.borrow_owner().len(),
+                        start.elapsed(),
+                    ),
+                    Ok(None) => metrics.fetch_failed(
+                        base_graph_id,
+                        &GraphTileProviderError::TileDoesNotExist,
+                        start.elapsed(),
+                    ),
+                    Err(e) => metrics.fetch_failed(base_graph_id, e, start.elapsed()),
+                }
+            }
+
+            let tile = result?;
+            let evicted = self
+                .lru_cache
+                .lock()
+                .map_err(|e| GraphTileProviderError::PoisonedCacheLock(e.to_string()))?
+                .push(base_graph_id, tile.clone());
+            if let Some(metrics) = &self.metrics
+                && let Some((evicted_id, _)) = evicted
+                && evicted_id != base_graph_id
+            {
+                metrics.cache_eviction(evicted_id);
+            }
+            tile
+        };
+
+        tile.ok_or(GraphTileProviderError::TileDoesNotExist)
+    }
+}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    use super::{HttpFetcher, HttpTileProvider, TileFetcher, index_bin_size};
+    use crate::GraphId;
+    use crate::graph_tile::GraphTile;
+    use crate::tile_provider::metrics::testing::CountingMetrics;
+    use crate::tile_provider::{GraphTileProvider, GraphTileProviderError, OwnedGraphTileProvider};
+    use geo::point;
+    use std::io::{BufRead, BufReader, ErrorKind, Write};
+    use std::net::TcpListener;
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn fixtures() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures")
+    }
+
+    /// Serves the fixture tiles under `/tiles/` (and the fixture extract under `/extracts/`),
+    /// returning the server's address and a counter of requests served.
+    fn serve_fixtures() -> (String, Arc<AtomicUsize>) {
+        let fixtures = fixtures();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let request_count = Arc::new(AtomicUsize::new(0));
+
+        let counter = request_count.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut range = None;
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    if let Some((name, value)) = line.split_once(':')
+                        && name.eq_ignore_ascii_case("range")
+                    {
+                        let (start, end) = value
+                            .trim()
+                            .strip_prefix("bytes=")
+                            .and_then(|range| range.split_once('-'))
+                            .unwrap();
+                        range = Some(
+                            start.parse::<usize>().unwrap()..end.parse::<usize>().unwrap() + 1,
+                        );
+                    }
+                    line.clear();
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+
+                let path = request_line.split_whitespace().nth(1).unwrap();
+                let file = path
+                    .strip_prefix("/tiles/")
+                    .map(|path| fixtures.join("andorra-tiles").join(path))
+                    .or_else(|| {
+                        path.strip_prefix("/extracts/")
+                            .map(|path| fixtures.join(path))
+                    })
+                    .and_then(|path| std::fs::read(path).ok());
+                match (file, range) {
+                    (Some(body), Some(range)) => {
+                        write!(
+                            stream,
+                            "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                            range.start,
+                            range.end - 1,
+                            body.len(),
+                            range.len()
+                        )
+                        .unwrap();
+                        stream.write_all(&body[range]).unwrap();
+                    }
+                    // Exercise both framing styles
+                    (Some(body), None) if path.starts_with("/tiles/2/") => {
+                        write!(
+                            stream,
+                            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n"
+                        )
+                        .unwrap();
+                        for chunk in body.chunks(4096) {
+                            write!(stream, "{:x}\r\n", chunk.len()).unwrap();
+                            stream.write_all(chunk).unwrap();
+                            stream.write_all(b"\r\n").unwrap();
+                        }
+                        stream.write_all(b"0\r\n\r\n").unwrap();
+                    }
+                    (Some(body), None) => {
+                        write!(
+                            stream,
+                            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                            body.len()
+                        )
+                        .unwrap();
+                        stream.write_all(&body).unwrap();
+                    }
+                    (None, _) => {
+                        stream
+                            .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                            .unwrap();
+                    }
+                }
+            }
+        });
+
+        (address, request_count)
+    }
+
+    #[test]
+    fn test_get_tile() {
+        let (address, request_count) = serve_fixtures();
+        let metrics = Arc::new(CountingMetrics::default());
+        let provider = HttpTileProvider::from_base_url(
+            &format!("{address}/tiles"),
+            NonZeroUsize::new(4).unwrap(),
+        )
+        .unwrap()
+        .with_metrics(metrics.clone());
+
+        for (level, tile_id) in [(0, 3015), (2, 762_485)] {
+            let graph_id = GraphId::try_from_components(level, tile_id, 0).unwrap();
+            let tile = provider.get_handle_for_tile_containing(graph_id).unwrap();
+            assert_eq!(tile.header().graph_id(), graph_id);
+        }
+        assert_eq!(request_count.load(Ordering::SeqCst), 2);
+
+        // Cached tiles (and missing ones) don't need another request
+        let missing = GraphId::try_from_components(2, 0, 0).unwrap();
+        for _ in 0..2 {
+            assert!(
+                provider
+                    .get_handle_for_tile_containing(
+                        GraphId::try_from_components(0, 3015, 1).unwrap()
+                    )
+                    .is_ok()
+            );
+            assert!(matches!(
+                provider.get_handle_for_tile_containing(missing),
+                Err(GraphTileProviderError::TileDoesNotExist)
+            ));
+        }
+        assert_eq!(request_count.load(Ordering::SeqCst), 3);
+
+        // Hits, misses, evictions, fetches, and failures
+        let (hits, misses, evictions, fetches, failures, _) = metrics.counts();
+        assert_eq!(
+            (hits, misses, evictions, fetches, failures),
+            (3, 3, 0, 2, 1)
+        );
+    }
+
+    #[test]
+    fn test_get_tile_from_tarball() {
+        let (address, request_count) = serve_fixtures();
+        let provider = HttpTileProvider::from_tarball_url(
+            &format!("{address}/extracts/andorra-tiles.tar"),
+            NonZeroUsize::new(16).unwrap(),
+        )
+        .unwrap();
+        // The tar header and the index
+        assert_eq!(request_count.load(Ordering::SeqCst), 2);
+
+        // Checking which tiles exist doesn't need any requests
+        let tiles = provider.available_tiles().unwrap();
+        assert!(!tiles.is_empty());
+        assert!(
+            !provider
+                .enumerate_tiles_within_radius(point!(x: 1.5, y: 42.5), 10_000.0)
+                .is_empty()
+        );
+        assert_eq!(request_count.load(Ordering::SeqCst), 2);
+
+        for &graph_id in &tiles {
+            let tile = provider.get_handle_for_tile_containing(graph_id).unwrap();
+            let expected = std::fs::read(
+                fixtures()
+                    .join("andorra-tiles")
+                    .join(graph_id.file_path("gph").unwrap()),
+            )
+            .unwrap();
+            assert_eq!(&tile.borrow_owner()[..], &expected[..]);
+        }
+        assert_eq!(request_count.load(Ordering::SeqCst), 2 + tiles.len());
+
+        assert!(matches!(
+            provider.get_handle_for_tile_containing(GraphId::try_from_components(2, 0, 0).unwrap()),
+            Err(GraphTileProviderError::TileDoesNotExist)
+        ));
+        assert_eq!(request_count.load(Ordering::SeqCst), 2 + tiles.len());
+
+        assert!(
+            HttpTileProvider::from_tarball_url(
+                &format!("{address}/extracts/missing.tar"),
+                NonZeroUsize::MIN
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_max_response_size() {
+        let (address, _) = serve_fixtures();
+        let fetcher = HttpFetcher::new(&format!("{address}/tiles"))
+            .unwrap()
+            .with_max_response_size(1024);
+        for path in ["0/003/015.gph", "2/000/762/485.gph"] {
+            let error = fetcher.fetch(path).unwrap_err();
+            assert!(
+                matches!(&error, GraphTileProviderError::IoError(e) if e.kind() == ErrorKind::FileTooLarge),
+                "{error}"
+            );
+            assert!(!error.is_retryable());
+        }
+        assert_eq!(
+            fetcher
+                .fetch_range("0/003/015.gph", 0..1024)
+                .unwrap()
+                .unwrap()
+                .len(),
+            1024
+        );
+    }
+
+    #[test]
+    fn test_parse_base_url() {
+        let fetcher = HttpFetcher::new("http://tiles.internal:8080/planet").unwrap();
+        assert_eq!(fetcher.base_url(), "http://tiles.internal:8080/planet/");
+
+        let fetcher = HttpFetcher::new("https://[::1]/").unwrap();
+        assert_eq!(fetcher.base_url(), "https://[::1]/");
+        assert!(HttpFetcher::new("http://user:secret@[::1]:8080").is_ok());
+
+        for url in [
+            "ftp://example.com",
+            "http://",
+            "http://example.com:port/",
+            "tiles",
+        ] {
+            assert!(matches!(
+                HttpFetcher::new(url),
+                Err(GraphTileProviderError::InvalidUrl(_))
+            ));
+        }
+        assert!(matches!(
+            HttpTileProvider::from_tarball_url("https://example.com/tiles/", NonZeroUsize::MIN),
+            Err(GraphTileProviderError::InvalidUrl(_))
+        ));
+    }
+
+    #[test]
+    fn test_index_bin_size() {
+        let header = std::fs::read(fixtures().join("andorra-tiles.tar")).unwrap();
+        let size = index_bin_size(&header[..512]).unwrap();
+        assert!(size > 0 && size.is_multiple_of(16));
+
+        assert!(matches!(
+            index_bin_size(&[0; 512]),
+            Err(GraphTileProviderError::InvalidTarball(_))
+        ));
+    }
+
+    #[test]
+    fn test_custom_fetcher() {
+        struct MapFetcher;
+        impl TileFetcher for MapFetcher {
+            fn fetch(&self, path: &str) -> Result<Option<Vec<u8>>, GraphTileProviderError> {
+                assert_eq!(path, "0/003/015.gph");
+                Ok(Some(std::fs::read(
+                    fixtures().join("andorra-tiles").join(path),
+                )?))
+            }
+        }
+
+        let provider = HttpTileProvider::new(MapFetcher, NonZeroUsize::MIN);
+        let graph_id = GraphId::try_from_components(0, 3015, 0).unwrap();
+        let tile = provider.get_handle_for_tile_containing(graph_id).unwrap();
+        assert_eq!(tile.graph_id(), graph_id);
+
+        // Range requests aren't supported unless the fetcher implements them
+        assert!(matches!(
+            HttpTileProvider::from_tarball(MapFetcher, "andorra-tiles.tar", NonZeroUsize::MIN),
+            Err(GraphTileProviderError::IoError(e)) if e.kind() == ErrorKind::Unsupported
+        ));
+    }
+}
@@ -0,0 +1,103 @@
+use super::GraphTileProviderError;
+use crate::GraphId;
+use std::time::Duration;
+
+/// Hooks for observing tile cache and fetch activity.
+///
+/// Implement this to feed your metrics system of choice (Prometheus, `StatsD`, etc.),
+/// and attach it to a provider (ex: [`DirectoryGraphTileProvider::with_metrics`](super::DirectoryGraphTileProvider::with_metrics)).
+/// This makes it possible to see whether the cache is sized adequately in production
+/// (ex: a high eviction rate or slow fetches mean that it is too small).
+///
+/// Every method has a no-op default, so you only need to implement the ones you care about.
+/// Hooks are called synchronously on the lookup path, so they should be cheap
+/// (ex: incrementing an atomic counter).
+pub trait TileProviderMetrics: Send + Sync {
+    /// Called when a lookup is served from the cache.
+    fn cache_hit(&self, graph_id: GraphId) {
+        let _ = graph_id;
+    }
+
+    /// Called when a lookup has to fetch a tile.
+    fn cache_miss(&self, graph_id: GraphId) {
+        let _ = graph_id;
+    }
+
+    /// Called when a tile is evicted from the cache to make room for another.
+    fn cache_eviction(&self, graph_id: GraphId) {
+        let _ = graph_id;
+    }
+
+    /// Called after a tile is successfully fetched (and decoded),
+    /// with the size of the tile and the time taken.
+    fn tile_fetched(&self, graph_id: GraphId, bytes: usize, latency: Duration) {
+        let _ = (graph_id, bytes, latency);
+    }
+
+    /// Called when fetching a tile fails (including when the tile doesn't exist).
+    fn fetch_failed(&self, graph_id: GraphId, error: &GraphTileProviderError, latency: Duration) {
+        let _ = (graph_id, error, latency);
+    }
+}
+
+#[cfg(all(test, any(feature = "fs", feature = "http")))]
+pub(crate) mod testing {
+    use super::TileProviderMetrics;
+    use crate::GraphId;
+    use crate::tile_provider::GraphTileProviderError;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Counts every event, for checking that providers call the hooks.
+    #[derive(Default)]
+    pub(crate) struct CountingMetrics {
+        pub hits: AtomicU64,
+        pub misses: AtomicU64,
+        pub evictions: AtomicU64,
+        pub fetches: AtomicU64,
+        pub failures: AtomicU64,
+        pub bytes_read: AtomicUsize,
+    }
+
+    impl TileProviderMetrics for CountingMetrics {
+        fn cache_hit(&self, _graph_id: GraphId) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn cache_miss(&self, _graph_id: GraphId) {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn cache_eviction(&self, _graph_id: GraphId) {
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn tile_fetched(&self, _graph_id: GraphId, bytes: usize, _latency: Duration) {
+            self.fetches.fetch_add(1, Ordering::Relaxed);
+            self.bytes_read.fetch_add(bytes, Ordering::Relaxed);
+        }
+
+        fn fetch_failed(
+            &self,
+            _graph_id: GraphId,
+            _error: &GraphTileProviderError,
+            _latency: Duration,
+        ) {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    impl CountingMetrics {
+        /// Hits, misses, evictions, fetches, failures, and bytes read.
+        pub fn counts(&self) -> (u64, u64, u64, u64, u64, usize) {
+            (
+                self.hits.load(Ordering::Relaxed),
+                self.misses.load(Ordering::Relaxed),
+                self.evictions.load(Ordering::Relaxed),
+                self.fetches.load(Ordering::Relaxed),
+                self.failures.load(Ordering::Relaxed),
+                self.bytes_read.load(Ordering::Relaxed),
+            )
+        }
+    }
+}
@@ -5,26 +5,75 @@
 //! due to the fundamental difference in how memory maps work vs file systems.
 
 use crate::GraphId;
+#[cfg(any(feature = "fs", feature = "http"))]
 use dashmap::DashMap;
-use geo::{CoordFloat, Point};
+use geo::{CoordFloat, Distance, Haversine, Point, Rect, coord};
 use num_traits::FromPrimitive;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::ErrorKind;
 use std::sync::Arc;
+#[cfg(any(feature = "fs", feature = "http"))]
 use std::sync::Mutex;
 use thiserror::Error;
 
+#[cfg(feature = "tokio")]
+mod async_provider;
+mod blue_green;
+#[cfg(feature = "fs")]
+mod cache;
+#[cfg(feature = "fs")]
 mod directory;
+#[cfg(feature = "http")]
+mod http;
+mod metrics;
+#[cfg(feature = "fs")]
+mod reloading;
+#[cfg(feature = "spatial-index")]
+mod spatial_index;
+#[cfg(feature = "fs")]
 mod tarball;
+#[cfg(any(feature = "fs", feature = "http"))]
+mod tarball_index;
+#[cfg(feature = "fs")]
 mod traffic;
+#[cfg(feature = "fs")]
+mod traffic_coverage;
+#[cfg(feature = "fs")]
+mod traffic_extract;
+#[cfg(feature = "fs")]
+mod traffic_validation;
 
 use crate::graph_id::InvalidGraphIdError;
 use crate::graph_tile::{
-    GraphNode, GraphTile, GraphTileDecodingError, GraphTileView, LookupError, NodeInfo,
-    OpposingEdgeIndex, OwnedGraphTileHandle,
+    DirectedEdge, GraphNode, GraphTile, GraphTileDecodingError, GraphTileView, LookupError,
+    NodeInfo, NodeTransition, OpposingEdgeIndex, OwnedGraphTileHandle,
 };
+use crate::spatial::{bbox_with_center, closest_point_on_line};
+use crate::tile_hierarchy::{STANDARD_LEVELS, tiles_for_bbox};
+#[cfg(feature = "tokio")]
+pub use async_provider::AsyncGraphTileProvider;
+pub use blue_green::{BlueGreenTileProvider, TILESET_HEADER, Tileset, UnknownTilesetError};
+#[cfg(feature = "fs")]
+pub use cache::{CachePolicy, CacheStats};
+#[cfg(feature = "fs")]
 pub use directory::DirectoryGraphTileProvider;
+#[cfg(feature = "http")]
+pub use http::{HttpFetcher, HttpTileProvider, TileFetcher};
+pub use metrics::TileProviderMetrics;
+#[cfg(feature = "fs")]
+pub use reloading::ReloadingTileProvider;
+#[cfg(feature = "spatial-index")]
+pub use spatial_index::EdgeSpatialIndex;
+#[cfg(feature = "fs")]
 pub use tarball::TarballTileProvider;
+#[cfg(feature = "fs")]
 pub use traffic::TrafficTileProvider;
+#[cfg(feature = "fs")]
+pub use traffic_coverage::{TileTrafficCoverage, TrafficCoverage, traffic_coverage};
+#[cfg(feature = "fs")]
+pub use traffic_extract::{TrafficExtractBuilder, TrafficExtractUpdater};
+#[cfg(feature = "fs")]
+pub use traffic_validation::{TrafficExtractIssue, TrafficExtractReport, validate_traffic_extract};
 
 #[derive(Debug, Error)]
 pub enum GraphTileProviderError {
@@ -46,6 +95,132 @@ pub enum GraphTileProviderError {
     InvalidTarball(String),
     #[error("Unsupported tile version; this may or may not be compatible.")]
     UnsupportedTileVersion,
+    #[error("Invalid tile source URL: {0}")]
+    InvalidUrl(String),
+    #[error("Background tile lookup failed: {0}")]
+    BlockingTaskFailed(String),
+}
+
+impl GraphTileProviderError {
+    /// Could retrying the same operation succeed?
+    ///
+    /// This is true for transient failures like interrupted or timed out I/O,
+    /// and errors fetching tiles from a remote source.
+    /// Services can use this to decide whether to retry a request
+    /// rather than failing it outright.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::TileFetchError(_) => true,
+            Self::IoError(e) => matches!(
+                e.kind(),
+                ErrorKind::Interrupted
+                    | ErrorKind::TimedOut
+                    | ErrorKind::WouldBlock
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::ResourceBusy
+            ),
+            Self::DecodingError(e) => e.is_retryable(),
+            Self::GraphTileLookupError(e) => e.is_retryable(),
+            Self::TileDoesNotExist
+            | Self::InvalidGraphId(_)
+            | Self::PoisonedCacheLock(_)
+            | Self::InvalidTarball(_)
+            | Self::UnsupportedTileVersion
+            | Self::InvalidUrl(_)
+            | Self::BlockingTaskFailed(_) => false,
+        }
+    }
+
+    /// Does this error indicate that the underlying data (tiles or an extract) is corrupt?
+    ///
+    /// These errors generally warrant an alert (and probably a rebuild),
+    /// rather than being blamed on the request.
+    pub fn is_data_corruption(&self) -> bool {
+        match self {
+            Self::IoError(e) => {
+                matches!(e.kind(), ErrorKind::InvalidData | ErrorKind::UnexpectedEof)
+            }
+            Self::DecodingError(e) => e.is_data_corruption(),
+            Self::GraphTileLookupError(e) => e.is_data_corruption(),
+            Self::InvalidTarball(_) => true,
+            Self::TileDoesNotExist
+            | Self::TileFetchError(_)
+            | Self::InvalidGraphId(_)
+            | Self::PoisonedCacheLock(_)
+            | Self::UnsupportedTileVersion
+            | Self::InvalidUrl(_)
+            | Self::BlockingTaskFailed(_) => false,
+        }
+    }
+}
+
+/// A directed edge near a point of interest.
+///
+/// See [`GraphTileProvider::find_nearest_edges`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeCandidate {
+    pub edge_id: GraphId,
+    /// The distance from the search point to the closest point on the edge shape, in meters.
+    pub distance: f64,
+    /// The closest point on the edge shape.
+    pub snapped_point: Point<f64>,
+    /// How far along the edge the snapped point lies, from 0 (start node) to 1 (end node).
+    pub percent_along: f64,
+}
+
+/// Gets the edges in a tile whose shapes may intersect the bbox, using its spatial index.
+///
+/// Returns `None` if the provider doesn't have a spatial index for the tile.
+#[cfg(feature = "spatial-index")]
+fn indexed_edges_in_bbox<P: GraphTileProvider>(
+    provider: &P,
+    tile_id: GraphId,
+    bbox: Rect<f64>,
+) -> Result<Option<Vec<GraphId>>, GraphTileProviderError> {
+    let (min, max) = (bbox.min(), bbox.max());
+    Ok(provider
+        .edge_spatial_index(tile_id)?
+        .map(|index| index.edges_in_bbox(max.y, max.x, min.y, min.x).collect()))
+}
+
+#[cfg(not(feature = "spatial-index"))]
+#[expect(
+    clippy::unnecessary_wraps,
+    reason = "Matches the spatial-index version"
+)]
+fn indexed_edges_in_bbox<P: GraphTileProvider>(
+    _provider: &P,
+    _tile_id: GraphId,
+    _bbox: Rect<f64>,
+) -> Result<Option<Vec<GraphId>>, GraphTileProviderError> {
+    Ok(None)
+}
+
+/// A point snapped to an edge shape, before filtering (see [`GraphTileProvider::locate_many`]).
+struct PendingCandidate {
+    point_index: usize,
+    edge_id: GraphId,
+    snapped_point: Point<f64>,
+    /// The fraction along the shape, in the direction of the edge info.
+    fraction: f64,
+    distance: f64,
+}
+
+impl PendingCandidate {
+    fn into_candidate(self, edge: &DirectedEdge) -> EdgeCandidate {
+        EdgeCandidate {
+            edge_id: self.edge_id,
+            distance: self.distance,
+            snapped_point: self.snapped_point,
+            // The shape is stored in the direction of the edge info
+            percent_along: if edge.edge_info_is_forward() {
+                self.fraction
+            } else {
+                1.0 - self.fraction
+            },
+        }
+    }
 }
 
 pub trait GraphTileProvider {
@@ -86,6 +261,37 @@ pub trait GraphTileProvider {
         radius: N,
     ) -> Vec<GraphId>;
 
+    /// Lists the base graph IDs of every tile in the tileset, ordered by level and then tile ID.
+    ///
+    /// This is useful for processing an entire tileset (ex: exports and validation).
+    ///
+    /// # Performance
+    ///
+    /// The default implementation tries every possible tile ID in the standard hierarchy,
+    /// which means millions of lookups regardless of the size of the tileset.
+    /// Providers which can list their tiles directly (ex: from a directory listing or an index)
+    /// override this so that it scales with the number of tiles present.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the tileset can't be listed, or if a tile lookup fails
+    /// for any reason other than the tile not existing.
+    fn available_tiles(&self) -> Result<Vec<GraphId>, GraphTileProviderError> {
+        let mut tiles = Vec::new();
+        for level in &*STANDARD_LEVELS {
+            for tile_id in 0..level.tiling_system.tile_count() {
+                let graph_id = GraphId::try_from_components(level.level, u64::from(tile_id), 0)?;
+                match self.with_tile_containing(graph_id, |_| ()) {
+                    Ok(()) => tiles.push(graph_id),
+                    Err(GraphTileProviderError::TileDoesNotExist) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        Ok(tiles)
+    }
+
     /// Gets a tile containing the given graph ID, or else panics.
     ///
     /// This is an unfortunately necessary convenience,
@@ -365,6 +571,311 @@ pub trait GraphTileProvider {
         })?
     }
 
+    /// Finds the node corresponding to `node_id` on another level of the hierarchy.
+    ///
+    /// Nodes which exist on multiple levels are linked by transitions
+    /// (see [`GraphTile::get_transitions_for_node`]).
+    /// If there is no direct transition to `target_level`,
+    /// this will follow transitions one level at a time in the direction of the target.
+    ///
+    /// Returns `Ok(None)` if the node does not exist on the target level.
+    /// If the node is already on the target level, `node_id` is returned as-is.
+    ///
+    /// # Errors
+    ///
+    /// This can fail if a tile fails to load,
+    /// or if `node_id` (or the end node of a transition) doesn't exist in its tile.
+    fn transition_node(
+        &self,
+        node_id: GraphId,
+        target_level: u8,
+    ) -> Result<Option<GraphId>, GraphTileProviderError> {
+        let mut current = node_id;
+        while current.level() != target_level {
+            let next = self.with_tile_containing(current, |tile| {
+                let transitions = tile.get_transitions_for_node(current)?;
+
+                // Prefer a direct transition to the target level.
+                // Otherwise, take the transition that gets closest to it (without overshooting).
+                let next = transitions
+                    .iter()
+                    .map(NodeTransition::corresponding_end_node_id)
+                    .filter(|id| {
+                        let level = id.level();
+                        if target_level < current.level() {
+                            (target_level..current.level()).contains(&level)
+                        } else {
+                            (current.level() + 1..=target_level).contains(&level)
+                        }
+                    })
+                    .min_by_key(|id| id.level().abs_diff(target_level));
+                Ok::<_, GraphTileProviderError>(next)
+            })??;
+
+            match next {
+                Some(id) => current = id,
+                None => return Ok(None),
+            }
+        }
+
+        Ok(Some(current))
+    }
+
+    /// Gets the edge spatial index for the tile containing the given graph ID,
+    /// if the provider is configured to maintain one (requires the `spatial-index` feature).
+    ///
+    /// The index is built lazily on first access, and then cached alongside the tile.
+    /// Providers which don't support spatial indexes (or have them disabled)
+    /// return `None`, in which case callers should fall back to the tile's edge bins.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the tile cannot be fetched or the index cannot be built.
+    #[cfg(feature = "spatial-index")]
+    fn edge_spatial_index(
+        &self,
+        graph_id: GraphId,
+    ) -> Result<Option<Arc<EdgeSpatialIndex>>, GraphTileProviderError> {
+        let _ = graph_id;
+        Ok(None)
+    }
+
+    /// Finds the directed edges closest to a point, nearest first.
+    ///
+    /// Candidates are gathered from the edge bins of all local tiles within `radius_in_meters`
+    /// (along with their opposing edges), and then measured against the edge shapes.
+    /// With the `spatial-index` feature, tiles which have an edge spatial index
+    /// are searched with the index instead, which narrows down the candidates much further.
+    /// At most `max_results` edges within the radius which pass the `filter` are returned.
+    /// Opposing edges share a shape, so they will typically be returned as a pair
+    /// (with complementary `percent_along` values).
+    ///
+    /// # Errors
+    ///
+    /// Fails if any tile or edge shape referenced by the bins cannot be loaded.
+    fn find_nearest_edges<F>(
+        &self,
+        point: Point<f64>,
+        radius_in_meters: f64,
+        max_results: usize,
+        mut filter: F,
+    ) -> Result<Vec<EdgeCandidate>, GraphTileProviderError>
+    where
+        F: FnMut(&DirectedEdge) -> bool,
+        Self: Sized,
+    {
+        let (north, east, south, west) = bbox_with_center(point, radius_in_meters);
+        let bbox = Rect::new(coord! {x: west, y: south}, coord! {x: east, y: north});
+        // Valhalla only populates bins in the local level tiles
+        let local_level = STANDARD_LEVELS.last().map(|level| level.level);
+
+        let mut seen = HashSet::new();
+        let mut candidates = Vec::new();
+        for tile_id in self.enumerate_tiles_within_radius(point, radius_in_meters) {
+            let edge_ids = match indexed_edges_in_bbox(self, tile_id, bbox)? {
+                Some(edge_ids) => edge_ids,
+                None if Some(tile_id.level()) == local_level => {
+                    self.with_tile_containing(tile_id, |tile| tile.edges_in_bbox(bbox))?
+                }
+                None => continue,
+            };
+            for edge_id in edge_ids {
+                if seen.contains(&edge_id) {
+                    continue;
+                }
+
+                // Bins only list one edge of each pair, so the opposing edge gets considered too.
+                // Both share the same shape.
+                let snapped = self.with_tile_containing(edge_id, |tile| {
+                    let edge = tile.get_directed_edge(edge_id)?;
+                    let shape = tile.get_edge_info(edge)?.decode_raw_shape::<f64>()?;
+                    let Some((snapped, fraction)) = closest_point_on_line(point.0, &shape) else {
+                        return Ok(None);
+                    };
+                    let distance = Haversine.distance(point, snapped.into());
+                    if distance > radius_in_meters {
+                        return Ok(None);
+                    }
+
+                    let opposing_id = self.get_opposing_edge_id(edge_id, tile)?;
+                    Ok::<_, GraphTileProviderError>(Some((
+                        snapped,
+                        fraction,
+                        distance,
+                        opposing_id,
+                    )))
+                })??;
+                let Some((snapped, fraction, distance, opposing_id)) = snapped else {
+                    seen.insert(edge_id);
+                    continue;
+                };
+
+                for id in [edge_id, opposing_id] {
+                    if !seen.insert(id) {
+                        continue;
+                    }
+
+                    let candidate = self.with_tile_containing(id, |tile| {
+                        let edge = tile.get_directed_edge(id)?;
+                        Ok::<_, GraphTileProviderError>(filter(edge).then(|| EdgeCandidate {
+                            edge_id: id,
+                            distance,
+                            snapped_point: snapped.into(),
+                            // The shape is stored in the direction of the edge info
+                            percent_along: if edge.edge_info_is_forward() {
+                                fraction
+                            } else {
+                                1.0 - fraction
+                            },
+                        }))
+                    })??;
+                    candidates.extend(candidate);
+                }
+            }
+        }
+
+        candidates.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        candidates.truncate(max_results);
+        Ok(candidates)
+    }
+
+    /// Finds the directed edges closest to each of several points.
+    ///
+    /// This returns the same candidates as calling [`GraphTileProvider::find_nearest_edges`]
+    /// for each point (in the same order as `points`),
+    /// but the work is grouped by tile so that each tile is only fetched a handful of times
+    /// and each edge shape is only decoded once per batch,
+    /// no matter how many points are near it.
+    /// This makes a big difference for workloads like enriching dense GPS telemetry,
+    /// where many points fall in the same few tiles.
+    ///
+    /// Candidates for each point are sorted nearest first, with ties broken by edge ID.
+    ///
+    /// # Errors
+    ///
+    /// Fails if any tile or edge shape referenced by the bins cannot be loaded.
+    fn locate_many<F>(
+        &self,
+        points: &[Point<f64>],
+        radius_in_meters: f64,
+        max_results: usize,
+        mut filter: F,
+    ) -> Result<Vec<Vec<EdgeCandidate>>, GraphTileProviderError>
+    where
+        F: FnMut(&DirectedEdge) -> bool,
+        Self: Sized,
+    {
+        // Valhalla only populates bins in the local level tiles
+        let local_level = STANDARD_LEVELS.last().map(|level| level.level);
+        let bboxes: Vec<Rect<f64>> = points
+            .iter()
+            .map(|&point| {
+                let (north, east, south, west) = bbox_with_center(point, radius_in_meters);
+                Rect::new(coord! {x: west, y: south}, coord! {x: east, y: north})
+            })
+            .collect();
+
+        // Group the points by the local tiles whose bins need to be searched
+        let mut points_by_bin_tile: HashMap<GraphId, Vec<usize>> = HashMap::new();
+        for (point_index, &point) in points.iter().enumerate() {
+            for tile_id in self.enumerate_tiles_within_radius(point, radius_in_meters) {
+                if Some(tile_id.level()) == local_level {
+                    points_by_bin_tile
+                        .entry(tile_id)
+                        .or_default()
+                        .push(point_index);
+                }
+            }
+        }
+
+        // Gather the candidate edges from the bins, grouped by the tile containing each edge
+        let mut points_by_edge_tile: HashMap<GraphId, HashMap<GraphId, HashSet<usize>>> =
+            HashMap::new();
+        for (tile_id, point_indices) in points_by_bin_tile {
+            self.with_tile_containing(tile_id, |tile| {
+                for point_index in point_indices {
+                    for edge_id in tile.edges_in_bbox(bboxes[point_index]) {
+                        points_by_edge_tile
+                            .entry(edge_id.tile_base_id())
+                            .or_default()
+                            .entry(edge_id)
+                            .or_default()
+                            .insert(point_index);
+                    }
+                }
+            })?;
+        }
+
+        // Snap the points to each edge shape (and its opposing edge, as in find_nearest_edges)
+        let mut pending_by_tile: HashMap<GraphId, Vec<PendingCandidate>> = HashMap::new();
+        for (tile_id, edges) in points_by_edge_tile {
+            self.with_tile_containing(tile_id, |tile| {
+                for (edge_id, point_indices) in edges {
+                    let edge = tile.get_directed_edge(edge_id)?;
+                    let shape = tile.get_edge_info(edge)?.decode_raw_shape::<f64>()?;
+                    let mut opposing_id = None;
+                    for point_index in point_indices {
+                        let point = points[point_index];
+                        let Some((snapped, fraction)) = closest_point_on_line(point.0, &shape)
+                        else {
+                            continue;
+                        };
+                        let distance = Haversine.distance(point, snapped.into());
+                        if distance > radius_in_meters {
+                            continue;
+                        }
+
+                        let opposing_id = match opposing_id {
+                            Some(id) => id,
+                            None => *opposing_id.insert(self.get_opposing_edge_id(edge_id, tile)?),
+                        };
+                        for id in [edge_id, opposing_id] {
+                            pending_by_tile.entry(id.tile_base_id()).or_default().push(
+                                PendingCandidate {
+                                    point_index,
+                                    edge_id: id,
+                                    snapped_point: snapped.into(),
+                                    fraction,
+                                    distance,
+                                },
+                            );
+                        }
+                    }
+                }
+                Ok::<_, GraphTileProviderError>(())
+            })??;
+        }
+
+        // Apply the filter, and orient the fractions along each directed edge
+        let mut results = vec![Vec::new(); points.len()];
+        let mut seen = HashSet::new();
+        for (tile_id, pending) in pending_by_tile {
+            self.with_tile_containing(tile_id, |tile| {
+                for candidate in pending {
+                    if !seen.insert((candidate.point_index, candidate.edge_id)) {
+                        continue;
+                    }
+
+                    let edge = tile.get_directed_edge(candidate.edge_id)?;
+                    if filter(edge) {
+                        results[candidate.point_index].push(candidate.into_candidate(edge));
+                    }
+                }
+                Ok::<_, GraphTileProviderError>(())
+            })??;
+        }
+
+        for candidates in &mut results {
+            candidates.sort_by(|a, b| {
+                a.distance
+                    .total_cmp(&b.distance)
+                    .then(a.edge_id.cmp(&b.edge_id))
+            });
+            candidates.truncate(max_results);
+        }
+        Ok(results)
+    }
+
     /// Creates an iterator over all nodes within a given radius of a point.
     ///
     /// No sorting or filtering of nodes is performed besides ensuring that they are close enough.
@@ -408,6 +919,113 @@ pub trait GraphTileProvider {
             buffer: VecDeque::new(),
         }
     }
+
+    /// Loads every tile within `radius` meters of `center` (across all hierarchy levels),
+    /// so that later lookups in the area don't have to wait on cold cache misses.
+    ///
+    /// This is a no-op in terms of results; it only warms the provider's cache (if any).
+    /// Tiles are loaded one at a time, so it is safe to call on a shared provider
+    /// from a background thread without starving other work.
+    ///
+    /// Returns the number of tiles which are available
+    /// (tiles missing from the extract are skipped).
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first tile which exists but can't be loaded (ex: an I/O error).
+    fn prefetch<N: CoordFloat + FromPrimitive>(
+        &self,
+        center: Point<N>,
+        radius: N,
+    ) -> Result<usize, GraphTileProviderError> {
+        let (north, east, south, west) = bbox_with_center(center, radius);
+        prefetch_tiles(self, tiles_for_bbox(north, east, south, west))
+    }
+
+    /// Loads every tile within `buffer` meters of a path (ex: the shape of a route),
+    /// so that computations along a known corridor don't incur serial cold misses.
+    ///
+    /// Tiles are loaded in order along the path.
+    /// See [`GraphTileProvider::prefetch`] for details.
+    ///
+    /// The corridor does not handle paths which cross the antimeridian.
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first tile which exists but can't be loaded (ex: an I/O error).
+    fn prefetch_corridor<N: CoordFloat + FromPrimitive>(
+        &self,
+        path: &[Point<N>],
+        buffer: N,
+    ) -> Result<usize, GraphTileProviderError> {
+        prefetch_tiles(self, corridor_tiles(path, buffer))
+    }
+}
+
+/// Loads each tile, returning the number which exist.
+fn prefetch_tiles<P: GraphTileProvider + ?Sized>(
+    provider: &P,
+    tiles: Vec<GraphId>,
+) -> Result<usize, GraphTileProviderError> {
+    let mut available = 0;
+    for graph_id in tiles {
+        match provider.with_tile_containing(graph_id, |_| ()) {
+            Ok(()) => available += 1,
+            Err(GraphTileProviderError::TileDoesNotExist) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(available)
+}
+
+/// The base IDs of all tiles within `buffer` meters of a path, in order along the path.
+fn corridor_tiles<N: CoordFloat + FromPrimitive>(path: &[Point<N>], buffer: N) -> Vec<GraphId> {
+    // Long segments are split into steps so that the bbox of each step stays close to the line.
+    // Tiles are 0.25 degrees or more on a side, so this doesn't need to be very fine.
+    // These unwraps cannot fail
+    let max_step = buffer.max(N::from_f64(1_000.0).unwrap());
+
+    let mut boxes = Vec::new();
+    let mut push_step = |from: Point<N>, to: Point<N>| {
+        let (north_a, east_a, south_a, west_a) = bbox_with_center(from, buffer);
+        let (north_b, east_b, south_b, west_b) = bbox_with_center(to, buffer);
+        boxes.push((
+            north_a.max(north_b),
+            east_a.max(east_b),
+            south_a.min(south_b),
+            west_a.min(west_b),
+        ));
+    };
+    match path {
+        [] => {}
+        [point] => push_step(*point, *point),
+        _ => {
+            for segment in path.windows(2) {
+                let (start, end) = (segment[0], segment[1]);
+                let steps = (Haversine.distance(start, end) / max_step)
+                    .ceil()
+                    .to_usize()
+                    .unwrap_or(1)
+                    .max(1);
+                let n_steps = N::from_usize(steps).unwrap();
+                let mut from = start;
+                for step in 1..=steps {
+                    let t = N::from_usize(step).unwrap() / n_steps;
+                    let to = start + (end - start) * t;
+                    push_step(from, to);
+                    from = to;
+                }
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    boxes
+        .into_iter()
+        .flat_map(|(north, east, south, west)| tiles_for_bbox(north, east, south, west))
+        .filter(|graph_id| seen.insert(*graph_id))
+        .collect()
 }
 
 pub trait OwnedGraphTileProvider: GraphTileProvider {
@@ -430,8 +1048,10 @@ pub trait OwnedGraphTileProvider: GraphTileProvider {
 /// A keyed lock.
 ///
 /// This enables more granular locking than over an entire data structure.
+#[cfg(any(feature = "fs", feature = "http"))]
 pub(crate) struct LockTable<K>(DashMap<K, Arc<Mutex<()>>>);
 
+#[cfg(any(feature = "fs", feature = "http"))]
 impl<K: std::hash::Hash + Eq + Clone> LockTable<K> {
     pub fn new() -> Self {
         Self(DashMap::new())
@@ -497,11 +1117,19 @@ where
 #[cfg(test)]
 mod tests {
     use crate::GraphId;
-    use crate::graph_tile::GraphTile;
-    use crate::tile_provider::{DirectoryGraphTileProvider, GraphTileProvider};
+    use crate::tile_hierarchy::tiles_for_point;
+    use crate::tile_provider::corridor_tiles;
+    #[cfg(feature = "fs")]
+    use crate::{
+        graph_tile::{DirectedEdge, GraphTile},
+        tile_provider::{DirectoryGraphTileProvider, GraphTileProvider},
+    };
+    #[cfg(feature = "fs")]
+    use geo::Point;
     use geo::{Destination, Haversine, point};
-    use std::num::NonZeroUsize;
-    use std::path::PathBuf;
+    use std::collections::HashSet;
+    #[cfg(feature = "fs")]
+    use std::{num::NonZeroUsize, path::PathBuf};
 
     #[test]
     fn haversine_antimeridian_wraps() {
@@ -517,6 +1145,93 @@ mod tests {
     }
 
     #[test]
+    fn error_categories() {
+        use crate::graph_tile::{GraphTileDecodingError, LookupError};
+        use crate::tile_provider::GraphTileProviderError;
+        use std::io::{Error, ErrorKind};
+
+        let timed_out = GraphTileProviderError::IoError(Error::from(ErrorKind::TimedOut));
+        assert!(timed_out.is_retryable());
+        assert!(!timed_out.is_data_corruption());
+
+        let truncated = GraphTileProviderError::IoError(Error::from(ErrorKind::UnexpectedEof));
+        assert!(!truncated.is_retryable());
+        assert!(truncated.is_data_corruption());
+
+        let decoding = GraphTileProviderError::from(GraphTileDecodingError::SliceLength);
+        assert!(!decoding.is_retryable());
+        assert!(decoding.is_data_corruption());
+
+        let unsupported =
+            GraphTileProviderError::from(GraphTileDecodingError::UnsupportedTileLevel(7));
+        assert!(!unsupported.is_data_corruption());
+
+        for e in [
+            GraphTileProviderError::TileDoesNotExist,
+            GraphTileProviderError::from(LookupError::InvalidIndex),
+        ] {
+            assert!(!e.is_retryable());
+            assert!(!e.is_data_corruption());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_transition_node() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(4).unwrap());
+        let graph_id = GraphId::try_from_components(0, 3015, 0).expect("Unable to create graph ID");
+
+        provider.with_tile_containing_or_panic(graph_id, |tile| {
+            let sw = tile.header().sw_corner();
+            let mut checked = 0;
+
+            for (idx, node) in tile.nodes().iter().enumerate() {
+                let node_id = graph_id.with_feature_index(idx as u64).unwrap();
+                assert_eq!(
+                    provider.transition_node(node_id, 0).unwrap(),
+                    Some(node_id),
+                    "A node should always transition to itself on its own level"
+                );
+
+                for transition in tile.get_transitions_for_node(node_id).unwrap() {
+                    let expected = transition.corresponding_end_node_id();
+                    let transitioned = provider
+                        .transition_node(node_id, expected.level())
+                        .unwrap()
+                        .expect("Expected a transition to exist");
+                    assert_eq!(transitioned, expected);
+
+                    // The same intersection on another level should be at the same location
+                    let coord = provider.with_tile_containing_or_panic(transitioned, |other| {
+                        other
+                            .get_node(transitioned)
+                            .unwrap()
+                            .coordinate(other.header().sw_corner())
+                    });
+                    assert!((coord.x - node.coordinate(sw).x).abs() < 1e-5);
+                    assert!((coord.y - node.coordinate(sw).y).abs() < 1e-5);
+
+                    // ... and we should be able to get back again
+                    assert_eq!(
+                        provider.transition_node(transitioned, 0).unwrap(),
+                        Some(node_id)
+                    );
+                    checked += 1;
+                }
+            }
+
+            assert!(
+                checked > 0,
+                "Expected to find some transitions in the fixture"
+            );
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
     fn test_nodes_within_radius() {
         let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
             .join("fixtures")
@@ -555,4 +1270,199 @@ mod tests {
             }
         })
     }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_find_nearest_edges() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(4).unwrap());
+        let tile_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+
+        // Pick a point on the shape of an edge in the local tile
+        let (edge_id, point) = provider.with_tile_containing_or_panic(tile_id, |tile| {
+            let (edge_id, edge) = tile
+                .edges_with_ids()
+                .find(|(_, edge)| edge.length() > 100)
+                .unwrap();
+            let shape = tile
+                .get_edge_info(edge)
+                .unwrap()
+                .decode_raw_shape::<f64>()
+                .unwrap();
+            let midpoint = (shape[0] + shape[1]) / 2.0;
+            (edge_id, Point::from(midpoint))
+        });
+
+        let candidates = provider
+            .find_nearest_edges(point, 50.0, 10, |_| true)
+            .unwrap();
+        assert!(!candidates.is_empty());
+        assert!(candidates.len() <= 10);
+        assert!(candidates.is_sorted_by(|a, b| a.distance <= b.distance));
+        assert!(candidates.iter().all(|c| c.distance <= 50.0));
+
+        let candidate = candidates
+            .iter()
+            .find(|c| c.edge_id == edge_id)
+            .expect("Expected to find the edge the point lies on");
+        assert!(candidate.distance < 0.1);
+        assert!(candidate.percent_along > 0.0 && candidate.percent_along < 1.0);
+
+        // The opposing edge shares the same shape, but runs the other way
+        let opposing_id = provider.with_tile_containing_or_panic(edge_id, |tile| {
+            provider.get_opposing_edge_id(edge_id, tile).unwrap()
+        });
+        let opposing = candidates
+            .iter()
+            .find(|c| c.edge_id == opposing_id)
+            .expect("Expected to find the opposing edge");
+        assert!((candidate.percent_along + opposing.percent_along - 1.0).abs() < 1e-6);
+
+        // Filters are respected
+        let candidates = provider
+            .find_nearest_edges(point, 50.0, 10, |edge| {
+                !edge.is_shortcut() && edge.length() > 100_000
+            })
+            .unwrap();
+        assert!(candidates.is_empty());
+    }
+
+    #[test]
+    #[cfg(all(feature = "fs", feature = "spatial-index"))]
+    fn test_find_nearest_edges_with_spatial_index_matches_bins() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles");
+        let binned = DirectoryGraphTileProvider::new(base.clone(), NonZeroUsize::new(8).unwrap());
+        let indexed = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(8).unwrap())
+            .with_edge_spatial_index();
+        let tile_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+
+        // Search around a sample of the nodes in the tile
+        let points: Vec<Point> = binned.with_tile_containing_or_panic(tile_id, |tile| {
+            let sw = tile.header().sw_corner();
+            tile.nodes()
+                .iter()
+                .step_by(10)
+                .map(|node| {
+                    let coord = node.coordinate(sw);
+                    Point::new(f64::from(coord.x), f64::from(coord.y))
+                })
+                .collect()
+        });
+        assert!(!points.is_empty());
+        for point in points {
+            let expected = binned
+                .find_nearest_edges(point, 100.0, usize::MAX, |_| true)
+                .unwrap();
+            let actual = indexed
+                .find_nearest_edges(point, 100.0, usize::MAX, |_| true)
+                .unwrap();
+            assert!(!expected.is_empty());
+            assert_eq!(
+                actual.iter().map(|c| c.edge_id).collect::<HashSet<_>>(),
+                expected.iter().map(|c| c.edge_id).collect::<HashSet<_>>(),
+                "Mismatched candidates near {point:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_locate_many() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(4).unwrap());
+
+        // Several points close together, one near a tile boundary, and one in the middle of nowhere
+        let points = [
+            point!(x: 1.52, y: 42.51),
+            point!(x: 1.5201, y: 42.5101),
+            point!(x: 1.4999, y: 42.46),
+            point!(x: 1.0, y: 0.0),
+        ];
+        let filter = |edge: &DirectedEdge| !edge.is_shortcut();
+        let results = provider.locate_many(&points, 200.0, 100, filter).unwrap();
+        assert_eq!(results.len(), points.len());
+        assert!(results[3].is_empty());
+
+        for (point, candidates) in points.iter().zip(&results) {
+            let mut expected = provider
+                .find_nearest_edges(*point, 200.0, 100, filter)
+                .unwrap();
+            expected.sort_by(|a, b| {
+                a.distance
+                    .total_cmp(&b.distance)
+                    .then(a.edge_id.cmp(&b.edge_id))
+            });
+            assert_eq!(candidates, &expected);
+        }
+        assert!(results[..3].iter().all(|candidates| !candidates.is_empty()));
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_prefetch() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(8).unwrap());
+
+        // One tile at each level
+        let center = point!(x: 1.52, y: 42.51);
+        assert_eq!(provider.prefetch(center, 100.0).unwrap(), 3);
+        let stats = provider.cache_stats().unwrap();
+        assert_eq!(stats.cached_tiles, 3);
+
+        // Everything is already cached
+        assert_eq!(provider.prefetch(center, 100.0).unwrap(), 3);
+        assert_eq!(provider.cache_stats().unwrap().misses, stats.misses);
+
+        // Tiles outside the extract are skipped
+        assert_eq!(provider.prefetch(point!(x: 1.0, y: 0.0), 100.0).unwrap(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_prefetch_corridor() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(8).unwrap());
+
+        let path = [point!(x: 1.52, y: 42.51), point!(x: 1.4999, y: 42.46)];
+        let available = provider.prefetch_corridor(&path, 100.0).unwrap();
+        assert_eq!(available, provider.cache_stats().unwrap().cached_tiles);
+        assert!(available >= 3);
+        assert_eq!(provider.prefetch_corridor::<f64>(&[], 100.0).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_corridor_tiles() {
+        // A long diagonal segment, which spans several level 2 tiles in each direction
+        let start = point!(x: 0.1, y: 40.1);
+        let end = point!(x: 1.9, y: 41.9);
+        let tiles = corridor_tiles(&[start, end], 100.0);
+
+        // The endpoints are first and last at each level
+        let level_2 = |tiles: &[GraphId]| {
+            tiles
+                .iter()
+                .copied()
+                .filter(|graph_id| graph_id.level() == 2)
+                .collect::<Vec<_>>()
+        };
+        let along_path = level_2(&tiles);
+        assert_eq!(along_path.first(), level_2(&tiles_for_point(start)).first());
+        assert_eq!(along_path.last(), level_2(&tiles_for_point(end)).first());
+
+        // Much less than the full bbox (8x8 level 2 tiles), but at least the diagonal
+        assert!(along_path.len() >= 8);
+        assert!(along_path.len() < 64 / 2);
+        let unique: HashSet<_> = tiles.iter().collect();
+        assert_eq!(unique.len(), tiles.len());
+    }
 }
@@ -0,0 +1,273 @@
+#[cfg(feature = "spatial-index")]
+use super::EdgeSpatialIndex;
+use super::{GraphTileProvider, GraphTileProviderError, OwnedGraphTileProvider};
+use crate::GraphId;
+use crate::graph_tile::{GraphTileView, OwnedGraphTileHandle};
+use geo::{CoordFloat, Point};
+use num_traits::FromPrimitive;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::SystemTime;
+
+type Loader<P> = dyn Fn(&Path) -> Result<P, GraphTileProviderError> + Send + Sync;
+
+/// Identifies a version of a file or directory on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+    #[cfg(unix)]
+    inode: u64,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Result<Self, GraphTileProviderError> {
+        let metadata = std::fs::metadata(path)?;
+        Ok(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            #[cfg(unix)]
+            inode: std::os::unix::fs::MetadataExt::ino(&metadata),
+        })
+    }
+}
+
+/// A tile provider which can swap in a new tileset from disk while in use.
+///
+/// This lets long-running services pick up tile updates (ex: a nightly build) without a restart.
+/// The wrapped provider is rebuilt from scratch on reload,
+/// so stale cached tiles are dropped along with the old provider.
+/// Lookups which are already in flight finish against the old tileset;
+/// everything after the swap sees the new one.
+///
+/// Reloads can be triggered explicitly with [`ReloadingTileProvider::reload`],
+/// or by polling [`ReloadingTileProvider::reload_if_changed`] (ex: from a background timer),
+/// which reloads when the modification time, size, or inode of the path changes.
+/// Tarballs should be replaced atomically (written elsewhere and renamed into place),
+/// since the old file stays memory mapped until the old provider is dropped.
+/// For tile directories, change detection only sees the top-level directory,
+/// so swap in a whole new directory (ex: by renaming it or updating a symlink),
+/// or call `reload` after updating tiles in place.
+pub struct ReloadingTileProvider<P> {
+    path: PathBuf,
+    load: Box<Loader<P>>,
+    current: RwLock<Arc<P>>,
+    /// The stamp of the currently loaded tileset.
+    ///
+    /// This also serializes reloads.
+    stamp: Mutex<FileStamp>,
+}
+
+impl<P> ReloadingTileProvider<P> {
+    /// Loads the tileset at `path` with the given function (ex: [`TarballTileProvider::new_readonly`](super::TarballTileProvider::new_readonly)).
+    ///
+    /// The same function is used to reload the tileset later.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the path can't be read, or if the initial load fails.
+    pub fn new<F>(path: PathBuf, load: F) -> Result<Self, GraphTileProviderError>
+    where
+        F: Fn(&Path) -> Result<P, GraphTileProviderError> + Send + Sync + 'static,
+    {
+        let stamp = FileStamp::of(&path)?;
+        let provider = load(&path)?;
+        Ok(Self {
+            path,
+            load: Box::new(load),
+            current: RwLock::new(Arc::new(provider)),
+            stamp: Mutex::new(stamp),
+        })
+    }
+
+    /// Gets the currently loaded provider.
+    ///
+    /// The returned provider is not affected by later reloads,
+    /// so hold on to it for operations which must see a consistent tileset
+    /// (ex: a single route computation).
+    pub fn current(&self) -> Arc<P> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Reloads the tileset, regardless of whether it has changed.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the new tileset can't be loaded, in which case the current one stays in use.
+    pub fn reload(&self) -> Result<(), GraphTileProviderError> {
+        let mut stamp = self.stamp.lock().unwrap_or_else(PoisonError::into_inner);
+        self.reload_locked(&mut stamp)
+    }
+
+    /// Reloads the tileset if the path has changed since it was last loaded.
+    ///
+    /// Returns whether a reload happened.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the path can't be read or the new tileset can't be loaded,
+    /// in which case the current one stays in use.
+    pub fn reload_if_changed(&self) -> Result<bool, GraphTileProviderError> {
+        let mut stamp = self.stamp.lock().unwrap_or_else(PoisonError::into_inner);
+        if FileStamp::of(&self.path)? == *stamp {
+            return Ok(false);
+        }
+
+        self.reload_locked(&mut stamp)?;
+        Ok(true)
+    }
+
+    fn reload_locked(&self, stamp: &mut FileStamp) -> Result<(), GraphTileProviderError> {
+        // Take the stamp first, so a change during the load is picked up next time
+        let new_stamp = FileStamp::of(&self.path)?;
+        let provider = Arc::new((self.load)(&self.path)?);
+        *self.current.write().unwrap_or_else(PoisonError::into_inner) = provider;
+        *stamp = new_stamp;
+        Ok(())
+    }
+}
+
+impl<P: GraphTileProvider> GraphTileProvider for ReloadingTileProvider<P> {
+    #[inline]
+    fn with_tile_containing<F, T>(
+        &self,
+        graph_id: GraphId,
+        process: F,
+    ) -> Result<T, GraphTileProviderError>
+    where
+        F: FnOnce(&GraphTileView) -> T,
+    {
+        self.current().with_tile_containing(graph_id, process)
+    }
+
+    fn enumerate_tiles_within_radius<N: CoordFloat + FromPrimitive>(
+        &self,
+        center: Point<N>,
+        radius: N,
+    ) -> Vec<GraphId> {
+        self.current().enumerate_tiles_within_radius(center, radius)
+    }
+
+    #[cfg(feature = "spatial-index")]
+    fn edge_spatial_index(
+        &self,
+        graph_id: GraphId,
+    ) -> Result<Option<Arc<EdgeSpatialIndex>>, GraphTileProviderError> {
+        self.current().edge_spatial_index(graph_id)
+    }
+
+    fn available_tiles(&self) -> Result<Vec<GraphId>, GraphTileProviderError> {
+        self.current().available_tiles()
+    }
+}
+
+impl<P: OwnedGraphTileProvider> OwnedGraphTileProvider for ReloadingTileProvider<P> {
+    fn get_handle_for_tile_containing(
+        &self,
+        graph_id: GraphId,
+    ) -> Result<Arc<OwnedGraphTileHandle>, GraphTileProviderError> {
+        self.current().get_handle_for_tile_containing(graph_id)
+    }
+}
+
+#[cfg(all(test, not(miri)))]
+mod test {
+    use super::ReloadingTileProvider;
+    use crate::GraphId;
+    use crate::graph_tile::GraphTile;
+    use crate::tile_provider::{
+        DirectoryGraphTileProvider, GraphTileProvider, GraphTileProviderError, TarballTileProvider,
+    };
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    fn dataset_id<P: GraphTileProvider>(provider: &P) -> u64 {
+        let graph_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+        provider
+            .with_tile_containing(graph_id, |tile| tile.header().dataset_id.get())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_reload_tarball() {
+        let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles.tar");
+        let dir = std::env::temp_dir().join(format!("valinor-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("tiles.tar");
+        std::fs::copy(&fixture, &path).unwrap();
+
+        let provider = ReloadingTileProvider::new(path.clone(), |path| {
+            TarballTileProvider::new_readonly(path)
+        })
+        .unwrap();
+        let original_dataset_id = dataset_id(&provider);
+        assert!(!provider.reload_if_changed().unwrap());
+
+        // Build a modified tileset, and atomically swap it in
+        let staging = dir.join("staging.tar");
+        std::fs::copy(&fixture, &staging).unwrap();
+        let graph_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+        let writer = TarballTileProvider::new_mutable(&staging).unwrap();
+        // Safety: nothing else is reading the staging tarball
+        unsafe {
+            writer
+                .update_tile(graph_id, |builder| {
+                    Ok(builder.with_dataset_id(original_dataset_id + 1))
+                })
+                .unwrap();
+        }
+        drop(writer);
+        // Copies may preserve the modification time (ex: on Windows), and there are no inodes there
+        std::fs::File::options()
+            .write(true)
+            .open(&staging)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+
+        let before_reload = provider.current();
+        std::fs::rename(&staging, &path).unwrap();
+        assert!(provider.reload_if_changed().unwrap());
+        assert!(!provider.reload_if_changed().unwrap());
+        assert_eq!(dataset_id(&provider), original_dataset_id + 1);
+        // Existing handles keep the old tileset
+        assert_eq!(dataset_id(&*before_reload), original_dataset_id);
+
+        // A failed reload leaves the current tileset in place
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            provider.reload(),
+            Err(GraphTileProviderError::IoError(_))
+        ));
+        assert_eq!(dataset_id(&provider), original_dataset_id + 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_reload_directory() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles");
+        let provider = ReloadingTileProvider::new(base, |path| {
+            Ok(DirectoryGraphTileProvider::new(
+                path.to_path_buf(),
+                NonZeroUsize::MIN,
+            ))
+        })
+        .unwrap();
+        let before_reload = provider.current();
+        assert!(!provider.reload_if_changed().unwrap());
+
+        // An explicit reload always swaps in a fresh provider
+        provider.reload().unwrap();
+        assert!(!std::sync::Arc::ptr_eq(&before_reload, &provider.current()));
+        assert_eq!(dataset_id(&provider), dataset_id(&*before_reload));
+    }
+}
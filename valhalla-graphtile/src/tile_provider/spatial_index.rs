@@ -0,0 +1,192 @@
+use crate::GraphId;
+use crate::graph_tile::GraphTile;
+use crate::tile_provider::GraphTileProviderError;
+use geo::Point;
+use rstar::primitives::{GeomWithData, Rectangle};
+use rstar::{AABB, RTree};
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+type IndexedEdgeBbox = GeomWithData<Rectangle<[f64; 2]>, GraphId>;
+
+/// An R-tree over the bounding boxes of all directed edge shapes in a tile.
+///
+/// The edge bins in a graph tile are fairly coarse,
+/// so workloads that repeatedly search for candidates in the same tile
+/// (e.g. map matching a long trace) can benefit from a finer-grained index.
+/// Building the index requires decoding every shape in the tile,
+/// so it's only worth it when the tile will be queried many times.
+///
+/// You will usually get one of these via [`GraphTileProvider::edge_spatial_index`](super::GraphTileProvider::edge_spatial_index),
+/// which caches the index alongside the tile (when enabled).
+pub struct EdgeSpatialIndex {
+    tree: RTree<IndexedEdgeBbox>,
+}
+
+impl EdgeSpatialIndex {
+    /// Builds an index over all directed edges in the tile.
+    ///
+    /// Edges without any geometry are omitted from the index.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the edge info or shape for any edge cannot be decoded.
+    pub fn new<T: GraphTile>(tile: &T) -> Result<Self, GraphTileProviderError> {
+        let base_id = tile.graph_id().tile_base_id();
+        // Opposing edges share the edge info, so we only need to decode each shape once.
+        let mut bboxes: HashMap<u32, Option<Rectangle<[f64; 2]>>> = HashMap::new();
+        let mut entries = Vec::with_capacity(tile.directed_edges().len());
+
+        for (index, edge) in tile.directed_edges().iter().enumerate() {
+            let bbox = match bboxes.entry(edge.edge_info_offset()) {
+                Entry::Occupied(entry) => *entry.get(),
+                Entry::Vacant(entry) => {
+                    let shape = tile.get_edge_info(edge)?.decode_raw_shape::<f64>()?;
+                    let bbox = shape.split_first().map(|(first, rest)| {
+                        let (min, max) = rest.iter().fold(
+                            ([first.x, first.y], [first.x, first.y]),
+                            |(min, max), c| {
+                                (
+                                    [min[0].min(c.x), min[1].min(c.y)],
+                                    [max[0].max(c.x), max[1].max(c.y)],
+                                )
+                            },
+                        );
+                        Rectangle::from_corners(min, max)
+                    });
+                    *entry.insert(bbox)
+                }
+            };
+
+            if let Some(bbox) = bbox {
+                let edge_id = base_id.with_feature_index(index as u64)?;
+                entries.push(GeomWithData::new(bbox, edge_id));
+            }
+        }
+
+        Ok(Self {
+            tree: RTree::bulk_load(entries),
+        })
+    }
+
+    /// The number of edges in the index.
+    pub fn len(&self) -> usize {
+        self.tree.size()
+    }
+
+    /// Returns true if the index contains no edges.
+    pub fn is_empty(&self) -> bool {
+        self.tree.size() == 0
+    }
+
+    /// Gets the IDs of all directed edges whose shape bounding box intersects the given bbox.
+    ///
+    /// Note that this is a candidate search;
+    /// the edge shapes themselves may not actually intersect the bbox.
+    pub fn edges_in_bbox(
+        &self,
+        north: f64,
+        east: f64,
+        south: f64,
+        west: f64,
+    ) -> impl Iterator<Item = GraphId> + '_ {
+        let envelope = AABB::from_corners([west, south], [east, north]);
+        self.tree
+            .locate_in_envelope_intersecting(&envelope)
+            .map(|entry| entry.data)
+    }
+
+    /// Gets the IDs of all directed edges, ordered by the distance from the point
+    /// to their shape bounding box (closest first).
+    ///
+    /// Distances are measured in degrees, not meters,
+    /// so treat this ordering as a heuristic for candidate generation.
+    pub fn nearest_edges(&self, point: Point<f64>) -> impl Iterator<Item = GraphId> + '_ {
+        self.tree
+            .nearest_neighbor_iter(&[point.x(), point.y()])
+            .map(|entry| entry.data)
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod test {
+    use super::EdgeSpatialIndex;
+    use crate::GraphId;
+    use crate::graph_tile::GraphTile;
+    use crate::tile_provider::{
+        DirectoryGraphTileProvider, GraphTileProvider, OwnedGraphTileProvider,
+    };
+    use geo::{BoundingRect, LineString, point};
+    use std::collections::HashSet;
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn provider() -> DirectoryGraphTileProvider {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles");
+        DirectoryGraphTileProvider::new(base, NonZeroUsize::new(1).unwrap())
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        let tile_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+        assert!(provider().edge_spatial_index(tile_id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_edges_in_bbox_matches_scan() {
+        let provider = provider().with_edge_spatial_index();
+        let tile_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+        let index = provider
+            .edge_spatial_index(tile_id)
+            .unwrap()
+            .expect("The spatial index should be enabled");
+
+        // Cached alongside the tile
+        let cached = provider.edge_spatial_index(tile_id).unwrap().unwrap();
+        assert!(Arc::ptr_eq(&index, &cached));
+
+        let tile = provider.get_handle_for_tile_containing(tile_id).unwrap();
+        assert_eq!(index.len(), tile.directed_edges().len());
+
+        let (north, east, south, west) = (42.47, 1.50, 42.46, 1.48);
+        let expected: HashSet<_> = tile
+            .directed_edges()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, edge)| {
+                let shape = tile.get_edge_info(edge).ok()?.decode_raw_shape().ok()?;
+                let rect = LineString::<f64>::new(shape).bounding_rect()?;
+                let intersects = rect.min().x <= east
+                    && rect.max().x >= west
+                    && rect.min().y <= north
+                    && rect.max().y >= south;
+                intersects.then(|| tile_id.with_feature_index(i as u64).unwrap())
+            })
+            .collect();
+        let actual: HashSet<_> = index.edges_in_bbox(north, east, south, west).collect();
+
+        assert!(!expected.is_empty());
+        assert_eq!(actual, expected);
+
+        // Edges with a bbox containing the point have a distance of zero,
+        // so they must come before anything else.
+        let point = point!(x: 1.49, y: 42.465);
+        let nearest = index.nearest_edges(point).next().unwrap();
+        assert!(
+            index
+                .edges_in_bbox(42.465, 1.49, 42.465, 1.49)
+                .any(|id| id == nearest)
+        );
+    }
+
+    #[test]
+    fn test_build_from_tile() {
+        let tile_id = GraphId::try_from_components(0, 3015, 0).unwrap();
+        let tile = provider().get_handle_for_tile_containing(tile_id).unwrap();
+        let index = EdgeSpatialIndex::new(&*tile).unwrap();
+        assert!(!index.is_empty());
+    }
+}
@@ -1,8 +1,12 @@
+use super::tarball_index::index_tiles;
 use super::{GraphTileProvider, GraphTileProviderError};
 use crate::GraphId;
-use crate::graph_tile::{GraphTileView, MmapTilePointer, TileOffset};
+use crate::graph_tile::{
+    GraphTileBuildError, GraphTileBuilder, GraphTileView, MmapTilePointer, OwnedGraphTileHandle,
+    TileOffset,
+};
 use crate::spatial::bbox_with_center;
-use crate::tile_hierarchy::STANDARD_LEVELS;
+use crate::tile_hierarchy::tiles_for_bbox;
 use geo::{CoordFloat, Point};
 use memmap2::{MmapOptions, MmapRaw};
 use num_traits::FromPrimitive;
@@ -12,8 +16,6 @@ use std::io::Read;
 use std::path::Path;
 use std::sync::Arc;
 use tar::Archive;
-use zerocopy::{FromBytes, LE, U32, U64};
-use zerocopy_derive::{FromBytes, Immutable, IntoBytes, Unaligned};
 
 /// A tile provider backed by a memory-mapped tarball archive.
 ///
@@ -75,29 +77,7 @@ impl<const MUT: bool> TarballTileProvider<MUT> {
         let mut index_bytes = Vec::with_capacity(entry.header().size()? as usize);
         entry.read_to_end(&mut index_bytes)?;
 
-        let index_entries = parse_index_bin(&index_bytes)?;
-
-        // Index the index
-        let mut tile_index: HashMap<GraphId, TileOffset> =
-            HashMap::with_capacity(index_entries.len());
-        for entry in index_entries {
-            let graph_id = entry.graph_id()?;
-            let offset = entry.offset.get();
-            if offset == 0 || !offset.is_multiple_of(512) {
-                return Err(GraphTileProviderError::InvalidTarball(format!(
-                    "Expected all index offsets to lie on a 512-byte boundary, but the index entry for {} has offset {}",
-                    graph_id, offset,
-                )));
-            }
-
-            tile_index.insert(
-                graph_id,
-                TileOffset {
-                    offset,
-                    size: entry.size.get(),
-                },
-            );
-        }
+        let tile_index = index_tiles(&index_bytes)?;
 
         // Explicitly (not strictly necessary) close the archive reader handle
         drop(archive);
@@ -159,19 +139,18 @@ impl<const MUT: bool> GraphTileProvider for TarballTileProvider<MUT> {
         center: Point<N>,
         radius: N,
     ) -> Vec<GraphId> {
-        let mut out: Vec<GraphId> = Vec::new();
-
         let (north, east, south, west) = bbox_with_center(center, radius);
 
-        for level in STANDARD_LEVELS.iter() {
-            for gid in level.tiles_intersecting_bbox(north, east, south, west) {
-                if self.tile_index.contains_key(&gid) {
-                    out.push(gid);
-                }
-            }
-        }
+        tiles_for_bbox(north, east, south, west)
+            .into_iter()
+            .filter(|&gid| self.tile_index.contains_key(&gid))
+            .collect()
+    }
 
-        out
+    fn available_tiles(&self) -> Result<Vec<GraphId>, GraphTileProviderError> {
+        let mut tiles: Vec<_> = self.tile_index.keys().copied().collect();
+        tiles.sort_by_key(|graph_id| (graph_id.level(), graph_id.tile_id()));
+        Ok(tiles)
     }
 }
 
@@ -242,162 +221,78 @@ impl TarballTileProvider<true> {
     pub fn flush(&self) -> std::io::Result<()> {
         self.mmap.flush()
     }
-}
-
-/// A tile index entry enabling efficient random access into a tarball archive.
-///
-/// # The `index.bin` file
-///
-/// Tarballs were designed for an era of tape drives, where access was essentially sequential.
-/// This gives tarballs some interesting properties, like being a series of entries that can
-/// be written sequentially without requiring (necessarily) a header at the start with full info.
-/// They can even be concatenated together!
-///
-/// But I digress... Tarballs *can* be accessed randomly by seeking to specific offsets in the file,
-/// but you need to know where each file is located.
-/// Valhalla has a convention of writing an `index.bin` file as the first entry of the archive
-/// which contains these offsets.
-/// Once you parse this, you have the keys to random access of any graph tile within a single file
-/// (typically a memory map).
-///
-/// The `index.bin` file is just a series of these structs written out sequentially;
-/// no padding or headers, just bytes.
-#[derive(FromBytes, IntoBytes, Immutable, Unaligned, PartialEq)]
-#[repr(C)]
-pub struct TileIndexBinEntry {
-    /// Byte offset from the beginning of the tar
-    offset: U64<LE>,
-    /// Just the level and tile index, hence fitting in 32 bits
-    tile_id: U32<LE>,
-    /// The size of the tile in bytes.
-    size: U32<LE>,
-}
 
-impl TileIndexBinEntry {
-    fn graph_id(&self) -> Result<GraphId, GraphTileProviderError> {
-        // SAFETY: We know that the bit field cannot contain a value
-        // larger than the max allowed value (it's limited to 46 bits).
-        // Therefore, this is guaranteed to be a valid Graph ID bit pattern.
-        let graph_id = unsafe { GraphId::from_id_unchecked(self.tile_id.into()) };
+    /// Checks out the tile containing the given graph ID as a builder,
+    /// applies `edit` to it, and rewrites the tile's entry in the tarball.
+    ///
+    /// The tarball index (and the position of every other tile) is fixed,
+    /// so the edited tile must be exactly the same size as the original.
+    /// This holds for edits which only change fixed-size fields (ex: header metadata or edge attributes);
+    /// anything which adds or removes data needs a directory of tiles (or a rebuilt tarball).
+    /// The tile is rewritten in place and flushed to disk before returning.
+    ///
+    /// # Safety
+    ///
+    /// The tile is rewritten without any synchronization.
+    /// The caller must ensure that nothing reads this tile during the call:
+    /// no other threads using this provider (ex: via [`GraphTileProvider::with_tile_containing`]),
+    /// no outstanding slices from [`MmapTilePointer::as_tile_bytes`],
+    /// and no other processes mapping the same file.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the tile is not part of the tarball, if `edit` returns an error
+    /// (in which case nothing is written), if the size of the tile changes,
+    /// or if the changes can't be flushed to disk.
+    pub unsafe fn update_tile<F>(
+        &self,
+        graph_id: GraphId,
+        edit: F,
+    ) -> Result<(), GraphTileBuildError>
+    where
+        F: for<'a> FnOnce(
+            GraphTileBuilder<'a>,
+        ) -> Result<GraphTileBuilder<'a>, GraphTileBuildError>,
+    {
+        let tile_pointer = self.get_pointer_for_tile_containing(graph_id)?;
+        // Copy the original out of the map, since the builder borrows from it
+        let original_bytes = unsafe { tile_pointer.as_tile_bytes() }.to_vec();
+        let original =
+            OwnedGraphTileHandle::try_from(original_bytes).map_err(GraphTileProviderError::from)?;
+
+        let bytes = edit(GraphTileBuilder::from(&original))?.into_bytes()?;
+        let TileOffset { offset, size } = tile_pointer.offsets;
+        let offset = usize::try_from(offset)?;
+        let size = usize::try_from(size)?;
+        if bytes.len() != size {
+            return Err(GraphTileBuildError::TileSizeChanged {
+                expected: size,
+                actual: bytes.len(),
+            });
+        }
 
-        if graph_id.feature_index() == 0 {
-            Ok(graph_id)
-        } else {
-            Err(GraphTileProviderError::InvalidTarball(format!(
-                "Invalid GraphID {}; expected the index bits to be zero.",
-                self.tile_id.get()
-            )))
+        unsafe {
+            // The index guarantees that this range lies within the map
+            std::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                self.mmap.as_mut_ptr().add(offset),
+                bytes.len(),
+            );
         }
-    }
-}
+        self.mmap.flush_range(offset, size)?;
 
-/// Parses an `index.bin` file to enable random access.
-///
-/// See [`TileIndexBinEntry`] for a description of the tile format.
-pub fn parse_index_bin(index_bytes: &[u8]) -> Result<&[TileIndexBinEntry], GraphTileProviderError> {
-    const INDEX_ENTRY_SIZE: usize = size_of::<TileIndexBinEntry>();
-
-    if index_bytes.is_empty() || !index_bytes.len().is_multiple_of(INDEX_ENTRY_SIZE) {
-        return Err(GraphTileProviderError::InvalidTarball(format!(
-            "Malformed index.bin: expected length to be non-zero and a multiple of {INDEX_ENTRY_SIZE}; was {}",
-            index_bytes.len()
-        )));
+        Ok(())
     }
-
-    // Decode the index as a sequence of TileIndexEntry
-    let num_tiles = index_bytes.len() / INDEX_ENTRY_SIZE;
-
-    let (index_entries, tail) =
-        <[TileIndexBinEntry]>::ref_from_prefix_with_elems(&index_bytes, num_tiles).map_err(
-            |e| GraphTileProviderError::InvalidTarball(format!("Malformed index.bin: {e:?}")),
-        )?;
-
-    assert!(
-        tail.is_empty(),
-        "Expected no remaining bytes after parsing the index. This is a programming error in Valinor, not your code. Please report an issue."
-    );
-
-    Ok(index_entries)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::graph_tile::{GraphTile, GraphTileView};
+    use crate::graph_tile::{AdminInfo, GraphTile, GraphTileView};
     use crate::tile_provider::{DirectoryGraphTileProvider, OwnedGraphTileProvider};
     use std::num::NonZeroUsize;
     use std::path::PathBuf;
 
-    /// Bytes taken from the start of a large extract generated by official Valhalla tooling.
-    const INDEX_BIN_FIXTURE: &[u8] = &[
-        // Tile 1
-        0x00, 0x5a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x60, 0x4f, 0x00, 0x00, 0x9c, 0x08, 0x12,
-        0x00, // Tile 2
-        0x00, 0x6a, 0x12, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x52, 0x00, 0x00, 0xc8, 0x67, 0x01,
-        0x00, // Tile 3
-        0x00, 0xd8, 0x13, 0x00, 0x00, 0x00, 0x00, 0x00, 0x28, 0x52, 0x00, 0x00, 0x28, 0x9d, 0x1b,
-        0x00,
-    ];
-
-    #[test]
-    fn test_parse_index_bin_invalid() {
-        assert!(
-            matches!(
-                parse_index_bin(&[]),
-                Err(GraphTileProviderError::InvalidTarball(_))
-            ),
-            "Empty indexes are invalid"
-        );
-        assert!(
-            matches!(
-                parse_index_bin(&[0x00, 0x5a]),
-                Err(GraphTileProviderError::InvalidTarball(_)),
-            ),
-            "Index must contain at least one full entry"
-        );
-        assert!(
-            matches!(
-                parse_index_bin(&[
-                    // Contains one extra byte
-                    0x00, 0x5a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x60, 0x4f, 0x00, 0x00, 0x9c,
-                    0x08, 0x12, 0x00, 0x00
-                ]),
-                Err(GraphTileProviderError::InvalidTarball(_)),
-            ),
-            "Index must be completely parseable with no bytes leftover"
-        );
-    }
-
-    #[test]
-    fn test_parse_index_bin() {
-        let index_entries = parse_index_bin(INDEX_BIN_FIXTURE).expect("Unable ta parse fixture");
-
-        assert_eq!(index_entries.len(), 3);
-
-        // All sizes were verified on disk from the original tiles when constructing the test
-
-        // First entry
-        assert_eq!(index_entries[0].offset.get(), 23040);
-        assert_eq!(index_entries[0].graph_id().unwrap(), unsafe {
-            GraphId::from_components_unchecked(0, 2540, 0)
-        });
-        assert_eq!(index_entries[0].size.get(), 1_181_852);
-
-        // Second entry
-        assert_eq!(index_entries[1].offset.get(), 1_206_784);
-        assert_eq!(index_entries[1].graph_id().unwrap(), unsafe {
-            GraphId::from_components_unchecked(0, 2628, 0)
-        });
-        assert_eq!(index_entries[1].size.get(), 92_104);
-
-        // Third entry
-        assert_eq!(index_entries[2].offset.get(), 1_300_480);
-        assert_eq!(index_entries[2].graph_id().unwrap(), unsafe {
-            GraphId::from_components_unchecked(0, 2629, 0)
-        });
-        assert_eq!(index_entries[2].size.get(), 1_809_704);
-    }
-
     #[cfg(not(miri))]
     #[test]
     fn test_get_tile() {
@@ -460,4 +355,101 @@ mod test {
             assert_eq!(directory_tile.borrow_owner(), tarball_tile_bytes);
         }
     }
+
+    #[cfg(not(miri))]
+    #[test]
+    fn test_available_tiles() {
+        let expected_ids = [
+            GraphId::try_from_components(0, 3015, 0).expect("Unable to create graph ID"),
+            GraphId::try_from_components(1, 47701, 0).expect("Unable to create graph ID"),
+            GraphId::try_from_components(2, 762_485, 0).expect("Unable to create graph ID"),
+            GraphId::try_from_components(2, 762_486, 0).expect("Unable to create graph ID"),
+            GraphId::try_from_components(2, 763_925, 0).expect("Unable to create graph ID"),
+            GraphId::try_from_components(2, 763_926, 0).expect("Unable to create graph ID"),
+            GraphId::try_from_components(2, 763_927, 0).expect("Unable to create graph ID"),
+        ];
+
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles");
+        let directory_provider =
+            DirectoryGraphTileProvider::new(base, NonZeroUsize::new(1).unwrap());
+        assert_eq!(
+            directory_provider
+                .available_tiles()
+                .expect("Unable to list tiles"),
+            expected_ids
+        );
+
+        let tarball_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-tiles.tar");
+        let tarball_provider =
+            TarballTileProvider::new_readonly(tarball_path).expect("Unable to init tile provider");
+        assert_eq!(
+            tarball_provider
+                .available_tiles()
+                .expect("Unable to list tiles"),
+            expected_ids
+        );
+    }
+
+    #[cfg(not(miri))]
+    #[test]
+    fn test_update_tile() {
+        let tarball_path =
+            std::env::temp_dir().join(format!("valinor-update-tile-{}.tar", std::process::id()));
+        std::fs::copy(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("fixtures")
+                .join("andorra-tiles.tar"),
+            &tarball_path,
+        )
+        .unwrap();
+
+        let graph_id = GraphId::try_from_components(2, 762_485, 0).unwrap();
+        let provider = TarballTileProvider::new_mutable(&tarball_path).unwrap();
+        let admin_count = provider
+            .with_tile_containing(graph_id, |tile| tile.admins().len())
+            .unwrap();
+        // Safety: nothing else is reading the tile
+        unsafe {
+            provider
+                .update_tile(graph_id, |builder| Ok(builder.with_dataset_id(42)))
+                .unwrap();
+        }
+        assert_eq!(
+            provider
+                .with_tile_containing(graph_id, |tile| tile.header().dataset_id.get())
+                .unwrap(),
+            42
+        );
+
+        // Edits which change the size of the tile can't be written in place
+        let admin = AdminInfo {
+            country_iso: "XX".into(),
+            principal_subdivision_iso: "A".into(),
+            country_name: "Testland".into(),
+            principal_subdivision_name: "Andorra".into(),
+        };
+        let result =
+            unsafe { provider.update_tile(graph_id, |builder| builder.with_admin(&admin)) };
+        assert!(matches!(
+            result,
+            Err(GraphTileBuildError::TileSizeChanged { expected, actual }) if actual > expected
+        ));
+        drop(provider);
+
+        // The change was persisted, and the rest of the tarball is untouched
+        let provider = TarballTileProvider::new_readonly(&tarball_path).unwrap();
+        let tile = provider
+            .with_tile_containing(graph_id, |tile| {
+                (tile.header().dataset_id.get(), tile.admins().len())
+            })
+            .unwrap();
+        assert_eq!(tile, (42, admin_count));
+        assert_eq!(provider.tile_ids().count(), 7);
+
+        std::fs::remove_file(tarball_path).unwrap();
+    }
 }
@@ -0,0 +1,207 @@
+//! Valhalla's `index.bin` convention for random access into tile tarballs.
+//!
+//! This is shared by the memory-mapped [`TarballTileProvider`](super::TarballTileProvider)
+//! and by remote tarballs read with ranged requests.
+
+use super::GraphTileProviderError;
+use crate::GraphId;
+use crate::graph_tile::TileOffset;
+use std::collections::HashMap;
+use zerocopy::{FromBytes, LE, U32, U64};
+use zerocopy_derive::{FromBytes, Immutable, IntoBytes, Unaligned};
+
+/// A tile index entry enabling efficient random access into a tarball archive.
+///
+/// # The `index.bin` file
+///
+/// Tarballs were designed for an era of tape drives, where access was essentially sequential.
+/// This gives tarballs some interesting properties, like being a series of entries that can
+/// be written sequentially without requiring (necessarily) a header at the start with full info.
+/// They can even be concatenated together!
+///
+/// But I digress... Tarballs *can* be accessed randomly by seeking to specific offsets in the file,
+/// but you need to know where each file is located.
+/// Valhalla has a convention of writing an `index.bin` file as the first entry of the archive
+/// which contains these offsets.
+/// Once you parse this, you have the keys to random access of any graph tile within a single file
+/// (typically a memory map).
+///
+/// The `index.bin` file is just a series of these structs written out sequentially;
+/// no padding or headers, just bytes.
+#[derive(FromBytes, IntoBytes, Immutable, Unaligned, PartialEq)]
+#[repr(C)]
+pub struct TileIndexBinEntry {
+    /// Byte offset from the beginning of the tar
+    offset: U64<LE>,
+    /// Just the level and tile index, hence fitting in 32 bits
+    tile_id: U32<LE>,
+    /// The size of the tile in bytes.
+    size: U32<LE>,
+}
+
+impl TileIndexBinEntry {
+    /// Creates an index entry (for writing extracts).
+    #[cfg(feature = "fs")]
+    pub(crate) fn new(offset: u64, graph_id: GraphId, size: u32) -> Self {
+        let tile_id = u32::try_from(graph_id.tile_base_id().value())
+            .expect("Base graph IDs only use the level and tile ID bits, so they fit in a u32");
+        Self {
+            offset: U64::new(offset),
+            tile_id: U32::new(tile_id),
+            size: U32::new(size),
+        }
+    }
+
+    fn graph_id(&self) -> Result<GraphId, GraphTileProviderError> {
+        // SAFETY: We know that the bit field cannot contain a value
+        // larger than the max allowed value (it's limited to 46 bits).
+        // Therefore, this is guaranteed to be a valid Graph ID bit pattern.
+        let graph_id = unsafe { GraphId::from_id_unchecked(self.tile_id.into()) };
+
+        if graph_id.feature_index() == 0 {
+            Ok(graph_id)
+        } else {
+            Err(GraphTileProviderError::InvalidTarball(format!(
+                "Invalid GraphID {}; expected the index bits to be zero.",
+                self.tile_id.get()
+            )))
+        }
+    }
+}
+
+/// Parses an `index.bin` file to enable random access.
+///
+/// See [`TileIndexBinEntry`] for a description of the tile format.
+pub fn parse_index_bin(index_bytes: &[u8]) -> Result<&[TileIndexBinEntry], GraphTileProviderError> {
+    const INDEX_ENTRY_SIZE: usize = size_of::<TileIndexBinEntry>();
+
+    if index_bytes.is_empty() || !index_bytes.len().is_multiple_of(INDEX_ENTRY_SIZE) {
+        return Err(GraphTileProviderError::InvalidTarball(format!(
+            "Malformed index.bin: expected length to be non-zero and a multiple of {INDEX_ENTRY_SIZE}; was {}",
+            index_bytes.len()
+        )));
+    }
+
+    // Decode the index as a sequence of TileIndexEntry
+    let num_tiles = index_bytes.len() / INDEX_ENTRY_SIZE;
+
+    let (index_entries, tail) =
+        <[TileIndexBinEntry]>::ref_from_prefix_with_elems(index_bytes, num_tiles).map_err(|e| {
+            GraphTileProviderError::InvalidTarball(format!("Malformed index.bin: {e:?}"))
+        })?;
+
+    assert!(
+        tail.is_empty(),
+        "Expected no remaining bytes after parsing the index. This is a programming error in Valinor, not your code. Please report an issue."
+    );
+
+    Ok(index_entries)
+}
+
+/// Parses an `index.bin` file into a map from tile IDs to their location in the tarball.
+///
+/// # Errors
+///
+/// Fails if the index is malformed,
+/// or a tile doesn't start on a tar block boundary.
+pub(crate) fn index_tiles(
+    index_bytes: &[u8],
+) -> Result<HashMap<GraphId, TileOffset>, GraphTileProviderError> {
+    let index_entries = parse_index_bin(index_bytes)?;
+
+    let mut tile_index: HashMap<GraphId, TileOffset> = HashMap::with_capacity(index_entries.len());
+    for entry in index_entries {
+        let graph_id = entry.graph_id()?;
+        let offset = entry.offset.get();
+        if offset == 0 || !offset.is_multiple_of(512) {
+            return Err(GraphTileProviderError::InvalidTarball(format!(
+                "Expected all index offsets to lie on a 512-byte boundary, but the index entry for {graph_id} has offset {offset}",
+            )));
+        }
+
+        tile_index.insert(
+            graph_id,
+            TileOffset {
+                offset,
+                size: entry.size.get(),
+            },
+        );
+    }
+
+    Ok(tile_index)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Bytes taken from the start of a large extract generated by official Valhalla tooling.
+    const INDEX_BIN_FIXTURE: &[u8] = &[
+        // Tile 1
+        0x00, 0x5a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x60, 0x4f, 0x00, 0x00, 0x9c, 0x08, 0x12,
+        0x00, // Tile 2
+        0x00, 0x6a, 0x12, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x52, 0x00, 0x00, 0xc8, 0x67, 0x01,
+        0x00, // Tile 3
+        0x00, 0xd8, 0x13, 0x00, 0x00, 0x00, 0x00, 0x00, 0x28, 0x52, 0x00, 0x00, 0x28, 0x9d, 0x1b,
+        0x00,
+    ];
+
+    #[test]
+    fn test_parse_index_bin_invalid() {
+        assert!(
+            matches!(
+                parse_index_bin(&[]),
+                Err(GraphTileProviderError::InvalidTarball(_))
+            ),
+            "Empty indexes are invalid"
+        );
+        assert!(
+            matches!(
+                parse_index_bin(&[0x00, 0x5a]),
+                Err(GraphTileProviderError::InvalidTarball(_)),
+            ),
+            "Index must contain at least one full entry"
+        );
+        assert!(
+            matches!(
+                parse_index_bin(&[
+                    // Contains one extra byte
+                    0x00, 0x5a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x60, 0x4f, 0x00, 0x00, 0x9c,
+                    0x08, 0x12, 0x00, 0x00
+                ]),
+                Err(GraphTileProviderError::InvalidTarball(_)),
+            ),
+            "Index must be completely parseable with no bytes leftover"
+        );
+    }
+
+    #[test]
+    fn test_parse_index_bin() {
+        let index_entries = parse_index_bin(INDEX_BIN_FIXTURE).expect("Unable ta parse fixture");
+
+        assert_eq!(index_entries.len(), 3);
+
+        // All sizes were verified on disk from the original tiles when constructing the test
+
+        // First entry
+        assert_eq!(index_entries[0].offset.get(), 23040);
+        assert_eq!(index_entries[0].graph_id().unwrap(), unsafe {
+            GraphId::from_components_unchecked(0, 2540, 0)
+        });
+        assert_eq!(index_entries[0].size.get(), 1_181_852);
+
+        // Second entry
+        assert_eq!(index_entries[1].offset.get(), 1_206_784);
+        assert_eq!(index_entries[1].graph_id().unwrap(), unsafe {
+            GraphId::from_components_unchecked(0, 2628, 0)
+        });
+        assert_eq!(index_entries[1].size.get(), 92_104);
+
+        // Third entry
+        assert_eq!(index_entries[2].offset.get(), 1_300_480);
+        assert_eq!(index_entries[2].graph_id().unwrap(), unsafe {
+            GraphId::from_components_unchecked(0, 2629, 0)
+        });
+        assert_eq!(index_entries[2].size.get(), 1_809_704);
+    }
+}
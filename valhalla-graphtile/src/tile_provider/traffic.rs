@@ -97,6 +97,55 @@ impl<const MUT: bool> TrafficTileProvider<MUT> {
         let tile_pointer = self
             .tarball_tile_provider
             .get_pointer_for_tile_containing(graph_id)?;
+        // SAFETY: Same assumptions as this function.
+        let header = unsafe { Self::read_header(&tile_pointer)? };
+
+        if graph_id.feature_index() >= u64::from(header.directed_edge_count()) {
+            return Err(GraphTileProviderError::GraphTileLookupError(
+                LookupError::InvalidIndex,
+            ));
+        }
+
+        Ok(MmapTilePointer {
+            mmap: tile_pointer.mmap.clone(),
+            offsets: TileOffset {
+                // Tile structure: header + [TileSpeed]
+                offset: tile_pointer.offsets.offset
+                    + (HEADER_SIZE as u64)
+                    + (SPEED_SIZE as u64 * graph_id.feature_index()),
+                size: SPEED_SIZE as u32,
+            },
+        })
+    }
+
+    /// Reads the header of the tile at the given pointer, and checks the version.
+    ///
+    /// # Safety
+    ///
+    /// Assumes that the header is present and valid.
+    /// It is the responsibility of the caller to ensure this.
+    unsafe fn read_header(
+        tile_pointer: &MmapTilePointer,
+    ) -> Result<TrafficTileHeader, GraphTileProviderError> {
+        // SAFETY: See function-level docs.
+        let header = unsafe { Self::read_header_unchecked(tile_pointer) };
+
+        if header.traffic_tile_version() != TRAFFIC_TILE_VERSION {
+            return Err(GraphTileProviderError::UnsupportedTileVersion);
+        }
+
+        Ok(header)
+    }
+
+    /// Reads the header of the tile at the given pointer, without checking the version.
+    ///
+    /// # Safety
+    ///
+    /// Assumes that the header is present and valid.
+    /// It is the responsibility of the caller to ensure this.
+    unsafe fn read_header_unchecked(tile_pointer: &MmapTilePointer) -> TrafficTileHeader {
+        const HEADER_SIZE: usize = size_of::<TrafficTileHeader>();
+
         let header_pointer = MmapTilePointer {
             mmap: tile_pointer.mmap.clone(),
             offsets: TileOffset {
@@ -113,28 +162,80 @@ impl<const MUT: bool> TrafficTileProvider<MUT> {
         // Additionally, Valhalla (at the time of this writing) has no mechanism for hot swapping
         // the underlying graph, which means we can assume the directed edge count will never change
         // for a given tile during the life of the program.
-        let header: TrafficTileHeader = unsafe { header_pointer.read_volatile() };
+        unsafe { header_pointer.read_volatile() }
+    }
 
-        if header.traffic_tile_version() != TRAFFIC_TILE_VERSION {
-            return Err(GraphTileProviderError::UnsupportedTileVersion);
+    /// Gets the most recent update time (in seconds since the epoch) across all tiles.
+    ///
+    /// Returns `None` when no tile in the extract has been updated yet.
+    /// This is useful for monitoring whether the traffic feed has gone stale.
+    ///
+    /// # Safety
+    ///
+    /// Assumes that all tile headers are present and valid.
+    /// See the [type-level documentation](TrafficTileProvider) for details.
+    ///
+    /// # Errors
+    ///
+    /// Fails if any tile cannot be read, or uses an unsupported version.
+    pub unsafe fn last_update(&self) -> Result<Option<u64>, GraphTileProviderError> {
+        let mut latest = None;
+        for tile_id in self.tile_ids() {
+            let tile_pointer = self
+                .tarball_tile_provider
+                .get_pointer_for_tile_containing(*tile_id)?;
+            // SAFETY: See function-level docs.
+            let last_update = unsafe { Self::read_header(&tile_pointer)? }.last_update();
+            if last_update > 0 {
+                latest = latest.max(Some(last_update));
+            }
         }
 
-        if graph_id.feature_index() >= u64::from(header.directed_edge_count()) {
-            return Err(GraphTileProviderError::GraphTileLookupError(
-                LookupError::InvalidIndex,
-            ));
-        }
+        Ok(latest)
+    }
 
-        Ok(MmapTilePointer {
-            mmap: tile_pointer.mmap.clone(),
-            offsets: TileOffset {
-                // Tile structure: header + [TileSpeed]
-                offset: tile_pointer.offsets.offset
-                    + (HEADER_SIZE as u64)
-                    + (SPEED_SIZE as u64 * graph_id.feature_index()),
-                size: SPEED_SIZE as u32,
-            },
-        })
+    /// Reads the header of the tile containing `graph_id`.
+    ///
+    /// # Safety
+    ///
+    /// Assumes that the header is present and valid.
+    /// See the [type-level documentation](TrafficTileProvider) for details.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the tile doesn't exist, or uses an unsupported version.
+    pub unsafe fn tile_header(
+        &self,
+        graph_id: GraphId,
+    ) -> Result<TrafficTileHeader, GraphTileProviderError> {
+        let tile_pointer = self
+            .tarball_tile_provider
+            .get_pointer_for_tile_containing(graph_id)?;
+        // SAFETY: See function-level docs.
+        unsafe { Self::read_header(&tile_pointer) }
+    }
+
+    /// Reads the header of the tile containing `graph_id`, regardless of its version.
+    ///
+    /// This is for validating extracts; only the fields common to all versions are meaningful.
+    ///
+    /// # Safety
+    ///
+    /// Assumes that the header is present and valid.
+    /// See the [type-level documentation](TrafficTileProvider) for details.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the tile doesn't exist.
+    pub(crate) unsafe fn tile_header_unchecked(
+        &self,
+        graph_id: GraphId,
+    ) -> Result<TrafficTileHeader, GraphTileProviderError> {
+        let tile_pointer = self
+            .tarball_tile_provider
+            .get_pointer_for_tile_containing(graph_id)?;
+        // SAFETY: See function-level docs.
+        Ok(unsafe { Self::read_header_unchecked(&tile_pointer) })
     }
 
     /// An iterator over all tile IDs contained in the tarball, in arbitrary order.
@@ -192,7 +293,11 @@ impl TrafficTileProvider<true> {
         self.tarball_tile_provider.flush()
     }
 
-    /// Updates the speed data stored for an edge.
+    /// Sets the speed data stored for an edge.
+    ///
+    /// This lets live traffic be written in-process
+    /// (ex: by a service consuming a traffic feed) without a separate tool.
+    /// Readers in other processes (ex: Valhalla) sharing the file see the update immediately.
     ///
     /// NOTE: this will update the memory map immediately,
     /// but it does not guarantee that the data has been durably stored until you call
@@ -207,7 +312,7 @@ impl TrafficTileProvider<true> {
     /// # Errors
     ///
     /// Fails if the edge doesn't exist in the traffic tile.
-    pub unsafe fn update_speed_for_edge(
+    pub unsafe fn set_speed(
         &self,
         graph_id: GraphId,
         speed: TrafficSpeed,
@@ -222,6 +327,54 @@ impl TrafficTileProvider<true> {
         unsafe { speed_pointer.write_volatile(speed) };
         Ok(())
     }
+
+    /// Sets the last update time (in seconds since the epoch) of the tile containing `graph_id`.
+    ///
+    /// Valhalla doesn't use this for routing, but it is how consumers (ex: monitoring)
+    /// tell whether the traffic data is fresh,
+    /// so set it after writing a batch of speeds to a tile.
+    ///
+    /// NOTE: like [`TrafficTileProvider<true>::set_speed`], this is not durable
+    /// until you call [`TrafficTileProvider<true>::flush`].
+    ///
+    /// # Safety
+    ///
+    /// Assumes that the header is present and valid,
+    /// and that the platform supports atomic 64-bit integer store operations.
+    /// See the [type-level documentation](TrafficTileProvider) for details.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the tile doesn't exist, or uses an unsupported version.
+    pub unsafe fn set_last_update(
+        &self,
+        graph_id: GraphId,
+        timestamp: u64,
+    ) -> Result<(), GraphTileProviderError> {
+        const LAST_UPDATE_SIZE: u32 = u64::BITS / 8;
+
+        let tile_pointer = self
+            .tarball_tile_provider
+            .get_pointer_for_tile_containing(graph_id)?;
+        // SAFETY: See function-level docs.
+        // This also checks the version, so we don't write into a tile we don't understand.
+        unsafe { Self::read_header(&tile_pointer)? };
+
+        let last_update_pointer = MmapTilePointer {
+            mmap: tile_pointer.mmap.clone(),
+            offsets: TileOffset {
+                offset: tile_pointer.offsets.offset + TrafficTileHeader::LAST_UPDATE_OFFSET as u64,
+                size: LAST_UPDATE_SIZE,
+            },
+        };
+
+        // SAFETY: The header is within the tile (checked when reading it above),
+        // and we write a plain (aligned, checked internally) u64 rather than the unaligned
+        // header field type so that the store is atomic.
+        // The tile format is little endian.
+        unsafe { last_update_pointer.write_volatile(timestamp.to_le()) };
+        Ok(())
+    }
 }
 
 #[cfg(all(test, not(miri)))]
@@ -291,7 +444,7 @@ mod tests {
 
         unsafe {
             provider
-                .update_speed_for_edge(
+                .set_speed(
                     graph_id,
                     TrafficSpeed::single_speed(SpeedValue::try_new(DESIRED_SPEED).unwrap(), None),
                 )
@@ -306,4 +459,59 @@ mod tests {
         assert!(edge_speed.has_valid_speed());
         assert_eq!(edge_speed.overall_speed(), Some(DESIRED_SPEED));
     }
+
+    #[test]
+    fn test_last_update() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-traffic.tar");
+        let provider =
+            TrafficTileProvider::new_readonly(path).expect("Unable to init tile provider");
+
+        let last_update = unsafe { provider.last_update().expect("Unable to read headers") };
+        // The fixture was generated with valhalla_build_extract, and never updated
+        assert_eq!(last_update, None);
+    }
+
+    #[test]
+    fn test_set_last_update() {
+        const TIMESTAMP: u64 = 1_750_000_000;
+
+        let fixture_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join("andorra-traffic.tar");
+        let tmp_dir = option_env!("RUNNER_TEMP").unwrap_or("/tmp");
+        let tmp_path = PathBuf::from(tmp_dir).join("traffic-test-set-last-update.tar");
+        std::fs::copy(fixture_path, &tmp_path).expect("Failed to copy");
+        let provider =
+            TrafficTileProvider::new_mutable(&tmp_path).expect("Unable to init tile provider");
+
+        let graph_id = GraphId::try_from_components(0, 3015, 0).expect("Unable to create graph ID");
+        unsafe {
+            provider
+                .set_last_update(graph_id, TIMESTAMP)
+                .expect("Failed to set last update");
+        }
+        provider.flush().expect("Failed to flush");
+        drop(provider);
+
+        // Reopen to check that the update went to disk, and that speeds are untouched
+        let provider =
+            TrafficTileProvider::new_readonly(&tmp_path).expect("Unable to init tile provider");
+        let last_update = unsafe { provider.last_update().expect("Unable to read headers") };
+        assert_eq!(last_update, Some(TIMESTAMP));
+        let edge_speed = unsafe {
+            provider
+                .get_speeds_for_edge(GraphId::try_from_components(0, 3015, 42).unwrap())
+                .expect("Unable to get speed")
+        };
+        assert_eq!(edge_speed.overall_speed(), Some(32));
+
+        let missing = GraphId::try_from_components(0, 0, 0).expect("Unable to create graph ID");
+        let provider =
+            TrafficTileProvider::new_mutable(&tmp_path).expect("Unable to init tile provider");
+        assert!(unsafe { provider.set_last_update(missing, TIMESTAMP) }.is_err());
+
+        std::fs::remove_file(tmp_path).expect("Failed to clean up");
+    }
 }
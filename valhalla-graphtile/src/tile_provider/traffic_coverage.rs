@@ -0,0 +1,310 @@
+use super::{GraphTileProvider, GraphTileProviderError, TrafficTileProvider};
+use crate::GraphId;
+use crate::graph_tile::GraphTile;
+use crate::traffic_tile::{SegmentTrafficInfo, TrafficSpeed};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Live traffic coverage of a single graph tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileTrafficCoverage {
+    /// The base ID of the graph tile.
+    pub graph_id: GraphId,
+    /// Whether the traffic extract contains a tile for this graph tile.
+    pub has_traffic_tile: bool,
+    /// The number of directed edges in the graph tile.
+    pub edge_count: u32,
+    /// The number of edges with valid speed information (including closures).
+    pub edges_with_speed: u32,
+    /// The number of edges which are completely or partially closed.
+    pub closed_edges: u32,
+    /// The number of segments with a known congestion value.
+    pub congestion_samples: u64,
+    /// The sum of all known congestion values (1-63), for computing averages.
+    pub total_congestion: u64,
+    /// The last time the traffic tile was updated, in seconds since the epoch.
+    ///
+    /// This is `None` if the tile has never been updated (or doesn't exist).
+    pub last_update: Option<u64>,
+}
+
+impl TileTrafficCoverage {
+    fn new(graph_id: GraphId, edge_count: u32) -> Self {
+        Self {
+            graph_id,
+            has_traffic_tile: false,
+            edge_count,
+            edges_with_speed: 0,
+            closed_edges: 0,
+            congestion_samples: 0,
+            total_congestion: 0,
+            last_update: None,
+        }
+    }
+
+    fn add_edge(&mut self, speed: TrafficSpeed) {
+        if !speed.has_valid_speed() {
+            return;
+        }
+
+        self.edges_with_speed += 1;
+        if speed.is_completely_closed() || (0..3).any(|segment| speed.is_segment_closed(segment)) {
+            self.closed_edges += 1;
+        }
+        for segment in 0..3 {
+            if let SegmentTrafficInfo::Speed {
+                congestion: Some(congestion),
+                ..
+            } = speed.segment_info(segment)
+            {
+                self.congestion_samples += 1;
+                self.total_congestion += u64::from(congestion);
+            }
+        }
+    }
+
+    /// The fraction of edges with valid speed information (0 to 1).
+    pub fn speed_coverage(&self) -> f64 {
+        ratio(u64::from(self.edges_with_speed), u64::from(self.edge_count))
+    }
+
+    /// The average congestion (1-63) across segments with a known congestion value.
+    pub fn average_congestion(&self) -> Option<f64> {
+        average(self.total_congestion, self.congestion_samples)
+    }
+
+    /// How long ago the tile was last updated, relative to `now`.
+    ///
+    /// Returns `None` if the tile has never been updated.
+    pub fn staleness(&self, now: SystemTime) -> Option<Duration> {
+        self.last_update
+            .map(|last_update| staleness(last_update, now))
+    }
+}
+
+/// Live traffic coverage across a whole routing graph.
+///
+/// This is useful for monitoring the health of a traffic feed
+/// (ex: a sudden drop in coverage, or tiles which haven't been updated recently).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrafficCoverage {
+    /// Coverage for each graph tile, ordered by level and then tile ID.
+    pub tiles: Vec<TileTrafficCoverage>,
+}
+
+impl TrafficCoverage {
+    /// The total number of directed edges in the graph.
+    pub fn edge_count(&self) -> u64 {
+        self.tiles
+            .iter()
+            .map(|tile| u64::from(tile.edge_count))
+            .sum()
+    }
+
+    /// The total number of edges with valid speed information (including closures).
+    pub fn edges_with_speed(&self) -> u64 {
+        self.tiles
+            .iter()
+            .map(|tile| u64::from(tile.edges_with_speed))
+            .sum()
+    }
+
+    /// The total number of edges which are completely or partially closed.
+    pub fn closed_edges(&self) -> u64 {
+        self.tiles
+            .iter()
+            .map(|tile| u64::from(tile.closed_edges))
+            .sum()
+    }
+
+    /// The fraction of edges in the graph with valid speed information (0 to 1).
+    pub fn speed_coverage(&self) -> f64 {
+        ratio(self.edges_with_speed(), self.edge_count())
+    }
+
+    /// The average congestion (1-63) across all segments with a known congestion value.
+    pub fn average_congestion(&self) -> Option<f64> {
+        let (total, samples) = self.tiles.iter().fold((0, 0), |(total, samples), tile| {
+            (
+                total + tile.total_congestion,
+                samples + tile.congestion_samples,
+            )
+        });
+        average(total, samples)
+    }
+
+    /// The graph tiles which have no traffic tile in the extract.
+    pub fn missing_tiles(&self) -> impl Iterator<Item = GraphId> {
+        self.tiles
+            .iter()
+            .filter(|tile| !tile.has_traffic_tile)
+            .map(|tile| tile.graph_id)
+    }
+
+    /// The oldest update time (in seconds since the epoch) among tiles which have been updated.
+    pub fn oldest_update(&self) -> Option<u64> {
+        self.tiles.iter().filter_map(|tile| tile.last_update).min()
+    }
+
+    /// The most recent update time (in seconds since the epoch) across all tiles.
+    pub fn newest_update(&self) -> Option<u64> {
+        self.tiles.iter().filter_map(|tile| tile.last_update).max()
+    }
+
+    /// How long ago the least recently updated tile was updated, relative to `now`.
+    ///
+    /// Tiles which have never been updated are not considered.
+    pub fn max_staleness(&self, now: SystemTime) -> Option<Duration> {
+        self.oldest_update()
+            .map(|last_update| staleness(last_update, now))
+    }
+}
+
+/// Computes live traffic coverage statistics for every tile in `graph`.
+///
+/// This reads every speed in the extract, so it is relatively expensive for large graphs;
+/// run it periodically (ex: after each feed update) rather than per request.
+///
+/// # Safety
+///
+/// Reads speeds through a shared memory map.
+/// See the [`TrafficTileProvider`] docs for details.
+///
+/// # Errors
+///
+/// Fails if a graph or traffic tile can't be read,
+/// or if a traffic tile doesn't have the same number of edges as its graph tile
+/// (which means that the extract was built for a different graph).
+pub unsafe fn traffic_coverage<P: GraphTileProvider, const MUT: bool>(
+    graph: &P,
+    traffic: &TrafficTileProvider<MUT>,
+) -> Result<TrafficCoverage, GraphTileProviderError> {
+    let mut tiles = Vec::new();
+    for graph_id in graph.available_tiles()? {
+        let edge_count =
+            graph.with_tile_containing(graph_id, |tile| tile.header().directed_edge_count())?;
+        let mut coverage = TileTrafficCoverage::new(graph_id, edge_count);
+
+        // SAFETY: See function-level docs.
+        let header = match unsafe { traffic.tile_header(graph_id) } {
+            Ok(header) => header,
+            Err(GraphTileProviderError::TileDoesNotExist) => {
+                tiles.push(coverage);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        if header.directed_edge_count() != edge_count {
+            return Err(GraphTileProviderError::InvalidTarball(format!(
+                "Traffic tile {graph_id} has {} edges, but the graph tile has {edge_count}",
+                header.directed_edge_count()
+            )));
+        }
+
+        coverage.has_traffic_tile = true;
+        coverage.last_update = Some(header.last_update()).filter(|&timestamp| timestamp > 0);
+        for index in 0..u64::from(edge_count) {
+            let edge_id =
+                GraphId::try_from_components(graph_id.level(), graph_id.tile_id(), index)?;
+            // SAFETY: See function-level docs.
+            coverage.add_edge(unsafe { traffic.get_speeds_for_edge(edge_id)? });
+        }
+        tiles.push(coverage);
+    }
+
+    Ok(TrafficCoverage { tiles })
+}
+
+fn staleness(last_update: u64, now: SystemTime) -> Duration {
+    let updated_at = UNIX_EPOCH + Duration::from_secs(last_update);
+    // Clock skew can put the update in the future
+    now.duration_since(updated_at).unwrap_or_default()
+}
+
+#[expect(
+    clippy::cast_precision_loss,
+    reason = "Edge counts are well within the exactly representable range of an f64"
+)]
+fn ratio(numerator: u64, denominator: u64) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+fn average(total: u64, samples: u64) -> Option<f64> {
+    (samples > 0).then(|| ratio(total, samples))
+}
+
+#[cfg(all(test, not(miri)))]
+mod tests {
+    use super::traffic_coverage;
+    use crate::GraphId;
+    use crate::tile_provider::{DirectoryGraphTileProvider, TrafficTileProvider};
+    use crate::traffic_tile::{CongestionValue, SpeedValue, TrafficSpeed};
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_traffic_coverage() {
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let graph =
+            DirectoryGraphTileProvider::new(fixtures.join("andorra-tiles"), NonZeroUsize::MIN);
+        let path = std::env::temp_dir().join(format!(
+            "valinor-traffic-coverage-{}.tar",
+            std::process::id()
+        ));
+        std::fs::copy(fixtures.join("andorra-traffic.tar"), &path).unwrap();
+
+        let traffic = TrafficTileProvider::new_mutable(&path).unwrap();
+        let coverage = unsafe { traffic_coverage(&graph, &traffic) }.unwrap();
+        assert_eq!(coverage.tiles.len(), 7);
+        assert_eq!(coverage.missing_tiles().count(), 0);
+        assert!(coverage.edges_with_speed() > 0);
+        assert!(coverage.speed_coverage() > 0.0 && coverage.speed_coverage() < 1.0);
+        assert_eq!(coverage.newest_update(), None);
+        let edges_with_speed = coverage.edges_with_speed();
+        let closed_edges = coverage.closed_edges();
+
+        // Close one edge which had no speed, and add a congested speed to another
+        let tile_id = GraphId::try_from_components(0, 3015, 0).unwrap();
+        let congested_edge = GraphId::try_from_components(0, 3015, 1).unwrap();
+        for edge_id in [tile_id, congested_edge] {
+            assert!(
+                !unsafe { traffic.get_speeds_for_edge(edge_id) }
+                    .unwrap()
+                    .has_valid_speed()
+            );
+        }
+        unsafe {
+            traffic.set_speed(tile_id, TrafficSpeed::closed()).unwrap();
+            traffic
+                .set_speed(
+                    congested_edge,
+                    TrafficSpeed::single_speed(
+                        SpeedValue::try_new(32).unwrap(),
+                        Some(CongestionValue::try_new(20).unwrap()),
+                    ),
+                )
+                .unwrap();
+            traffic.set_last_update(tile_id, 1_750_000_000).unwrap();
+        }
+
+        let coverage = unsafe { traffic_coverage(&graph, &traffic) }.unwrap();
+        assert_eq!(coverage.edges_with_speed(), edges_with_speed + 2);
+        assert_eq!(coverage.closed_edges(), closed_edges + 1);
+        assert_eq!(coverage.average_congestion(), Some(20.0));
+        assert_eq!(coverage.oldest_update(), Some(1_750_000_000));
+        let now = UNIX_EPOCH + Duration::from_secs(1_750_000_090);
+        assert_eq!(coverage.max_staleness(now), Some(Duration::from_secs(90)));
+        assert_eq!(
+            coverage.tiles[0].staleness(now),
+            Some(Duration::from_secs(90))
+        );
+        assert_eq!(coverage.tiles[1].staleness(now), None);
+
+        drop(traffic);
+        std::fs::remove_file(path).unwrap();
+    }
+}
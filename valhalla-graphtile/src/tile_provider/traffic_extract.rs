@@ -0,0 +1,358 @@
+use super::tarball_index::TileIndexBinEntry;
+use super::{GraphTileProvider, GraphTileProviderError, TrafficTileProvider};
+use crate::GraphId;
+use crate::graph_tile::GraphTile;
+use crate::traffic_tile::{TrafficSpeed, TrafficTileHeader};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use tar::{EntryType, Header};
+use zerocopy::IntoBytes;
+
+/// The size of a tar header block (entries are also padded to a multiple of this).
+const TAR_BLOCK_SIZE: u64 = 512;
+
+/// Builds a live traffic extract (the tarball Valhalla reads via `mjolnir.traffic_extract`).
+///
+/// The extract contains one traffic tile for each graph tile,
+/// with a slot for every directed edge.
+/// All speeds start out with no data;
+/// use a [`TrafficExtractUpdater`] to fill them in.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use std::num::NonZeroUsize;
+/// # use valhalla_graphtile::tile_provider::{DirectoryGraphTileProvider, TrafficExtractBuilder};
+/// let graph = DirectoryGraphTileProvider::new("valhalla_tiles".into(), NonZeroUsize::MIN);
+/// TrafficExtractBuilder::from_graph(&graph)?.write_to_path("traffic.tar")?;
+/// # Ok::<(), valhalla_graphtile::tile_provider::GraphTileProviderError>(())
+/// ```
+#[derive(Default)]
+pub struct TrafficExtractBuilder {
+    /// Base graph IDs and their directed edge counts.
+    tiles: Vec<(GraphId, u32)>,
+}
+
+impl TrafficExtractBuilder {
+    /// Creates a builder for an empty extract.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a builder with a traffic tile for every tile in the routing graph.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the graph tiles can't be listed or read.
+    pub fn from_graph<P: GraphTileProvider>(provider: &P) -> Result<Self, GraphTileProviderError> {
+        provider
+            .available_tiles()?
+            .into_iter()
+            .try_fold(Self::new(), |builder, graph_id| {
+                let directed_edge_count = provider
+                    .with_tile_containing(graph_id, |tile| tile.header().directed_edge_count())?;
+                Ok(builder.with_tile(graph_id, directed_edge_count))
+            })
+    }
+
+    /// Adds a traffic tile for the graph tile containing `graph_id`,
+    /// which has the given number of directed edges.
+    ///
+    /// Adding the same tile again replaces the edge count.
+    #[must_use]
+    pub fn with_tile(mut self, graph_id: GraphId, directed_edge_count: u32) -> Self {
+        let base_id = graph_id.tile_base_id();
+        if let Some(tile) = self.tiles.iter_mut().find(|(id, _)| *id == base_id) {
+            tile.1 = directed_edge_count;
+        } else {
+            self.tiles.push((base_id, directed_edge_count));
+        }
+        self
+    }
+
+    /// Writes the extract (including the `index.bin` which Valhalla and
+    /// [`TrafficTileProvider`] require) to `writer`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if writing fails, or if a tile is too large for the index format.
+    pub fn write<W: Write>(mut self, writer: W) -> Result<W, GraphTileProviderError> {
+        self.tiles
+            .sort_by_key(|(graph_id, _)| (graph_id.level(), graph_id.tile_id()));
+
+        let tile_sizes = self
+            .tiles
+            .iter()
+            .map(|&(_, directed_edge_count)| {
+                let size = size_of::<TrafficTileHeader>() as u64
+                    + size_of::<TrafficSpeed>() as u64 * u64::from(directed_edge_count);
+                u32::try_from(size).map_err(|_| {
+                    GraphTileProviderError::InvalidTarball(format!(
+                        "A traffic tile with {directed_edge_count} edges is too large for the index"
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Lay out the archive up front, since the index comes first
+        let index_size = (size_of::<TileIndexBinEntry>() * self.tiles.len()) as u64;
+        let mut offset = TAR_BLOCK_SIZE + index_size.next_multiple_of(TAR_BLOCK_SIZE);
+        let mut index = Vec::with_capacity(self.tiles.len());
+        for (&(graph_id, _), &size) in self.tiles.iter().zip(&tile_sizes) {
+            index.push(TileIndexBinEntry::new(
+                offset + TAR_BLOCK_SIZE,
+                graph_id,
+                size,
+            ));
+            offset += TAR_BLOCK_SIZE + u64::from(size).next_multiple_of(TAR_BLOCK_SIZE);
+        }
+
+        let mut archive = tar::Builder::new(writer);
+        append_entry(&mut archive, Path::new("index.bin"), index.as_bytes())?;
+
+        let mut tile_bytes = Vec::new();
+        for (&(graph_id, directed_edge_count), &size) in self.tiles.iter().zip(&tile_sizes) {
+            tile_bytes.clear();
+            tile_bytes.reserve(size as usize);
+            tile_bytes.extend_from_slice(
+                TrafficTileHeader::new(graph_id, directed_edge_count).as_bytes(),
+            );
+            tile_bytes.resize(size as usize, 0);
+            append_entry(&mut archive, &graph_id.file_path("gph")?, &tile_bytes)?;
+        }
+
+        Ok(archive.into_inner()?)
+    }
+
+    /// Writes the extract to a file at `path`, replacing any existing file.
+    ///
+    /// To replace an extract which is in use, write to a temporary path
+    /// and rename it into place, since readers memory map the file.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can't be written, or if a tile is too large for the index format.
+    pub fn write_to_path<P: AsRef<Path>>(self, path: P) -> Result<(), GraphTileProviderError> {
+        let writer = self.write(BufWriter::new(File::create(path)?))?;
+        writer
+            .into_inner()
+            .map_err(std::io::IntoInnerError::into_error)?
+            .sync_all()?;
+        Ok(())
+    }
+}
+
+fn append_entry<W: Write>(
+    archive: &mut tar::Builder<W>,
+    path: &Path,
+    data: &[u8],
+) -> Result<(), GraphTileProviderError> {
+    let mut header = Header::new_ustar();
+    header.set_path(path)?;
+    header.set_entry_type(EntryType::Regular);
+    header.set_mode(0o644);
+    header.set_size(data.len() as u64);
+    header.set_cksum();
+    archive.append(&header, data)?;
+    Ok(())
+}
+
+/// Applies live traffic updates to an existing traffic extract.
+///
+/// Updates are written in place through a shared memory map,
+/// so a Valhalla instance (or [`TrafficTileProvider`]) reading the same file
+/// picks them up without a reload.
+pub struct TrafficExtractUpdater {
+    provider: TrafficTileProvider<true>,
+}
+
+impl TrafficExtractUpdater {
+    /// Opens the traffic extract at `path` for updating.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the extract can't be opened, or doesn't have a valid `index.bin`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, GraphTileProviderError> {
+        Ok(Self {
+            provider: TrafficTileProvider::new_mutable(path)?,
+        })
+    }
+
+    /// The underlying provider, for reading back speeds.
+    pub fn provider(&self) -> &TrafficTileProvider<true> {
+        &self.provider
+    }
+
+    /// Sets the speeds of the given edges,
+    /// stamps each affected tile with `timestamp` (in seconds since the epoch),
+    /// and flushes the changes to disk.
+    ///
+    /// Returns the number of edges updated.
+    ///
+    /// # Safety
+    ///
+    /// See [`TrafficTileProvider<true>::set_speed`].
+    ///
+    /// # Errors
+    ///
+    /// Fails on the first edge which isn't in the extract.
+    /// Updates before the failing edge have already been applied.
+    pub unsafe fn update<I>(
+        &self,
+        speeds: I,
+        timestamp: u64,
+    ) -> Result<usize, GraphTileProviderError>
+    where
+        I: IntoIterator<Item = (GraphId, TrafficSpeed)>,
+    {
+        let mut updated_tiles = HashSet::new();
+        let mut edge_count = 0;
+        for (graph_id, speed) in speeds {
+            // SAFETY: See function-level docs.
+            unsafe { self.provider.set_speed(graph_id, speed)? };
+            updated_tiles.insert(graph_id.tile_base_id());
+            edge_count += 1;
+        }
+
+        for graph_id in updated_tiles {
+            // SAFETY: See function-level docs.
+            unsafe { self.provider.set_last_update(graph_id, timestamp)? };
+        }
+
+        self.provider.flush()?;
+        Ok(edge_count)
+    }
+
+    /// Clears the speeds of every edge in the tile containing `graph_id`,
+    /// stamps it with `timestamp` (in seconds since the epoch), and flushes the changes to disk.
+    ///
+    /// Use this before writing a fresh set of speeds for a tile,
+    /// so that edges which are no longer in the feed don't keep stale speeds.
+    ///
+    /// # Safety
+    ///
+    /// See [`TrafficTileProvider<true>::set_speed`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the tile isn't in the extract.
+    pub unsafe fn clear_tile(
+        &self,
+        graph_id: GraphId,
+        timestamp: u64,
+    ) -> Result<(), GraphTileProviderError> {
+        let base_id = graph_id.tile_base_id();
+        // SAFETY: See function-level docs.
+        let header = unsafe { self.provider.tile_header(base_id)? };
+        for index in 0..u64::from(header.directed_edge_count()) {
+            let edge_id = GraphId::try_from_components(base_id.level(), base_id.tile_id(), index)?;
+            // SAFETY: See function-level docs.
+            unsafe { self.provider.set_speed(edge_id, TrafficSpeed::new())? };
+        }
+        // SAFETY: See function-level docs.
+        unsafe { self.provider.set_last_update(base_id, timestamp)? };
+
+        self.provider.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, not(miri)))]
+mod tests {
+    use super::{TrafficExtractBuilder, TrafficExtractUpdater};
+    use crate::GraphId;
+    use crate::graph_tile::GraphTile;
+    use crate::tile_provider::{
+        DirectoryGraphTileProvider, GraphTileProvider, TarballTileProvider, TrafficTileProvider,
+    };
+    use crate::traffic_tile::{SpeedValue, TrafficSpeed};
+    use std::collections::HashMap;
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+
+    fn fixture_path(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn test_build_and_update_extract() {
+        let graph =
+            DirectoryGraphTileProvider::new(fixture_path("andorra-tiles"), NonZeroUsize::MIN);
+        let path = std::env::temp_dir().join(format!(
+            "valinor-traffic-extract-{}.tar",
+            std::process::id()
+        ));
+        TrafficExtractBuilder::from_graph(&graph)
+            .expect("Unable to read graph")
+            .write_to_path(&path)
+            .expect("Unable to write extract");
+
+        // The layout should match what valhalla_build_extract produces
+        let sizes = |path: &PathBuf| -> HashMap<GraphId, usize> {
+            let provider = TarballTileProvider::new_readonly(path).expect("Unable to open");
+            provider
+                .tile_ids()
+                .map(|&graph_id| {
+                    let pointer = provider.get_pointer_for_tile_containing(graph_id).unwrap();
+                    (graph_id, unsafe { pointer.as_tile_bytes() }.len())
+                })
+                .collect()
+        };
+        assert_eq!(sizes(&path), sizes(&fixture_path("andorra-traffic.tar")));
+
+        let traffic = TrafficTileProvider::new_readonly(&path).expect("Unable to open extract");
+        for graph_id in graph.available_tiles().unwrap() {
+            let header = unsafe { traffic.tile_header(graph_id) }.expect("Missing tile");
+            let edge_count = graph
+                .with_tile_containing(graph_id, |tile| tile.header().directed_edge_count())
+                .unwrap();
+            assert_eq!(header.directed_edge_count(), edge_count);
+        }
+        let edge_id = GraphId::try_from_components(0, 3015, 42).unwrap();
+        assert!(
+            !unsafe { traffic.get_speeds_for_edge(edge_id) }
+                .unwrap()
+                .has_valid_speed()
+        );
+        assert_eq!(unsafe { traffic.last_update() }.unwrap(), None);
+        drop(traffic);
+
+        let updater = TrafficExtractUpdater::open(&path).expect("Unable to open extract");
+        let speed = TrafficSpeed::single_speed(SpeedValue::try_new(50).unwrap(), None);
+        let edge_count = unsafe { updater.update([(edge_id, speed)], 1_750_000_000) }
+            .expect("Unable to update speeds");
+        assert_eq!(edge_count, 1);
+        let provider = updater.provider();
+        assert_eq!(
+            unsafe { provider.get_speeds_for_edge(edge_id) }
+                .unwrap()
+                .overall_speed(),
+            Some(50)
+        );
+        assert_eq!(
+            unsafe { provider.last_update() }.unwrap(),
+            Some(1_750_000_000)
+        );
+
+        unsafe { updater.clear_tile(edge_id, 1_750_000_060) }.expect("Unable to clear tile");
+        assert!(
+            !unsafe { provider.get_speeds_for_edge(edge_id) }
+                .unwrap()
+                .has_valid_speed()
+        );
+        assert_eq!(
+            unsafe { provider.last_update() }.unwrap(),
+            Some(1_750_000_060)
+        );
+
+        let missing = GraphId::try_from_components(0, 0, 0).unwrap();
+        assert!(unsafe { updater.update([(missing, speed)], 0) }.is_err());
+
+        drop(updater);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,200 @@
+use super::{GraphTileProvider, GraphTileProviderError, TrafficTileProvider};
+use crate::GraphId;
+use crate::graph_tile::GraphTile;
+use crate::traffic_tile::TRAFFIC_TILE_VERSION;
+use std::collections::HashSet;
+use thiserror::Error;
+
+/// A mismatch between a traffic extract and the routing graph it is meant for.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TrafficExtractIssue {
+    #[error("Graph tile {graph_id} has no traffic tile.")]
+    MissingTrafficTile { graph_id: GraphId },
+    #[error("Traffic tile {graph_id} has no corresponding graph tile.")]
+    OrphanTrafficTile { graph_id: GraphId },
+    #[error("Traffic tile {graph_id} has version {version}; expected {TRAFFIC_TILE_VERSION}.")]
+    UnsupportedVersion { graph_id: GraphId, version: u32 },
+    #[error("Traffic tile {graph_id} has a header for {}.", describe_tile_id(*.header_tile_id))]
+    TileIdMismatch {
+        graph_id: GraphId,
+        /// The tile ID in the header, or `None` if it isn't a valid graph ID.
+        header_tile_id: Option<GraphId>,
+    },
+    #[error(
+        "Traffic tile {graph_id} has {traffic_edge_count} directed edges, but the graph tile has {graph_edge_count}."
+    )]
+    EdgeCountMismatch {
+        graph_id: GraphId,
+        graph_edge_count: u32,
+        traffic_edge_count: u32,
+    },
+}
+
+fn describe_tile_id(tile_id: Option<GraphId>) -> String {
+    tile_id.map_or_else(
+        || "an invalid tile ID".to_string(),
+        |id| format!("tile {id}"),
+    )
+}
+
+/// The result of validating a traffic extract against a routing graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrafficExtractReport {
+    /// The number of tiles in the routing graph.
+    pub graph_tile_count: usize,
+    /// The number of tiles in the traffic extract.
+    pub traffic_tile_count: usize,
+    /// Every issue found, ordered by graph tile (orphaned traffic tiles come last).
+    pub issues: Vec<TrafficExtractIssue>,
+}
+
+impl TrafficExtractReport {
+    /// Returns true if no issues were found.
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Cross-checks a traffic extract against the routing graph it is meant for.
+///
+/// Valhalla looks up live speeds by directed edge index,
+/// so an extract built for a different graph silently assigns speeds to the wrong edges.
+/// This checks that every graph tile has a traffic tile (and vice versa),
+/// and that each traffic tile header has the right tile ID, edge count, and version.
+/// Run it before shipping a new graph or extract to production.
+///
+/// # Safety
+///
+/// Reads tile headers through a shared memory map.
+/// See the [`TrafficTileProvider`] docs for details.
+///
+/// # Errors
+///
+/// Fails if a graph tile can't be read, or if the traffic extract can't be read at all.
+/// Mismatches are reported in the [`TrafficExtractReport`] rather than as errors.
+pub unsafe fn validate_traffic_extract<P: GraphTileProvider, const MUT: bool>(
+    graph: &P,
+    traffic: &TrafficTileProvider<MUT>,
+) -> Result<TrafficExtractReport, GraphTileProviderError> {
+    let graph_tiles = graph.available_tiles()?;
+    let mut issues = Vec::new();
+
+    for &graph_id in &graph_tiles {
+        // SAFETY: See function-level docs.
+        let header = match unsafe { traffic.tile_header_unchecked(graph_id) } {
+            Ok(header) => header,
+            Err(GraphTileProviderError::TileDoesNotExist) => {
+                issues.push(TrafficExtractIssue::MissingTrafficTile { graph_id });
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        let version = header.traffic_tile_version();
+        if version != TRAFFIC_TILE_VERSION {
+            // The rest of the header can't be trusted
+            issues.push(TrafficExtractIssue::UnsupportedVersion { graph_id, version });
+            continue;
+        }
+
+        let header_tile_id = header.tile_id().ok();
+        if header_tile_id != Some(graph_id) {
+            issues.push(TrafficExtractIssue::TileIdMismatch {
+                graph_id,
+                header_tile_id,
+            });
+        }
+
+        let graph_edge_count =
+            graph.with_tile_containing(graph_id, |tile| tile.header().directed_edge_count())?;
+        let traffic_edge_count = header.directed_edge_count();
+        if traffic_edge_count != graph_edge_count {
+            issues.push(TrafficExtractIssue::EdgeCountMismatch {
+                graph_id,
+                graph_edge_count,
+                traffic_edge_count,
+            });
+        }
+    }
+
+    let graph_tile_set: HashSet<_> = graph_tiles.iter().collect();
+    let mut orphans: Vec<_> = traffic
+        .tile_ids()
+        .filter(|graph_id| !graph_tile_set.contains(graph_id))
+        .copied()
+        .collect();
+    orphans.sort_unstable_by_key(|graph_id| (graph_id.level(), graph_id.tile_id()));
+    issues.extend(
+        orphans
+            .into_iter()
+            .map(|graph_id| TrafficExtractIssue::OrphanTrafficTile { graph_id }),
+    );
+
+    Ok(TrafficExtractReport {
+        graph_tile_count: graph_tiles.len(),
+        traffic_tile_count: traffic.tile_ids().count(),
+        issues,
+    })
+}
+
+#[cfg(all(test, not(miri)))]
+mod tests {
+    use super::{TrafficExtractIssue, validate_traffic_extract};
+    use crate::GraphId;
+    use crate::tile_provider::{
+        DirectoryGraphTileProvider, TrafficExtractBuilder, TrafficTileProvider,
+    };
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_validate_traffic_extract() {
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let graph =
+            DirectoryGraphTileProvider::new(fixtures.join("andorra-tiles"), NonZeroUsize::MIN);
+
+        let traffic =
+            TrafficTileProvider::new_readonly(fixtures.join("andorra-traffic.tar")).unwrap();
+        let report = unsafe { validate_traffic_extract(&graph, &traffic) }.unwrap();
+        assert!(report.is_valid(), "{:?}", report.issues);
+        assert_eq!(report.graph_tile_count, 7);
+        assert_eq!(report.traffic_tile_count, 7);
+
+        // An extract for a slightly different graph
+        let path = std::env::temp_dir().join(format!(
+            "valinor-traffic-validation-{}.tar",
+            std::process::id()
+        ));
+        let wrong_edge_count = GraphId::try_from_components(0, 3015, 0).unwrap();
+        let orphan = GraphId::try_from_components(0, 3016, 0).unwrap();
+        TrafficExtractBuilder::new()
+            .with_tile(wrong_edge_count, 1)
+            .with_tile(orphan, 1)
+            .write_to_path(&path)
+            .unwrap();
+        let traffic = TrafficTileProvider::new_readonly(&path).unwrap();
+        let report = unsafe { validate_traffic_extract(&graph, &traffic) }.unwrap();
+        assert_eq!(report.traffic_tile_count, 2);
+        assert_eq!(report.issues.len(), 8);
+        assert!(matches!(
+            report.issues[0],
+            TrafficExtractIssue::EdgeCountMismatch {
+                graph_id,
+                traffic_edge_count: 1,
+                ..
+            } if graph_id == wrong_edge_count
+        ));
+        assert!(
+            report.issues[1..7]
+                .iter()
+                .all(|issue| matches!(issue, TrafficExtractIssue::MissingTrafficTile { .. }))
+        );
+        assert_eq!(
+            report.issues[7],
+            TrafficExtractIssue::OrphanTrafficTile { graph_id: orphan }
+        );
+
+        drop(traffic);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -3,11 +3,12 @@
 //! This module provides data structures for working with Valhalla live traffic tiles.
 //! These follow a different format from the routing graph.
 
+use crate::spatial::line_substring;
 use crate::traffic_tile::TrafficSpeedBuilderError::{SectionLengthExceedsEdge, TooManySegments};
+use crate::{GraphId, InvalidGraphIdError};
 use bitfield_struct::bitfield;
+use geo::{Coord, LineString};
 use nutype::nutype;
-#[cfg(feature = "serde")]
-use serde::{Serialize, Serializer, ser::SerializeStruct};
 use thiserror::Error;
 use zerocopy::{LE, U32, U64};
 use zerocopy_derive::{FromBytes, Immutable, IntoBytes, Unaligned};
@@ -28,6 +29,10 @@ const CLOSED_TRAFFIC_SPEED_RAW: u8 = 0;
 pub const UNKNOWN_CONGESTION_VAL: u8 = 0;
 pub const MAX_CONGESTION_VAL: u8 = 63;
 
+/// Adjacent profile readings within this many kph of each other are treated as the same speed
+/// (see [`TrafficSpeedBuilder::from_profile`]).
+pub const SIMILAR_SPEED_TOLERANCE_KPH: u8 = 4;
+
 /// The Valhalla traffic tile version.
 /// See the note on the `traffic_tile_version` field.
 /// In Valhalla, this is currently tied to the Valhalla major version.
@@ -36,7 +41,7 @@ pub const TRAFFIC_TILE_VERSION: u32 = 3;
 /// The header for a traffic tile.
 ///
 /// Every tile starts off with one of these.
-#[derive(FromBytes, IntoBytes, Unaligned)]
+#[derive(FromBytes, IntoBytes, Immutable, Unaligned)]
 #[repr(C)]
 pub struct TrafficTileHeader {
     tile_id: U64<LE>,
@@ -59,10 +64,46 @@ pub struct TrafficTileHeader {
 }
 
 impl TrafficTileHeader {
+    /// Creates a header for a tile which has never been updated.
+    ///
+    /// `tile_id` should be the base ID of the corresponding graph tile,
+    /// and `directed_edge_count` must match the graph tile
+    /// (Valhalla looks up speeds by directed edge index).
+    pub fn new(tile_id: GraphId, directed_edge_count: u32) -> Self {
+        Self {
+            tile_id: U64::new(tile_id.tile_base_id().value()),
+            last_update: U64::ZERO,
+            directed_edge_count: U32::new(directed_edge_count),
+            traffic_tile_version: U32::new(TRAFFIC_TILE_VERSION),
+            _spare2: U32::ZERO,
+            _spare3: U32::ZERO,
+        }
+    }
+
+    /// The byte offset of the last update field, for writing it in place.
+    #[cfg(feature = "fs")]
+    pub(crate) const LAST_UPDATE_OFFSET: usize = std::mem::offset_of!(Self, last_update);
+
+    /// The base ID of the graph tile which this traffic tile covers.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the header contains an invalid graph ID.
+    pub fn tile_id(&self) -> Result<GraphId, InvalidGraphIdError> {
+        GraphId::try_from_id(self.tile_id.get())
+    }
+
     pub fn directed_edge_count(&self) -> u32 {
         self.directed_edge_count.get()
     }
 
+    /// The last time the tile was updated, in seconds since the epoch.
+    ///
+    /// Valhalla leaves this at zero for tiles that have never been updated.
+    pub fn last_update(&self) -> u64 {
+        self.last_update.get()
+    }
+
     /// Gets the traffic tile version number.
     ///
     /// This is currently tied to the Valhalla major version number,
@@ -117,8 +158,10 @@ impl SpeedValue {
 pub struct CongestionValue(u8);
 
 /// The traffic conditions along a single segment in a traffic tile.
+///
+/// When the `serde` feature is enabled, breakpoints are (de)serialized
+/// as a fraction of the edge length (0 to 1) rather than the raw 0-255 value.
 #[derive(Eq, PartialEq, Copy, Clone, Debug)]
-#[cfg_attr(feature = "serde", derive(Serialize))]
 pub enum SegmentTrafficInfo {
     /// There is no data for this segment.
     ///
@@ -308,6 +351,47 @@ impl TrafficSpeed {
         }
     }
 
+    /// Splits an edge shape into the segments described by this speed.
+    ///
+    /// Each segment's traffic info is paired with the part of the shape it covers,
+    /// where the breakpoints are treated as fractions of the shape's length.
+    /// This is useful for visualizations and exports that need to show partial closures
+    /// or slowdowns on the right part of the edge.
+    ///
+    /// `shape` must be in the direction of travel
+    /// (see [`EdgeInfo::traffic_segment_shapes`](crate::graph_tile::EdgeInfo::traffic_segment_shapes),
+    /// which handles this for you).
+    ///
+    /// Returns an empty vector if there is no speed data for the edge.
+    pub fn segment_shapes(
+        &self,
+        shape: &[Coord<f64>],
+    ) -> Vec<(SegmentTrafficInfo, LineString<f64>)> {
+        let mut segments = Vec::with_capacity(3);
+        let mut start = 0;
+        for segment_index in 0..3 {
+            let info = self.segment_info(segment_index);
+            let end = match info {
+                SegmentTrafficInfo::NoData { breakpoint }
+                | SegmentTrafficInfo::Closed { breakpoint }
+                | SegmentTrafficInfo::Speed { breakpoint, .. } => breakpoint,
+            };
+            // A zero breakpoint means there is no data from here on
+            if end == 0 || end < start {
+                break;
+            }
+
+            let line = line_substring(shape, f64::from(start) / 255.0, f64::from(end) / 255.0);
+            segments.push((info, LineString::new(line)));
+            if end == 255 {
+                break;
+            }
+            start = end;
+        }
+
+        segments
+    }
+
     // Builder methods
 
     /// Constructs a new [`TrafficSpeed`] where a single speed is applied to the entire edge.
@@ -356,23 +440,247 @@ impl TrafficSpeed {
 }
 
 #[cfg(feature = "serde")]
-impl Serialize for TrafficSpeed {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let num_fields = 7;
-        let mut state = serializer.serialize_struct("TrafficSpeed", num_fields)?;
-
-        state.serialize_field("has_valid_speed", &self.has_valid_speed())?;
-        state.serialize_field("is_completely_closed", &self.is_completely_closed())?;
-        state.serialize_field("overall_speed", &self.overall_speed())?;
-        state.serialize_field("segment_info_0", &self.segment_info(0))?;
-        state.serialize_field("segment_info_1", &self.segment_info(1))?;
-        state.serialize_field("segment_info_2", &self.segment_info(2))?;
-        state.serialize_field("has_incident_tile", &self.has_incidents())?;
-
-        state.end()
+mod serde_impls {
+    //! Human-readable (de)serialization for traffic tile types.
+    //!
+    //! These go through plain representation types
+    //! so that the JSON shows speeds in kph and breakpoints as fractions of the edge,
+    //! rather than the packed bitfields.
+
+    use super::{
+        CLOSED_TRAFFIC_SPEED_RAW, CongestionValue, MAX_TRAFFIC_SPEED_KPH, SegmentTrafficInfo,
+        SpeedValue, TrafficSpeed, TrafficTileHeader, UNKNOWN_CONGESTION_VAL,
+        UNKNOWN_TRAFFIC_SPEED_RAW,
+    };
+    use crate::GraphId;
+    use serde::de::Error as _;
+    use serde::ser::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use zerocopy::{LE, U32, U64};
+
+    #[derive(Serialize, Deserialize)]
+    struct TrafficTileHeaderRepr {
+        #[serde(with = "crate::hierarchical_graph_id")]
+        tile_id: GraphId,
+        last_update: u64,
+        directed_edge_count: u32,
+        traffic_tile_version: u32,
+    }
+
+    impl Serialize for TrafficTileHeader {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            TrafficTileHeaderRepr {
+                tile_id: GraphId::try_from_id(self.tile_id.get()).map_err(S::Error::custom)?,
+                last_update: self.last_update(),
+                directed_edge_count: self.directed_edge_count(),
+                traffic_tile_version: self.traffic_tile_version(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for TrafficTileHeader {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let repr = TrafficTileHeaderRepr::deserialize(deserializer)?;
+            Ok(Self {
+                tile_id: U64::new(repr.tile_id.value()),
+                last_update: U64::new(repr.last_update),
+                directed_edge_count: U32::<LE>::new(repr.directed_edge_count),
+                traffic_tile_version: U32::new(repr.traffic_tile_version),
+                _spare2: U32::ZERO,
+                _spare3: U32::ZERO,
+            })
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    enum SegmentTrafficInfoRepr {
+        NoData {
+            breakpoint: f64,
+        },
+        Closed {
+            breakpoint: f64,
+        },
+        Speed {
+            speed_kph: u8,
+            congestion: Option<u8>,
+            breakpoint: f64,
+        },
+    }
+
+    fn breakpoint_to_fraction(breakpoint: u8) -> f64 {
+        f64::from(breakpoint) / 255.0
+    }
+
+    fn breakpoint_from_fraction<E: serde::de::Error>(fraction: f64) -> Result<u8, E> {
+        if !(0.0..=1.0).contains(&fraction) {
+            return Err(E::custom(format!(
+                "Breakpoints must be a fraction between 0 and 1; got {fraction}"
+            )));
+        }
+
+        #[expect(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "The fraction is checked to be within [0, 1]"
+        )]
+        Ok((fraction * 255.0).round() as u8)
+    }
+
+    impl Serialize for SegmentTrafficInfo {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match *self {
+                Self::NoData { breakpoint } => SegmentTrafficInfoRepr::NoData {
+                    breakpoint: breakpoint_to_fraction(breakpoint),
+                },
+                Self::Closed { breakpoint } => SegmentTrafficInfoRepr::Closed {
+                    breakpoint: breakpoint_to_fraction(breakpoint),
+                },
+                Self::Speed {
+                    speed_kph,
+                    congestion,
+                    breakpoint,
+                } => SegmentTrafficInfoRepr::Speed {
+                    speed_kph,
+                    congestion,
+                    breakpoint: breakpoint_to_fraction(breakpoint),
+                },
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for SegmentTrafficInfo {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            Ok(match SegmentTrafficInfoRepr::deserialize(deserializer)? {
+                SegmentTrafficInfoRepr::NoData { breakpoint } => Self::NoData {
+                    breakpoint: breakpoint_from_fraction(breakpoint)?,
+                },
+                SegmentTrafficInfoRepr::Closed { breakpoint } => Self::Closed {
+                    breakpoint: breakpoint_from_fraction(breakpoint)?,
+                },
+                SegmentTrafficInfoRepr::Speed {
+                    speed_kph,
+                    congestion,
+                    breakpoint,
+                } => Self::Speed {
+                    speed_kph,
+                    congestion,
+                    breakpoint: breakpoint_from_fraction(breakpoint)?,
+                },
+            })
+        }
+    }
+
+    /// The derived flags are output for convenience, but ignored when deserializing;
+    /// the speed is reconstructed from the overall speed and segments.
+    #[derive(Serialize, Deserialize)]
+    struct TrafficSpeedRepr {
+        #[serde(default, skip_deserializing)]
+        has_valid_speed: bool,
+        #[serde(default, skip_deserializing)]
+        is_completely_closed: bool,
+        overall_speed: Option<u8>,
+        segment_info_0: SegmentTrafficInfo,
+        segment_info_1: SegmentTrafficInfo,
+        segment_info_2: SegmentTrafficInfo,
+        #[serde(default)]
+        has_incident_tile: bool,
+    }
+
+    /// Encodes a segment as (raw speed, breakpoint, congestion) bitfield values.
+    fn encode_segment<E: serde::de::Error>(segment: SegmentTrafficInfo) -> Result<(u8, u8, u8), E> {
+        match segment {
+            // A zero breakpoint marks the end of the data, so the speed bits are left empty
+            SegmentTrafficInfo::NoData { breakpoint: 0 } => Ok((0, 0, UNKNOWN_CONGESTION_VAL)),
+            SegmentTrafficInfo::NoData { breakpoint } => Ok((
+                UNKNOWN_TRAFFIC_SPEED_RAW,
+                breakpoint,
+                UNKNOWN_CONGESTION_VAL,
+            )),
+            SegmentTrafficInfo::Closed { breakpoint } => {
+                Ok((CLOSED_TRAFFIC_SPEED_RAW, breakpoint, UNKNOWN_CONGESTION_VAL))
+            }
+            SegmentTrafficInfo::Speed {
+                speed_kph,
+                congestion,
+                breakpoint,
+            } => {
+                let speed = SpeedValue::try_new(speed_kph).map_err(E::custom)?;
+                let congestion = match congestion {
+                    Some(value) => CongestionValue::try_new(value)
+                        .map_err(E::custom)?
+                        .into_inner(),
+                    None => UNKNOWN_CONGESTION_VAL,
+                };
+                Ok((speed.into_encoded_value(), breakpoint, congestion))
+            }
+        }
+    }
+
+    impl Serialize for TrafficSpeed {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            TrafficSpeedRepr {
+                has_valid_speed: self.has_valid_speed(),
+                is_completely_closed: self.is_completely_closed(),
+                overall_speed: self.overall_speed(),
+                segment_info_0: self.segment_info(0),
+                segment_info_1: self.segment_info(1),
+                segment_info_2: self.segment_info(2),
+                has_incident_tile: self.has_incidents() != 0,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for TrafficSpeed {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let repr = TrafficSpeedRepr::deserialize(deserializer)?;
+            let (speed1, breakpoint1, congestion1) = encode_segment(repr.segment_info_0)?;
+            let (speed2, breakpoint2, congestion2) = encode_segment(repr.segment_info_1)?;
+            // The last breakpoint is implied by the second one
+            let (speed3, _, congestion3) = encode_segment(repr.segment_info_2)?;
+            let overall_encoded_speed = match repr.overall_speed {
+                Some(speed_kph) if speed_kph > MAX_TRAFFIC_SPEED_KPH => {
+                    return Err(D::Error::custom(format!(
+                        "Overall speed {speed_kph} exceeds the maximum of {MAX_TRAFFIC_SPEED_KPH} kph"
+                    )));
+                }
+                Some(speed_kph) => speed_kph >> 1,
+                None if breakpoint1 == 0 => 0,
+                None => UNKNOWN_TRAFFIC_SPEED_RAW,
+            };
+
+            Ok(Self::new()
+                .with_overall_encoded_speed(overall_encoded_speed)
+                .with_encoded_speed1(speed1)
+                .with_encoded_speed2(speed2)
+                .with_encoded_speed3(speed3)
+                .with_breakpoint1(breakpoint1)
+                .with_breakpoint2(breakpoint2)
+                .with_congestion1(congestion1)
+                .with_congestion2(congestion2)
+                .with_congestion3(congestion3)
+                .with_has_incidents(u8::from(repr.has_incident_tile)))
+        }
     }
 }
 
@@ -438,6 +746,85 @@ impl TrafficSpeedBuilder {
         }
     }
 
+    /// Constructs a builder from a speed profile along an edge of the given length (in meters).
+    ///
+    /// The profile is a list of `(length, speed)` readings in order along the edge
+    /// (e.g. one reading per meter, as is common for probe data),
+    /// and the lengths must add up to the edge length.
+    ///
+    /// Adjacent readings are merged into a section while they are within
+    /// [`SIMILAR_SPEED_TOLERANCE_KPH`] of the section's average speed.
+    /// If more than 3 sections remain, they are grouped into the 3 segments
+    /// which best approximate the profile (minimizing the length-weighted squared speed error),
+    /// and each segment gets the length-weighted average speed of its readings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use valhalla_graphtile::traffic_tile::{SegmentTrafficInfo, SpeedValue, TrafficSpeedBuilder};
+    /// let speed = |kph| SpeedValue::try_new(kph).unwrap();
+    /// // A slowdown in the middle of a 300m edge, with some noise
+    /// let profile = [(100, speed(80)), (50, speed(30)), (50, speed(34)), (100, speed(80))];
+    /// let traffic_speed = TrafficSpeedBuilder::from_profile(300, &profile)
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     traffic_speed.segment_info(1),
+    ///     SegmentTrafficInfo::Speed { speed_kph: 32, congestion: None, breakpoint: 170 }
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if the profile is empty, or the lengths do not add up to the edge length.
+    pub fn from_profile(
+        edge_length: u32,
+        profile: &[(u32, SpeedValue)],
+    ) -> Result<Self, TrafficSpeedBuilderError> {
+        let total_length = profile
+            .iter()
+            .try_fold(0u32, |acc, &(length, _)| acc.checked_add(length))
+            .unwrap_or(u32::MAX);
+        if total_length > edge_length {
+            return Err(SectionLengthExceedsEdge {
+                current_length: 0,
+                new_section_length: total_length,
+                edge_length,
+            });
+        } else if total_length < edge_length {
+            return Err(TrafficSpeedBuilderError::IncompleteCoverage);
+        }
+
+        let mut sections: Vec<SpeedSums> = Vec::new();
+        for &(length, speed) in profile.iter().filter(|(length, _)| *length > 0) {
+            let reading = SpeedSums::new(length, speed);
+            match sections.last_mut() {
+                Some(section)
+                    if (reading.mean() - section.mean()).abs()
+                        <= f64::from(SIMILAR_SPEED_TOLERANCE_KPH) =>
+                {
+                    *section = *section + reading;
+                }
+                _ => sections.push(reading),
+            }
+        }
+        if sections.is_empty() {
+            return Err(TrafficSpeedBuilderError::NoSegments);
+        }
+
+        let segments = if sections.len() <= 3 {
+            sections
+        } else {
+            best_segmentation(&sections, 3)
+        };
+        segments
+            .into_iter()
+            .try_fold(Self::with_edge_length(edge_length), |builder, segment| {
+                builder.with_speed_segment(segment.speed(), None, segment.length)
+            })
+    }
+
     fn test_can_add_segment_of_length(&self, length: u32) -> Result<(), TrafficSpeedBuilderError> {
         if self.speeds.len() == 3 {
             return Err(TooManySegments);
@@ -587,6 +974,119 @@ impl TrafficSpeedBuilder {
     }
 }
 
+/// Length-weighted sums over a run of speed readings.
+///
+/// These add and subtract, so the mean and squared error of any range of readings
+/// can be found in constant time from prefix sums.
+#[derive(Debug, Clone, Copy, Default)]
+struct SpeedSums {
+    length: u32,
+    /// The sum of `length * speed`.
+    weighted: f64,
+    /// The sum of `length * speed^2`.
+    squared: f64,
+}
+
+impl SpeedSums {
+    fn new(length: u32, speed: SpeedValue) -> Self {
+        let (weight, speed) = (f64::from(length), f64::from(speed.into_inner()));
+        Self {
+            length,
+            weighted: weight * speed,
+            squared: weight * speed * speed,
+        }
+    }
+
+    /// The length-weighted mean speed (the sums must cover a non-zero length).
+    fn mean(&self) -> f64 {
+        self.weighted / f64::from(self.length)
+    }
+
+    /// The length-weighted squared error around the mean.
+    fn squared_error(&self) -> f64 {
+        self.squared - self.weighted * self.mean()
+    }
+
+    fn speed(&self) -> SpeedValue {
+        // A weighted mean of valid speeds is always a valid speed
+        #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let mean = self.mean().round() as u8;
+        SpeedValue::try_new(mean).expect("The mean of valid speeds should be valid")
+    }
+}
+
+impl std::ops::Add for SpeedSums {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            length: self.length + other.length,
+            weighted: self.weighted + other.weighted,
+            squared: self.squared + other.squared,
+        }
+    }
+}
+
+impl std::ops::Sub for SpeedSums {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            length: self.length - other.length,
+            weighted: self.weighted - other.weighted,
+            squared: self.squared - other.squared,
+        }
+    }
+}
+
+/// Splits the sections into `segment_count` contiguous segments with the least squared error.
+///
+/// This is the classic dynamic program for optimal segmentation,
+/// taking `O(segment_count * n^2)` time for `n` sections.
+/// Merging similar readings first keeps `n` small,
+/// even for per-meter readings along a long edge.
+fn best_segmentation(sections: &[SpeedSums], segment_count: usize) -> Vec<SpeedSums> {
+    let n = sections.len();
+    assert!(
+        (1..=n).contains(&segment_count),
+        "Can't split {n} sections into {segment_count} segments"
+    );
+
+    let mut prefix_sums = Vec::with_capacity(n + 1);
+    prefix_sums.push(SpeedSums::default());
+    for &section in sections {
+        prefix_sums.push(prefix_sums[prefix_sums.len() - 1] + section);
+    }
+    let range = |start: usize, end: usize| prefix_sums[end] - prefix_sums[start];
+
+    // best[k][end] is the least error of splitting sections[..end] into k + 1 segments,
+    // and where the last of those segments starts
+    let mut best = vec![vec![(f64::INFINITY, 0); n + 1]; segment_count];
+    for (end, best_for_end) in best[0].iter_mut().enumerate().skip(1) {
+        *best_for_end = (range(0, end).squared_error(), 0);
+    }
+    for k in 1..segment_count {
+        for end in k + 1..=n {
+            for start in k..end {
+                let error = best[k - 1][start].0 + range(start, end).squared_error();
+                if error < best[k][end].0 {
+                    best[k][end] = (error, start);
+                }
+            }
+        }
+    }
+
+    let mut segments = Vec::with_capacity(segment_count);
+    let mut end = n;
+    for k in (0..segment_count).rev() {
+        let start = best[k][end].1;
+        segments.push(range(start, end));
+        end = start;
+    }
+    segments.reverse();
+    segments
+}
+
 /// Helper that normalizes progress along the edge into the range (0, 255).
 #[inline]
 fn normalize_progress(pos: u32, len: u32) -> u8 {
@@ -603,7 +1103,7 @@ fn normalize_progress(pos: u32, len: u32) -> u8 {
 #[cfg(test)]
 mod test {
     use super::*;
-    use proptest::proptest;
+    use proptest::{prop_assert, prop_assert_eq, proptest};
     use zerocopy::IntoBytes;
 
     #[test]
@@ -872,6 +1372,246 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_builder_from_profile_merges_similar_readings() {
+        let speed = |kph| SpeedValue::try_new(kph).unwrap();
+        // 40 and 41 kph are the same once encoded
+        let profile = [
+            (100, speed(40)),
+            (0, speed(90)),
+            (100, speed(41)),
+            (300, speed(80)),
+        ];
+        let traffic_speed = TrafficSpeedBuilder::from_profile(500, &profile)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            traffic_speed.segment_info(0),
+            SegmentTrafficInfo::Speed {
+                speed_kph: 40,
+                congestion: None,
+                breakpoint: 102,
+            }
+        );
+        assert_eq!(
+            traffic_speed.segment_info(1),
+            SegmentTrafficInfo::Speed {
+                speed_kph: 80,
+                congestion: None,
+                breakpoint: 255,
+            }
+        );
+        assert_eq!(
+            traffic_speed.segment_info(2),
+            SegmentTrafficInfo::NoData { breakpoint: 0 }
+        );
+    }
+
+    #[test]
+    fn test_builder_from_profile_merges_within_tolerance() {
+        let speed = |kph| SpeedValue::try_new(kph).unwrap();
+        // Within 4 kph of each other (but encoded differently), then a clear change
+        let profile = [
+            (100, speed(40)),
+            (100, speed(44)),
+            (100, speed(42)),
+            (100, speed(70)),
+        ];
+        let traffic_speed = TrafficSpeedBuilder::from_profile(400, &profile)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            traffic_speed.segment_info(0),
+            SegmentTrafficInfo::Speed {
+                speed_kph: 42,
+                congestion: None,
+                breakpoint: 191,
+            }
+        );
+        assert_eq!(
+            traffic_speed.segment_info(1),
+            SegmentTrafficInfo::Speed {
+                speed_kph: 70,
+                congestion: None,
+                breakpoint: 255,
+            }
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn best_segmentation_is_optimal(
+            readings in proptest::collection::vec((1u32..50, 1u8..=MAX_TRAFFIC_SPEED_KPH), 4..40)
+        ) {
+            let sections: Vec<_> = readings
+                .iter()
+                .map(|&(length, speed)| SpeedSums::new(length, SpeedValue::try_new(speed).unwrap()))
+                .collect();
+            let segments = best_segmentation(&sections, 3);
+            let error: f64 = segments.iter().map(SpeedSums::squared_error).sum();
+
+            // Check every pair of split points
+            let range_error = |start: usize, end: usize| {
+                sections[start..end]
+                    .iter()
+                    .fold(SpeedSums::default(), |sum, &section| sum + section)
+                    .squared_error()
+            };
+            let n = sections.len();
+            let best_error = (1..n - 1)
+                .flat_map(|first| (first + 1..n).map(move |second| (first, second)))
+                .map(|(first, second)| {
+                    range_error(0, first) + range_error(first, second) + range_error(second, n)
+                })
+                .fold(f64::INFINITY, f64::min);
+
+            prop_assert_eq!(segments.iter().map(|segment| segment.length).sum::<u32>(),
+                readings.iter().map(|(length, _)| length).sum::<u32>());
+            prop_assert!(error <= best_error + 1e-6 * best_error.max(1.0), "{error} > {best_error}");
+        }
+    }
+
+    #[test]
+    fn test_builder_from_per_meter_profile() {
+        // Noisy per-meter readings with two clear changes in speed
+        let profile: Vec<_> = (0..1000u32)
+            .map(|meter| {
+                let base = match meter {
+                    0..300 => 30,
+                    300..700 => 90,
+                    _ => 50,
+                };
+                let noise = if meter % 2 == 0 { 0 } else { 2 };
+                (1, SpeedValue::try_new(base + noise).unwrap())
+            })
+            .collect();
+        let traffic_speed = TrafficSpeedBuilder::from_profile(1000, &profile)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let expected = [(30, 76), (90, 178), (50, 255)];
+        for (index, (speed_kph, breakpoint)) in (0..3).zip(expected) {
+            assert_eq!(
+                traffic_speed.segment_info(index),
+                SegmentTrafficInfo::Speed {
+                    speed_kph,
+                    congestion: None,
+                    breakpoint,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_builder_from_invalid_profile() {
+        let speed = SpeedValue::try_new(42).unwrap();
+        assert!(matches!(
+            TrafficSpeedBuilder::from_profile(100, &[]),
+            Err(TrafficSpeedBuilderError::IncompleteCoverage)
+        ));
+        assert!(matches!(
+            TrafficSpeedBuilder::from_profile(0, &[]),
+            Err(TrafficSpeedBuilderError::NoSegments)
+        ));
+        assert!(matches!(
+            TrafficSpeedBuilder::from_profile(100, &[(60, speed), (60, speed)]),
+            Err(SectionLengthExceedsEdge {
+                new_section_length: 120,
+                ..
+            })
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_traffic_speed_serde_round_trip() {
+        let speed = |kph| SpeedValue::try_new(kph).unwrap();
+        let speeds = [
+            TrafficSpeed::new(),
+            TrafficSpeed::closed(),
+            TrafficSpeed::single_speed(speed(42), Some(CongestionValue::try_new(12).unwrap()))
+                .with_incident_tile_bit(),
+            TrafficSpeedBuilder::with_edge_length(300)
+                .with_speed_segment(speed(80), None, 100)
+                .unwrap()
+                .with_closed_segment(100)
+                .unwrap()
+                .with_speed_segment(speed(30), Some(CongestionValue::try_new(40).unwrap()), 100)
+                .unwrap()
+                .build()
+                .unwrap(),
+            TrafficSpeedBuilder::with_edge_length(1000)
+                .with_unknown_segment(500)
+                .unwrap()
+                .with_closed_segment(500)
+                .unwrap()
+                .build()
+                .unwrap(),
+        ];
+
+        for speed in speeds {
+            let json = serde_json::to_value(speed).expect("Unable to serialize");
+            let round_tripped: TrafficSpeed =
+                serde_json::from_value(json.clone()).expect("Unable to deserialize");
+            assert_eq!(round_tripped, speed, "Round trip failed for {json}");
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_traffic_speed_serde_representation() {
+        let speed = TrafficSpeedBuilder::with_edge_length(1000)
+            .with_speed_segment(SpeedValue::try_new(42).unwrap(), None, 500)
+            .unwrap()
+            .with_closed_segment(500)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(speed).unwrap(),
+            serde_json::json!({
+                "has_valid_speed": true,
+                "is_completely_closed": false,
+                "overall_speed": speed.overall_speed(),
+                "segment_info_0": {"Speed": {"speed_kph": 42, "congestion": null, "breakpoint": 127.0 / 255.0}},
+                "segment_info_1": {"Closed": {"breakpoint": 1.0}},
+                "segment_info_2": {"NoData": {"breakpoint": 0.0}},
+                "has_incident_tile": false,
+            })
+        );
+
+        // Invalid values are rejected
+        let mut json = serde_json::to_value(speed).unwrap();
+        json["segment_info_0"]["Speed"]["breakpoint"] = serde_json::json!(1.5);
+        assert!(serde_json::from_value::<TrafficSpeed>(json).is_err());
+        let mut json = serde_json::to_value(speed).unwrap();
+        json["segment_info_0"]["Speed"]["speed_kph"] = serde_json::json!(MAX_TRAFFIC_SPEED_KPH + 2);
+        assert!(serde_json::from_value::<TrafficSpeed>(json).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_traffic_tile_header_serde_round_trip() {
+        let json = serde_json::json!({
+            "tile_id": "0/3015/0",
+            "last_update": 1_750_000_000,
+            "directed_edge_count": 42,
+            "traffic_tile_version": TRAFFIC_TILE_VERSION,
+        });
+        let header: TrafficTileHeader =
+            serde_json::from_value(json.clone()).expect("Unable to deserialize");
+        assert_eq!(header.last_update(), 1_750_000_000);
+        assert_eq!(header.directed_edge_count(), 42);
+        assert_eq!(header.traffic_tile_version(), TRAFFIC_TILE_VERSION);
+        assert_eq!(serde_json::to_value(&header).unwrap(), json);
+    }
+
     proptest! {
         #[test]
         fn prop_zero_progress_is_always_zero(len: u32) {
@@ -0,0 +1,395 @@
+//! Socket monitoring and reconnection.
+//!
+//! Valhalla's services are often restarted independently (ex: on a config change),
+//! and a service which is connected to the old endpoint is otherwise stuck.
+//! Each socket is monitored, and reconnected (with exponential backoff)
+//! when the connection is lost.
+//!
+//! The pure Rust ZeroMQ implementation only reports some events on its monitors
+//! (notably, not disconnects for dealer and push sockets),
+//! so lost connections are mostly detected when a send fails.
+
+use futures::StreamExt;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::{info, trace, warn};
+use zeromq::{Socket, SocketEvent, SocketSend, ZmqMessage, ZmqResult};
+
+/// The role of a socket in the service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketRole {
+    /// The socket which the service receives work from.
+    Upstream,
+    /// The socket which the service passes work on to.
+    Downstream,
+    /// The socket which the service sends HTTP responses to.
+    Loopback,
+}
+
+impl Display for SocketRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Upstream => "upstream",
+            Self::Downstream => "downstream",
+            Self::Loopback => "loopback",
+        })
+    }
+}
+
+/// A change in the state of a connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    /// The socket connected to its endpoint.
+    Connected {
+        socket: SocketRole,
+        endpoint: String,
+    },
+    /// The connection was lost.
+    Disconnected {
+        socket: SocketRole,
+        endpoint: String,
+        reason: String,
+    },
+    /// The service is about to try reconnecting, after a delay.
+    Reconnecting {
+        socket: SocketRole,
+        endpoint: String,
+        attempt: u32,
+        delay: Duration,
+    },
+}
+
+/// A function which is told about every connection event (ex: to update a health check).
+pub(crate) type ConnectionCallback = Arc<dyn Fn(&ConnectionEvent) + Send + Sync>;
+
+/// How to reconnect when a connection is lost.
+///
+/// The delay between attempts starts small, and doubles after every failed attempt,
+/// up to a maximum.
+#[derive(Debug, Clone, Copy)]
+pub struct ReconnectPolicy {
+    initial_delay: Duration,
+    max_delay: Duration,
+    max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    /// Retries forever, starting after 100ms, and backing off to a delay of 30 seconds.
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(30),
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Sets the delay before the first attempt.
+    #[must_use]
+    pub fn with_initial_delay(self, initial_delay: Duration) -> Self {
+        Self {
+            initial_delay,
+            ..self
+        }
+    }
+
+    /// Sets the longest delay between attempts.
+    #[must_use]
+    pub fn with_max_delay(self, max_delay: Duration) -> Self {
+        Self { max_delay, ..self }
+    }
+
+    /// Gives up after this many attempts, rather than retrying forever.
+    ///
+    /// The next failure on the socket starts another round of attempts.
+    #[must_use]
+    pub fn with_max_attempts(self, max_attempts: u32) -> Self {
+        Self {
+            max_attempts: Some(max_attempts),
+            ..self
+        }
+    }
+
+    /// The delay before the given attempt (starting from 1).
+    fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+}
+
+/// Where connection events go: the logs, and the callback (if any).
+#[derive(Clone, Default)]
+pub(crate) struct ConnectionEvents {
+    callback: Option<ConnectionCallback>,
+}
+
+impl ConnectionEvents {
+    pub(crate) fn new(callback: Option<ConnectionCallback>) -> Self {
+        Self { callback }
+    }
+
+    fn emit(&self, event: &ConnectionEvent) {
+        match event {
+            ConnectionEvent::Connected { socket, endpoint } => {
+                info!("Connected the {socket} socket to {endpoint}");
+            }
+            ConnectionEvent::Disconnected {
+                socket,
+                endpoint,
+                reason,
+            } => warn!("Lost the {socket} connection to {endpoint}: {reason}"),
+            ConnectionEvent::Reconnecting {
+                socket,
+                endpoint,
+                attempt,
+                delay,
+            } => info!(
+                "Reconnecting the {socket} socket to {endpoint} in {delay:?} (attempt {attempt})"
+            ),
+        }
+        if let Some(callback) = &self.callback {
+            callback(event);
+        }
+    }
+}
+
+/// A monitored socket, which can be reconnected to its endpoint.
+pub(crate) struct Connection<S> {
+    role: SocketRole,
+    endpoint: String,
+    socket: S,
+    /// Notified when the monitor reports that the connection was lost.
+    lost: Arc<Notify>,
+    monitor: JoinHandle<()>,
+    policy: ReconnectPolicy,
+    events: ConnectionEvents,
+}
+
+impl<S: Socket> Connection<S> {
+    /// Connects a new socket to the endpoint.
+    pub(crate) async fn open(
+        role: SocketRole,
+        endpoint: &str,
+        policy: ReconnectPolicy,
+        events: ConnectionEvents,
+    ) -> ZmqResult<Self> {
+        let lost = Arc::new(Notify::new());
+        let (socket, monitor) = Self::connect(role, endpoint, &lost, &events).await?;
+        Ok(Self {
+            role,
+            endpoint: endpoint.to_string(),
+            socket,
+            lost,
+            monitor,
+            policy,
+            events,
+        })
+    }
+
+    async fn connect(
+        role: SocketRole,
+        endpoint: &str,
+        lost: &Arc<Notify>,
+        events: &ConnectionEvents,
+    ) -> ZmqResult<(S, JoinHandle<()>)> {
+        let mut socket = S::new();
+        let monitor = Self::monitor(role, endpoint, &mut socket, lost.clone(), events.clone());
+        if let Err(e) = socket.connect(endpoint).await {
+            monitor.abort();
+            return Err(e);
+        }
+        Ok((socket, monitor))
+    }
+
+    /// Watches the socket's events in the background.
+    fn monitor(
+        role: SocketRole,
+        endpoint: &str,
+        socket: &mut S,
+        lost: Arc<Notify>,
+        events: ConnectionEvents,
+    ) -> JoinHandle<()> {
+        let mut monitor = socket.monitor();
+        let endpoint = endpoint.to_string();
+        tokio::spawn(async move {
+            while let Some(event) = monitor.next().await {
+                match event {
+                    SocketEvent::Connected(..) => events.emit(&ConnectionEvent::Connected {
+                        socket: role,
+                        endpoint: endpoint.clone(),
+                    }),
+                    SocketEvent::Disconnected(_) | SocketEvent::Closed => lost.notify_one(),
+                    event => trace!("{role} socket event: {event:?}"),
+                }
+            }
+        })
+    }
+
+    pub(crate) const fn socket(&mut self) -> &mut S {
+        &mut self.socket
+    }
+
+    /// Resolves once the monitor reports that the connection was lost.
+    ///
+    /// This is a separate handle so that it can be awaited alongside the socket.
+    pub(crate) fn lost(&self) -> Arc<Notify> {
+        self.lost.clone()
+    }
+
+    /// Replaces the socket with a newly connected one,
+    /// retrying with backoff according to the reconnect policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns the last connection error if the policy gives up.
+    pub(crate) async fn reconnect(&mut self, reason: &str) -> ZmqResult<()> {
+        self.events.emit(&ConnectionEvent::Disconnected {
+            socket: self.role,
+            endpoint: self.endpoint.clone(),
+            reason: reason.to_string(),
+        });
+        let mut attempt = 1;
+        loop {
+            let delay = self.policy.delay(attempt);
+            self.events.emit(&ConnectionEvent::Reconnecting {
+                socket: self.role,
+                endpoint: self.endpoint.clone(),
+                attempt,
+                delay,
+            });
+            tokio::time::sleep(delay).await;
+
+            match Self::connect(self.role, &self.endpoint, &self.lost, &self.events).await {
+                Ok((socket, monitor)) => {
+                    // The old socket may still report events; it's not interesting any more
+                    self.monitor.abort();
+                    self.socket = socket;
+                    self.monitor = monitor;
+                    return Ok(());
+                }
+                Err(e) if self.policy.max_attempts.is_some_and(|max| attempt >= max) => {
+                    warn!(
+                        "Giving up on reconnecting the {} socket to {} after {attempt} attempts",
+                        self.role, self.endpoint
+                    );
+                    return Err(e);
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to reconnect the {} socket to {}: {e}",
+                        self.role, self.endpoint
+                    );
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// Closes the socket.
+    pub(crate) async fn close(self) -> Vec<zeromq::ZmqError> {
+        self.monitor.abort();
+        self.socket.close().await
+    }
+}
+
+impl<S: Socket + SocketSend> Connection<S> {
+    /// Sends a message, reconnecting if the connection has been lost.
+    ///
+    /// A message which fails to send is not resent after reconnecting,
+    /// since the peer which was waiting for it is gone.
+    ///
+    /// # Errors
+    ///
+    /// Returns the send error (or the reconnection error, if reconnecting fails).
+    pub(crate) async fn send(&mut self, message: ZmqMessage) -> ZmqResult<()> {
+        let result = self.socket.send(message).await;
+        if let Err(e) = &result {
+            self.reconnect(&e.to_string()).await?;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Connection, ConnectionEvent, ConnectionEvents, ReconnectPolicy, SocketRole};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use zeromq::{PullSocket, PushSocket, Socket, SocketRecv, ZmqMessage};
+
+    #[test]
+    fn test_backoff() {
+        let policy = ReconnectPolicy::default()
+            .with_initial_delay(Duration::from_millis(100))
+            .with_max_delay(Duration::from_secs(1));
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(4), Duration::from_millis(800));
+        assert_eq!(policy.delay(5), Duration::from_secs(1));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_reconnect() {
+        const PATH: &str = "/tmp/valhalla_microservice_test_reconnect";
+        const ENDPOINT: &str = "ipc:///tmp/valhalla_microservice_test_reconnect";
+        let _ = std::fs::remove_file(PATH);
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let callback_events = events.clone();
+        let policy = ReconnectPolicy::default().with_initial_delay(Duration::from_millis(10));
+        let mut pull = PullSocket::new();
+        pull.bind(ENDPOINT).await.unwrap();
+        let mut connection = Connection::<PushSocket>::open(
+            SocketRole::Loopback,
+            ENDPOINT,
+            policy,
+            ConnectionEvents::new(Some(Arc::new(move |event: &ConnectionEvent| {
+                callback_events.lock().unwrap().push(event.clone());
+            }))),
+        )
+        .await
+        .unwrap();
+        connection.send(ZmqMessage::from("before")).await.unwrap();
+        assert_eq!(pull.recv().await.unwrap().get(0).unwrap(), "before");
+
+        // The peer restarts; the message which fails to send is lost, but the next one arrives
+        drop(pull);
+        std::fs::remove_file(PATH).unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let mut pull = PullSocket::new();
+        pull.bind(ENDPOINT).await.unwrap();
+        assert!(connection.send(ZmqMessage::from("lost")).await.is_err());
+        connection.send(ZmqMessage::from("after")).await.unwrap();
+        assert_eq!(pull.recv().await.unwrap().get(0).unwrap(), "after");
+
+        // Connected events come from the socket monitor, so they may arrive in the background
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let events = events.lock().unwrap();
+        let endpoint = ENDPOINT.to_string();
+        assert!(matches!(
+            events.as_slice(),
+            [
+                ConnectionEvent::Connected { .. },
+                ConnectionEvent::Disconnected { .. },
+                ConnectionEvent::Reconnecting { attempt: 1, .. },
+                ConnectionEvent::Connected { .. },
+            ]
+        ));
+        assert_eq!(
+            events[2],
+            ConnectionEvent::Reconnecting {
+                socket: SocketRole::Loopback,
+                endpoint,
+                attempt: 1,
+                delay: Duration::from_millis(10),
+            }
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Cross-Origin Resource Sharing (CORS) configuration.
+//!
+//! Valhalla (via `prime_server`) sets `Access-Control-Allow-Origin: *` on every response,
+//! and answers `OPTIONS` preflight requests with the allowed methods.
+//! The default configuration mirrors this behavior,
+//! but it can be tightened (or disabled entirely) for deployments
+//! where browsers talk to the services directly.
+
+use http::header::{
+    ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
+    ACCESS_CONTROL_MAX_AGE,
+};
+use http::{HeaderMap, HeaderName, HeaderValue, Method};
+use itertools::Itertools;
+use std::time::Duration;
+
+/// CORS settings applied to every HTTP response generated by a service.
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    /// The value of the `Access-Control-Allow-Origin` header.
+    ///
+    /// When this is `None`, no CORS headers are injected at all.
+    allow_origin: Option<HeaderValue>,
+    /// Methods advertised in response to preflight requests.
+    allow_methods: Vec<Method>,
+    /// Request headers advertised in response to preflight requests.
+    allow_headers: Vec<HeaderName>,
+    /// How long browsers may cache the result of a preflight request.
+    max_age: Option<Duration>,
+}
+
+impl Default for CorsConfig {
+    /// The same permissive configuration that Valhalla uses.
+    fn default() -> Self {
+        Self {
+            allow_origin: Some(HeaderValue::from_static("*")),
+            allow_methods: vec![Method::GET, Method::POST, Method::OPTIONS],
+            allow_headers: vec![http::header::CONTENT_TYPE],
+            max_age: None,
+        }
+    }
+}
+
+impl CorsConfig {
+    /// A configuration which never adds any CORS headers.
+    ///
+    /// This is useful when the services sit behind a proxy that handles CORS on its own.
+    pub fn disabled() -> Self {
+        Self {
+            allow_origin: None,
+            allow_methods: Vec::new(),
+            allow_headers: Vec::new(),
+            max_age: None,
+        }
+    }
+
+    /// Sets the allowed origin (e.g. `https://example.com`, or `*` for any origin).
+    #[must_use]
+    pub fn with_allowed_origin(self, origin: HeaderValue) -> Self {
+        Self {
+            allow_origin: Some(origin),
+            ..self
+        }
+    }
+
+    /// Sets the methods advertised in response to preflight requests.
+    #[must_use]
+    pub fn with_allowed_methods(self, methods: impl IntoIterator<Item = Method>) -> Self {
+        Self {
+            allow_methods: methods.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Sets the request headers advertised in response to preflight requests.
+    #[must_use]
+    pub fn with_allowed_headers(self, headers: impl IntoIterator<Item = HeaderName>) -> Self {
+        Self {
+            allow_headers: headers.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Sets how long browsers may cache preflight responses.
+    #[must_use]
+    pub fn with_max_age(self, max_age: Duration) -> Self {
+        Self {
+            max_age: Some(max_age),
+            ..self
+        }
+    }
+
+    /// Injects the headers which belong on every response.
+    ///
+    /// Any existing values for these headers will be clobbered.
+    pub(crate) fn apply(&self, headers: &mut HeaderMap) {
+        if let Some(origin) = &self.allow_origin {
+            headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+        }
+    }
+
+    /// Injects the headers for a response to an `OPTIONS` preflight request.
+    ///
+    /// This includes everything from [`CorsConfig::apply`].
+    pub(crate) fn apply_preflight(&self, headers: &mut HeaderMap) {
+        if self.allow_origin.is_none() {
+            return;
+        }
+
+        self.apply(headers);
+
+        if !self.allow_methods.is_empty() {
+            let methods = self.allow_methods.iter().map(Method::as_str).join(",");
+            // Method tokens are always valid header values.
+            headers.insert(ACCESS_CONTROL_ALLOW_METHODS, methods.parse().unwrap());
+        }
+
+        if !self.allow_headers.is_empty() {
+            let allowed = self.allow_headers.iter().map(HeaderName::as_str).join(",");
+            // Header names are always valid header values.
+            headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, allowed.parse().unwrap());
+        }
+
+        if let Some(max_age) = self.max_age {
+            headers.insert(ACCESS_CONTROL_MAX_AGE, max_age.as_secs().into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_matches_valhalla() {
+        let mut headers = HeaderMap::new();
+        CorsConfig::default().apply(&mut headers);
+
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers.get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(), "*");
+    }
+
+    #[test]
+    fn disabled_adds_nothing() {
+        let mut headers = HeaderMap::new();
+        let cors = CorsConfig::disabled();
+        cors.apply(&mut headers);
+        cors.apply_preflight(&mut headers);
+
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn preflight_headers() {
+        let mut headers = HeaderMap::new();
+        CorsConfig::default()
+            .with_allowed_origin(HeaderValue::from_static("https://example.com"))
+            .with_allowed_methods([Method::GET, Method::OPTIONS])
+            .with_max_age(Duration::from_secs(90))
+            .apply_preflight(&mut headers);
+
+        assert_eq!(
+            headers.get(ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://example.com"
+        );
+        assert_eq!(
+            headers.get(ACCESS_CONTROL_ALLOW_METHODS).unwrap(),
+            "GET,OPTIONS"
+        );
+        assert_eq!(
+            headers.get(ACCESS_CONTROL_ALLOW_HEADERS).unwrap(),
+            "content-type"
+        );
+        assert_eq!(headers.get(ACCESS_CONTROL_MAX_AGE).unwrap(), "90");
+    }
+}
@@ -10,3 +10,22 @@ pub enum Error {
     #[error("Invalid message: {0}")]
     InvalidMessage(String),
 }
+
+impl Error {
+    /// Could the service recover from this error and keep processing messages?
+    ///
+    /// ZeroMQ errors are typically transient (e.g. a socket hiccup),
+    /// so it's worth carrying on.
+    /// Once the upstream is shutting down, there is nothing left to retry.
+    pub const fn is_retryable(&self) -> bool {
+        matches!(self, Self::ZeroMq(_))
+    }
+
+    /// Does this error indicate corrupt data?
+    ///
+    /// This is the case for messages which we cannot decode,
+    /// usually because of a protocol mismatch with the upstream.
+    pub const fn is_data_corruption(&self) -> bool {
+        matches!(self, Self::InvalidMessage(_))
+    }
+}
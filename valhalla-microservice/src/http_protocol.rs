@@ -24,10 +24,81 @@ pub struct HttpRequestInfo {
     /// or hit some bad behavior!
     timestamp: U32<LE>,
     inner_bitfield: HttpRequestInfoInnerBitfield,
-    /// Required padding to make the struct 16-byte aligned.
+    /// The content encodings accepted by the client (see [`ContentEncoding`]).
     ///
-    /// NB: This is implicit in the C++ struct definition.
-    _spare: U16<LE>,
+    /// NB: This is padding in the C++ struct definition (to make it 16-byte aligned),
+    /// which `prime_server` leaves zeroed,
+    /// so responses on the loopback path are only compressed when a front end sets this
+    /// (see [`HttpRequestInfo::set_accept_encoding`]).
+    accept_encoding: U16<LE>,
+}
+
+/// A content encoding (compression) which can be applied to response bodies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+    Gzip,
+    Brotli,
+}
+
+impl ContentEncoding {
+    /// The encodings, in order of preference.
+    const PREFERRED: [ContentEncoding; 2] = [ContentEncoding::Brotli, ContentEncoding::Gzip];
+
+    /// The name of the encoding, as used in the `Accept-Encoding` and `Content-Encoding` headers.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Brotli => "br",
+        }
+    }
+
+    /// The encoding to compress a response with, given the request's `Accept-Encoding` header
+    /// (ex: `gzip, deflate, br;q=0.8`), if the client accepts any.
+    pub fn negotiate(accept_encoding: &str) -> Option<ContentEncoding> {
+        Self::preferred(accepted_flags(accept_encoding))
+    }
+
+    /// The most preferred encoding whose flag is set.
+    fn preferred(flags: u16) -> Option<ContentEncoding> {
+        Self::PREFERRED
+            .into_iter()
+            .find(|encoding| flags & encoding.flag() != 0)
+    }
+
+    const fn flag(self) -> u16 {
+        match self {
+            Self::Gzip => 1,
+            Self::Brotli => 1 << 1,
+        }
+    }
+}
+
+/// Parses an `Accept-Encoding` header into [`ContentEncoding`] flags.
+///
+/// Encodings which the client explicitly refuses (with `q=0`) aren't accepted.
+fn accepted_flags(header: &str) -> u16 {
+    let (mut accepted, mut refused) = (0, 0);
+    for item in header.split(',') {
+        let mut params = item.split(';').map(str::trim);
+        let name = params.next().unwrap_or_default();
+        let is_refused = params.any(|param| {
+            param
+                .strip_prefix("q=")
+                .and_then(|q| q.parse::<f32>().ok())
+                .is_some_and(|q| q <= 0.0)
+        });
+        let flags = ContentEncoding::PREFERRED
+            .into_iter()
+            .filter(|encoding| name == "*" || name.eq_ignore_ascii_case(encoding.as_str()))
+            .fold(0, |flags, encoding| flags | encoding.flag());
+        if !is_refused {
+            accepted |= flags;
+        } else if name != "*" {
+            // A refused wildcard only refuses encodings which aren't listed
+            refused |= flags;
+        }
+    }
+    accepted & !refused
 }
 
 #[bitfield(u16,
@@ -64,6 +135,11 @@ impl HttpRequestInfo {
         self.id.into()
     }
 
+    /// When the server received the request, in seconds since the Unix epoch.
+    pub fn timestamp(&self) -> u32 {
+        self.timestamp.into()
+    }
+
     /// The HTTP version string for this request (e.g. "HTTP/1.1").
     pub fn http_version_string(&self) -> &'static str {
         let version = self.inner_bitfield.version();
@@ -92,6 +168,26 @@ impl HttpRequestInfo {
     pub fn set_response_code(&mut self, code: u16) {
         self.inner_bitfield.set_response_code(code.into());
     }
+
+    /// Does the client accept responses with the given encoding?
+    pub fn accepts_encoding(&self, encoding: ContentEncoding) -> bool {
+        u16::from(self.accept_encoding) & encoding.flag() != 0
+    }
+
+    /// The encoding to compress the response with, if the client accepts any.
+    pub fn preferred_encoding(&self) -> Option<ContentEncoding> {
+        ContentEncoding::preferred(self.accept_encoding.into())
+    }
+
+    /// Records the encodings accepted by the client, from its `Accept-Encoding` header
+    /// (ex: `gzip, deflate, br;q=0.8`).
+    ///
+    /// `prime_server` doesn't pass the header along,
+    /// so this is for front ends which build the request info themselves.
+    /// Encodings which the client explicitly refuses (with `q=0`) aren't accepted.
+    pub fn set_accept_encoding(&mut self, header: &str) {
+        self.accept_encoding = accepted_flags(header).into();
+    }
 }
 
 #[cfg(test)]
@@ -110,4 +206,32 @@ mod tests {
             insta::assert_debug_snapshot!(parsed);
         }
     }
+
+    #[test]
+    fn accept_encoding() {
+        const MESSAGE: [u8; 12] = [
+            0x00, 0x00, 0x00, 0x00, 0xf5, 0x76, 0xb1, 0x68, 0x01, 0x00, 0x00, 0x00,
+        ];
+        let mut info: HttpRequestInfo = transmute!(MESSAGE);
+        assert_eq!(info.preferred_encoding(), None);
+
+        info.set_accept_encoding("gzip, deflate");
+        assert!(info.accepts_encoding(ContentEncoding::Gzip));
+        assert_eq!(info.preferred_encoding(), Some(ContentEncoding::Gzip));
+
+        info.set_accept_encoding("GZIP;q=0.5, br");
+        assert_eq!(info.preferred_encoding(), Some(ContentEncoding::Brotli));
+
+        info.set_accept_encoding("*, br;q=0");
+        assert_eq!(info.preferred_encoding(), Some(ContentEncoding::Gzip));
+
+        info.set_accept_encoding("identity");
+        assert_eq!(info.preferred_encoding(), None);
+
+        assert_eq!(
+            ContentEncoding::negotiate("br;q=0.5, gzip"),
+            Some(ContentEncoding::Brotli)
+        );
+        assert_eq!(ContentEncoding::negotiate("deflate"), None);
+    }
 }
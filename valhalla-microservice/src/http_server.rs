@@ -0,0 +1,521 @@
+//! A standalone HTTP front end, which runs a worker function without ZeroMQ.
+//!
+//! A full Valhalla deployment needs an HTTP server, proxies, and a loopback,
+//! which is a lot of moving parts for a service that can answer requests on its own.
+//! This front end accepts Valhalla HTTP requests (ex: `POST /route`, or `GET /route?json=...`),
+//! converts them to the `Api` protobuf, and calls the worker function directly.
+//!
+//! Valhalla's request parsing is extensive; only the common options are converted here:
+//! the action (from the path), `format`, `id`, `jsonp`, `language`, `units`, `costing`,
+//! `locations`, `sources`, `targets`, `exclude_locations`, `shape`, and `contours`.
+//!
+//! As with the ZeroMQ service, requests are worked on one at a time.
+//! Large responses are compressed with gzip or brotli, if the client's `Accept-Encoding` allows.
+
+use crate::cors::CorsConfig;
+use crate::http_protocol::ContentEncoding;
+use crate::result::compress;
+use crate::valhalla_error;
+use crate::worker::{AsyncWorkerFn, SyncWorkerFn, Worker};
+use crate::{CancellationToken, WorkerResult};
+use axum::Router;
+use axum::body::{Body, to_bytes};
+use axum::extract::{Query, Request};
+use axum::response::{IntoResponse, Response};
+use http::header::ACCEPT_ENCODING;
+use http::{HeaderMap, Method, StatusCode};
+use itertools::Itertools;
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio::sync::{mpsc, oneshot};
+use tracing::trace;
+use valhalla_proto::options::{Action, Format, HasId, HasJsonp, HasLanguage, Units};
+use valhalla_proto::{Api, Contour, LatLng, Location, Options, contour, costing, lat_lng};
+use valhalla_response::error::codes;
+
+/// The largest request body which is accepted.
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// How many requests may wait for the worker before new ones are turned away.
+const MAX_QUEUED_REQUESTS: usize = 64;
+
+/// A request, and where to send its result.
+struct Job {
+    request: Api,
+    result: oneshot::Sender<WorkerResult>,
+}
+
+/// An HTTP server which runs a worker function (see the [module docs](self)).
+pub struct HttpFrontend<W: Worker> {
+    worker: W,
+    cors: CorsConfig,
+}
+
+impl<F: Fn(Api, CancellationToken) -> WorkerResult> HttpFrontend<SyncWorkerFn<F>> {
+    /// Creates a front end for a worker function.
+    ///
+    /// The same rules apply as for [`ValhallaMicroserviceBuilder::build`](crate::ValhallaMicroserviceBuilder::build).
+    pub fn new(worker_fn: F) -> Self {
+        Self {
+            worker: SyncWorkerFn(worker_fn),
+            cors: CorsConfig::default(),
+        }
+    }
+}
+
+impl<F, Fut> HttpFrontend<AsyncWorkerFn<F>>
+where
+    F: Fn(Api, CancellationToken) -> Fut,
+    Fut: Future<Output = WorkerResult>,
+{
+    /// Creates a front end for an async worker function
+    /// (see [`ValhallaMicroserviceBuilder::build_async`](crate::ValhallaMicroserviceBuilder::build_async)).
+    pub fn new_async(worker_fn: F) -> Self {
+        Self {
+            worker: AsyncWorkerFn(worker_fn),
+            cors: CorsConfig::default(),
+        }
+    }
+}
+
+impl<W: Worker> HttpFrontend<W> {
+    /// Overrides the CORS configuration.
+    ///
+    /// By default, the front end behaves like Valhalla and allows requests from any origin.
+    #[must_use]
+    pub fn with_cors_config(self, cors: CorsConfig) -> Self {
+        Self { cors, ..self }
+    }
+
+    /// Serves requests on the given address until the server fails.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the address can't be bound, or if accepting connections fails.
+    pub async fn serve(self, addr: impl ToSocketAddrs) -> std::io::Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        let (jobs, mut queue) = mpsc::channel(MAX_QUEUED_REQUESTS);
+        let cors = self.cors.clone();
+        let router = Router::new()
+            .fallback(move |request: Request| handle(request, jobs.clone(), cors.clone()));
+
+        // The worker runs alongside the server (rather than in a task of its own),
+        // so worker functions don't need to be `Send`.
+        let work = async {
+            while let Some(job) = queue.recv().await {
+                self.work(job).await;
+            }
+        };
+        tokio::select! {
+            result = axum::serve(listener, router) => result,
+            () = work => Ok(()),
+        }
+    }
+
+    /// Runs the worker function, unless the client has gone away.
+    async fn work(&self, job: Job) {
+        let Job {
+            request,
+            mut result,
+        } = job;
+        if result.is_closed() {
+            trace!("Dropping a request whose client has gone away");
+            return;
+        }
+        // Async workers are stopped if the client goes away;
+        // sync workers can't be, but their result is dropped all the same.
+        tokio::select! {
+            response = self.worker.work(request, CancellationToken::default()) => {
+                let _ = result.send(response);
+            }
+            () = result.closed() => trace!("Dropping a request whose client went away"),
+        }
+    }
+}
+
+/// Converts an HTTP request to a job, and waits for its result.
+async fn handle(request: Request, jobs: mpsc::Sender<Job>, cors: CorsConfig) -> Response {
+    if request.method() == Method::OPTIONS {
+        return response(WorkerResult::CorsPreflight, &cors, None);
+    }
+    let encoding = request
+        .headers()
+        .get(ACCEPT_ENCODING)
+        .and_then(|header| header.to_str().ok())
+        .and_then(ContentEncoding::negotiate);
+    let request = match parse_request(request).await {
+        Ok(request) => request,
+        Err(error) => return response(error, &cors, encoding),
+    };
+
+    let (result, receiver) = oneshot::channel();
+    if jobs.try_send(Job { request, result }).is_err() {
+        return response(valhalla_error::overloaded_response(), &cors, encoding);
+    }
+    match receiver.await {
+        Ok(result) => response(result, &cors, encoding),
+        // The worker has stopped
+        Err(_) => StatusCode::SERVICE_UNAVAILABLE.into_response(),
+    }
+}
+
+/// Converts a worker result to an HTTP response,
+/// compressing the body with the negotiated encoding (if any).
+fn response(
+    result: WorkerResult,
+    cors: &CorsConfig,
+    encoding: Option<ContentEncoding>,
+) -> Response {
+    match result {
+        WorkerResult::HttpResponse {
+            status_code,
+            mut headers,
+            body,
+        } => {
+            let body = compress(encoding, &mut headers, body);
+            cors.apply(&mut headers);
+            (status_code, headers, body).into_response()
+        }
+        WorkerResult::CorsPreflight => {
+            let mut headers = HeaderMap::new();
+            cors.apply_preflight(&mut headers);
+            (StatusCode::OK, headers, Body::empty()).into_response()
+        }
+        // There's nowhere downstream to send the request to
+        WorkerResult::PlaceholderDownstreamTBD => response(
+            WorkerResult::valhalla_error(codes::NOT_IMPLEMENTED),
+            cors,
+            encoding,
+        ),
+    }
+}
+
+/// Converts an HTTP request to the `Api` protobuf, as Valhalla does.
+async fn parse_request(request: Request) -> Result<Api, WorkerResult> {
+    let (parts, body) = request.into_parts();
+    if parts.method != Method::GET && parts.method != Method::POST {
+        return Err(WorkerResult::valhalla_error(codes::UNSUPPORTED_METHOD));
+    }
+
+    let action = parts
+        .uri
+        .path()
+        .strip_prefix('/')
+        .and_then(Action::from_str_name)
+        .filter(|action| *action != Action::NoAction)
+        .ok_or_else(|| {
+            let actions = (1..)
+                .map_while(|action| Action::try_from(action).ok())
+                .map(|action| format!("'/{}'", action.as_str_name()))
+                .join(" ");
+            WorkerResult::valhalla_error_with_detail(codes::UNKNOWN_ACTION, actions)
+        })?;
+
+    // The JSON is the body of a POST request, or the `json` query parameter of a GET request
+    let json = if parts.method == Method::POST {
+        let body = to_bytes(body, MAX_BODY_SIZE)
+            .await
+            .map_err(|_| WorkerResult::valhalla_error(codes::FAILED_TO_PARSE_JSON))?;
+        serde_json::from_slice(&body)
+    } else {
+        let Query(query) = Query::<HashMap<String, String>>::try_from_uri(&parts.uri)
+            .map_err(|_| WorkerResult::valhalla_error(codes::FAILED_TO_PARSE_JSON))?;
+        query
+            .get("json")
+            .map_or(Ok(Value::Object(serde_json::Map::new())), |json| {
+                serde_json::from_str(json)
+            })
+    }
+    .map_err(|_| WorkerResult::valhalla_error(codes::FAILED_TO_PARSE_JSON))?;
+
+    Ok(Api {
+        options: Some(parse_options(action, &json)?),
+        ..Api::default()
+    })
+}
+
+/// Converts Valhalla's JSON request options to the protobuf equivalent.
+fn parse_options(action: Action, json: &Value) -> Result<Options, WorkerResult> {
+    let mut options = Options {
+        action: action.into(),
+        ..Options::default()
+    };
+
+    if let Some(format) = json
+        .get("format")
+        .and_then(Value::as_str)
+        .and_then(Format::from_str_name)
+    {
+        options.format = format.into();
+    }
+    if let Some(units) = json.get("units").and_then(Value::as_str) {
+        // Valhalla accepts abbreviations too
+        let units = match units {
+            "mi" => Some(Units::Miles),
+            "km" => Some(Units::Kilometers),
+            units => Units::from_str_name(units),
+        };
+        if let Some(units) = units {
+            options.units = units.into();
+        }
+    }
+    match json.get("id") {
+        Some(Value::String(id)) => options.has_id = Some(HasId::Id(id.clone())),
+        Some(Value::Number(id)) => options.has_id = Some(HasId::Id(id.to_string())),
+        _ => {}
+    }
+    if let Some(jsonp) = json.get("jsonp").and_then(Value::as_str) {
+        options.has_jsonp = Some(HasJsonp::Jsonp(jsonp.to_string()));
+    }
+    if let Some(language) = json.get("language").and_then(Value::as_str) {
+        options.has_language = Some(HasLanguage::Language(language.to_string()));
+    }
+    if let Some(costing) = json.get("costing") {
+        options.costing_type = costing
+            .as_str()
+            // The protobuf name is `auto_`, since `auto` is reserved in C++
+            .map(|costing| if costing == "auto" { "auto_" } else { costing })
+            .and_then(costing::Type::from_str_name)
+            .ok_or_else(|| WorkerResult::valhalla_error(codes::NO_COSTING))?
+            .into();
+    }
+
+    options.locations = parse_locations(json, "locations", codes::INVALID_LOCATION)?;
+    options.sources = parse_locations(json, "sources", codes::INVALID_SOURCE)?;
+    options.targets = parse_locations(json, "targets", codes::INVALID_TARGET)?;
+    options.exclude_locations = parse_locations(json, "exclude_locations", codes::INVALID_AVOID)?;
+    options.shape = parse_locations(json, "shape", codes::INVALID_SHAPE)?;
+    options.contours = json
+        .get("contours")
+        .and_then(Value::as_array)
+        .map(|contours| contours.iter().map(parse_contour).collect())
+        .unwrap_or_default();
+
+    Ok(options)
+}
+
+/// Parses a list of locations (objects with `lat` and `lon`),
+/// failing with the given error code if any are invalid.
+fn parse_locations(
+    json: &Value,
+    key: &str,
+    error_code: u16,
+) -> Result<Vec<Location>, WorkerResult> {
+    let Some(locations) = json.get(key) else {
+        return Ok(Vec::new());
+    };
+    let locations = locations
+        .as_array()
+        .ok_or_else(|| WorkerResult::valhalla_error(error_code))?;
+    locations
+        .iter()
+        .map(|location| {
+            let lat = location.get("lat").and_then(Value::as_f64);
+            let lon = location.get("lon").and_then(Value::as_f64);
+            let (Some(lat), Some(lon)) = (lat, lon) else {
+                return Err(WorkerResult::valhalla_error(error_code));
+            };
+            Ok(Location {
+                ll: Some(LatLng {
+                    has_lat: Some(lat_lng::HasLat::Lat(lat)),
+                    has_lng: Some(lat_lng::HasLng::Lng(lon)),
+                }),
+                ..Location::default()
+            })
+        })
+        .collect()
+}
+
+/// Parses an isochrone contour (an object with `time` or `distance`, and optionally `color`).
+fn parse_contour(json: &Value) -> Contour {
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "The protobuf stores contours as single precision floats"
+    )]
+    let float = |key| {
+        json.get(key)
+            .and_then(Value::as_f64)
+            .map(|value| value as f32)
+    };
+    Contour {
+        has_time: float("time").map(contour::HasTime::Time),
+        has_distance: float("distance").map(contour::HasDistance::Distance),
+        has_color: json
+            .get("color")
+            .and_then(Value::as_str)
+            .map(|color| contour::HasColor::Color(color.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HttpFrontend, parse_options};
+    use crate::WorkerResult;
+    use http::StatusCode;
+    use serde_json::json;
+    use std::io::Read;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use valhalla_proto::options::{Action, HasId, Units};
+    use valhalla_proto::{LatLng, costing, lat_lng};
+
+    fn status_code(result: &WorkerResult) -> StatusCode {
+        let WorkerResult::HttpResponse { status_code, .. } = result else {
+            panic!("Expected an HTTP response");
+        };
+        *status_code
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = parse_options(
+            Action::Route,
+            &json!({
+                "locations": [{"lat": 59.43, "lon": 24.75}, {"lat": 59.44, "lon": 24.76}],
+                "costing": "auto",
+                "units": "mi",
+                "id": 42
+            }),
+        )
+        .unwrap_or_else(|_| panic!("Expected valid options"));
+        assert_eq!(options.action(), Action::Route);
+        assert_eq!(options.costing_type(), costing::Type::Auto);
+        assert_eq!(options.units(), Units::Miles);
+        assert_eq!(options.has_id, Some(HasId::Id("42".to_string())));
+        assert_eq!(options.locations.len(), 2);
+        assert_eq!(
+            options.locations[0].ll,
+            Some(LatLng {
+                has_lat: Some(lat_lng::HasLat::Lat(59.43)),
+                has_lng: Some(lat_lng::HasLng::Lng(24.75)),
+            })
+        );
+
+        // Invalid input gets the same errors as from Valhalla
+        let error = parse_options(Action::Route, &json!({"locations": [{"lat": 59.43}]}));
+        assert_eq!(status_code(&error.unwrap_err()), StatusCode::BAD_REQUEST);
+        let error = parse_options(Action::Route, &json!({"costing": "hovercraft"}));
+        assert_eq!(status_code(&error.unwrap_err()), StatusCode::BAD_REQUEST);
+    }
+
+    async fn request_bytes(address: std::net::SocketAddr, request: &str) -> Vec<u8> {
+        let mut stream = loop {
+            if let Ok(stream) = tokio::net::TcpStream::connect(address).await {
+                break stream;
+            }
+            tokio::task::yield_now().await;
+        };
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        response
+    }
+
+    async fn request(address: std::net::SocketAddr, request: &str) -> String {
+        String::from_utf8(request_bytes(address, request).await).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_serve() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+        let frontend = HttpFrontend::new(|request, _| {
+            let options = request.options.unwrap_or_default();
+            WorkerResult::json(
+                StatusCode::OK,
+                json!({"action": options.action().as_str_name(), "locations": options.locations.len()}),
+            )
+        });
+        tokio::spawn(async move { frontend.serve(address).await });
+
+        let body = r#"{"locations":[{"lat":1,"lon":2}]}"#;
+        let response = request(
+            address,
+            &format!(
+                "POST /route HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            ),
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("access-control-allow-origin: *\r\n"));
+        assert!(response.ends_with(r#"{"action":"route","locations":1}"#));
+
+        let response = request(
+            address,
+            "GET /status?json=%7B%7D HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+        assert!(response.ends_with(r#"{"action":"status","locations":0}"#));
+
+        let response = request(
+            address,
+            "GET /teleport HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(response.contains(r#""error_code":106"#));
+    }
+
+    #[tokio::test]
+    async fn test_serve_compressed() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+        let frontend = HttpFrontend::new(|_, _| {
+            WorkerResult::json(
+                StatusCode::OK,
+                vec![json!({"lat": 59.437, "lon": 24.7536}); 100],
+            )
+        });
+        tokio::spawn(async move { frontend.serve(address).await });
+        let expected =
+            serde_json::to_vec(&vec![json!({"lat": 59.437, "lon": 24.7536}); 100]).unwrap();
+        let split = |response: Vec<u8>| {
+            let separator = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+            let head = String::from_utf8(response[..separator].to_vec()).unwrap();
+            (head, response[separator + 4..].to_vec())
+        };
+
+        let (head, body) = split(
+            request_bytes(
+                address,
+                "GET /route HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nAccept-Encoding: gzip, deflate\r\n\r\n",
+            )
+            .await,
+        );
+        assert!(head.contains("content-encoding: gzip\r\n"));
+        assert!(head.contains("vary: Accept-Encoding\r\n"));
+        assert!(head.contains(&format!("content-length: {}\r\n", body.len())));
+        let mut decompressed = Vec::new();
+        flate2::read::GzDecoder::new(body.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, expected);
+
+        let (head, body) = split(
+            request_bytes(
+                address,
+                "GET /route HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nAccept-Encoding: gzip, br\r\n\r\n",
+            )
+            .await,
+        );
+        assert!(head.contains("content-encoding: br\r\n"));
+        let mut decompressed = Vec::new();
+        brotli::BrotliDecompress(&mut body.as_slice(), &mut decompressed).unwrap();
+        assert_eq!(decompressed, expected);
+
+        // Clients which don't ask for compression don't get it
+        let (head, body) = split(
+            request_bytes(
+                address,
+                "GET /route HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            )
+            .await,
+        );
+        assert!(!head.contains("content-encoding"));
+        assert!(head.contains("vary: Accept-Encoding\r\n"));
+        assert_eq!(body, expected);
+    }
+}
@@ -0,0 +1,194 @@
+//! Request interrupts.
+//!
+//! When a client disconnects (or times out), `prime_server` publishes the request info
+//! on the interrupt socket, so that workers can stop burning CPU on a response nobody will read.
+//! Workers subscribe to every interrupt, and check whether it applies to their current request.
+
+use crate::http_protocol::HttpRequestInfo;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+use tokio::task::JoinHandle;
+use tracing::{trace, warn};
+use zerocopy::FromBytes;
+use zeromq::{SocketRecv, SubSocket};
+
+/// How many interrupted request IDs to remember.
+///
+/// Interrupts usually arrive while the request is in progress,
+/// but may also come before it is handed to this service (ex: while queued upstream).
+/// Request IDs are generated serially, so only recent ones are worth remembering.
+const MAX_REMEMBERED_INTERRUPTS: usize = 1024;
+
+/// Signals that the response to a request is no longer wanted,
+/// because the client has gone away, or the request has run out of time.
+///
+/// Worker functions which take a while should check [`CancellationToken::is_cancelled`]
+/// every so often, and return early (with any result) if it is set.
+/// The result of an interrupted request is discarded,
+/// and a request which runs out of time gets a timeout error instead.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    interrupted: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    /// Has the request been interrupted, or run out of time?
+    pub fn is_cancelled(&self) -> bool {
+        self.is_interrupted() || self.is_past_deadline()
+    }
+
+    /// When the request runs out of time, if there is a limit
+    /// (see [`ValhallaMicroserviceBuilder::with_max_processing_time`](crate::ValhallaMicroserviceBuilder::with_max_processing_time)).
+    pub const fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    pub(crate) fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn is_past_deadline(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    #[must_use]
+    pub(crate) const fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::Relaxed);
+    }
+}
+
+#[derive(Default)]
+struct InterruptState {
+    /// Recently interrupted request IDs, oldest first.
+    interrupted: VecDeque<u32>,
+    /// The request in progress.
+    current: Option<(u32, CancellationToken)>,
+}
+
+/// Tracks the interrupted requests, as they arrive on the interrupt socket.
+#[derive(Clone, Default)]
+pub(crate) struct Interrupts {
+    state: Arc<Mutex<InterruptState>>,
+}
+
+impl Interrupts {
+    /// Listens for interrupts in the background, until the returned listener is dropped.
+    pub(crate) fn listen(&self, mut socket: SubSocket) -> InterruptListener {
+        let interrupts = self.clone();
+        InterruptListener(tokio::spawn(async move {
+            loop {
+                let message = match socket.recv().await {
+                    Ok(message) => message,
+                    Err(e) => {
+                        warn!("Interrupt socket failed; no longer listening for interrupts: {e}");
+                        return;
+                    }
+                };
+                // The first frame is the request info of the interrupted request
+                if let Some(req_info) = message
+                    .get(0)
+                    .and_then(|frame| HttpRequestInfo::read_from_bytes(frame).ok())
+                {
+                    interrupts.interrupt(req_info.id());
+                } else {
+                    warn!("Ignoring an interrupt without valid request info");
+                }
+            }
+        }))
+    }
+
+    /// Records that a request has been interrupted, cancelling it if it's in progress.
+    pub(crate) fn interrupt(&self, request_id: u32) {
+        trace!("Request ID {request_id} interrupted");
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some((current_id, token)) = &state.current
+            && *current_id == request_id
+        {
+            token.interrupt();
+        }
+        if state.interrupted.len() == MAX_REMEMBERED_INTERRUPTS {
+            state.interrupted.pop_front();
+        }
+        state.interrupted.push_back(request_id);
+    }
+
+    /// Starts work on a request, returning its cancellation token.
+    ///
+    /// Returns `None` if the request has already been interrupted.
+    pub(crate) fn start(&self, request_id: u32) -> Option<CancellationToken> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.interrupted.contains(&request_id) {
+            return None;
+        }
+        let token = CancellationToken::default();
+        state.current = Some((request_id, token.clone()));
+        Some(token)
+    }
+
+    /// Marks the request in progress as done.
+    pub(crate) fn finish(&self) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.current = None;
+    }
+}
+
+/// The background task which listens for interrupts, which stops when dropped.
+pub(crate) struct InterruptListener(JoinHandle<()>);
+
+impl Drop for InterruptListener {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CancellationToken, Interrupts};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_interrupts() {
+        let interrupts = Interrupts::default();
+
+        // Interrupting the request in progress cancels it
+        let token = interrupts.start(1).unwrap();
+        assert!(!token.is_cancelled());
+        interrupts.interrupt(2);
+        assert!(!token.is_cancelled());
+        interrupts.interrupt(1);
+        assert!(token.is_cancelled());
+        interrupts.finish();
+
+        // Requests which were interrupted before they started are dropped
+        assert!(interrupts.start(2).is_none());
+        assert!(
+            interrupts
+                .start(3)
+                .is_some_and(|token| !token.is_cancelled())
+        );
+        interrupts.finish();
+    }
+
+    #[test]
+    fn test_deadline() {
+        let token = CancellationToken::default();
+        assert!(
+            !token
+                .clone()
+                .with_deadline(Instant::now() + Duration::from_mins(1))
+                .is_cancelled()
+        );
+        let token = token.with_deadline(Instant::now());
+        assert!(token.is_cancelled());
+        assert!(!token.is_interrupted());
+    }
+}
@@ -1,21 +1,45 @@
 #![doc = include_str!("../README.md")]
 
+use crate::connection::{Connection, ConnectionEvents};
 use crate::http_protocol::HttpRequestInfo;
-use tracing::trace;
+use crate::interrupt::{InterruptListener, Interrupts};
+use std::time::{Duration, Instant};
+use tracing::{trace, warn};
 use valhalla_proto::Api;
+use valhalla_proto::options::Action;
 use zerocopy::{IntoBytes, transmute};
-use zeromq::{DealerSocket, PushSocket, ZmqMessage, ZmqResult, prelude::*};
+use zeromq::{DealerSocket, PushSocket, SubSocket, ZmqMessage, ZmqResult, prelude::*};
 
+mod connection;
+mod cors;
 mod error;
 pub mod http_protocol;
+#[cfg(feature = "http-server")]
+pub mod http_server;
+mod interrupt;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod proxy;
 mod result;
+pub mod service_limits;
+mod valhalla_error;
+mod worker;
 
+pub use connection::{ConnectionEvent, ReconnectPolicy, SocketRole};
+pub use cors::CorsConfig;
 pub use error::Error;
+#[cfg(feature = "http-server")]
+pub use http_server::HttpFrontend;
+pub use interrupt::CancellationToken;
+#[cfg(feature = "metrics")]
+pub use metrics::ServiceMetrics;
+pub use proxy::ValhallaProxy;
 pub use result::WorkerResult;
 use valhalla_proto::prost::Message;
+pub use worker::{AsyncWorkerFn, SyncWorkerFn, Worker};
 
 /// A Valhalla-compatible microservice.
-pub struct ValhallaMicroservice<F: Fn(Api) -> WorkerResult> {
+pub struct ValhallaMicroservice<W: Worker> {
     /// The ZMQ socket upstream from this service.
     ///
     /// The service connects to this to listen for messages from upstream services.
@@ -25,7 +49,7 @@ pub struct ValhallaMicroservice<F: Fn(Api) -> WorkerResult> {
     /// In the standard Valhalla configuration,
     /// this is typically named `<this service>_out` (e.g., `thor_out`),
     /// which can be a bit confusing.
-    upstream: DealerSocket,
+    upstream: Connection<DealerSocket>,
     /// The ZMQ socket downstream from this service.
     ///
     /// The service uses this to push messages to downstream services.
@@ -35,29 +59,95 @@ pub struct ValhallaMicroservice<F: Fn(Api) -> WorkerResult> {
     /// In the standard Valhalla configuration,
     /// this is typically named `<downstream service>_in` (e.g., `odin_in`),
     /// which can be a bit confusing.
-    downstream: Option<DealerSocket>,
-    // TODO: Interrupt socket
+    downstream: Option<Connection<DealerSocket>>,
+    /// Requests which have been interrupted (ex: because the client disconnected).
+    ///
+    /// These are published on the interrupt socket (typically `ipc:///tmp/interrupt`),
+    /// which a background task listens to, if configured.
+    interrupts: Interrupts,
+    interrupt_listener: Option<InterruptListener>,
     /// The loopback zmq socket.
     ///
     /// This is used to deliver the final HTTP response,
     /// if this service is capable of generating it (rather than passing it downstream).
-    loopback: PushSocket,
+    loopback: Connection<PushSocket>,
+    /// CORS settings applied to every HTTP response.
+    cors: CorsConfig,
+    /// The longest a request may be worked on before it gets a timeout error.
+    max_processing_time: Option<Duration>,
     /// The worker function to be invoked for each upstream message.
-    worker_fn: F,
+    worker: W,
+    #[cfg(feature = "metrics")]
+    metrics: ServiceMetrics,
+    /// Have we told the upstream that we're ready for a message, without receiving one yet?
+    advertised: bool,
+    /// The message being handled, which is kept until the response has been sent.
+    ///
+    /// If a tick is cancelled partway through handling a message (ex: on Ctrl-C),
+    /// it is handled again by the next tick, or by [`ValhallaMicroservice::shutdown`].
+    in_flight: Option<ZmqMessage>,
 }
 
-impl<F: Fn(Api) -> WorkerResult> ValhallaMicroservice<F> {
+impl<W: Worker> ValhallaMicroservice<W> {
     /// Advertises our presence to the upstream service, indicating we are ready for the next message.
     async fn advertise(&mut self) -> ZmqResult<()> {
         self.upstream.send(ZmqMessage::from("")).await
     }
 
+    /// Metrics about the requests handled by this service (see [`metrics`]).
+    #[cfg(feature = "metrics")]
+    pub const fn metrics(&self) -> &ServiceMetrics {
+        &self.metrics
+    }
+
+    /// Runs the worker function, unless the request has been interrupted.
+    ///
+    /// Returns `None` if the request was interrupted before or during the work,
+    /// since nobody is waiting for the result.
+    /// Requests which exceed the max processing time get a timeout error.
+    async fn work(&self, request_id: u32, request: Api) -> Option<WorkerResult> {
+        let Some(mut token) = self.interrupts.start(request_id) else {
+            trace!("Dropping request ID {request_id}, which was interrupted before it started");
+            return None;
+        };
+        let action = action_name(&request);
+        let result = if let Some(max_processing_time) = self.max_processing_time {
+            token = token.with_deadline(Instant::now() + max_processing_time);
+            // Async workers are stopped at the deadline; sync workers have to check the token
+            tokio::time::timeout(
+                max_processing_time,
+                self.worker.work(request, token.clone()),
+            )
+            .await
+            .ok()
+        } else {
+            Some(self.worker.work(request, token.clone()).await)
+        };
+        self.interrupts.finish();
+
+        if token.is_interrupted() {
+            trace!("Dropping the result of request ID {request_id}, which was interrupted");
+            return None;
+        }
+        match result {
+            Some(result) if !token.is_past_deadline() => Some(result),
+            _ => {
+                warn!(
+                    "Request ID {request_id} ({action}) exceeded the max processing time of {:?}",
+                    self.max_processing_time.unwrap_or_default()
+                );
+                Some(valhalla_error::timeout_response())
+            }
+        }
+    }
+
     /// Run one iteration of the main "loop" for this service.
     ///
     /// This takes care of advertising presence,
     /// waiting for work from upstream,
-    /// executing [`self.worker_fn`],
+    /// executing the worker function,
     /// and publishing the result where appropriate.
+    /// Requests which are interrupted (see [`CancellationToken`]) get no response.
     ///
     /// # Errors
     ///
@@ -70,6 +160,9 @@ impl<F: Fn(Api) -> WorkerResult> ValhallaMicroservice<F> {
     /// Notably, these *may* not be as serious as the initial connection errors
     /// (in which case the service can't be started).
     /// Whether to continue is left up to the caller.
+    /// When a socket's connection is lost, it is reconnected before the tick returns
+    /// (see [`ValhallaMicroserviceBuilder::with_reconnect_policy`]),
+    /// but a response which failed to send is lost.
     ///
     /// [`Error::InvalidMessage`] indicates unexpected data.
     /// Callers should take note of this, but this isn't necessarily cause to terminate the service.
@@ -77,21 +170,112 @@ impl<F: Fn(Api) -> WorkerResult> ValhallaMicroservice<F> {
     /// but since Valhalla uses multi-part (multi-frame) messages,
     /// and ZMQ guarantees "all or none" delivery,
     /// it is safe to continue.
+    ///
+    /// Ticks may be cancelled (ex: in a `select!` with a shutdown signal);
+    /// a message which was being handled is kept, and handled again by the next tick,
+    /// or by [`ValhallaMicroservice::shutdown`].
     pub async fn tick(&mut self) -> Result<(), Error> {
-        const HTTP_REQ_INFO_SIZE: usize = size_of::<HttpRequestInfo>();
+        if self.in_flight.is_none() {
+            // Announce that we are ready for the next message (unless a cancelled tick already did).
+            // FIXME: The way that Valhalla (prime_server??) implements this, a "dead" process will never be detected.
+            // The messaging system will not give it any more work, but it WILL cause a request to get lost in limbo.
+            if !self.advertised {
+                self.advertise().await?;
+                self.advertised = true;
+            }
+
+            // ZeroMQ only reports lost connections on some sockets,
+            // so an upstream which goes away while we're idle may go unnoticed.
+            let lost = self.upstream.lost();
+            let message = tokio::select! {
+                message = self.upstream.socket().recv() => message,
+                () = lost.notified() => {
+                    self.upstream.reconnect("the upstream disconnected").await?;
+                    self.advertised = false;
+                    return Ok(());
+                }
+            };
+            self.advertised = false;
+            let message = match message {
+                Ok(message) => message,
+                Err(e) => {
+                    self.upstream.reconnect(&e.to_string()).await?;
+                    return Err(e.into());
+                }
+            };
+            self.in_flight = Some(message);
+        }
+
+        self.handle_in_flight().await
+    }
+
+    /// Handles the message in flight (if any), and forgets it once done.
+    async fn handle_in_flight(&mut self) -> Result<(), Error> {
+        let Some(message) = self.in_flight.clone() else {
+            return Ok(());
+        };
+        let result = self.handle(message).await;
+        self.in_flight = None;
+        #[cfg(feature = "metrics")]
+        if matches!(result, Err(Error::InvalidMessage(_))) {
+            self.metrics.record_invalid_message();
+        }
+        result
+    }
+
+    /// Stops the service gracefully.
+    ///
+    /// No more work is requested from upstream.
+    /// If a tick was cancelled partway through handling a message,
+    /// that work is finished and the response is sent (within the timeout)
+    /// before the sockets are closed.
+    ///
+    /// Note that if the service has already advertised that it's ready,
+    /// the upstream may still send it a request, which will be lost
+    /// (as with Valhalla's own workers).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the message in flight can't be handled (see [`ValhallaMicroservice::tick`]).
+    /// Work which doesn't finish within the timeout is abandoned, with a warning.
+    pub async fn shutdown(mut self, timeout: Duration) -> Result<(), Error> {
+        let result =
+            if let Ok(result) = tokio::time::timeout(timeout, self.handle_in_flight()).await {
+                result
+            } else {
+                warn!("Work in flight didn't finish within {timeout:?}; abandoning it");
+                Ok(())
+            };
+
+        let Self {
+            upstream,
+            downstream,
+            loopback,
+            interrupt_listener,
+            ..
+        } = self;
+        drop(interrupt_listener);
+        let mut errors = upstream.close().await;
+        if let Some(downstream) = downstream {
+            errors.extend(downstream.close().await);
+        }
+        errors.extend(loopback.close().await);
+        for error in errors {
+            warn!("Error closing a socket: {error}");
+        }
+
+        result
+    }
 
-        // Announce that we are ready for the next message.
-        // FIXME: The way that Valhalla (prime_server??) implements this, a "dead" process will never be detected.
-        // The messaging system will not give it any more work, but it WILL cause a request to get lost in limbo.
-        self.advertise().await?;
+    /// Decodes a message from upstream, runs the worker function,
+    /// and publishes the result.
+    async fn handle(&mut self, message: ZmqMessage) -> Result<(), Error> {
+        const HTTP_REQ_INFO_SIZE: usize = size_of::<HttpRequestInfo>();
 
         //
-        // Receive and decode the message
+        // Decode the message
         //
 
-        // TODO: Set up a monitor instead so we've got a channel (stream)!
-        // let mut monitor = self.upstream.monitor();
-        let message = self.upstream.recv().await?;
         let mut frames = message.into_vecdeque(); // Zero cost unwrap
 
         // Sanity checks
@@ -130,7 +314,18 @@ impl<F: Fn(Api) -> WorkerResult> ValhallaMicroservice<F> {
         // Handle the request
         //
 
-        match (self.worker_fn)(request) {
+        #[cfg(feature = "metrics")]
+        let timer = metrics::RequestTimer::start(&req_info, &request);
+        let result = self.work(req_info.id(), request).await;
+        #[cfg(feature = "metrics")]
+        match &result {
+            Some(result) => self.metrics.record(&timer, result),
+            None => self.metrics.record_interrupted(&timer),
+        }
+        let Some(result) = result else {
+            return Ok(());
+        };
+        match result {
             WorkerResult::HttpResponse {
                 status_code,
                 headers,
@@ -140,7 +335,17 @@ impl<F: Fn(Api) -> WorkerResult> ValhallaMicroservice<F> {
                 req_info.set_response_code(status_code.as_u16());
                 let mut message = ZmqMessage::from(req_info.as_bytes().to_vec());
 
-                let http_response = result::serialize_http(req_info, status_code, headers, body);
+                let http_response =
+                    result::serialize_http(req_info, &self.cors, status_code, headers, body);
+                message.push_back(http_response.into());
+
+                self.loopback.send(message).await?;
+            }
+            WorkerResult::CorsPreflight => {
+                req_info.set_response_code(http::StatusCode::OK.as_u16());
+                let mut message = ZmqMessage::from(req_info.as_bytes().to_vec());
+
+                let http_response = result::serialize_preflight(req_info, &self.cors);
                 message.push_back(http_response.into());
 
                 self.loopback.send(message).await?;
@@ -152,10 +357,24 @@ impl<F: Fn(Api) -> WorkerResult> ValhallaMicroservice<F> {
     }
 }
 
+/// The name of a request's action (ex: `route`), for logs and metrics.
+pub(crate) fn action_name(request: &Api) -> &'static str {
+    request
+        .options
+        .as_ref()
+        .and_then(|options| Action::try_from(options.action).ok())
+        .map_or("unknown", |action| action.as_str_name())
+}
+
 pub struct ValhallaMicroserviceBuilder<'a> {
     upstream_socket_endpoint: &'a str,
     downstream_socket_endpoint: Option<&'a str>,
+    interrupt_socket_endpoint: Option<&'a str>,
     loopback_socket_endpoint: &'a str,
+    cors: CorsConfig,
+    max_processing_time: Option<Duration>,
+    reconnect_policy: ReconnectPolicy,
+    connection_callback: Option<connection::ConnectionCallback>,
 }
 
 impl<'a> ValhallaMicroserviceBuilder<'a> {
@@ -170,7 +389,12 @@ impl<'a> ValhallaMicroserviceBuilder<'a> {
         ValhallaMicroserviceBuilder {
             upstream_socket_endpoint,
             downstream_socket_endpoint: None,
+            interrupt_socket_endpoint: None,
             loopback_socket_endpoint,
+            cors: CorsConfig::default(),
+            max_processing_time: None,
+            reconnect_policy: ReconnectPolicy::default(),
+            connection_callback: None,
         }
     }
 
@@ -189,39 +413,177 @@ impl<'a> ValhallaMicroserviceBuilder<'a> {
         }
     }
 
+    /// Adds an interrupt endpoint (typically `ipc:///tmp/interrupt`).
+    ///
+    /// Without this, the service never learns that a client has gone away,
+    /// so every request is worked on to completion.
+    #[must_use]
+    pub fn with_interrupt_socket_endpoint(
+        self,
+        interrupt_socket_endpoint: &'a str,
+    ) -> ValhallaMicroserviceBuilder<'a> {
+        ValhallaMicroserviceBuilder {
+            interrupt_socket_endpoint: Some(interrupt_socket_endpoint),
+            ..self
+        }
+    }
+
+    /// Overrides the CORS configuration.
+    ///
+    /// By default, services behave like Valhalla and allow requests from any origin.
+    #[must_use]
+    pub fn with_cors_config(self, cors: CorsConfig) -> ValhallaMicroserviceBuilder<'a> {
+        ValhallaMicroserviceBuilder { cors, ..self }
+    }
+
+    /// Limits how long each request may be worked on.
+    ///
+    /// Requests which take longer get a timeout error (HTTP 504) rather than being left in limbo.
+    /// Async worker functions are stopped at the deadline;
+    /// other worker functions can't be stopped, so they should check the [`CancellationToken`].
+    /// By default, there is no limit.
+    #[must_use]
+    pub fn with_max_processing_time(
+        self,
+        max_processing_time: Duration,
+    ) -> ValhallaMicroserviceBuilder<'a> {
+        ValhallaMicroserviceBuilder {
+            max_processing_time: Some(max_processing_time),
+            ..self
+        }
+    }
+
+    /// Overrides how sockets are reconnected when their connection is lost
+    /// (ex: because the upstream service restarted).
+    ///
+    /// By default, the service retries forever, with exponential backoff.
+    #[must_use]
+    pub fn with_reconnect_policy(
+        self,
+        reconnect_policy: ReconnectPolicy,
+    ) -> ValhallaMicroserviceBuilder<'a> {
+        ValhallaMicroserviceBuilder {
+            reconnect_policy,
+            ..self
+        }
+    }
+
+    /// Calls a function whenever a socket connects, disconnects, or is about to reconnect
+    /// (ex: to report the service as unhealthy).
+    ///
+    /// These events are also logged, so this is only needed to act on them.
+    /// The callback may be called from a background task.
+    #[must_use]
+    pub fn with_connection_callback(
+        self,
+        callback: impl Fn(&ConnectionEvent) + Send + Sync + 'static,
+    ) -> ValhallaMicroserviceBuilder<'a> {
+        ValhallaMicroserviceBuilder {
+            connection_callback: Some(std::sync::Arc::new(callback)),
+            ..self
+        }
+    }
+
     /// Tries to build the service.
     ///
     /// # Rules for worker functions
     ///
     /// - Don't panic; neither this crate nor Valhalla have well-defined behavior for this failure mode.
     /// - The usual Tokio rules for async contexts. If you're going to be working for a while, spawn a blocking thread, use a pool, channels, etc. rather than blocking.
+    /// - Long-running work should check the [`CancellationToken`] now and then,
+    ///   and give up once the request is interrupted.
+    ///
+    /// This must be called from within a Tokio runtime,
+    /// since socket events and interrupts are received in the background.
     ///
     /// # Errors
     ///
     /// This may fail if we are unable to configure the ZeroMQ sockets as requested.
-    pub async fn build<F: Fn(Api) -> WorkerResult>(
+    pub async fn build<F: Fn(Api, CancellationToken) -> WorkerResult>(
         self,
         worker_fn: F,
-    ) -> ZmqResult<ValhallaMicroservice<F>> {
-        let mut upstream = DealerSocket::new();
-        upstream.connect(self.upstream_socket_endpoint).await?;
+    ) -> ZmqResult<ValhallaMicroservice<SyncWorkerFn<F>>> {
+        self.build_with_worker(SyncWorkerFn(worker_fn)).await
+    }
+
+    /// Tries to build the service, with an async worker function.
+    ///
+    /// This is the same as [`ValhallaMicroserviceBuilder::build`],
+    /// except that the worker function returns a future,
+    /// so it can await I/O (ex: tile fetches or database calls) naturally.
+    /// The service handles one request at a time either way,
+    /// so CPU-heavy work should still be moved off the runtime.
+    ///
+    /// # Errors
+    ///
+    /// This may fail if we are unable to configure the ZeroMQ sockets as requested.
+    pub async fn build_async<F, Fut>(
+        self,
+        worker_fn: F,
+    ) -> ZmqResult<ValhallaMicroservice<AsyncWorkerFn<F>>>
+    where
+        F: Fn(Api, CancellationToken) -> Fut,
+        Fut: Future<Output = WorkerResult>,
+    {
+        self.build_with_worker(AsyncWorkerFn(worker_fn)).await
+    }
+
+    async fn build_with_worker<W: Worker>(self, worker: W) -> ZmqResult<ValhallaMicroservice<W>> {
+        let events = ConnectionEvents::new(self.connection_callback);
+        let upstream = Connection::open(
+            SocketRole::Upstream,
+            self.upstream_socket_endpoint,
+            self.reconnect_policy,
+            events.clone(),
+        )
+        .await?;
 
         let downstream = if let Some(downstream_socket_endpoint) = self.downstream_socket_endpoint {
-            let mut sock = DealerSocket::new();
-            sock.connect(downstream_socket_endpoint).await?;
-            Some(sock)
+            Some(
+                Connection::open(
+                    SocketRole::Downstream,
+                    downstream_socket_endpoint,
+                    self.reconnect_policy,
+                    events.clone(),
+                )
+                .await?,
+            )
         } else {
             None
         };
 
-        let mut loopback = PushSocket::new();
-        loopback.connect(self.loopback_socket_endpoint).await?;
+        let loopback = Connection::open(
+            SocketRole::Loopback,
+            self.loopback_socket_endpoint,
+            self.reconnect_policy,
+            events,
+        )
+        .await?;
+
+        let interrupts = Interrupts::default();
+        let interrupt_listener =
+            if let Some(interrupt_socket_endpoint) = self.interrupt_socket_endpoint {
+                let mut sock = SubSocket::new();
+                sock.connect(interrupt_socket_endpoint).await?;
+                sock.subscribe("").await?;
+                Some(interrupts.listen(sock))
+            } else {
+                None
+            };
 
         Ok(ValhallaMicroservice {
             upstream,
             downstream,
+            interrupts,
+            interrupt_listener,
             loopback,
-            worker_fn,
+            cors: self.cors,
+            max_processing_time: self.max_processing_time,
+            worker,
+            #[cfg(feature = "metrics")]
+            metrics: ServiceMetrics::default(),
+            advertised: false,
+            in_flight: None,
         })
     }
 }
@@ -0,0 +1,371 @@
+//! Prometheus metrics.
+//!
+//! Services record the requests they handle by action
+//! (how many, how long the work took, how long they waited in the queue, and how many failed),
+//! so Rust workers can be monitored alongside the rest of a Valhalla cluster.
+//! The metrics are rendered in the Prometheus text exposition format,
+//! and can be served to scrapers with [`ServiceMetrics::serve`].
+
+use crate::http_protocol::HttpRequestInfo;
+use crate::{WorkerResult, action_name};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tracing::warn;
+use valhalla_proto::Api;
+
+/// The prefix of every metric name.
+const PREFIX: &str = "valhalla_microservice";
+
+/// The upper bounds of the latency histogram buckets, in seconds (Prometheus' defaults).
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The upper bounds of the queue wait histogram buckets, in seconds.
+///
+/// Requests are only timestamped to the second, so finer buckets would be meaningless.
+const QUEUE_WAIT_BUCKETS: [f64; 7] = [0.0, 1.0, 2.0, 5.0, 10.0, 30.0, 60.0];
+
+/// A histogram with fixed buckets.
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// The number of observations in each bucket (not cumulative).
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, buckets: &[f64], value: f64) {
+        if self.counts.is_empty() {
+            self.counts = vec![0; buckets.len()];
+        }
+        if let Some(bucket) = buckets.iter().position(|&bound| value <= bound) {
+            self.counts[bucket] += 1;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, output: &mut String, name: &str, action: &str, buckets: &[f64]) {
+        let mut cumulative = 0;
+        for (index, bound) in buckets.iter().enumerate() {
+            cumulative += self.counts.get(index).copied().unwrap_or_default();
+            let _ = writeln!(
+                output,
+                "{name}_bucket{{action=\"{action}\",le=\"{bound}\"}} {cumulative}"
+            );
+        }
+        let _ = writeln!(
+            output,
+            "{name}_bucket{{action=\"{action}\",le=\"+Inf\"}} {}",
+            self.count
+        );
+        let _ = writeln!(output, "{name}_sum{{action=\"{action}\"}} {}", self.sum);
+        let _ = writeln!(output, "{name}_count{{action=\"{action}\"}} {}", self.count);
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct ActionMetrics {
+    requests: u64,
+    /// Responses with a 4xx or 5xx status code.
+    errors: u64,
+    interrupted: u64,
+    latency: Histogram,
+    queue_wait: Histogram,
+}
+
+#[derive(Debug, Default)]
+struct MetricsState {
+    actions: BTreeMap<&'static str, ActionMetrics>,
+    invalid_messages: u64,
+}
+
+/// A request which is being worked on.
+pub(crate) struct RequestTimer {
+    action: &'static str,
+    queue_wait: Duration,
+    started: Instant,
+}
+
+impl RequestTimer {
+    pub(crate) fn start(req_info: &HttpRequestInfo, request: &Api) -> Self {
+        let action = action_name(request);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Self {
+            action,
+            queue_wait: now.saturating_sub(Duration::from_secs(req_info.timestamp().into())),
+            started: Instant::now(),
+        }
+    }
+}
+
+/// Metrics about the requests handled by a service.
+///
+/// This is cheap to clone; clones share the same metrics.
+#[derive(Debug, Clone, Default)]
+pub struct ServiceMetrics {
+    state: Arc<Mutex<MetricsState>>,
+}
+
+impl ServiceMetrics {
+    fn with_action(&self, action: &'static str, f: impl FnOnce(&mut ActionMetrics)) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        f(state.actions.entry(action).or_default());
+    }
+
+    /// Records a request which was worked on to completion.
+    pub(crate) fn record(&self, timer: &RequestTimer, result: &WorkerResult) {
+        let latency = timer.started.elapsed();
+        self.with_action(timer.action, |metrics| {
+            metrics.requests += 1;
+            if let WorkerResult::HttpResponse { status_code, .. } = result
+                && (status_code.is_client_error() || status_code.is_server_error())
+            {
+                metrics.errors += 1;
+            }
+            metrics
+                .latency
+                .observe(&LATENCY_BUCKETS, latency.as_secs_f64());
+            metrics
+                .queue_wait
+                .observe(&QUEUE_WAIT_BUCKETS, timer.queue_wait.as_secs_f64());
+        });
+    }
+
+    /// Records a request which was interrupted, so its result (if any) was discarded.
+    pub(crate) fn record_interrupted(&self, timer: &RequestTimer) {
+        self.with_action(timer.action, |metrics| {
+            metrics.requests += 1;
+            metrics.interrupted += 1;
+        });
+    }
+
+    /// Records a message which couldn't be decoded.
+    pub(crate) fn record_invalid_message(&self) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.invalid_messages += 1;
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut output = String::new();
+        render_counter(
+            &mut output,
+            &state,
+            "requests_total",
+            "Requests handled, by action.",
+            |metrics| metrics.requests,
+        );
+        render_counter(
+            &mut output,
+            &state,
+            "errors_total",
+            "Responses with an error status code, by action.",
+            |metrics| metrics.errors,
+        );
+        render_counter(
+            &mut output,
+            &state,
+            "interrupted_total",
+            "Requests interrupted before their response was sent, by action.",
+            |metrics| metrics.interrupted,
+        );
+        render_histogram(
+            &mut output,
+            &state,
+            "request_duration_seconds",
+            "Time spent working on requests, by action.",
+            &LATENCY_BUCKETS,
+            |metrics| &metrics.latency,
+        );
+        render_histogram(
+            &mut output,
+            &state,
+            "queue_wait_seconds",
+            "Time between a request arriving at the server and work starting, by action.",
+            &QUEUE_WAIT_BUCKETS,
+            |metrics| &metrics.queue_wait,
+        );
+
+        let _ = writeln!(
+            output,
+            "# HELP {PREFIX}_invalid_messages_total Messages from upstream which couldn't be decoded."
+        );
+        let _ = writeln!(output, "# TYPE {PREFIX}_invalid_messages_total counter");
+        let _ = writeln!(
+            output,
+            "{PREFIX}_invalid_messages_total {}",
+            state.invalid_messages
+        );
+        output
+    }
+
+    /// Serves the metrics to Prometheus scrapers over HTTP (on any path).
+    ///
+    /// This runs until the listener fails, so it's usually spawned as a task
+    /// alongside the service loop.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the address can't be bound, or the listener fails.
+    /// Errors on individual connections are logged.
+    pub async fn serve(self, address: impl ToSocketAddrs) -> std::io::Result<()> {
+        let listener = TcpListener::bind(address).await?;
+        loop {
+            let (mut stream, _) = listener.accept().await?;
+            let metrics = self.clone();
+            tokio::spawn(async move {
+                // The request doesn't matter, but reading it avoids resetting the connection
+                let mut request = [0; 1024];
+                let body = metrics.render();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let result = async {
+                    let _ = stream.read(&mut request).await?;
+                    stream.write_all(response.as_bytes()).await?;
+                    stream.shutdown().await
+                };
+                if let Err(e) = result.await {
+                    warn!("Error serving metrics: {e}");
+                }
+            });
+        }
+    }
+}
+
+/// Renders a counter with a value for each action.
+fn render_counter(
+    output: &mut String,
+    state: &MetricsState,
+    name: &str,
+    help: &str,
+    value: impl Fn(&ActionMetrics) -> u64,
+) {
+    let _ = writeln!(output, "# HELP {PREFIX}_{name} {help}");
+    let _ = writeln!(output, "# TYPE {PREFIX}_{name} counter");
+    for (action, metrics) in &state.actions {
+        let _ = writeln!(
+            output,
+            "{PREFIX}_{name}{{action=\"{action}\"}} {}",
+            value(metrics)
+        );
+    }
+}
+
+/// Renders a histogram for each action.
+fn render_histogram(
+    output: &mut String,
+    state: &MetricsState,
+    name: &str,
+    help: &str,
+    buckets: &[f64],
+    histogram: impl Fn(&ActionMetrics) -> &Histogram,
+) {
+    let name = format!("{PREFIX}_{name}");
+    let _ = writeln!(output, "# HELP {name} {help}");
+    let _ = writeln!(output, "# TYPE {name} histogram");
+    for (action, metrics) in &state.actions {
+        histogram(metrics).render(output, &name, action, buckets);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RequestTimer, ServiceMetrics};
+    use crate::WorkerResult;
+    use crate::http_protocol::HttpRequestInfo;
+    use http::StatusCode;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use valhalla_proto::Api;
+    use valhalla_proto::Options;
+    use valhalla_proto::options::Action;
+    use zerocopy::transmute;
+
+    fn request(action: Action) -> (HttpRequestInfo, Api) {
+        const REQ_INFO_BYTES: [u8; 12] = [
+            0x00, 0x00, 0x00, 0x00, 0xf5, 0x76, 0xb1, 0x68, 0x01, 0x00, 0x00, 0x00,
+        ];
+        let request = Api {
+            options: Some(Options {
+                action: action.into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        (transmute!(REQ_INFO_BYTES), request)
+    }
+
+    #[test]
+    fn test_render() {
+        let metrics = ServiceMetrics::default();
+        let (req_info, status) = request(Action::Status);
+        let (_, route) = request(Action::Route);
+        metrics.record(
+            &RequestTimer::start(&req_info, &status),
+            &WorkerResult::json(StatusCode::OK, "ok"),
+        );
+        metrics.record(
+            &RequestTimer::start(&req_info, &route),
+            &WorkerResult::json(StatusCode::BAD_REQUEST, "bad"),
+        );
+        metrics.record_interrupted(&RequestTimer::start(&req_info, &route));
+        metrics.record_invalid_message();
+
+        let output = metrics.render();
+        let lines: Vec<_> = output.lines().collect();
+        for expected in [
+            "# TYPE valhalla_microservice_requests_total counter",
+            r#"valhalla_microservice_requests_total{action="route"} 2"#,
+            r#"valhalla_microservice_requests_total{action="status"} 1"#,
+            r#"valhalla_microservice_errors_total{action="route"} 1"#,
+            r#"valhalla_microservice_errors_total{action="status"} 0"#,
+            r#"valhalla_microservice_interrupted_total{action="route"} 1"#,
+            "# TYPE valhalla_microservice_request_duration_seconds histogram",
+            r#"valhalla_microservice_request_duration_seconds_bucket{action="status",le="10"} 1"#,
+            r#"valhalla_microservice_request_duration_seconds_bucket{action="status",le="+Inf"} 1"#,
+            r#"valhalla_microservice_request_duration_seconds_count{action="route"} 1"#,
+            // The request is timestamped in 2025, so it's been waiting a while
+            r#"valhalla_microservice_queue_wait_seconds_bucket{action="status",le="60"} 0"#,
+            r#"valhalla_microservice_queue_wait_seconds_count{action="status"} 1"#,
+            "valhalla_microservice_invalid_messages_total 1",
+        ] {
+            assert!(lines.contains(&expected), "{expected} not in {output}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_serve() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+        let metrics = ServiceMetrics::default();
+        metrics.record_invalid_message();
+        tokio::spawn(metrics.clone().serve(address));
+
+        let mut stream = loop {
+            if let Ok(stream) = tokio::net::TcpStream::connect(address).await {
+                break stream;
+            }
+            tokio::task::yield_now().await;
+        };
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(&metrics.render()));
+    }
+}
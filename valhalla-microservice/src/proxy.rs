@@ -0,0 +1,282 @@
+//! A Rust implementation of the `prime_server` proxy.
+//!
+//! Each stage of a Valhalla pipeline sits behind a proxy,
+//! which hands jobs from the previous stage (or the HTTP server) to idle workers.
+//! Workers announce that they are ready for a job by sending a heartbeat,
+//! and the proxy gives each job to the worker which has been idle the longest.
+//!
+//! Both sides are router sockets, so the proxy knows where every message came from:
+//!
+//! - Upstream, senders (dealer sockets) send jobs, which the proxy strips of the sender's identity.
+//! - Downstream, workers (dealer sockets) send heartbeats,
+//!   and receive jobs addressed to them.
+
+use crate::Error;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tracing::{trace, warn};
+use zeromq::{RouterSocket, ZmqMessage, ZmqResult, prelude::*};
+
+/// A worker which is ready for a job.
+#[derive(Debug)]
+struct ReadyWorker {
+    /// The worker's identity on the downstream socket.
+    address: Vec<u8>,
+    /// When the worker last sent a heartbeat.
+    heartbeat: Instant,
+}
+
+/// The workers which are ready for a job, from least to most recently used.
+#[derive(Debug, Default)]
+struct ReadyWorkers {
+    queue: VecDeque<ReadyWorker>,
+}
+
+impl ReadyWorkers {
+    /// Records a heartbeat from a worker.
+    ///
+    /// Workers which are already waiting keep their place in the queue.
+    fn heartbeat(&mut self, address: Vec<u8>, now: Instant) {
+        if let Some(worker) = self
+            .queue
+            .iter_mut()
+            .find(|worker| worker.address == address)
+        {
+            worker.heartbeat = now;
+        } else {
+            self.queue.push_back(ReadyWorker {
+                address,
+                heartbeat: now,
+            });
+        }
+    }
+
+    /// Forgets workers which haven't sent a heartbeat recently.
+    fn expire(&mut self, now: Instant, expiry: Duration) {
+        self.queue.retain(|worker| {
+            let alive = now.duration_since(worker.heartbeat) < expiry;
+            if !alive {
+                warn!("Worker {:?} expired without a heartbeat", worker.address);
+            }
+            alive
+        });
+    }
+
+    /// Takes the least recently used worker.
+    fn next(&mut self) -> Option<Vec<u8>> {
+        self.queue.pop_front().map(|worker| worker.address)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+/// A proxy between two stages of a Valhalla pipeline (see the [module docs](self)).
+pub struct ValhallaProxy {
+    /// The socket which jobs arrive on (ex: `ipc:///tmp/thor_in`).
+    upstream: RouterSocket,
+    /// The socket which workers connect to (ex: `ipc:///tmp/thor_out`).
+    downstream: RouterSocket,
+    workers: ReadyWorkers,
+    /// How long a worker may go without a heartbeat before it's considered dead.
+    worker_expiry: Option<Duration>,
+    /// A job which couldn't be delivered, because every ready worker has gone away.
+    pending: Option<ZmqMessage>,
+}
+
+impl ValhallaProxy {
+    /// Binds the upstream and downstream sockets.
+    ///
+    /// # Errors
+    ///
+    /// Fails if either socket can't be bound (ex: because the endpoint is in use).
+    pub async fn bind(
+        upstream_socket_endpoint: &str,
+        downstream_socket_endpoint: &str,
+    ) -> ZmqResult<Self> {
+        let mut upstream = RouterSocket::new();
+        upstream.bind(upstream_socket_endpoint).await?;
+        let mut downstream = RouterSocket::new();
+        downstream.bind(downstream_socket_endpoint).await?;
+        Ok(Self {
+            upstream,
+            downstream,
+            workers: ReadyWorkers::default(),
+            worker_expiry: None,
+            pending: None,
+        })
+    }
+
+    /// Forgets ready workers which haven't sent a heartbeat within the expiry,
+    /// so that jobs aren't lost to workers which have died.
+    ///
+    /// Only use this with workers which send heartbeats periodically while idle
+    /// (`prime_server` workers do; [`ValhallaMicroservice`](crate::ValhallaMicroservice) doesn't).
+    /// By default, workers never expire.
+    #[must_use]
+    pub fn with_worker_expiry(self, worker_expiry: Duration) -> Self {
+        Self {
+            worker_expiry: Some(worker_expiry),
+            ..self
+        }
+    }
+
+    /// Runs one iteration of the proxy loop:
+    /// receives a heartbeat from a worker, or hands a job to a ready worker.
+    ///
+    /// Jobs are only received while there is a worker ready for them,
+    /// so they queue up in the upstream socket otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Fails on ZeroMQ errors, or if a message is malformed.
+    /// Neither is fatal; the proxy can keep going.
+    pub async fn tick(&mut self) -> Result<(), Error> {
+        if let Some(expiry) = self.worker_expiry {
+            self.workers.expire(Instant::now(), expiry);
+        }
+
+        if self.workers.is_empty() {
+            let message = self.downstream.recv().await?;
+            return self.heartbeat(&message);
+        }
+        if let Some(job) = self.pending.take() {
+            return self.dispatch(job).await;
+        }
+
+        tokio::select! {
+            message = self.downstream.recv() => self.heartbeat(&message?),
+            message = self.upstream.recv() => {
+                let mut message = message?;
+                if message.len() < 2 {
+                    return Err(Error::InvalidMessage(
+                        "Expected a job with at least one frame after the sender's identity".to_string(),
+                    ));
+                }
+                // Strip the sender's identity
+                let job = message.split_off(1);
+                self.dispatch(job).await
+            }
+        }
+    }
+
+    /// Runs the proxy until a ZeroMQ error occurs.
+    ///
+    /// Malformed messages are logged and skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first ZeroMQ error.
+    pub async fn run(&mut self) -> Result<(), Error> {
+        loop {
+            match self.tick().await {
+                Ok(()) => {}
+                Err(Error::InvalidMessage(e)) => warn!("{e}"),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Records a heartbeat message (the worker's identity, and a heartbeat frame).
+    fn heartbeat(&mut self, message: &ZmqMessage) -> Result<(), Error> {
+        if message.len() != 2 {
+            return Err(Error::InvalidMessage(format!(
+                "Expected a heartbeat with two frames (the worker's identity and a heartbeat); got {} frames instead",
+                message.len()
+            )));
+        }
+        // Infallible because we checked the length above
+        let address = message.get(0).unwrap().to_vec();
+        trace!("Heartbeat from worker {address:?}");
+        self.workers.heartbeat(address, Instant::now());
+        Ok(())
+    }
+
+    /// Sends a job to the least recently used worker.
+    ///
+    /// Workers which have disconnected are skipped,
+    /// and the job waits for the next heartbeat if none are left.
+    async fn dispatch(&mut self, job: ZmqMessage) -> Result<(), Error> {
+        while let Some(address) = self.workers.next() {
+            trace!("Sending a job to worker {address:?}");
+            let mut message = job.clone();
+            message.push_front(address.clone().into());
+            match self.downstream.send(message).await {
+                Ok(()) => return Ok(()),
+                Err(e) => warn!("Failed to send a job to worker {address:?}: {e}"),
+            }
+        }
+        self.pending = Some(job);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReadyWorkers, ValhallaProxy};
+    use crate::{ValhallaMicroserviceBuilder, WorkerResult};
+    use http::StatusCode;
+    use serde_json::json;
+    use std::time::{Duration, Instant};
+    use valhalla_proto::Api;
+    use valhalla_proto::prost::Message;
+    use zeromq::{DealerSocket, PullSocket, Socket, SocketRecv, SocketSend, ZmqMessage};
+
+    #[test]
+    fn test_ready_workers() {
+        let now = Instant::now();
+        let mut workers = ReadyWorkers::default();
+        workers.heartbeat(vec![1], now);
+        workers.heartbeat(vec![2], now + Duration::from_secs(1));
+        // A repeated heartbeat doesn't move the worker to the back of the queue
+        workers.heartbeat(vec![1], now + Duration::from_secs(2));
+
+        workers.expire(now + Duration::from_secs(3), Duration::from_millis(1500));
+        assert_eq!(workers.next(), Some(vec![1]));
+        assert_eq!(workers.next(), None);
+    }
+
+    #[tokio::test]
+    async fn test_pipeline() {
+        const UPSTREAM: &str = "ipc:///tmp/valhalla_microservice_test_proxy_in";
+        const DOWNSTREAM: &str = "ipc:///tmp/valhalla_microservice_test_proxy_out";
+        const LOOPBACK: &str = "ipc:///tmp/valhalla_microservice_test_proxy_loopback";
+        for endpoint in [UPSTREAM, DOWNSTREAM, LOOPBACK] {
+            let _ = std::fs::remove_file(endpoint.trim_start_matches("ipc://"));
+        }
+
+        let mut loopback = PullSocket::new();
+        loopback.bind(LOOPBACK).await.unwrap();
+        let mut proxy = ValhallaProxy::bind(UPSTREAM, DOWNSTREAM).await.unwrap();
+        tokio::spawn(async move { proxy.run().await });
+
+        let mut service = ValhallaMicroserviceBuilder::new(DOWNSTREAM, LOOPBACK)
+            .build(|_, _| WorkerResult::json(StatusCode::OK, json!({"ok": true})))
+            .await
+            .unwrap();
+        tokio::spawn(async move {
+            loop {
+                service.tick().await.unwrap();
+            }
+        });
+
+        // Version 1 (HTTP/1.1), request ID 7
+        let req_info: [u8; 12] = [7, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0];
+        let mut job = ZmqMessage::from(req_info.to_vec());
+        job.push_back(Api::default().encode_to_vec().into());
+        let mut sender = DealerSocket::new();
+        sender.connect(UPSTREAM).await.unwrap();
+        sender.send(job).await.unwrap();
+
+        let response = tokio::time::timeout(Duration::from_secs(5), loopback.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(response.len(), 2);
+        assert_eq!(response.get(0).unwrap()[0], 7);
+        let http_response = String::from_utf8(response.get(1).unwrap().to_vec()).unwrap();
+        assert!(http_response.starts_with("HTTP/1.1 200 OK"));
+        assert!(http_response.ends_with(r#"{"ok":true}"#));
+    }
+}
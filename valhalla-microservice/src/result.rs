@@ -1,8 +1,25 @@
+use crate::cors::CorsConfig;
+#[cfg(feature = "compression")]
+use crate::http_protocol::ContentEncoding;
 use crate::http_protocol::HttpRequestInfo;
-use http::header::{ACCESS_CONTROL_ALLOW_ORIGIN, CONNECTION, CONTENT_LENGTH, CONTENT_TYPE};
+#[cfg(feature = "compression")]
+use brotli::enc::BrotliEncoderParams;
+#[cfg(feature = "compression")]
+use flate2::Compression;
+#[cfg(feature = "compression")]
+use flate2::write::GzEncoder;
+#[cfg(feature = "compression")]
+use http::HeaderValue;
+use http::header::{CONNECTION, CONTENT_LENGTH, CONTENT_TYPE};
+#[cfg(feature = "compression")]
+use http::header::{CONTENT_ENCODING, VARY};
 use http::{HeaderMap, StatusCode};
 use itertools::intersperse;
 use serde::Serialize;
+#[cfg(feature = "compression")]
+use std::io::Write;
+#[cfg(feature = "compression")]
+use tracing::warn;
 
 /// The result of a worker computation.
 pub enum WorkerResult {
@@ -18,11 +35,15 @@ pub enum WorkerResult {
         /// If you are constructing this enum variant on your own (or implementing a new helper),
         /// be careful to set `Content-Type`, and any other headers specific to this type of response.
         ///
-        /// `Content-Length`, `Access-Control-Allow-Origin`, and `Connection`
+        /// `Content-Length`, `Connection`, and the CORS headers (see [`CorsConfig`])
         /// may be set automatically during serialization; any values you set here will be clobbered.
         headers: HeaderMap,
         body: Vec<u8>,
     },
+    /// An empty response to an HTTP `OPTIONS` (CORS preflight) request.
+    ///
+    /// The `Access-Control-Allow-*` headers are filled in from the service's [`CorsConfig`].
+    CorsPreflight,
     // TODO: Figure out the other variants here...
     PlaceholderDownstreamTBD,
 }
@@ -47,8 +68,77 @@ impl WorkerResult {
     }
 }
 
+/// Bodies smaller than this aren't worth compressing.
+#[cfg(feature = "compression")]
+const MIN_COMPRESSIBLE_SIZE: usize = 1024;
+
+/// Brotli's default (maximum) quality is far too slow for responses;
+/// this level compresses about as well as gzip's default, but faster.
+#[cfg(feature = "compression")]
+const BROTLI_QUALITY: i32 = 5;
+
+/// Compresses the body with the encoding negotiated with the client (if any),
+/// updating the headers to match.
+///
+/// Bodies which are small, or already encoded, are left alone.
+/// Otherwise, the response depends on the client's encodings, so this adds `Vary: Accept-Encoding`.
+#[cfg(feature = "compression")]
+pub(crate) fn compress(
+    encoding: Option<ContentEncoding>,
+    headers: &mut HeaderMap,
+    body: Vec<u8>,
+) -> Vec<u8> {
+    if body.len() < MIN_COMPRESSIBLE_SIZE || headers.contains_key(CONTENT_ENCODING) {
+        return body;
+    }
+    // Caches need to know that the response depends on the request's encodings
+    headers.append(VARY, HeaderValue::from_static("Accept-Encoding"));
+    let Some(encoding) = encoding else {
+        return body;
+    };
+
+    match encode(encoding, &body) {
+        Ok(compressed) => {
+            headers.insert(
+                CONTENT_ENCODING,
+                HeaderValue::from_static(encoding.as_str()),
+            );
+            compressed
+        }
+        Err(e) => {
+            // Compressing into memory shouldn't fail, but the response is still good without it
+            warn!(
+                "Failed to compress a response with {}: {e}",
+                encoding.as_str()
+            );
+            body
+        }
+    }
+}
+
+#[cfg(feature = "compression")]
+fn encode(encoding: ContentEncoding, body: &[u8]) -> std::io::Result<Vec<u8>> {
+    match encoding {
+        ContentEncoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(body)?;
+            encoder.finish()
+        }
+        ContentEncoding::Brotli => {
+            let mut compressed = Vec::new();
+            let params = BrotliEncoderParams {
+                quality: BROTLI_QUALITY,
+                ..BrotliEncoderParams::default()
+            };
+            brotli::BrotliCompress(&mut &body[..], &mut compressed, &params)?;
+            Ok(compressed)
+        }
+    }
+}
+
 pub(crate) fn serialize_http(
     request_info: HttpRequestInfo,
+    cors: &CorsConfig,
     status_code: StatusCode,
     headers: HeaderMap,
     body: Vec<u8>,
@@ -56,8 +146,15 @@ pub(crate) fn serialize_http(
     let prelude: Vec<u8> =
         format!("{} {}\r\n", request_info.http_version_string(), status_code).into_bytes();
     let mut headers = headers;
+    // prime_server leaves the accepted encodings zeroed,
+    // so the response only varies when a front end filled them in
+    #[cfg(feature = "compression")]
+    let body = match request_info.preferred_encoding() {
+        Some(encoding) => compress(Some(encoding), &mut headers, body),
+        None => body,
+    };
     headers.insert(CONTENT_LENGTH, body.len().to_string().parse().unwrap());
-    headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, "*".parse().unwrap());
+    cors.apply(&mut headers);
 
     if request_info.connection_keep_alive() {
         headers.insert(CONNECTION, "Keep-Alive".parse().unwrap());
@@ -77,6 +174,13 @@ pub(crate) fn serialize_http(
     [prelude, headers, "\r\n\r\n".to_string().into_bytes(), body].concat()
 }
 
+/// Serializes the response to a CORS preflight (`OPTIONS`) request.
+pub(crate) fn serialize_preflight(request_info: HttpRequestInfo, cors: &CorsConfig) -> Vec<u8> {
+    let mut headers = HeaderMap::new();
+    cors.apply_preflight(&mut headers);
+    serialize_http(request_info, cors, StatusCode::OK, headers, Vec::new())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -97,7 +201,13 @@ mod tests {
         );
         headers.insert(CONTENT_LENGTH, BODY.len().to_string().parse().unwrap());
 
-        let result = serialize_http(req_info, StatusCode::OK, headers, BODY.to_vec());
+        let result = serialize_http(
+            req_info,
+            &CorsConfig::default(),
+            StatusCode::OK,
+            headers,
+            BODY.to_vec(),
+        );
 
         let reesult_utf8 = String::from_utf8(result).expect("Expected a UTF-8 result.");
 
@@ -105,4 +215,70 @@ mod tests {
             insta::assert_snapshot!(reesult_utf8);
         }
     }
+
+    #[test]
+    fn test_serialize_preflight() {
+        const REQ_INFO_BYTES: [u8; 12] = [
+            0x00, 0x00, 0x00, 0x00, 0xf5, 0x76, 0xb1, 0x68, 0x01, 0x00, 0x00, 0x00,
+        ];
+        let req_info: HttpRequestInfo = transmute!(REQ_INFO_BYTES);
+
+        let result = serialize_preflight(req_info, &CorsConfig::default());
+
+        let result_utf8 = String::from_utf8(result).expect("Expected a UTF-8 result.");
+
+        if !cfg!(miri) {
+            insta::assert_snapshot!(result_utf8);
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_compression() {
+        use std::io::Read;
+
+        const REQ_INFO_BYTES: [u8; 12] = [
+            0x00, 0x00, 0x00, 0x00, 0xf5, 0x76, 0xb1, 0x68, 0x01, 0x00, 0x00, 0x00,
+        ];
+        let body = br#"{"lat":59.437,"lon":24.7536},"#.repeat(100);
+        let split = |response: Vec<u8>| {
+            let separator = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+            let head = String::from_utf8(response[..separator].to_vec()).unwrap();
+            (head, response[separator + 4..].to_vec())
+        };
+        let serialize = |req_info| {
+            split(serialize_http(
+                req_info,
+                &CorsConfig::default(),
+                StatusCode::OK,
+                HeaderMap::new(),
+                body.clone(),
+            ))
+        };
+
+        // Requests from prime_server don't carry the client's encodings, so nothing varies
+        let mut req_info: HttpRequestInfo = transmute!(REQ_INFO_BYTES);
+        let (head, uncompressed) = serialize(req_info);
+        assert!(!head.contains("vary"));
+        assert!(!head.contains("content-encoding"));
+        assert_eq!(uncompressed, body);
+
+        req_info.set_accept_encoding("gzip");
+        let (head, compressed) = serialize(req_info);
+        assert!(head.contains("vary: Accept-Encoding"));
+        assert!(head.contains("content-encoding: gzip"));
+        assert!(head.contains(&format!("content-length: {}", compressed.len())));
+        let mut decompressed = Vec::new();
+        flate2::read::GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, body);
+
+        req_info.set_accept_encoding("gzip, br");
+        let (head, compressed) = serialize(req_info);
+        assert!(head.contains("content-encoding: br"));
+        let mut decompressed = Vec::new();
+        brotli::BrotliDecompress(&mut compressed.as_slice(), &mut decompressed).unwrap();
+        assert_eq!(decompressed, body);
+    }
 }
@@ -0,0 +1,570 @@
+//! Valhalla's `service_limits` configuration.
+//!
+//! Valhalla caps the size of requests (number of locations, path distance, matrix size, etc.)
+//! on a per-costing basis.
+//! Without these, a single request can tie up a worker for a very long time,
+//! which makes public deployments easy targets for denial of service attacks.
+//!
+//! The structures here deserialize from the `service_limits` section of `valhalla.json`,
+//! and default to the same values as `valhalla_build_config`.
+
+use crate::{WorkerResult, valhalla_error};
+use http::StatusCode;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::path::Path;
+use thiserror::Error;
+use valhalla_proto::costing::Type as CostingType;
+use valhalla_proto::options::Action;
+use valhalla_proto::{LatLng, Location, Options, contour, lat_lng};
+use valhalla_response::error::codes;
+
+/// The radius of the earth used by Valhalla for distance calculations (meters).
+const EARTH_RADIUS_METERS: f64 = 6_378_160.0;
+
+/// Limits which apply to a single costing model.
+///
+/// Like in Valhalla, any keys missing from a costing's section of the config
+/// fall back to that costing's defaults.
+/// When deserialized on their own, missing keys fall back to the motor vehicle defaults.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct CostingLimits {
+    /// The maximum path distance (sum of the straight line distances between locations), in meters.
+    pub max_distance: f64,
+    /// The maximum number of locations in a route request.
+    pub max_locations: usize,
+    /// The maximum distance between any source and target in a matrix request, in meters.
+    pub max_matrix_distance: f64,
+    /// The maximum number of source-target pairs in a matrix request.
+    pub max_matrix_location_pairs: usize,
+}
+
+impl CostingLimits {
+    const fn new(
+        max_distance: f64,
+        max_locations: usize,
+        max_matrix_distance: f64,
+        max_matrix_location_pairs: usize,
+    ) -> Self {
+        Self {
+            max_distance,
+            max_locations,
+            max_matrix_distance,
+            max_matrix_location_pairs,
+        }
+    }
+
+    const fn motor_vehicle() -> Self {
+        Self::new(5_000_000.0, 20, 400_000.0, 2500)
+    }
+
+    const fn slow_mode() -> Self {
+        Self::new(500_000.0, 50, 200_000.0, 2500)
+    }
+
+    const fn multimodal() -> Self {
+        Self::new(500_000.0, 50, 0.0, 0)
+    }
+
+    const fn pedestrian() -> Self {
+        Self::new(250_000.0, 50, 200_000.0, 2500)
+    }
+}
+
+impl Default for CostingLimits {
+    fn default() -> Self {
+        Self::motor_vehicle()
+    }
+}
+
+/// A costing's section of the config, where any of the keys may be missing.
+#[derive(Deserialize)]
+#[expect(
+    clippy::struct_field_names,
+    reason = "The names match the config keys (and CostingLimits)"
+)]
+struct CostingLimitsConfig {
+    max_distance: Option<f64>,
+    max_locations: Option<usize>,
+    max_matrix_distance: Option<f64>,
+    max_matrix_location_pairs: Option<usize>,
+}
+
+impl CostingLimitsConfig {
+    /// Deserializes a costing's limits, filling in any missing keys from its defaults.
+    fn deserialize_or<'de, D: Deserializer<'de>>(
+        deserializer: D,
+        defaults: CostingLimits,
+    ) -> Result<CostingLimits, D::Error> {
+        let config = Self::deserialize(deserializer)?;
+        Ok(CostingLimits {
+            max_distance: config.max_distance.unwrap_or(defaults.max_distance),
+            max_locations: config.max_locations.unwrap_or(defaults.max_locations),
+            max_matrix_distance: config
+                .max_matrix_distance
+                .unwrap_or(defaults.max_matrix_distance),
+            max_matrix_location_pairs: config
+                .max_matrix_location_pairs
+                .unwrap_or(defaults.max_matrix_location_pairs),
+        })
+    }
+}
+
+fn motor_vehicle_limits<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<CostingLimits, D::Error> {
+    CostingLimitsConfig::deserialize_or(deserializer, CostingLimits::motor_vehicle())
+}
+
+fn slow_mode_limits<'de, D: Deserializer<'de>>(deserializer: D) -> Result<CostingLimits, D::Error> {
+    CostingLimitsConfig::deserialize_or(deserializer, CostingLimits::slow_mode())
+}
+
+fn multimodal_limits<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<CostingLimits, D::Error> {
+    CostingLimitsConfig::deserialize_or(deserializer, CostingLimits::multimodal())
+}
+
+fn pedestrian_limits<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<CostingLimits, D::Error> {
+    CostingLimitsConfig::deserialize_or(deserializer, CostingLimits::pedestrian())
+}
+
+/// Limits for isochrone requests.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct IsochroneLimits {
+    /// The maximum number of contours.
+    pub max_contours: usize,
+    /// The maximum time contour, in minutes.
+    pub max_time_contour: f32,
+    /// The maximum distance contour, in kilometers.
+    pub max_distance_contour: f32,
+    /// The maximum number of locations.
+    pub max_locations: usize,
+}
+
+impl Default for IsochroneLimits {
+    fn default() -> Self {
+        Self {
+            max_contours: 4,
+            max_time_contour: 120.0,
+            max_distance_contour: 200.0,
+            max_locations: 1,
+        }
+    }
+}
+
+/// The `service_limits` section of the Valhalla config.
+///
+/// Costings which aren't listed in the config file use the Valhalla defaults.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ServiceLimits {
+    #[serde(rename = "auto", deserialize_with = "motor_vehicle_limits")]
+    pub auto_: CostingLimits,
+    #[serde(deserialize_with = "slow_mode_limits")]
+    pub bicycle: CostingLimits,
+    #[serde(deserialize_with = "slow_mode_limits")]
+    pub bikeshare: CostingLimits,
+    #[serde(deserialize_with = "motor_vehicle_limits")]
+    pub bus: CostingLimits,
+    #[serde(deserialize_with = "slow_mode_limits")]
+    pub low_speed_vehicle: CostingLimits,
+    #[serde(deserialize_with = "slow_mode_limits")]
+    pub motor_scooter: CostingLimits,
+    #[serde(deserialize_with = "slow_mode_limits")]
+    pub motorcycle: CostingLimits,
+    #[serde(deserialize_with = "multimodal_limits")]
+    pub multimodal: CostingLimits,
+    #[serde(deserialize_with = "pedestrian_limits")]
+    pub pedestrian: CostingLimits,
+    #[serde(deserialize_with = "motor_vehicle_limits")]
+    pub taxi: CostingLimits,
+    #[serde(deserialize_with = "motor_vehicle_limits")]
+    pub truck: CostingLimits,
+    pub isochrone: IsochroneLimits,
+    /// The maximum number of locations to exclude (avoid).
+    pub max_exclude_locations: usize,
+}
+
+impl Default for ServiceLimits {
+    fn default() -> Self {
+        Self {
+            auto_: CostingLimits::motor_vehicle(),
+            bicycle: CostingLimits::slow_mode(),
+            bikeshare: CostingLimits::slow_mode(),
+            bus: CostingLimits::motor_vehicle(),
+            low_speed_vehicle: CostingLimits::slow_mode(),
+            motor_scooter: CostingLimits::slow_mode(),
+            motorcycle: CostingLimits::slow_mode(),
+            multimodal: CostingLimits::multimodal(),
+            pedestrian: CostingLimits::pedestrian(),
+            taxi: CostingLimits::motor_vehicle(),
+            truck: CostingLimits::motor_vehicle(),
+            isochrone: IsochroneLimits::default(),
+            max_exclude_locations: 50,
+        }
+    }
+}
+
+/// A request which exceeds the configured service limits.
+///
+/// The messages and error codes match those returned by Valhalla.
+#[derive(Error, Debug, PartialEq)]
+pub enum ServiceLimitError {
+    #[error("Exceeded max locations: {0}")]
+    MaxLocations(usize),
+    #[error("Exceeded max time: {0}")]
+    MaxTimeContour(f32),
+    #[error("Exceeded max contours: {0}")]
+    MaxContours(usize),
+    #[error("Path distance exceeds the max distance limit: {0}")]
+    MaxDistance(f64),
+    #[error("Exceeded max avoid locations: {0}")]
+    MaxExcludeLocations(usize),
+    #[error("Exceeded max distance: {0}")]
+    MaxDistanceContour(f32),
+}
+
+impl ServiceLimitError {
+    /// The Valhalla error code.
+    pub const fn error_code(&self) -> u16 {
+        match self {
+            Self::MaxLocations(_) => codes::EXCEEDED_MAX_LOCATIONS,
+            Self::MaxTimeContour(_) => codes::EXCEEDED_MAX_TIME,
+            Self::MaxContours(_) => codes::EXCEEDED_MAX_CONTOURS,
+            Self::MaxDistance(_) => codes::EXCEEDED_MAX_PATH_DISTANCE,
+            Self::MaxExcludeLocations(_) => codes::EXCEEDED_MAX_AVOID_LOCATIONS,
+            Self::MaxDistanceContour(_) => codes::EXCEEDED_MAX_DISTANCE,
+        }
+    }
+}
+
+impl From<ServiceLimitError> for WorkerResult {
+    fn from(value: ServiceLimitError) -> Self {
+        valhalla_error::error_response(
+            value.error_code(),
+            &value.to_string(),
+            StatusCode::BAD_REQUEST,
+        )
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ServiceLimitsConfigError {
+    #[error("Unable to read the config file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid service limits config: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+impl ServiceLimits {
+    /// Loads the service limits from a Valhalla config file (`valhalla.json`).
+    ///
+    /// If the file has no `service_limits` section, the defaults are used.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be read, or the limits are malformed.
+    pub fn from_config_file<P: AsRef<Path>>(path: P) -> Result<Self, ServiceLimitsConfigError> {
+        let mut config: serde_json::Value = serde_json::from_slice(&std::fs::read(path)?)?;
+        match config.get_mut("service_limits") {
+            Some(limits) => Ok(serde_json::from_value(limits.take())?),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Gets the limits for the given costing.
+    ///
+    /// Costings without limits of their own (e.g. transit) use the multimodal limits.
+    pub fn for_costing(&self, costing: CostingType) -> &CostingLimits {
+        match costing {
+            CostingType::Auto | CostingType::None => &self.auto_,
+            CostingType::Bicycle => &self.bicycle,
+            CostingType::Bikeshare => &self.bikeshare,
+            CostingType::Bus => &self.bus,
+            CostingType::LowSpeedVehicle => &self.low_speed_vehicle,
+            CostingType::MotorScooter => &self.motor_scooter,
+            CostingType::Motorcycle => &self.motorcycle,
+            CostingType::Multimodal | CostingType::Transit => &self.multimodal,
+            CostingType::Pedestrian => &self.pedestrian,
+            CostingType::Taxi => &self.taxi,
+            CostingType::Truck => &self.truck,
+        }
+    }
+
+    /// Checks a request against the limits.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first limit which the request exceeds.
+    pub fn check(&self, options: &Options) -> Result<(), ServiceLimitError> {
+        if options.exclude_locations.len() > self.max_exclude_locations {
+            return Err(ServiceLimitError::MaxExcludeLocations(
+                self.max_exclude_locations,
+            ));
+        }
+
+        let limits =
+            self.for_costing(CostingType::try_from(options.costing_type).unwrap_or_default());
+        match Action::try_from(options.action) {
+            Ok(Action::Route | Action::OptimizedRoute) => {
+                if options.locations.len() > limits.max_locations {
+                    return Err(ServiceLimitError::MaxLocations(limits.max_locations));
+                }
+
+                let path_distance: f64 = options
+                    .locations
+                    .windows(2)
+                    .map(|pair| distance(&pair[0], &pair[1]))
+                    .sum();
+                if path_distance > limits.max_distance {
+                    return Err(ServiceLimitError::MaxDistance(limits.max_distance));
+                }
+            }
+            Ok(Action::SourcesToTargets) => {
+                let pairs = options.sources.len() * options.targets.len();
+                if pairs > limits.max_matrix_location_pairs {
+                    return Err(ServiceLimitError::MaxLocations(
+                        limits.max_matrix_location_pairs,
+                    ));
+                }
+
+                let exceeds_distance = options.sources.iter().any(|source| {
+                    options
+                        .targets
+                        .iter()
+                        .any(|target| distance(source, target) > limits.max_matrix_distance)
+                });
+                if exceeds_distance {
+                    return Err(ServiceLimitError::MaxDistance(limits.max_matrix_distance));
+                }
+            }
+            Ok(Action::Isochrone) => {
+                let limits = &self.isochrone;
+                if options.locations.len() > limits.max_locations {
+                    return Err(ServiceLimitError::MaxLocations(limits.max_locations));
+                }
+
+                if options.contours.len() > limits.max_contours {
+                    return Err(ServiceLimitError::MaxContours(limits.max_contours));
+                }
+
+                for contour in &options.contours {
+                    if let Some(contour::HasTime::Time(time)) = contour.has_time
+                        && time > limits.max_time_contour
+                    {
+                        return Err(ServiceLimitError::MaxTimeContour(limits.max_time_contour));
+                    }
+
+                    if let Some(contour::HasDistance::Distance(distance)) = contour.has_distance
+                        && distance > limits.max_distance_contour
+                    {
+                        return Err(ServiceLimitError::MaxDistanceContour(
+                            limits.max_distance_contour,
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// The great-circle distance between two locations, in meters.
+///
+/// Locations without coordinates are treated as being zero distance from everything.
+fn distance(a: &Location, b: &Location) -> f64 {
+    fn lat_lng(ll: Option<&LatLng>) -> Option<(f64, f64)> {
+        let ll = ll?;
+        let Some(lat_lng::HasLat::Lat(lat)) = ll.has_lat else {
+            return None;
+        };
+        let Some(lat_lng::HasLng::Lng(lng)) = ll.has_lng else {
+            return None;
+        };
+        Some((lat.to_radians(), lng.to_radians()))
+    }
+
+    let (Some((lat1, lng1)), Some((lat2, lng2))) = (lat_lng(a.ll.as_ref()), lat_lng(b.ll.as_ref()))
+    else {
+        return 0.0;
+    };
+
+    let h = ((lat2 - lat1) / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * ((lng2 - lng1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * h.sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use valhalla_proto::Contour;
+
+    fn location(lat: f64, lng: f64) -> Location {
+        Location {
+            ll: Some(LatLng {
+                has_lat: Some(lat_lng::HasLat::Lat(lat)),
+                has_lng: Some(lat_lng::HasLng::Lng(lng)),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn deserialize_partial_config() {
+        let limits: ServiceLimits = serde_json::from_value(json!({
+            "auto": {
+                "max_distance": 1000.0,
+                "max_locations": 2,
+                "max_matrix_distance": 1000.0,
+                "max_matrix_location_pairs": 4
+            },
+            "isochrone": { "max_contours": 2 },
+            "max_exclude_locations": 3,
+            "trace": { "max_shape": 16000 }
+        }))
+        .unwrap();
+
+        assert_eq!(limits.auto_, CostingLimits::new(1000.0, 2, 1000.0, 4));
+        assert_eq!(limits.isochrone.max_contours, 2);
+        assert_eq!(limits.isochrone.max_locations, 1);
+        assert_eq!(limits.max_exclude_locations, 3);
+        assert_eq!(limits.truck, ServiceLimits::default().truck);
+    }
+
+    #[test]
+    fn deserialize_partial_costing() {
+        let limits: ServiceLimits = serde_json::from_value(json!({
+            "pedestrian": { "max_distance": 100_000.0 },
+            "truck": { "max_locations": 5 }
+        }))
+        .unwrap();
+
+        // Missing keys fall back to the costing's own defaults
+        assert_eq!(
+            limits.pedestrian,
+            CostingLimits {
+                max_distance: 100_000.0,
+                ..CostingLimits::pedestrian()
+            }
+        );
+        assert_eq!(
+            limits.truck,
+            CostingLimits {
+                max_locations: 5,
+                ..CostingLimits::motor_vehicle()
+            }
+        );
+
+        let limits: CostingLimits =
+            serde_json::from_value(json!({ "max_matrix_location_pairs": 100 })).unwrap();
+        assert_eq!(
+            limits,
+            CostingLimits {
+                max_matrix_location_pairs: 100,
+                ..CostingLimits::default()
+            }
+        );
+    }
+
+    #[test]
+    fn route_limits() {
+        let limits = ServiceLimits::default();
+        let mut options = Options {
+            action: Action::Route.into(),
+            costing_type: CostingType::Pedestrian.into(),
+            // Roughly 111km apart
+            locations: vec![location(0.0, 0.0), location(1.0, 0.0)],
+            ..Default::default()
+        };
+        assert_eq!(limits.check(&options), Ok(()));
+
+        // Several hundred km is too far to walk
+        options.locations.push(location(3.0, 0.0));
+        let err = limits.check(&options).unwrap_err();
+        assert_eq!(err, ServiceLimitError::MaxDistance(250_000.0));
+        assert_eq!(err.error_code(), 154);
+
+        // But not too far to drive
+        options.costing_type = CostingType::Auto.into();
+        assert_eq!(limits.check(&options), Ok(()));
+
+        options.locations = vec![location(0.0, 0.0); 21];
+        assert_eq!(
+            limits.check(&options),
+            Err(ServiceLimitError::MaxLocations(20))
+        );
+    }
+
+    #[test]
+    fn matrix_limits() {
+        let limits = ServiceLimits::default();
+        let options = Options {
+            action: Action::SourcesToTargets.into(),
+            costing_type: CostingType::Auto.into(),
+            sources: vec![location(0.0, 0.0); 51],
+            targets: vec![location(0.0, 0.0); 50],
+            ..Default::default()
+        };
+        assert_eq!(
+            limits.check(&options),
+            Err(ServiceLimitError::MaxLocations(2500))
+        );
+    }
+
+    #[test]
+    fn isochrone_limits() {
+        let limits = ServiceLimits::default();
+        let contour = |time| Contour {
+            has_time: Some(contour::HasTime::Time(time)),
+            ..Default::default()
+        };
+        let mut options = Options {
+            action: Action::Isochrone.into(),
+            locations: vec![location(0.0, 0.0)],
+            contours: vec![contour(10.0), contour(20.0)],
+            ..Default::default()
+        };
+        assert_eq!(limits.check(&options), Ok(()));
+
+        options.contours.push(contour(180.0));
+        assert_eq!(
+            limits.check(&options),
+            Err(ServiceLimitError::MaxTimeContour(120.0))
+        );
+
+        options.contours = vec![contour(10.0); 5];
+        assert_eq!(
+            limits.check(&options),
+            Err(ServiceLimitError::MaxContours(4))
+        );
+    }
+
+    #[test]
+    fn error_response() {
+        let WorkerResult::HttpResponse {
+            status_code, body, ..
+        } = ServiceLimitError::MaxLocations(20).into()
+        else {
+            panic!("Expected an HTTP response");
+        };
+
+        assert_eq!(status_code, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            json!({
+                "error_code": 150,
+                "error": "Exceeded max locations: 20",
+                "status_code": 400,
+                "status": "Bad Request"
+            })
+        );
+    }
+}
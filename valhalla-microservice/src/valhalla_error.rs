@@ -0,0 +1,115 @@
+//! Valhalla-compatible error responses.
+//!
+//! The error bodies and codes are shared with other crates through
+//! [`valhalla_response::error`], so every service emits identical error JSON.
+
+use crate::WorkerResult;
+use http::StatusCode;
+use std::fmt::Display;
+use valhalla_response::ValhallaError;
+use valhalla_response::error::codes;
+
+/// The response to a request which exceeded the max processing time.
+pub(crate) fn timeout_response() -> WorkerResult {
+    error_response(
+        codes::SERVER_ERROR,
+        "Exceeded the max processing time",
+        StatusCode::GATEWAY_TIMEOUT,
+    )
+}
+
+/// The response to a request which can't be queued, because too many are waiting already.
+#[cfg(feature = "http-server")]
+pub(crate) fn overloaded_response() -> WorkerResult {
+    error_response(
+        codes::SERVER_ERROR,
+        "Too many requests are waiting",
+        StatusCode::SERVICE_UNAVAILABLE,
+    )
+}
+
+/// Builds a Valhalla error response.
+pub(crate) fn error_response(code: u16, message: &str, status_code: StatusCode) -> WorkerResult {
+    WorkerResult::from(ValhallaError::new(code, message, status_code))
+}
+
+impl From<ValhallaError> for WorkerResult {
+    fn from(error: ValhallaError) -> Self {
+        WorkerResult::json(error.http_status(), error)
+    }
+}
+
+impl WorkerResult {
+    /// Helper for constructing a Valhalla error response,
+    /// with the standard message and HTTP status for the error code.
+    ///
+    /// The response is identical to Valhalla's, so clients can handle errors the same way:
+    ///
+    /// ```json
+    /// {"error_code":171,"error":"No suitable edges near location","status_code":400,"status":"Bad Request"}
+    /// ```
+    pub fn valhalla_error(code: u16) -> WorkerResult {
+        WorkerResult::from(ValhallaError::from_code(code))
+    }
+
+    /// Like [`WorkerResult::valhalla_error`], with some detail appended to the message
+    /// (ex: the limit which was exceeded), as Valhalla does.
+    pub fn valhalla_error_with_detail(code: u16, detail: impl Display) -> WorkerResult {
+        WorkerResult::from(ValhallaError::with_detail(code, detail))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::timeout_response;
+    use crate::WorkerResult;
+    use http::StatusCode;
+    use serde_json::json;
+
+    fn response(result: WorkerResult) -> (StatusCode, serde_json::Value) {
+        let WorkerResult::HttpResponse {
+            status_code, body, ..
+        } = result
+        else {
+            panic!("Expected an HTTP response");
+        };
+        (status_code, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[test]
+    fn valhalla_error() {
+        assert_eq!(
+            response(WorkerResult::valhalla_error(171)),
+            (
+                StatusCode::BAD_REQUEST,
+                json!({
+                    "error_code": 171,
+                    "error": "No suitable edges near location",
+                    "status_code": 400,
+                    "status": "Bad Request"
+                })
+            )
+        );
+        assert_eq!(
+            response(WorkerResult::valhalla_error_with_detail(
+                106,
+                "route, status"
+            ))
+            .1["error"],
+            "Try any of:route, status"
+        );
+
+        let (status_code, body) = response(WorkerResult::valhalla_error(107));
+        assert_eq!(status_code, StatusCode::NOT_IMPLEMENTED);
+        assert_eq!(body["status"], "Not Implemented");
+
+        let (status_code, body) = response(timeout_response());
+        assert_eq!(status_code, StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(body["status"], "Gateway Timeout");
+
+        // Unknown codes are kept, but treated as internal errors
+        let (status_code, body) = response(WorkerResult::valhalla_error(9999));
+        assert_eq!(status_code, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["error_code"], 9999);
+    }
+}
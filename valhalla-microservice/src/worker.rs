@@ -0,0 +1,84 @@
+//! Worker functions, which handle each request.
+//!
+//! Workers may be plain functions, or async ones
+//! (ex: to await tile fetches or database calls without blocking the runtime).
+
+use crate::{CancellationToken, WorkerResult};
+use valhalla_proto::Api;
+
+/// Handles the requests to a service.
+///
+/// This is implemented by [`SyncWorkerFn`] and [`AsyncWorkerFn`],
+/// which wrap the worker functions passed to the service builder.
+pub trait Worker {
+    fn work(
+        &self,
+        request: Api,
+        cancellation: CancellationToken,
+    ) -> impl Future<Output = WorkerResult>;
+}
+
+/// A worker function which returns its result directly
+/// (see [`ValhallaMicroserviceBuilder::build`](crate::ValhallaMicroserviceBuilder::build)).
+pub struct SyncWorkerFn<F>(pub(crate) F);
+
+impl<F: Fn(Api, CancellationToken) -> WorkerResult> Worker for SyncWorkerFn<F> {
+    async fn work(&self, request: Api, cancellation: CancellationToken) -> WorkerResult {
+        (self.0)(request, cancellation)
+    }
+}
+
+/// A worker function which returns a future of its result
+/// (see [`ValhallaMicroserviceBuilder::build_async`](crate::ValhallaMicroserviceBuilder::build_async)).
+pub struct AsyncWorkerFn<F>(pub(crate) F);
+
+impl<F, Fut> Worker for AsyncWorkerFn<F>
+where
+    F: Fn(Api, CancellationToken) -> Fut,
+    Fut: Future<Output = WorkerResult>,
+{
+    fn work(
+        &self,
+        request: Api,
+        cancellation: CancellationToken,
+    ) -> impl Future<Output = WorkerResult> {
+        (self.0)(request, cancellation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AsyncWorkerFn, SyncWorkerFn, Worker};
+    use crate::{CancellationToken, WorkerResult};
+    use http::StatusCode;
+    use valhalla_proto::Api;
+
+    fn status_code(result: &WorkerResult) -> Option<StatusCode> {
+        match result {
+            WorkerResult::HttpResponse { status_code, .. } => Some(*status_code),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_workers() {
+        let sync_worker = SyncWorkerFn(|_, _| WorkerResult::json(StatusCode::OK, "sync"));
+        let result = sync_worker
+            .work(Api::default(), CancellationToken::default())
+            .await;
+        assert_eq!(status_code(&result), Some(StatusCode::OK));
+
+        let async_worker = AsyncWorkerFn(|_, cancellation: CancellationToken| async move {
+            tokio::task::yield_now().await;
+            if cancellation.is_cancelled() {
+                WorkerResult::json(StatusCode::SERVICE_UNAVAILABLE, "cancelled")
+            } else {
+                WorkerResult::json(StatusCode::ACCEPTED, "async")
+            }
+        });
+        let result = async_worker
+            .work(Api::default(), CancellationToken::default())
+            .await;
+        assert_eq!(status_code(&result), Some(StatusCode::ACCEPTED));
+    }
+}
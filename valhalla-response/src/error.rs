@@ -0,0 +1,466 @@
+//! Valhalla error responses.
+//!
+//! Valhalla errors are JSON objects with a numeric `error_code` (from a fixed table),
+//! a human-readable `error`, and the HTTP `status_code` and `status`:
+//!
+//! ```json
+//! {"error_code":171,"error":"No suitable edges near location","status_code":400,"status":"Bad Request"}
+//! ```
+//!
+//! Clients often switch on the error code, so Rust services should use the same codes.
+
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Valhalla's error codes (from `valhalla/exceptions.h`).
+///
+/// 1xx codes are request errors (usually caught before routing),
+/// and 4xx codes are routing errors.
+pub mod codes {
+    /// Errors which the server handles itself, outside the request handlers
+    /// (ex: timeouts and overload).
+    ///
+    /// Valhalla has no codes for these (`prime_server` sends them without a JSON body),
+    /// so this is deliberately outside the table.
+    pub const SERVER_ERROR: u16 = 0;
+    /// Failed to parse json request.
+    pub const FAILED_TO_PARSE_JSON: u16 = 100;
+    /// Try a POST or GET request instead.
+    pub const UNSUPPORTED_METHOD: u16 = 101;
+    /// The action is unknown (the message is followed by the supported actions).
+    pub const UNKNOWN_ACTION: u16 = 106;
+    /// The action isn't implemented.
+    pub const NOT_IMPLEMENTED: u16 = 107;
+    /// Insufficiently specified required parameter `locations`.
+    pub const MISSING_LOCATIONS: u16 = 110;
+    /// Insufficiently specified required parameter `time`.
+    pub const MISSING_TIME: u16 = 111;
+    /// Insufficiently specified required parameter `locations` or `sources & targets`.
+    pub const MISSING_SOURCES_AND_TARGETS: u16 = 112;
+    /// Insufficiently specified required parameter `contours`.
+    pub const MISSING_CONTOURS: u16 = 113;
+    /// Insufficiently specified required parameter `shape` or `encoded_polyline`.
+    pub const MISSING_SHAPE: u16 = 114;
+    /// Insufficient number of locations provided.
+    pub const TOO_FEW_LOCATIONS: u16 = 120;
+    /// Insufficient number of sources provided.
+    pub const TOO_FEW_SOURCES: u16 = 121;
+    /// Insufficient number of targets provided.
+    pub const TOO_FEW_TARGETS: u16 = 122;
+    /// Insufficient shape provided.
+    pub const INSUFFICIENT_SHAPE: u16 = 123;
+    /// No edge/node costing provided.
+    pub const NO_EDGE_OR_NODE_COSTING: u16 = 124;
+    /// No costing method found.
+    pub const NO_COSTING: u16 = 125;
+    /// No shape provided.
+    pub const NO_SHAPE: u16 = 126;
+    /// Failed to parse location.
+    pub const INVALID_LOCATION: u16 = 130;
+    /// Failed to parse source.
+    pub const INVALID_SOURCE: u16 = 131;
+    /// Failed to parse target.
+    pub const INVALID_TARGET: u16 = 132;
+    /// Failed to parse avoid.
+    pub const INVALID_AVOID: u16 = 133;
+    /// Failed to parse shape.
+    pub const INVALID_SHAPE: u16 = 134;
+    /// Failed to parse trace.
+    pub const INVALID_TRACE: u16 = 135;
+    /// Action does not support multimodal costing.
+    pub const MULTIMODAL_NOT_SUPPORTED: u16 = 140;
+    /// Arrive by for multimodal not implemented yet.
+    pub const MULTIMODAL_ARRIVE_BY_NOT_IMPLEMENTED: u16 = 141;
+    /// Arrive by not implemented for isochrones.
+    pub const ISOCHRONE_ARRIVE_BY_NOT_IMPLEMENTED: u16 = 142;
+    /// Exceeded max locations.
+    pub const EXCEEDED_MAX_LOCATIONS: u16 = 150;
+    /// Exceeded max time.
+    pub const EXCEEDED_MAX_TIME: u16 = 151;
+    /// Exceeded max contours.
+    pub const EXCEEDED_MAX_CONTOURS: u16 = 152;
+    /// Too many shape points.
+    pub const EXCEEDED_MAX_SHAPE: u16 = 153;
+    /// Path distance exceeds the max distance limit.
+    pub const EXCEEDED_MAX_PATH_DISTANCE: u16 = 154;
+    /// Exceeded max avoid locations.
+    pub const EXCEEDED_MAX_AVOID_LOCATIONS: u16 = 157;
+    /// Input trace option is out of bounds.
+    pub const TRACE_OPTION_OUT_OF_BOUNDS: u16 = 158;
+    /// Date and time required for origin for `date_type` of depart at.
+    pub const MISSING_DEPART_DATE_TIME: u16 = 160;
+    /// Date and time required for destination for `date_type` of arrive by.
+    pub const MISSING_ARRIVE_DATE_TIME: u16 = 161;
+    /// Date and time is invalid.  Format is YYYY-MM-DDTHH:MM.
+    pub const INVALID_DATE_TIME: u16 = 162;
+    /// Invalid `date_type`.
+    pub const INVALID_DATE_TYPE: u16 = 163;
+    /// Invalid shape format.
+    pub const INVALID_SHAPE_FORMAT: u16 = 164;
+    /// Exceeded max distance.
+    pub const EXCEEDED_MAX_DISTANCE: u16 = 166;
+    /// Locations are in unconnected regions. Go check/edit the map at osm.org.
+    pub const UNCONNECTED_LOCATIONS: u16 = 170;
+    /// No suitable edges near location.
+    pub const NO_SUITABLE_EDGES: u16 = 171;
+    /// Exceeded breakage distance for all pairs.
+    pub const EXCEEDED_BREAKAGE_DISTANCE: u16 = 172;
+    /// An unknown request error.
+    pub const UNKNOWN_REQUEST_ERROR: u16 = 199;
+    /// Exceeded max iterations in `CostMatrix::SourceToTarget`.
+    pub const EXCEEDED_MAX_ITERATIONS: u16 = 430;
+    /// Cannot reach destination - too far from a transit stop.
+    pub const TOO_FAR_FROM_TRANSIT: u16 = 440;
+    /// No path could be found for input.
+    pub const NO_PATH: u16 = 442;
+    /// Exact route match algorithm failed to find path.
+    pub const EXACT_ROUTE_MATCH_FAILED: u16 = 443;
+    /// Map Match algorithm failed to find path.
+    pub const MAP_MATCH_FAILED: u16 = 444;
+    /// An unknown routing error.
+    pub const UNKNOWN_ROUTING_ERROR: u16 = 499;
+}
+
+/// The standard message and HTTP status for each error code, sorted by code.
+const ERROR_CODES: &[(u16, &str, StatusCode)] = &[
+    (
+        codes::FAILED_TO_PARSE_JSON,
+        "Failed to parse json request",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::UNSUPPORTED_METHOD,
+        "Try a POST or GET request instead",
+        StatusCode::METHOD_NOT_ALLOWED,
+    ),
+    (codes::UNKNOWN_ACTION, "Try any of", StatusCode::NOT_FOUND),
+    (
+        codes::NOT_IMPLEMENTED,
+        "Not Implemented",
+        StatusCode::NOT_IMPLEMENTED,
+    ),
+    (
+        codes::MISSING_LOCATIONS,
+        "Insufficiently specified required parameter 'locations'",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::MISSING_TIME,
+        "Insufficiently specified required parameter 'time'",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::MISSING_SOURCES_AND_TARGETS,
+        "Insufficiently specified required parameter 'locations' or 'sources & targets'",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::MISSING_CONTOURS,
+        "Insufficiently specified required parameter 'contours'",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::MISSING_SHAPE,
+        "Insufficiently specified required parameter 'shape' or 'encoded_polyline'",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::TOO_FEW_LOCATIONS,
+        "Insufficient number of locations provided",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::TOO_FEW_SOURCES,
+        "Insufficient number of sources provided",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::TOO_FEW_TARGETS,
+        "Insufficient number of targets provided",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::INSUFFICIENT_SHAPE,
+        "Insufficient shape provided",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::NO_EDGE_OR_NODE_COSTING,
+        "No edge/node costing provided",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::NO_COSTING,
+        "No costing method found",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::NO_SHAPE,
+        "No shape provided",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::INVALID_LOCATION,
+        "Failed to parse location",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::INVALID_SOURCE,
+        "Failed to parse source",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::INVALID_TARGET,
+        "Failed to parse target",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::INVALID_AVOID,
+        "Failed to parse avoid",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::INVALID_SHAPE,
+        "Failed to parse shape",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::INVALID_TRACE,
+        "Failed to parse trace",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::MULTIMODAL_NOT_SUPPORTED,
+        "Action does not support multimodal costing",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::MULTIMODAL_ARRIVE_BY_NOT_IMPLEMENTED,
+        "Arrive by for multimodal not implemented yet",
+        StatusCode::NOT_IMPLEMENTED,
+    ),
+    (
+        codes::ISOCHRONE_ARRIVE_BY_NOT_IMPLEMENTED,
+        "Arrive by not implemented for isochrones",
+        StatusCode::NOT_IMPLEMENTED,
+    ),
+    (
+        codes::EXCEEDED_MAX_LOCATIONS,
+        "Exceeded max locations",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::EXCEEDED_MAX_TIME,
+        "Exceeded max time",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::EXCEEDED_MAX_CONTOURS,
+        "Exceeded max contours",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::EXCEEDED_MAX_SHAPE,
+        "Too many shape points",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::EXCEEDED_MAX_PATH_DISTANCE,
+        "Path distance exceeds the max distance limit",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::EXCEEDED_MAX_AVOID_LOCATIONS,
+        "Exceeded max avoid locations",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::TRACE_OPTION_OUT_OF_BOUNDS,
+        "Input trace option is out of bounds",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::MISSING_DEPART_DATE_TIME,
+        "Date and time required for origin for date_type of depart at",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::MISSING_ARRIVE_DATE_TIME,
+        "Date and time required for destination for date_type of arrive by",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::INVALID_DATE_TIME,
+        "Date and time is invalid.  Format is YYYY-MM-DDTHH:MM",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::INVALID_DATE_TYPE,
+        "Invalid date_type",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::INVALID_SHAPE_FORMAT,
+        "Invalid shape format",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::EXCEEDED_MAX_DISTANCE,
+        "Exceeded max distance",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::UNCONNECTED_LOCATIONS,
+        "Locations are in unconnected regions. Go check/edit the map at osm.org",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::NO_SUITABLE_EDGES,
+        "No suitable edges near location",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::EXCEEDED_BREAKAGE_DISTANCE,
+        "Exceeded breakage distance for all pairs",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::UNKNOWN_REQUEST_ERROR,
+        "Unknown",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::EXCEEDED_MAX_ITERATIONS,
+        "Exceeded max iterations in CostMatrix::SourceToTarget",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::TOO_FAR_FROM_TRANSIT,
+        "Cannot reach destination - too far from a transit stop",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::NO_PATH,
+        "No path could be found for input",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::EXACT_ROUTE_MATCH_FAILED,
+        "Exact route match algorithm failed to find path",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::MAP_MATCH_FAILED,
+        "Map Match algorithm failed to find path",
+        StatusCode::BAD_REQUEST,
+    ),
+    (
+        codes::UNKNOWN_ROUTING_ERROR,
+        "Unknown",
+        StatusCode::BAD_REQUEST,
+    ),
+];
+
+/// A Valhalla error response body.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ValhallaError {
+    /// The Valhalla error code (see [`codes`]).
+    pub error_code: u16,
+    /// A human-readable description of the error.
+    pub error: String,
+    /// The HTTP status code.
+    pub status_code: u16,
+    /// The HTTP status text (ex: `Bad Request`).
+    pub status: String,
+}
+
+impl ValhallaError {
+    /// Creates an error with any message and HTTP status.
+    ///
+    /// Prefer [`ValhallaError::from_code`] for codes in the table,
+    /// so that the message matches Valhalla's.
+    pub fn new(error_code: u16, error: impl Into<String>, status_code: StatusCode) -> Self {
+        Self {
+            error_code,
+            error: error.into(),
+            status_code: status_code.as_u16(),
+            status: status_code
+                .canonical_reason()
+                .unwrap_or_default()
+                .to_string(),
+        }
+    }
+
+    /// Creates an error with the standard message and HTTP status for the error code.
+    ///
+    /// Codes which aren't in the table are reported as unknown internal errors.
+    pub fn from_code(error_code: u16) -> Self {
+        let (message, status_code) = lookup(error_code);
+        Self::new(error_code, message, status_code)
+    }
+
+    /// Like [`ValhallaError::from_code`], with some detail appended to the message
+    /// (ex: the limit which was exceeded), as Valhalla does.
+    pub fn with_detail(error_code: u16, detail: impl Display) -> Self {
+        let (message, status_code) = lookup(error_code);
+        Self::new(error_code, format!("{message}:{detail}"), status_code)
+    }
+
+    /// The HTTP status code.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the status code isn't a valid HTTP status (only possible for deserialized errors).
+    pub fn http_status(&self) -> StatusCode {
+        StatusCode::from_u16(self.status_code).expect("Invalid HTTP status code")
+    }
+}
+
+/// The standard message and HTTP status for an error code.
+fn lookup(error_code: u16) -> (&'static str, StatusCode) {
+    ERROR_CODES
+        .binary_search_by_key(&error_code, |(code, _, _)| *code)
+        .map_or(("Unknown", StatusCode::INTERNAL_SERVER_ERROR), |index| {
+            let (_, message, status_code) = ERROR_CODES[index];
+            (message, status_code)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ERROR_CODES, ValhallaError, codes};
+    use crate::testing::to_json_and_back;
+    use http::StatusCode;
+
+    #[test]
+    fn table_is_sorted() {
+        assert!(ERROR_CODES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn body_comes_from_the_code_table() {
+        let error = ValhallaError::from_code(codes::NO_SUITABLE_EDGES);
+        let (json, parsed) = to_json_and_back(&error);
+        assert_eq!(
+            json,
+            r#"{"error_code":171,"error":"No suitable edges near location","status_code":400,"status":"Bad Request"}"#
+        );
+        assert_eq!(parsed, error);
+        assert_eq!(
+            ValhallaError::with_detail(codes::UNKNOWN_ACTION, "route, status").error,
+            "Try any of:route, status"
+        );
+
+        let error = ValhallaError::from_code(codes::NOT_IMPLEMENTED);
+        assert_eq!(error.http_status(), StatusCode::NOT_IMPLEMENTED);
+        assert_eq!(error.status, "Not Implemented");
+    }
+
+    #[test]
+    fn unknown_codes_are_internal_errors() {
+        let error = ValhallaError::from_code(9999);
+        assert_eq!(error.error_code, 9999);
+        assert_eq!(error.http_status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,110 @@
+//! Expansion responses (for the `expansion` action).
+//!
+//! For debugging, Valhalla can return every edge which a search touched, in order,
+//! as a GeoJSON feature collection with one line string per edge.
+//! The search algorithm is named in the collection's properties,
+//! and each feature only has the properties which the request's `expansion_properties` ask for.
+
+use crate::geojson::{CollectionProperties, Feature, FeatureCollection, Geometry, Position};
+use serde::{Deserialize, Serialize};
+
+/// A Valhalla expansion response.
+pub type ExpansionResponse = FeatureCollection<ExpansionProperties>;
+
+/// What the search had done with an edge when it was recorded.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeStatus {
+    /// The edge was reached (and added to the queue).
+    #[serde(rename = "r")]
+    Reached,
+    /// The edge was settled (its best cost is known).
+    #[serde(rename = "s")]
+    Settled,
+    /// The edge connects the forward and reverse searches.
+    #[serde(rename = "c")]
+    Connected,
+}
+
+/// The direction of the search which recorded an edge.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExpansionType {
+    /// Searching forward from the origin.
+    Forward,
+    /// Searching in reverse from the destination.
+    Reverse,
+}
+
+/// The properties of an expanded edge.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExpansionProperties {
+    /// The edge's ID in the graph (as a 64-bit graph ID value).
+    pub edge_id: Option<u64>,
+    /// The edge which the search reached this one from, if any.
+    pub pred_edge_id: Option<u64>,
+    pub status: Option<EdgeStatus>,
+    /// The time from the origin (or to the destination), in whole seconds.
+    pub duration: Option<u32>,
+    /// The distance from the origin (or to the destination), in whole meters.
+    pub distance: Option<u32>,
+    /// The cost from the origin (or to the destination).
+    pub cost: Option<u32>,
+    pub expansion_type: Option<ExpansionType>,
+}
+
+impl ExpansionResponse {
+    /// Creates an expansion response, with the name of the search algorithm.
+    pub fn with_algorithm(
+        algorithm: impl Into<String>,
+        features: Vec<Feature<ExpansionProperties>>,
+    ) -> Self {
+        Self {
+            properties: Some(CollectionProperties {
+                algorithm: Some(algorithm.into()),
+            }),
+            ..Self::new(features)
+        }
+    }
+}
+
+/// Creates the feature for an expanded edge, from its shape (in the direction of travel).
+pub fn edge_feature(
+    shape: Vec<Position>,
+    properties: ExpansionProperties,
+) -> Feature<ExpansionProperties> {
+    Feature {
+        geometry: Geometry::LineString(shape),
+        properties,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EdgeStatus, ExpansionProperties, ExpansionResponse, ExpansionType, edge_feature};
+    use crate::geojson::Position;
+    use crate::testing::to_json_and_back;
+
+    #[test]
+    fn only_requested_properties_are_written() {
+        let response = ExpansionResponse::with_algorithm(
+            "bidirectional_a*",
+            vec![edge_feature(
+                vec![Position::new(24.75, 59.43), Position::new(24.76, 59.44)],
+                ExpansionProperties {
+                    edge_id: Some(1_234),
+                    status: Some(EdgeStatus::Settled),
+                    duration: Some(12),
+                    expansion_type: Some(ExpansionType::Reverse),
+                    ..ExpansionProperties::default()
+                },
+            )],
+        );
+        let (json, parsed) = to_json_and_back(&response);
+        assert_eq!(
+            json,
+            r#"{"type":"FeatureCollection","properties":{"algorithm":"bidirectional_a*"},"features":[{"type":"Feature","geometry":{"type":"LineString","coordinates":[[24.75,59.43],[24.76,59.44]]},"properties":{"edge_id":1234,"status":"s","duration":12,"expansion_type":"reverse"}}]}"#
+        );
+        assert_eq!(parsed, response);
+    }
+}
@@ -0,0 +1,132 @@
+//! The subset of GeoJSON which Valhalla writes (for isochrones and expansions).
+//!
+//! Properties are generic, since each action has its own.
+
+use crate::{Warning, precision};
+use geo::{Coord, coord};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Serialize, Serializer};
+
+/// A position, written as `[lon, lat]` with 6 decimal places.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(from = "[f64; 2]")]
+pub struct Position {
+    pub lon: f64,
+    pub lat: f64,
+}
+
+impl Position {
+    pub const fn new(lon: f64, lat: f64) -> Self {
+        Self { lon, lat }
+    }
+}
+
+impl From<[f64; 2]> for Position {
+    fn from([lon, lat]: [f64; 2]) -> Self {
+        Self { lon, lat }
+    }
+}
+
+impl From<Coord<f64>> for Position {
+    fn from(coord: Coord<f64>) -> Self {
+        Self::new(coord.x, coord.y)
+    }
+}
+
+impl From<Position> for Coord<f64> {
+    fn from(position: Position) -> Self {
+        coord! { x: position.lon, y: position.lat }
+    }
+}
+
+impl Serialize for Position {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&precision::round(self.lon, 6))?;
+        tuple.serialize_element(&precision::round(self.lat, 6))?;
+        tuple.end()
+    }
+}
+
+/// A GeoJSON geometry.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", content = "coordinates")]
+pub enum Geometry {
+    Point(Position),
+    MultiPoint(Vec<Position>),
+    LineString(Vec<Position>),
+    MultiLineString(Vec<Vec<Position>>),
+    /// A polygon, as its outer ring followed by any holes.
+    Polygon(Vec<Vec<Position>>),
+    MultiPolygon(Vec<Vec<Vec<Position>>>),
+}
+
+/// A GeoJSON feature.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename = "Feature")]
+pub struct Feature<P> {
+    pub geometry: Geometry,
+    pub properties: P,
+}
+
+/// A GeoJSON feature collection, with Valhalla's extra members.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename = "FeatureCollection")]
+pub struct FeatureCollection<P> {
+    /// Properties of the whole collection (only used by some actions).
+    pub properties: Option<CollectionProperties>,
+    pub features: Vec<Feature<P>>,
+    /// The request ID, if the request had one.
+    pub id: Option<String>,
+    /// Any warnings raised while processing the request.
+    pub warnings: Option<Vec<Warning>>,
+}
+
+/// The properties of a feature collection.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct CollectionProperties {
+    /// The search algorithm which produced the features (ex: `bidirectional_a*`).
+    pub algorithm: Option<String>,
+}
+
+impl<P> FeatureCollection<P> {
+    pub fn new(features: Vec<Feature<P>>) -> Self {
+        Self {
+            properties: None,
+            features,
+            id: None,
+            warnings: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Feature, FeatureCollection, Geometry, Position};
+    use serde_json::json;
+
+    #[test]
+    fn serialize_feature_collection() {
+        let collection = FeatureCollection {
+            properties: None,
+            features: vec![Feature {
+                geometry: Geometry::LineString(vec![
+                    Position::new(24.753_575_4, 59.436_961),
+                    Position::new(24.76, 59.44),
+                ]),
+                properties: json!({"name": "a"}),
+            }],
+            id: Some("lines".to_string()),
+            warnings: None,
+        };
+        let json = serde_json::to_string(&collection).unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"FeatureCollection","features":[{"type":"Feature","geometry":{"type":"LineString","coordinates":[[24.753575,59.436961],[24.76,59.44]]},"properties":{"name":"a"}}],"id":"lines"}"#
+        );
+        let parsed: FeatureCollection<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+    }
+}
@@ -0,0 +1,193 @@
+//! Isochrone responses (for the `isochrone` action).
+//!
+//! Valhalla answers with a GeoJSON feature collection:
+//! one feature per contour, from the largest to the smallest,
+//! optionally followed by the input and snapped locations (with `show_locations`).
+//! Contours are polygons when the request sets `polygons`, and lines otherwise.
+
+use crate::geojson::{Feature, FeatureCollection, Geometry, Position};
+use serde::{Deserialize, Serialize};
+
+/// A Valhalla isochrone response.
+pub type IsochroneResponse = FeatureCollection<IsochroneProperties>;
+
+/// The properties of a feature in an isochrone response.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum IsochroneProperties {
+    Contour(ContourProperties),
+    Location(LocationProperties),
+}
+
+/// What a contour measures.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContourMetric {
+    /// An isochrone, in minutes.
+    Time,
+    /// An isodistance, in kilometers.
+    Distance,
+}
+
+/// The properties of a contour.
+///
+/// The color and opacity are repeated under the names used by the common map styling specs
+/// (Leaflet and simplestyle), as in Valhalla.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ContourProperties {
+    #[serde(rename = "fill-opacity")]
+    pub fill_opacity_simplestyle: f64,
+    #[serde(rename = "fillColor")]
+    pub fill_color_leaflet: String,
+    pub opacity: f64,
+    pub fill: String,
+    #[serde(rename = "fillOpacity")]
+    pub fill_opacity_leaflet: f64,
+    pub color: String,
+    /// The contour value (in minutes or kilometers, depending on the metric).
+    pub contour: f64,
+    pub metric: ContourMetric,
+}
+
+impl ContourProperties {
+    /// The opacity which Valhalla gives every contour.
+    pub const OPACITY: f64 = 0.33;
+
+    /// Creates the properties of a contour.
+    ///
+    /// The color is a hex RGB string (ex: `#ff0000`), like the `color` of a request contour
+    /// (which Valhalla sends without the `#`).
+    pub fn new(metric: ContourMetric, contour: f64, color: impl Into<String>) -> Self {
+        let color = color.into();
+        Self {
+            fill_opacity_simplestyle: Self::OPACITY,
+            fill_color_leaflet: color.clone(),
+            opacity: Self::OPACITY,
+            fill: color.clone(),
+            fill_opacity_leaflet: Self::OPACITY,
+            color,
+            contour,
+            metric,
+        }
+    }
+}
+
+/// Whether a location feature is where the location was requested, or where it was snapped to.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LocationKind {
+    Input,
+    Snapped,
+}
+
+/// The properties of a location (included when the request sets `show_locations`).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocationProperties {
+    #[serde(rename = "type")]
+    pub kind: LocationKind,
+    /// The index of the location in the request.
+    pub location_index: u32,
+}
+
+/// Creates the feature for a contour.
+///
+/// For polygons, each polygon is a list of rings (the outer ring first, then any holes),
+/// and a single polygon is written as a `Polygon` rather than a `MultiPolygon`.
+/// For lines, only the outer rings are kept (as a `LineString` or `MultiLineString`).
+pub fn contour_feature(
+    properties: ContourProperties,
+    mut polygons: Vec<Vec<Vec<Position>>>,
+    as_polygons: bool,
+) -> Feature<IsochroneProperties> {
+    let geometry = match (as_polygons, polygons.len()) {
+        (true, 1) => Geometry::Polygon(polygons.remove(0)),
+        (true, _) => Geometry::MultiPolygon(polygons),
+        (false, _) => {
+            let mut lines: Vec<_> = polygons
+                .into_iter()
+                .filter_map(|rings| rings.into_iter().next())
+                .collect();
+            if lines.len() == 1 {
+                Geometry::LineString(lines.remove(0))
+            } else {
+                Geometry::MultiLineString(lines)
+            }
+        }
+    };
+    Feature {
+        geometry,
+        properties: IsochroneProperties::Contour(properties),
+    }
+}
+
+/// Creates the feature for a location, where the request had it or where it was snapped to.
+pub fn location_feature(
+    kind: LocationKind,
+    location_index: u32,
+    positions: Vec<Position>,
+) -> Feature<IsochroneProperties> {
+    Feature {
+        geometry: Geometry::MultiPoint(positions),
+        properties: IsochroneProperties::Location(LocationProperties {
+            kind,
+            location_index,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ContourMetric, ContourProperties, IsochroneProperties, IsochroneResponse, LocationKind,
+        contour_feature, location_feature,
+    };
+    use crate::geojson::{Geometry, Position};
+    use crate::testing::to_json_and_back;
+
+    fn ring() -> Vec<Position> {
+        vec![
+            Position::new(24.75, 59.43),
+            Position::new(24.76, 59.43),
+            Position::new(24.76, 59.44),
+            Position::new(24.75, 59.43),
+        ]
+    }
+
+    #[test]
+    fn contour_geometry() {
+        let properties = ContourProperties::new(ContourMetric::Time, 15.0, "#ff0000");
+        let polygon = contour_feature(properties.clone(), vec![vec![ring()]], true);
+        assert!(matches!(polygon.geometry, Geometry::Polygon(ref rings) if rings.len() == 1));
+        let lines = contour_feature(properties, vec![vec![ring()], vec![ring()]], false);
+        assert!(matches!(lines.geometry, Geometry::MultiLineString(ref lines) if lines.len() == 2));
+    }
+
+    #[test]
+    fn contours_have_every_style_alias() {
+        let response = IsochroneResponse::new(vec![
+            contour_feature(
+                ContourProperties::new(ContourMetric::Distance, 2.5, "#00ff00"),
+                vec![vec![ring()]],
+                false,
+            ),
+            location_feature(
+                LocationKind::Snapped,
+                0,
+                vec![Position::new(24.753_575, 59.436_961)],
+            ),
+        ]);
+        let (json, parsed) = to_json_and_back(&response);
+        // Valhalla writes the colors and opacities under the names each renderer expects
+        assert!(json.contains(
+            r##""properties":{"fill-opacity":0.33,"fillColor":"#00ff00","opacity":0.33,"fill":"#00ff00","fillOpacity":0.33,"color":"#00ff00","contour":2.5,"metric":"distance"}"##
+        ));
+        assert!(json.ends_with(
+            r#"{"type":"Feature","geometry":{"type":"MultiPoint","coordinates":[[24.753575,59.436961]]},"properties":{"type":"snapped","location_index":0}}]}"#
+        ));
+
+        assert!(matches!(
+            parsed.features[1].properties,
+            IsochroneProperties::Location(_)
+        ));
+    }
+}
@@ -2,6 +2,68 @@
 
 use serde::{Deserialize, Serialize};
 
+pub mod error;
+pub mod expansion;
+pub mod geojson;
+pub mod isochrone;
+pub mod maneuver;
+pub mod matrix;
+pub mod osrm;
+mod precision;
+pub mod route;
+pub mod trace_attributes;
+
+pub use error::ValhallaError;
+pub use expansion::ExpansionResponse;
+pub use isochrone::IsochroneResponse;
+pub use maneuver::ManeuverType;
+pub use matrix::MatrixResponse;
+pub use osrm::OsrmResponse;
+pub use route::RouteResponse;
+pub use trace_attributes::TraceAttributesResponse;
+
+/// A non-fatal warning raised while processing a request.
+///
+/// Valhalla surfaces these to clients for things like deprecated request parameters
+/// or options which were clamped to the server limits.
+/// The codes follow Valhalla's warning codes (100-199 at the time of this writing).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// The Valhalla warning code.
+    pub code: u64,
+    /// A human-readable description of the warning.
+    pub text: String,
+}
+
+impl Warning {
+    /// Creates a new warning with the given code and text.
+    pub fn new(code: u64, text: impl Into<String>) -> Self {
+        Self {
+            code,
+            text: text.into(),
+        }
+    }
+}
+
+/// Units of length.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    #[default]
+    Kilometers,
+    Miles,
+}
+
+impl Units {
+    /// The length of one unit, in meters.
+    pub const fn meters(self) -> f64 {
+        match self {
+            Self::Kilometers => 1_000.0,
+            Self::Miles => 1_609.344,
+        }
+    }
+}
+
 /// A Valhalla status response including server version, capabilities, etc.
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Deserialize, Debug)]
@@ -39,4 +101,42 @@ pub struct StatusResponse {
     ///
     /// Only included in verbose responses.
     pub osm_changeset: Option<u64>,
+    /// The UNIX timestamp (integer seconds) of the most recent live traffic update.
+    ///
+    /// Only included when a traffic extract is configured and has been updated at least once.
+    pub traffic_last_update: Option<u64>,
+    /// The expected interval (integer seconds) between live traffic updates.
+    ///
+    /// Only included when configured.
+    /// Monitoring can compare this with `traffic_last_update` to detect a stale traffic feed.
+    pub traffic_update_interval: Option<u64>,
+    /// The dataset ID of the tileset serving production traffic
+    /// (Valhalla sets this to the newest OSM changeset in the build).
+    ///
+    /// Only included when blue/green tilesets are configured.
+    pub dataset_id: Option<u64>,
+    /// The dataset ID of the candidate tileset, which takes over from the current one on a swap.
+    ///
+    /// Only included when blue/green tilesets are configured.
+    pub candidate_dataset_id: Option<u64>,
+    /// Any warnings raised while processing the request.
+    ///
+    /// Omitted from the response when there are none.
+    pub warnings: Option<Vec<Warning>>,
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use serde::Serialize;
+    use serde::de::DeserializeOwned;
+
+    /// Serializes a response the way a service would, then parses the JSON back like a client.
+    ///
+    /// Returns both, so tests can check the exact output as well as what survives the trip.
+    pub(crate) fn to_json_and_back<T: Serialize + DeserializeOwned>(response: &T) -> (String, T) {
+        let json = serde_json::to_string(response).unwrap();
+        let parsed = serde_json::from_str(&json)
+            .unwrap_or_else(|e| panic!("Unable to parse the serialized response ({e}): {json}"));
+        (json, parsed)
+    }
 }
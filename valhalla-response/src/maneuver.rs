@@ -0,0 +1,166 @@
+//! Maneuver types and signs, as written in route responses.
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use serde::{Deserialize, Serialize};
+
+/// The type of a maneuver.
+///
+/// Valhalla writes these as numbers (the `DirectionsLeg::Maneuver::Type` values),
+/// so the discriminants must not change.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Default,
+    IntoPrimitive,
+    TryFromPrimitive,
+)]
+#[serde(into = "u8", try_from = "u8")]
+#[repr(u8)]
+pub enum ManeuverType {
+    #[default]
+    None = 0,
+    Start = 1,
+    StartRight = 2,
+    StartLeft = 3,
+    Destination = 4,
+    DestinationRight = 5,
+    DestinationLeft = 6,
+    /// The road changes names, without a turn.
+    Becomes = 7,
+    Continue = 8,
+    SlightRight = 9,
+    Right = 10,
+    SharpRight = 11,
+    UturnRight = 12,
+    UturnLeft = 13,
+    SharpLeft = 14,
+    Left = 15,
+    SlightLeft = 16,
+    RampStraight = 17,
+    RampRight = 18,
+    RampLeft = 19,
+    ExitRight = 20,
+    ExitLeft = 21,
+    /// Keep straight at a fork.
+    StayStraight = 22,
+    /// Keep right at a fork.
+    StayRight = 23,
+    /// Keep left at a fork.
+    StayLeft = 24,
+    Merge = 25,
+    RoundaboutEnter = 26,
+    RoundaboutExit = 27,
+    FerryEnter = 28,
+    FerryExit = 29,
+    Transit = 30,
+    TransitTransfer = 31,
+    TransitRemainOn = 32,
+    TransitConnectionStart = 33,
+    TransitConnectionTransfer = 34,
+    TransitConnectionDestination = 35,
+    PostTransitConnectionDestination = 36,
+    MergeRight = 37,
+    MergeLeft = 38,
+    ElevatorEnter = 39,
+    StepsEnter = 40,
+    EscalatorEnter = 41,
+    BuildingEnter = 42,
+    BuildingExit = 43,
+}
+
+/// The text on the signs at the start of a maneuver (ex: at a highway exit).
+///
+/// Valhalla only writes the lists which have elements.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Sign {
+    /// Exit numbers (ex: `91B`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exit_number_elements: Vec<SignElement>,
+    /// The roads which the exit leads to (ex: `I 95 North`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exit_branch_elements: Vec<SignElement>,
+    /// The places which the exit leads toward (ex: `Baltimore`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exit_toward_elements: Vec<SignElement>,
+    /// Exit names (ex: `Gettysburg Pike`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exit_name_elements: Vec<SignElement>,
+}
+
+impl Sign {
+    pub fn is_empty(&self) -> bool {
+        self.exit_number_elements.is_empty()
+            && self.exit_branch_elements.is_empty()
+            && self.exit_toward_elements.is_empty()
+            && self.exit_name_elements.is_empty()
+    }
+}
+
+/// A single piece of text on a sign.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct SignElement {
+    pub text: String,
+    /// How many of the following maneuvers also have this text on their signs
+    /// (so that instructions can favor text which stays relevant).
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub consecutive_count: u32,
+}
+
+impl SignElement {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            consecutive_count: 0,
+        }
+    }
+}
+
+#[expect(
+    clippy::trivially_copy_pass_by_ref,
+    reason = "skip_serializing_if passes fields by reference"
+)]
+const fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ManeuverType, Sign, SignElement};
+    use serde_json::json;
+
+    #[test]
+    fn maneuver_type_numbers() {
+        assert_eq!(serde_json::to_value(ManeuverType::Start).unwrap(), json!(1));
+        assert_eq!(
+            serde_json::to_value(ManeuverType::BuildingExit).unwrap(),
+            json!(43)
+        );
+        assert_eq!(
+            serde_json::from_value::<ManeuverType>(json!(23)).unwrap(),
+            ManeuverType::StayRight
+        );
+        assert!(serde_json::from_value::<ManeuverType>(json!(44)).is_err());
+    }
+
+    #[test]
+    fn sign_skips_empty_elements() {
+        let sign = Sign {
+            exit_number_elements: vec![SignElement::new("91B")],
+            exit_toward_elements: vec![SignElement {
+                text: "Baltimore".to_string(),
+                consecutive_count: 2,
+            }],
+            ..Sign::default()
+        };
+        assert_eq!(
+            serde_json::to_string(&sign).unwrap(),
+            r#"{"exit_number_elements":[{"text":"91B"}],"exit_toward_elements":[{"text":"Baltimore","consecutive_count":2}]}"#
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Matrix responses (for the `sources_to_targets` action).
+
+use crate::geojson::Geometry;
+use crate::{Units, Warning, precision};
+use serde::{Deserialize, Serialize};
+
+/// A Valhalla matrix response (in the default, verbose format).
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MatrixResponse {
+    /// The request ID, if the request had one.
+    pub id: Option<String>,
+    /// One row per source, with one entry per target (in the order they were given).
+    pub sources_to_targets: Vec<Vec<MatrixEntry>>,
+    /// The sources, as they were correlated to the graph.
+    pub sources: Vec<MatrixLocation>,
+    /// The targets, as they were correlated to the graph.
+    pub targets: Vec<MatrixLocation>,
+    /// The units of all distances.
+    pub units: Units,
+    /// The algorithm which computed the matrix.
+    pub algorithm: Option<MatrixAlgorithm>,
+    /// Any warnings raised while processing the request.
+    pub warnings: Option<Vec<Warning>>,
+}
+
+impl MatrixResponse {
+    /// The entry for a source and target, by their indexes.
+    pub fn get(&self, from_index: usize, to_index: usize) -> Option<&MatrixEntry> {
+        self.sources_to_targets.get(from_index)?.get(to_index)
+    }
+}
+
+/// The algorithms which Valhalla uses for matrices.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MatrixAlgorithm {
+    /// One-to-many (or many-to-one) searches.
+    TimeDistanceMatrix,
+    /// Like [`MatrixAlgorithm::TimeDistanceMatrix`], for bike share.
+    TimeDistanceBssMatrix,
+    /// Bidirectional many-to-many searches.
+    CostMatrix,
+}
+
+/// A source or target location.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct MatrixLocation {
+    #[serde(serialize_with = "precision::six")]
+    pub lon: f64,
+    #[serde(serialize_with = "precision::six")]
+    pub lat: f64,
+}
+
+/// The best route from a source to a target.
+///
+/// Unreachable targets have a `null` distance and time, and no other details.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct MatrixEntry {
+    /// The travel distance, in the response's units.
+    #[serialize_always]
+    #[serde(serialize_with = "precision::three_or_null")]
+    pub distance: Option<f64>,
+    /// The travel time, in whole seconds.
+    #[serialize_always]
+    pub time: Option<u32>,
+    /// The index of the source.
+    pub from_index: u32,
+    /// The index of the target.
+    pub to_index: u32,
+    /// The local date and time of departure or arrival (ex: `2025-09-01T08:00`),
+    /// when the request sets a date and time.
+    pub date_time: Option<String>,
+    /// The UTC offset at the other end of the route (ex: `+02:00`), with `date_time`.
+    pub time_zone_offset: Option<String>,
+    /// The IANA time zone at the other end of the route (ex: `Europe/Tallinn`), with `date_time`.
+    pub time_zone_name: Option<String>,
+    /// The shape of the route, when the request sets `shape_format`.
+    pub shape: Option<MatrixShape>,
+}
+
+/// The shape of a route in a matrix, in the requested format.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum MatrixShape {
+    /// An encoded polyline, with 5 or 6 digits of precision.
+    Polyline(String),
+    /// A GeoJSON line string.
+    GeoJson(Geometry),
+}
+
+impl MatrixEntry {
+    /// Creates the entry for a target which can't be reached.
+    pub fn unreachable(from_index: u32, to_index: u32) -> Self {
+        Self {
+            from_index,
+            to_index,
+            ..Self::default()
+        }
+    }
+
+    /// Whether the target can be reached from the source.
+    pub const fn is_reachable(&self) -> bool {
+        self.time.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MatrixAlgorithm, MatrixEntry, MatrixLocation, MatrixResponse};
+    use crate::Units;
+    use crate::testing::to_json_and_back;
+
+    #[test]
+    fn unreachable_entries_are_null() {
+        let location = MatrixLocation {
+            lon: 24.753_575,
+            lat: 59.436_961,
+        };
+        let response = MatrixResponse {
+            id: None,
+            sources_to_targets: vec![vec![
+                MatrixEntry {
+                    distance: Some(1.234_56),
+                    time: Some(98),
+                    from_index: 0,
+                    to_index: 0,
+                    ..MatrixEntry::default()
+                },
+                MatrixEntry::unreachable(0, 1),
+            ]],
+            sources: vec![location],
+            targets: vec![location, location],
+            units: Units::Miles,
+            algorithm: Some(MatrixAlgorithm::TimeDistanceMatrix),
+            warnings: None,
+        };
+        let (json, parsed) = to_json_and_back(&response);
+        // Distances are rounded to 3 digits, like Valhalla
+        assert!(json.starts_with(
+            r#"{"sources_to_targets":[[{"distance":1.235,"time":98,"from_index":0,"to_index":0},{"distance":null,"time":null,"from_index":0,"to_index":1}]]"#
+        ));
+        assert!(json.ends_with(r#""units":"miles","algorithm":"timedistancematrix"}"#));
+
+        assert!(parsed.get(0, 0).unwrap().is_reachable());
+        assert!(!parsed.get(0, 1).unwrap().is_reachable());
+        assert_eq!(parsed.get(1, 0), None);
+    }
+}
@@ -0,0 +1,780 @@
+//! OSRM-format responses (for route requests with `format=osrm`).
+//!
+//! Some clients (notably navigation SDKs) only speak the OSRM route schema,
+//! with Mapbox's banner and voice instruction extensions.
+//! Rather than building these separately, convert a native route response
+//! with [`OsrmResponse::from_route`], like Valhalla does.
+//!
+//! Distances are in meters (regardless of the trip's units), and durations in seconds.
+
+use crate::geojson::Position;
+use crate::maneuver::ManeuverType;
+use crate::route::{Leg, LocationType, Maneuver, RouteResponse, TravelMode, Trip};
+use crate::{Units, precision};
+use geo::Coord;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use valhalla_graphtile::shape_codec::{PolylineDecodeError, decode_polyline, encode_polyline};
+
+/// The precision of Valhalla's route shapes (polyline6), which OSRM responses also use.
+const SHAPE_PRECISION: u8 = 6;
+
+/// How far before a maneuver its verbal pre-transition instruction is announced, in meters.
+const PRE_TRANSITION_DISTANCE: f64 = 100.0;
+
+#[derive(Debug, Error)]
+pub enum OsrmConversionError {
+    #[error("Invalid leg shape: {0}")]
+    InvalidShape(#[from] PolylineDecodeError),
+    #[error("Maneuver shape indexes {begin}..={end} are outside the leg shape ({len} points)")]
+    ShapeIndexOutOfRange { begin: u32, end: u32, len: usize },
+}
+
+/// Options for converting to the OSRM format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OsrmOptions {
+    /// Include Mapbox-style banner instructions with every step.
+    pub banner_instructions: bool,
+    /// Include Mapbox-style voice instructions with every step.
+    pub voice_instructions: bool,
+    /// The name of the weight (Valhalla uses the costing, ex: `auto`).
+    pub weight_name: String,
+}
+
+impl Default for OsrmOptions {
+    fn default() -> Self {
+        Self {
+            banner_instructions: false,
+            voice_instructions: false,
+            weight_name: "auto".to_string(),
+        }
+    }
+}
+
+/// An OSRM route response.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OsrmResponse {
+    /// Always `Ok` (errors use the Valhalla error format).
+    pub code: String,
+    /// The best route, followed by any alternates.
+    pub routes: Vec<OsrmRoute>,
+    /// The break locations, in order.
+    pub waypoints: Vec<Waypoint>,
+    /// The request ID, if the request had one.
+    pub id: Option<String>,
+}
+
+/// A route through all the waypoints.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OsrmRoute {
+    #[serde(serialize_with = "precision::one")]
+    pub distance: f64,
+    #[serde(serialize_with = "precision::one")]
+    pub duration: f64,
+    #[serde(serialize_with = "precision::one")]
+    pub weight: f64,
+    pub weight_name: String,
+    /// The shape of the whole route, encoded as a polyline with 6 digits of precision.
+    pub geometry: String,
+    pub legs: Vec<OsrmLeg>,
+    /// The language of the voice instructions (ex: `en-US`).
+    #[serde(rename = "voiceLocale")]
+    pub voice_locale: Option<String>,
+}
+
+/// A part of the route between two waypoints.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OsrmLeg {
+    /// The names of the (up to) two most significant roads, in route order (ex: `Main Street, Elm Street`).
+    pub summary: String,
+    #[serde(serialize_with = "precision::one")]
+    pub distance: f64,
+    #[serde(serialize_with = "precision::one")]
+    pub duration: f64,
+    #[serde(serialize_with = "precision::one")]
+    pub weight: f64,
+    pub steps: Vec<RouteStep>,
+}
+
+/// A part of a leg which starts with a maneuver.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RouteStep {
+    #[serde(serialize_with = "precision::one")]
+    pub distance: f64,
+    #[serde(serialize_with = "precision::one")]
+    pub duration: f64,
+    #[serde(serialize_with = "precision::one")]
+    pub weight: f64,
+    /// The name of the road (empty if it has none).
+    pub name: String,
+    /// The shape of the step, encoded as a polyline with 6 digits of precision.
+    pub geometry: String,
+    pub mode: OsrmMode,
+    pub maneuver: StepManeuver,
+    pub intersections: Vec<Intersection>,
+    #[serde(rename = "bannerInstructions")]
+    pub banner_instructions: Option<Vec<BannerInstruction>>,
+    #[serde(rename = "voiceInstructions")]
+    pub voice_instructions: Option<Vec<VoiceInstruction>>,
+}
+
+/// How a step is travelled.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OsrmMode {
+    Driving,
+    Walking,
+    Cycling,
+    Ferry,
+    Transit,
+}
+
+/// The type of an OSRM maneuver.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OsrmManeuverType {
+    Depart,
+    Arrive,
+    Turn,
+    Continue,
+    #[serde(rename = "new name")]
+    NewName,
+    Merge,
+    #[serde(rename = "on ramp")]
+    OnRamp,
+    #[serde(rename = "off ramp")]
+    OffRamp,
+    Fork,
+    #[serde(rename = "end of road")]
+    EndOfRoad,
+    Roundabout,
+    #[serde(rename = "exit roundabout")]
+    ExitRoundabout,
+    /// Something other than a change of direction (ex: boarding a ferry).
+    Notification,
+}
+
+/// The direction of an OSRM maneuver.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    #[serde(rename = "uturn")]
+    UTurn,
+    #[serde(rename = "sharp right")]
+    SharpRight,
+    #[serde(rename = "right")]
+    Right,
+    #[serde(rename = "slight right")]
+    SlightRight,
+    #[serde(rename = "straight")]
+    Straight,
+    #[serde(rename = "slight left")]
+    SlightLeft,
+    #[serde(rename = "left")]
+    Left,
+    #[serde(rename = "sharp left")]
+    SharpLeft,
+}
+
+/// The OSRM type and direction of a Valhalla maneuver.
+const fn osrm_maneuver(maneuver_type: ManeuverType) -> (OsrmManeuverType, Option<Modifier>) {
+    use ManeuverType as T;
+    use OsrmManeuverType as O;
+    match maneuver_type {
+        T::Start => (O::Depart, None),
+        T::StartRight => (O::Depart, Some(Modifier::Right)),
+        T::StartLeft => (O::Depart, Some(Modifier::Left)),
+        T::Destination => (O::Arrive, None),
+        T::DestinationRight => (O::Arrive, Some(Modifier::Right)),
+        T::DestinationLeft => (O::Arrive, Some(Modifier::Left)),
+        T::Becomes => (O::NewName, Some(Modifier::Straight)),
+        T::Continue => (O::Continue, Some(Modifier::Straight)),
+        T::SlightRight => (O::Turn, Some(Modifier::SlightRight)),
+        T::Right => (O::Turn, Some(Modifier::Right)),
+        T::SharpRight => (O::Turn, Some(Modifier::SharpRight)),
+        T::UturnRight | T::UturnLeft => (O::Turn, Some(Modifier::UTurn)),
+        T::SharpLeft => (O::Turn, Some(Modifier::SharpLeft)),
+        T::Left => (O::Turn, Some(Modifier::Left)),
+        T::SlightLeft => (O::Turn, Some(Modifier::SlightLeft)),
+        T::RampStraight => (O::OnRamp, Some(Modifier::Straight)),
+        T::RampRight => (O::OnRamp, Some(Modifier::SlightRight)),
+        T::RampLeft => (O::OnRamp, Some(Modifier::SlightLeft)),
+        T::ExitRight => (O::OffRamp, Some(Modifier::SlightRight)),
+        T::ExitLeft => (O::OffRamp, Some(Modifier::SlightLeft)),
+        T::StayStraight => (O::Fork, Some(Modifier::Straight)),
+        T::StayRight => (O::Fork, Some(Modifier::SlightRight)),
+        T::StayLeft => (O::Fork, Some(Modifier::SlightLeft)),
+        T::Merge => (O::Merge, Some(Modifier::Straight)),
+        T::MergeRight => (O::Merge, Some(Modifier::SlightRight)),
+        T::MergeLeft => (O::Merge, Some(Modifier::SlightLeft)),
+        T::RoundaboutEnter => (O::Roundabout, None),
+        T::RoundaboutExit => (O::ExitRoundabout, None),
+        T::None
+        | T::FerryEnter
+        | T::FerryExit
+        | T::Transit
+        | T::TransitTransfer
+        | T::TransitRemainOn
+        | T::TransitConnectionStart
+        | T::TransitConnectionTransfer
+        | T::TransitConnectionDestination
+        | T::PostTransitConnectionDestination
+        | T::ElevatorEnter
+        | T::StepsEnter
+        | T::EscalatorEnter
+        | T::BuildingEnter
+        | T::BuildingExit => (O::Notification, None),
+    }
+}
+
+/// The maneuver at the start of a step.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StepManeuver {
+    pub location: Position,
+    /// The direction of travel before the maneuver, in degrees from north (0 when departing).
+    pub bearing_before: u16,
+    /// The direction of travel after the maneuver, in degrees from north (0 when arriving).
+    pub bearing_after: u16,
+    #[serde(rename = "type")]
+    pub maneuver_type: OsrmManeuverType,
+    pub modifier: Option<Modifier>,
+    /// The exit to take, for roundabouts.
+    pub exit: Option<u32>,
+    /// The written instruction.
+    pub instruction: String,
+}
+
+/// An intersection along a step.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Intersection {
+    pub location: Position,
+    /// The directions of the roads at the intersection, in degrees from north (in ascending order).
+    pub bearings: Vec<u16>,
+    /// Whether each road (by the index in `bearings`) can be entered.
+    pub entry: Vec<bool>,
+    /// The index of the road which the route arrives by.
+    #[serde(rename = "in")]
+    pub in_index: Option<usize>,
+    /// The index of the road which the route leaves by.
+    #[serde(rename = "out")]
+    pub out_index: Option<usize>,
+}
+
+/// A banner to show for the maneuver at the end of a step.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BannerInstruction {
+    /// How far before the end of the step to show the banner, in meters.
+    #[serde(rename = "distanceAlongGeometry")]
+    #[serde(serialize_with = "precision::one")]
+    pub distance_along_geometry: f64,
+    pub primary: BannerContent,
+    pub secondary: Option<BannerContent>,
+}
+
+/// The text of a banner.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BannerContent {
+    pub text: String,
+    pub components: Vec<BannerComponent>,
+    #[serde(rename = "type")]
+    pub maneuver_type: OsrmManeuverType,
+    pub modifier: Option<Modifier>,
+}
+
+/// A piece of the text of a banner.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BannerComponent {
+    pub text: String,
+    /// The kind of text (always `text`; Valhalla has no shields or lane icons).
+    #[serde(rename = "type")]
+    pub component_type: String,
+}
+
+/// An announcement to speak during a step.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct VoiceInstruction {
+    /// How far before the end of the step to speak the announcement, in meters.
+    #[serde(rename = "distanceAlongGeometry")]
+    pub distance_along_geometry: u32,
+    pub announcement: String,
+    /// The announcement, as SSML (for speech synthesizers).
+    #[serde(rename = "ssmlAnnouncement")]
+    pub ssml_announcement: String,
+}
+
+impl VoiceInstruction {
+    fn new(distance_along_geometry: f64, announcement: &str) -> Self {
+        let escaped = announcement
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;");
+        Self {
+            // Rounded to whole meters, as Valhalla does
+            #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            distance_along_geometry: distance_along_geometry.round() as u32,
+            announcement: announcement.to_string(),
+            ssml_announcement: format!(
+                r#"<speak><amazon:effect name="drc"><prosody rate="1.08">{escaped}</prosody></amazon:effect></speak>"#
+            ),
+        }
+    }
+}
+
+/// A break location.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Waypoint {
+    /// The name of the street at the location (empty if unknown).
+    pub name: String,
+    pub location: Position,
+    /// The distance from the requested location to the route, in meters.
+    pub distance: Option<f64>,
+}
+
+impl OsrmResponse {
+    /// Converts a native route response (including any alternates).
+    ///
+    /// # Errors
+    ///
+    /// Fails if a leg shape isn't a valid polyline6 string,
+    /// or a maneuver's shape indexes are outside the leg shape.
+    pub fn from_route(
+        response: &RouteResponse,
+        options: &OsrmOptions,
+    ) -> Result<Self, OsrmConversionError> {
+        let alternates = response.alternates.iter().flatten();
+        let routes = std::iter::once(&response.trip)
+            .chain(alternates.map(|alternate| &alternate.trip))
+            .map(|trip| OsrmRoute::from_trip(trip, options))
+            .collect::<Result<_, _>>()?;
+        let waypoints = response
+            .trip
+            .locations
+            .iter()
+            .filter(|location| {
+                matches!(
+                    location.location_type,
+                    LocationType::Break | LocationType::BreakThrough
+                )
+            })
+            .map(|location| Waypoint {
+                name: location
+                    .street
+                    .clone()
+                    .or_else(|| location.name.clone())
+                    .unwrap_or_default(),
+                location: Position::new(location.lon, location.lat),
+                distance: None,
+            })
+            .collect();
+        Ok(Self {
+            code: "Ok".to_string(),
+            routes,
+            waypoints,
+            id: response.id.clone(),
+        })
+    }
+}
+
+impl OsrmRoute {
+    /// Converts a native trip.
+    ///
+    /// # Errors
+    ///
+    /// See [`OsrmResponse::from_route`].
+    pub fn from_trip(trip: &Trip, options: &OsrmOptions) -> Result<Self, OsrmConversionError> {
+        let meters = trip.units.meters();
+        let mut shape: Vec<Position> = Vec::new();
+        let mut legs = Vec::with_capacity(trip.legs.len());
+        for leg in &trip.legs {
+            let leg_shape = decode_shape(&leg.shape)?;
+            legs.push(OsrmLeg::from_leg(leg, &leg_shape, trip.units, options)?);
+            // Each leg starts where the previous one ended
+            let skip = usize::from(shape.last().is_some() && shape.last() == leg_shape.first());
+            shape.extend(leg_shape.into_iter().skip(skip));
+        }
+        Ok(Self {
+            distance: trip.summary.length * meters,
+            duration: trip.summary.time,
+            weight: trip.summary.cost,
+            weight_name: options.weight_name.clone(),
+            geometry: encode_shape(&shape),
+            legs,
+            voice_locale: options.voice_instructions.then(|| trip.language.clone()),
+        })
+    }
+}
+
+fn decode_shape(polyline: &str) -> Result<Vec<Position>, PolylineDecodeError> {
+    Ok(decode_polyline::<f64>(polyline, SHAPE_PRECISION)?
+        .into_iter()
+        .map(Position::from)
+        .collect())
+}
+
+fn encode_shape(shape: &[Position]) -> String {
+    let coords: Vec<_> = shape.iter().copied().map(Coord::from).collect();
+    encode_polyline(&coords, SHAPE_PRECISION)
+}
+
+impl OsrmLeg {
+    fn from_leg(
+        leg: &Leg,
+        shape: &[Position],
+        units: Units,
+        options: &OsrmOptions,
+    ) -> Result<Self, OsrmConversionError> {
+        let meters = units.meters();
+        let steps = leg
+            .maneuvers
+            .iter()
+            .enumerate()
+            .map(|(index, maneuver)| {
+                let next = leg.maneuvers.get(index + 1);
+                RouteStep::from_maneuver(maneuver, next, shape, meters, options)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            summary: summarize(&steps),
+            distance: leg.summary.length * meters,
+            duration: leg.summary.time,
+            weight: leg.summary.cost,
+            steps,
+        })
+    }
+}
+
+/// The names of the (up to) two longest named steps, in route order.
+fn summarize(steps: &[RouteStep]) -> String {
+    let mut named: Vec<(usize, &RouteStep)> = steps
+        .iter()
+        .enumerate()
+        .filter(|(_, step)| !step.name.is_empty())
+        .collect();
+    named.sort_by(|(_, a), (_, b)| b.distance.total_cmp(&a.distance));
+    let mut longest: Vec<(usize, &str)> = Vec::with_capacity(2);
+    for (index, step) in named {
+        if longest.iter().all(|(_, name)| *name != step.name) {
+            longest.push((index, &step.name));
+        }
+        if longest.len() == 2 {
+            break;
+        }
+    }
+    longest.sort_unstable_by_key(|(index, _)| *index);
+    longest
+        .into_iter()
+        .map(|(_, name)| name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl RouteStep {
+    fn from_maneuver(
+        maneuver: &Maneuver,
+        next: Option<&Maneuver>,
+        shape: &[Position],
+        meters: f64,
+        options: &OsrmOptions,
+    ) -> Result<Self, OsrmConversionError> {
+        let (begin, end) = (maneuver.begin_shape_index, maneuver.end_shape_index);
+        let step_shape = usize::try_from(begin)
+            .ok()
+            .zip(usize::try_from(end).ok())
+            .filter(|(begin, end)| begin <= end)
+            .and_then(|(begin, end)| shape.get(begin..=end))
+            .ok_or(OsrmConversionError::ShapeIndexOutOfRange {
+                begin,
+                end,
+                len: shape.len(),
+            })?;
+        let location = step_shape[0];
+        let distance = maneuver.length * meters;
+        let (maneuver_type, modifier) = osrm_maneuver(maneuver.maneuver_type);
+
+        let banner_instructions = options
+            .banner_instructions
+            .then(|| vec![BannerInstruction::new(next.unwrap_or(maneuver), distance)]);
+        let voice_instructions = options.voice_instructions.then(|| {
+            let post = maneuver
+                .verbal_post_transition_instruction
+                .as_deref()
+                .map(|text| VoiceInstruction::new(distance, text));
+            let pre = next
+                .and_then(|next| next.verbal_pre_transition_instruction.as_deref())
+                .map(|text| VoiceInstruction::new(distance.min(PRE_TRANSITION_DISTANCE), text));
+            post.into_iter().chain(pre).collect()
+        });
+
+        Ok(Self {
+            distance,
+            duration: maneuver.time,
+            weight: maneuver.cost,
+            name: maneuver
+                .street_names
+                .as_ref()
+                .and_then(|names| names.first())
+                .cloned()
+                .unwrap_or_default(),
+            geometry: encode_shape(step_shape),
+            mode: if maneuver.ferry {
+                OsrmMode::Ferry
+            } else {
+                match maneuver.travel_mode {
+                    TravelMode::Drive => OsrmMode::Driving,
+                    TravelMode::Pedestrian => OsrmMode::Walking,
+                    TravelMode::Bicycle => OsrmMode::Cycling,
+                    TravelMode::Transit => OsrmMode::Transit,
+                }
+            },
+            maneuver: StepManeuver {
+                location,
+                bearing_before: maneuver.bearing_before.unwrap_or_default(),
+                bearing_after: maneuver.bearing_after.unwrap_or_default(),
+                maneuver_type,
+                modifier,
+                exit: maneuver.roundabout_exit_count,
+                instruction: maneuver.instruction.clone(),
+            },
+            intersections: vec![Intersection::at_maneuver(location, maneuver)],
+            banner_instructions,
+            voice_instructions,
+        })
+    }
+}
+
+impl Intersection {
+    /// The intersection where a maneuver starts, with the roads which the route uses.
+    ///
+    /// Valhalla's trip doesn't describe the other roads at the intersection.
+    fn at_maneuver(location: Position, maneuver: &Maneuver) -> Self {
+        let (maneuver_type, _) = osrm_maneuver(maneuver.maneuver_type);
+        // The road which the route arrives by, pointing away from the intersection
+        let arrival = maneuver
+            .bearing_before
+            .filter(|_| maneuver_type != OsrmManeuverType::Depart)
+            .map(|bearing| (bearing + 180) % 360);
+        let departure = maneuver
+            .bearing_after
+            .filter(|_| maneuver_type != OsrmManeuverType::Arrive);
+
+        let mut roads: Vec<(u16, bool)> = arrival
+            .map(|bearing| (bearing, false))
+            .into_iter()
+            .chain(departure.map(|bearing| (bearing, true)))
+            .collect();
+        roads.sort_unstable();
+        let index_of = |bearing: Option<u16>, entry: bool| {
+            bearing.and_then(|bearing| roads.iter().position(|road| *road == (bearing, entry)))
+        };
+        Self {
+            location,
+            in_index: index_of(arrival, false),
+            out_index: index_of(departure, true),
+            bearings: roads.iter().map(|(bearing, _)| *bearing).collect(),
+            entry: roads.iter().map(|(_, entry)| *entry).collect(),
+        }
+    }
+}
+
+impl BannerInstruction {
+    /// The banner for a maneuver, shown from the given distance before it.
+    fn new(maneuver: &Maneuver, distance_along_geometry: f64) -> Self {
+        let text = maneuver
+            .street_names
+            .as_ref()
+            .filter(|names| !names.is_empty())
+            .map_or_else(|| maneuver.instruction.clone(), |names| names.join(" / "));
+        let (maneuver_type, modifier) = osrm_maneuver(maneuver.maneuver_type);
+        Self {
+            distance_along_geometry,
+            primary: BannerContent {
+                components: vec![BannerComponent {
+                    text: text.clone(),
+                    component_type: "text".to_string(),
+                }],
+                text,
+                maneuver_type,
+                modifier,
+            },
+            secondary: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Modifier, OsrmConversionError, OsrmManeuverType, OsrmMode, OsrmOptions, OsrmResponse,
+        decode_shape, encode_shape,
+    };
+    use crate::Units;
+    use crate::geojson::Position;
+    use crate::maneuver::ManeuverType;
+    use crate::route::{Leg, Location, Maneuver, RouteResponse, Summary, TravelMode, Trip};
+
+    fn maneuver(
+        maneuver_type: ManeuverType,
+        street: &str,
+        shape_indexes: (u32, u32),
+        length: f64,
+    ) -> Maneuver {
+        Maneuver {
+            maneuver_type,
+            instruction: format!("{maneuver_type:?} {street}."),
+            verbal_transition_alert_instruction: None,
+            verbal_succinct_transition_instruction: None,
+            verbal_pre_transition_instruction: Some(format!("{maneuver_type:?} now.")),
+            verbal_post_transition_instruction: None,
+            street_names: Some(vec![street.to_string()]),
+            begin_street_names: None,
+            bearing_before: Some(0),
+            bearing_after: Some(90),
+            time: length * 60.0,
+            length,
+            cost: length * 60.0,
+            begin_shape_index: shape_indexes.0,
+            end_shape_index: shape_indexes.1,
+            toll: false,
+            highway: false,
+            rough: false,
+            gate: false,
+            ferry: false,
+            sign: crate::maneuver::Sign::default(),
+            roundabout_exit_count: None,
+            depart_instruction: None,
+            verbal_depart_instruction: None,
+            arrive_instruction: None,
+            verbal_arrive_instruction: None,
+            verbal_multi_cue: false,
+            travel_mode: TravelMode::Drive,
+            travel_type: "car".to_string(),
+        }
+    }
+
+    fn response(units: Units) -> RouteResponse {
+        let shape = encode_shape(&[
+            Position::new(24.75, 59.43),
+            Position::new(24.75, 59.44),
+            Position::new(24.76, 59.44),
+        ]);
+        let leg = Leg {
+            maneuvers: vec![
+                maneuver(ManeuverType::Start, "Narva mnt", (0, 1), 1.1),
+                maneuver(ManeuverType::Right, "Pärnu mnt", (1, 2), 0.6),
+                maneuver(ManeuverType::DestinationLeft, "Pärnu mnt", (2, 2), 0.0),
+            ],
+            summary: Summary {
+                time: 102.0,
+                length: 1.7,
+                cost: 102.0,
+                ..Summary::default()
+            },
+            shape,
+        };
+        let location = |lon, lat| Location {
+            lon,
+            lat,
+            street: Some("Narva mnt".to_string()),
+            ..Location::default()
+        };
+        RouteResponse {
+            trip: Trip::new(
+                vec![location(24.75, 59.43), location(24.76, 59.44)],
+                vec![leg],
+                units,
+                "et-EE".to_string(),
+            ),
+            id: None,
+            alternates: None,
+            warnings: None,
+        }
+    }
+
+    #[test]
+    fn convert_route() {
+        let options = OsrmOptions {
+            banner_instructions: true,
+            voice_instructions: true,
+            ..OsrmOptions::default()
+        };
+        let osrm = OsrmResponse::from_route(&response(Units::Kilometers), &options).unwrap();
+        assert_eq!(osrm.waypoints.len(), 2);
+        let route = &osrm.routes[0];
+        assert!((route.distance - 1_700.0).abs() < 1e-6);
+        assert_eq!(route.voice_locale.as_deref(), Some("et-EE"));
+        assert_eq!(decode_shape(&route.geometry).unwrap().len(), 3);
+
+        let leg = &route.legs[0];
+        assert_eq!(leg.summary, "Narva mnt, Pärnu mnt");
+        let [depart, turn, arrive] = leg.steps.as_slice() else {
+            panic!("Expected three steps");
+        };
+        assert_eq!(depart.maneuver.maneuver_type, OsrmManeuverType::Depart);
+        assert_eq!(depart.maneuver.modifier, None);
+        assert_eq!(depart.mode, OsrmMode::Driving);
+        assert_eq!(depart.intersections[0].bearings, vec![90]);
+        assert_eq!(depart.intersections[0].out_index, Some(0));
+        assert_eq!(turn.maneuver.modifier, Some(Modifier::Right));
+        assert_eq!(turn.intersections[0].bearings, vec![90, 180]);
+        assert_eq!(turn.intersections[0].in_index, Some(1));
+        assert_eq!(arrive.maneuver.maneuver_type, OsrmManeuverType::Arrive);
+        assert_eq!(arrive.maneuver.modifier, Some(Modifier::Left));
+        assert_eq!(decode_shape(&arrive.geometry).unwrap().len(), 1);
+
+        // The banner and pre-transition announcement describe the next maneuver
+        let banner = &depart.banner_instructions.as_ref().unwrap()[0];
+        assert_eq!(banner.primary.text, "Pärnu mnt");
+        assert_eq!(banner.primary.modifier, Some(Modifier::Right));
+        let voice = &depart.voice_instructions.as_ref().unwrap()[0];
+        assert_eq!(voice.announcement, "Right now.");
+        assert_eq!(voice.distance_along_geometry, 100);
+
+        let json = serde_json::to_value(&osrm).unwrap();
+        assert_eq!(json["code"], "Ok");
+        assert_eq!(
+            json["routes"][0]["legs"][0]["steps"][1]["maneuver"]["type"],
+            "turn"
+        );
+        assert_eq!(
+            json["routes"][0]["legs"][0]["steps"][0]["bannerInstructions"][0]["distanceAlongGeometry"],
+            1_100.0
+        );
+        assert_eq!(json["waypoints"][0]["location"][1], 59.43);
+    }
+
+    #[test]
+    fn convert_units_and_options() {
+        let osrm =
+            OsrmResponse::from_route(&response(Units::Miles), &OsrmOptions::default()).unwrap();
+        let step = &osrm.routes[0].legs[0].steps[0];
+        assert!((step.distance - 1.1 * 1_609.344).abs() < 1e-6);
+        assert_eq!(step.banner_instructions, None);
+        assert_eq!(osrm.routes[0].voice_locale, None);
+        assert!(
+            !serde_json::to_string(&osrm)
+                .unwrap()
+                .contains("voiceInstructions")
+        );
+    }
+
+    #[test]
+    fn invalid_shape_indexes() {
+        let mut response = response(Units::Kilometers);
+        response.trip.legs[0].maneuvers[1].end_shape_index = 3;
+        assert!(matches!(
+            OsrmResponse::from_route(&response, &OsrmOptions::default()),
+            Err(OsrmConversionError::ShapeIndexOutOfRange {
+                begin: 1,
+                end: 3,
+                len: 3
+            })
+        ));
+    }
+}
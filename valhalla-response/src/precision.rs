@@ -0,0 +1,57 @@
+//! Rounding for numbers which Valhalla writes with a fixed precision.
+//!
+//! Valhalla writes times, lengths, and costs with 3 decimal places
+//! (1 in OSRM-format responses), and coordinates with 6.
+//! Rounding to the same precision keeps responses from carrying noise
+//! like `0.30000000000000004`, and makes them match Valhalla's JSON values.
+//! (Trailing zeros are not kept, so `1.500` is written as `1.5`.)
+
+use serde::Serializer;
+
+/// Rounds to the given number of decimal places.
+pub(crate) fn round(value: f64, digits: i32) -> f64 {
+    let factor = 10f64.powi(digits);
+    (value * factor).round() / factor
+}
+
+/// Serializes with 3 decimal places (for times, lengths, and costs).
+#[expect(
+    clippy::trivially_copy_pass_by_ref,
+    reason = "serialize_with passes fields by reference"
+)]
+pub(crate) fn three<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(round(*value, 3))
+}
+
+/// Serializes with 6 decimal places (for coordinates).
+#[expect(
+    clippy::trivially_copy_pass_by_ref,
+    reason = "serialize_with passes fields by reference"
+)]
+pub(crate) fn six<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(round(*value, 6))
+}
+
+/// Serializes an optional value with 3 decimal places (`None` is written as `null`).
+#[expect(
+    clippy::ref_option,
+    reason = "serialize_with passes fields by reference"
+)]
+pub(crate) fn three_or_null<S: Serializer>(
+    value: &Option<f64>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => serializer.serialize_f64(round(*value, 3)),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Serializes with 1 decimal place (for OSRM distances, durations, and weights).
+#[expect(
+    clippy::trivially_copy_pass_by_ref,
+    reason = "serialize_with passes fields by reference"
+)]
+pub(crate) fn one<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(round(*value, 1))
+}
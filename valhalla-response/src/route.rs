@@ -0,0 +1,369 @@
+//! Route responses (for the `route` and `optimized_route` actions).
+//!
+//! The structures mirror Valhalla's JSON output field for field (and in the same order),
+//! so clients can't tell a Rust service from Valhalla.
+
+use crate::maneuver::{ManeuverType, Sign};
+use crate::{Units, Warning, precision};
+use serde::{Deserialize, Serialize};
+use std::ops::Not;
+
+/// A Valhalla route response.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RouteResponse {
+    /// The best route.
+    pub trip: Trip,
+    /// The request ID, if the request had one.
+    pub id: Option<String>,
+    /// Alternate routes, if requested and found.
+    pub alternates: Option<Vec<Alternate>>,
+    /// Any warnings raised while processing the request.
+    pub warnings: Option<Vec<Warning>>,
+}
+
+/// An alternate route.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Alternate {
+    pub trip: Trip,
+}
+
+/// A route through all the locations.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Trip {
+    /// The locations, as they were correlated to the graph.
+    pub locations: Vec<Location>,
+    /// The legs between each pair of break locations.
+    pub legs: Vec<Leg>,
+    /// A summary of the whole trip (see [`Summary::combine`]).
+    pub summary: Summary,
+    /// Always `Found route between points` (for compatibility).
+    pub status_message: String,
+    /// Always 0 (for compatibility).
+    pub status: u32,
+    /// The units of all lengths.
+    pub units: Units,
+    /// The language of the instructions (an IETF BCP 47 language tag, ex: `en-US`).
+    pub language: String,
+}
+
+impl Trip {
+    /// The status message of every trip.
+    pub const STATUS_MESSAGE: &str = "Found route between points";
+
+    /// Creates a trip from its legs, summarizing them.
+    pub fn new(locations: Vec<Location>, legs: Vec<Leg>, units: Units, language: String) -> Self {
+        let summary = Summary::combine(legs.iter().map(|leg| &leg.summary));
+        Self {
+            locations,
+            legs,
+            summary,
+            status_message: Self::STATUS_MESSAGE.to_string(),
+            status: 0,
+            units,
+            language,
+        }
+    }
+}
+
+/// How a location is used in a route.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LocationType {
+    /// A stop, which starts a new leg (and where u-turns are allowed).
+    #[default]
+    Break,
+    /// A location the route passes through, without starting a new leg.
+    Through,
+    /// Like [`LocationType::Through`], but u-turns are allowed.
+    Via,
+    /// Like [`LocationType::Break`], but u-turns aren't allowed.
+    BreakThrough,
+}
+
+/// Which side of the street a location is on, relative to the direction of travel.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SideOfStreet {
+    Left,
+    Right,
+}
+
+/// A location in a route.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Location {
+    #[serde(rename = "type")]
+    pub location_type: LocationType,
+    #[serde(serialize_with = "precision::six")]
+    pub lat: f64,
+    #[serde(serialize_with = "precision::six")]
+    pub lon: f64,
+    pub name: Option<String>,
+    pub street: Option<String>,
+    pub city: Option<String>,
+    pub state: Option<String>,
+    pub postal_code: Option<String>,
+    pub country: Option<String>,
+    /// The local date and time at the location (ex: `2025-09-01T08:00`), for time-dependent routes.
+    pub date_time: Option<String>,
+    /// The preferred direction of travel from the location, in degrees from north.
+    pub heading: Option<u16>,
+    pub side_of_street: Option<SideOfStreet>,
+    /// The index of the location in the request (useful for optimized routes).
+    pub original_index: Option<u32>,
+}
+
+/// A part of the route between two break locations.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Leg {
+    pub maneuvers: Vec<Maneuver>,
+    pub summary: Summary,
+    /// The shape of the leg, encoded as a polyline with 6 digits of precision.
+    pub shape: String,
+}
+
+/// Totals and the bounding box of a leg or trip.
+#[expect(clippy::struct_excessive_bools, reason = "Mirrors Valhalla's JSON")]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Summary {
+    pub has_time_restrictions: bool,
+    pub has_toll: bool,
+    pub has_highway: bool,
+    pub has_ferry: bool,
+    #[serde(serialize_with = "precision::six")]
+    pub min_lat: f64,
+    #[serde(serialize_with = "precision::six")]
+    pub min_lon: f64,
+    #[serde(serialize_with = "precision::six")]
+    pub max_lat: f64,
+    #[serde(serialize_with = "precision::six")]
+    pub max_lon: f64,
+    /// The estimated travel time, in seconds.
+    #[serde(serialize_with = "precision::three")]
+    pub time: f64,
+    /// The length, in the trip's units.
+    #[serde(serialize_with = "precision::three")]
+    pub length: f64,
+    /// The cost (an abstract measure of how undesirable the route is).
+    #[serde(serialize_with = "precision::three")]
+    pub cost: f64,
+}
+
+impl Summary {
+    /// Combines the summaries of several legs, as Valhalla does for the trip summary:
+    /// totals are summed, and bounding boxes are merged.
+    pub fn combine<'a>(summaries: impl IntoIterator<Item = &'a Summary>) -> Summary {
+        summaries
+            .into_iter()
+            .enumerate()
+            .fold(Summary::default(), |combined, (index, summary)| {
+                let bounds = if index == 0 { summary } else { &combined };
+                Summary {
+                    has_time_restrictions: combined.has_time_restrictions
+                        || summary.has_time_restrictions,
+                    has_toll: combined.has_toll || summary.has_toll,
+                    has_highway: combined.has_highway || summary.has_highway,
+                    has_ferry: combined.has_ferry || summary.has_ferry,
+                    min_lat: bounds.min_lat.min(summary.min_lat),
+                    min_lon: bounds.min_lon.min(summary.min_lon),
+                    max_lat: bounds.max_lat.max(summary.max_lat),
+                    max_lon: bounds.max_lon.max(summary.max_lon),
+                    time: combined.time + summary.time,
+                    length: combined.length + summary.length,
+                    cost: combined.cost + summary.cost,
+                }
+            })
+    }
+}
+
+/// How a maneuver is travelled.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TravelMode {
+    Drive,
+    Pedestrian,
+    Bicycle,
+    Transit,
+}
+
+/// A single instruction in a leg (ex: turn right onto Main Street).
+///
+/// Flags are only included in the JSON when set, as in Valhalla.
+#[serde_with::skip_serializing_none]
+#[expect(clippy::struct_excessive_bools, reason = "Mirrors Valhalla's JSON")]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Maneuver {
+    #[serde(rename = "type")]
+    pub maneuver_type: ManeuverType,
+    /// The written instruction.
+    pub instruction: String,
+    /// A spoken alert well ahead of the maneuver (ex: `Turn right onto Main Street.`).
+    pub verbal_transition_alert_instruction: Option<String>,
+    /// A short spoken instruction, for when there is little time (ex: `Turn right.`).
+    pub verbal_succinct_transition_instruction: Option<String>,
+    /// The spoken instruction right before the maneuver.
+    pub verbal_pre_transition_instruction: Option<String>,
+    /// The spoken instruction right after the maneuver (ex: `Continue for 2 kilometers.`).
+    pub verbal_post_transition_instruction: Option<String>,
+    /// The names of the street(s) which the maneuver is on.
+    pub street_names: Option<Vec<String>>,
+    /// The names of the street(s) at the start of the maneuver, when they differ.
+    pub begin_street_names: Option<Vec<String>>,
+    /// The direction of travel before the maneuver, in degrees from north.
+    pub bearing_before: Option<u16>,
+    /// The direction of travel after the maneuver, in degrees from north.
+    pub bearing_after: Option<u16>,
+    /// The estimated time, in seconds.
+    #[serde(serialize_with = "precision::three")]
+    pub time: f64,
+    /// The length, in the trip's units.
+    #[serde(serialize_with = "precision::three")]
+    pub length: f64,
+    #[serde(serialize_with = "precision::three")]
+    pub cost: f64,
+    /// The index of the first point of the maneuver in the leg's shape.
+    pub begin_shape_index: u32,
+    /// The index of the last point of the maneuver in the leg's shape.
+    pub end_shape_index: u32,
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub toll: bool,
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub highway: bool,
+    /// Some of the maneuver is on unpaved or rough roads.
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub rough: bool,
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub gate: bool,
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub ferry: bool,
+    #[serde(default, skip_serializing_if = "Sign::is_empty")]
+    pub sign: Sign,
+    /// The exit to take, for roundabouts.
+    pub roundabout_exit_count: Option<u32>,
+    /// The written instruction for leaving a location (ex: `Depart: 8:00 AM from 8 St - Nyu.`).
+    pub depart_instruction: Option<String>,
+    pub verbal_depart_instruction: Option<String>,
+    /// The written instruction for arriving at a location (ex: `Arrive: 8:30 AM at Union St.`).
+    pub arrive_instruction: Option<String>,
+    pub verbal_arrive_instruction: Option<String>,
+    /// The verbal pre-transition instruction also covers the next maneuver,
+    /// because the two follow each other closely.
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub verbal_multi_cue: bool,
+    pub travel_mode: TravelMode,
+    /// The type of vehicle or travel (ex: `car`, `foot`, `road`).
+    pub travel_type: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Leg, Location, Maneuver, RouteResponse, Summary, TravelMode, Trip};
+    use crate::Units;
+    use crate::maneuver::{ManeuverType, Sign};
+    use crate::testing::to_json_and_back;
+    use serde_json::json;
+
+    fn summary(min_lat: f64, max_lat: f64, time: f64) -> Summary {
+        Summary {
+            min_lat,
+            min_lon: 24.7,
+            max_lat,
+            max_lon: 24.8,
+            time,
+            length: 1.0,
+            cost: time,
+            ..Summary::default()
+        }
+    }
+
+    #[test]
+    fn combine_summaries() {
+        let combined = Summary::combine(&[
+            summary(59.43, 59.44, 60.0),
+            Summary {
+                has_toll: true,
+                ..summary(59.42, 59.435, 30.5)
+            },
+        ]);
+        assert!(combined.has_toll);
+        assert!(!combined.has_ferry);
+        assert_eq!((combined.min_lat, combined.max_lat), (59.42, 59.44));
+        assert!((combined.time - 90.5).abs() < f64::EPSILON);
+        assert!((combined.length - 2.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn fields_are_written_in_valhalla_order() {
+        let maneuver = Maneuver {
+            maneuver_type: ManeuverType::StartRight,
+            instruction: "Drive north on Main Street.".to_string(),
+            verbal_transition_alert_instruction: None,
+            verbal_succinct_transition_instruction: None,
+            verbal_pre_transition_instruction: Some("Drive north on Main Street.".to_string()),
+            verbal_post_transition_instruction: None,
+            street_names: Some(vec!["Main Street".to_string()]),
+            begin_street_names: None,
+            bearing_before: None,
+            bearing_after: Some(0),
+            time: 0.1 + 0.2,
+            length: 0.0125,
+            cost: 1.5,
+            begin_shape_index: 0,
+            end_shape_index: 1,
+            toll: false,
+            highway: false,
+            rough: false,
+            gate: false,
+            ferry: true,
+            sign: Sign::default(),
+            roundabout_exit_count: None,
+            depart_instruction: None,
+            verbal_depart_instruction: None,
+            arrive_instruction: None,
+            verbal_arrive_instruction: None,
+            verbal_multi_cue: true,
+            travel_mode: TravelMode::Drive,
+            travel_type: "car".to_string(),
+        };
+        let leg = Leg {
+            maneuvers: vec![maneuver],
+            summary: summary(59.43, 59.44, 0.3),
+            shape: "_gr~nBaxhwo@".to_string(),
+        };
+        let response = RouteResponse {
+            trip: Trip::new(
+                vec![Location {
+                    lat: 59.436_961_234,
+                    lon: 24.753_575,
+                    original_index: Some(0),
+                    ..Location::default()
+                }],
+                vec![leg],
+                Units::Kilometers,
+                "en-US".to_string(),
+            ),
+            id: Some("my_route".to_string()),
+            alternates: None,
+            warnings: None,
+        };
+
+        let (json, parsed) = to_json_and_back(&response);
+        // Fields are written in the same order as Valhalla
+        assert!(json.starts_with(
+            r#"{"trip":{"locations":[{"type":"break","lat":59.436961,"lon":24.753575,"original_index":0}],"legs":[{"maneuvers":[{"type":2,"instruction":"Drive north on Main Street.","verbal_pre_transition_instruction":"Drive north on Main Street.","street_names":["Main Street"],"bearing_after":0,"time":0.3,"length":0.013,"cost":1.5,"begin_shape_index":0,"end_shape_index":1,"ferry":true,"verbal_multi_cue":true,"travel_mode":"drive","travel_type":"car"}]"#
+        ));
+        assert!(json.ends_with(
+            r#""status_message":"Found route between points","status":0,"units":"kilometers","language":"en-US"},"id":"my_route"}"#
+        ));
+
+        assert_eq!(
+            parsed.trip.legs[0].maneuvers[0].instruction,
+            "Drive north on Main Street."
+        );
+        assert_eq!(
+            serde_json::to_value(&parsed.trip.summary).unwrap()["has_toll"],
+            json!(false)
+        );
+    }
+}
@@ -0,0 +1,387 @@
+//! Trace attributes responses (for the `trace_attributes` action).
+//!
+//! A trace is matched to a path through the graph, and the response describes
+//! every edge of the path and where each trace point was matched.
+//! Most edge attributes are only included when the request's `filters` ask for them,
+//! so nearly all of them are optional.
+
+use crate::route::TravelMode;
+use crate::{Units, Warning, precision};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A Valhalla trace attributes response.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct TraceAttributesResponse {
+    /// The request ID, if the request had one.
+    pub id: Option<String>,
+    /// The newest OSM changeset in the tiles.
+    pub osm_changeset: Option<u64>,
+    /// The administrative areas which the edges are in
+    /// (referenced by [`EndNode::admin_index`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub admins: Vec<Admin>,
+    /// The edges of the matched path, in order.
+    #[serde(default)]
+    pub edges: Vec<Edge>,
+    /// One entry per trace point, in the order they were given.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matched_points: Vec<MatchedPoint>,
+    /// The shape of the matched path, encoded as a polyline with 6 digits of precision.
+    pub shape: Option<String>,
+    /// How confident the matcher is in the path, from 0 to 1.
+    pub confidence_score: Option<f64>,
+    /// The matcher's score for the path (lower is better).
+    pub raw_score: Option<f64>,
+    /// The units of all lengths.
+    pub units: Option<Units>,
+    /// The next best paths, when the request asks for `alternates`.
+    pub alternate_paths: Option<Vec<TraceAttributesResponse>>,
+    /// Any warnings raised while processing the request.
+    pub warnings: Option<Vec<Warning>>,
+}
+
+/// A country and (optionally) state.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct Admin {
+    /// The ISO 3166-1 country code (ex: `EE`).
+    #[serde(rename = "iso_3166_1")]
+    pub country_code: Option<String>,
+    pub country_text: Option<String>,
+    /// The ISO 3166-2 subdivision code, without the country (ex: `37`).
+    #[serde(rename = "iso_3166_2")]
+    pub state_code: Option<String>,
+    pub state_text: Option<String>,
+}
+
+/// How a trace point was matched.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchType {
+    /// The point couldn't be matched to the path.
+    #[default]
+    Unmatched,
+    /// The point was placed along the path between matched points
+    /// (because it was too close to the previous one to match separately).
+    Interpolated,
+    Matched,
+}
+
+/// Where a trace point was matched.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct MatchedPoint {
+    #[serde(rename = "type")]
+    pub match_type: MatchType,
+    /// The latitude of the matched point (or the trace point, if unmatched).
+    #[serde(serialize_with = "precision::six")]
+    pub lat: f64,
+    #[serde(serialize_with = "precision::six")]
+    pub lon: f64,
+    /// The index of the edge in [`TraceAttributesResponse::edges`] (not included when unmatched).
+    pub edge_index: Option<u32>,
+    /// The path is broken before this point (ex: because the trace had a gap).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub begin_route_discontinuity: bool,
+    /// The path is broken after this point.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub end_route_discontinuity: bool,
+    /// How far along the edge the point was matched, from 0 to 1.
+    pub distance_along_edge: Option<f64>,
+    /// The distance between the trace point and the matched point, in meters.
+    pub distance_from_trace_point: Option<f64>,
+}
+
+/// The importance of a road.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RoadClass {
+    Motorway,
+    Trunk,
+    Primary,
+    Secondary,
+    Tertiary,
+    Unclassified,
+    Residential,
+    ServiceOther,
+}
+
+/// The directions an edge can be travelled in (for the request's costing).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Traversability {
+    Forward,
+    Backward,
+    Both,
+}
+
+/// The surface of a road, from smoothest to roughest.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Surface {
+    PavedSmooth,
+    Paved,
+    PavedRough,
+    Compacted,
+    Dirt,
+    Gravel,
+    Path,
+    Impassable,
+}
+
+/// The kind of bike lane on a road.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CycleLane {
+    None,
+    /// A lane shared with cars (ex: marked with sharrows).
+    Shared,
+    /// A painted lane.
+    Dedicated,
+    /// A lane separated from cars (ex: by a curb or bollards).
+    Separated,
+}
+
+/// Which sides of a road have a sidewalk.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Sidewalk {
+    Left,
+    Right,
+    Both,
+}
+
+/// A posted speed limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeedLimit {
+    /// A limit, in the response's units per hour.
+    Limited(u32),
+    /// No limit (written as `unlimited`).
+    Unlimited,
+}
+
+impl Serialize for SpeedLimit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Limited(limit) => serializer.serialize_u32(*limit),
+            Self::Unlimited => serializer.serialize_str("unlimited"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SpeedLimit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Value {
+            Limited(u32),
+            Text(String),
+        }
+
+        match Value::deserialize(deserializer)? {
+            Value::Limited(limit) => Ok(Self::Limited(limit)),
+            Value::Text(text) if text == "unlimited" => Ok(Self::Unlimited),
+            Value::Text(text) => Err(serde::de::Error::custom(format!(
+                "Expected a speed limit or `unlimited`; got `{text}`"
+            ))),
+        }
+    }
+}
+
+/// The text of the signs at the start of an edge.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct EdgeSign {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exit_number: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exit_branch: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exit_toward: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exit_name: Vec<String>,
+}
+
+/// An edge of the matched path.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Edge {
+    pub names: Option<Vec<String>>,
+    /// The length of the edge (or the part of it which was travelled), in the response's units.
+    #[serde(serialize_with = "precision::three_or_null")]
+    pub length: Option<f64>,
+    /// The speed used for routing, in the response's units per hour.
+    pub speed: Option<f64>,
+    pub road_class: Option<RoadClass>,
+    /// The direction at the start of the edge, in degrees from north.
+    pub begin_heading: Option<u16>,
+    /// The direction at the end of the edge, in degrees from north.
+    pub end_heading: Option<u16>,
+    /// The index of the first point of the edge in the shape.
+    pub begin_shape_index: Option<u32>,
+    /// The index of the last point of the edge in the shape.
+    pub end_shape_index: Option<u32>,
+    pub traversability: Option<Traversability>,
+    /// The use of the edge (ex: `road`, `ramp`, `footway`, `ferry`).
+    #[serde(rename = "use")]
+    pub edge_use: Option<String>,
+    pub toll: Option<bool>,
+    pub unpaved: Option<bool>,
+    pub tunnel: Option<bool>,
+    pub bridge: Option<bool>,
+    pub roundabout: Option<bool>,
+    /// The edge is inside an intersection (ex: between the carriageways of a dual carriageway).
+    pub internal_intersection: Option<bool>,
+    pub drive_on_right: Option<bool>,
+    pub surface: Option<Surface>,
+    pub sign: Option<EdgeSign>,
+    pub travel_mode: Option<TravelMode>,
+    /// The type of vehicle, for drive mode (ex: `car`, `truck`).
+    pub vehicle_type: Option<String>,
+    /// The type of pedestrian, for pedestrian mode (ex: `foot`, `wheelchair`).
+    pub pedestrian_type: Option<String>,
+    /// The type of bicycle, for bicycle mode (ex: `road`, `hybrid`).
+    pub bicycle_type: Option<String>,
+    /// The type of transit, for transit mode (ex: `bus`, `metro`).
+    pub transit_type: Option<String>,
+    /// The edge's ID in the graph.
+    pub id: Option<u64>,
+    /// The OSM way which the edge is part of.
+    pub way_id: Option<u64>,
+    /// The average grade (as a percentage), weighted for its effect on routing.
+    pub weighted_grade: Option<f64>,
+    pub max_upward_grade: Option<i32>,
+    pub max_downward_grade: Option<i32>,
+    /// The mean elevation, in meters.
+    pub mean_elevation: Option<f64>,
+    pub lane_count: Option<u32>,
+    pub cycle_lane: Option<CycleLane>,
+    /// A bitmask of the bike networks which the edge is part of
+    /// (1 = national, 2 = regional, 4 = local, 8 = mountain bike).
+    pub bicycle_network: Option<u8>,
+    pub sidewalk: Option<Sidewalk>,
+    /// How built up the area is, from 0 (rural) to 15 (dense urban).
+    pub density: Option<u8>,
+    pub speed_limit: Option<SpeedLimit>,
+    /// The truck speed, in the response's units per hour.
+    pub truck_speed: Option<f64>,
+    /// The edge is part of a designated truck route.
+    pub truck_route: Option<bool>,
+    /// The node at the end of the edge.
+    pub end_node: Option<EndNode>,
+    /// Where along the edge the path starts, from 0 to 1 (for the first edge).
+    pub source_percent_along: Option<f64>,
+    /// Where along the edge the path ends, from 0 to 1 (for the last edge).
+    pub target_percent_along: Option<f64>,
+}
+
+/// The node at the end of an edge.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct EndNode {
+    /// The other edges which leave the node.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub intersecting_edges: Vec<IntersectingEdge>,
+    /// The time from the start of the path to the node, in seconds.
+    pub elapsed_time: Option<f64>,
+    /// The index of the node's admin in [`TraceAttributesResponse::admins`].
+    pub admin_index: Option<u32>,
+    /// The type of node (ex: `street_intersection`, `gate`, `bollard`, `toll_booth`).
+    #[serde(rename = "type")]
+    pub node_type: Option<String>,
+    /// The path continues along one branch of a fork.
+    pub fork: Option<bool>,
+    /// The IANA time zone of the node (ex: `Europe/Tallinn`).
+    pub time_zone: Option<String>,
+}
+
+/// An edge leaving a node, which the path doesn't take.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct IntersectingEdge {
+    /// The direction of the edge, in degrees from north.
+    pub begin_heading: Option<u16>,
+    /// The edge has a name in common with the edge before the node.
+    pub from_edge_name_consistency: Option<bool>,
+    /// The edge has a name in common with the edge after the node.
+    pub to_edge_name_consistency: Option<bool>,
+    pub driveability: Option<Traversability>,
+    pub cyclability: Option<Traversability>,
+    pub walkability: Option<Traversability>,
+    #[serde(rename = "use")]
+    pub edge_use: Option<String>,
+    pub road_class: Option<RoadClass>,
+    pub lane_count: Option<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Edge, EndNode, MatchType, MatchedPoint, RoadClass, SpeedLimit, TraceAttributesResponse,
+    };
+    use crate::Units;
+    use crate::testing::to_json_and_back;
+    use serde_json::json;
+
+    #[test]
+    fn speed_limits() {
+        assert_eq!(
+            serde_json::to_value(SpeedLimit::Limited(50)).unwrap(),
+            json!(50)
+        );
+        assert_eq!(
+            serde_json::to_value(SpeedLimit::Unlimited).unwrap(),
+            json!("unlimited")
+        );
+        assert_eq!(
+            serde_json::from_value::<SpeedLimit>(json!("unlimited")).unwrap(),
+            SpeedLimit::Unlimited
+        );
+        assert!(serde_json::from_value::<SpeedLimit>(json!("fast")).is_err());
+    }
+
+    #[test]
+    fn unset_attributes_are_omitted() {
+        let response = TraceAttributesResponse {
+            edges: vec![Edge {
+                names: Some(vec!["Narva mnt".to_string()]),
+                length: Some(0.123),
+                road_class: Some(RoadClass::Primary),
+                edge_use: Some("road".to_string()),
+                speed_limit: Some(SpeedLimit::Limited(50)),
+                end_node: Some(EndNode {
+                    admin_index: Some(0),
+                    node_type: Some("street_intersection".to_string()),
+                    ..EndNode::default()
+                }),
+                ..Edge::default()
+            }],
+            matched_points: vec![
+                MatchedPoint {
+                    match_type: MatchType::Matched,
+                    lat: 59.436_961,
+                    lon: 24.753_575,
+                    edge_index: Some(0),
+                    begin_route_discontinuity: true,
+                    distance_along_edge: Some(0.5),
+                    distance_from_trace_point: Some(3.2),
+                    ..MatchedPoint::default()
+                },
+                MatchedPoint {
+                    lat: 59.44,
+                    lon: 24.76,
+                    ..MatchedPoint::default()
+                },
+            ],
+            shape: Some("_gr~nBaxhwo@".to_string()),
+            units: Some(Units::Kilometers),
+            ..TraceAttributesResponse::default()
+        };
+        let (json, parsed) = to_json_and_back(&response);
+        assert_eq!(
+            json,
+            r#"{"edges":[{"names":["Narva mnt"],"length":0.123,"road_class":"primary","use":"road","speed_limit":50,"end_node":{"admin_index":0,"type":"street_intersection"}}],"matched_points":[{"type":"matched","lat":59.436961,"lon":24.753575,"edge_index":0,"begin_route_discontinuity":true,"distance_along_edge":0.5,"distance_from_trace_point":3.2},{"type":"unmatched","lat":59.44,"lon":24.76}],"shape":"_gr~nBaxhwo@","units":"kilometers"}"#
+        );
+        assert_eq!(parsed, response);
+    }
+}
@@ -1,18 +1,26 @@
-use std::{fs, path::PathBuf, str::FromStr};
+use std::io::{BufRead, BufReader};
+use std::{fs, num::NonZeroUsize, path::PathBuf};
 
 use anyhow::{Context, anyhow};
 use clap::{Parser, Subcommand};
+use geo::{CoordFloat, Point};
+use num_traits::FromPrimitive;
 use serde_json::Value as JsonValue;
 use tracing::info;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{EnvFilter, Layer};
-use valhalla_graphtile::tile_provider::TrafficTileProvider;
+use valhalla_graphtile::tile_provider::{
+    GraphTileProviderError, TrafficTileProvider, validate_traffic_extract,
+};
 use valhalla_graphtile::{
     GraphId,
-    graph_tile::GraphTile,
+    graph_tile::{GraphTile, GraphTileView},
     tile_provider::{DirectoryGraphTileProvider, GraphTileProvider, TarballTileProvider},
 };
+use valinor_sif::{
+    AutoCosting, AutoCostingOptions, MapMatcher, MatchConfig, MatchResult, Router, TracePoint,
+};
 
 #[derive(Parser, Debug)]
 #[command(name = "valinor-cli", author, version, about, long_about = None)]
@@ -28,31 +36,68 @@ struct Cli {
 
 #[derive(Subcommand, Debug)]
 enum Commands {
-    /// Pretty-print information about a directed edge (incl. live traffic, if available)
+    /// Pretty-print information about directed edges (incl. live traffic, if available)
+    ///
+    /// A single edge is printed as an object, and several as an array.
+    #[command(arg_required_else_help = true)]
     GetEdge {
-        /// Graph ID (u64) or slash-form level/tile/index
-        graph_id: String,
+        /// Graph IDs (u64) or slash-form level/tile/index
+        graph_ids: Vec<GraphId>,
+        /// A file listing more graph IDs, one per line (or `-` to read them from stdin)
+        ///
+        /// Blank lines and anything after a `#` are ignored.
+        #[arg(long, value_parser = read_graph_id_list)]
+        edge_list: Option<GraphIdList>,
+    },
+    /// Check every tile in the routing graph for structural corruption
+    ///
+    /// Exits with an error if any problems are found.
+    Validate,
+    /// Check that the traffic extract matches the routing graph
+    ///
+    /// Every graph tile must have a traffic tile (and vice versa)
+    /// with the same tile ID, directed edge count, and a supported version.
+    /// Exits with an error if any mismatches are found.
+    ValidateTraffic,
+    /// Match a GPS trace (GPX or CSV) to the routing graph by car, and print the result as JSON
+    ///
+    /// CSV traces have one `lat,lon[,timestamp]` point per line.
+    MatchTrace {
+        /// Path to a .gpx or .csv trace
+        trace: PathBuf,
+        /// Standard deviation of the GPS noise, in meters
+        #[arg(long)]
+        sigma_z: Option<f64>,
+        /// Tolerance for routes between points which are longer than the distance between them
+        #[arg(long)]
+        beta: Option<f64>,
+        /// Radius to search for candidate edges around each point, in meters
+        #[arg(long)]
+        search_radius: Option<f64>,
     },
 }
 
-fn parse_graph_id(input: &str) -> anyhow::Result<GraphId> {
-    // Try pure integer
-    if let Ok(id) = input.parse::<u64>() {
-        return GraphId::try_from_id(id).map_err(|e| anyhow!(e));
-    }
-
-    // Try slash-separated level/tile/index
-    let parts: Vec<&str> = input.split('/').collect();
-    if parts.len() == 3 {
-        let level = u8::from_str(parts[0]).context("invalid level in graph id")?;
-        let tile_id = u64::from_str(parts[1]).context("invalid tile id in graph id")?;
-        let index = u64::from_str(parts[2]).context("invalid index in graph id")?;
-        return GraphId::try_from_components(level, tile_id, index).map_err(|e| anyhow!(e));
-    }
+/// Graph IDs read from a list with [`read_graph_id_list`].
+#[derive(Debug, Clone)]
+struct GraphIdList(Vec<GraphId>);
 
-    Err(anyhow!(
-        "Unrecognized graph id format. Use a u64 integer or level/tile/index"
-    ))
+/// Reads a list of graph IDs from a file (or stdin for `-`), reporting every bad line.
+fn read_graph_id_list(path: &str) -> Result<GraphIdList, String> {
+    let reader: Box<dyn BufRead> = if path == "-" {
+        Box::new(std::io::stdin().lock())
+    } else {
+        let file = fs::File::open(path).map_err(|e| format!("Unable to open {path}: {e}"))?;
+        Box::new(BufReader::new(file))
+    };
+    GraphId::read_list(reader)
+        .map(GraphIdList)
+        .map_err(|errors| {
+            errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
 }
 
 #[derive(Debug, Clone)]
@@ -67,6 +112,69 @@ enum RoutingGraphDataSource {
     TileDir(PathBuf),
 }
 
+/// A routing graph opened from either of the supported data sources.
+enum RoutingGraph {
+    Tarball(TarballTileProvider<false>),
+    TileDir(Box<DirectoryGraphTileProvider>),
+}
+
+/// Opens the routing graph, preferring the tarball if the config has both.
+///
+/// `cache_size` is the number of tiles to keep in memory when reading from a tile directory.
+fn open_routing_graph(
+    source: Option<RoutingGraphDataSource>,
+    cache_size: NonZeroUsize,
+) -> anyhow::Result<RoutingGraph> {
+    match source {
+        Some(RoutingGraphDataSource::Tarball(path)) => {
+            info!(path = path.to_str(), "Using tarball tile extract");
+            Ok(RoutingGraph::Tarball(TarballTileProvider::new(&path)?))
+        }
+        Some(RoutingGraphDataSource::TileDir(path)) => {
+            info!(path = path.to_str(), "Using tile directory");
+            let provider = DirectoryGraphTileProvider::new(path, cache_size);
+            Ok(RoutingGraph::TileDir(Box::new(provider)))
+        }
+        None => Err(anyhow!(
+            "No routing graph data sources could be loaded. Expected a valid 'tile_extract' (tarball) or 'tile_dir' in the config."
+        )),
+    }
+}
+
+impl GraphTileProvider for RoutingGraph {
+    fn with_tile_containing<F, T>(
+        &self,
+        graph_id: GraphId,
+        process: F,
+    ) -> Result<T, GraphTileProviderError>
+    where
+        F: FnOnce(&GraphTileView) -> T,
+    {
+        match self {
+            Self::Tarball(provider) => provider.with_tile_containing(graph_id, process),
+            Self::TileDir(provider) => provider.with_tile_containing(graph_id, process),
+        }
+    }
+
+    fn enumerate_tiles_within_radius<N: CoordFloat + FromPrimitive>(
+        &self,
+        center: Point<N>,
+        radius: N,
+    ) -> Vec<GraphId> {
+        match self {
+            Self::Tarball(provider) => provider.enumerate_tiles_within_radius(center, radius),
+            Self::TileDir(provider) => provider.enumerate_tiles_within_radius(center, radius),
+        }
+    }
+
+    fn available_tiles(&self) -> Result<Vec<GraphId>, GraphTileProviderError> {
+        match self {
+            Self::Tarball(provider) => provider.available_tiles(),
+            Self::TileDir(provider) => provider.available_tiles(),
+        }
+    }
+}
+
 fn parse_valhalla_data_paths(path: &PathBuf) -> anyhow::Result<DataSources> {
     let bytes =
         fs::read(path).with_context(|| format!("Failed to read config at {}", path.display()))?;
@@ -98,26 +206,175 @@ fn parse_valhalla_data_paths(path: &PathBuf) -> anyhow::Result<DataSources> {
 }
 
 fn pretty_print_edge_info<T: GraphTileProvider>(
+    provider: &T,
+    traffic_provider: Option<&TrafficTileProvider<false>>,
+    graph_ids: &[GraphId],
+) -> anyhow::Result<()> {
+    let mut edges = graph_ids
+        .iter()
+        .map(|&gid| edge_info_json(provider, traffic_provider, gid))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let output = if edges.len() == 1 {
+        edges.remove(0)
+    } else {
+        JsonValue::Array(edges)
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+fn edge_info_json<T: GraphTileProvider>(
     provider: &T,
     traffic_provider: Option<&TrafficTileProvider<false>>,
     gid: GraphId,
+) -> anyhow::Result<JsonValue> {
+    provider
+        .with_tile_containing(gid, |tile| {
+            let edge = tile.get_directed_edge(gid)?;
+            let edge_info = tile.get_edge_info(edge)?;
+            let traffic_info =
+                traffic_provider.map(|tp| unsafe { tp.get_speeds_for_edge(gid).ok() });
+
+            Ok::<JsonValue, anyhow::Error>(serde_json::json!({
+                "graph_id": gid,
+                "directed_edge": edge,
+                "edge_info": edge_info,
+                "traffic": traffic_info,
+            }))
+        })?
+        .with_context(|| format!("Failed to read edge {gid}"))
+}
+
+/// Validates each of the given tiles, printing any issues found.
+///
+/// Tiles which don't exist are silently skipped.
+/// Tiles which can't be read for other reasons (ex: I/O errors) count as failures,
+/// since they haven't been checked.
+fn validate_tiles<T: GraphTileProvider>(
+    provider: &T,
+    tile_ids: impl IntoIterator<Item = GraphId>,
+) -> anyhow::Result<()> {
+    let mut tile_count: usize = 0;
+    let mut invalid_tile_count: usize = 0;
+    let mut unreadable_tile_count: usize = 0;
+    for tile_id in tile_ids {
+        let report = match provider.with_tile_containing(tile_id, |tile| tile.validate()) {
+            Ok(report) => report,
+            Err(GraphTileProviderError::TileDoesNotExist) => continue,
+            Err(e) if !e.is_data_corruption() => {
+                unreadable_tile_count += 1;
+                println!("{tile_id}: Failed to read tile: {e}");
+                continue;
+            }
+            Err(e) => {
+                tile_count += 1;
+                invalid_tile_count += 1;
+                println!("{tile_id}: {e}");
+                continue;
+            }
+        };
+
+        tile_count += 1;
+        if !report.is_valid() {
+            invalid_tile_count += 1;
+            for issue in &report.issues {
+                println!("{}: {issue}", report.graph_id);
+            }
+        }
+    }
+
+    if unreadable_tile_count > 0 {
+        Err(anyhow!(
+            "{unreadable_tile_count} tiles could not be read, and {invalid_tile_count} of {tile_count} tiles failed validation."
+        ))
+    } else if invalid_tile_count > 0 {
+        Err(anyhow!(
+            "{invalid_tile_count} of {tile_count} tiles failed validation."
+        ))
+    } else {
+        println!("Validated {tile_count} tiles; no issues found.");
+        Ok(())
+    }
+}
+
+/// Cross-checks the traffic extract against the routing graph, printing any mismatches found.
+fn validate_traffic<T: GraphTileProvider>(
+    provider: &T,
+    traffic_provider: &TrafficTileProvider<false>,
 ) -> anyhow::Result<()> {
-    let output = provider.with_tile_containing(gid, |tile| {
-        let edge = tile.get_directed_edge(gid)?;
-        let edge_info = tile.get_edge_info(edge)?;
-        let traffic_info = traffic_provider.map(|tp| unsafe { tp.get_speeds_for_edge(gid).ok() });
-
-        Ok::<JsonValue, anyhow::Error>(serde_json::json!({
-            "graph_id": gid,
-            "directed_edge": edge,
-            "edge_info": edge_info,
-            "traffic": traffic_info,
-        }))
-    })??;
+    let report = unsafe { validate_traffic_extract(provider, traffic_provider)? };
+    for issue in &report.issues {
+        println!("{issue}");
+    }
+
+    if report.is_valid() {
+        println!(
+            "Validated {} traffic tiles against {} graph tiles; no issues found.",
+            report.traffic_tile_count, report.graph_tile_count
+        );
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "The traffic extract has {} issues.",
+            report.issues.len()
+        ))
+    }
+}
+
+/// Reads a trace from a GPX or CSV file (by its extension).
+fn read_trace(path: &PathBuf) -> anyhow::Result<Vec<TracePoint>> {
+    let input = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read trace at {}", path.display()))?;
+    let is_gpx = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("gpx"));
+    let trace = if is_gpx {
+        TracePoint::parse_gpx(&input)?
+    } else {
+        TracePoint::parse_csv(&input)?
+    };
+    info!(points = trace.len(), "Read trace");
+    Ok(trace)
+}
+
+fn print_match_result(result: &MatchResult) -> anyhow::Result<()> {
+    let output = serde_json::json!({
+        "legs": result.legs.iter().map(|leg| serde_json::json!({
+            "first_index": leg.first_index,
+            "last_index": leg.last_index,
+            "length": leg.path.length,
+            "time": leg.path.cost.secs,
+            "edges": leg.path.edge_ids().collect::<Vec<_>>(),
+        })).collect::<Vec<_>>(),
+        "discontinuities": result.discontinuities.iter().map(|discontinuity| serde_json::json!({
+            "from_index": discontinuity.from_index,
+            "to_index": discontinuity.to_index,
+            "reason": format!("{:?}", discontinuity.reason),
+        })).collect::<Vec<_>>(),
+        "points": result.points.iter().map(|point| point.map(|point| serde_json::json!({
+            "edge_id": point.location.edge_id,
+            "percent_along": point.location.percent_along,
+            "lon": point.snapped_point.x,
+            "lat": point.snapped_point.y,
+            "distance": point.distance,
+            "interpolated": point.interpolated,
+        }))).collect::<Vec<_>>(),
+    });
     println!("{}", serde_json::to_string_pretty(&output)?);
     Ok(())
 }
 
+/// Matches a trace by car, printing the result.
+fn match_trace<T: GraphTileProvider>(
+    provider: T,
+    trace: &[TracePoint],
+    config: MatchConfig,
+) -> anyhow::Result<()> {
+    let router = Router::new(provider, AutoCosting::new(AutoCostingOptions::default()));
+    let result = MapMatcher::new(&router, config).match_trace(trace)?;
+    print_match_result(&result)
+}
+
 fn main() -> anyhow::Result<()> {
     tracing_subscriber::registry()
         // Standard logger, configured via the RUST_LOG env variable
@@ -125,11 +382,18 @@ fn main() -> anyhow::Result<()> {
         .init();
 
     let cli = Cli::parse();
+    let sources = parse_valhalla_data_paths(&cli.valhalla_config)?;
 
     match cli.command {
-        Commands::GetEdge { graph_id } => {
-            let gid = parse_graph_id(&graph_id)?;
-            let sources = parse_valhalla_data_paths(&cli.valhalla_config)?;
+        Commands::GetEdge {
+            mut graph_ids,
+            edge_list,
+        } => {
+            graph_ids.extend(edge_list.into_iter().flat_map(|list| list.0));
+            if graph_ids.is_empty() {
+                return Err(anyhow!("No graph IDs were given."));
+            }
+
             let traffic_extract = if let Some(path) = sources.traffic_extract {
                 info!(path = path.to_str(), "Using traffic extract");
                 Some(TrafficTileProvider::new_readonly(path)?)
@@ -138,30 +402,44 @@ fn main() -> anyhow::Result<()> {
                 None
             };
 
-            // Prefer tarball if set, fall back to directory
-            if let Some(graph) = sources.routing_graph {
-                match graph {
-                    RoutingGraphDataSource::Tarball(path) => {
-                        info!(path = path.to_str(), "Using tarball tile extract");
-
-                        let provider = TarballTileProvider::<false>::new(&path)?;
-                        pretty_print_edge_info(&provider, traffic_extract.as_ref(), gid)
-                    }
-                    RoutingGraphDataSource::TileDir(path) => {
-                        info!(path = path.to_str(), "Using tile directory");
-
-                        let provider = DirectoryGraphTileProvider::new(
-                            path,
-                            std::num::NonZeroUsize::new(1).unwrap(),
-                        );
-                        pretty_print_edge_info(&provider, traffic_extract.as_ref(), gid)
-                    }
-                }
-            } else {
-                Err(anyhow!(
-                    "No routing graph data sources could be loaded. Expected a valid 'tile_extract' (tarball) or 'tile_dir' in the config."
-                ))
-            }
+            let provider = open_routing_graph(sources.routing_graph, NonZeroUsize::MIN)?;
+            pretty_print_edge_info(&provider, traffic_extract.as_ref(), &graph_ids)
+        }
+        Commands::Validate => {
+            let provider = open_routing_graph(sources.routing_graph, NonZeroUsize::MIN)?;
+            let tile_ids = provider.available_tiles()?;
+            validate_tiles(&provider, tile_ids)
+        }
+        Commands::ValidateTraffic => {
+            let Some(traffic_path) = sources.traffic_extract else {
+                return Err(anyhow!(
+                    "No traffic extract could be loaded. Expected a valid 'traffic_extract' in the config."
+                ));
+            };
+            info!(path = traffic_path.to_str(), "Using traffic extract");
+            let traffic_extract = TrafficTileProvider::new_readonly(traffic_path)?;
+
+            let provider = open_routing_graph(sources.routing_graph, NonZeroUsize::MIN)?;
+            validate_traffic(&provider, &traffic_extract)
+        }
+        Commands::MatchTrace {
+            trace,
+            sigma_z,
+            beta,
+            search_radius,
+        } => {
+            let trace = read_trace(&trace)?;
+            let defaults = MatchConfig::default();
+            let config = defaults
+                .clone()
+                .with_sigma_z(sigma_z.unwrap_or(defaults.sigma_z))
+                .with_beta(beta.unwrap_or(defaults.beta))
+                .with_search_radius(search_radius.unwrap_or(defaults.search_radius));
+
+            // Matching revisits the same few tiles many times
+            let cache_size = NonZeroUsize::new(16).unwrap();
+            let provider = open_routing_graph(sources.routing_graph, cache_size)?;
+            match_trace(provider, &trace, config)
         }
     }
 }
@@ -1,8 +1,9 @@
 use clap::Parser;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::{BufRead, BufReader, BufWriter};
 use std::num::NonZeroUsize;
 use std::path::PathBuf;
 use std::sync::OnceLock;
@@ -11,9 +12,8 @@ use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{EnvFilter, Layer};
 use valhalla_graphtile::graph_tile::{DirectedEdge, GraphTile, GraphTileView};
-use valhalla_graphtile::tile_hierarchy::STANDARD_LEVELS;
 use valhalla_graphtile::tile_provider::{
-    DirectoryGraphTileProvider, GraphTileProvider, GraphTileProviderError, OwnedGraphTileProvider,
+    DirectoryGraphTileProvider, GraphTileProvider, OwnedGraphTileProvider,
 };
 use valhalla_graphtile::{GraphId, RoadUse};
 
@@ -53,6 +53,13 @@ struct Cli {
     #[arg(env, long)]
     skip_unnamed: bool,
 
+    /// Only exports the edges in this file (or stdin, given `-`).
+    ///
+    /// The file lists one graph ID per line, as a raw u64 or level/tile/index.
+    /// Blank lines and anything after a `#` are ignored.
+    #[arg(env, long, value_parser = read_edge_filter)]
+    edges: Option<EdgeFilter>,
+
     /// Write tippecanoe properties which will improve the PMTiles output.
     ///
     /// This is only needed if you plan to export to PMTiles later.
@@ -60,13 +67,38 @@ struct Cli {
     write_tippecanoe_properties: bool,
 }
 
+/// The set of edges to export, if not all of them.
+#[derive(Debug, Clone)]
+struct EdgeFilter(HashSet<GraphId>);
+
+/// Reads an edge filter from a file (or stdin for `-`), reporting every bad line.
+fn read_edge_filter(path: &str) -> Result<EdgeFilter, String> {
+    let reader: Box<dyn BufRead> = if path == "-" {
+        Box::new(std::io::stdin().lock())
+    } else {
+        let file = File::open(path).map_err(|e| format!("Unable to open {path}: {e}"))?;
+        Box::new(BufReader::new(file))
+    };
+    match GraphId::read_list(reader) {
+        Ok(edges) => Ok(EdgeFilter(edges.into_iter().collect())),
+        Err(errors) => Err(errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")),
+    }
+}
+
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
     let tile_path = cli.tile_path.clone();
     let reader = DirectoryGraphTileProvider::new(tile_path.clone(), NonZeroUsize::new(25).unwrap());
 
-    let should_skip_edge = |edge: &DirectedEdge, names: &Vec<Cow<str>>| {
-        (cli.skip_transit && edge.is_transit_line())
+    let should_skip_edge = |edge_id: GraphId, edge: &DirectedEdge, names: &Vec<Cow<str>>| {
+        cli.edges
+            .as_ref()
+            .is_some_and(|filter| !filter.0.contains(&edge_id))
+            || (cli.skip_transit && edge.is_transit_line())
             || (cli.skip_ferries && edge.road_use() == RoadUse::Ferry)
             || (cli.skip_unnamed && names.is_empty())
     };
@@ -88,42 +120,28 @@ fn main() -> anyhow::Result<()> {
     // TODO: Almost all code below feels like it can be abstracted into a graph traversal helper...
     // We could even make processing plugins with WASM LOL
 
-    // Enumerate edges in available tiles
-    let mut tile_set = Vec::new();
-    let mut edge_count: usize = 0;
-    for level in &*STANDARD_LEVELS {
-        // For each tile in that level...
-        let n_tiles = level.tiling_system.n_rows * level.tiling_system.n_cols;
-
-        let progress_bar = PROGRESS_STYLE.get().map(|style| {
-            let bar = ProgressBar::new(u64::from(n_tiles));
-            bar.set_message(format!("Scanning tiles in level {}...", level.level));
-            bar.set_style(style.clone());
-            bar
-        });
-
-        for tile_id in 0..n_tiles {
-            progress_bar.as_ref().inspect(|bar| bar.inc(1));
-            // Get the index pointer for each tile in the level
-            let graph_id = GraphId::try_from_components(level.level, u64::from(tile_id), 0)?;
-            match reader.get_handle_for_tile_containing(graph_id) {
-                Ok(tile) => {
-                    tile_set.push(graph_id);
-                    let tile_edge_count = tile.header().directed_edge_count() as usize;
-                    edge_count += tile_edge_count;
-                }
-                Err(GraphTileProviderError::TileDoesNotExist) => {
-                    // Ignore; not all tiles will exist for extracts
-                }
-                Err(e) => return Err(e.into()),
-            }
-        }
+    // Enumerate edges in available tiles (only those with filtered edges, if any)
+    let mut tile_set = reader.available_tiles()?;
+    if let Some(filter) = &cli.edges {
+        let filtered_tiles: HashSet<_> = filter.0.iter().map(GraphId::tile_base_id).collect();
+        tile_set.retain(|tile_id| filtered_tiles.contains(tile_id));
+    }
+    let progress_bar = PROGRESS_STYLE.get().map(|style| {
+        let bar = ProgressBar::new(tile_set.len() as u64);
+        bar.set_message(format!("Scanning {} tiles...", tile_set.len()));
+        bar.set_style(style.clone());
+        bar
+    });
 
-        progress_bar.inspect(ProgressBar::finish);
+    let mut edge_count: usize = 0;
+    for graph_id in &tile_set {
+        progress_bar.as_ref().inspect(|bar| bar.inc(1));
+        let tile = reader.get_handle_for_tile_containing(*graph_id)?;
+        edge_count += tile.header().directed_edge_count() as usize;
     }
 
-    // Drop mutability
-    let tile_set = tile_set;
+    progress_bar.inspect(ProgressBar::finish);
+
     let progress_bar = PROGRESS_STYLE.get().map(|style| {
         let bar = ProgressBar::new(edge_count as u64);
         bar.set_message(format!(
@@ -146,7 +164,6 @@ fn main() -> anyhow::Result<()> {
             export_edges_for_tile(
                 &mut writer,
                 tile,
-                *tile_id,
                 &progress_bar,
                 &should_skip_edge,
                 cli.write_tippecanoe_properties,
@@ -173,22 +190,17 @@ fn main() -> anyhow::Result<()> {
 fn export_edges_for_tile(
     writer: &mut writer::StreamingEdgeWriter,
     tile: &GraphTileView,
-    tile_id: GraphId,
     progress_bar: &Option<ProgressBar>,
-    should_skip_edge: &impl Fn(&DirectedEdge, &Vec<Cow<str>>) -> bool,
+    should_skip_edge: &impl Fn(GraphId, &DirectedEdge, &Vec<Cow<str>>) -> bool,
     write_tippecanoe_properties: bool,
 ) -> anyhow::Result<()> {
-    for index in 0..tile.header().directed_edge_count() as usize {
-        // Get the edge
-        let edge_id = tile_id.with_feature_index(index as u64)?;
-        let edge = tile.get_directed_edge(edge_id)?;
-
+    for (edge_id, edge) in tile.edges_with_ids() {
         progress_bar.as_ref().inspect(|bar| bar.inc(1));
 
         // Skip certain edge types based on the config
         let edge_info = tile.get_edge_info(edge)?;
         let names = edge_info.get_names();
-        if should_skip_edge(edge, &names) {
+        if should_skip_edge(edge_id, edge, &names) {
             continue;
         }
 
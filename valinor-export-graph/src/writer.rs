@@ -37,6 +37,12 @@ impl<'a> StreamingEdgeWriter<'a> {
         Ok(pidx + 1)
     }
 
+    fn prop_u16(&mut self, colname: &str, value: u16, pidx: usize) -> Result<usize, GeozeroError> {
+        self.fgb
+            .property(pidx, colname, &ColumnValue::UShort(value))?;
+        Ok(pidx + 1)
+    }
+
     fn prop_u64(&mut self, colname: &str, value: u64, pidx: usize) -> Result<usize, GeozeroError> {
         self.fgb
             .property(pidx, colname, &ColumnValue::ULong(value))?;
@@ -158,13 +164,25 @@ impl<'a> StreamingEdgeWriter<'a> {
                 .collect::<String>(),
             pidx,
         )?;
+        let pidx = self.prop_bool("is_bike_network", edge.is_bike_network(), pidx)?;
+        let pidx = self.prop_string("cycle_lane", &format!("{:?}", edge.cycle_lane()), pidx)?;
         let pidx = self.prop_bool("truck_route", edge.truck_route(), pidx)?;
         let pidx = self.prop_bool(
             "is_internal_intersection",
             edge.is_intersection_internal(),
             pidx,
         )?;
-        let _pidx = self.prop_bool("is_shortcut", edge.is_shortcut(), pidx)?;
+        let pidx = self.prop_bool("is_shortcut", edge.is_shortcut(), pidx)?;
+
+        // Headings (in whole degrees, in the direction of travel).
+        // These are left empty for degenerate edges with no length.
+        if let Some((begin, end)) = edge_info.headings(edge.edge_info_is_forward())? {
+            // Headings are in the range [0, 360), so this won't truncate
+            #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let whole_degrees = |heading: f64| heading.round() as u16 % 360;
+            let pidx = self.prop_u16("begin_heading", whole_degrees(begin), pidx)?;
+            self.prop_u16("end_heading", whole_degrees(end), pidx)?;
+        }
 
         self.fgb.properties_end()?;
 
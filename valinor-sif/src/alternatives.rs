@@ -0,0 +1,180 @@
+//! # Alternative routes
+//!
+//! Alternatives are found with the penalty method:
+//! after each search, the edges of the route it found are made more expensive,
+//! and the search is repeated, which pushes it onto different roads.
+//! Each candidate is accepted if it doesn't share too much of its length with the routes found so far,
+//! and isn't too much more expensive than the best route (with the penalties left out).
+//!
+//! Penalties build up on edges which keep being chosen,
+//! so later searches eventually leave even the roads which every sensible route uses.
+//! The number of searches is capped, since it may take several to find a good alternative (or none).
+
+use crate::search::{SearchContext, penalized_path};
+use crate::{Costing, Path, RouteOptions, RoutingError};
+use std::collections::{HashMap, HashSet};
+use valhalla_graphtile::GraphId;
+use valhalla_graphtile::tile_provider::GraphTileProvider;
+
+/// Options for finding alternative routes.
+///
+/// The defaults are similar to Valhalla's.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlternativeOptions {
+    /// The maximum number of alternatives to find (besides the best route).
+    pub count: usize,
+    /// The maximum fraction of an alternative's length which it may share
+    /// with the best route or any other alternative.
+    pub max_share: f64,
+    /// The maximum cost of an alternative, relative to the cost of the best route
+    /// (ex: 1.25 allows alternatives to cost 25% more).
+    pub max_stretch: f32,
+    /// The factor applied to the cost of each edge of a route once it has been found.
+    pub penalty_factor: f32,
+    /// The maximum number of searches to run after the first.
+    pub max_searches: usize,
+}
+
+impl Default for AlternativeOptions {
+    fn default() -> Self {
+        Self {
+            count: 1,
+            max_share: 0.75,
+            max_stretch: 1.25,
+            penalty_factor: 1.5,
+            max_searches: 8,
+        }
+    }
+}
+
+impl AlternativeOptions {
+    #[must_use]
+    pub const fn with_count(mut self, count: usize) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// The fraction of a candidate's length which it shares with any of the routes.
+    fn shared_fraction(candidate: &Path, routes: &[Path]) -> f64 {
+        if candidate.length <= 0.0 {
+            return 1.0;
+        }
+        let used: HashSet<GraphId> = routes.iter().flat_map(Path::edge_ids).collect();
+        let shared: f64 = candidate
+            .edges
+            .iter()
+            .filter(|edge| used.contains(&edge.edge_id))
+            .map(|edge| edge.length)
+            .sum();
+        shared / candidate.length
+    }
+
+    /// Does a candidate differ enough from the routes found so far, without costing too much more?
+    fn accepts(&self, candidate: &Path, routes: &[Path]) -> bool {
+        let best = &routes[0];
+        candidate.cost.cost <= best.cost.cost * self.max_stretch
+            && Self::shared_fraction(candidate, routes) <= self.max_share
+    }
+}
+
+/// Finds the best path between two nodes, followed by up to `alternatives.count` alternatives.
+///
+/// Alternatives are in the order they were found, which is usually (but not always)
+/// the order of increasing cost.
+///
+/// # Errors
+///
+/// Fails like [`crate::Router::route`] if the best path can't be found.
+/// Once it has been, a failed search for an alternative (ex: due to the expansion limit)
+/// ends the search for alternatives instead.
+#[expect(clippy::too_many_arguments, reason = "Mirrors penalized_path")]
+pub(crate) fn alternative_paths<P: GraphTileProvider, C: Costing>(
+    provider: &P,
+    costing: &C,
+    context: &SearchContext,
+    origin: GraphId,
+    destination: GraphId,
+    avoided: &HashSet<GraphId>,
+    options: &RouteOptions,
+    alternatives: &AlternativeOptions,
+) -> Result<Vec<Path>, RoutingError> {
+    let search = |penalties: &HashMap<GraphId, f32>| {
+        penalized_path(
+            provider,
+            costing,
+            context,
+            origin,
+            destination,
+            avoided,
+            penalties,
+            options,
+        )
+    };
+
+    let mut penalties = HashMap::new();
+    let best = Path::from_edges(search(&penalties)?.ok_or(RoutingError::NoRoute)?);
+    if best.edges.is_empty() {
+        return Ok(vec![best]);
+    }
+
+    let mut candidate = best.clone();
+    let mut routes = vec![best];
+    for _ in 0..alternatives.max_searches {
+        if routes.len() > alternatives.count {
+            break;
+        }
+        for edge_id in candidate.edge_ids() {
+            *penalties.entry(edge_id).or_insert(1.0) *= alternatives.penalty_factor;
+        }
+        let Ok(Some(edges)) = search(&penalties) else {
+            break;
+        };
+
+        candidate = Path::from_edges(edges);
+        if alternatives.accepts(&candidate, &routes) {
+            routes.push(candidate.clone());
+        }
+    }
+
+    Ok(routes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AlternativeOptions;
+    use crate::{Cost, Path, PathEdge};
+    use valhalla_graphtile::GraphId;
+
+    fn path(edges: &[(u64, f32)]) -> Path {
+        Path::from_edges(
+            edges
+                .iter()
+                .map(|&(index, cost)| PathEdge {
+                    edge_id: GraphId::try_from_components(2, 763_926, index).unwrap(),
+                    cost: Cost::new(cost, cost),
+                    length: 10.0,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_accepts() {
+        let options = AlternativeOptions::default();
+        let best = path(&[(0, 1.0), (1, 1.0), (2, 1.0), (3, 1.0)]);
+        let routes = [best];
+
+        // Shares half of its length, and costs 25% more
+        let candidate = path(&[(0, 1.0), (4, 1.0), (5, 2.0), (3, 1.0)]);
+        assert!((AlternativeOptions::shared_fraction(&candidate, &routes) - 0.5).abs() < 1e-9);
+        assert!(options.accepts(&candidate, &routes));
+
+        // Too similar
+        let candidate = path(&[(0, 1.0), (1, 1.0), (2, 1.0), (5, 1.0), (3, 1.0)]);
+        assert!(!options.accepts(&candidate, &routes));
+
+        // Too expensive
+        let candidate = path(&[(4, 1.0), (5, 2.0), (6, 3.0)]);
+        assert!(!options.accepts(&candidate, &routes));
+    }
+}
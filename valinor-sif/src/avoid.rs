@@ -0,0 +1,139 @@
+//! # Avoid areas
+//!
+//! Routes can exclude parts of the map,
+//! like Valhalla's `exclude_polygons` and `exclude_locations` request parameters.
+//! Before the search starts, the edges whose shape intersects an area are collected
+//! (using the tiles' spatial indexes when the provider has them and the `spatial-index` feature is on),
+//! and the search skips those edges during expansion.
+
+use geo::{BoundingRect, Coord, Distance, Haversine, Intersects, LineString, Point, Polygon, Rect};
+use std::collections::{HashMap, HashSet};
+use valhalla_graphtile::GraphId;
+use valhalla_graphtile::graph_tile::GraphTile;
+use valhalla_graphtile::spatial::{bbox_with_center, closest_point_on_line};
+use valhalla_graphtile::tile_hierarchy::tiles_for_bbox;
+use valhalla_graphtile::tile_provider::{GraphTileProvider, GraphTileProviderError};
+
+/// A circular area for a route to avoid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AvoidLocation {
+    /// The center of the area (longitude, latitude).
+    pub coordinate: Coord<f64>,
+    /// The radius of the area, in meters.
+    ///
+    /// Edges which pass within this distance of the coordinate are avoided.
+    pub radius: f64,
+}
+
+impl AvoidLocation {
+    pub const fn new(coordinate: Coord<f64>, radius: f64) -> Self {
+        Self { coordinate, radius }
+    }
+
+    fn bbox(&self) -> Rect<f64> {
+        let (north, east, south, west) = bbox_with_center(Point(self.coordinate), self.radius);
+        Rect::new(Coord { x: west, y: south }, Coord { x: east, y: north })
+    }
+
+    fn intersects(&self, shape: &[Coord<f64>]) -> bool {
+        closest_point_on_line(self.coordinate, shape).is_some_and(|(closest, _)| {
+            Haversine.distance(Point(self.coordinate), Point(closest)) <= self.radius
+        })
+    }
+}
+
+/// An area to avoid, with its bounding box.
+enum AvoidArea<'a> {
+    Polygon(&'a Polygon<f64>, Rect<f64>),
+    Location(&'a AvoidLocation, Rect<f64>),
+}
+
+impl AvoidArea<'_> {
+    const fn bbox(&self) -> Rect<f64> {
+        match self {
+            Self::Polygon(_, bbox) | Self::Location(_, bbox) => *bbox,
+        }
+    }
+
+    fn intersects(&self, shape: &[Coord<f64>]) -> bool {
+        match self {
+            Self::Polygon(polygon, _) => polygon.intersects(&LineString::from(shape.to_vec())),
+            Self::Location(location, _) => location.intersects(shape),
+        }
+    }
+}
+
+/// Finds the directed edges (on any hierarchy level) whose shape intersects an area.
+///
+/// Both directions of an edge share its shape, so they are always avoided together.
+/// Tiles which don't exist in the graph are skipped.
+///
+/// # Errors
+///
+/// Fails if a tile can't be loaded, or an edge shape can't be decoded.
+pub(crate) fn avoided_edges(
+    provider: &impl GraphTileProvider,
+    polygons: &[Polygon<f64>],
+    locations: &[AvoidLocation],
+) -> Result<HashSet<GraphId>, GraphTileProviderError> {
+    let areas = polygons
+        .iter()
+        .filter_map(|polygon| {
+            polygon
+                .bounding_rect()
+                .map(|bbox| AvoidArea::Polygon(polygon, bbox))
+        })
+        .chain(
+            locations
+                .iter()
+                .map(|location| AvoidArea::Location(location, location.bbox())),
+        );
+
+    let mut avoided = HashSet::new();
+    for area in areas {
+        let bbox = area.bbox();
+        let (north, east, south, west) = (bbox.max().y, bbox.max().x, bbox.min().y, bbox.min().x);
+        for tile_id in tiles_for_bbox(north, east, south, west) {
+            #[cfg(feature = "spatial-index")]
+            let spatial_index = match provider.edge_spatial_index(tile_id) {
+                Err(GraphTileProviderError::TileDoesNotExist) => continue,
+                result => result?,
+            };
+            let result = provider.with_tile_containing(tile_id, |tile| {
+                #[cfg(feature = "spatial-index")]
+                let candidates: Vec<_> = match &spatial_index {
+                    Some(index) => index.edges_in_bbox(north, east, south, west).collect(),
+                    None => tile.edges_with_ids().map(|(edge_id, _)| edge_id).collect(),
+                };
+                #[cfg(not(feature = "spatial-index"))]
+                let candidates: Vec<_> =
+                    tile.edges_with_ids().map(|(edge_id, _)| edge_id).collect();
+                // Opposing edges share a shape, so each one only needs to be checked once
+                let mut intersects_by_shape = HashMap::new();
+                for edge_id in candidates {
+                    let edge = tile.get_directed_edge(edge_id)?;
+                    let intersects = if let Some(&intersects) =
+                        intersects_by_shape.get(&edge.edge_info_offset())
+                    {
+                        intersects
+                    } else {
+                        let shape = tile.get_edge_info(edge)?.decode_raw_shape::<f64>()?;
+                        let intersects = area.intersects(&shape);
+                        intersects_by_shape.insert(edge.edge_info_offset(), intersects);
+                        intersects
+                    };
+                    if intersects {
+                        avoided.insert(edge_id);
+                    }
+                }
+                Ok::<_, GraphTileProviderError>(())
+            });
+            match result {
+                Err(GraphTileProviderError::TileDoesNotExist) => {}
+                result => result??,
+            }
+        }
+    }
+
+    Ok(avoided)
+}
@@ -0,0 +1,343 @@
+//! # Costing
+//!
+//! Costing models decide which edges a route may use, and how expensive they are.
+//! The search minimizes the abstract [`Cost::cost`],
+//! which lets models express preferences (ex: avoiding tolls) beyond the travel time.
+
+mod auto;
+mod motor_scooter;
+mod options;
+mod truck;
+mod turn;
+
+pub use auto::{AutoCosting, AutoCostingOptions};
+pub use motor_scooter::{MotorScooterCosting, MotorScooterCostingOptions};
+pub use options::{CostingModel, CostingOptions, CostingOptionsError};
+pub use truck::{TruckCosting, TruckCostingOptions, TruckProfile};
+pub(crate) use turn::TurnType;
+
+use crate::TimeInfo;
+use std::ops::{Add, AddAssign};
+use valhalla_graphtile::graph_tile::{
+    AccessRestrictionType, DirectedEdge, GraphTile, GraphTileView, NodeInfo,
+};
+use valhalla_graphtile::traffic_tile::TrafficSpeed;
+use valhalla_graphtile::{Access, GraphId};
+
+/// Meters per kilometer divided by seconds per hour, for converting kph to m/s.
+const KPH_TO_METERS_PER_SECOND: f32 = 1.0 / 3.6;
+
+/// The cost of traversing (part of) a path.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Cost {
+    /// The abstract cost which the search minimizes.
+    pub cost: f32,
+    /// The estimated travel time, in seconds.
+    pub secs: f32,
+}
+
+impl Cost {
+    pub const ZERO: Self = Self::new(0.0, 0.0);
+
+    pub const fn new(cost: f32, secs: f32) -> Self {
+        Self { cost, secs }
+    }
+}
+
+impl Add for Cost {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.cost + rhs.cost, self.secs + rhs.secs)
+    }
+}
+
+impl AddAssign for Cost {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+/// What the search knows about an edge traversal, beyond the edge itself.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct EdgeContext {
+    /// When the edge is traversed, if the route has a departure or arrival time.
+    pub time: Option<TimeInfo>,
+    /// The live traffic speed of the edge, if the model uses live traffic and there is any.
+    pub live_speed: Option<TrafficSpeed>,
+    /// How much the live speed counts, from 0 (not at all) to 1 (it replaces the historical speed).
+    ///
+    /// This decays with distance from the origin, since live speeds are only accurate for now.
+    pub live_weight: f32,
+}
+
+/// A costing model, which determines the edges a route may use and their costs.
+///
+/// Edges are always evaluated in their direction of travel,
+/// along with the tile which contains them
+/// (for looking up access restrictions, predicted speeds, etc.).
+pub trait Costing {
+    /// The travel mode which this model routes for.
+    fn access_mode(&self) -> Access;
+
+    /// Does the model use live traffic?
+    ///
+    /// If so, the search looks up live speeds for each edge,
+    /// and skips edges which live traffic reports as closed.
+    /// The default implementation doesn't.
+    fn uses_live_traffic(&self) -> bool {
+        false
+    }
+
+    /// A lower bound on the cost per meter of any path, for the A* heuristic.
+    ///
+    /// This must never be greater than the cost of an edge (or path) divided by its length,
+    /// or the search may miss the best path.
+    /// The default implementation is zero, which disables the heuristic.
+    fn astar_cost_factor(&self) -> f32 {
+        0.0
+    }
+
+    /// Should routes stay out of not-thru regions and destination-only roads
+    /// (except at either end of the route)?
+    ///
+    /// These regions are marked for motor vehicles,
+    /// so the default implementation doesn't restrict them.
+    fn prunes_restricted_regions(&self) -> bool {
+        false
+    }
+
+    /// Can the edge be traversed?
+    ///
+    /// `context` has the time of travel, when it is known,
+    /// for evaluating restrictions which only apply at certain times.
+    /// The default implementation checks the forward access of the edge for the travel mode,
+    /// and its timed access restrictions.
+    fn edge_allowed(
+        &self,
+        edge_id: GraphId,
+        edge: &DirectedEdge,
+        tile: &GraphTileView,
+        context: &EdgeContext,
+    ) -> bool {
+        let access_mode = self.access_mode();
+        edge.forward_access().contains(access_mode)
+            && allowed_at_time(edge_id, edge, tile, access_mode, context.time.as_ref())
+    }
+
+    /// The cost of traversing the whole edge.
+    ///
+    /// `context` has the time of travel and live traffic, when they are known.
+    fn edge_cost(
+        &self,
+        edge_id: GraphId,
+        edge: &DirectedEdge,
+        tile: &GraphTileView,
+        context: &EdgeContext,
+    ) -> Cost;
+
+    /// The cost of turning from `predecessor` onto `edge` at `node`.
+    ///
+    /// The default implementation is free.
+    fn transition_cost(
+        &self,
+        node: &NodeInfo,
+        predecessor: &DirectedEdge,
+        edge: &DirectedEdge,
+    ) -> Cost {
+        let _ = (node, predecessor, edge);
+        Cost::ZERO
+    }
+}
+
+/// Does the edge allow the access mode at the time of travel,
+/// according to its timed access restrictions?
+///
+/// Like Valhalla, timed restrictions are ignored when the time is unknown.
+/// Otherwise, the edge is denied during any `TimedDenied` range,
+/// and if it has `TimedAllowed` ranges, it is only allowed during one of them.
+pub(crate) fn allowed_at_time(
+    edge_id: GraphId,
+    edge: &DirectedEdge,
+    tile: &GraphTileView,
+    access_mode: Access,
+    time_info: Option<&TimeInfo>,
+) -> bool {
+    let Some(time_info) = time_info else {
+        return true;
+    };
+    if !edge.access_restriction_modes().contains(access_mode) {
+        return true;
+    }
+    let Ok(index) = u32::try_from(edge_id.feature_index()) else {
+        return true;
+    };
+
+    let mut allowed = None;
+    for restriction in tile.get_access_restrictions(index, access_mode.into()) {
+        let Some(time_domain) = restriction.time_domain() else {
+            continue;
+        };
+        let in_range = time_domain.contains(time_info.local_time);
+        match restriction.restriction_type() {
+            AccessRestrictionType::TimedDenied if in_range => return false,
+            AccessRestrictionType::TimedAllowed => {
+                allowed = Some(allowed.unwrap_or(false) || in_range);
+            }
+            _ => {}
+        }
+    }
+
+    allowed.unwrap_or(true)
+}
+
+/// A minimal costing model which finds the fastest route at each edge's default speed.
+///
+/// This has no preferences beyond travel time,
+/// which makes it useful for testing and for comparing against more complex models.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeCosting {
+    access_mode: Access,
+}
+
+impl TimeCosting {
+    pub const fn new(access_mode: Access) -> Self {
+        Self { access_mode }
+    }
+}
+
+impl Costing for TimeCosting {
+    fn access_mode(&self) -> Access {
+        self.access_mode
+    }
+
+    fn astar_cost_factor(&self) -> f32 {
+        // The time per meter at the fastest speed that an edge can have
+        1.0 / (f32::from(u8::MAX) * KPH_TO_METERS_PER_SECOND)
+    }
+
+    fn edge_cost(
+        &self,
+        _edge_id: GraphId,
+        edge: &DirectedEdge,
+        _tile: &GraphTileView,
+        _context: &EdgeContext,
+    ) -> Cost {
+        #[expect(
+            clippy::cast_precision_loss,
+            reason = "Edge lengths are well within the exactly representable range of an f32"
+        )]
+        let length = edge.length() as f32;
+        // Guard against zero speeds (ex: edges which aren't meant for driving)
+        let speed = f32::from(edge.speed().max(1)) * KPH_TO_METERS_PER_SECOND;
+        let secs = length / speed;
+        Cost::new(secs, secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AutoCosting, Costing, EdgeContext, TimeInfo, TruckCosting};
+    use chrono::{NaiveDate, Offset, Utc};
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use valhalla_graphtile::graph_tile::{
+        AccessRestriction, AccessRestrictionType, GraphTile, GraphTileBuilder,
+        OwnedGraphTileHandle, TimeDomain,
+    };
+    use valhalla_graphtile::tile_provider::{DirectoryGraphTileProvider, OwnedGraphTileProvider};
+    use valhalla_graphtile::{Access, GraphId};
+
+    #[test]
+    fn test_timed_access_restrictions() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::MIN);
+        let tile_id = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let original = provider.get_handle_for_tile_containing(tile_id).unwrap();
+        let costing = AutoCosting::default();
+
+        let mut unrestricted = original.edges_with_ids().filter(|(edge_id, edge)| {
+            edge.access_restriction_modes().is_empty()
+                && costing.edge_allowed(
+                    *edge_id,
+                    edge,
+                    original.borrow_dependent(),
+                    &EdgeContext::default(),
+                )
+        });
+        let (denied_id, _) = unrestricted.next().unwrap();
+        let (allowed_id, _) = unrestricted.next().unwrap();
+
+        // Mo-Fr 07:00-09:00
+        let rush_hour = TimeDomain {
+            dow_mask: 0b011_1110,
+            begin_hours: 7,
+            end_hours: 9,
+            ..TimeDomain::default()
+        }
+        .try_to_raw()
+        .unwrap();
+        let restriction = |restriction_type| {
+            AccessRestriction::new(restriction_type, Access::Auto.into(), rush_hour)
+        };
+        let index = |edge_id: GraphId| usize::try_from(edge_id.feature_index()).unwrap();
+        let tile = OwnedGraphTileHandle::try_from(
+            GraphTileBuilder::from(&*original)
+                .with_access_restriction(
+                    index(denied_id),
+                    restriction(AccessRestrictionType::TimedDenied),
+                )
+                .unwrap()
+                .with_access_restriction(
+                    index(allowed_id),
+                    restriction(AccessRestrictionType::TimedAllowed),
+                )
+                .unwrap()
+                .into_bytes()
+                .unwrap(),
+        )
+        .unwrap();
+
+        let context = |hour: Option<u32>| {
+            // A Monday
+            let time_info = hour.map(|hour| {
+                let local = NaiveDate::from_ymd_opt(2025, 6, 2)
+                    .unwrap()
+                    .and_hms_opt(hour, 0, 0)
+                    .unwrap();
+                TimeInfo::at(&Utc.fix(), 0, local.and_utc())
+            });
+            EdgeContext {
+                time: time_info,
+                ..EdgeContext::default()
+            }
+        };
+        let allowed = |costing: &dyn Costing, edge_id: GraphId, hour| {
+            let edge = tile.get_directed_edge(edge_id).unwrap();
+            costing.edge_allowed(edge_id, edge, tile.borrow_dependent(), &context(hour))
+        };
+
+        // Timed restrictions don't apply when the time is unknown
+        assert!(allowed(&costing, denied_id, None));
+        assert!(allowed(&costing, allowed_id, None));
+
+        assert!(!allowed(&costing, denied_id, Some(8)));
+        assert!(allowed(&costing, denied_id, Some(10)));
+        assert!(allowed(&costing, allowed_id, Some(8)));
+        assert!(!allowed(&costing, allowed_id, Some(10)));
+
+        // Restrictions for other modes don't apply
+        let truck = TruckCosting::default();
+        let edge = tile.get_directed_edge(denied_id).unwrap();
+        assert_eq!(
+            allowed(&truck, denied_id, Some(8)),
+            truck.edge_allowed(
+                denied_id,
+                edge,
+                tile.borrow_dependent(),
+                &EdgeContext::default()
+            )
+        );
+    }
+}
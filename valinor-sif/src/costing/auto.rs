@@ -0,0 +1,591 @@
+//! # Automobile costing
+//!
+//! A port of Valhalla's `autocost`.
+//! Edge costs are the travel time scaled by a factor for the road's class, surface, density, etc.,
+//! and transitions add fixed costs for things like gates, toll booths, and ferries,
+//! plus the delay of turning at intersections.
+
+use super::turn::{MAX_LOCAL_EDGES, TurnType};
+use super::{Cost, Costing, EdgeContext, KPH_TO_METERS_PER_SECOND, allowed_at_time};
+use enumset::EnumSet;
+use valhalla_graphtile::graph_tile::{
+    DirectedEdge, GraphTile, GraphTileView, NodeInfo, SpeedSource,
+};
+use valhalla_graphtile::{Access, GraphId, RoadUse, Surface};
+
+/// The fastest speed that any vehicle is assumed to travel, in kph.
+pub(crate) const MAX_SPEED_KPH: f32 = 252.0;
+/// The maximum penalty for a ferry, applied when `use_ferry` is 0 (8 hours).
+const MAX_FERRY_PENALTY: f32 = 8.0 * 60.0 * 60.0;
+/// The highway factor when `use_highways` is 0.
+const MAX_HIGHWAY_BIAS_FACTOR: f32 = 8.0;
+/// The toll factor when `use_tolls` is 0.
+const MAX_TOLL_BIAS_FACTOR: f32 = 8.0;
+
+/// Weights the highway factor by road class (index = [`RoadClass`](valhalla_graphtile::RoadClass) discriminant).
+const HIGHWAY_FACTOR: [f32; 8] = [1.0, 0.5, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+
+/// Scales intersection delays by node density (index = [`NodeInfo::density`]).
+const TRANSITION_DENSITY_FACTOR: [f32; 16] = [
+    1.0, 1.0, 1.0, 1.0, 1.0, 1.1, 1.2, 1.3, 1.4, 1.6, 1.9, 2.2, 2.5, 2.8, 3.1, 3.5,
+];
+/// The extra time (in seconds) to get on or off a ramp.
+const RAMP_TRANSITION_COST: f32 = 1.5;
+
+// Node types (see `graphconstants.h` in Valhalla)
+pub(crate) const NODE_TYPE_GATE: u8 = 1;
+pub(crate) const NODE_TYPE_TOLL_BOOTH: u8 = 3;
+pub(crate) const NODE_TYPE_BORDER_CONTROL: u8 = 10;
+
+/// Options for [`AutoCosting`].
+///
+/// The defaults match Valhalla's.
+/// Preferences (`use_*`) range from 0 (avoid) to 1 (prefer), with 0.5 being neutral.
+/// Costs are in seconds and add to both the cost and the travel time,
+/// while penalties only add to the cost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoCostingOptions {
+    pub use_highways: f32,
+    pub use_tolls: f32,
+    pub use_ferry: f32,
+    pub use_living_streets: f32,
+    pub use_tracks: f32,
+    /// The fastest the vehicle will travel, in kph.
+    pub top_speed: f32,
+    /// Travel at this speed on every edge (in kph), ignoring the speeds in the graph.
+    pub fixed_speed: Option<f32>,
+    /// Minimize distance instead of time.
+    pub shortest: bool,
+    /// Avoid unpaved roads entirely.
+    pub exclude_unpaved: bool,
+    /// The speed sources to use when selecting edge speeds.
+    pub flow_mask: EnumSet<SpeedSource>,
+    /// Scales the cost of roads with a rough surface.
+    pub surface_factor: f32,
+    pub alley_factor: f32,
+    pub service_factor: f32,
+    pub destination_only_penalty: f32,
+    pub private_access_penalty: f32,
+    pub service_penalty: f32,
+    pub gate_cost: f32,
+    pub gate_penalty: f32,
+    pub toll_booth_cost: f32,
+    pub toll_booth_penalty: f32,
+    pub ferry_cost: f32,
+    pub country_crossing_cost: f32,
+    pub country_crossing_penalty: f32,
+    /// The penalty for a U-turn, except at dead ends where it's the only option.
+    pub u_turn_penalty: f32,
+    /// The penalty for passing through a traffic signal.
+    ///
+    /// The stop impact of an intersection already accounts for signals,
+    /// so this only needs to be set to avoid them more strongly.
+    pub traffic_signal_penalty: f32,
+}
+
+impl Default for AutoCostingOptions {
+    fn default() -> Self {
+        Self {
+            use_highways: 1.0,
+            use_tolls: 0.5,
+            use_ferry: 0.5,
+            use_living_streets: 0.1,
+            use_tracks: 0.0,
+            top_speed: 140.0,
+            fixed_speed: None,
+            shortest: false,
+            exclude_unpaved: false,
+            flow_mask: EnumSet::all(),
+            surface_factor: 0.5,
+            alley_factor: 1.0,
+            service_factor: 1.0,
+            destination_only_penalty: 600.0,
+            private_access_penalty: 450.0,
+            service_penalty: 15.0,
+            gate_cost: 30.0,
+            gate_penalty: 300.0,
+            toll_booth_cost: 15.0,
+            toll_booth_penalty: 0.0,
+            ferry_cost: 300.0,
+            country_crossing_cost: 600.0,
+            country_crossing_penalty: 0.0,
+            u_turn_penalty: 15.0,
+            traffic_signal_penalty: 0.0,
+        }
+    }
+}
+
+/// Converts a preference between 0 and 1 into a cost factor
+/// which is 1 at 0.5, rises to `max_factor` at 0, and falls to 0.5 at 1.
+fn preference_factor(preference: f32, max_factor: f32) -> f32 {
+    if preference < 0.5 {
+        (max_factor - 1.0).mul_add(1.0 - preference * 2.0, 1.0)
+    } else {
+        1.5 - preference
+    }
+}
+
+/// Converts a preference between 0 and 1 into an additive bias,
+/// which is a large penalty at 0, neutral at 0.5, and a slight bonus at 1.
+fn bias_factor(preference: f32, max_factor: f32) -> f32 {
+    if preference >= 0.5 {
+        (0.5 - preference).powi(3)
+    } else {
+        max_factor * (1.0 - preference * 2.0).powi(2)
+    }
+}
+
+/// The relative cost of each surface type.
+const fn surface_factor(surface: &Surface) -> f32 {
+    match surface {
+        Surface::PavedSmooth | Surface::Paved | Surface::PavedRough => 0.0,
+        Surface::Compacted => 0.1,
+        Surface::Dirt => 0.2,
+        Surface::Gravel => 0.5,
+        Surface::Path | Surface::Impassable => 1.0,
+    }
+}
+
+/// Is the surface unpaved?
+pub(crate) const fn is_unpaved(surface: &Surface) -> bool {
+    !matches!(
+        surface,
+        Surface::PavedSmooth | Surface::Paved | Surface::PavedRough
+    )
+}
+
+/// A costing model for cars, ported from Valhalla's `autocost`.
+#[derive(Debug, Clone)]
+pub struct AutoCosting {
+    options: AutoCostingOptions,
+    highway_factor: f32,
+    toll_factor: f32,
+    ferry_factor: f32,
+    ferry_transition: Cost,
+    living_street_factor: f32,
+    track_factor: f32,
+}
+
+impl AutoCosting {
+    pub fn new(options: AutoCostingOptions) -> Self {
+        let ferry_penalty = if options.use_ferry < 0.5 {
+            MAX_FERRY_PENALTY * (1.0 - options.use_ferry * 2.0)
+        } else {
+            0.0
+        };
+        Self {
+            highway_factor: bias_factor(options.use_highways, MAX_HIGHWAY_BIAS_FACTOR),
+            toll_factor: bias_factor(options.use_tolls, MAX_TOLL_BIAS_FACTOR),
+            ferry_factor: preference_factor(options.use_ferry, 10.0),
+            ferry_transition: Cost::new(options.ferry_cost + ferry_penalty, options.ferry_cost),
+            living_street_factor: preference_factor(options.use_living_streets, 3.0),
+            track_factor: preference_factor(options.use_tracks, 5.0),
+            options,
+        }
+    }
+
+    pub const fn options(&self) -> &AutoCostingOptions {
+        &self.options
+    }
+
+    /// The speed to travel on an edge, in kph.
+    ///
+    /// Predicted speeds are only used when the time of travel is known.
+    /// Live speeds are blended with the historical speed according to their weight.
+    pub(crate) fn edge_speed(
+        edge_id: GraphId,
+        edge: &DirectedEdge,
+        tile: &GraphTileView,
+        context: &EdgeContext,
+        flow_mask: EnumSet<SpeedSource>,
+    ) -> f32 {
+        let seconds_of_week = context.time.map(|time| time.seconds_of_week);
+        let speed = |live| {
+            tile.speed_for_edge(edge_id, seconds_of_week, live, flow_mask)
+                .map_or_else(|_| f32::from(edge.speed()), |speed| speed.kph)
+        };
+
+        let historical = speed(None);
+        match context.live_speed {
+            Some(live) if context.live_weight > 0.0 => {
+                (speed(Some(live)) - historical).mul_add(context.live_weight, historical)
+            }
+            _ => historical,
+        }
+    }
+
+    /// Can a vehicle with the given access mode traverse the edge (at the time of travel)?
+    ///
+    /// This is shared by the other motor vehicle costing models.
+    pub(crate) fn allows(
+        &self,
+        edge_id: GraphId,
+        edge: &DirectedEdge,
+        tile: &GraphTileView,
+        access_mode: Access,
+        context: &EdgeContext,
+    ) -> bool {
+        let surface = edge.surface();
+        edge.forward_access().contains(access_mode)
+            && surface != Surface::Impassable
+            && !(self.options.exclude_unpaved && is_unpaved(&surface))
+            && edge.road_use() != RoadUse::Construction
+            && allowed_at_time(edge_id, edge, tile, access_mode, context.time.as_ref())
+    }
+
+    /// The cost of traversing an edge at `speed` (in kph).
+    pub(crate) fn cost_at_speed(&self, edge: &DirectedEdge, speed: f32) -> Cost {
+        let travel_speed = self.options.fixed_speed.unwrap_or(match edge.road_use() {
+            // Ferries run on a schedule, so the top speed doesn't apply
+            RoadUse::Ferry | RoadUse::RailFerry => speed,
+            _ => speed.min(self.options.top_speed),
+        });
+        #[expect(
+            clippy::cast_precision_loss,
+            reason = "Edge lengths are well within the exactly representable range of an f32"
+        )]
+        let length = edge.length() as f32;
+        let secs = length / (travel_speed.clamp(1.0, MAX_SPEED_KPH) * KPH_TO_METERS_PER_SECOND);
+
+        if self.options.shortest {
+            Cost::new(length, secs)
+        } else {
+            Cost::new(secs * self.edge_factor(edge, speed), secs)
+        }
+    }
+
+    /// The cost of turning from `predecessor` onto `edge` at an intersection.
+    ///
+    /// The delay depends on the type of turn and the side of the road that traffic drives on,
+    /// scaled by the stop impact of the transition and the density of the area.
+    pub(crate) fn turn_cost(
+        &self,
+        node: &NodeInfo,
+        predecessor: &DirectedEdge,
+        edge: &DirectedEdge,
+    ) -> Cost {
+        let index = predecessor.opposing_local_edge_index();
+        if self.options.shortest || index >= MAX_LOCAL_EDGES {
+            return Cost::ZERO;
+        }
+
+        let turn = TurnType::between(node, predecessor, edge);
+        let mut cost = Cost::ZERO;
+        let stop_impact = edge.stop_impact(index);
+        if stop_impact > 0 {
+            let crossing = edge.edge_to_left(index) && edge.edge_to_right(index);
+            let mut secs = turn
+                .unwrap_or(TurnType::Straight)
+                .cost(node.drive_on_right(), crossing);
+            let is_ramp = |edge: &DirectedEdge| edge.road_use() == RoadUse::Ramp;
+            if is_ramp(edge) != is_ramp(predecessor) {
+                secs += RAMP_TRANSITION_COST;
+            }
+            secs *= f32::from(stop_impact)
+                * TRANSITION_DENSITY_FACTOR[usize::from(node.density() & 0xf)];
+            cost += Cost::new(secs, secs);
+        }
+
+        // U-turns at dead ends are the only way out
+        if turn == Some(TurnType::Reverse) && node.local_edge_count() > 1 {
+            cost.cost += self.options.u_turn_penalty;
+        }
+        if node.is_traffic_signal() || predecessor.has_traffic_signal() {
+            cost.cost += self.options.traffic_signal_penalty;
+        }
+
+        cost
+    }
+
+    /// A lower bound on the cost per meter of any edge, for the A* heuristic.
+    ///
+    /// This is the smallest possible [edge factor](Self::edge_factor) at the fastest speed.
+    /// Ferries can be faster than the top speed, so they are bounded separately.
+    pub(crate) fn min_cost_per_meter(&self) -> f32 {
+        if self.options.shortest {
+            return 1.0;
+        }
+
+        let speed = |max_speed: f32| {
+            self.options
+                .fixed_speed
+                .unwrap_or(max_speed)
+                .clamp(1.0, MAX_SPEED_KPH)
+                * KPH_TO_METERS_PER_SECOND
+        };
+        let road_factor = (0.85
+            + self.highway_factor.min(0.0)
+            + self.toll_factor.min(0.0)
+            + self.options.surface_factor.min(0.0) * surface_factor(&Surface::Impassable))
+            * [
+                self.options.alley_factor,
+                self.living_street_factor,
+                self.track_factor,
+                self.options.service_factor,
+            ]
+            .into_iter()
+            .fold(1.0, f32::min);
+
+        (road_factor.max(0.0) / speed(self.options.top_speed))
+            .min(self.ferry_factor / speed(MAX_SPEED_KPH))
+    }
+
+    /// The cost factor for an edge, before scaling by time.
+    fn edge_factor(&self, edge: &DirectedEdge, speed: f32) -> f32 {
+        let road_use = edge.road_use();
+        match road_use {
+            RoadUse::Ferry | RoadUse::RailFerry => return self.ferry_factor,
+            _ => {}
+        }
+
+        let class_index = usize::from(edge.classification().discriminant());
+        let mut factor = 0.018f32.mul_add(f32::from(edge.density()), 0.85)
+            + self.highway_factor * HIGHWAY_FACTOR[class_index]
+            + self.options.surface_factor * surface_factor(&edge.surface());
+        // Mildly penalize roads which are faster than the vehicle can go
+        if speed > self.options.top_speed {
+            factor += (speed - self.options.top_speed) * 0.05;
+        }
+        if edge.toll() {
+            factor += self.toll_factor;
+        }
+
+        factor
+            * match road_use {
+                RoadUse::Alley => self.options.alley_factor,
+                RoadUse::LivingStreet => self.living_street_factor,
+                RoadUse::Track => self.track_factor,
+                RoadUse::Driveway
+                | RoadUse::ParkingAisle
+                | RoadUse::DriveThru
+                | RoadUse::ServiceRoad => self.options.service_factor,
+                _ => 1.0,
+            }
+    }
+}
+
+impl Default for AutoCosting {
+    fn default() -> Self {
+        Self::new(AutoCostingOptions::default())
+    }
+}
+
+impl Costing for AutoCosting {
+    fn access_mode(&self) -> Access {
+        Access::Auto
+    }
+
+    fn uses_live_traffic(&self) -> bool {
+        self.options.flow_mask.contains(SpeedSource::Live)
+    }
+
+    fn astar_cost_factor(&self) -> f32 {
+        self.min_cost_per_meter()
+    }
+
+    fn prunes_restricted_regions(&self) -> bool {
+        true
+    }
+
+    fn edge_allowed(
+        &self,
+        edge_id: GraphId,
+        edge: &DirectedEdge,
+        tile: &GraphTileView,
+        context: &EdgeContext,
+    ) -> bool {
+        self.allows(edge_id, edge, tile, Access::Auto, context)
+    }
+
+    fn edge_cost(
+        &self,
+        edge_id: GraphId,
+        edge: &DirectedEdge,
+        tile: &GraphTileView,
+        context: &EdgeContext,
+    ) -> Cost {
+        let speed = Self::edge_speed(edge_id, edge, tile, context, self.options.flow_mask);
+        self.cost_at_speed(edge, speed)
+    }
+
+    fn transition_cost(
+        &self,
+        node: &NodeInfo,
+        predecessor: &DirectedEdge,
+        edge: &DirectedEdge,
+    ) -> Cost {
+        let options = &self.options;
+        let mut cost = Cost::ZERO;
+
+        match node.node_type() {
+            NODE_TYPE_GATE => {
+                cost += Cost::new(options.gate_cost + options.gate_penalty, options.gate_cost);
+            }
+            NODE_TYPE_TOLL_BOOTH => {
+                cost += Cost::new(
+                    options.toll_booth_cost + options.toll_booth_penalty,
+                    options.toll_booth_cost,
+                );
+            }
+            _ => {}
+        }
+        if node.private_access() {
+            cost.cost += options.private_access_penalty;
+        }
+
+        // Only penalize entering these, so that routes which start on one don't pay for it
+        if edge.dest_only() && !predecessor.dest_only() {
+            cost.cost += options.destination_only_penalty;
+        }
+        let is_service = |edge: &DirectedEdge| edge.road_use() == RoadUse::ServiceRoad;
+        if is_service(edge) && !is_service(predecessor) {
+            cost.cost += options.service_penalty;
+        }
+        let is_ferry =
+            |edge: &DirectedEdge| matches!(edge.road_use(), RoadUse::Ferry | RoadUse::RailFerry);
+        if is_ferry(edge) && !is_ferry(predecessor) {
+            cost += self.ferry_transition;
+        }
+        if edge.country_crossing() || node.node_type() == NODE_TYPE_BORDER_CONTROL {
+            cost += Cost::new(
+                options.country_crossing_cost + options.country_crossing_penalty,
+                options.country_crossing_cost,
+            );
+        }
+
+        cost + self.turn_cost(node, predecessor, edge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AutoCosting, AutoCostingOptions, bias_factor, preference_factor};
+    use crate::costing::turn::TurnType;
+    use crate::{Cost, Costing, EdgeContext};
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use valhalla_graphtile::graph_tile::GraphTile;
+    use valhalla_graphtile::tile_provider::{DirectoryGraphTileProvider, GraphTileProvider};
+    use valhalla_graphtile::{GraphId, RoadClass};
+
+    #[test]
+    fn test_preference_factors() {
+        assert!((preference_factor(0.5, 10.0) - 1.0).abs() < f32::EPSILON);
+        assert!((preference_factor(0.0, 10.0) - 10.0).abs() < f32::EPSILON);
+        assert!((preference_factor(1.0, 10.0) - 0.5).abs() < f32::EPSILON);
+
+        assert!(bias_factor(0.5, 8.0).abs() < f32::EPSILON);
+        assert!((bias_factor(0.0, 8.0) - 8.0).abs() < f32::EPSILON);
+        assert!((bias_factor(1.0, 8.0) + 0.125).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_edge_costs() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::MIN);
+        let fastest = AutoCosting::default();
+        let shortest = AutoCosting::new(AutoCostingOptions {
+            shortest: true,
+            ..Default::default()
+        });
+        let avoid_highways = AutoCosting::new(AutoCostingOptions {
+            use_highways: 0.0,
+            ..Default::default()
+        });
+
+        let tile_id = GraphId::try_from_components(0, 3015, 0).unwrap();
+        provider
+            .with_tile_containing(tile_id, |tile| {
+                let mut checked = 0;
+                for (edge_id, edge) in tile.edges_with_ids() {
+                    if !fastest.edge_allowed(edge_id, edge, tile, &EdgeContext::default()) {
+                        continue;
+                    }
+
+                    let cost = fastest.edge_cost(edge_id, edge, tile, &EdgeContext::default());
+                    assert!(cost.secs > 0.0);
+                    assert!(cost.cost > 0.0);
+                    // The A* heuristic must never overestimate
+                    let length = f32::from(u16::try_from(edge.length()).unwrap());
+                    for costing in [&fastest, &avoid_highways] {
+                        let cost = costing.edge_cost(edge_id, edge, tile, &EdgeContext::default());
+                        assert!(costing.astar_cost_factor() * length <= cost.cost);
+                    }
+
+                    // The time doesn't depend on preferences
+                    let shortest_cost =
+                        shortest.edge_cost(edge_id, edge, tile, &EdgeContext::default());
+                    assert_eq!(shortest_cost.secs.to_bits(), cost.secs.to_bits());
+                    assert!((shortest_cost.cost - length).abs() < 1e-3);
+
+                    let avoid_cost =
+                        avoid_highways.edge_cost(edge_id, edge, tile, &EdgeContext::default());
+                    if edge.classification() == RoadClass::Motorway {
+                        assert!(avoid_cost.cost > cost.cost * 2.0);
+                    }
+                    checked += 1;
+                }
+                assert!(checked > 0);
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_transition_costs() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::MIN);
+        let costing = AutoCosting::default();
+        let penalized = AutoCosting::new(AutoCostingOptions {
+            u_turn_penalty: 100.0,
+            traffic_signal_penalty: 60.0,
+            ..Default::default()
+        });
+        let shortest = AutoCosting::new(AutoCostingOptions {
+            shortest: true,
+            ..Default::default()
+        });
+
+        let tile_id = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        provider
+            .with_tile_containing(tile_id, |tile| {
+                let (mut u_turns, mut signals, mut delays) = (0, 0, 0);
+                for (_, predecessor) in tile.edges_with_ids() {
+                    let Ok(node) = tile.get_node(predecessor.end_node_id()) else {
+                        continue;
+                    };
+                    for (_, edge) in tile.outbound_edges_with_ids(node) {
+                        let cost = costing.turn_cost(node, predecessor, edge);
+                        assert!(cost.secs >= 0.0 && cost.cost >= cost.secs);
+                        if cost.secs > 0.0 {
+                            delays += 1;
+                        }
+                        assert_eq!(shortest.turn_cost(node, predecessor, edge), Cost::ZERO);
+
+                        let turn = TurnType::between(node, predecessor, edge);
+                        let is_u_turn =
+                            edge.local_edge_index() == predecessor.opposing_local_edge_index();
+                        assert_eq!(turn == Some(TurnType::Reverse), is_u_turn);
+
+                        let mut extra = 0.0;
+                        if is_u_turn && node.local_edge_count() > 1 {
+                            extra += 85.0;
+                            u_turns += 1;
+                        }
+                        if node.is_traffic_signal() || predecessor.has_traffic_signal() {
+                            extra += 60.0;
+                            signals += 1;
+                        }
+                        let penalized_cost = penalized.turn_cost(node, predecessor, edge);
+                        assert_eq!(penalized_cost.secs.to_bits(), cost.secs.to_bits());
+                        assert!((penalized_cost.cost - cost.cost - extra).abs() < 0.001);
+                    }
+                }
+                assert!(u_turns > 0);
+                assert!(signals > 0);
+                assert!(delays > 0);
+            })
+            .unwrap();
+    }
+}
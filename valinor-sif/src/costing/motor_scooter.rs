@@ -0,0 +1,206 @@
+//! # Motor scooter costing
+//!
+//! A port of Valhalla's `motorscootercost` (for mopeds and motor scooters).
+//! These are costed like cars (see [`AutoCosting`]),
+//! but with a low top speed, a strong preference for avoiding highways,
+//! and slower speeds on rough surfaces.
+
+use super::{AutoCosting, AutoCostingOptions, Cost, Costing, EdgeContext};
+use valhalla_graphtile::graph_tile::{DirectedEdge, GraphTileView, NodeInfo};
+use valhalla_graphtile::{Access, GraphId, RoadClass, Surface};
+
+/// The roughest surface that a motor scooter can travel on.
+const MINIMUM_SURFACE: Surface = Surface::Gravel;
+
+/// How much slower a motor scooter travels on each surface type.
+const fn surface_speed_factor(surface: &Surface) -> f32 {
+    match surface {
+        Surface::PavedSmooth | Surface::Paved => 1.0,
+        Surface::PavedRough => 0.9,
+        Surface::Compacted => 0.6,
+        Surface::Dirt => 0.5,
+        Surface::Gravel => 0.3,
+        Surface::Path => 0.2,
+        Surface::Impassable => 0.0,
+    }
+}
+
+/// Options for [`MotorScooterCosting`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MotorScooterCostingOptions {
+    /// The options shared with automobile costing.
+    ///
+    /// The defaults are adjusted for motor scooters:
+    /// the top speed is 45 kph, and highways are avoided (`use_highways` is 0).
+    pub auto: AutoCostingOptions,
+    /// Preference for primary roads, from 0 (avoid) to 1 (prefer).
+    ///
+    /// Primary roads are usually the most direct, but also the busiest.
+    pub use_primary: f32,
+}
+
+impl Default for MotorScooterCostingOptions {
+    fn default() -> Self {
+        Self {
+            auto: AutoCostingOptions {
+                top_speed: 45.0,
+                use_highways: 0.0,
+                ..Default::default()
+            },
+            use_primary: 0.5,
+        }
+    }
+}
+
+/// A costing model for mopeds and motor scooters, ported from Valhalla's `motorscootercost`.
+#[derive(Debug, Clone)]
+pub struct MotorScooterCosting {
+    auto: AutoCosting,
+    primary_factor: f32,
+}
+
+impl MotorScooterCosting {
+    pub fn new(options: MotorScooterCostingOptions) -> Self {
+        Self {
+            auto: AutoCosting::new(options.auto),
+            // Ranges from 1.5 (avoid) to 0.5 (prefer)
+            primary_factor: 1.5 - options.use_primary.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Default for MotorScooterCosting {
+    fn default() -> Self {
+        Self::new(MotorScooterCostingOptions::default())
+    }
+}
+
+impl Costing for MotorScooterCosting {
+    fn access_mode(&self) -> Access {
+        Access::Moped
+    }
+
+    fn uses_live_traffic(&self) -> bool {
+        self.auto.uses_live_traffic()
+    }
+
+    fn astar_cost_factor(&self) -> f32 {
+        let factor = self.auto.min_cost_per_meter();
+        if self.auto.options().shortest {
+            factor
+        } else {
+            // Preferring primary roads can make them cheaper than any other edge
+            factor * self.primary_factor.min(1.0)
+        }
+    }
+
+    fn prunes_restricted_regions(&self) -> bool {
+        self.auto.prunes_restricted_regions()
+    }
+
+    fn edge_allowed(
+        &self,
+        edge_id: GraphId,
+        edge: &DirectedEdge,
+        tile: &GraphTileView,
+        context: &EdgeContext,
+    ) -> bool {
+        // Reject surfaces rougher than the minimum
+        self.auto
+            .allows(edge_id, edge, tile, Access::Moped, context)
+            && surface_speed_factor(&edge.surface()) >= surface_speed_factor(&MINIMUM_SURFACE)
+    }
+
+    fn edge_cost(
+        &self,
+        edge_id: GraphId,
+        edge: &DirectedEdge,
+        tile: &GraphTileView,
+        context: &EdgeContext,
+    ) -> Cost {
+        let speed =
+            AutoCosting::edge_speed(edge_id, edge, tile, context, self.auto.options().flow_mask)
+                .min(self.auto.options().top_speed)
+                * surface_speed_factor(&edge.surface());
+
+        let mut cost = self.auto.cost_at_speed(edge, speed);
+        if edge.classification() == RoadClass::Primary && !self.auto.options().shortest {
+            cost.cost *= self.primary_factor;
+        }
+
+        cost
+    }
+
+    fn transition_cost(
+        &self,
+        node: &NodeInfo,
+        predecessor: &DirectedEdge,
+        edge: &DirectedEdge,
+    ) -> Cost {
+        self.auto.transition_cost(node, predecessor, edge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MotorScooterCosting, MotorScooterCostingOptions};
+    use crate::{AutoCosting, Costing, EdgeContext};
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use valhalla_graphtile::graph_tile::GraphTile;
+    use valhalla_graphtile::tile_provider::{DirectoryGraphTileProvider, GraphTileProvider};
+    use valhalla_graphtile::{Access, GraphId, RoadClass, Surface};
+
+    #[test]
+    fn test_edge_costs() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::MIN);
+        let scooter = MotorScooterCosting::default();
+        let avoid_primary = MotorScooterCosting::new(MotorScooterCostingOptions {
+            use_primary: 0.0,
+            ..Default::default()
+        });
+        let auto = AutoCosting::default();
+
+        let tile_id = GraphId::try_from_components(1, 47_701, 0).unwrap();
+        provider
+            .with_tile_containing(tile_id, |tile| {
+                let mut checked = 0;
+                for (edge_id, edge) in tile.edges_with_ids() {
+                    if !scooter.edge_allowed(edge_id, edge, tile, &EdgeContext::default()) {
+                        continue;
+                    }
+                    assert!(edge.forward_access().contains(Access::Moped));
+                    assert!(!matches!(
+                        edge.surface(),
+                        Surface::Path | Surface::Impassable
+                    ));
+
+                    let cost = scooter.edge_cost(edge_id, edge, tile, &EdgeContext::default());
+                    // Never faster than the top speed
+                    let min_secs = f32::from(u16::try_from(edge.length()).unwrap()) / (45.0 / 3.6);
+                    assert!(cost.secs >= min_secs * 0.999);
+                    if auto.edge_allowed(edge_id, edge, tile, &EdgeContext::default()) {
+                        assert!(
+                            cost.secs
+                                >= auto
+                                    .edge_cost(edge_id, edge, tile, &EdgeContext::default())
+                                    .secs
+                        );
+                    }
+
+                    let avoid_cost =
+                        avoid_primary.edge_cost(edge_id, edge, tile, &EdgeContext::default());
+                    if edge.classification() == RoadClass::Primary {
+                        assert!(avoid_cost.cost > cost.cost);
+                    } else {
+                        assert_eq!(avoid_cost, cost);
+                    }
+                    checked += 1;
+                }
+                assert!(checked > 0);
+            })
+            .unwrap();
+    }
+}
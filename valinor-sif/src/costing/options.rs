@@ -0,0 +1,634 @@
+//! # Costing options from Valhalla requests
+//!
+//! Valhalla requests pick a costing model by name (ex: `auto`),
+//! and tune it with `costing_options`.
+//! This module parses both the JSON API shape and the protobuf `Options` message
+//! into the equivalent Rust costing models,
+//! applying the same ranges as Valhalla (out of range values are clamped).
+
+use super::{
+    AutoCosting, AutoCostingOptions, Cost, Costing, EdgeContext, MotorScooterCosting,
+    MotorScooterCostingOptions, TruckCosting, TruckCostingOptions,
+};
+#[cfg(any(feature = "json", feature = "proto"))]
+use enumset::EnumSet;
+#[cfg(any(feature = "json", feature = "proto"))]
+use std::ops::RangeInclusive;
+use thiserror::Error;
+#[cfg(any(feature = "json", feature = "proto"))]
+use valhalla_graphtile::graph_tile::SpeedSource;
+use valhalla_graphtile::graph_tile::{DirectedEdge, GraphTileView, NodeInfo};
+use valhalla_graphtile::{Access, GraphId};
+
+/// The range of preferences (`use_*` options).
+#[cfg(any(feature = "json", feature = "proto"))]
+const PREFERENCE_RANGE: RangeInclusive<f32> = 0.0..=1.0;
+/// The range of costs and penalties, in seconds.
+#[cfg(any(feature = "json", feature = "proto"))]
+const COST_RANGE: RangeInclusive<f32> = 0.0..=43_200.0;
+/// The range of top speeds, in kph.
+#[cfg(any(feature = "json", feature = "proto"))]
+const TOP_SPEED_RANGE: RangeInclusive<f32> = 10.0..=252.0;
+/// The range of factors which scale edge costs.
+#[cfg(any(feature = "json", feature = "proto"))]
+const FACTOR_RANGE: RangeInclusive<f32> = 0.1..=100_000.0;
+
+#[derive(Debug, Error)]
+pub enum CostingOptionsError {
+    #[error("Unsupported costing: {0}")]
+    UnsupportedCosting(String),
+    #[error("The request does not specify a costing")]
+    MissingCosting,
+    #[cfg(feature = "json")]
+    #[error("Invalid costing options: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+}
+
+/// Options as they appear in a Valhalla request, before validation.
+///
+/// Only the options which the Rust costing models support are included;
+/// everything else is ignored (as Valhalla does with options that don't apply to a model).
+#[cfg(any(feature = "json", feature = "proto"))]
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize))]
+struct RequestOptions {
+    use_highways: Option<f32>,
+    use_tolls: Option<f32>,
+    use_ferry: Option<f32>,
+    use_living_streets: Option<f32>,
+    use_tracks: Option<f32>,
+    use_primary: Option<f32>,
+    use_truck_route: Option<f32>,
+    top_speed: Option<f32>,
+    fixed_speed: Option<u32>,
+    shortest: Option<bool>,
+    exclude_unpaved: Option<bool>,
+    /// Valhalla's speed type names (`freeflow`, `constrained`, `predicted`, and `current`).
+    speed_types: Option<Vec<String>>,
+    /// The protobuf equivalent of `speed_types`.
+    #[cfg_attr(feature = "json", serde(skip))]
+    flow_mask: Option<u32>,
+    alley_factor: Option<f32>,
+    service_factor: Option<f32>,
+    destination_only_penalty: Option<f32>,
+    private_access_penalty: Option<f32>,
+    service_penalty: Option<f32>,
+    gate_cost: Option<f32>,
+    gate_penalty: Option<f32>,
+    toll_booth_cost: Option<f32>,
+    toll_booth_penalty: Option<f32>,
+    ferry_cost: Option<f32>,
+    country_crossing_cost: Option<f32>,
+    country_crossing_penalty: Option<f32>,
+    low_class_penalty: Option<f32>,
+    height: Option<f32>,
+    width: Option<f32>,
+    length: Option<f32>,
+    weight: Option<f32>,
+    axle_load: Option<f32>,
+    axle_count: Option<u32>,
+    hazmat: Option<bool>,
+}
+
+/// Overwrites `target` with `value` (clamped to `range`), if present.
+#[cfg(any(feature = "json", feature = "proto"))]
+fn set(target: &mut f32, value: Option<f32>, range: RangeInclusive<f32>) {
+    if let Some(value) = value {
+        *target = value.clamp(*range.start(), *range.end());
+    }
+}
+
+/// Converts Valhalla's flow mask bits into speed sources.
+#[cfg(any(feature = "json", feature = "proto"))]
+fn speed_sources_from_flow_mask(flow_mask: u32) -> EnumSet<SpeedSource> {
+    [
+        (1, SpeedSource::FreeFlow),
+        (2, SpeedSource::ConstrainedFlow),
+        (4, SpeedSource::Predicted),
+        (8, SpeedSource::Live),
+    ]
+    .into_iter()
+    .filter(|(bit, _)| flow_mask & bit != 0)
+    .map(|(_, source)| source)
+    .collect()
+}
+
+/// Converts Valhalla's speed type names into speed sources.
+#[cfg(any(feature = "json", feature = "proto"))]
+fn speed_sources_from_names(names: &[String]) -> EnumSet<SpeedSource> {
+    names
+        .iter()
+        .filter_map(|name| match name.as_str() {
+            "freeflow" => Some(SpeedSource::FreeFlow),
+            "constrained" => Some(SpeedSource::ConstrainedFlow),
+            "predicted" => Some(SpeedSource::Predicted),
+            "current" => Some(SpeedSource::Live),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(any(feature = "json", feature = "proto"))]
+impl RequestOptions {
+    fn apply_to_auto(&self, options: &mut AutoCostingOptions) {
+        set(
+            &mut options.use_highways,
+            self.use_highways,
+            PREFERENCE_RANGE,
+        );
+        set(&mut options.use_tolls, self.use_tolls, PREFERENCE_RANGE);
+        set(&mut options.use_ferry, self.use_ferry, PREFERENCE_RANGE);
+        set(
+            &mut options.use_living_streets,
+            self.use_living_streets,
+            PREFERENCE_RANGE,
+        );
+        set(&mut options.use_tracks, self.use_tracks, PREFERENCE_RANGE);
+        set(&mut options.top_speed, self.top_speed, TOP_SPEED_RANGE);
+        // Zero means unset in the protobuf API
+        if let Some(fixed_speed) = self.fixed_speed.filter(|&speed| speed > 0) {
+            options.fixed_speed = u16::try_from(fixed_speed)
+                .ok()
+                .map(f32::from)
+                .map(|speed| speed.min(*TOP_SPEED_RANGE.end()))
+                .or(Some(*TOP_SPEED_RANGE.end()));
+        }
+        if let Some(shortest) = self.shortest {
+            options.shortest = shortest;
+        }
+        if let Some(exclude_unpaved) = self.exclude_unpaved {
+            options.exclude_unpaved = exclude_unpaved;
+        }
+        if let Some(names) = &self.speed_types {
+            options.flow_mask = speed_sources_from_names(names);
+        } else if let Some(flow_mask) = self.flow_mask {
+            options.flow_mask = speed_sources_from_flow_mask(flow_mask);
+        }
+        set(&mut options.alley_factor, self.alley_factor, FACTOR_RANGE);
+        set(
+            &mut options.service_factor,
+            self.service_factor,
+            FACTOR_RANGE,
+        );
+        set(
+            &mut options.destination_only_penalty,
+            self.destination_only_penalty,
+            COST_RANGE,
+        );
+        set(
+            &mut options.private_access_penalty,
+            self.private_access_penalty,
+            COST_RANGE,
+        );
+        set(
+            &mut options.service_penalty,
+            self.service_penalty,
+            COST_RANGE,
+        );
+        set(&mut options.gate_cost, self.gate_cost, COST_RANGE);
+        set(&mut options.gate_penalty, self.gate_penalty, COST_RANGE);
+        set(
+            &mut options.toll_booth_cost,
+            self.toll_booth_cost,
+            COST_RANGE,
+        );
+        set(
+            &mut options.toll_booth_penalty,
+            self.toll_booth_penalty,
+            COST_RANGE,
+        );
+        set(&mut options.ferry_cost, self.ferry_cost, COST_RANGE);
+        set(
+            &mut options.country_crossing_cost,
+            self.country_crossing_cost,
+            COST_RANGE,
+        );
+        set(
+            &mut options.country_crossing_penalty,
+            self.country_crossing_penalty,
+            COST_RANGE,
+        );
+    }
+
+    fn apply_to_truck(&self, options: &mut TruckCostingOptions) {
+        self.apply_to_auto(&mut options.auto);
+        set(
+            &mut options.use_truck_route,
+            self.use_truck_route,
+            PREFERENCE_RANGE,
+        );
+        set(
+            &mut options.low_class_penalty,
+            self.low_class_penalty,
+            COST_RANGE,
+        );
+
+        let profile = &mut options.profile;
+        set(&mut profile.height, self.height, 0.0..=10.0);
+        set(&mut profile.width, self.width, 0.0..=10.0);
+        set(&mut profile.length, self.length, 0.0..=50.0);
+        set(&mut profile.weight, self.weight, 0.0..=100.0);
+        set(&mut profile.axle_load, self.axle_load, 0.0..=40.0);
+        // Zero means unset in the protobuf API
+        if let Some(axle_count) = self.axle_count.filter(|&count| count > 0) {
+            profile.axle_count = u8::try_from(axle_count.clamp(2, 20)).unwrap_or(20);
+        }
+        if let Some(hazmat) = self.hazmat {
+            profile.hazmat = hazmat;
+        }
+    }
+
+    fn apply_to_motor_scooter(&self, options: &mut MotorScooterCostingOptions) {
+        self.apply_to_auto(&mut options.auto);
+        set(&mut options.use_primary, self.use_primary, PREFERENCE_RANGE);
+    }
+
+    #[cfg(feature = "proto")]
+    fn from_proto(options: &valhalla_proto::costing::Options) -> Self {
+        use valhalla_proto::costing::options as o;
+
+        /// Extracts the value of a single-field `oneof`.
+        macro_rules! oneof {
+            ($field:ident, $variant:path) => {
+                options.$field.clone().map(|$variant(value)| value)
+            };
+        }
+
+        Self {
+            use_highways: oneof!(has_use_highways, o::HasUseHighways::UseHighways),
+            use_tolls: oneof!(has_use_tolls, o::HasUseTolls::UseTolls),
+            use_ferry: oneof!(has_use_ferry, o::HasUseFerry::UseFerry),
+            use_living_streets: oneof!(
+                has_use_living_streets,
+                o::HasUseLivingStreets::UseLivingStreets
+            ),
+            use_tracks: oneof!(has_use_tracks, o::HasUseTracks::UseTracks),
+            use_primary: oneof!(has_use_primary, o::HasUsePrimary::UsePrimary),
+            use_truck_route: Some(options.use_truck_route),
+            top_speed: oneof!(has_top_speed, o::HasTopSpeed::TopSpeed),
+            fixed_speed: Some(options.fixed_speed),
+            shortest: oneof!(has_shortest, o::HasShortest::Shortest),
+            exclude_unpaved: oneof!(has_exclude_unpaved, o::HasExcludeUnpaved::ExcludeUnpaved),
+            speed_types: None,
+            flow_mask: oneof!(has_flow_mask, o::HasFlowMask::FlowMask),
+            alley_factor: oneof!(has_alley_factor, o::HasAlleyFactor::AlleyFactor),
+            service_factor: oneof!(has_service_factor, o::HasServiceFactor::ServiceFactor),
+            destination_only_penalty: oneof!(
+                has_destination_only_penalty,
+                o::HasDestinationOnlyPenalty::DestinationOnlyPenalty
+            ),
+            private_access_penalty: oneof!(
+                has_private_access_penalty,
+                o::HasPrivateAccessPenalty::PrivateAccessPenalty
+            ),
+            service_penalty: oneof!(has_service_penalty, o::HasServicePenalty::ServicePenalty),
+            gate_cost: oneof!(has_gate_cost, o::HasGateCost::GateCost),
+            gate_penalty: oneof!(has_gate_penalty, o::HasGatePenalty::GatePenalty),
+            toll_booth_cost: oneof!(has_toll_booth_cost, o::HasTollBoothCost::TollBoothCost),
+            toll_booth_penalty: oneof!(
+                has_toll_booth_penalty,
+                o::HasTollBoothPenalty::TollBoothPenalty
+            ),
+            ferry_cost: oneof!(has_ferry_cost, o::HasFerryCost::FerryCost),
+            country_crossing_cost: oneof!(
+                has_country_crossing_cost,
+                o::HasCountryCrossingCost::CountryCrossingCost
+            ),
+            country_crossing_penalty: oneof!(
+                has_country_crossing_penalty,
+                o::HasCountryCrossingPenalty::CountryCrossingPenalty
+            ),
+            low_class_penalty: oneof!(
+                has_low_class_penalty,
+                o::HasLowClassPenalty::LowClassPenalty
+            ),
+            height: oneof!(has_height, o::HasHeight::Height),
+            width: oneof!(has_width, o::HasWidth::Width),
+            length: oneof!(has_length, o::HasLength::Length),
+            weight: oneof!(has_weight, o::HasWeight::Weight),
+            axle_load: oneof!(has_axle_load, o::HasAxleLoad::AxleLoad),
+            axle_count: Some(options.axle_count),
+            hazmat: oneof!(has_hazmat, o::HasHazmat::Hazmat),
+        }
+    }
+}
+
+/// A costing model and its options, as configured by a Valhalla request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CostingOptions {
+    Auto(AutoCostingOptions),
+    Truck(TruckCostingOptions),
+    MotorScooter(MotorScooterCostingOptions),
+}
+
+impl CostingOptions {
+    /// The default options for a costing model, by its Valhalla name (ex: `auto`).
+    ///
+    /// # Errors
+    ///
+    /// Fails if there is no Rust implementation of the costing model.
+    pub fn default_for(costing: &str) -> Result<Self, CostingOptionsError> {
+        match costing {
+            "auto" => Ok(Self::Auto(AutoCostingOptions::default())),
+            "truck" => Ok(Self::Truck(TruckCostingOptions::default())),
+            "motor_scooter" => Ok(Self::MotorScooter(MotorScooterCostingOptions::default())),
+            _ => Err(CostingOptionsError::UnsupportedCosting(costing.to_string())),
+        }
+    }
+
+    /// The Valhalla name of the costing model.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Auto(_) => "auto",
+            Self::Truck(_) => "truck",
+            Self::MotorScooter(_) => "motor_scooter",
+        }
+    }
+
+    #[cfg(any(feature = "json", feature = "proto"))]
+    fn with_request_options(mut self, request_options: &RequestOptions) -> Self {
+        match &mut self {
+            Self::Auto(options) => request_options.apply_to_auto(options),
+            Self::Truck(options) => request_options.apply_to_truck(options),
+            Self::MotorScooter(options) => request_options.apply_to_motor_scooter(options),
+        }
+        self
+    }
+
+    /// Parses the costing from a Valhalla JSON request
+    /// (the `costing` name, and its entry in `costing_options`, if any).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the request doesn't name a supported costing,
+    /// or if its options have the wrong types.
+    #[cfg(feature = "json")]
+    pub fn from_json_request(request: &serde_json::Value) -> Result<Self, CostingOptionsError> {
+        let costing = request
+            .get("costing")
+            .and_then(serde_json::Value::as_str)
+            .ok_or(CostingOptionsError::MissingCosting)?;
+        let defaults = Self::default_for(costing)?;
+        let request_options = match request
+            .get("costing_options")
+            .and_then(|options| options.get(costing))
+        {
+            Some(options) => RequestOptions::deserialize(options)?,
+            None => RequestOptions::default(),
+        };
+
+        Ok(defaults.with_request_options(&request_options))
+    }
+
+    /// Parses the costing from a Valhalla protobuf request.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the request doesn't specify a supported costing.
+    #[cfg(feature = "proto")]
+    pub fn from_proto(options: &valhalla_proto::Options) -> Result<Self, CostingOptionsError> {
+        use valhalla_proto::costing::{HasOptions, Type};
+
+        let costing_type = Type::try_from(options.costing_type).map_err(|_| {
+            CostingOptionsError::UnsupportedCosting(options.costing_type.to_string())
+        })?;
+        let defaults = match costing_type {
+            Type::None => return Err(CostingOptionsError::MissingCosting),
+            Type::Auto => Self::Auto(AutoCostingOptions::default()),
+            Type::Truck => Self::Truck(TruckCostingOptions::default()),
+            Type::MotorScooter => Self::MotorScooter(MotorScooterCostingOptions::default()),
+            other => {
+                return Err(CostingOptionsError::UnsupportedCosting(
+                    other.as_str_name().to_string(),
+                ));
+            }
+        };
+        let request_options = match options
+            .costings
+            .get(&options.costing_type)
+            .and_then(|costing| costing.has_options.as_ref())
+        {
+            Some(HasOptions::Options(options)) => RequestOptions::from_proto(options),
+            None => RequestOptions::default(),
+        };
+
+        Ok(defaults.with_request_options(&request_options))
+    }
+
+    /// Builds the costing model.
+    pub fn build(self) -> CostingModel {
+        match self {
+            Self::Auto(options) => CostingModel::Auto(AutoCosting::new(options)),
+            Self::Truck(options) => CostingModel::Truck(TruckCosting::new(options)),
+            Self::MotorScooter(options) => {
+                CostingModel::MotorScooter(MotorScooterCosting::new(options))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl RequestOptions {
+    fn deserialize(value: &serde_json::Value) -> Result<Self, serde_json::Error> {
+        serde::Deserialize::deserialize(value)
+    }
+}
+
+/// Any of the built-in costing models, selected at runtime.
+///
+/// This is what [`CostingOptions::build`] produces,
+/// so that request handlers don't need to be generic over the costing model.
+#[derive(Debug, Clone)]
+pub enum CostingModel {
+    Auto(AutoCosting),
+    Truck(TruckCosting),
+    MotorScooter(MotorScooterCosting),
+}
+
+impl CostingModel {
+    fn as_costing(&self) -> &dyn Costing {
+        match self {
+            Self::Auto(costing) => costing,
+            Self::Truck(costing) => costing,
+            Self::MotorScooter(costing) => costing,
+        }
+    }
+}
+
+impl Costing for CostingModel {
+    fn access_mode(&self) -> Access {
+        self.as_costing().access_mode()
+    }
+
+    fn uses_live_traffic(&self) -> bool {
+        self.as_costing().uses_live_traffic()
+    }
+
+    fn astar_cost_factor(&self) -> f32 {
+        self.as_costing().astar_cost_factor()
+    }
+
+    fn prunes_restricted_regions(&self) -> bool {
+        self.as_costing().prunes_restricted_regions()
+    }
+
+    fn edge_allowed(
+        &self,
+        edge_id: GraphId,
+        edge: &DirectedEdge,
+        tile: &GraphTileView,
+        context: &EdgeContext,
+    ) -> bool {
+        self.as_costing().edge_allowed(edge_id, edge, tile, context)
+    }
+
+    fn edge_cost(
+        &self,
+        edge_id: GraphId,
+        edge: &DirectedEdge,
+        tile: &GraphTileView,
+        context: &EdgeContext,
+    ) -> Cost {
+        self.as_costing().edge_cost(edge_id, edge, tile, context)
+    }
+
+    fn transition_cost(
+        &self,
+        node: &NodeInfo,
+        predecessor: &DirectedEdge,
+        edge: &DirectedEdge,
+    ) -> Cost {
+        self.as_costing().transition_cost(node, predecessor, edge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CostingOptions, CostingOptionsError};
+    use crate::AutoCostingOptions;
+
+    #[test]
+    fn test_default_for() {
+        assert_eq!(
+            CostingOptions::default_for("auto").unwrap(),
+            CostingOptions::Auto(AutoCostingOptions::default())
+        );
+        assert_eq!(
+            CostingOptions::default_for("motor_scooter").unwrap().name(),
+            "motor_scooter"
+        );
+        assert!(matches!(
+            CostingOptions::default_for("bicycle"),
+            Err(CostingOptionsError::UnsupportedCosting(name)) if name == "bicycle"
+        ));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_from_json_request() {
+        use enumset::EnumSet;
+        use valhalla_graphtile::graph_tile::SpeedSource;
+
+        let request = serde_json::json!({
+            "locations": [],
+            "costing": "truck",
+            "costing_options": {
+                "auto": {"use_highways": 0.0},
+                "truck": {
+                    "use_highways": 0.25,
+                    "top_speed": 500,
+                    "height": 3.5,
+                    "axle_count": 3,
+                    "hazmat": true,
+                    "speed_types": ["freeflow", "constrained"],
+                    "some_option_we_do_not_support": 1
+                }
+            }
+        });
+        let CostingOptions::Truck(options) = CostingOptions::from_json_request(&request).unwrap()
+        else {
+            panic!("Expected truck costing");
+        };
+        assert!((options.auto.use_highways - 0.25).abs() < f32::EPSILON);
+        // Clamped to the maximum
+        assert!((options.auto.top_speed - 252.0).abs() < f32::EPSILON);
+        assert!((options.profile.height - 3.5).abs() < f32::EPSILON);
+        assert_eq!(options.profile.axle_count, 3);
+        assert!(options.profile.hazmat);
+        assert_eq!(
+            options.auto.flow_mask,
+            SpeedSource::FreeFlow | SpeedSource::ConstrainedFlow
+        );
+        // Untouched
+        assert!((options.auto.use_tolls - 0.5).abs() < f32::EPSILON);
+
+        // Costing options are optional
+        let request = serde_json::json!({"costing": "auto"});
+        assert_eq!(
+            CostingOptions::from_json_request(&request).unwrap(),
+            CostingOptions::Auto(AutoCostingOptions::default())
+        );
+        assert_eq!(
+            AutoCostingOptions::default().flow_mask,
+            EnumSet::<SpeedSource>::all()
+        );
+
+        let request = serde_json::json!({"costing": "auto", "costing_options": {"auto": {"use_tolls": "yes"}}});
+        assert!(matches!(
+            CostingOptions::from_json_request(&request),
+            Err(CostingOptionsError::InvalidJson(_))
+        ));
+        assert!(matches!(
+            CostingOptions::from_json_request(&serde_json::json!({})),
+            Err(CostingOptionsError::MissingCosting)
+        ));
+    }
+
+    #[cfg(feature = "proto")]
+    #[test]
+    fn test_from_proto() {
+        use valhalla_proto::costing::{HasOptions, Type, options as o};
+        use valhalla_proto::{Costing, Options};
+
+        let costing_options = valhalla_proto::costing::Options {
+            has_use_primary: Some(o::HasUsePrimary::UsePrimary(0.9)),
+            has_destination_only_penalty: Some(
+                o::HasDestinationOnlyPenalty::DestinationOnlyPenalty(-5.0),
+            ),
+            fixed_speed: 30,
+            ..Default::default()
+        };
+        let options = Options {
+            costing_type: Type::MotorScooter.into(),
+            costings: [(
+                Type::MotorScooter.into(),
+                Costing {
+                    r#type: Type::MotorScooter.into(),
+                    has_options: Some(HasOptions::Options(costing_options)),
+                    ..Default::default()
+                },
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let CostingOptions::MotorScooter(options) = CostingOptions::from_proto(&options).unwrap()
+        else {
+            panic!("Expected motor scooter costing");
+        };
+        assert!((options.use_primary - 0.9).abs() < f32::EPSILON);
+        // Clamped to the minimum
+        assert!(options.auto.destination_only_penalty.abs() < f32::EPSILON);
+        assert_eq!(options.auto.fixed_speed, Some(30.0));
+        // Untouched
+        assert!((options.auto.top_speed - 45.0).abs() < f32::EPSILON);
+
+        let options = Options {
+            costing_type: Type::Bicycle.into(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            CostingOptions::from_proto(&options),
+            Err(CostingOptionsError::UnsupportedCosting(_))
+        ));
+    }
+}
@@ -0,0 +1,320 @@
+//! # Truck costing
+//!
+//! A port of Valhalla's `truckcost`.
+//! Trucks are costed like cars (see [`AutoCosting`]),
+//! but also have to respect dimension, weight, and hazmat restrictions,
+//! and prefer designated truck routes.
+
+use super::{AutoCosting, AutoCostingOptions, Cost, Costing, EdgeContext};
+use valhalla_graphtile::graph_tile::{
+    AccessRestriction, AccessRestrictionType, DirectedEdge, GraphTile, GraphTileView, NodeInfo,
+};
+use valhalla_graphtile::{Access, GraphId, RoadClass};
+
+/// The maximum factor for edges which are not on a truck route, applied when `use_truck_route` is 1.
+const MAX_NON_TRUCK_ROUTE_FACTOR: f32 = 5.0;
+
+/// The dimensions and cargo of a truck.
+///
+/// The defaults match Valhalla's (a typical US tractor-trailer).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TruckProfile {
+    /// Height, in meters.
+    pub height: f32,
+    /// Width, in meters.
+    pub width: f32,
+    /// Length, in meters.
+    pub length: f32,
+    /// Gross weight, in metric tons.
+    pub weight: f32,
+    /// Weight per axle, in metric tons.
+    pub axle_load: f32,
+    pub axle_count: u8,
+    /// Is the truck carrying hazardous materials?
+    pub hazmat: bool,
+}
+
+impl Default for TruckProfile {
+    fn default() -> Self {
+        Self {
+            height: 4.11,
+            width: 2.6,
+            length: 21.64,
+            weight: 21.77,
+            axle_load: 9.07,
+            axle_count: 5,
+            hazmat: false,
+        }
+    }
+}
+
+impl TruckProfile {
+    /// Does the truck violate an access restriction?
+    ///
+    /// Restriction types which don't describe the vehicle (ex: time-based restrictions)
+    /// are never violated.
+    pub fn violates(&self, restriction: &AccessRestriction) -> bool {
+        // Dimension and weight limits are stored in hundredths
+        #[expect(
+            clippy::cast_precision_loss,
+            reason = "Limits are small enough to be exactly representable"
+        )]
+        let limit = restriction.value() as f32 / 100.0;
+        match restriction.restriction_type() {
+            AccessRestrictionType::Hazmat => self.hazmat && restriction.value() == 0,
+            AccessRestrictionType::MaxHeight => self.height > limit,
+            AccessRestrictionType::MaxWidth => self.width > limit,
+            AccessRestrictionType::MaxLength => self.length > limit,
+            AccessRestrictionType::MaxWeight => self.weight > limit,
+            AccessRestrictionType::MaxAxleLoad => self.axle_load > limit,
+            AccessRestrictionType::MaxAxles => u64::from(self.axle_count) > restriction.value(),
+            AccessRestrictionType::TimedAllowed
+            | AccessRestrictionType::TimedDenied
+            | AccessRestrictionType::DestinationAllowed => false,
+        }
+    }
+}
+
+/// Options for [`TruckCosting`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TruckCostingOptions {
+    /// The options shared with automobile costing.
+    ///
+    /// The default top speed is lowered to 120 kph.
+    pub auto: AutoCostingOptions,
+    pub profile: TruckProfile,
+    /// Preference for designated truck routes, from 0 (indifferent) to 1 (strongly prefer).
+    pub use_truck_route: f32,
+    /// The penalty for turning onto a low class road (ex: residential) from a higher class one.
+    pub low_class_penalty: f32,
+    /// If set, edges which violate a restriction in the [`TruckProfile`]
+    /// are allowed with this penalty, rather than being rejected outright.
+    ///
+    /// This is useful as a fallback when no compliant route exists
+    /// (ex: the destination is on a restricted road).
+    pub restriction_penalty: Option<f32>,
+}
+
+impl Default for TruckCostingOptions {
+    fn default() -> Self {
+        Self {
+            auto: AutoCostingOptions {
+                top_speed: 120.0,
+                ..Default::default()
+            },
+            profile: TruckProfile::default(),
+            use_truck_route: 0.0,
+            low_class_penalty: 30.0,
+            restriction_penalty: None,
+        }
+    }
+}
+
+/// A costing model for trucks, ported from Valhalla's `truckcost`.
+#[derive(Debug, Clone)]
+pub struct TruckCosting {
+    auto: AutoCosting,
+    profile: TruckProfile,
+    non_truck_route_factor: f32,
+    low_class_penalty: f32,
+    restriction_penalty: Option<f32>,
+}
+
+impl TruckCosting {
+    pub fn new(options: TruckCostingOptions) -> Self {
+        Self {
+            auto: AutoCosting::new(options.auto),
+            profile: options.profile,
+            non_truck_route_factor: (MAX_NON_TRUCK_ROUTE_FACTOR - 1.0)
+                .mul_add(options.use_truck_route.clamp(0.0, 1.0), 1.0),
+            low_class_penalty: options.low_class_penalty,
+            restriction_penalty: options.restriction_penalty,
+        }
+    }
+
+    pub const fn profile(&self) -> &TruckProfile {
+        &self.profile
+    }
+
+    /// Does the edge have a restriction which the truck violates?
+    fn is_restricted(&self, edge_id: GraphId, edge: &DirectedEdge, tile: &GraphTileView) -> bool {
+        if !edge.access_restriction_modes().contains(Access::Truck) {
+            return false;
+        }
+        let Ok(index) = u32::try_from(edge_id.feature_index()) else {
+            return false;
+        };
+        tile.get_access_restrictions(index, Access::Truck.into())
+            .into_iter()
+            .any(|restriction| self.profile.violates(restriction))
+    }
+}
+
+impl Default for TruckCosting {
+    fn default() -> Self {
+        Self::new(TruckCostingOptions::default())
+    }
+}
+
+impl Costing for TruckCosting {
+    fn access_mode(&self) -> Access {
+        Access::Truck
+    }
+
+    fn uses_live_traffic(&self) -> bool {
+        self.auto.uses_live_traffic()
+    }
+
+    fn astar_cost_factor(&self) -> f32 {
+        // Truck speeds and the truck route factor only ever make edges more expensive
+        self.auto.min_cost_per_meter()
+    }
+
+    fn prunes_restricted_regions(&self) -> bool {
+        self.auto.prunes_restricted_regions()
+    }
+
+    fn edge_allowed(
+        &self,
+        edge_id: GraphId,
+        edge: &DirectedEdge,
+        tile: &GraphTileView,
+        context: &EdgeContext,
+    ) -> bool {
+        self.auto
+            .allows(edge_id, edge, tile, Access::Truck, context)
+            && (self.restriction_penalty.is_some() || !self.is_restricted(edge_id, edge, tile))
+    }
+
+    fn edge_cost(
+        &self,
+        edge_id: GraphId,
+        edge: &DirectedEdge,
+        tile: &GraphTileView,
+        context: &EdgeContext,
+    ) -> Cost {
+        let speed =
+            AutoCosting::edge_speed(edge_id, edge, tile, context, self.auto.options().flow_mask);
+        // Trucks are often slower than the general traffic
+        let speed = match edge.truck_speed() {
+            0 => speed,
+            truck_speed => speed.min(f32::from(truck_speed)),
+        };
+
+        let mut cost = self.auto.cost_at_speed(edge, speed);
+        if !edge.truck_route() && !self.auto.options().shortest {
+            cost.cost *= self.non_truck_route_factor;
+        }
+        if let Some(penalty) = self.restriction_penalty
+            && self.is_restricted(edge_id, edge, tile)
+        {
+            cost.cost += penalty;
+        }
+
+        cost
+    }
+
+    fn transition_cost(
+        &self,
+        node: &NodeInfo,
+        predecessor: &DirectedEdge,
+        edge: &DirectedEdge,
+    ) -> Cost {
+        let mut cost = self.auto.transition_cost(node, predecessor, edge);
+        let is_low_class = |edge: &DirectedEdge| {
+            matches!(
+                edge.classification(),
+                RoadClass::Residential | RoadClass::ServiceOther
+            )
+        };
+        if is_low_class(edge) && !is_low_class(predecessor) {
+            cost.cost += self.low_class_penalty;
+        }
+
+        cost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TruckCosting, TruckCostingOptions, TruckProfile};
+    use crate::{AutoCosting, Costing, EdgeContext};
+    use enumset::EnumSet;
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use valhalla_graphtile::graph_tile::{AccessRestriction, AccessRestrictionType, GraphTile};
+    use valhalla_graphtile::tile_provider::{DirectoryGraphTileProvider, GraphTileProvider};
+    use valhalla_graphtile::{Access, GraphId};
+
+    #[test]
+    fn test_profile_violations() {
+        let profile = TruckProfile::default();
+        let restriction = |restriction_type, value| {
+            AccessRestriction::new(restriction_type, EnumSet::only(Access::Truck), value)
+        };
+
+        assert!(profile.violates(&restriction(AccessRestrictionType::MaxHeight, 400)));
+        assert!(!profile.violates(&restriction(AccessRestrictionType::MaxHeight, 450)));
+        assert!(profile.violates(&restriction(AccessRestrictionType::MaxWeight, 750)));
+        assert!(!profile.violates(&restriction(AccessRestrictionType::MaxWeight, 4000)));
+        assert!(profile.violates(&restriction(AccessRestrictionType::MaxAxles, 3)));
+        assert!(!profile.violates(&restriction(AccessRestrictionType::MaxAxles, 5)));
+        assert!(!profile.violates(&restriction(AccessRestrictionType::TimedDenied, 0)));
+
+        // Hazmat restrictions only apply when carrying hazardous materials
+        assert!(!profile.violates(&restriction(AccessRestrictionType::Hazmat, 0)));
+        let hazmat = TruckProfile {
+            hazmat: true,
+            ..Default::default()
+        };
+        assert!(hazmat.violates(&restriction(AccessRestrictionType::Hazmat, 0)));
+        assert!(!hazmat.violates(&restriction(AccessRestrictionType::Hazmat, 1)));
+    }
+
+    #[test]
+    fn test_edge_costs() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::MIN);
+        let truck = TruckCosting::default();
+        let auto = AutoCosting::default();
+        let tiny_truck = TruckCosting::new(TruckCostingOptions {
+            profile: TruckProfile {
+                height: 2.0,
+                width: 2.0,
+                length: 5.0,
+                weight: 3.0,
+                axle_load: 1.5,
+                axle_count: 2,
+                hazmat: false,
+            },
+            ..Default::default()
+        });
+
+        let tile_id = GraphId::try_from_components(1, 47_701, 0).unwrap();
+        provider
+            .with_tile_containing(tile_id, |tile| {
+                let mut checked = 0;
+                for (edge_id, edge) in tile.edges_with_ids() {
+                    if !truck.edge_allowed(edge_id, edge, tile, &EdgeContext::default()) {
+                        continue;
+                    }
+                    assert!(edge.forward_access().contains(Access::Truck));
+                    // A smaller truck can go anywhere a larger one can
+                    assert!(tiny_truck.edge_allowed(edge_id, edge, tile, &EdgeContext::default()));
+
+                    // Trucks are never faster than cars
+                    if auto.edge_allowed(edge_id, edge, tile, &EdgeContext::default()) {
+                        let truck_cost =
+                            truck.edge_cost(edge_id, edge, tile, &EdgeContext::default());
+                        let auto_cost =
+                            auto.edge_cost(edge_id, edge, tile, &EdgeContext::default());
+                        assert!(truck_cost.secs >= auto_cost.secs);
+                    }
+                    checked += 1;
+                }
+                assert!(checked > 0);
+            })
+            .unwrap();
+    }
+}
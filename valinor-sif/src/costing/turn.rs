@@ -0,0 +1,124 @@
+//! # Turns
+//!
+//! Classifies the transition between two edges at a node,
+//! following Valhalla's `Turn` helpers and the turn cost tables in `dynamiccost`.
+//! Turns are measured from the node's edge headings,
+//! in degrees clockwise from straight ahead.
+
+use valhalla_graphtile::graph_tile::{DirectedEdge, NodeInfo};
+
+/// The number of local edges with per-transition attributes (headings, stop impact, etc.).
+pub(crate) const MAX_LOCAL_EDGES: u8 = 8;
+
+/// The time (in seconds) to cross an intersection with edges on both sides.
+const CROSSING_COST: f32 = 2.0;
+
+/// The type of a turn between two edges.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TurnType {
+    Straight,
+    SlightRight,
+    Right,
+    SharpRight,
+    /// A U-turn back onto the opposing edge.
+    Reverse,
+    SharpLeft,
+    Left,
+    SlightLeft,
+}
+
+impl TurnType {
+    /// Classifies a turn by its degree (0-359, clockwise from straight ahead).
+    pub(crate) const fn from_degree(degree: u16) -> Self {
+        match degree {
+            0..11 | 350.. => Self::Straight,
+            11..45 => Self::SlightRight,
+            45..136 => Self::Right,
+            136..180 => Self::SharpRight,
+            180..225 => Self::SharpLeft,
+            225..316 => Self::Left,
+            _ => Self::SlightLeft,
+        }
+    }
+
+    /// Classifies the turn from `predecessor` onto `edge` at `node`.
+    ///
+    /// Returns `None` when the node doesn't record headings for both edges
+    /// (only the first eight local edges have them).
+    pub(crate) fn between(
+        node: &NodeInfo,
+        predecessor: &DirectedEdge,
+        edge: &DirectedEdge,
+    ) -> Option<Self> {
+        let from_index = predecessor.opposing_local_edge_index();
+        let to_index = edge.local_edge_index();
+        if from_index == to_index {
+            return Some(Self::Reverse);
+        }
+
+        let heading = |index: u8| {
+            (index < node.local_edge_count().min(MAX_LOCAL_EDGES))
+                .then(|| node.heading(index))
+                .flatten()
+        };
+        // The heading of the opposing edge points back the way we came
+        let inbound = (heading(from_index)? + 180) % 360;
+        let degree = (heading(to_index)? + 360 - inbound) % 360;
+        Some(Self::from_degree(degree))
+    }
+
+    /// Does the turn cross oncoming traffic?
+    const fn is_unfavorable(self, drive_on_right: bool) -> bool {
+        match self {
+            Self::SlightLeft | Self::Left | Self::SharpLeft => drive_on_right,
+            Self::SlightRight | Self::Right | Self::SharpRight => !drive_on_right,
+            Self::Straight | Self::Reverse => false,
+        }
+    }
+
+    /// The base time (in seconds) of making the turn at an intersection,
+    /// before scaling by the stop impact.
+    ///
+    /// `crossing` is true when there are edges on both sides of the turn.
+    pub(crate) const fn cost(self, drive_on_right: bool, crossing: bool) -> f32 {
+        if crossing && !matches!(self, Self::Reverse) {
+            return CROSSING_COST;
+        }
+        let unfavorable = self.is_unfavorable(drive_on_right);
+        match self {
+            Self::Straight => 0.5,
+            Self::SlightRight | Self::SlightLeft if unfavorable => 1.25,
+            Self::SlightRight | Self::SlightLeft => 0.75,
+            Self::Right | Self::Left if unfavorable => 2.5,
+            Self::Right | Self::Left => 1.0,
+            Self::SharpRight | Self::SharpLeft if unfavorable => 3.5,
+            Self::SharpRight | Self::SharpLeft => 1.5,
+            Self::Reverse => 9.5,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TurnType;
+
+    #[test]
+    fn test_turn_types() {
+        assert_eq!(TurnType::from_degree(0), TurnType::Straight);
+        assert_eq!(TurnType::from_degree(355), TurnType::Straight);
+        assert_eq!(TurnType::from_degree(30), TurnType::SlightRight);
+        assert_eq!(TurnType::from_degree(90), TurnType::Right);
+        assert_eq!(TurnType::from_degree(170), TurnType::SharpRight);
+        assert_eq!(TurnType::from_degree(190), TurnType::SharpLeft);
+        assert_eq!(TurnType::from_degree(270), TurnType::Left);
+        assert_eq!(TurnType::from_degree(330), TurnType::SlightLeft);
+
+        // Turns across traffic are more expensive, and depend on the side of the road
+        let right = TurnType::Right.cost(true, false);
+        let left = TurnType::Left.cost(true, false);
+        assert!(left > right);
+        assert!((TurnType::Left.cost(false, false) - right).abs() < f32::EPSILON);
+        assert!((TurnType::Right.cost(true, true) - 2.0).abs() < f32::EPSILON);
+        assert!(TurnType::Reverse.cost(true, true) > left);
+    }
+}
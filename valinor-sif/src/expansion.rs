@@ -0,0 +1,184 @@
+//! # Search expansion
+//!
+//! For debugging costing models (ex: comparing against Valhalla),
+//! the router can record every edge which the search settles, in order,
+//! like Valhalla's `expansion` action.
+//! The recorded edges can be written out as newline-delimited GeoJSON,
+//! with one line string feature per edge,
+//! which most GIS tools can load and animate by the `order` property.
+
+use crate::Cost;
+use std::io::Write;
+use thiserror::Error;
+use valhalla_graphtile::GraphId;
+use valhalla_graphtile::graph_tile::GraphTile;
+use valhalla_graphtile::tile_provider::{GraphTileProvider, GraphTileProviderError};
+
+#[derive(Debug, Error)]
+pub enum ExpansionError {
+    #[error("Tile provider error: {0}")]
+    TileProvider(#[from] GraphTileProviderError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// The direction of the search which settled an edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpansionDirection {
+    /// Searching forward from the origin.
+    Forward,
+    /// Searching in reverse from the destination.
+    Reverse,
+}
+
+impl ExpansionDirection {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Forward => "forward",
+            Self::Reverse => "reverse",
+        }
+    }
+}
+
+/// An edge settled by the search.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExpandedEdge {
+    pub edge_id: GraphId,
+    /// The edge which the search reached this one from, if any.
+    ///
+    /// In the reverse search, this is the next edge along the path.
+    pub predecessor_edge_id: Option<GraphId>,
+    pub direction: ExpansionDirection,
+    /// The cost of the path from the origin (or to the destination) including this edge.
+    pub cost: Cost,
+    /// The length of the path from the origin (or to the destination) including this edge,
+    /// in meters.
+    pub distance: f64,
+}
+
+/// The edges settled by a search, in order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Expansion {
+    pub edges: Vec<ExpandedEdge>,
+}
+
+impl Expansion {
+    /// Writes the expansion as newline-delimited GeoJSON, with one feature per line.
+    ///
+    /// Each feature is the shape of an edge (in its direction of travel), with the properties
+    /// `order`, `edge_id`, `pred_edge_id` (both as 64-bit graph ID values, or null),
+    /// `expansion_type` (`forward` or `reverse`), `cost`, `duration` (seconds),
+    /// and `distance` (meters).
+    ///
+    /// # Errors
+    ///
+    /// Fails if a tile can't be loaded, an edge shape can't be decoded,
+    /// or the writer fails.
+    pub fn write_geojson(
+        &self,
+        provider: &impl GraphTileProvider,
+        mut writer: impl Write,
+    ) -> Result<(), ExpansionError> {
+        for (order, expanded) in self.edges.iter().enumerate() {
+            let (mut shape, is_forward) =
+                provider.with_tile_containing(expanded.edge_id, |tile| {
+                    let edge = tile.get_directed_edge(expanded.edge_id)?;
+                    let shape = tile.get_edge_info(edge)?.decode_raw_shape::<f64>()?;
+                    Ok::<_, GraphTileProviderError>((shape, edge.edge_info_is_forward()))
+                })??;
+            if !is_forward {
+                shape.reverse();
+            }
+            let coordinates = shape
+                .iter()
+                .map(|coord| format!("[{:.6},{:.6}]", coord.x, coord.y))
+                .collect::<Vec<_>>()
+                .join(",");
+            let pred_edge_id = expanded
+                .predecessor_edge_id
+                .map_or_else(|| "null".to_string(), |edge_id| edge_id.value().to_string());
+
+            writeln!(
+                writer,
+                r#"{{"type":"Feature","geometry":{{"type":"LineString","coordinates":[{coordinates}]}},"properties":{{"order":{order},"edge_id":{},"pred_edge_id":{pred_edge_id},"expansion_type":"{}","cost":{},"duration":{},"distance":{}}}}}"#,
+                expanded.edge_id.value(),
+                expanded.direction.as_str(),
+                expanded.cost.cost,
+                expanded.cost.secs,
+                expanded.distance,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExpansionDirection;
+    use crate::{RouteOptions, Router, TimeCosting};
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use valhalla_graphtile::tile_provider::DirectoryGraphTileProvider;
+    use valhalla_graphtile::{Access, GraphId};
+
+    #[test]
+    fn test_expansion() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(4).unwrap());
+        let router = Router::new(provider, TimeCosting::new(Access::Auto));
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let destination = GraphId::try_from_components(2, 763_926, 123).unwrap();
+        let options = RouteOptions::default();
+
+        // Both directions expand, starting from the ends of the route
+        let expansion = router.expansion(origin, destination, &options).unwrap();
+        let path = router.route(origin, destination, &options).unwrap();
+        assert!(expansion.edges.len() > path.edges.len());
+        for direction in [ExpansionDirection::Forward, ExpansionDirection::Reverse] {
+            assert!(
+                expansion
+                    .edges
+                    .iter()
+                    .any(|edge| edge.direction == direction)
+            );
+        }
+        let first_edge_id = path.edges[0].edge_id;
+        assert!(expansion.edges.iter().any(|expanded| {
+            expanded.edge_id == first_edge_id && expanded.direction == ExpansionDirection::Forward
+        }));
+        assert!(expansion.edges[0].predecessor_edge_id.is_none());
+
+        let mut output = Vec::new();
+        expansion
+            .write_geojson(router.provider(), &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), expansion.edges.len());
+        for (order, (line, expanded)) in output.lines().zip(&expansion.edges).enumerate() {
+            let feature: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(feature["type"], "Feature");
+            assert!(
+                feature["geometry"]["coordinates"].as_array().unwrap().len() >= 2,
+                "{line}"
+            );
+            let properties = &feature["properties"];
+            assert_eq!(properties["order"], order);
+            assert_eq!(properties["edge_id"], expanded.edge_id.value());
+            assert!(
+                (properties["cost"].as_f64().unwrap() - f64::from(expanded.cost.cost)).abs() < 1e-3
+            );
+        }
+
+        // A failed search still has an expansion
+        let limited = router
+            .expansion(
+                origin,
+                destination,
+                &RouteOptions::default().with_max_expansions(10),
+            )
+            .unwrap();
+        assert_eq!(limited.edges.len(), 10);
+    }
+}
@@ -0,0 +1,220 @@
+//! # Route geometry
+//!
+//! Edge shapes are stored once per edge pair, in the direction of the edge info,
+//! so the shape of each directed edge may need to be reversed to match the direction of travel.
+//! The shapes of consecutive edges share a coordinate at the node between them,
+//! which only appears once in the route shape.
+//! Routes which start or end partway along an edge are trimmed to the fraction of the edge they use.
+
+use crate::{EdgeLocation, Path};
+use geo::{Coord, LineString};
+use valhalla_graphtile::graph_tile::GraphTile;
+use valhalla_graphtile::shape_codec::encode_polyline;
+use valhalla_graphtile::spatial::line_substring;
+use valhalla_graphtile::tile_provider::{GraphTileProvider, GraphTileProviderError};
+
+/// The precision of Valhalla's route shapes (polyline6).
+const POLYLINE_PRECISION: u8 = 6;
+
+/// The shape of a path, and the range of shape indexes (inclusive) covered by each edge.
+pub(crate) type EdgeShapes = (Vec<Coord<f64>>, Vec<(usize, usize)>);
+
+/// Assembles the shape of a [`Path`] from the shapes of its edges.
+///
+/// By default, the whole of every edge is included.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathGeometry {
+    start_fraction: f64,
+    end_fraction: f64,
+}
+
+impl Default for PathGeometry {
+    fn default() -> Self {
+        Self {
+            start_fraction: 0.0,
+            end_fraction: 1.0,
+        }
+    }
+}
+
+impl PathGeometry {
+    /// Starts the shape at a fraction (from 0 to 1) of the length of the first edge.
+    #[must_use]
+    pub const fn with_start_fraction(mut self, fraction: f64) -> Self {
+        self.start_fraction = fraction;
+        self
+    }
+
+    /// Ends the shape at a fraction (from 0 to 1) of the length of the last edge.
+    ///
+    /// When the path only has one edge, this must not be less than the start fraction.
+    #[must_use]
+    pub const fn with_end_fraction(mut self, fraction: f64) -> Self {
+        self.end_fraction = fraction;
+        self
+    }
+
+    /// Trims the shape to a route between two locations partway along edges
+    /// (see [`crate::Router::route_between`]).
+    pub const fn between(origin: &EdgeLocation, destination: &EdgeLocation) -> Self {
+        Self {
+            start_fraction: origin.percent_along,
+            end_fraction: destination.percent_along,
+        }
+    }
+
+    /// Builds the shape of a path, in the direction of travel.
+    ///
+    /// The shape is empty if the path has no edges.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a tile can't be loaded, or an edge shape can't be decoded.
+    pub fn assemble(
+        &self,
+        provider: &impl GraphTileProvider,
+        path: &Path,
+    ) -> Result<LineString<f64>, GraphTileProviderError> {
+        Ok(LineString::new(self.assemble_edges(provider, path)?.0))
+    }
+
+    /// Builds the shape of a path, along with the range of shape indexes (inclusive)
+    /// covered by each edge.
+    pub(crate) fn assemble_edges(
+        &self,
+        provider: &impl GraphTileProvider,
+        path: &Path,
+    ) -> Result<EdgeShapes, GraphTileProviderError> {
+        let last_index = path.edges.len().saturating_sub(1);
+        let mut coordinates: Vec<Coord<f64>> = Vec::new();
+        let mut ranges = Vec::with_capacity(path.edges.len());
+        for (index, path_edge) in path.edges.iter().enumerate() {
+            let mut shape = provider.with_tile_containing(path_edge.edge_id, |tile| {
+                let edge = tile.get_directed_edge(path_edge.edge_id)?;
+                let mut shape = tile.get_edge_info(edge)?.decode_raw_shape::<f64>()?;
+                if !edge.edge_info_is_forward() {
+                    shape.reverse();
+                }
+                Ok::<_, GraphTileProviderError>(shape)
+            })??;
+
+            let start = if index == 0 { self.start_fraction } else { 0.0 };
+            let end = if index == last_index {
+                self.end_fraction
+            } else {
+                1.0
+            };
+            if start > 0.0 || end < 1.0 {
+                shape = line_substring(&shape, start, end);
+            }
+
+            // Skip the coordinate shared with the previous edge
+            let skip = usize::from(!coordinates.is_empty() && coordinates.last() == shape.first());
+            let begin = coordinates.len() - skip;
+            coordinates.extend(shape.into_iter().skip(skip));
+            ranges.push((begin, coordinates.len().saturating_sub(1).max(begin)));
+        }
+
+        Ok((coordinates, ranges))
+    }
+
+    /// Builds the shape of a path, encoded as a polyline with 6 digits of precision
+    /// (as in Valhalla route responses).
+    ///
+    /// # Errors
+    ///
+    /// See [`PathGeometry::assemble`].
+    pub fn polyline6(
+        &self,
+        provider: &impl GraphTileProvider,
+        path: &Path,
+    ) -> Result<String, GraphTileProviderError> {
+        Ok(encode_polyline(
+            &self.assemble(provider, path)?.0,
+            POLYLINE_PRECISION,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PathGeometry;
+    use crate::{Path, RouteOptions, Router, TimeCosting};
+    use geo::{Coord, Distance, Haversine, Length, LineString, Point};
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use valhalla_graphtile::graph_tile::GraphTile;
+    use valhalla_graphtile::shape_codec::decode_polyline;
+    use valhalla_graphtile::tile_provider::{DirectoryGraphTileProvider, GraphTileProvider};
+    use valhalla_graphtile::{Access, GraphId};
+
+    fn node_coordinate(provider: &DirectoryGraphTileProvider, node_id: GraphId) -> Coord<f64> {
+        let coordinate = provider
+            .with_tile_containing(node_id, |tile| {
+                tile.get_node(node_id)
+                    .unwrap()
+                    .coordinate(tile.header().sw_corner())
+            })
+            .unwrap();
+        Coord {
+            x: f64::from(coordinate.x),
+            y: f64::from(coordinate.y),
+        }
+    }
+
+    fn distance(a: Coord<f64>, b: Coord<f64>) -> f64 {
+        Haversine.distance(Point(a), Point(b))
+    }
+
+    #[test]
+    fn test_assemble() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(4).unwrap());
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let destination = GraphId::try_from_components(2, 763_926, 123).unwrap();
+        let router = Router::new(provider, TimeCosting::new(Access::Auto));
+        let path = router
+            .route(origin, destination, &RouteOptions::default())
+            .unwrap();
+        let provider = router.provider();
+
+        let line = PathGeometry::default().assemble(provider, &path).unwrap();
+        // The shape runs from the origin to the destination, without repeating joins
+        assert!(distance(line.0[0], node_coordinate(provider, origin)) < 1.0);
+        assert!(
+            distance(
+                *line.0.last().unwrap(),
+                node_coordinate(provider, destination)
+            ) < 1.0
+        );
+        assert!(line.0.windows(2).all(|pair| pair[0] != pair[1]));
+        let length = Haversine.length(&line);
+        assert!((length - path.length).abs() < path.length * 0.01 + 1.0);
+
+        // Trimming removes half of the first and last edges
+        let trimmed = PathGeometry::default()
+            .with_start_fraction(0.5)
+            .with_end_fraction(0.5)
+            .assemble(provider, &path)
+            .unwrap();
+        let trimmed_length = path.edges[0].length / 2.0 + path.edges.last().unwrap().length / 2.0;
+        assert!((length - Haversine.length(&trimmed) - trimmed_length).abs() < 2.0);
+        assert!(distance(line.0[0], trimmed.0[0]) > 0.0);
+
+        let polyline = PathGeometry::default().polyline6(provider, &path).unwrap();
+        let decoded: Vec<Coord<f64>> = decode_polyline(&polyline, 6).unwrap();
+        assert_eq!(decoded.len(), line.0.len());
+        assert!(
+            decoded
+                .iter()
+                .zip(&line.0)
+                .all(|(&a, &b)| distance(a, b) < 0.5)
+        );
+
+        let empty = PathGeometry::default()
+            .assemble(provider, &Path::default())
+            .unwrap();
+        assert_eq!(empty, LineString::new(Vec::new()));
+    }
+}
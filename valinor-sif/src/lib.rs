@@ -0,0 +1,42 @@
+#![doc = include_str!("../README.md")]
+
+mod alternatives;
+mod avoid;
+mod costing;
+mod expansion;
+mod geometry;
+mod locate;
+mod location;
+mod maneuver;
+mod map_match;
+mod matrix;
+mod path;
+mod router;
+mod search;
+mod time;
+mod trace;
+mod traffic;
+
+// Pub use for re-export without too many levels of hierarchy.
+pub use alternatives::AlternativeOptions;
+pub use avoid::AvoidLocation;
+pub use costing::{
+    AutoCosting, AutoCostingOptions, Cost, Costing, CostingModel, CostingOptions,
+    CostingOptionsError, EdgeContext, MotorScooterCosting, MotorScooterCostingOptions, TimeCosting,
+    TruckCosting, TruckCostingOptions, TruckProfile,
+};
+pub use expansion::{ExpandedEdge, Expansion, ExpansionDirection, ExpansionError};
+pub use geometry::PathGeometry;
+pub use locate::{LocateOptions, LocationCandidate, SideOfStreet};
+pub use location::EdgeLocation;
+pub use maneuver::{Maneuver, ManeuverBuilder, ManeuverKind, ManeuverSigns};
+pub use map_match::{
+    Discontinuity, DiscontinuityReason, MapMatcher, MatchConfig, MatchError, MatchResult,
+    MatchUpdate, MatchedLeg, MatchedPoint, OnlineMatcher,
+};
+pub use matrix::{Matrix, MatrixEntry};
+pub use path::{Path, PathEdge};
+pub use router::{RouteOptions, RouteTime, Router, RoutingError};
+pub use time::{TimeInfo, TimeZones};
+pub use trace::{TraceParseError, TracePoint};
+pub use traffic::LiveTraffic;
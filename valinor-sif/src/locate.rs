@@ -0,0 +1,412 @@
+//! # Locating coordinates in the graph
+//!
+//! Before routing, each input coordinate has to be matched to the edges it could be on,
+//! like Valhalla's `loki`.
+//! The candidates are the directed edges within a radius which the costing model allows,
+//! ranked by their distance from the coordinate.
+//! Both directions of a two-way road are candidates, with complementary positions along the edge.
+//!
+//! Snaps within a few meters of either end of an edge are moved onto the node,
+//! which avoids routes that start with a sliver of an edge.
+//! Each candidate also records which side of the edge the coordinate is on
+//! (relative to its direction of travel), unless it is close enough to be on the street itself.
+//!
+//! Traces (ex: for map matching) can have thousands of points, mostly in the same few tiles,
+//! so their candidates are found in one batch from the edge bins,
+//! with a search radius for each point.
+
+use crate::{Costing, EdgeContext, EdgeLocation, TracePoint};
+use geo::{Bearing, Coord, Haversine, Point};
+use std::collections::HashMap;
+use valhalla_graphtile::Access;
+use valhalla_graphtile::graph_tile::{DirectedEdge, GraphTile, HEADING_SAMPLE_DISTANCE};
+use valhalla_graphtile::spatial::{heading_along_line, line_substring};
+use valhalla_graphtile::tile_provider::{EdgeCandidate, GraphTileProvider, GraphTileProviderError};
+
+/// Which side of an edge a coordinate lies on, facing its direction of travel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SideOfStreet {
+    Left,
+    Right,
+    /// The coordinate is on (or very close to) the street.
+    None,
+}
+
+/// An edge which a coordinate could be on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocationCandidate {
+    /// The edge, and the position of the snapped point along it.
+    pub location: EdgeLocation,
+    /// The distance from the coordinate to the snapped point, in meters.
+    pub distance: f64,
+    /// The closest point to the coordinate on the edge (or its start or end node).
+    pub snapped_point: Coord<f64>,
+    pub side_of_street: SideOfStreet,
+}
+
+/// Options for finding [`LocationCandidate`]s.
+///
+/// The defaults are similar to Valhalla's.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocateOptions {
+    /// The maximum distance from the coordinate to a candidate, in meters.
+    pub radius: f64,
+    /// The maximum number of candidates to return.
+    pub max_candidates: usize,
+    /// Snaps within this distance of either end of an edge are moved onto the node, in meters.
+    pub node_snap_tolerance: f64,
+    /// Coordinates within this distance of an edge are on the street, rather than to one side,
+    /// in meters.
+    pub street_side_tolerance: f64,
+}
+
+impl Default for LocateOptions {
+    fn default() -> Self {
+        Self {
+            radius: 50.0,
+            max_candidates: 10,
+            node_snap_tolerance: 5.0,
+            street_side_tolerance: 5.0,
+        }
+    }
+}
+
+impl LocateOptions {
+    #[must_use]
+    pub const fn with_radius(mut self, radius: f64) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_max_candidates(mut self, max_candidates: usize) -> Self {
+        self.max_candidates = max_candidates;
+        self
+    }
+}
+
+/// Finds the edges near a coordinate which the costing model allows, nearest first.
+///
+/// Shortcuts are never candidates, since routes never use them directly.
+///
+/// # Errors
+///
+/// Fails if a tile can't be loaded, or an edge shape can't be decoded.
+pub(crate) fn locate<C: Costing>(
+    provider: &impl GraphTileProvider,
+    costing: &C,
+    coordinate: Coord<f64>,
+    options: &LocateOptions,
+) -> Result<Vec<LocationCandidate>, GraphTileProviderError> {
+    let access_mode = costing.access_mode();
+    let nearest =
+        provider.find_nearest_edges(Point(coordinate), options.radius, usize::MAX, |edge| {
+            edge_filter(edge, access_mode)
+        })?;
+
+    refine_candidates(provider, costing, coordinate, nearest, options)
+}
+
+/// Finds the edges near each point of a trace which the costing model allows, nearest first.
+///
+/// This returns the same candidates as calling [`locate`] for each point
+/// (using the point's radius, if it has one),
+/// but gathers them from the edge bins in batches,
+/// so each tile is only fetched a handful of times no matter how long the trace is.
+///
+/// # Errors
+///
+/// Fails if a tile can't be loaded, or an edge shape can't be decoded.
+pub(crate) fn locate_trace<C: Costing>(
+    provider: &impl GraphTileProvider,
+    costing: &C,
+    trace: &[TracePoint],
+    options: &LocateOptions,
+) -> Result<Vec<Vec<LocationCandidate>>, GraphTileProviderError> {
+    let access_mode = costing.access_mode();
+
+    // Each batch shares a radius (usually, the whole trace is one batch)
+    let mut batches: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (index, point) in trace.iter().enumerate() {
+        let radius = point.radius.unwrap_or(options.radius);
+        batches.entry(radius.to_bits()).or_default().push(index);
+    }
+
+    let mut results = vec![Vec::new(); trace.len()];
+    for (radius, indices) in batches {
+        let points: Vec<_> = indices
+            .iter()
+            .map(|&index| Point(trace[index].coordinate))
+            .collect();
+        let nearest =
+            provider.locate_many(&points, f64::from_bits(radius), usize::MAX, |edge| {
+                edge_filter(edge, access_mode)
+            })?;
+        for (index, nearest) in indices.into_iter().zip(nearest) {
+            results[index] =
+                refine_candidates(provider, costing, trace[index].coordinate, nearest, options)?;
+        }
+    }
+
+    Ok(results)
+}
+
+/// Filters the edges found in the bins.
+///
+/// The edge filter can only see the edge, so the rest of the costing is checked afterwards.
+fn edge_filter(edge: &DirectedEdge, access_mode: Access) -> bool {
+    !edge.is_shortcut() && edge.forward_access().contains(access_mode)
+}
+
+/// Turns the nearest edges to a coordinate into candidates,
+/// dropping any which the costing model doesn't allow.
+fn refine_candidates<C: Costing>(
+    provider: &impl GraphTileProvider,
+    costing: &C,
+    coordinate: Coord<f64>,
+    nearest: Vec<EdgeCandidate>,
+    options: &LocateOptions,
+) -> Result<Vec<LocationCandidate>, GraphTileProviderError> {
+    let mut candidates = Vec::new();
+    for nearest in nearest {
+        if candidates.len() >= options.max_candidates {
+            break;
+        }
+        let edge_id = nearest.edge_id;
+        let candidate = provider.with_tile_containing(edge_id, |tile| {
+            let edge = tile.get_directed_edge(edge_id)?;
+            if !costing.edge_allowed(edge_id, edge, tile, &EdgeContext::default()) {
+                return Ok(None);
+            }
+            let mut shape = tile.get_edge_info(edge)?.decode_raw_shape::<f64>()?;
+            if !edge.edge_info_is_forward() {
+                shape.reverse();
+            }
+
+            // Snap to the nearer node, since short edges may be within tolerance of both
+            let length = f64::from(edge.length());
+            let to_start = nearest.percent_along * length;
+            let to_end = (1.0 - nearest.percent_along) * length;
+            let (percent_along, snapped_point) = match (shape.first(), shape.last()) {
+                (Some(&start), _)
+                    if to_start <= options.node_snap_tolerance && to_start <= to_end =>
+                {
+                    (0.0, start)
+                }
+                (_, Some(&end)) if to_end <= options.node_snap_tolerance => (1.0, end),
+                _ => (nearest.percent_along, nearest.snapped_point.0),
+            };
+            let side_of_street = if nearest.distance <= options.street_side_tolerance {
+                SideOfStreet::None
+            } else {
+                side_of_street(
+                    &shape,
+                    nearest.percent_along,
+                    nearest.snapped_point.0,
+                    coordinate,
+                )
+            };
+
+            Ok::<_, GraphTileProviderError>(Some(LocationCandidate {
+                location: EdgeLocation::new(edge_id, percent_along),
+                distance: nearest.distance,
+                snapped_point,
+                side_of_street,
+            }))
+        })??;
+        candidates.extend(candidate);
+    }
+
+    Ok(candidates)
+}
+
+/// Which side of a shape (in its direction of travel) a coordinate is on,
+/// given its closest point along the shape.
+fn side_of_street(
+    shape: &[Coord<f64>],
+    percent_along: f64,
+    snapped_point: Coord<f64>,
+    coordinate: Coord<f64>,
+) -> SideOfStreet {
+    // The heading of the edge at the snapped point (looking backwards at the very end)
+    let ahead = line_substring(shape, percent_along, 1.0);
+    let heading = heading_along_line(&ahead, HEADING_SAMPLE_DISTANCE).or_else(|| {
+        let mut behind = line_substring(shape, 0.0, percent_along);
+        behind.reverse();
+        heading_along_line(&behind, HEADING_SAMPLE_DISTANCE)
+            .map(|heading| (heading + 180.0).rem_euclid(360.0))
+    });
+    let Some(heading) = heading else {
+        return SideOfStreet::None;
+    };
+
+    let bearing = Haversine.bearing(Point(snapped_point), Point(coordinate));
+    if (bearing - heading).rem_euclid(360.0) < 180.0 {
+        SideOfStreet::Right
+    } else {
+        SideOfStreet::Left
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LocateOptions, LocationCandidate, SideOfStreet, side_of_street};
+    use crate::{PathGeometry, RouteOptions, Router, TimeCosting, TracePoint};
+    use geo::Coord;
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use valhalla_graphtile::graph_tile::GraphTile;
+    use valhalla_graphtile::tile_provider::{DirectoryGraphTileProvider, GraphTileProvider};
+    use valhalla_graphtile::{Access, GraphId};
+
+    #[test]
+    fn test_side_of_street() {
+        // Heading north
+        let shape = [Coord { x: 0.0, y: 0.0 }, Coord { x: 0.0, y: 0.001 }];
+        let snapped = Coord { x: 0.0, y: 0.0005 };
+        let east = Coord {
+            x: 0.0002,
+            y: 0.0005,
+        };
+        let west = Coord {
+            x: -0.0002,
+            y: 0.0005,
+        };
+        assert_eq!(
+            side_of_street(&shape, 0.5, snapped, east),
+            SideOfStreet::Right
+        );
+        assert_eq!(
+            side_of_street(&shape, 0.5, snapped, west),
+            SideOfStreet::Left
+        );
+        // At the very end of the edge
+        assert_eq!(
+            side_of_street(&shape, 1.0, shape[1], east + Coord { x: 0.0, y: 0.0005 }),
+            SideOfStreet::Right
+        );
+    }
+
+    #[test]
+    fn test_locate() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(4).unwrap());
+        let router = Router::new(provider, TimeCosting::new(Access::Auto));
+        let provider = router.provider();
+
+        // A point just off the middle of the first edge leaving a node
+        let node_id = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let (edge_id, point) = provider
+            .with_tile_containing(node_id, |tile| {
+                let node = tile.get_node(node_id).unwrap();
+                let (edge_id, edge) = tile.outbound_edges_with_ids(node).next().unwrap();
+                let shape = tile
+                    .get_edge_info(edge)
+                    .unwrap()
+                    .decode_raw_shape::<f64>()
+                    .unwrap();
+                let midpoint = (shape[0] + shape[shape.len() - 1]) / 2.0;
+                (edge_id, midpoint + Coord { x: 0.0001, y: 0.0 })
+            })
+            .unwrap();
+
+        let candidates = router.locate(point, &LocateOptions::default()).unwrap();
+        assert!(!candidates.is_empty() && candidates.len() <= 10);
+        assert!(candidates.is_sorted_by(|a, b| a.distance <= b.distance));
+        assert!(
+            candidates
+                .iter()
+                .all(|candidate| candidate.distance <= 50.0)
+        );
+        let candidate = candidates
+            .iter()
+            .find(|candidate| candidate.location.edge_id == edge_id)
+            .unwrap();
+        // The point is about 8 meters east of the edge
+        assert!(candidate.distance > 5.0);
+        assert_ne!(candidate.side_of_street, SideOfStreet::None);
+        // Every candidate is usable by car
+        for candidate in &candidates {
+            let edge_id = candidate.location.edge_id;
+            provider
+                .with_tile_containing(edge_id, |tile| {
+                    let edge = tile.get_directed_edge(edge_id).unwrap();
+                    assert!(edge.forward_access().contains(Access::Auto));
+                    assert!(!edge.is_shortcut());
+                })
+                .unwrap();
+        }
+
+        let candidates = router
+            .locate(point, &LocateOptions::default().with_max_candidates(1))
+            .unwrap();
+        assert_eq!(candidates.len(), 1);
+        let candidates = router
+            .locate(
+                Coord { x: 1.0, y: 0.0 },
+                &LocateOptions::default().with_radius(10.0),
+            )
+            .unwrap();
+        assert!(candidates.is_empty());
+    }
+
+    #[test]
+    fn test_locate_trace() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(4).unwrap());
+        let router = Router::new(provider, TimeCosting::new(Access::Auto));
+
+        // A trace following a route, with some noise
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let destination = GraphId::try_from_components(2, 763_926, 123).unwrap();
+        let path = router
+            .route(origin, destination, &RouteOptions::default())
+            .unwrap();
+        let shape = PathGeometry::default()
+            .assemble(router.provider(), &path)
+            .unwrap();
+        let mut trace: Vec<_> = shape
+            .coords()
+            .enumerate()
+            .map(|(index, &coordinate)| {
+                let offset = if index % 2 == 0 { 0.00005 } else { -0.00005 };
+                TracePoint::new(coordinate + Coord { x: offset, y: 0.0 })
+            })
+            .collect();
+        // Points with their own radius, near a tile boundary, and in the middle of nowhere
+        trace.push(TracePoint::new(shape[0]).with_radius(10.0));
+        trace.push(
+            TracePoint::new(Coord {
+                x: 1.4999,
+                y: 42.46,
+            })
+            .with_radius(200.0),
+        );
+        trace.push(TracePoint::new(Coord { x: 1.0, y: 0.0 }));
+
+        let options = LocateOptions::default().with_max_candidates(usize::MAX);
+        let sorted = |mut candidates: Vec<LocationCandidate>| {
+            candidates.sort_by(|a, b| {
+                a.distance
+                    .total_cmp(&b.distance)
+                    .then(a.location.edge_id.cmp(&b.location.edge_id))
+            });
+            candidates
+        };
+        let results = router.locate_trace(&trace, &options).unwrap();
+        assert_eq!(results.len(), trace.len());
+        for (point, candidates) in trace.iter().zip(&results) {
+            let options = options
+                .clone()
+                .with_radius(point.radius.unwrap_or(options.radius));
+            let expected = router.locate(point.coordinate, &options).unwrap();
+            assert_eq!(sorted(candidates.clone()), sorted(expected));
+        }
+        assert!(results[..shape.0.len()].iter().all(|c| !c.is_empty()));
+        assert!(results[shape.0.len()].iter().all(|c| c.distance <= 10.0));
+        assert!(!results[shape.0.len() + 1].is_empty());
+        assert!(results[shape.0.len() + 2].is_empty());
+    }
+}
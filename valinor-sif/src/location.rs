@@ -0,0 +1,107 @@
+//! # Locations along edges
+//!
+//! Real origins and destinations rarely fall exactly on a node,
+//! so routes can start and end partway along a directed edge.
+//! A location is the edge, plus how far along it the point lies (as a fraction of its length).
+//! Routes between locations include only the part of the first and last edges which they use,
+//! with the cost, length, and shape of those edges reduced in proportion.
+
+use geo::Coord;
+use valhalla_graphtile::GraphId;
+use valhalla_graphtile::graph_tile::GraphTile;
+use valhalla_graphtile::spatial::closest_point_on_line;
+use valhalla_graphtile::tile_provider::{GraphTileProvider, GraphTileProviderError};
+
+/// A point partway along a directed edge.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeLocation {
+    pub edge_id: GraphId,
+    /// How far along the edge the point lies, in its direction of travel,
+    /// from 0 (the start node) to 1 (the end node).
+    pub percent_along: f64,
+}
+
+impl EdgeLocation {
+    /// Creates a location, clamping `percent_along` to the range 0 to 1.
+    pub const fn new(edge_id: GraphId, percent_along: f64) -> Self {
+        Self {
+            edge_id,
+            percent_along: percent_along.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Snaps a coordinate to the closest point along an edge.
+    ///
+    /// Returns the location along the edge, and the snapped coordinate.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the tile can't be loaded, or the edge shape can't be decoded.
+    pub fn snap(
+        provider: &impl GraphTileProvider,
+        edge_id: GraphId,
+        coordinate: Coord<f64>,
+    ) -> Result<(Self, Coord<f64>), GraphTileProviderError> {
+        provider.with_tile_containing(edge_id, |tile| {
+            let edge = tile.get_directed_edge(edge_id)?;
+            let mut shape = tile.get_edge_info(edge)?.decode_raw_shape::<f64>()?;
+            if !edge.edge_info_is_forward() {
+                shape.reverse();
+            }
+            let (snapped, fraction) =
+                closest_point_on_line(coordinate, &shape).unwrap_or((coordinate, 0.0));
+            Ok((Self::new(edge_id, fraction), snapped))
+        })?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EdgeLocation;
+    use geo::{Coord, Distance, Haversine, Point};
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use valhalla_graphtile::GraphId;
+    use valhalla_graphtile::graph_tile::GraphTile;
+    use valhalla_graphtile::tile_provider::{DirectoryGraphTileProvider, GraphTileProvider};
+
+    #[test]
+    fn test_snap() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::MIN);
+        let node_id = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let (edge_id, opp_edge_id, shape) = provider
+            .with_tile_containing(node_id, |tile| {
+                let node = tile.get_node(node_id).unwrap();
+                let (edge_id, edge) = tile.outbound_edges_with_ids(node).next().unwrap();
+                let mut shape = tile
+                    .get_edge_info(edge)
+                    .unwrap()
+                    .decode_raw_shape::<f64>()
+                    .unwrap();
+                if !edge.edge_info_is_forward() {
+                    shape.reverse();
+                }
+                let opp_edge_id = provider.get_opposing_edge_id(edge_id, tile).unwrap();
+                (edge_id, opp_edge_id, shape)
+            })
+            .unwrap();
+
+        // The start and end of the edge, in its direction of travel
+        let (start, _) = EdgeLocation::snap(&provider, edge_id, shape[0]).unwrap();
+        assert!(start.percent_along < 1e-6);
+        let (end, _) = EdgeLocation::snap(&provider, edge_id, shape[shape.len() - 1]).unwrap();
+        assert!(end.percent_along > 1.0 - 1e-6);
+
+        // A point just off the edge snaps onto it, from either direction
+        let midpoint = (shape[0] + shape[shape.len() - 1]) / 2.0 + Coord { x: 0.0001, y: 0.0 };
+        let (location, snapped) = EdgeLocation::snap(&provider, edge_id, midpoint).unwrap();
+        let (opposite, opposite_snapped) =
+            EdgeLocation::snap(&provider, opp_edge_id, midpoint).unwrap();
+        assert!((location.percent_along + opposite.percent_along - 1.0).abs() < 1e-6);
+        assert!(Haversine.distance(Point(snapped), Point(opposite_snapped)) < 0.01);
+
+        assert!((EdgeLocation::new(edge_id, 1.5).percent_along - 1.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,477 @@
+//! # Maneuvers
+//!
+//! Turn-by-turn maneuvers are built by walking the edges of a path, like Valhalla's `odin`.
+//! Consecutive edges are combined into one maneuver while the route follows the same road,
+//! and a new maneuver starts wherever the driver has to act:
+//! at turns, where the road name changes, and when getting on or off ramps, ferries, and roundabouts.
+//!
+//! Turns are measured between the headings of the edge shapes at each intersection.
+//! Bends at nodes where there is no other way to go never start a maneuver,
+//! and neither do edges inside an intersection (which are folded into the maneuver before them).
+
+use crate::costing::TurnType;
+use crate::{Path, PathGeometry};
+use geo::Coord;
+use std::borrow::Cow;
+use valhalla_graphtile::graph_tile::{GraphTile, HEADING_SAMPLE_DISTANCE, NodeInfo, SignType};
+use valhalla_graphtile::spatial::heading_along_line;
+use valhalla_graphtile::tile_provider::{GraphTileProvider, GraphTileProviderError};
+use valhalla_graphtile::{GraphId, RoadClass, RoadUse};
+
+/// The type of a maneuver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManeuverKind {
+    /// Leave the origin.
+    Start,
+    /// Arrive at the destination.
+    Destination,
+    /// Continue straight onto a road with a different name.
+    Continue,
+    SlightRight,
+    Right,
+    SharpRight,
+    UTurn,
+    SharpLeft,
+    Left,
+    SlightLeft,
+    /// Take a ramp straight ahead.
+    RampStraight,
+    RampRight,
+    RampLeft,
+    /// Leave a highway by an exit ramp on the right.
+    ExitRight,
+    /// Leave a highway by an exit ramp on the left.
+    ExitLeft,
+    /// Merge from a ramp onto a highway.
+    Merge,
+    RoundaboutEnter,
+    RoundaboutExit,
+    FerryEnter,
+    FerryExit,
+}
+
+impl ManeuverKind {
+    /// The maneuver for an ordinary turn.
+    const fn from_turn(turn: TurnType) -> Self {
+        match turn {
+            TurnType::Straight => Self::Continue,
+            TurnType::SlightRight => Self::SlightRight,
+            TurnType::Right => Self::Right,
+            TurnType::SharpRight => Self::SharpRight,
+            TurnType::Reverse => Self::UTurn,
+            TurnType::SharpLeft => Self::SharpLeft,
+            TurnType::Left => Self::Left,
+            TurnType::SlightLeft => Self::SlightLeft,
+        }
+    }
+
+    /// The maneuver for getting onto a ramp, from a highway or another road.
+    const fn ramp(turn: TurnType, from_highway: bool) -> Self {
+        let is_left = matches!(
+            turn,
+            TurnType::SlightLeft | TurnType::Left | TurnType::SharpLeft
+        );
+        match (from_highway, is_left, turn) {
+            (true, true, _) => Self::ExitLeft,
+            (true, false, _) => Self::ExitRight,
+            (false, _, TurnType::Straight) => Self::RampStraight,
+            (false, true, _) => Self::RampLeft,
+            (false, false, _) => Self::RampRight,
+        }
+    }
+}
+
+/// The text of the signs at the start of a maneuver (ex: at a highway exit).
+///
+/// Guide signs are included with the exit branches and destinations.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ManeuverSigns {
+    pub exit_number: Vec<String>,
+    /// The roads which the exit leads to.
+    pub exit_branch: Vec<String>,
+    /// The places which the exit leads toward.
+    pub exit_toward: Vec<String>,
+    pub exit_name: Vec<String>,
+}
+
+impl ManeuverSigns {
+    pub fn is_empty(&self) -> bool {
+        self.exit_number.is_empty()
+            && self.exit_branch.is_empty()
+            && self.exit_toward.is_empty()
+            && self.exit_name.is_empty()
+    }
+}
+
+/// A single instruction along a route.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Maneuver {
+    pub kind: ManeuverKind,
+    /// The names of the road that the maneuver leads onto.
+    pub street_names: Vec<String>,
+    /// The length, in meters.
+    pub length: f64,
+    /// The travel time, in seconds.
+    pub time: f32,
+    /// The index in the route shape where the maneuver begins.
+    pub begin_shape_index: usize,
+    /// The index in the route shape where the maneuver ends (and the next one begins).
+    pub end_shape_index: usize,
+    /// For roundabout entries, the number of the exit to take.
+    pub roundabout_exit_count: Option<u32>,
+    pub signs: ManeuverSigns,
+}
+
+impl Maneuver {
+    fn new(kind: ManeuverKind, edge: &EdgeFacts) -> Self {
+        Self {
+            kind,
+            street_names: edge.names.clone(),
+            length: 0.0,
+            time: 0.0,
+            begin_shape_index: edge.begin_shape_index,
+            end_shape_index: edge.begin_shape_index,
+            roundabout_exit_count: None,
+            signs: edge.signs.clone(),
+        }
+    }
+}
+
+/// What the builder needs to know about each edge of a path.
+struct EdgeFacts {
+    names: Vec<String>,
+    road_use: RoadUse,
+    classification: RoadClass,
+    roundabout: bool,
+    internal: bool,
+    /// Identifies the shape, which an edge shares with its opposing edge.
+    shape_key: (GraphId, u32),
+    signs: ManeuverSigns,
+    /// The number of local edges at the node where the edge begins.
+    begin_node_edge_count: u8,
+    begin_heading: Option<f64>,
+    end_heading: Option<f64>,
+    begin_shape_index: usize,
+    end_shape_index: usize,
+    length: f64,
+    time: f32,
+}
+
+impl EdgeFacts {
+    const fn is_ferry(&self) -> bool {
+        matches!(self.road_use, RoadUse::Ferry | RoadUse::RailFerry)
+    }
+
+    const fn is_ramp(&self) -> bool {
+        matches!(self.road_use, RoadUse::Ramp)
+    }
+
+    const fn is_highway(&self) -> bool {
+        matches!(self.classification, RoadClass::Motorway | RoadClass::Trunk)
+    }
+
+    fn shares_name(&self, names: &[String]) -> bool {
+        self.names.iter().any(|name| names.contains(name))
+    }
+}
+
+/// Builds turn-by-turn [`Maneuver`]s for a [`Path`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ManeuverBuilder {
+    geometry: PathGeometry,
+}
+
+impl ManeuverBuilder {
+    /// Sets how the route shape is assembled,
+    /// so that shape indexes match a trimmed shape.
+    #[must_use]
+    pub const fn with_geometry(mut self, geometry: PathGeometry) -> Self {
+        self.geometry = geometry;
+        self
+    }
+
+    /// Builds the maneuvers for a path.
+    ///
+    /// The first maneuver is always [`ManeuverKind::Start`],
+    /// and the last is [`ManeuverKind::Destination`] (with no length).
+    /// A path with no edges has no maneuvers.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a tile can't be loaded, or an edge shape can't be decoded.
+    pub fn build(
+        &self,
+        provider: &impl GraphTileProvider,
+        path: &Path,
+    ) -> Result<Vec<Maneuver>, GraphTileProviderError> {
+        let edges = edge_facts(provider, path, &self.geometry)?;
+        let Some((first, rest)) = edges.split_first() else {
+            return Ok(Vec::new());
+        };
+
+        let mut maneuvers = Vec::new();
+        let mut current = Maneuver::new(ManeuverKind::Start, first);
+        let mut previous = first;
+        extend(&mut current, first);
+        for edge in rest {
+            if edge.internal {
+                extend(&mut current, edge);
+                continue;
+            }
+
+            match next_maneuver(previous, edge, &current) {
+                Some(kind) => {
+                    maneuvers.push(current);
+                    current = Maneuver::new(kind, edge);
+                    if kind == ManeuverKind::RoundaboutEnter {
+                        current.roundabout_exit_count = Some(1);
+                    }
+                }
+                None => {
+                    // Count the exits passed in a roundabout
+                    if edge.roundabout
+                        && edge.begin_node_edge_count > 2
+                        && let Some(count) = current.roundabout_exit_count.as_mut()
+                    {
+                        *count += 1;
+                    }
+                }
+            }
+            extend(&mut current, edge);
+            previous = edge;
+        }
+
+        let end = current.end_shape_index;
+        maneuvers.push(current);
+        maneuvers.push(Maneuver {
+            kind: ManeuverKind::Destination,
+            street_names: Vec::new(),
+            length: 0.0,
+            time: 0.0,
+            begin_shape_index: end,
+            end_shape_index: end,
+            roundabout_exit_count: None,
+            signs: ManeuverSigns::default(),
+        });
+
+        Ok(maneuvers)
+    }
+}
+
+/// Adds an edge to the end of a maneuver.
+fn extend(maneuver: &mut Maneuver, edge: &EdgeFacts) {
+    maneuver.length += edge.length;
+    maneuver.time += edge.time;
+    maneuver.end_shape_index = edge.end_shape_index;
+}
+
+/// Decides whether moving from `previous` onto `edge` starts a new maneuver, and of which kind.
+fn next_maneuver(
+    previous: &EdgeFacts,
+    edge: &EdgeFacts,
+    current: &Maneuver,
+) -> Option<ManeuverKind> {
+    if edge.is_ferry() != previous.is_ferry() {
+        return Some(if edge.is_ferry() {
+            ManeuverKind::FerryEnter
+        } else {
+            ManeuverKind::FerryExit
+        });
+    }
+    if edge.roundabout != previous.roundabout {
+        return Some(if edge.roundabout {
+            ManeuverKind::RoundaboutEnter
+        } else {
+            ManeuverKind::RoundaboutExit
+        });
+    }
+    if edge.roundabout {
+        return None;
+    }
+    if edge.shape_key == previous.shape_key {
+        return Some(ManeuverKind::UTurn);
+    }
+
+    let turn = match (previous.end_heading, edge.begin_heading) {
+        (Some(from), Some(to)) => {
+            #[expect(
+                clippy::cast_possible_truncation,
+                clippy::cast_sign_loss,
+                reason = "The degree is between 0 and 360"
+            )]
+            let degree = (to - from).rem_euclid(360.0).round() as u16;
+            TurnType::from_degree(degree)
+        }
+        _ => TurnType::Straight,
+    };
+    if edge.is_ramp() && !previous.is_ramp() {
+        return Some(ManeuverKind::ramp(turn, previous.is_highway()));
+    }
+    if previous.is_ramp() && !edge.is_ramp() && edge.is_highway() {
+        return Some(ManeuverKind::Merge);
+    }
+
+    let renamed = !edge.names.is_empty() && !edge.shares_name(&current.street_names);
+    match turn {
+        // Roads often bend at intersections without really turning
+        TurnType::Straight | TurnType::SlightLeft | TurnType::SlightRight if !renamed => None,
+        // A bend where there is nowhere else to go isn't a turn
+        _ if edge.begin_node_edge_count <= 2 => renamed.then_some(ManeuverKind::Continue),
+        TurnType::Straight => Some(ManeuverKind::Continue),
+        _ => Some(ManeuverKind::from_turn(turn)),
+    }
+}
+
+/// Looks up what the builder needs to know about each edge.
+fn edge_facts(
+    provider: &impl GraphTileProvider,
+    path: &Path,
+    geometry: &PathGeometry,
+) -> Result<Vec<EdgeFacts>, GraphTileProviderError> {
+    let (shape, ranges) = geometry.assemble_edges(provider, path)?;
+    let mut facts = Vec::with_capacity(path.edges.len());
+    let mut begin_node = None;
+    for (path_edge, &(begin_shape_index, end_shape_index)) in path.edges.iter().zip(&ranges) {
+        let begin_node_edge_count = match begin_node {
+            Some(node_id) => provider.with_tile_containing(node_id, |tile| {
+                tile.get_node(node_id).map(NodeInfo::local_edge_count)
+            })??,
+            None => 0,
+        };
+
+        let edge_id = path_edge.edge_id;
+        let mut edge_facts = provider.with_tile_containing(edge_id, |tile| {
+            let edge = tile.get_directed_edge(edge_id)?;
+            let names = tile
+                .get_edge_info(edge)?
+                .get_names()
+                .into_iter()
+                .map(Cow::into_owned)
+                .collect();
+            let mut signs = ManeuverSigns::default();
+            if edge.has_exit_signs() {
+                let index = u32::try_from(edge_id.feature_index()).unwrap_or(u32::MAX);
+                for (sign_type, text) in tile.get_edge_signs(index)? {
+                    let list = match sign_type {
+                        SignType::ExitNumber => &mut signs.exit_number,
+                        SignType::ExitBranch | SignType::GuideBranch => &mut signs.exit_branch,
+                        SignType::ExitToward | SignType::GuideToward => &mut signs.exit_toward,
+                        SignType::ExitName => &mut signs.exit_name,
+                        _ => continue,
+                    };
+                    list.push(text.into_owned());
+                }
+            }
+            begin_node = Some(edge.end_node_id());
+
+            Ok::<_, GraphTileProviderError>(EdgeFacts {
+                names,
+                road_use: edge.road_use(),
+                classification: edge.classification(),
+                roundabout: edge.roundabout(),
+                internal: edge.is_intersection_internal(),
+                shape_key: (edge_id.tile_base_id(), edge.edge_info_offset()),
+                signs,
+                begin_node_edge_count,
+                begin_heading: None,
+                end_heading: None,
+                begin_shape_index,
+                end_shape_index,
+                length: path_edge.length,
+                time: path_edge.cost.secs,
+            })
+        })??;
+
+        let edge_shape = &shape[begin_shape_index..=end_shape_index];
+        edge_facts.begin_heading = heading_along_line(edge_shape, HEADING_SAMPLE_DISTANCE);
+        // Measure backwards from the end, then flip it around to face the direction of travel
+        let reversed: Vec<Coord<f64>> = edge_shape.iter().rev().copied().collect();
+        edge_facts.end_heading = heading_along_line(&reversed, HEADING_SAMPLE_DISTANCE)
+            .map(|heading| (heading + 180.0).rem_euclid(360.0));
+        facts.push(edge_facts);
+    }
+
+    Ok(facts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ManeuverBuilder, ManeuverKind};
+    use crate::costing::TurnType;
+    use crate::{PathGeometry, RouteOptions, Router, TimeCosting};
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use valhalla_graphtile::tile_provider::DirectoryGraphTileProvider;
+    use valhalla_graphtile::{Access, GraphId};
+
+    #[test]
+    fn test_ramp_kinds() {
+        assert_eq!(
+            ManeuverKind::ramp(TurnType::SlightRight, true),
+            ManeuverKind::ExitRight
+        );
+        assert_eq!(
+            ManeuverKind::ramp(TurnType::Straight, true),
+            ManeuverKind::ExitRight
+        );
+        assert_eq!(
+            ManeuverKind::ramp(TurnType::Left, true),
+            ManeuverKind::ExitLeft
+        );
+        assert_eq!(
+            ManeuverKind::ramp(TurnType::Straight, false),
+            ManeuverKind::RampStraight
+        );
+        assert_eq!(
+            ManeuverKind::ramp(TurnType::SharpLeft, false),
+            ManeuverKind::RampLeft
+        );
+    }
+
+    #[test]
+    fn test_build() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(4).unwrap());
+        let router = Router::new(provider, TimeCosting::new(Access::Auto));
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let destination = GraphId::try_from_components(2, 763_926, 123).unwrap();
+        let path = router
+            .route(origin, destination, &RouteOptions::default())
+            .unwrap();
+        let provider = router.provider();
+
+        let maneuvers = ManeuverBuilder::default().build(provider, &path).unwrap();
+        let shape = PathGeometry::default().assemble(provider, &path).unwrap();
+        assert!(maneuvers.len() >= 3);
+        assert!(maneuvers.len() < path.edges.len() + 2);
+        assert_eq!(maneuvers[0].kind, ManeuverKind::Start);
+        assert_eq!(maneuvers[0].begin_shape_index, 0);
+        let destination = maneuvers.last().unwrap();
+        assert_eq!(destination.kind, ManeuverKind::Destination);
+        assert_eq!(destination.begin_shape_index, shape.0.len() - 1);
+        assert!(destination.length.abs() < f64::EPSILON);
+
+        // The maneuvers cover the whole route without gaps
+        for pair in maneuvers.windows(2) {
+            assert_eq!(pair[0].end_shape_index, pair[1].begin_shape_index);
+            assert!(pair[0].begin_shape_index <= pair[0].end_shape_index);
+        }
+        let length: f64 = maneuvers.iter().map(|maneuver| maneuver.length).sum();
+        assert!((length - path.length).abs() < 1e-6);
+        let time: f32 = maneuvers.iter().map(|maneuver| maneuver.time).sum();
+        assert!((time - path.cost.secs).abs() < 0.01);
+        assert!(
+            maneuvers[1..maneuvers.len() - 1]
+                .iter()
+                .all(|maneuver| maneuver.kind != ManeuverKind::Start)
+        );
+
+        assert!(
+            ManeuverBuilder::default()
+                .build(provider, &crate::Path::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
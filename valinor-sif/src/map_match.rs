@@ -0,0 +1,1162 @@
+//! # Map matching
+//!
+//! Map matching finds the path through the graph which a trace most likely followed,
+//! using a hidden Markov model (Newson and Krumm, 2009), like Valhalla's `meili`.
+//!
+//! The candidate edges near each trace point are the states.
+//! A candidate is more likely the closer it is to its point
+//! (assuming normally distributed GPS noise),
+//! and a pair of candidates for consecutive points is more likely
+//! the closer the route between them is in length to the distance between the points
+//! (so detours are unlikely).
+//! The Viterbi algorithm then picks the most likely sequence of candidates,
+//! and the routes between them make up the path.
+//!
+//! Points very close to the last matched point add little information,
+//! so they are skipped by the model and interpolated onto the path afterwards.
+//!
+//! Real traces have gaps (ex: in tunnels, or while the receiver was off).
+//! Rather than forcing an unlikely route across a gap,
+//! the match is split into legs wherever consecutive points are too far apart
+//! (in distance or time), or no route connects them.
+//!
+//! Live traces (ex: from vehicle tracking) can be matched online, one point at a time.
+//! The lattice is kept over the points which aren't settled yet,
+//! and the path is confirmed once it falls far enough behind the latest point.
+
+use crate::search::one_to_many;
+use crate::{
+    Costing, EdgeLocation, LocateOptions, LocationCandidate, Path, PathEdge, RouteOptions, Router,
+    RoutingError, TracePoint,
+};
+use geo::{Coord, Distance, Haversine, Point};
+use std::collections::{HashSet, VecDeque};
+use thiserror::Error;
+use valhalla_graphtile::GraphId;
+use valhalla_graphtile::tile_provider::{GraphTileProvider, GraphTileProviderError};
+
+/// The slowest speed (in meters per second) assumed when bounding the search between candidates.
+///
+/// Searches stop once the cost would cover the maximum route distance at this speed,
+/// so routes which are too long are usually not explored at all.
+const BOUNDING_SPEED: f64 = 1.0;
+
+#[derive(Debug, Error)]
+pub enum MatchError {
+    #[error("Tile provider error: {0}")]
+    TileProvider(#[from] GraphTileProviderError),
+    #[error("Routing error: {0}")]
+    Routing(#[from] RoutingError),
+    #[error("No trace point has any candidate edges")]
+    NoCandidates,
+}
+
+/// Parameters of the map matching model.
+///
+/// The defaults are Valhalla's, which suit vehicle traces at typical GPS sampling rates.
+/// Pedestrian traces usually need a smaller search radius and interpolation distance,
+/// and a smaller `beta`, since walkers rarely detour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchConfig {
+    /// The standard deviation of the GPS noise, in meters.
+    pub sigma_z: f64,
+    /// How much the route between consecutive points may differ in length
+    /// from the distance between them, in meters (the scale of the exponential distribution).
+    ///
+    /// Larger values tolerate detours better.
+    pub beta: f64,
+    /// The radius to search for candidate edges around each point, in meters.
+    ///
+    /// Points may override this (see [`TracePoint::radius`]).
+    pub search_radius: f64,
+    /// The maximum number of candidate edges for each point.
+    pub max_candidates: usize,
+    /// The maximum length of the route between consecutive points,
+    /// as a multiple of the distance between them.
+    pub max_route_distance_factor: f64,
+    /// Points within this distance of the last matched point are interpolated onto the path,
+    /// instead of being matched on their own, in meters.
+    pub interpolation_distance: f64,
+    /// The match is split between consecutive points further apart than this, in meters.
+    pub breakage_distance: f64,
+    /// The match is split between consecutive points further apart in time than this,
+    /// in seconds (when both have a time).
+    pub breakage_time: f64,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            sigma_z: 4.07,
+            beta: 3.0,
+            search_radius: 50.0,
+            max_candidates: 10,
+            max_route_distance_factor: 5.0,
+            interpolation_distance: 10.0,
+            breakage_distance: 2000.0,
+            breakage_time: 300.0,
+        }
+    }
+}
+
+impl MatchConfig {
+    #[must_use]
+    pub const fn with_sigma_z(mut self, sigma_z: f64) -> Self {
+        self.sigma_z = sigma_z;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_beta(mut self, beta: f64) -> Self {
+        self.beta = beta;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_search_radius(mut self, search_radius: f64) -> Self {
+        self.search_radius = search_radius;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_max_candidates(mut self, max_candidates: usize) -> Self {
+        self.max_candidates = max_candidates;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_max_route_distance_factor(mut self, factor: f64) -> Self {
+        self.max_route_distance_factor = factor;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_interpolation_distance(mut self, interpolation_distance: f64) -> Self {
+        self.interpolation_distance = interpolation_distance;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_breakage_distance(mut self, breakage_distance: f64) -> Self {
+        self.breakage_distance = breakage_distance;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_breakage_time(mut self, breakage_time: f64) -> Self {
+        self.breakage_time = breakage_time;
+        self
+    }
+
+    /// Is there a gap between consecutive points, given the distance between them (in meters)?
+    fn discontinuity_reason(
+        &self,
+        from: &TracePoint,
+        to: &TracePoint,
+        distance: f64,
+    ) -> Option<DiscontinuityReason> {
+        if distance > self.breakage_distance {
+            return Some(DiscontinuityReason::Distance);
+        }
+        match (from.time, to.time) {
+            (Some(from), Some(to)) if (to - from).as_seconds_f64() > self.breakage_time => {
+                Some(DiscontinuityReason::Time)
+            }
+            _ => None,
+        }
+    }
+
+    /// The log likelihood of a candidate at a distance (in meters) from its point.
+    ///
+    /// Constant terms are left out, since only the differences between states matter.
+    fn emission_log_probability(&self, distance: f64) -> f64 {
+        -0.5 * (distance / self.sigma_z).powi(2)
+    }
+
+    /// The log likelihood of a route between candidates,
+    /// given its length and the distance between their points (both in meters).
+    fn transition_log_probability(&self, route_distance: f64, distance: f64) -> f64 {
+        -(route_distance - distance).abs() / self.beta
+    }
+}
+
+/// A trace point, matched to a location on the path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchedPoint {
+    pub location: EdgeLocation,
+    /// The point on the edge which the trace point was matched to.
+    pub snapped_point: Coord<f64>,
+    /// The distance from the trace point to the snapped point, in meters.
+    pub distance: f64,
+    /// Was the point interpolated onto the path (see [`MatchConfig::interpolation_distance`]),
+    /// rather than matched on its own?
+    pub interpolated: bool,
+}
+
+/// Why a match was split between two trace points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiscontinuityReason {
+    /// The points are further apart than [`MatchConfig::breakage_distance`].
+    Distance,
+    /// The time between the points is longer than [`MatchConfig::breakage_time`].
+    Time,
+    /// No route connects the candidates of the points
+    /// (within [`MatchConfig::max_route_distance_factor`]).
+    NoRoute,
+}
+
+/// A gap in a trace, between the legs of a match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Discontinuity {
+    /// The index of the last trace point before the gap.
+    pub from_index: usize,
+    /// The index of the first trace point after the gap.
+    pub to_index: usize,
+    pub reason: DiscontinuityReason,
+}
+
+/// A continuous part of a match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchedLeg {
+    /// The index of the first trace point on the leg.
+    pub first_index: usize,
+    /// The index of the last trace point matched to the leg on its own
+    /// (any points interpolated just after it lie on the leg too).
+    pub last_index: usize,
+    /// The path from the first point to the last.
+    ///
+    /// The first and last edges are partial, like the paths from [`Router::route_between`].
+    pub path: Path,
+}
+
+/// The most likely path followed by a trace.
+///
+/// Traces with gaps (see [`DiscontinuityReason`]) are matched in several legs,
+/// rather than forcing an unlikely route across each gap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchResult {
+    /// The location of each trace point on the path,
+    /// or `None` for points which couldn't be matched (ex: with no edges nearby).
+    pub points: Vec<Option<MatchedPoint>>,
+    /// The legs of the match, in order.
+    pub legs: Vec<MatchedLeg>,
+    /// The gaps between the legs, in order.
+    pub discontinuities: Vec<Discontinuity>,
+}
+
+/// The newly confirmed parts of a match, from [`OnlineMatcher::push`].
+///
+/// Joining the edges of every update (up to each discontinuity) gives the path of each leg,
+/// the same as [`MatchedLeg::path`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MatchUpdate {
+    /// Trace points whose match is now final, by their index (in the order they were pushed),
+    /// in order.
+    ///
+    /// The location is `None` for points which couldn't be matched (ex: with no edges nearby).
+    pub points: Vec<(usize, Option<MatchedPoint>)>,
+    /// Newly confirmed edges of the path, in order.
+    ///
+    /// The last edge of a leg is only confirmed once the leg ends,
+    /// since the next route may continue along it.
+    pub edges: Vec<PathEdge>,
+    /// The gap before the point just pushed, if it starts a new leg.
+    ///
+    /// The points and edges of this update are all on the leg before the gap.
+    pub discontinuity: Option<Discontinuity>,
+}
+
+/// A candidate location for a trace point.
+struct State {
+    location: EdgeLocation,
+    snapped_point: Coord<f64>,
+    distance: f64,
+}
+
+/// The best way to reach a state from the states of the previous point.
+struct Arrival {
+    log_probability: f64,
+    predecessor: usize,
+    route: Path,
+}
+
+/// The Viterbi lattice over the states of a continuous run of trace points.
+struct Lattice {
+    /// The log probability of the most likely sequence of states
+    /// ending at each state of the last step.
+    log_probabilities: Vec<f64>,
+    /// The best arrival at each state of each step after the first.
+    arrivals: Vec<Vec<Option<Arrival>>>,
+}
+
+impl Lattice {
+    fn new(states: &[State], config: &MatchConfig) -> Self {
+        Self {
+            log_probabilities: states
+                .iter()
+                .map(|state| config.emission_log_probability(state.distance))
+                .collect(),
+            arrivals: Vec::new(),
+        }
+    }
+
+    /// The number of transitions between steps in the lattice.
+    const fn transitions(&self) -> usize {
+        self.arrivals.len()
+    }
+
+    /// Adds the states of the next step, given the routes to them from the states of the last one.
+    ///
+    /// Returns false (leaving the lattice unchanged) if none of them can be reached.
+    fn advance(
+        &mut self,
+        routes: Vec<Vec<Option<Path>>>,
+        to_states: &[State],
+        distance: f64,
+        config: &MatchConfig,
+    ) -> bool {
+        let mut step_arrivals: Vec<Option<Arrival>> = Vec::new();
+        step_arrivals.resize_with(to_states.len(), || None);
+        for (from, routes) in routes.into_iter().enumerate() {
+            // States which couldn't be reached themselves lead nowhere
+            if !self.log_probabilities[from].is_finite() {
+                continue;
+            }
+            for (to, route) in routes.into_iter().enumerate() {
+                let Some(route) = route else {
+                    continue;
+                };
+                let log_probability = self.log_probabilities[from]
+                    + config.transition_log_probability(route.length, distance)
+                    + config.emission_log_probability(to_states[to].distance);
+                if step_arrivals[to]
+                    .as_ref()
+                    .is_none_or(|best| log_probability > best.log_probability)
+                {
+                    step_arrivals[to] = Some(Arrival {
+                        log_probability,
+                        predecessor: from,
+                        route,
+                    });
+                }
+            }
+        }
+
+        if step_arrivals.iter().all(Option::is_none) {
+            return false;
+        }
+        self.log_probabilities = step_arrivals
+            .iter()
+            .map(|arrival| {
+                arrival
+                    .as_ref()
+                    .map_or(f64::NEG_INFINITY, |arrival| arrival.log_probability)
+            })
+            .collect();
+        self.arrivals.push(step_arrivals);
+        true
+    }
+
+    /// The most likely state of each step, given what has been seen so far.
+    fn best_states(&self) -> Vec<usize> {
+        let mut best = self
+            .log_probabilities
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map_or(0, |(index, _)| index);
+        let mut chosen = vec![best; self.arrivals.len() + 1];
+        for (step, step_arrivals) in self.arrivals.iter().enumerate().rev() {
+            let Some(arrival) = &step_arrivals[best] else {
+                unreachable!("Every chosen state was reached from the previous step");
+            };
+            best = arrival.predecessor;
+            chosen[step] = best;
+        }
+        chosen
+    }
+
+    /// Settles the states of the first `count + 1` steps, and the routes between them.
+    ///
+    /// The last settled step becomes the first step of the lattice,
+    /// and only the sequences of states through its settled state are kept.
+    fn confirm(&mut self, count: usize) -> (Vec<usize>, Vec<Path>) {
+        let mut chosen = self.best_states();
+        chosen.truncate(count + 1);
+        let routes = self
+            .arrivals
+            .drain(..count)
+            .zip(&chosen[1..])
+            .map(|(step_arrivals, &to)| {
+                let Some(arrival) = step_arrivals.into_iter().nth(to).flatten() else {
+                    unreachable!("Every chosen state was reached from the previous step");
+                };
+                arrival.route
+            })
+            .collect();
+
+        let mut alive = vec![false; chosen[count] + 1];
+        alive[chosen[count]] = true;
+        for step_arrivals in &mut self.arrivals {
+            for arrival in step_arrivals.iter_mut() {
+                if arrival.as_ref().is_some_and(|arrival| {
+                    !alive.get(arrival.predecessor).copied().unwrap_or(false)
+                }) {
+                    *arrival = None;
+                }
+            }
+            alive = step_arrivals.iter().map(Option::is_some).collect();
+        }
+        for (state, log_probability) in self.log_probabilities.iter_mut().enumerate() {
+            if !alive.get(state).copied().unwrap_or(false) {
+                *log_probability = f64::NEG_INFINITY;
+            }
+        }
+
+        (chosen, routes)
+    }
+}
+
+/// A trace point which was matched on its own, in a lattice.
+struct Step {
+    index: usize,
+    point: TracePoint,
+    states: Vec<State>,
+    /// The later points which weren't matched on their own,
+    /// which are settled along with the route out of this step.
+    followers: Vec<Follower>,
+}
+
+/// A trace point which wasn't matched on its own.
+struct Follower {
+    index: usize,
+    /// The coordinate of a point to interpolate onto the path,
+    /// or `None` for a point without any candidates.
+    coordinate: Option<Coord<f64>>,
+}
+
+/// Matches traces to the graph of a [`Router`], using its costing model.
+///
+/// A matcher is cheap to create, so services can make one per request
+/// (ex: with the request's [`MatchConfig`]) around a shared router.
+///
+/// ```
+/// # use std::num::NonZeroUsize;
+/// # use std::path::PathBuf;
+/// # use geo::Coord;
+/// # use valhalla_graphtile::Access;
+/// # use valhalla_graphtile::tile_provider::DirectoryGraphTileProvider;
+/// use valinor_sif::{MapMatcher, MatchConfig, Router, TimeCosting, TracePoint};
+///
+/// # let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+/// #     .join("../valhalla-graphtile/fixtures/andorra-tiles");
+/// # let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(4).unwrap());
+/// let router = Router::new(provider, TimeCosting::new(Access::Auto));
+/// let matcher = MapMatcher::new(&router, MatchConfig::default());
+///
+/// let trace = TracePoint::parse_csv("42.5063,1.5218\n42.5068,1.5226\n").unwrap();
+/// let result = matcher.match_trace(&trace).unwrap();
+/// for leg in &result.legs {
+///     println!("{} edges; {:.0}m", leg.path.edges.len(), leg.path.length);
+/// }
+/// ```
+pub struct MapMatcher<'a, P, C> {
+    router: &'a Router<P, C>,
+    config: MatchConfig,
+}
+
+impl<'a, P: GraphTileProvider, C: Costing> MapMatcher<'a, P, C> {
+    pub const fn new(router: &'a Router<P, C>, config: MatchConfig) -> Self {
+        Self { router, config }
+    }
+
+    /// The parameters of the model.
+    pub const fn config(&self) -> &MatchConfig {
+        &self.config
+    }
+
+    /// Finds the most likely path followed by a trace,
+    /// along with the location of each trace point on it.
+    ///
+    /// The match is split into legs at any gaps in the trace (see [`MatchResult`]).
+    ///
+    /// # Errors
+    ///
+    /// Fails if no point has any candidate edges, or if a tile can't be loaded along the way.
+    pub fn match_trace(&self, trace: &[TracePoint]) -> Result<MatchResult, MatchError> {
+        match_trace(self.router, trace, &self.config)
+    }
+
+    /// Starts matching a trace as it arrives, one point at a time (see [`OnlineMatcher`]).
+    ///
+    /// The match of each point is confirmed once at most `max_lag` later points
+    /// have been matched on their own.
+    pub fn online(&self, max_lag: usize) -> OnlineMatcher<'a, P, C> {
+        OnlineMatcher {
+            router: self.router,
+            config: self.config.clone(),
+            max_lag,
+            point_count: 0,
+            last_kept: None,
+            steps: VecDeque::new(),
+            lattice: Lattice::new(&[], &self.config),
+            last_edge: None,
+        }
+    }
+}
+
+/// Matches a trace incrementally, as its points arrive (ex: for live vehicle tracking).
+///
+/// The matcher keeps the Viterbi lattice over the points which aren't confirmed yet.
+/// Later points can change which candidates are most likely for earlier ones,
+/// so the match of a point is only confirmed once enough later points have been matched
+/// (the lag, in points matched on their own; see [`MapMatcher::online`]).
+/// A smaller lag confirms the path sooner, but may pick a less likely one
+/// (or even one which leads nowhere, ending the leg early).
+/// Everything left is confirmed at a gap in the trace, or when the trace is finished.
+///
+/// With an unbounded lag, this finds the same path as [`MapMatcher::match_trace`].
+pub struct OnlineMatcher<'a, P, C> {
+    router: &'a Router<P, C>,
+    config: MatchConfig,
+    max_lag: usize,
+    /// The number of points pushed so far.
+    point_count: usize,
+    /// The coordinate of the last point which wasn't interpolated.
+    last_kept: Option<Coord<f64>>,
+    /// The steps of the current leg which aren't settled yet, in order.
+    ///
+    /// The state of the first step may be confirmed already,
+    /// but not the route out of it.
+    steps: VecDeque<Step>,
+    lattice: Lattice,
+    /// The last confirmed edge, which the next route may continue along.
+    last_edge: Option<PathEdge>,
+}
+
+impl<P: GraphTileProvider, C: Costing> OnlineMatcher<'_, P, C> {
+    /// Matches the next point of the trace,
+    /// returning the parts of the match which are now confirmed.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a tile can't be loaded, or a route can't be found between candidates.
+    pub fn push(&mut self, point: TracePoint) -> Result<MatchUpdate, MatchError> {
+        let options = locate_options(&self.config);
+        let router = self.router;
+        self.push_with(point, |point| {
+            let mut candidates = router.locate_trace(std::slice::from_ref(point), &options)?;
+            Ok(candidates.pop().unwrap_or_default())
+        })
+    }
+
+    /// Confirms the rest of the match, once the trace is finished.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a tile can't be loaded.
+    pub fn finish(mut self) -> Result<MatchUpdate, MatchError> {
+        let mut update = MatchUpdate::default();
+        self.finish_leg(&mut update)?;
+        Ok(update)
+    }
+
+    /// Matches the next point of the trace, with a function to locate its candidates
+    /// (if it isn't interpolated).
+    fn push_with(
+        &mut self,
+        point: TracePoint,
+        locate: impl FnOnce(&TracePoint) -> Result<Vec<LocationCandidate>, GraphTileProviderError>,
+    ) -> Result<MatchUpdate, MatchError> {
+        let index = self.point_count;
+        self.point_count += 1;
+        let mut update = MatchUpdate::default();
+
+        // Points which are too close to the last one kept are interpolated onto the path
+        if self.last_kept.is_some_and(|last| {
+            is_within_interpolation_distance(&self.config, last, point.coordinate)
+        }) {
+            self.follow(index, Some(point.coordinate), &mut update);
+            return Ok(update);
+        }
+        self.last_kept = Some(point.coordinate);
+
+        let states: Vec<_> = locate(&point)?
+            .into_iter()
+            .map(|candidate| State {
+                location: candidate.location,
+                snapped_point: candidate.snapped_point,
+                distance: candidate.distance,
+            })
+            .collect();
+        // Points without candidates can't be matched at all
+        if states.is_empty() {
+            self.follow(index, None, &mut update);
+            return Ok(update);
+        }
+        let step = Step {
+            index,
+            point,
+            states,
+            followers: Vec::new(),
+        };
+
+        let Some(last) = self.steps.back() else {
+            self.start_leg(step);
+            return Ok(update);
+        };
+        let distance = Haversine.distance(Point(last.point.coordinate), Point(point.coordinate));
+        let mut reason = self
+            .config
+            .discontinuity_reason(&last.point, &point, distance);
+        if reason.is_none() {
+            let routes = transition_routes(
+                self.router,
+                &last.states,
+                &step.states,
+                distance,
+                &self.config,
+            )?;
+            if !self
+                .lattice
+                .advance(routes, &step.states, distance, &self.config)
+            {
+                reason = Some(DiscontinuityReason::NoRoute);
+            }
+        }
+        if let Some(reason) = reason {
+            let from_index = last.index;
+            self.finish_leg(&mut update)?;
+            update.discontinuity = Some(Discontinuity {
+                from_index,
+                to_index: index,
+                reason,
+            });
+            self.start_leg(step);
+            return Ok(update);
+        }
+
+        self.steps.push_back(step);
+        let count = self.lattice.transitions().saturating_sub(self.max_lag);
+        if count > 0 {
+            let (chosen, routes) = self.lattice.confirm(count);
+            let settled: Vec<_> = self.steps.drain(..count).collect();
+            for ((step, state), route) in settled.into_iter().zip(chosen).zip(routes) {
+                self.settle(step, state, Some(route), &mut update)?;
+            }
+        }
+        Ok(update)
+    }
+
+    /// Adds a point which isn't matched on its own to the last step.
+    fn follow(&mut self, index: usize, coordinate: Option<Coord<f64>>, update: &mut MatchUpdate) {
+        match self.steps.back_mut() {
+            Some(step) => step.followers.push(Follower { index, coordinate }),
+            // There's nothing to interpolate onto before the first match
+            None => update.points.push((index, None)),
+        }
+    }
+
+    fn start_leg(&mut self, step: Step) {
+        self.lattice = Lattice::new(&step.states, &self.config);
+        self.steps.push_back(step);
+    }
+
+    /// Confirms the rest of the current leg.
+    fn finish_leg(&mut self, update: &mut MatchUpdate) -> Result<(), MatchError> {
+        if self.steps.is_empty() {
+            return Ok(());
+        }
+        let (chosen, routes) = self.lattice.confirm(self.lattice.transitions());
+        let mut routes = routes.into_iter();
+        for (step, state) in std::mem::take(&mut self.steps).into_iter().zip(chosen) {
+            self.settle(step, state, routes.next(), update)?;
+        }
+        update.edges.extend(self.last_edge.take());
+        Ok(())
+    }
+
+    /// Confirms the state of a step, the route out of it (if it isn't the last of its leg),
+    /// and the points which follow it.
+    fn settle(
+        &mut self,
+        step: Step,
+        state: usize,
+        route: Option<Path>,
+        update: &mut MatchUpdate,
+    ) -> Result<(), MatchError> {
+        let state = &step.states[state];
+        update.points.push((
+            step.index,
+            Some(MatchedPoint {
+                location: state.location,
+                snapped_point: state.snapped_point,
+                distance: state.distance,
+                interpolated: false,
+            }),
+        ));
+
+        // Points are interpolated onto the route they fall within
+        // (or the edge of the last matched point, before a gap or at the end)
+        let edge_ids: Vec<_> = match &route {
+            Some(route) => route.edge_ids().collect(),
+            None => vec![state.location.edge_id],
+        };
+        for follower in step.followers {
+            let matched = match follower.coordinate {
+                Some(coordinate) => interpolate(self.router.provider(), &edge_ids, coordinate)?,
+                None => None,
+            };
+            update.points.push((follower.index, matched));
+        }
+
+        match route {
+            // Join the routes, merging the partial edges where they meet
+            Some(route) => {
+                for path_edge in route.edges {
+                    match &mut self.last_edge {
+                        Some(last) if last.edge_id == path_edge.edge_id => {
+                            last.cost += path_edge.cost;
+                            last.length += path_edge.length;
+                        }
+                        last => update.edges.extend(last.replace(path_edge)),
+                    }
+                }
+            }
+            // A leg matched from a single point still has an edge
+            None if self.last_edge.is_none() => {
+                self.last_edge = Some(self.router.partial_edge(state.location.edge_id, 0.0)?.0);
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// The options for finding the candidates of each point.
+fn locate_options(config: &MatchConfig) -> LocateOptions {
+    LocateOptions::default()
+        .with_radius(config.search_radius)
+        .with_max_candidates(config.max_candidates)
+}
+
+/// Is a point close enough to the last point kept to be interpolated?
+fn is_within_interpolation_distance(
+    config: &MatchConfig,
+    last: Coord<f64>,
+    next: Coord<f64>,
+) -> bool {
+    Haversine.distance(Point(last), Point(next)) <= config.interpolation_distance
+}
+
+/// A leg of a match, as it's confirmed.
+#[derive(Default)]
+struct LegBuilder {
+    /// The indices of the first and last points matched on their own.
+    indices: Option<(usize, usize)>,
+    edges: Vec<PathEdge>,
+}
+
+impl LegBuilder {
+    /// Adds the confirmed parts of an update to the result (and to this leg, if on it).
+    fn add(&mut self, update: MatchUpdate, result: &mut MatchResult) {
+        for (index, matched) in update.points {
+            if matched.is_some_and(|matched| !matched.interpolated) {
+                let (first, _) = self.indices.unwrap_or((index, index));
+                self.indices = Some((first, index));
+            }
+            result.points[index] = matched;
+        }
+        self.edges.extend(update.edges);
+        if let Some(discontinuity) = update.discontinuity {
+            self.finish(result);
+            result.discontinuities.push(discontinuity);
+        }
+    }
+
+    fn finish(&mut self, result: &mut MatchResult) {
+        if let Some((first_index, last_index)) = self.indices.take() {
+            result.legs.push(MatchedLeg {
+                first_index,
+                last_index,
+                path: Path::from_edges(std::mem::take(&mut self.edges)),
+            });
+        }
+    }
+}
+
+/// Finds the most likely path followed by a trace.
+fn match_trace<P: GraphTileProvider, C: Costing>(
+    router: &Router<P, C>,
+    trace: &[TracePoint],
+    config: &MatchConfig,
+) -> Result<MatchResult, MatchError> {
+    // Locating the candidates of every point matched on its own at once is much faster
+    // (these are the points the online matcher will ask for, in order)
+    let mut kept_trace: Vec<TracePoint> = Vec::new();
+    for point in trace {
+        if kept_trace.last().is_none_or(|last| {
+            !is_within_interpolation_distance(config, last.coordinate, point.coordinate)
+        }) {
+            kept_trace.push(*point);
+        }
+    }
+    let mut candidates = router
+        .locate_trace(&kept_trace, &locate_options(config))?
+        .into_iter();
+
+    // With an unbounded lag, nothing is confirmed until the end of each leg
+    let mut matcher = MapMatcher::new(router, config.clone()).online(usize::MAX);
+    let mut result = MatchResult {
+        points: vec![None; trace.len()],
+        legs: Vec::new(),
+        discontinuities: Vec::new(),
+    };
+    let mut leg = LegBuilder::default();
+    for point in trace {
+        let update = matcher.push_with(*point, |_| Ok(candidates.next().unwrap_or_default()))?;
+        leg.add(update, &mut result);
+    }
+    leg.add(matcher.finish()?, &mut result);
+    leg.finish(&mut result);
+
+    if result.legs.is_empty() {
+        return Err(MatchError::NoCandidates);
+    }
+    Ok(result)
+}
+
+/// Finds the routes from each of the states of one point to each of the states of the next,
+/// if they are short enough (see [`MatchConfig::max_route_distance_factor`]).
+fn transition_routes<P: GraphTileProvider, C: Costing>(
+    router: &Router<P, C>,
+    from_states: &[State],
+    to_states: &[State],
+    distance: f64,
+    config: &MatchConfig,
+) -> Result<Vec<Vec<Option<Path>>>, RoutingError> {
+    let max_route_distance = distance * config.max_route_distance_factor;
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "Costs are f32; the bound doesn't need to be precise"
+    )]
+    let max_cost = (max_route_distance / BOUNDING_SPEED) as f32;
+    let options = RouteOptions::default().with_max_cost(max_cost);
+    let context = router.search_context();
+    let start_nodes = to_states
+        .iter()
+        .map(|state| router.start_node_id(state.location.edge_id))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut paths = Vec::with_capacity(from_states.len());
+    for from in from_states {
+        let end_node = router.directed_edge(from.location.edge_id)?.end_node_id();
+        let middles = one_to_many(
+            router.provider(),
+            router.costing(),
+            &context,
+            end_node,
+            &start_nodes,
+            &HashSet::new(),
+            &options,
+        )?;
+
+        let from_length = f64::from(router.directed_edge(from.location.edge_id)?.length());
+        let mut from_paths = Vec::with_capacity(to_states.len());
+        for (to, middle) in to_states.iter().zip(middles) {
+            // GPS noise can put a point slightly behind the previous one on the same edge,
+            // which is treated as standing still rather than as a U-turn
+            let advance = (to.location.percent_along - from.location.percent_along) * from_length;
+            let route =
+                if from.location.edge_id == to.location.edge_id && advance >= -config.sigma_z {
+                    let fraction = to.location.percent_along - from.location.percent_along;
+                    Some(Path::from_edges(vec![
+                        router
+                            .partial_edge(to.location.edge_id, fraction.max(0.0))?
+                            .0,
+                    ]))
+                } else if let Some(middle) = middle {
+                    Some(router.join_partial_edges(from.location, middle, to.location)?)
+                } else {
+                    None
+                };
+            from_paths.push(route.filter(|route| route.length <= max_route_distance));
+        }
+        paths.push(from_paths);
+    }
+
+    Ok(paths)
+}
+
+/// Matches a point which was skipped by the model to the closest of some edges.
+fn interpolate(
+    provider: &impl GraphTileProvider,
+    edge_ids: &[GraphId],
+    coordinate: Coord<f64>,
+) -> Result<Option<MatchedPoint>, GraphTileProviderError> {
+    let mut best: Option<MatchedPoint> = None;
+    for &edge_id in edge_ids {
+        let (location, snapped_point) = EdgeLocation::snap(provider, edge_id, coordinate)?;
+        let distance = Haversine.distance(Point(coordinate), Point(snapped_point));
+        if best.is_none_or(|best| distance < best.distance) {
+            best = Some(MatchedPoint {
+                location,
+                snapped_point,
+                distance,
+                interpolated: true,
+            });
+        }
+    }
+
+    Ok(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Discontinuity, DiscontinuityReason, MapMatcher, MatchConfig, MatchError};
+    use crate::PathEdge;
+    use crate::{PathGeometry, RouteOptions, Router, TimeCosting, TracePoint};
+    use chrono::{TimeDelta, Utc};
+    use geo::{Coord, Densify, Haversine, LineString};
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use valhalla_graphtile::tile_provider::DirectoryGraphTileProvider;
+    use valhalla_graphtile::{Access, GraphId};
+
+    fn router() -> Router<DirectoryGraphTileProvider, TimeCosting> {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(4).unwrap());
+        Router::new(provider, TimeCosting::new(Access::Auto))
+    }
+
+    /// A trace along the shape of a route, with a point every few meters and some noise.
+    fn trace_along(shape: &LineString<f64>, spacing: f64) -> Vec<TracePoint> {
+        Haversine
+            .densify(shape, spacing)
+            .coords()
+            .enumerate()
+            .map(|(index, &coordinate)| {
+                let offset = if index % 2 == 0 { 0.00003 } else { -0.00003 };
+                TracePoint::new(
+                    coordinate
+                        + Coord {
+                            x: offset,
+                            y: offset,
+                        },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_match_trace() {
+        let router = router();
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let destination = GraphId::try_from_components(2, 763_926, 123).unwrap();
+        let route = router
+            .route(origin, destination, &RouteOptions::default())
+            .unwrap();
+        let shape = PathGeometry::default()
+            .assemble(router.provider(), &route)
+            .unwrap();
+        let trace = trace_along(&shape, 15.0);
+
+        let result = MapMatcher::new(&router, MatchConfig::default())
+            .match_trace(&trace)
+            .unwrap();
+        assert_eq!(result.points.len(), trace.len());
+        assert_eq!(result.legs.len(), 1);
+        assert!(result.discontinuities.is_empty());
+        let leg = &result.legs[0];
+        assert_eq!((leg.first_index, leg.last_index), (0, trace.len() - 1));
+        assert!(result.points.iter().all(Option::is_some));
+        assert!(
+            result
+                .points
+                .iter()
+                .flatten()
+                .all(|point| point.distance < 15.0)
+        );
+
+        // The matched path follows the route (apart from the partial edges at either end),
+        // though noise may add the odd U-turn on a very short edge
+        let mut matched = leg.path.edge_ids();
+        assert!(
+            route.edges[1..route.edges.len() - 1]
+                .iter()
+                .all(|edge| matched.any(|edge_id| edge_id == edge.edge_id)),
+        );
+        assert!((leg.path.length - route.length).abs() < 30.0);
+
+        // Points close together are interpolated
+        let result = MapMatcher::new(
+            &router,
+            MatchConfig::default().with_interpolation_distance(40.0),
+        )
+        .match_trace(&trace)
+        .unwrap();
+        assert!(
+            result
+                .points
+                .iter()
+                .flatten()
+                .any(|point| point.interpolated)
+        );
+        assert!(result.points.iter().all(Option::is_some));
+    }
+
+    #[test]
+    fn test_match_trace_discontinuities() {
+        let router = router();
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let destination = GraphId::try_from_components(2, 763_926, 123).unwrap();
+        let route = router
+            .route(origin, destination, &RouteOptions::default())
+            .unwrap();
+        let shape = PathGeometry::default()
+            .assemble(router.provider(), &route)
+            .unwrap();
+        let start = Utc::now();
+        let mut trace: Vec<_> = trace_along(&shape, 15.0)
+            .into_iter()
+            .zip(0..)
+            .map(|(point, secs)| point.with_time(start + TimeDelta::seconds(secs)))
+            .collect();
+        let count = trace.len();
+
+        // A gap in the middle of the trace
+        let gap = count / 3..count / 3 + 20;
+        let trace_with_gap: Vec<_> = trace
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !gap.contains(index))
+            .map(|(_, point)| *point)
+            .collect();
+        let result = MapMatcher::new(&router, MatchConfig::default().with_breakage_distance(60.0))
+            .match_trace(&trace_with_gap)
+            .unwrap();
+        assert_eq!(result.legs.len(), 2);
+        assert_eq!(
+            result.discontinuities,
+            [Discontinuity {
+                from_index: gap.start - 1,
+                to_index: gap.start,
+                reason: DiscontinuityReason::Distance,
+            }]
+        );
+        assert_eq!(result.legs[0].last_index, gap.start - 1);
+        assert_eq!(result.legs[1].first_index, gap.start);
+        assert!(result.points.iter().all(Option::is_some));
+
+        // A pause in the middle of the trace
+        for point in &mut trace[count / 2..] {
+            point.time = point.time.map(|time| time + TimeDelta::minutes(10));
+        }
+        let result = MapMatcher::new(&router, MatchConfig::default())
+            .match_trace(&trace)
+            .unwrap();
+        assert_eq!(result.legs.len(), 2);
+        assert_eq!(result.discontinuities[0].reason, DiscontinuityReason::Time);
+        assert_eq!(result.discontinuities[0].to_index, count / 2);
+
+        // No routes short enough between most points
+        let result = MapMatcher::new(
+            &router,
+            MatchConfig::default().with_max_route_distance_factor(0.1),
+        )
+        .match_trace(&trace[..10])
+        .unwrap();
+        assert!(result.legs.len() > 1);
+        assert_eq!(result.discontinuities.len(), result.legs.len() - 1);
+        assert!(
+            result
+                .discontinuities
+                .iter()
+                .all(|discontinuity| discontinuity.reason == DiscontinuityReason::NoRoute)
+        );
+    }
+
+    #[test]
+    fn test_online_match() {
+        let router = router();
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let destination = GraphId::try_from_components(2, 763_926, 123).unwrap();
+        let route = router
+            .route(origin, destination, &RouteOptions::default())
+            .unwrap();
+        let shape = PathGeometry::default()
+            .assemble(router.provider(), &route)
+            .unwrap();
+        let trace = trace_along(&shape, 15.0);
+        let config = MatchConfig::default().with_interpolation_distance(0.0);
+        let matcher = MapMatcher::new(&router, config);
+        let batch = matcher.match_trace(&trace).unwrap();
+
+        for max_lag in [3, usize::MAX] {
+            let mut online = matcher.online(max_lag);
+            let mut points = Vec::new();
+            let mut edges: Vec<PathEdge> = Vec::new();
+            for (pushed, point) in trace.iter().enumerate() {
+                let update = online.push(*point).unwrap();
+                assert!(update.discontinuity.is_none());
+                points.extend(update.points);
+                edges.extend(update.edges);
+                // Points are confirmed in order, within the lag
+                assert!(points.iter().map(|(index, _)| *index).eq(0..points.len()));
+                assert!(pushed + 1 - points.len() <= max_lag.saturating_add(1));
+            }
+            let update = online.finish().unwrap();
+            points.extend(update.points);
+            edges.extend(update.edges);
+            assert_eq!(points.len(), trace.len());
+            assert!(points.iter().all(|(_, matched)| matched.is_some()));
+            assert!(
+                edges
+                    .windows(2)
+                    .all(|pair| pair[0].edge_id != pair[1].edge_id)
+            );
+
+            // On a trace this clean, a short lag is enough to find the most likely path
+            assert_eq!(edges, batch.legs[0].path.edges);
+            assert!(
+                points
+                    .into_iter()
+                    .map(|(_, matched)| matched)
+                    .eq(batch.points.iter().copied())
+            );
+        }
+
+        // Gaps end the leg, confirming everything before them
+        let mut online = MapMatcher::new(
+            &router,
+            MatchConfig::default().with_breakage_distance(100.0),
+        )
+        .online(usize::MAX);
+        assert!(online.push(trace[0]).unwrap().points.is_empty());
+        let update = online.push(trace[trace.len() - 1]).unwrap();
+        assert_eq!(update.points.len(), 1);
+        assert_eq!(update.edges.len(), 1);
+        assert_eq!(
+            update.discontinuity,
+            Some(Discontinuity {
+                from_index: 0,
+                to_index: 1,
+                reason: DiscontinuityReason::Distance,
+            })
+        );
+    }
+
+    #[test]
+    fn test_match_trace_without_candidates() {
+        let trace = [
+            TracePoint::new(Coord { x: 1.0, y: 0.0 }),
+            TracePoint::new(Coord { x: 1.001, y: 0.0 }),
+        ];
+        assert!(matches!(
+            MapMatcher::new(&router(), MatchConfig::default()).match_trace(&trace),
+            Err(MatchError::NoCandidates)
+        ));
+    }
+}
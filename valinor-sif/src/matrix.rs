@@ -0,0 +1,49 @@
+//! # Time/distance matrices
+//!
+//! A matrix holds the best time and distance from each of a set of sources to each of a set of targets,
+//! like the `sources_to_targets` field of Valhalla's matrix response.
+//! Rather than routing every pair separately,
+//! the router runs a single one-to-many search per source
+//! (or per target, when arriving by a given time),
+//! which stops as soon as every other location has been reached.
+
+use crate::{Path, PathEdge};
+
+/// The best route between a source and a target in a [`Matrix`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixEntry {
+    /// The index of the source.
+    pub from_index: usize,
+    /// The index of the target.
+    pub to_index: usize,
+    /// The travel time, in seconds, or `None` if the target can't be reached.
+    pub time: Option<f32>,
+    /// The travel distance, in meters, or `None` if the target can't be reached.
+    pub distance: Option<f64>,
+}
+
+impl MatrixEntry {
+    pub(crate) fn new(from_index: usize, to_index: usize, edges: Option<Vec<PathEdge>>) -> Self {
+        let path = edges.map(Path::from_edges);
+        Self {
+            from_index,
+            to_index,
+            time: path.as_ref().map(|path| path.cost.secs),
+            distance: path.map(|path| path.length),
+        }
+    }
+}
+
+/// The best routes from each source to each target.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Matrix {
+    /// One row per source, with one entry per target (in the order they were given).
+    pub sources_to_targets: Vec<Vec<MatrixEntry>>,
+}
+
+impl Matrix {
+    /// The entry for a source and target, by their indexes.
+    pub fn get(&self, from_index: usize, to_index: usize) -> Option<&MatrixEntry> {
+        self.sources_to_targets.get(from_index)?.get(to_index)
+    }
+}
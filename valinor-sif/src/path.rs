@@ -0,0 +1,44 @@
+use crate::Cost;
+use valhalla_graphtile::GraphId;
+
+/// A single directed edge along a [`Path`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathEdge {
+    pub edge_id: GraphId,
+    /// The cost of the edge, including the transition onto it from the previous edge.
+    pub cost: Cost,
+    /// The length of the edge, in meters.
+    pub length: f64,
+}
+
+/// A route through the graph, as a sequence of connected directed edges.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Path {
+    /// The edges in order of travel.
+    ///
+    /// This is empty when the origin and destination are the same.
+    pub edges: Vec<PathEdge>,
+    /// The total cost of the path.
+    pub cost: Cost,
+    /// The total length of the path, in meters.
+    pub length: f64,
+}
+
+impl Path {
+    pub(crate) fn from_edges(edges: Vec<PathEdge>) -> Self {
+        let cost = edges
+            .iter()
+            .fold(Cost::ZERO, |total, edge| total + edge.cost);
+        let length = edges.iter().fold(0.0, |total, edge| total + edge.length);
+        Self {
+            edges,
+            cost,
+            length,
+        }
+    }
+
+    /// The IDs of the edges in order of travel.
+    pub fn edge_ids(&self) -> impl Iterator<Item = GraphId> + '_ {
+        self.edges.iter().map(|edge| edge.edge_id)
+    }
+}
@@ -0,0 +1,995 @@
+use crate::alternatives::alternative_paths;
+use crate::avoid::avoided_edges;
+use crate::locate::{locate, locate_trace};
+use crate::search::{SearchContext, expansion, one_to_many, shortest_path};
+use crate::{
+    AlternativeOptions, AvoidLocation, Cost, Costing, EdgeContext, EdgeLocation, Expansion,
+    LiveTraffic, LocateOptions, LocationCandidate, Matrix, MatrixEntry, Path, PathEdge, TimeZones,
+    TracePoint,
+};
+use chrono::{NaiveDateTime, Offset, Utc};
+use geo::{Coord, Polygon};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use thiserror::Error;
+use valhalla_graphtile::GraphId;
+use valhalla_graphtile::graph_tile::{DirectedEdge, GraphTile, LookupError};
+use valhalla_graphtile::tile_provider::{GraphTileProvider, GraphTileProviderError};
+
+#[derive(Debug, Error)]
+pub enum RoutingError {
+    #[error("Tile provider error: {0}")]
+    TileProvider(#[from] GraphTileProviderError),
+    #[error("Graph tile lookup error: {0}")]
+    Lookup(#[from] LookupError),
+    #[error("No route found between the origin and destination")]
+    NoRoute,
+    #[error("Search gave up after {expansions} expansions")]
+    ExpansionLimitExceeded { expansions: usize },
+}
+
+/// The default distance from the origin at which live traffic stops counting, in meters.
+///
+/// Live speeds are rarely accurate for more than about half an hour of driving.
+const DEFAULT_LIVE_TRAFFIC_DECAY_DISTANCE: f64 = 30_000.0;
+
+/// When a route should depart or arrive.
+///
+/// Times are local to the origin (when departing) or destination (when arriving),
+/// as in Valhalla's `date_time` request parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteTime {
+    DepartAt(NaiveDateTime),
+    ArriveBy(NaiveDateTime),
+}
+
+/// Options which apply to a single route request.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RouteOptions {
+    /// The maximum cost of a route.
+    ///
+    /// The search gives up once every remaining candidate exceeds this,
+    /// which bounds the work done for unreachable destinations.
+    pub max_cost: Option<f32>,
+    /// The maximum number of edges to expand (across both search directions).
+    ///
+    /// This caps the work done for a single request,
+    /// regardless of how the costing model shapes the search.
+    pub max_expansions: Option<usize>,
+    /// The departure or arrival time, for time-dependent speeds.
+    ///
+    /// Without a time, edges are costed at a typical speed.
+    pub time: Option<RouteTime>,
+    /// Areas which the route must not pass through (like Valhalla's `exclude_polygons`).
+    ///
+    /// Coordinates are in longitude, latitude order.
+    pub avoid_polygons: Vec<Polygon<f64>>,
+    /// Points which the route must keep clear of (like Valhalla's `exclude_locations`).
+    pub avoid_locations: Vec<AvoidLocation>,
+}
+
+impl RouteOptions {
+    #[must_use]
+    pub fn with_max_cost(mut self, max_cost: f32) -> Self {
+        self.max_cost = Some(max_cost);
+        self
+    }
+
+    #[must_use]
+    pub fn with_max_expansions(mut self, max_expansions: usize) -> Self {
+        self.max_expansions = Some(max_expansions);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_depart_at(mut self, local_time: NaiveDateTime) -> Self {
+        self.time = Some(RouteTime::DepartAt(local_time));
+        self
+    }
+
+    #[must_use]
+    pub const fn with_arrive_by(mut self, local_time: NaiveDateTime) -> Self {
+        self.time = Some(RouteTime::ArriveBy(local_time));
+        self
+    }
+
+    #[must_use]
+    pub fn with_avoid_polygon(mut self, polygon: Polygon<f64>) -> Self {
+        self.avoid_polygons.push(polygon);
+        self
+    }
+
+    #[must_use]
+    pub fn with_avoid_location(mut self, location: AvoidLocation) -> Self {
+        self.avoid_locations.push(location);
+        self
+    }
+}
+
+/// Computes routes over a routing graph using a costing model.
+///
+/// The router owns its tile provider and costing model,
+/// and can serve any number of route requests.
+/// It is `Send` and `Sync` when they are (as all the built-in providers and costing models are),
+/// so a single router and its tile cache can be shared between threads
+/// (ex: in an `Arc`, with tokio's `spawn_blocking`).
+pub struct Router<P, C> {
+    provider: P,
+    costing: C,
+    time_zones: Box<dyn TimeZones + Send + Sync>,
+    live_traffic: Option<Box<dyn LiveTraffic + Send + Sync>>,
+    live_traffic_decay_distance: f64,
+}
+
+impl<P: GraphTileProvider, C: Costing> Router<P, C> {
+    /// Creates a router which treats every time zone as UTC.
+    ///
+    /// See [`Router::with_time_zones`] for routes with a departure or arrival time.
+    pub fn new(provider: P, costing: C) -> Self {
+        Self {
+            provider,
+            costing,
+            time_zones: Box::new(Utc.fix()),
+            live_traffic: None,
+            live_traffic_decay_distance: DEFAULT_LIVE_TRAFFIC_DECAY_DISTANCE,
+        }
+    }
+
+    /// Sets the lookup used to convert times to the local time of each node.
+    #[must_use]
+    pub fn with_time_zones(mut self, time_zones: impl TimeZones + Send + Sync + 'static) -> Self {
+        self.time_zones = Box::new(time_zones);
+        self
+    }
+
+    /// Sets the source of live traffic speeds (ex: a traffic extract).
+    ///
+    /// Live traffic only affects costing models which use it
+    /// (see [`Costing::uses_live_traffic`]),
+    /// and only routes without a departure or arrival time.
+    /// Edges which live traffic reports as closed are never used.
+    #[must_use]
+    pub fn with_live_traffic(
+        mut self,
+        live_traffic: impl LiveTraffic + Send + Sync + 'static,
+    ) -> Self {
+        self.live_traffic = Some(Box::new(live_traffic));
+        self
+    }
+
+    /// Sets the distance from the origin (in meters) at which live speeds stop counting.
+    ///
+    /// Live speeds replace historical speeds at the origin,
+    /// and their weight decreases linearly until this distance.
+    #[must_use]
+    pub const fn with_live_traffic_decay_distance(mut self, meters: f64) -> Self {
+        self.live_traffic_decay_distance = meters;
+        self
+    }
+
+    /// The tile provider used for graph lookups.
+    pub const fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// The costing model used to evaluate edges.
+    pub const fn costing(&self) -> &C {
+        &self.costing
+    }
+
+    /// Finds the edges near a coordinate (longitude, latitude) which the costing model allows,
+    /// nearest first.
+    ///
+    /// These are candidates for the origin or destination of a route
+    /// (see [`Router::route_between`]).
+    ///
+    /// # Errors
+    ///
+    /// Fails if a tile can't be loaded, or an edge shape can't be decoded.
+    pub fn locate(
+        &self,
+        coordinate: Coord<f64>,
+        options: &LocateOptions,
+    ) -> Result<Vec<LocationCandidate>, GraphTileProviderError> {
+        locate(&self.provider, &self.costing, coordinate, options)
+    }
+
+    /// Finds the candidate edges for every point of a trace, as [`Router::locate`] would.
+    ///
+    /// Points without their own radius use the one in `options`.
+    /// The candidates are gathered in batches from the edge bins,
+    /// which is much faster than locating each point separately for long traces.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a tile can't be loaded, or an edge shape can't be decoded.
+    pub fn locate_trace(
+        &self,
+        trace: &[TracePoint],
+        options: &LocateOptions,
+    ) -> Result<Vec<Vec<LocationCandidate>>, GraphTileProviderError> {
+        locate_trace(&self.provider, &self.costing, trace, options)
+    }
+
+    /// Finds the lowest cost path between two nodes.
+    ///
+    /// Nodes may be given on any hierarchy level;
+    /// the search follows transitions between levels as needed.
+    /// If the origin and destination are the same node, the path is empty.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`RoutingError::NoRoute`] if the destination can't be reached
+    /// (within [`RouteOptions::max_cost`], if set),
+    /// with [`RoutingError::ExpansionLimitExceeded`] if the search hits [`RouteOptions::max_expansions`],
+    /// or if a tile can't be loaded along the way.
+    pub fn route(
+        &self,
+        origin: GraphId,
+        destination: GraphId,
+        options: &RouteOptions,
+    ) -> Result<Path, RoutingError> {
+        shortest_path(
+            &self.provider,
+            &self.costing,
+            &self.search_context(),
+            origin,
+            destination,
+            options,
+        )?
+        .map(Path::from_edges)
+        .ok_or(RoutingError::NoRoute)
+    }
+
+    /// Runs the same search as [`Router::route`], recording every edge which it settles.
+    ///
+    /// This is for debugging (see [`Expansion::write_geojson`]).
+    /// The expansion is returned even if the search doesn't find a route,
+    /// or hits [`RouteOptions::max_expansions`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if a tile can't be loaded along the way.
+    pub fn expansion(
+        &self,
+        origin: GraphId,
+        destination: GraphId,
+        options: &RouteOptions,
+    ) -> Result<Expansion, RoutingError> {
+        Ok(Expansion {
+            edges: expansion(
+                &self.provider,
+                &self.costing,
+                &self.search_context(),
+                origin,
+                destination,
+                options,
+            )?,
+        })
+    }
+
+    /// Finds the lowest cost path between two locations partway along edges.
+    ///
+    /// The first and last edges of the path are the edges of the locations,
+    /// with their cost and length reduced to the part of the edge which the route uses
+    /// (see [`crate::PathGeometry::between`] for the matching shape).
+    /// When the destination is further along the same edge as the origin,
+    /// the path is just the part of the edge between them.
+    /// The partial edges are costed at their typical speed, without a time or live traffic.
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`Router::route`],
+    /// and with [`RoutingError::NoRoute`] if the costing model doesn't allow either edge.
+    pub fn route_between(
+        &self,
+        origin: EdgeLocation,
+        destination: EdgeLocation,
+        options: &RouteOptions,
+    ) -> Result<Path, RoutingError> {
+        if origin.edge_id == destination.edge_id
+            && origin.percent_along <= destination.percent_along
+        {
+            let (edge, _) = self.partial_edge(
+                origin.edge_id,
+                destination.percent_along - origin.percent_along,
+            )?;
+            return Ok(Path::from_edges(vec![edge]));
+        }
+
+        let origin_end_node = self.directed_edge(origin.edge_id)?.end_node_id();
+        let destination_start_node = self.start_node_id(destination.edge_id)?;
+        let middle = self.route(origin_end_node, destination_start_node, options)?;
+        self.join_partial_edges(origin, middle.edges, destination)
+    }
+
+    /// Joins a path between two nodes with the parts of the edges leading to and from them,
+    /// from the origin location to the destination location.
+    pub(crate) fn join_partial_edges(
+        &self,
+        origin: EdgeLocation,
+        middle: Vec<PathEdge>,
+        destination: EdgeLocation,
+    ) -> Result<Path, RoutingError> {
+        let (first, origin_edge) = self.partial_edge(origin.edge_id, 1.0 - origin.percent_along)?;
+        let (mut last, destination_edge) =
+            self.partial_edge(destination.edge_id, destination.percent_along)?;
+
+        // The middle of the route starts without a transition onto its first edge
+        let mut predecessor = origin_edge;
+        let mut edges = vec![first];
+        for (index, mut path_edge) in middle.into_iter().enumerate() {
+            let edge = self.directed_edge(path_edge.edge_id)?;
+            if index == 0 {
+                path_edge.cost += self.transition_cost(&predecessor, &edge)?;
+            }
+            edges.push(path_edge);
+            predecessor = edge;
+        }
+        last.cost += self.transition_cost(&predecessor, &destination_edge)?;
+        edges.push(last);
+
+        Ok(Path::from_edges(edges))
+    }
+
+    /// Finds the lowest cost path between two nodes, followed by up to
+    /// [`AlternativeOptions::count`] alternative paths.
+    ///
+    /// Alternatives must be sufficiently different from the other routes,
+    /// and not too much more expensive than the best one (see [`AlternativeOptions`]),
+    /// so there may be fewer than requested (or none).
+    ///
+    /// # Errors
+    ///
+    /// Fails like [`Router::route`] if the best path can't be found.
+    pub fn route_with_alternatives(
+        &self,
+        origin: GraphId,
+        destination: GraphId,
+        options: &RouteOptions,
+        alternatives: &AlternativeOptions,
+    ) -> Result<Vec<Path>, RoutingError> {
+        let avoided = avoided_edges(
+            &self.provider,
+            &options.avoid_polygons,
+            &options.avoid_locations,
+        )?;
+        alternative_paths(
+            &self.provider,
+            &self.costing,
+            &self.search_context(),
+            origin,
+            destination,
+            &avoided,
+            options,
+            alternatives,
+        )
+    }
+
+    /// Computes the best time and distance from each source node to each target node.
+    ///
+    /// This runs one search per source (or per target, when arriving by a given time),
+    /// all sharing the router's tile provider (and its cache).
+    /// The options apply to every search,
+    /// so [`RouteOptions::max_cost`] and [`RouteOptions::max_expansions`] limit each one.
+    /// Unlike [`Router::route`], targets which can't be reached within the limits
+    /// are left empty in the matrix rather than failing the whole request.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a tile can't be loaded along the way.
+    pub fn matrix(
+        &self,
+        sources: &[GraphId],
+        targets: &[GraphId],
+        options: &RouteOptions,
+    ) -> Result<Matrix, RoutingError> {
+        let context = self.search_context();
+        let avoided = avoided_edges(
+            &self.provider,
+            &options.avoid_polygons,
+            &options.avoid_locations,
+        )?;
+        let search = |node, others| {
+            one_to_many(
+                &self.provider,
+                &self.costing,
+                &context,
+                node,
+                others,
+                &avoided,
+                options,
+            )
+        };
+
+        let mut sources_to_targets = Vec::with_capacity(sources.len());
+        if let Some(RouteTime::ArriveBy(_)) = options.time {
+            // Arrival times are fixed at the targets, so search from each of them in reverse
+            let mut columns = targets
+                .iter()
+                .map(|&target| Ok(search(target, sources)?.into_iter()))
+                .collect::<Result<Vec<_>, RoutingError>>()?;
+            for from_index in 0..sources.len() {
+                sources_to_targets.push(
+                    columns
+                        .iter_mut()
+                        .enumerate()
+                        .map(|(to_index, column)| {
+                            MatrixEntry::new(from_index, to_index, column.next().flatten())
+                        })
+                        .collect(),
+                );
+            }
+        } else {
+            for (from_index, &source) in sources.iter().enumerate() {
+                sources_to_targets.push(
+                    search(source, targets)?
+                        .into_iter()
+                        .enumerate()
+                        .map(|(to_index, edges)| MatrixEntry::new(from_index, to_index, edges))
+                        .collect(),
+                );
+            }
+        }
+
+        Ok(Matrix { sources_to_targets })
+    }
+
+    pub(crate) fn directed_edge(&self, edge_id: GraphId) -> Result<DirectedEdge, RoutingError> {
+        Ok(self
+            .provider
+            .with_tile_containing(edge_id, |tile| tile.get_directed_edge(edge_id).cloned())??)
+    }
+
+    /// The node at the start of an edge (the end node of its opposing edge).
+    pub(crate) fn start_node_id(&self, edge_id: GraphId) -> Result<GraphId, RoutingError> {
+        self.provider.with_tile_containing(edge_id, |tile| {
+            let opp_edge_id = self.provider.get_opposing_edge_id(edge_id, tile)?;
+            self.provider
+                .with_tile_containing(opp_edge_id, |opp_tile| {
+                    Ok::<_, RoutingError>(opp_tile.get_directed_edge(opp_edge_id)?.end_node_id())
+                })?
+        })?
+    }
+
+    /// The cost of turning from one edge onto the next, at the end node of the first.
+    fn transition_cost(
+        &self,
+        predecessor: &DirectedEdge,
+        edge: &DirectedEdge,
+    ) -> Result<Cost, RoutingError> {
+        let node_id = predecessor.end_node_id();
+        Ok(self.provider.with_tile_containing(node_id, |tile| {
+            tile.get_node(node_id)
+                .map(|node| self.costing.transition_cost(node, predecessor, edge))
+        })??)
+    }
+
+    /// The cost and length of a fraction of an edge (along with the edge itself),
+    /// or [`RoutingError::NoRoute`] if the costing model doesn't allow the edge.
+    pub(crate) fn partial_edge(
+        &self,
+        edge_id: GraphId,
+        fraction: f64,
+    ) -> Result<(PathEdge, DirectedEdge), RoutingError> {
+        self.provider.with_tile_containing(edge_id, |tile| {
+            let edge = tile.get_directed_edge(edge_id)?;
+            let context = EdgeContext::default();
+            if !self.costing.edge_allowed(edge_id, edge, tile, &context) {
+                return Err(RoutingError::NoRoute);
+            }
+            let cost = self.costing.edge_cost(edge_id, edge, tile, &context);
+            #[expect(
+                clippy::cast_possible_truncation,
+                reason = "The fraction is between 0 and 1"
+            )]
+            let factor = fraction as f32;
+            let path_edge = PathEdge {
+                edge_id,
+                cost: Cost::new(cost.cost * factor, cost.secs * factor),
+                length: f64::from(edge.length()) * fraction,
+            };
+            Ok((path_edge, edge.clone()))
+        })?
+    }
+
+    pub(crate) fn search_context(&self) -> SearchContext<'_> {
+        SearchContext {
+            time_zones: self.time_zones.as_ref(),
+            live_traffic: self
+                .live_traffic
+                .as_deref()
+                .map(|live_traffic| live_traffic as &dyn LiveTraffic),
+            live_traffic_decay_distance: self.live_traffic_decay_distance,
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<P: GraphTileProvider + Sync, C: Costing + Sync> Router<P, C> {
+    /// Finds the lowest cost paths between many pairs of nodes, in parallel.
+    ///
+    /// The routes run on the current rayon thread pool
+    /// (the global one, unless called within [`rayon::ThreadPool::install`]),
+    /// all sharing the router's tile provider and its cache.
+    /// The results are in the same order as the pairs,
+    /// and each one succeeds or fails on its own, as in [`Router::route`].
+    pub fn route_many(
+        &self,
+        pairs: &[(GraphId, GraphId)],
+        options: &RouteOptions,
+    ) -> Vec<Result<Path, RoutingError>> {
+        pairs
+            .par_iter()
+            .map(|&(origin, destination)| self.route(origin, destination, options))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RouteOptions, Router, RoutingError};
+    use crate::{
+        AlternativeOptions, AutoCosting, AvoidLocation, Cost, CostingModel, EdgeLocation,
+        PathGeometry, TimeCosting,
+    };
+    use chrono::NaiveDate;
+    use geo::{Coord, LineString, Polygon};
+    use geo::{Haversine, Length};
+    use std::collections::HashMap;
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use valhalla_graphtile::graph_tile::GraphTile;
+    use valhalla_graphtile::tile_provider::{DirectoryGraphTileProvider, GraphTileProvider};
+    use valhalla_graphtile::traffic_tile::{SpeedValue, TrafficSpeed};
+    use valhalla_graphtile::{Access, GraphId};
+
+    fn provider() -> DirectoryGraphTileProvider {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles");
+        DirectoryGraphTileProvider::new(base, NonZeroUsize::new(4).unwrap())
+    }
+
+    fn router() -> Router<DirectoryGraphTileProvider, TimeCosting> {
+        Router::new(provider(), TimeCosting::new(Access::Auto))
+    }
+
+    #[test]
+    fn test_router_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Router<DirectoryGraphTileProvider, CostingModel>>();
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_route_many() {
+        let router = router();
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let pairs: Vec<_> = [123, 194, 485, 1067]
+            .into_iter()
+            .map(|index| GraphId::try_from_components(2, 763_926, index).unwrap())
+            .flat_map(|node_id| [(origin, node_id), (node_id, origin)])
+            .collect();
+
+        let options = RouteOptions::default();
+        let results = router.route_many(&pairs, &options);
+        assert_eq!(results.len(), pairs.len());
+        for (&(origin, destination), result) in pairs.iter().zip(results) {
+            match (result, router.route(origin, destination, &options)) {
+                (Ok(path), Ok(expected)) => assert_eq!(path, expected),
+                (Err(RoutingError::NoRoute), Err(RoutingError::NoRoute)) => {}
+                (result, expected) => panic!("{result:?} != {expected:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_route() {
+        let router = router();
+        let provider = router.provider();
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let destination = GraphId::try_from_components(2, 763_926, 123).unwrap();
+        let path = router
+            .route(origin, destination, &RouteOptions::default())
+            .unwrap();
+        assert!(!path.edges.is_empty());
+
+        // Each edge must start where the previous one ended (possibly on another level)
+        let mut node_id = origin;
+        for path_edge in &path.edges {
+            let (opp_edge_id, end_node_id, access) = provider
+                .with_tile_containing(path_edge.edge_id, |tile| {
+                    let edge = tile.get_directed_edge(path_edge.edge_id).unwrap();
+                    let opp_edge_id = provider
+                        .get_opposing_edge_id(path_edge.edge_id, tile)
+                        .unwrap();
+                    (opp_edge_id, edge.end_node_id(), edge.forward_access())
+                })
+                .unwrap();
+            let start_node_id = provider
+                .with_tile_containing(opp_edge_id, |tile| {
+                    tile.get_directed_edge(opp_edge_id).unwrap().end_node_id()
+                })
+                .unwrap();
+            assert_eq!(
+                provider
+                    .transition_node(node_id, start_node_id.level())
+                    .unwrap(),
+                Some(start_node_id)
+            );
+            assert!(access.contains(Access::Auto));
+            node_id = end_node_id;
+        }
+        assert_eq!(
+            provider
+                .transition_node(node_id, destination.level())
+                .unwrap(),
+            Some(destination)
+        );
+
+        let total = path
+            .edges
+            .iter()
+            .fold(Cost::ZERO, |total, edge| total + edge.cost);
+        assert_eq!(path.cost, total);
+        let length: f64 = path.edges.iter().map(|edge| edge.length).sum();
+        assert!((path.length - length).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_route_single_edge() {
+        let router = router();
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let (edge_id, destination, length) = router
+            .provider()
+            .with_tile_containing(origin, |tile| {
+                let node = tile.get_node(origin).unwrap();
+                let (edge_id, edge) = tile.outbound_edges_with_ids(node).next().unwrap();
+                (edge_id, edge.end_node_id(), edge.length())
+            })
+            .unwrap();
+
+        let path = router
+            .route(origin, destination, &RouteOptions::default())
+            .unwrap();
+        assert_eq!(path.edge_ids().collect::<Vec<_>>(), vec![edge_id]);
+        assert_eq!(path.edges[0].cost, path.cost);
+        assert!((path.length - f64::from(length)).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_route_to_origin() {
+        let router = router();
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let path = router
+            .route(origin, origin, &RouteOptions::default())
+            .unwrap();
+        assert!(path.edges.is_empty());
+        assert_eq!(path.cost, Cost::ZERO);
+        assert!(path.length.abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_route_max_cost() {
+        let router = router();
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let destination = GraphId::try_from_components(2, 763_926, 123).unwrap();
+        let result = router.route(
+            origin,
+            destination,
+            &RouteOptions::default().with_max_cost(60.0),
+        );
+        assert!(matches!(result, Err(RoutingError::NoRoute)));
+    }
+
+    #[test]
+    fn test_route_max_expansions() {
+        let router = router();
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let destination = GraphId::try_from_components(2, 763_926, 123).unwrap();
+        let result = router.route(
+            origin,
+            destination,
+            &RouteOptions::default().with_max_expansions(10),
+        );
+        assert!(matches!(
+            result,
+            Err(RoutingError::ExpansionLimitExceeded { expansions: 10 })
+        ));
+    }
+
+    #[test]
+    fn test_route_live_traffic() {
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let destination = GraphId::try_from_components(2, 763_926, 123).unwrap();
+        let options = RouteOptions::default();
+        let baseline = Router::new(provider(), AutoCosting::default())
+            .route(origin, destination, &options)
+            .unwrap();
+
+        // A traffic jam at the origin makes the route slower
+        let first_edge_id = baseline.edges[0].edge_id;
+        let jam = HashMap::from([(
+            first_edge_id,
+            TrafficSpeed::single_speed(SpeedValue::try_new(5).unwrap(), None),
+        )]);
+        let path = Router::new(provider(), AutoCosting::default())
+            .with_live_traffic(jam.clone())
+            .route(origin, destination, &options)
+            .unwrap();
+        assert!(path.cost.secs > baseline.cost.secs);
+        // Unless live traffic doesn't count for any distance
+        let path = Router::new(provider(), AutoCosting::default())
+            .with_live_traffic(jam)
+            .with_live_traffic_decay_distance(0.0)
+            .route(origin, destination, &options)
+            .unwrap();
+        assert_eq!(path.cost, baseline.cost);
+
+        // Closed edges are avoided entirely
+        let closed_edge_id = baseline.edges[baseline.edges.len() / 2].edge_id;
+        let closure = HashMap::from([(closed_edge_id, TrafficSpeed::closed())]);
+        match Router::new(provider(), AutoCosting::default())
+            .with_live_traffic(closure)
+            .route(origin, destination, &options)
+        {
+            Ok(path) => assert!(path.edge_ids().all(|edge_id| edge_id != closed_edge_id)),
+            Err(error) => assert!(matches!(error, RoutingError::NoRoute)),
+        }
+    }
+
+    #[test]
+    fn test_route_avoid_areas() {
+        let router = router();
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let destination = GraphId::try_from_components(2, 763_926, 123).unwrap();
+        let baseline = router
+            .route(origin, destination, &RouteOptions::default())
+            .unwrap();
+
+        // Avoid the middle of an edge partway along the route
+        let avoided_edge_id = baseline.edges[baseline.edges.len() / 2].edge_id;
+        let shape = router
+            .provider()
+            .with_tile_containing(avoided_edge_id, |tile| {
+                let edge = tile.get_directed_edge(avoided_edge_id).unwrap();
+                tile.get_edge_info(edge)
+                    .unwrap()
+                    .decode_raw_shape::<f64>()
+                    .unwrap()
+            })
+            .unwrap();
+        let midpoint = (shape[0] + shape[shape.len() - 1]) / 2.0;
+        let closest = valhalla_graphtile::spatial::closest_point_on_line(midpoint, &shape)
+            .unwrap()
+            .0;
+
+        // There is a detour around it
+        let check = |options: &RouteOptions| {
+            let path = router.route(origin, destination, options).unwrap();
+            assert!(path.edge_ids().all(|edge_id| edge_id != avoided_edge_id));
+            assert!(path.cost.cost > baseline.cost.cost);
+        };
+        check(&RouteOptions::default().with_avoid_location(AvoidLocation::new(closest, 1.0)));
+
+        let square = |size: f64| {
+            Polygon::new(
+                LineString::from(vec![
+                    closest + Coord { x: -size, y: -size },
+                    closest + Coord { x: size, y: -size },
+                    closest + Coord { x: size, y: size },
+                    closest + Coord { x: -size, y: size },
+                    closest + Coord { x: -size, y: -size },
+                ]),
+                vec![],
+            )
+        };
+        check(&RouteOptions::default().with_avoid_polygon(square(0.000_01)));
+
+        // Areas away from the route have no effect
+        let far_away = AvoidLocation::new(Coord { x: 0.0, y: 0.0 }, 100.0);
+        let path = router
+            .route(
+                origin,
+                destination,
+                &RouteOptions::default().with_avoid_location(far_away),
+            )
+            .unwrap();
+        assert_eq!(path, baseline);
+    }
+
+    #[test]
+    fn test_matrix() {
+        let router = router();
+        let nodes: Vec<_> = [0, 123, 194, 485, 1067]
+            .into_iter()
+            .map(|index| GraphId::try_from_components(2, 763_926, index).unwrap())
+            .collect();
+        let time = NaiveDate::from_ymd_opt(2025, 6, 2)
+            .unwrap()
+            .and_hms_opt(8, 0, 0)
+            .unwrap();
+
+        for options in [
+            RouteOptions::default(),
+            RouteOptions::default().with_arrive_by(time),
+        ] {
+            let matrix = router.matrix(&nodes[..2], &nodes, &options).unwrap();
+            assert_eq!(matrix.sources_to_targets.len(), 2);
+            let mut reachable = 0;
+            for (from_index, &source) in nodes[..2].iter().enumerate() {
+                for (to_index, &target) in nodes.iter().enumerate() {
+                    let entry = matrix.get(from_index, to_index).unwrap();
+                    assert_eq!((entry.from_index, entry.to_index), (from_index, to_index));
+                    // Every entry matches the route between the pair
+                    match router.route(source, target, &options) {
+                        Ok(path) => {
+                            assert!((entry.time.unwrap() - path.cost.secs).abs() < 0.01);
+                            reachable += 1;
+                        }
+                        Err(RoutingError::NoRoute) => {
+                            assert!(entry.time.is_none() && entry.distance.is_none());
+                        }
+                        Err(error) => panic!("{error}"),
+                    }
+                }
+            }
+            assert!(reachable > 2);
+            assert!(matrix.get(0, 0).unwrap().distance.unwrap().abs() < f64::EPSILON);
+        }
+
+        let matrix = router
+            .matrix(&nodes, &[], &RouteOptions::default())
+            .unwrap();
+        assert!(matrix.sources_to_targets.iter().all(Vec::is_empty));
+    }
+
+    #[test]
+    fn test_route_with_alternatives() {
+        let router = router();
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let destination = GraphId::try_from_components(2, 763_926, 123).unwrap();
+        let options = RouteOptions::default();
+        let best = router.route(origin, destination, &options).unwrap();
+
+        let alternatives = AlternativeOptions::default().with_count(2);
+        let found = router
+            .route_with_alternatives(origin, destination, &options, &alternatives)
+            .unwrap();
+        assert_eq!(found[0], best);
+        assert!(found.len() > 1 && found.len() <= 3);
+        for (index, route) in found.iter().enumerate().skip(1) {
+            assert!(route.cost.cost <= best.cost.cost * alternatives.max_stretch);
+            assert!(route.cost.cost >= best.cost.cost);
+            assert_ne!(route.edges, found[index - 1].edges);
+        }
+
+        // Without any alternatives, this is the same as a single route
+        let found = router
+            .route_with_alternatives(
+                origin,
+                destination,
+                &options,
+                &AlternativeOptions::default().with_count(0),
+            )
+            .unwrap();
+        assert_eq!(found, vec![best]);
+    }
+
+    #[test]
+    fn test_route_between() {
+        let router = router();
+        let provider = router.provider();
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let destination = GraphId::try_from_components(2, 763_926, 123).unwrap();
+        let options = RouteOptions::default();
+        let best = router.route(origin, destination, &options).unwrap();
+        let first = best.edges[0];
+        let last = *best.edges.last().unwrap();
+
+        // Halfway along the first edge to halfway along the last one
+        let from = EdgeLocation::new(first.edge_id, 0.5);
+        let to = EdgeLocation::new(last.edge_id, 0.5);
+        let path = router.route_between(from, to, &options).unwrap();
+        assert_eq!(path.edges[0].edge_id, first.edge_id);
+        assert_eq!(path.edges.last().unwrap().edge_id, last.edge_id);
+        assert!((path.edges[0].length - first.length / 2.0).abs() < 1e-6);
+        let expected_length = best.length - first.length / 2.0 - last.length / 2.0;
+        assert!(path.length <= expected_length + 1e-6);
+        assert!(path.cost.cost < best.cost.cost);
+        let shape = PathGeometry::between(&from, &to)
+            .assemble(provider, &path)
+            .unwrap();
+        assert!((Haversine.length(&shape) - path.length).abs() < path.length * 0.01 + 1.0);
+
+        // Along a single edge
+        let path = router
+            .route_between(
+                EdgeLocation::new(first.edge_id, 0.25),
+                EdgeLocation::new(first.edge_id, 0.75),
+                &options,
+            )
+            .unwrap();
+        assert_eq!(path.edge_ids().collect::<Vec<_>>(), vec![first.edge_id]);
+        assert!((path.length - first.length / 2.0).abs() < 1e-6);
+        assert!((path.cost.secs - first.cost.secs / 2.0).abs() < 0.01);
+
+        // Backwards along the same edge means going around
+        match router.route_between(
+            EdgeLocation::new(first.edge_id, 0.75),
+            EdgeLocation::new(first.edge_id, 0.25),
+            &options,
+        ) {
+            Ok(path) => {
+                assert!(path.edges.len() > 2);
+                assert!(path.length > first.length / 2.0);
+            }
+            Err(error) => assert!(matches!(error, RoutingError::NoRoute)),
+        }
+    }
+
+    #[test]
+    fn test_route_restricted_regions() {
+        let router = Router::new(provider(), AutoCosting::default());
+        let provider = router.provider();
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let time = NaiveDate::from_ymd_opt(2025, 6, 2)
+            .unwrap()
+            .and_hms_opt(8, 0, 0)
+            .unwrap();
+
+        // A route may leave a region at the start, and enter one at the end, but not pass through
+        let passes_through = |flags: &[bool]| {
+            let Some(first_outside) = flags.iter().position(|&flag| !flag) else {
+                return false;
+            };
+            let Some(entry) = flags[first_outside..].iter().position(|&flag| flag) else {
+                return false;
+            };
+            flags[first_outside + entry..].iter().any(|&flag| !flag)
+        };
+
+        // The end of the first not-thru road in the tile which cars can use
+        let region_node = provider
+            .with_tile_containing(origin, |tile| {
+                tile.edges_with_ids()
+                    .find(|(_, edge)| {
+                        edge.no_thru() && edge.forward_access().contains(Access::Auto)
+                    })
+                    .map(|(_, edge)| edge.end_node_id())
+                    .unwrap()
+            })
+            .unwrap();
+
+        let mut ends_inside = 0;
+        let destinations = (0..1400)
+            .step_by(97)
+            .map(|index| GraphId::try_from_components(2, 763_926, index).unwrap());
+        for destination in destinations.chain([region_node]) {
+            for options in [
+                RouteOptions::default(),
+                RouteOptions::default().with_depart_at(time),
+            ] {
+                let Ok(path) = router.route(origin, destination, &options) else {
+                    continue;
+                };
+                let (not_thru, dest_only): (Vec<_>, Vec<_>) = path
+                    .edge_ids()
+                    .map(|edge_id| {
+                        provider
+                            .with_tile_containing(edge_id, |tile| {
+                                let edge = tile.get_directed_edge(edge_id).unwrap();
+                                (edge.no_thru(), edge.dest_only())
+                            })
+                            .unwrap()
+                    })
+                    .unzip();
+                assert!(!passes_through(&not_thru), "{destination} ({options:?})");
+                assert!(!passes_through(&dest_only), "{destination} ({options:?})");
+                ends_inside += usize::from(not_thru.last() == Some(&true));
+            }
+        }
+        // Destinations inside a region can still be reached, with or without a departure time
+        assert!(ends_inside >= 2);
+    }
+}
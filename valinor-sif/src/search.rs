@@ -0,0 +1,1380 @@
+//! # Path search
+//!
+//! A bidirectional, edge-based search over the routing graph (similar to Valhalla's `thor`).
+//!
+//! Labels are kept per directed edge rather than per node,
+//! since turn costs depend on the edge used to reach a node.
+//! The forward search expands outbound edges from the origin,
+//! and the reverse search expands inbound edges to the destination.
+//! Reverse labels are keyed by the ID of the edge in its direction of travel,
+//! so the two searches connect when they both reach the same edge.
+//!
+//! The searches use A* when the costing model has a lower bound on the cost per meter
+//! (see [`Costing::astar_cost_factor`]),
+//! prioritizing labels by their cost plus an estimate based on great-circle distances.
+//! A one-way search estimates the cost to its target.
+//! The bidirectional search uses the average of the estimates to the destination and from the origin
+//! (half of one minus the other), so that both directions agree on the remaining cost,
+//! and the search can stop at the same point as it would without estimates.
+//!
+//! Live traffic is only trusted near the origin, so only the forward search uses live speeds,
+//! though both searches avoid edges which live traffic reports as closed.
+//!
+//! Edges in the areas which a route avoids are found before the search starts,
+//! and never expanded.
+//!
+//! For motor vehicles, the search also stays out of not-thru regions and destination-only roads
+//! (like driveways and private roads), except where the route starts or ends in one.
+//! Each label records whether its path has left such a region yet;
+//! once it has, the search may not enter another.
+//! Since the reverse search starts from the destination,
+//! a bidirectional route can still leave the origin's region and enter the destination's.
+//! A one-way search can't tell whether a region leads to its target,
+//! so it only stays out of these regions when the target isn't in one.
+//! Edges can also be penalized, which makes them more expensive to the search
+//! without changing the cost of the paths which use them.
+//! Alternative routes are found this way, by penalizing the edges of the routes found so far.
+//!
+//! Time-dependent routes only search in one direction,
+//! since the time of each edge traversal is only known relative to a fixed end of the route:
+//! forward from the origin when departing at a given time,
+//! and in reverse from the destination when arriving by a given time.
+
+use crate::avoid::avoided_edges;
+use crate::expansion::{ExpandedEdge, ExpansionDirection};
+use crate::time::{add_secs, local_to_utc};
+use crate::traffic::live_weight;
+use crate::{
+    Cost, Costing, EdgeContext, LiveTraffic, PathEdge, RouteOptions, RouteTime, RoutingError,
+    TimeInfo, TimeZones,
+};
+use chrono::{DateTime, Utc};
+use geo::{Coord, Distance, Haversine, Point};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use valhalla_graphtile::GraphId;
+use valhalla_graphtile::graph_tile::{
+    DirectedEdge, GraphTile, LookupError, NodeInfo, NodeTransition,
+};
+use valhalla_graphtile::tile_provider::{GraphTileProvider, GraphTileProviderError};
+
+/// A directed edge reached by the search.
+struct EdgeLabel {
+    edge_id: GraphId,
+    edge: DirectedEdge,
+    /// The node where the search continues from this edge.
+    ///
+    /// This is the end node in the forward search, and the start node in the reverse search.
+    node_id: GraphId,
+    /// The index of the label for the previous edge in the search tree.
+    ///
+    /// This is the next edge along the path in the reverse search.
+    predecessor: Option<usize>,
+    /// The cost of traversing the edge itself.
+    edge_cost: Cost,
+    /// The cost of the transition between this edge and its predecessor.
+    ///
+    /// In the forward search, this is the transition onto this edge.
+    /// In the reverse search, it is the transition from this edge onto the next one.
+    transition_cost: Cost,
+    /// The cost of the path from the origin (or to the destination) including this edge.
+    total_cost: Cost,
+    /// The length of the path from the origin (or to the destination) including this edge,
+    /// in meters.
+    distance: f64,
+    /// The A* potential of the label's node, which is added to its cost in the queue.
+    potential: f32,
+    /// The penalty included in the edge cost, which is left out of the path cost.
+    penalty: f32,
+    /// Has the path left any not-thru region (so that it may not enter another)?
+    not_thru_pruning: bool,
+    /// Has the path left any destination-only roads (so that it may not enter others)?
+    dest_only_pruning: bool,
+}
+
+impl EdgeLabel {
+    /// The cost of the edge itself, without any penalty.
+    fn path_edge_cost(&self) -> Cost {
+        Cost::new(self.edge_cost.cost - self.penalty, self.edge_cost.secs)
+    }
+}
+
+/// An entry in the priority queue, ordered so that the lowest cost is popped first.
+struct QueueEntry {
+    /// The cost of the label plus its A* estimate.
+    cost: f32,
+    label_index: usize,
+}
+
+impl PartialEq for QueueEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueueEntry {}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QueueEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed, since BinaryHeap is a max-heap
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.label_index.cmp(&self.label_index))
+    }
+}
+
+/// Finds every node which represents the same location as `node_id` on other hierarchy levels.
+///
+/// The result includes `node_id` itself.
+fn equivalent_nodes<P: GraphTileProvider>(
+    provider: &P,
+    node_id: GraphId,
+) -> Result<Vec<GraphId>, RoutingError> {
+    let mut nodes = vec![node_id];
+    let mut queue = VecDeque::from([node_id]);
+    while let Some(current) = queue.pop_front() {
+        let transitions = provider.with_tile_containing(current, |tile| {
+            tile.get_transitions_for_node(current).map(|transitions| {
+                transitions
+                    .iter()
+                    .map(NodeTransition::corresponding_end_node_id)
+                    .collect::<Vec<_>>()
+            })
+        })??;
+        for next in transitions {
+            if !nodes.contains(&next) {
+                nodes.push(next);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    Ok(nodes)
+}
+
+/// The labels and queue for one direction of the search.
+#[derive(Default)]
+struct Frontier {
+    labels: Vec<EdgeLabel>,
+    queue: BinaryHeap<QueueEntry>,
+    /// The index of the lowest cost label for each edge reached so far.
+    best: HashMap<GraphId, usize>,
+    settled: HashSet<GraphId>,
+}
+
+impl Frontier {
+    /// The lowest cost (including the A* estimate) in the queue.
+    ///
+    /// This may belong to a stale entry, so it is a lower bound on the next label to be settled.
+    fn min_cost(&self) -> Option<f32> {
+        self.queue.peek().map(|entry| entry.cost)
+    }
+
+    /// Adds a label, unless the edge has already been reached at a lower cost.
+    fn push(&mut self, label: EdgeLabel) -> Option<usize> {
+        if self.settled.contains(&label.edge_id)
+            || self
+                .best
+                .get(&label.edge_id)
+                .is_some_and(|&index| self.labels[index].total_cost.cost <= label.total_cost.cost)
+        {
+            return None;
+        }
+
+        let index = self.labels.len();
+        self.best.insert(label.edge_id, index);
+        self.queue.push(QueueEntry {
+            cost: label.total_cost.cost + label.potential,
+            label_index: index,
+        });
+        self.labels.push(label);
+        Some(index)
+    }
+
+    /// Settles the next label in the queue, skipping stale entries.
+    fn pop(&mut self) -> Option<usize> {
+        while let Some(QueueEntry { label_index, .. }) = self.queue.pop() {
+            let edge_id = self.labels[label_index].edge_id;
+            if self.best.get(&edge_id) == Some(&label_index) && self.settled.insert(edge_id) {
+                return Some(label_index);
+            }
+        }
+
+        None
+    }
+}
+
+/// The point where the forward and reverse searches meet.
+#[derive(Clone, Copy)]
+struct Connection {
+    cost: f32,
+    forward_index: usize,
+    reverse_index: usize,
+}
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Forward,
+    Reverse,
+}
+
+/// The fixed end of a time-dependent route.
+#[derive(Clone, Copy)]
+enum SearchTime {
+    DepartAt(DateTime<Utc>),
+    ArriveBy(DateTime<Utc>),
+}
+
+/// A* factor applied to the great-circle distance,
+/// which leaves some slack for edge lengths being rounded to the meter.
+const HEURISTIC_DISTANCE_FACTOR: f64 = 0.99;
+
+/// An A* heuristic, which estimates the remaining cost of a route from a node.
+///
+/// Great-circle distances are scaled by a lower bound on the cost per meter,
+/// so the estimates never exceed the cost of the best path.
+#[derive(Clone, Copy)]
+struct Heuristic {
+    origin: Point<f64>,
+    destination: Point<f64>,
+    cost_per_meter: f64,
+    /// Use the average of both estimates, for the bidirectional search.
+    balanced: bool,
+}
+
+/// The estimates for a node.
+#[derive(Clone, Copy)]
+struct Estimate {
+    /// The priority offset of a label at the node.
+    potential: f32,
+    /// A lower bound on the cost of the rest of the route.
+    remaining: f32,
+}
+
+impl Heuristic {
+    /// A heuristic between two nodes, or `None` if the costing model has no lower bound.
+    fn new<C: Costing>(costing: &C, origin: Coord<f32>, destination: Coord<f32>) -> Option<Self> {
+        let cost_per_meter = f64::from(costing.astar_cost_factor());
+        let point =
+            |coordinate: Coord<f32>| Point::new(f64::from(coordinate.x), f64::from(coordinate.y));
+        (cost_per_meter > 0.0).then(|| Self {
+            origin: point(origin),
+            destination: point(destination),
+            cost_per_meter: cost_per_meter * HEURISTIC_DISTANCE_FACTOR,
+            balanced: true,
+        })
+    }
+
+    /// Estimates the full remaining cost, for a search in one direction.
+    const fn unbalanced(self) -> Self {
+        Self {
+            balanced: false,
+            ..self
+        }
+    }
+
+    fn estimate(&self, direction: Direction, coordinate: Coord<f32>) -> Estimate {
+        let point = Point::new(f64::from(coordinate.x), f64::from(coordinate.y));
+        let to_destination = Haversine.distance(point, self.destination) * self.cost_per_meter;
+        let from_origin = Haversine.distance(self.origin, point) * self.cost_per_meter;
+        let (remaining, opposite) = match direction {
+            Direction::Forward => (to_destination, from_origin),
+            Direction::Reverse => (from_origin, to_destination),
+        };
+        let potential = if self.balanced {
+            (remaining - opposite) / 2.0
+        } else {
+            remaining
+        };
+        #[expect(
+            clippy::cast_possible_truncation,
+            reason = "Costs are stored as f32 anyway"
+        )]
+        Estimate {
+            potential: potential as f32,
+            remaining: remaining as f32,
+        }
+    }
+}
+
+/// Looks up the coordinate of a node.
+fn node_coordinate<P: GraphTileProvider>(
+    provider: &P,
+    node_id: GraphId,
+) -> Result<Coord<f32>, RoutingError> {
+    Ok(provider.with_tile_containing(node_id, |tile| {
+        tile.get_node(node_id)
+            .map(|node| node.coordinate(tile.header().sw_corner()))
+    })??)
+}
+
+/// Data sources which the search consults besides the routing graph.
+pub(crate) struct SearchContext<'a> {
+    pub(crate) time_zones: &'a dyn TimeZones,
+    pub(crate) live_traffic: Option<&'a dyn LiveTraffic>,
+    /// The distance from the origin (in meters) at which live speeds stop counting.
+    pub(crate) live_traffic_decay_distance: f64,
+}
+
+/// The state of a single bidirectional search.
+struct BidirectionalSearch<'a, P, C> {
+    provider: &'a P,
+    costing: &'a C,
+    context: &'a SearchContext<'a>,
+    time: Option<SearchTime>,
+    max_cost: Option<f32>,
+    heuristic: Option<Heuristic>,
+    /// Edges which the route must not use.
+    avoided: &'a HashSet<GraphId>,
+    /// Factors which multiply the cost of edges the route should try not to use.
+    penalties: Option<&'a HashMap<GraphId, f32>>,
+    /// Keep out of not-thru regions once the path has left them?
+    prune_not_thru: bool,
+    /// Keep off destination-only roads once the path has left them?
+    prune_dest_only: bool,
+    /// The settled edges, in order, when recording the expansion.
+    expansion: Option<Vec<ExpandedEdge>>,
+    forward: Frontier,
+    reverse: Frontier,
+    best_connection: Option<Connection>,
+}
+
+impl<'a, P: GraphTileProvider, C: Costing> BidirectionalSearch<'a, P, C> {
+    fn new(
+        provider: &'a P,
+        costing: &'a C,
+        context: &'a SearchContext<'a>,
+        max_cost: Option<f32>,
+        avoided: &'a HashSet<GraphId>,
+    ) -> Self {
+        Self {
+            provider,
+            costing,
+            context,
+            time: None,
+            max_cost,
+            heuristic: None,
+            avoided,
+            penalties: None,
+            prune_not_thru: costing.prunes_restricted_regions(),
+            prune_dest_only: costing.prunes_restricted_regions(),
+            expansion: None,
+            forward: Frontier::default(),
+            reverse: Frontier::default(),
+            best_connection: None,
+        }
+    }
+
+    /// When an edge is traversed, given the cost of the path so far in the direction of the search.
+    ///
+    /// The time is only known in the direction which starts from the fixed end of the route.
+    /// In the reverse search, this is the time at the end of the edge.
+    fn time_info(
+        &self,
+        direction: Direction,
+        time_zone_index: u16,
+        elapsed: Cost,
+    ) -> Option<TimeInfo> {
+        let at = match (self.time?, direction) {
+            (SearchTime::DepartAt(departure), Direction::Forward) => {
+                add_secs(departure, elapsed.secs)
+            }
+            (SearchTime::ArriveBy(arrival), Direction::Reverse) => add_secs(arrival, -elapsed.secs),
+            _ => return None,
+        };
+        Some(TimeInfo::at(self.context.time_zones, time_zone_index, at))
+    }
+
+    /// The context for costing an edge,
+    /// or `None` if live traffic reports that the edge is closed.
+    ///
+    /// `elapsed` and `distance` are the cost and length of the path so far
+    /// in the direction of the search.
+    fn edge_context(
+        &self,
+        direction: Direction,
+        edge_id: GraphId,
+        time_zone_index: u16,
+        elapsed: Cost,
+        distance: f64,
+    ) -> Option<EdgeContext> {
+        let live_speed = self
+            .context
+            .live_traffic
+            .filter(|_| self.costing.uses_live_traffic())
+            .and_then(|live_traffic| live_traffic.live_speed(edge_id));
+        if live_speed.is_some_and(|speed| speed.is_completely_closed()) {
+            return None;
+        }
+
+        // Live speeds describe the present, so they don't apply to routes at other times,
+        // and the distance from the origin is unknown in the reverse search.
+        let live_weight = match (direction, self.time) {
+            (Direction::Forward, None) => {
+                live_weight(distance, self.context.live_traffic_decay_distance)
+            }
+            _ => 0.0,
+        };
+        Some(EdgeContext {
+            time: self.time_info(direction, time_zone_index, elapsed),
+            live_speed,
+            live_weight,
+        })
+    }
+
+    /// The pruning flags for a label on `edge`,
+    /// or `None` if the edge enters a region which the path has already left.
+    fn region_pruning(
+        &self,
+        pred: Option<&EdgeLabel>,
+        edge: &DirectedEdge,
+    ) -> Option<(bool, bool)> {
+        let not_thru_pruning = pred.is_some_and(|pred| pred.not_thru_pruning);
+        let dest_only_pruning = pred.is_some_and(|pred| pred.dest_only_pruning);
+        if (self.prune_not_thru && not_thru_pruning && edge.no_thru())
+            || (self.prune_dest_only && dest_only_pruning && edge.dest_only())
+        {
+            return None;
+        }
+        Some((
+            not_thru_pruning || !edge.no_thru(),
+            dest_only_pruning || !edge.dest_only(),
+        ))
+    }
+
+    /// Finds the lowest cost path from `origin` to `destination` (see [`shortest_path`]).
+    fn find_path(
+        &mut self,
+        origin: GraphId,
+        destination: GraphId,
+        options: &RouteOptions,
+    ) -> Result<Option<Vec<PathEdge>>, RoutingError> {
+        let provider = self.provider;
+        let destinations = equivalent_nodes(provider, destination)?;
+        if destinations.contains(&origin) {
+            return Ok(Some(Vec::new()));
+        }
+
+        self.heuristic = Heuristic::new(
+            self.costing,
+            node_coordinate(provider, origin)?,
+            node_coordinate(provider, destination)?,
+        );
+        let time_zone_index = |node_id: GraphId| {
+            provider.with_tile_containing(node_id, |tile| {
+                tile.get_node(node_id).map(NodeInfo::time_zone_index)
+            })
+        };
+        let time_zones = self.context.time_zones;
+        match options.time {
+            None => {
+                self.expand_forward(origin, None)?;
+                self.expand_reverse(destination, None)?;
+                self.run_bidirectional(options.max_expansions)
+            }
+            Some(RouteTime::DepartAt(local)) => {
+                let departure = local_to_utc(time_zones, time_zone_index(origin)??, local);
+                self.time = Some(SearchTime::DepartAt(departure));
+                self.allow_regions_at(destination)?;
+                self.heuristic = self.heuristic.map(Heuristic::unbalanced);
+                self.expand_forward(origin, None)?;
+                self.run_unidirectional(Direction::Forward, &destinations, options.max_expansions)
+            }
+            Some(RouteTime::ArriveBy(local)) => {
+                let arrival = local_to_utc(time_zones, time_zone_index(destination)??, local);
+                self.time = Some(SearchTime::ArriveBy(arrival));
+                self.allow_regions_at(origin)?;
+                self.heuristic = self.heuristic.map(Heuristic::unbalanced);
+                self.expand_reverse(destination, None)?;
+                let origins = equivalent_nodes(provider, origin)?;
+                self.run_unidirectional(Direction::Reverse, &origins, options.max_expansions)
+            }
+        }
+    }
+
+    /// Records a settled label, if the search is recording its expansion.
+    fn record_expansion(&mut self, direction: Direction, index: usize) {
+        let Some(expansion) = self.expansion.as_mut() else {
+            return;
+        };
+        let frontier = match direction {
+            Direction::Forward => &self.forward,
+            Direction::Reverse => &self.reverse,
+        };
+        let label = &frontier.labels[index];
+        expansion.push(ExpandedEdge {
+            edge_id: label.edge_id,
+            predecessor_edge_id: label
+                .predecessor
+                .map(|predecessor| frontier.labels[predecessor].edge_id),
+            direction: match direction {
+                Direction::Forward => ExpansionDirection::Forward,
+                Direction::Reverse => ExpansionDirection::Reverse,
+            },
+            cost: label.total_cost,
+            distance: label.distance,
+        });
+    }
+
+    /// Stops keeping out of the kinds of restricted regions which touch a one-way search's target,
+    /// so that the search can reach it.
+    fn allow_regions_at(&mut self, target: GraphId) -> Result<(), RoutingError> {
+        let (not_thru, dest_only) = self.provider.with_tile_containing(target, |tile| {
+            let node = tile.get_node(target)?;
+            let edges = tile.get_outbound_edges_from_node(node);
+            Ok::<_, LookupError>((
+                edges.iter().any(DirectedEdge::no_thru),
+                edges.iter().any(DirectedEdge::dest_only),
+            ))
+        })??;
+        self.prune_not_thru &= !not_thru;
+        self.prune_dest_only &= !dest_only;
+        Ok(())
+    }
+
+    /// Applies any penalty for an edge to its cost, and returns the extra cost.
+    fn penalize(&self, edge_id: GraphId, edge_cost: &mut Cost) -> f32 {
+        let Some(&factor) = self.penalties.and_then(|penalties| penalties.get(&edge_id)) else {
+            return 0.0;
+        };
+        let penalty = edge_cost.cost * (factor - 1.0);
+        edge_cost.cost += penalty;
+        penalty
+    }
+
+    /// Adds a label in one direction, and checks whether it connects to the other.
+    fn add_label(
+        &mut self,
+        direction: Direction,
+        mut label: EdgeLabel,
+    ) -> Result<(), RoutingError> {
+        let mut remaining = 0.0;
+        if let Some(heuristic) = self.heuristic {
+            let coordinate = node_coordinate(self.provider, label.node_id)?;
+            let estimate = heuristic.estimate(direction, coordinate);
+            label.potential = estimate.potential;
+            remaining = estimate.remaining;
+        }
+        if self
+            .max_cost
+            .is_some_and(|max_cost| label.total_cost.cost + remaining > max_cost)
+        {
+            return Ok(());
+        }
+
+        let (frontier, other) = match direction {
+            Direction::Forward => (&mut self.forward, &self.reverse),
+            Direction::Reverse => (&mut self.reverse, &self.forward),
+        };
+        let Some(index) = frontier.push(label) else {
+            return Ok(());
+        };
+        let label = &frontier.labels[index];
+        let Some(&other_index) = other.best.get(&label.edge_id) else {
+            return Ok(());
+        };
+
+        // Both totals include the cost of the shared edge.
+        // The path uses the forward cost of the edge, so subtract the reverse one.
+        let other_label = &other.labels[other_index];
+        let reverse_edge_cost = match direction {
+            Direction::Forward => other_label.edge_cost,
+            Direction::Reverse => label.edge_cost,
+        };
+        let cost = label.total_cost.cost + other_label.total_cost.cost - reverse_edge_cost.cost;
+        if self
+            .best_connection
+            .is_none_or(|connection| cost < connection.cost)
+        {
+            let (forward_index, reverse_index) = match direction {
+                Direction::Forward => (index, other_index),
+                Direction::Reverse => (other_index, index),
+            };
+            self.best_connection = Some(Connection {
+                cost,
+                forward_index,
+                reverse_index,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Queues every allowed edge leaving `node_id` (on any hierarchy level).
+    fn expand_forward(
+        &mut self,
+        node_id: GraphId,
+        predecessor: Option<usize>,
+    ) -> Result<(), RoutingError> {
+        for node_id in equivalent_nodes(self.provider, node_id)? {
+            let pred = predecessor.map(|index| &self.forward.labels[index]);
+            let candidates = self.provider.with_tile_containing(node_id, |tile| {
+                let node = tile.get_node(node_id)?;
+                let candidates: Vec<_> = tile
+                    .outbound_edges_with_ids(node)
+                    .filter(|(edge_id, edge)| {
+                        // Shortcuts duplicate edges on the same level,
+                        // and would need to be expanded when building the path.
+                        !edge.is_shortcut()
+                            && !self.forward.settled.contains(edge_id)
+                            && !self.avoided.contains(edge_id)
+                    })
+                    .filter_map(|(edge_id, edge)| {
+                        let (not_thru_pruning, dest_only_pruning) =
+                            self.region_pruning(pred, edge)?;
+                        let transition_cost = pred.map_or(Cost::ZERO, |pred| {
+                            self.costing.transition_cost(node, &pred.edge, edge)
+                        });
+                        let elapsed =
+                            pred.map_or(Cost::ZERO, |pred| pred.total_cost) + transition_cost;
+                        let distance = pred.map_or(0.0, |pred| pred.distance);
+                        let context = self.edge_context(
+                            Direction::Forward,
+                            edge_id,
+                            node.time_zone_index(),
+                            elapsed,
+                            distance,
+                        )?;
+                        if !self.costing.edge_allowed(edge_id, edge, tile, &context) {
+                            return None;
+                        }
+                        let mut edge_cost = self.costing.edge_cost(edge_id, edge, tile, &context);
+                        let penalty = self.penalize(edge_id, &mut edge_cost);
+                        Some(EdgeLabel {
+                            edge_id,
+                            edge: edge.clone(),
+                            node_id: edge.end_node_id(),
+                            predecessor,
+                            edge_cost,
+                            transition_cost,
+                            total_cost: elapsed + edge_cost,
+                            distance: distance + f64::from(edge.length()),
+                            potential: 0.0,
+                            penalty,
+                            not_thru_pruning,
+                            dest_only_pruning,
+                        })
+                    })
+                    .collect();
+                Ok::<_, LookupError>(candidates)
+            })??;
+
+            for label in candidates {
+                self.add_label(Direction::Forward, label)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Queues every allowed edge arriving at `node_id` (on any hierarchy level).
+    fn expand_reverse(
+        &mut self,
+        node_id: GraphId,
+        predecessor: Option<usize>,
+    ) -> Result<(), RoutingError> {
+        for node_id in equivalent_nodes(self.provider, node_id)? {
+            // The inbound edges are the opposing edges of the outbound edges,
+            // and they start at the other end node.
+            let (node, inbound) = self.provider.with_tile_containing(node_id, |tile| {
+                let node = tile.get_node(node_id)?;
+                let inbound: Vec<_> = tile
+                    .get_outbound_edges_from_node(node)
+                    .iter()
+                    .filter(|edge| !edge.is_shortcut())
+                    .map(|edge| (edge.end_node_id(), edge.opposing_edge_index()))
+                    .collect();
+                Ok::<_, LookupError>((node.clone(), inbound))
+            })??;
+
+            for (start_node_id, opposing_edge_index) in inbound {
+                let pred = predecessor.map(|index| &self.reverse.labels[index]);
+                let label = self.provider.with_tile_containing(start_node_id, |tile| {
+                    let start_node = tile.get_node(start_node_id)?;
+                    let edge_id = tile
+                        .graph_id()
+                        .tile_base_id()
+                        .with_feature_index(u64::from(
+                            start_node.edge_index() + opposing_edge_index,
+                        ))?;
+                    let edge = tile.get_directed_edge(edge_id)?;
+                    if self.reverse.settled.contains(&edge_id) || self.avoided.contains(&edge_id) {
+                        return Ok(None);
+                    }
+                    let Some((not_thru_pruning, dest_only_pruning)) =
+                        self.region_pruning(pred, edge)
+                    else {
+                        return Ok(None);
+                    };
+
+                    let transition_cost = pred.map_or(Cost::ZERO, |pred| {
+                        self.costing.transition_cost(&node, edge, &pred.edge)
+                    });
+                    let elapsed = pred.map_or(Cost::ZERO, |pred| pred.total_cost) + transition_cost;
+                    let distance = pred.map_or(0.0, |pred| pred.distance);
+                    let Some(context) = self.edge_context(
+                        Direction::Reverse,
+                        edge_id,
+                        node.time_zone_index(),
+                        elapsed,
+                        distance,
+                    ) else {
+                        return Ok(None);
+                    };
+                    if !self.costing.edge_allowed(edge_id, edge, tile, &context) {
+                        return Ok(None);
+                    }
+                    let mut edge_cost = self.costing.edge_cost(edge_id, edge, tile, &context);
+                    let penalty = self.penalize(edge_id, &mut edge_cost);
+                    Ok::<_, GraphTileProviderError>(Some(EdgeLabel {
+                        edge_id,
+                        edge: edge.clone(),
+                        node_id: start_node_id,
+                        predecessor,
+                        edge_cost,
+                        transition_cost,
+                        total_cost: elapsed + edge_cost,
+                        distance: distance + f64::from(edge.length()),
+                        potential: 0.0,
+                        penalty,
+                        not_thru_pruning,
+                        dest_only_pruning,
+                    }))
+                })??;
+
+                if let Some(label) = label {
+                    self.add_label(Direction::Reverse, label)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The path from the origin to the edge of a forward label.
+    fn forward_edges(&self, index: usize) -> Vec<PathEdge> {
+        let mut edges = Vec::new();
+        let mut next = Some(index);
+        while let Some(index) = next {
+            let label = &self.forward.labels[index];
+            edges.push(PathEdge {
+                edge_id: label.edge_id,
+                cost: label.transition_cost + label.path_edge_cost(),
+                length: f64::from(label.edge.length()),
+            });
+            next = label.predecessor;
+        }
+        edges.reverse();
+
+        edges
+    }
+
+    /// Appends the path from a reverse label to the destination.
+    ///
+    /// `transition_cost` is the cost of the transition onto the label's edge.
+    fn extend_reverse_edges(
+        &self,
+        edges: &mut Vec<PathEdge>,
+        mut next: Option<usize>,
+        mut transition_cost: Cost,
+    ) {
+        // Reverse labels store the transition onto the next edge
+        while let Some(index) = next {
+            let label = &self.reverse.labels[index];
+            edges.push(PathEdge {
+                edge_id: label.edge_id,
+                cost: transition_cost + label.path_edge_cost(),
+                length: f64::from(label.edge.length()),
+            });
+            transition_cost = label.transition_cost;
+            next = label.predecessor;
+        }
+    }
+
+    /// The path from the origin to a forward label,
+    /// or from a reverse label to the destination.
+    fn settled_path(&self, direction: Direction, index: usize) -> Vec<PathEdge> {
+        match direction {
+            Direction::Forward => self.forward_edges(index),
+            Direction::Reverse => {
+                let mut edges = Vec::new();
+                self.extend_reverse_edges(&mut edges, Some(index), Cost::ZERO);
+                edges
+            }
+        }
+    }
+
+    /// Joins the forward and reverse paths at the connecting edge.
+    fn reconstruct_path(&self, connection: Connection) -> Vec<PathEdge> {
+        let mut edges = self.forward_edges(connection.forward_index);
+        let connecting_label = &self.reverse.labels[connection.reverse_index];
+        self.extend_reverse_edges(
+            &mut edges,
+            connecting_label.predecessor,
+            connecting_label.transition_cost,
+        );
+
+        edges
+    }
+
+    /// Alternates between the forward and reverse searches until the best connection is found.
+    ///
+    /// Each step expands whichever direction has the lower cost frontier.
+    /// The search stops once the two frontiers together cost more than the best connection found,
+    /// since no unexplored path can be any cheaper.
+    /// (The potentials of the two directions cancel out, so this holds for A* as well.)
+    fn run_bidirectional(
+        &mut self,
+        max_expansions: Option<usize>,
+    ) -> Result<Option<Vec<PathEdge>>, RoutingError> {
+        let mut expansions = 0;
+        // Once either side is exhausted, every connection has been found
+        while let (Some(forward_cost), Some(reverse_cost)) =
+            (self.forward.min_cost(), self.reverse.min_cost())
+        {
+            if self
+                .best_connection
+                .is_some_and(|connection| forward_cost + reverse_cost >= connection.cost)
+            {
+                break;
+            }
+
+            if max_expansions.is_some_and(|max_expansions| expansions >= max_expansions) {
+                return Err(RoutingError::ExpansionLimitExceeded { expansions });
+            }
+
+            if forward_cost <= reverse_cost {
+                if let Some(index) = self.forward.pop() {
+                    expansions += 1;
+                    self.record_expansion(Direction::Forward, index);
+                    self.expand_forward(self.forward.labels[index].node_id, Some(index))?;
+                }
+            } else if let Some(index) = self.reverse.pop() {
+                expansions += 1;
+                self.record_expansion(Direction::Reverse, index);
+                self.expand_reverse(self.reverse.labels[index].node_id, Some(index))?;
+            }
+        }
+
+        Ok(self
+            .best_connection
+            .map(|connection| self.reconstruct_path(connection)))
+    }
+
+    /// Expands a single direction until it settles an edge which reaches one of the `targets`.
+    ///
+    /// Labels are settled in order of cost, so the first one to reach a target is the best.
+    fn run_unidirectional(
+        &mut self,
+        direction: Direction,
+        targets: &[GraphId],
+        max_expansions: Option<usize>,
+    ) -> Result<Option<Vec<PathEdge>>, RoutingError> {
+        let mut expansions = 0;
+        loop {
+            let frontier = match direction {
+                Direction::Forward => &mut self.forward,
+                Direction::Reverse => &mut self.reverse,
+            };
+            if frontier.min_cost().is_none() {
+                return Ok(None);
+            }
+            if max_expansions.is_some_and(|max_expansions| expansions >= max_expansions) {
+                return Err(RoutingError::ExpansionLimitExceeded { expansions });
+            }
+            let Some(index) = frontier.pop() else {
+                return Ok(None);
+            };
+
+            let node_id = frontier.labels[index].node_id;
+            self.record_expansion(direction, index);
+            if targets.contains(&node_id) {
+                return Ok(Some(self.settled_path(direction, index)));
+            }
+
+            expansions += 1;
+            match direction {
+                Direction::Forward => self.expand_forward(node_id, Some(index))?,
+                Direction::Reverse => self.expand_reverse(node_id, Some(index))?,
+            }
+        }
+    }
+
+    /// Searches in one direction until every group of target nodes has a path.
+    ///
+    /// Each group is the set of equivalent nodes for a single target,
+    /// and `paths` holds any paths which are already known.
+    /// Targets which can't be reached (within the expansion limit) have no path.
+    fn run_one_to_many(
+        &mut self,
+        direction: Direction,
+        targets: &[Vec<GraphId>],
+        mut paths: Vec<Option<Vec<PathEdge>>>,
+        max_expansions: Option<usize>,
+    ) -> Result<Vec<Option<Vec<PathEdge>>>, RoutingError> {
+        let mut remaining = paths.iter().filter(|path| path.is_none()).count();
+        let mut expansions = 0;
+        // Stop as soon as the last target is reached
+        while remaining > 0
+            && max_expansions.is_none_or(|max_expansions| expansions < max_expansions)
+        {
+            let frontier = match direction {
+                Direction::Forward => &mut self.forward,
+                Direction::Reverse => &mut self.reverse,
+            };
+            let Some(index) = frontier.pop() else {
+                break;
+            };
+
+            let node_id = frontier.labels[index].node_id;
+            self.record_expansion(direction, index);
+            for (path, nodes) in paths.iter_mut().zip(targets) {
+                if path.is_none() && nodes.contains(&node_id) {
+                    *path = Some(self.settled_path(direction, index));
+                    remaining -= 1;
+                }
+            }
+
+            expansions += 1;
+            match direction {
+                Direction::Forward => self.expand_forward(node_id, Some(index))?,
+                Direction::Reverse => self.expand_reverse(node_id, Some(index))?,
+            }
+        }
+
+        Ok(paths)
+    }
+}
+
+/// Finds the lowest cost sequence of edges from `origin` to `destination`.
+///
+/// Without a departure or arrival time, this is a bidirectional search.
+/// With one, it searches from the end of the route where the time is fixed,
+/// converting local times using the time zone of the origin or destination node.
+///
+/// Returns `Ok(None)` if the destination is unreachable
+/// (or can only be reached at a cost greater than the maximum).
+pub(crate) fn shortest_path<P: GraphTileProvider, C: Costing>(
+    provider: &P,
+    costing: &C,
+    context: &SearchContext,
+    origin: GraphId,
+    destination: GraphId,
+    options: &RouteOptions,
+) -> Result<Option<Vec<PathEdge>>, RoutingError> {
+    let avoided = avoided_edges(provider, &options.avoid_polygons, &options.avoid_locations)?;
+    penalized_path(
+        provider,
+        costing,
+        context,
+        origin,
+        destination,
+        &avoided,
+        &HashMap::new(),
+        options,
+    )
+}
+
+/// Runs the same search as [`shortest_path`], recording each edge as it is settled.
+///
+/// The edges are returned in the order they were settled,
+/// whether or not the search found a path (or ran out of expansions).
+pub(crate) fn expansion<P: GraphTileProvider, C: Costing>(
+    provider: &P,
+    costing: &C,
+    context: &SearchContext,
+    origin: GraphId,
+    destination: GraphId,
+    options: &RouteOptions,
+) -> Result<Vec<ExpandedEdge>, RoutingError> {
+    let avoided = avoided_edges(provider, &options.avoid_polygons, &options.avoid_locations)?;
+    let mut search =
+        BidirectionalSearch::new(provider, costing, context, options.max_cost, &avoided);
+    search.expansion = Some(Vec::new());
+    match search.find_path(origin, destination, options) {
+        Ok(_) | Err(RoutingError::ExpansionLimitExceeded { .. }) => {
+            Ok(search.expansion.unwrap_or_default())
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Finds the lowest cost path like [`shortest_path`],
+/// with the cost of some edges multiplied by a penalty factor.
+///
+/// The penalties only steer the search:
+/// the costs of the returned edges don't include them.
+/// The edges to avoid are passed in,
+/// so that they only need to be found once when searching repeatedly.
+#[expect(clippy::too_many_arguments, reason = "Mirrors shortest_path")]
+pub(crate) fn penalized_path<P: GraphTileProvider, C: Costing>(
+    provider: &P,
+    costing: &C,
+    context: &SearchContext,
+    origin: GraphId,
+    destination: GraphId,
+    avoided: &HashSet<GraphId>,
+    penalties: &HashMap<GraphId, f32>,
+    options: &RouteOptions,
+) -> Result<Option<Vec<PathEdge>>, RoutingError> {
+    let mut search =
+        BidirectionalSearch::new(provider, costing, context, options.max_cost, avoided);
+    search.penalties = Some(penalties);
+    search.find_path(origin, destination, options)
+}
+
+/// Finds the lowest cost paths between one node and many others, with a single search.
+///
+/// This searches forward from `node` to each of `others`,
+/// except when arriving by a given time,
+/// when it searches in reverse from `node` (the destination) to each of `others` (the origins).
+/// The A* heuristic doesn't apply, since there are several targets,
+/// but the search stops as soon as every target has been reached.
+///
+/// The edges to avoid are passed in, so that they only need to be found once
+/// for a whole matrix.
+/// Targets which can't be reached (within the maximum cost and expansions) have no path.
+pub(crate) fn one_to_many<P: GraphTileProvider, C: Costing>(
+    provider: &P,
+    costing: &C,
+    context: &SearchContext,
+    node: GraphId,
+    others: &[GraphId],
+    avoided: &HashSet<GraphId>,
+    options: &RouteOptions,
+) -> Result<Vec<Option<Vec<PathEdge>>>, RoutingError> {
+    let mut search =
+        BidirectionalSearch::new(provider, costing, context, options.max_cost, avoided);
+    // The targets may be anywhere, including inside restricted regions
+    search.prune_not_thru = false;
+    search.prune_dest_only = false;
+    let local_to_utc = |local| -> Result<_, RoutingError> {
+        let time_zone_index = provider.with_tile_containing(node, |tile| {
+            tile.get_node(node).map(NodeInfo::time_zone_index)
+        })??;
+        Ok(local_to_utc(context.time_zones, time_zone_index, local))
+    };
+    let direction = match options.time {
+        None => Direction::Forward,
+        Some(RouteTime::DepartAt(local)) => {
+            search.time = Some(SearchTime::DepartAt(local_to_utc(local)?));
+            Direction::Forward
+        }
+        Some(RouteTime::ArriveBy(local)) => {
+            search.time = Some(SearchTime::ArriveBy(local_to_utc(local)?));
+            Direction::Reverse
+        }
+    };
+
+    let targets = others
+        .iter()
+        .map(|&other| equivalent_nodes(provider, other))
+        .collect::<Result<Vec<_>, _>>()?;
+    // The search never settles its own starting node
+    let paths = targets
+        .iter()
+        .map(|nodes| nodes.contains(&node).then(Vec::new))
+        .collect();
+    match direction {
+        Direction::Forward => search.expand_forward(node, None)?,
+        Direction::Reverse => search.expand_reverse(node, None)?,
+    }
+    search.run_one_to_many(direction, &targets, paths, options.max_expansions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SearchContext, equivalent_nodes, shortest_path};
+    use crate::{Cost, Costing, EdgeContext, PathEdge, RouteOptions};
+    use chrono::{FixedOffset, NaiveDate, Offset, Utc};
+    use std::cell::RefCell;
+    use std::collections::{HashMap, HashSet};
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use valhalla_graphtile::graph_tile::{DirectedEdge, GraphTile, GraphTileView};
+    use valhalla_graphtile::tile_provider::{DirectoryGraphTileProvider, GraphTileProvider};
+    use valhalla_graphtile::{Access, GraphId};
+
+    /// Shortest distance, so that costs are exact integers.
+    struct LengthCosting;
+
+    impl Costing for LengthCosting {
+        fn access_mode(&self) -> Access {
+            Access::Auto
+        }
+
+        fn astar_cost_factor(&self) -> f32 {
+            1.0
+        }
+
+        fn edge_cost(
+            &self,
+            _edge_id: GraphId,
+            edge: &DirectedEdge,
+            _tile: &GraphTileView,
+            _context: &EdgeContext,
+        ) -> Cost {
+            let length = f32::from(u16::try_from(edge.length()).unwrap());
+            Cost::new(length, length)
+        }
+    }
+
+    /// Records the times at which each edge is costed.
+    #[derive(Default)]
+    struct TimeRecordingCosting {
+        times: RefCell<HashMap<GraphId, HashSet<u32>>>,
+    }
+
+    impl Costing for TimeRecordingCosting {
+        fn access_mode(&self) -> Access {
+            Access::Auto
+        }
+
+        fn edge_cost(
+            &self,
+            edge_id: GraphId,
+            edge: &DirectedEdge,
+            tile: &GraphTileView,
+            context: &EdgeContext,
+        ) -> Cost {
+            if let Some(time) = context.time {
+                self.times
+                    .borrow_mut()
+                    .entry(edge_id)
+                    .or_default()
+                    .insert(time.seconds_of_week);
+            }
+            LengthCosting.edge_cost(edge_id, edge, tile, context)
+        }
+    }
+
+    /// Counts the edges which are costed, with or without the A* heuristic.
+    struct CountingCosting {
+        astar: bool,
+        count: RefCell<usize>,
+    }
+
+    impl Costing for CountingCosting {
+        fn access_mode(&self) -> Access {
+            Access::Auto
+        }
+
+        fn astar_cost_factor(&self) -> f32 {
+            if self.astar { 1.0 } else { 0.0 }
+        }
+
+        fn edge_cost(
+            &self,
+            edge_id: GraphId,
+            edge: &DirectedEdge,
+            tile: &GraphTileView,
+            context: &EdgeContext,
+        ) -> Cost {
+            *self.count.borrow_mut() += 1;
+            LengthCosting.edge_cost(edge_id, edge, tile, context)
+        }
+    }
+
+    fn context(time_zones: &FixedOffset) -> SearchContext<'_> {
+        SearchContext {
+            time_zones,
+            live_traffic: None,
+            live_traffic_decay_distance: 0.0,
+        }
+    }
+
+    /// A plain node-based Dijkstra search to compare against.
+    fn reference_distance(
+        provider: &DirectoryGraphTileProvider,
+        origin: GraphId,
+        destination: GraphId,
+    ) -> Option<u32> {
+        let destinations = equivalent_nodes(provider, destination).unwrap();
+        pathfinding::directed::dijkstra::dijkstra(
+            &origin,
+            |&node_id| {
+                let mut successors = Vec::new();
+                for node_id in equivalent_nodes(provider, node_id).unwrap() {
+                    provider
+                        .with_tile_containing(node_id, |tile| {
+                            let node = tile.get_node(node_id).unwrap();
+                            successors.extend(
+                                tile.outbound_edges_with_ids(node)
+                                    .filter(|(_, edge)| {
+                                        !edge.is_shortcut()
+                                            && edge.forward_access().contains(Access::Auto)
+                                    })
+                                    .map(|(_, edge)| (edge.end_node_id(), edge.length())),
+                            );
+                        })
+                        .unwrap();
+                }
+                successors
+            },
+            |node_id| destinations.contains(node_id),
+        )
+        .map(|(_, distance)| distance)
+    }
+
+    #[test]
+    fn test_bidirectional_search_is_optimal() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(4).unwrap());
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let time = NaiveDate::from_ymd_opt(2025, 6, 2)
+            .unwrap()
+            .and_hms_opt(8, 0, 0)
+            .unwrap();
+        let all_options = [
+            RouteOptions::default(),
+            RouteOptions::default().with_depart_at(time),
+            RouteOptions::default().with_arrive_by(time),
+        ];
+
+        let mut routes = 0;
+        for index in (0..1400).step_by(97) {
+            let destination = GraphId::try_from_components(2, 763_926, index).unwrap();
+            for (from, to) in [(origin, destination), (destination, origin)] {
+                let expected = reference_distance(&provider, from, to);
+                // The unidirectional searches must agree when costs don't depend on time
+                for options in &all_options {
+                    let path = shortest_path(
+                        &provider,
+                        &LengthCosting,
+                        &context(&Utc.fix()),
+                        from,
+                        to,
+                        options,
+                    )
+                    .unwrap();
+                    let actual = path.map(|edges| {
+                        edges
+                            .iter()
+                            .fold(Cost::ZERO, |total, edge| total + edge.cost)
+                            .cost
+                    });
+                    assert_eq!(
+                        actual.map(|cost| f64::from(cost).to_bits()),
+                        expected.map(|distance| f64::from(distance).to_bits()),
+                        "{from} -> {to} ({options:?})"
+                    );
+                }
+                routes += usize::from(expected.is_some());
+            }
+        }
+        // Many nodes in the fixture are only reachable on foot
+        assert!(routes >= 10);
+    }
+
+    #[test]
+    fn test_astar_prunes_search() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(4).unwrap());
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let destination = GraphId::try_from_components(2, 763_926, 123).unwrap();
+
+        let search = |astar| {
+            let costing = CountingCosting {
+                astar,
+                count: RefCell::new(0),
+            };
+            let path = shortest_path(
+                &provider,
+                &costing,
+                &context(&Utc.fix()),
+                origin,
+                destination,
+                &RouteOptions::default(),
+            )
+            .unwrap()
+            .unwrap();
+            let cost = path
+                .iter()
+                .fold(Cost::ZERO, |total, edge| total + edge.cost);
+            (cost, costing.count.into_inner())
+        };
+        let (dijkstra_cost, dijkstra_count) = search(false);
+        let (astar_cost, astar_count) = search(true);
+        assert_eq!(astar_cost, dijkstra_cost);
+        assert!(
+            astar_count < dijkstra_count,
+            "{astar_count} vs {dijkstra_count}"
+        );
+    }
+
+    #[test]
+    fn test_time_dependent_search() {
+        let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../valhalla-graphtile/fixtures/andorra-tiles");
+        let provider = DirectoryGraphTileProvider::new(base, NonZeroUsize::new(4).unwrap());
+        let origin = GraphId::try_from_components(2, 763_926, 0).unwrap();
+        let destination = GraphId::try_from_components(2, 763_926, 123).unwrap();
+        let cet = FixedOffset::east_opt(3600).unwrap();
+        // 08:00 on a Monday
+        let local_time = NaiveDate::from_ymd_opt(2025, 6, 2)
+            .unwrap()
+            .and_hms_opt(8, 0, 0)
+            .unwrap();
+        let seconds_of_week = f64::from(24 * 3600 + 8 * 3600);
+
+        // Each edge is costed when the vehicle enters it, one second per meter after departure
+        let costing = TimeRecordingCosting::default();
+        let options = RouteOptions::default().with_depart_at(local_time);
+        let edges = shortest_path(
+            &provider,
+            &costing,
+            &context(&cet),
+            origin,
+            destination,
+            &options,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(edges.len() > 1);
+        let times = costing.times.borrow();
+        let was_costed_at = |edge: &PathEdge, secs: f64| {
+            times[&edge.edge_id]
+                .iter()
+                .any(|&time| (f64::from(time) - secs).abs() < 0.5)
+        };
+        let mut elapsed = 0.0;
+        for edge in &edges {
+            assert!(was_costed_at(edge, seconds_of_week + elapsed));
+            elapsed += edge.length;
+        }
+
+        // Or when it leaves it, working backwards from the arrival
+        let costing = TimeRecordingCosting::default();
+        let options = RouteOptions::default().with_arrive_by(local_time);
+        let edges = shortest_path(
+            &provider,
+            &costing,
+            &context(&cet),
+            origin,
+            destination,
+            &options,
+        )
+        .unwrap()
+        .unwrap();
+        let times = costing.times.borrow();
+        let was_costed_at = |edge: &PathEdge, secs: f64| {
+            times[&edge.edge_id]
+                .iter()
+                .any(|&time| (f64::from(time) - secs).abs() < 0.5)
+        };
+        let mut remaining = 0.0;
+        for edge in edges.iter().rev() {
+            assert!(was_costed_at(edge, seconds_of_week - remaining));
+            remaining += edge.length;
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! # Time-dependent routing
+//!
+//! Speeds in the graph vary over the week (predicted speeds, and constrained vs free flow),
+//! and are indexed by **local** time.
+//! Each node records the index of its time zone (as assigned by Valhalla when building tiles),
+//! so the search tracks the absolute time of each edge traversal,
+//! and converts it to local time via a [`TimeZones`] lookup.
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDateTime, TimeDelta, Timelike, Utc};
+
+const SECONDS_PER_DAY: u32 = 24 * 60 * 60;
+
+/// A lookup of UTC offsets for the time zones in a graph.
+///
+/// Valhalla identifies time zones by their index in its bundled time zone database.
+/// This crate doesn't ship that database,
+/// so callers which need exact local times across zones (or DST transitions)
+/// can provide their own lookup.
+pub trait TimeZones {
+    /// The UTC offset of a time zone (by Valhalla's index) at an instant.
+    fn utc_offset(&self, time_zone_index: u16, at: DateTime<Utc>) -> FixedOffset;
+}
+
+/// Uses the same offset for every time zone.
+///
+/// This is accurate for graphs that cover a single time zone without daylight saving time.
+impl TimeZones for FixedOffset {
+    fn utc_offset(&self, _time_zone_index: u16, _at: DateTime<Utc>) -> FixedOffset {
+        *self
+    }
+}
+
+/// When an edge is traversed, for time-dependent costing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeInfo {
+    /// The local date and time.
+    pub local_time: NaiveDateTime,
+    /// Seconds since midnight Sunday, in local time.
+    pub seconds_of_week: u32,
+}
+
+impl TimeInfo {
+    /// The time of an edge traversal at an instant, in the given time zone.
+    pub(crate) fn at(time_zones: &dyn TimeZones, time_zone_index: u16, at: DateTime<Utc>) -> Self {
+        let local = at.naive_utc() + time_zones.utc_offset(time_zone_index, at);
+        Self {
+            local_time: local,
+            seconds_of_week: local.weekday().num_days_from_sunday() * SECONDS_PER_DAY
+                + local.num_seconds_from_midnight(),
+        }
+    }
+}
+
+/// Converts a local time to UTC.
+///
+/// The offset is looked up at the local time as if it were UTC,
+/// which is only off by an hour for times within the offset of a DST transition.
+pub(crate) fn local_to_utc(
+    time_zones: &dyn TimeZones,
+    time_zone_index: u16,
+    local: NaiveDateTime,
+) -> DateTime<Utc> {
+    let offset = time_zones.utc_offset(time_zone_index, local.and_utc());
+    (local - offset).and_utc()
+}
+
+/// Offsets an instant by a (possibly fractional) number of seconds, rounded to the nearest second.
+///
+/// Predicted speeds are bucketed by five minutes, so sub-second precision is never needed.
+pub(crate) fn add_secs(at: DateTime<Utc>, secs: f32) -> DateTime<Utc> {
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "Route durations are far smaller than i64::MAX seconds"
+    )]
+    let secs = secs.round() as i64;
+    at + TimeDelta::seconds(secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TimeInfo, add_secs, local_to_utc};
+    use chrono::{FixedOffset, NaiveDate};
+
+    #[test]
+    fn test_time_info() {
+        let cet = FixedOffset::east_opt(3600).unwrap();
+        // A Monday
+        let local = NaiveDate::from_ymd_opt(2025, 6, 2)
+            .unwrap()
+            .and_hms_opt(8, 30, 0)
+            .unwrap();
+        let utc = local_to_utc(&cet, 0, local);
+        assert_eq!(utc.to_rfc3339(), "2025-06-02T07:30:00+00:00");
+        assert_eq!(
+            TimeInfo::at(&cet, 0, utc).seconds_of_week,
+            24 * 3600 + 8 * 3600 + 30 * 60
+        );
+
+        // Weeks start on Sunday
+        let sunday = local_to_utc(&cet, 0, local - chrono::TimeDelta::days(1));
+        assert_eq!(
+            TimeInfo::at(&cet, 0, sunday).seconds_of_week,
+            8 * 3600 + 30 * 60
+        );
+        assert_eq!(
+            TimeInfo::at(&cet, 0, add_secs(utc, -8.0 * 3600.0 - 1800.4)).seconds_of_week,
+            24 * 3600
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! # Traces
+//!
+//! A trace is a sequence of observed positions (ex: from a GPS receiver),
+//! which map matching turns into a path through the graph.
+//!
+//! Traces can be read from simple CSV files (one `lat,lon[,timestamp]` point per line),
+//! or from the tracks of GPX 1.1 files (with the `gpx` feature).
+//! Timestamps are kept, since the time between points says a lot about
+//! which routes between them are plausible.
+
+use chrono::{DateTime, Utc};
+use geo::Coord;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TraceParseError {
+    #[cfg(feature = "gpx")]
+    #[error("Invalid XML: {0}")]
+    Xml(#[from] roxmltree::Error),
+    #[cfg(feature = "gpx")]
+    #[error("Not a GPX document (the root element is {0})")]
+    NotGpx(String),
+    #[error("Invalid point on line {line}: {reason}")]
+    InvalidPoint { line: usize, reason: String },
+}
+
+/// An observed position in a trace.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TracePoint {
+    pub coordinate: Coord<f64>,
+    /// When the position was observed, if known.
+    pub time: Option<DateTime<Utc>>,
+    /// The radius to search for candidate edges around this point, in meters.
+    ///
+    /// When this is `None`, the search radius in the options is used.
+    /// Set this to account for the accuracy of individual fixes (ex: in urban canyons).
+    pub radius: Option<f64>,
+}
+
+impl TracePoint {
+    pub const fn new(coordinate: Coord<f64>) -> Self {
+        Self {
+            coordinate,
+            time: None,
+            radius: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_time(mut self, time: DateTime<Utc>) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_radius(mut self, radius: f64) -> Self {
+        self.radius = Some(radius);
+        self
+    }
+
+    /// Reads a trace from CSV, with one `lat,lon` or `lat,lon,timestamp` point per line.
+    ///
+    /// Timestamps may be either RFC 3339 strings or Unix epoch seconds.
+    /// Blank lines are skipped, as is a header line (if the first line isn't a point).
+    ///
+    /// # Errors
+    ///
+    /// Fails if a line (other than the header) isn't a valid point.
+    pub fn parse_csv(input: &str) -> Result<Vec<Self>, TraceParseError> {
+        let mut points = Vec::new();
+        for (index, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = |reason: &str| TraceParseError::InvalidPoint {
+                line: index + 1,
+                reason: reason.to_string(),
+            };
+
+            let fields: Vec<_> = line.split(',').map(str::trim).collect();
+            let (lat, lon) = match (
+                fields.first().and_then(|lat| lat.parse::<f64>().ok()),
+                fields.get(1).and_then(|lon| lon.parse::<f64>().ok()),
+            ) {
+                (Some(lat), Some(lon)) => (lat, lon),
+                _ if index == 0 => continue,
+                _ => return Err(invalid("expected a latitude and longitude")),
+            };
+            let mut point = Self::new(coordinate(lat, lon).map_err(invalid)?);
+            match fields.get(2) {
+                Some(time) if !time.is_empty() => {
+                    point.time = Some(parse_time(time).map_err(invalid)?);
+                }
+                _ => {}
+            }
+            if fields.len() > 3 {
+                return Err(invalid("expected at most 3 fields"));
+            }
+            points.push(point);
+        }
+
+        Ok(points)
+    }
+
+    /// Reads the points of every track in a GPX 1.1 document, in order.
+    ///
+    /// The segments of each track (and the tracks themselves) are joined into a single trace.
+    /// Routes and waypoints are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the document isn't valid GPX, or a track point isn't valid.
+    #[cfg(feature = "gpx")]
+    pub fn parse_gpx(input: &str) -> Result<Vec<Self>, TraceParseError> {
+        let document = roxmltree::Document::parse(input)?;
+        let root = document.root_element();
+        if root.tag_name().name() != "gpx" {
+            return Err(TraceParseError::NotGpx(root.tag_name().name().to_string()));
+        }
+
+        let is_element = |node: &roxmltree::Node, name: &str| {
+            node.is_element() && node.tag_name().name() == name
+        };
+        let mut points = Vec::new();
+        for track_point in root
+            .children()
+            .filter(|node| is_element(node, "trk"))
+            .flat_map(|track| track.children().filter(|node| is_element(node, "trkseg")))
+            .flat_map(|segment| segment.children().filter(|node| is_element(node, "trkpt")))
+        {
+            let invalid = |reason: &str| TraceParseError::InvalidPoint {
+                line: document.text_pos_at(track_point.range().start).row as usize,
+                reason: reason.to_string(),
+            };
+            let (Some(lat), Some(lon)) = (
+                track_point
+                    .attribute("lat")
+                    .and_then(|lat| lat.trim().parse().ok()),
+                track_point
+                    .attribute("lon")
+                    .and_then(|lon| lon.trim().parse().ok()),
+            ) else {
+                return Err(invalid("expected lat and lon attributes"));
+            };
+
+            let mut point = Self::new(coordinate(lat, lon).map_err(invalid)?);
+            if let Some(time) = track_point
+                .children()
+                .find(|node| is_element(node, "time"))
+                .and_then(|node| node.text())
+            {
+                point.time = Some(parse_time(time.trim()).map_err(invalid)?);
+            }
+            points.push(point);
+        }
+
+        Ok(points)
+    }
+}
+
+/// Checks that a latitude and longitude are in range.
+fn coordinate(lat: f64, lon: f64) -> Result<Coord<f64>, &'static str> {
+    if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) {
+        Ok(Coord { x: lon, y: lat })
+    } else {
+        Err("the latitude or longitude is out of range")
+    }
+}
+
+/// Parses an RFC 3339 timestamp, or Unix epoch seconds (with an optional fraction).
+fn parse_time(time: &str) -> Result<DateTime<Utc>, &'static str> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(time) {
+        return Ok(time.to_utc());
+    }
+    let (secs, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let nanos = if fraction.is_empty() {
+        Some(0)
+    } else {
+        // Pad (or truncate) the fraction to nanoseconds
+        format!("{fraction:0<9}")
+            .get(..9)
+            .and_then(|nanos| nanos.parse().ok())
+    };
+    secs.parse()
+        .ok()
+        .zip(nanos)
+        .and_then(|(secs, nanos)| DateTime::from_timestamp(secs, nanos))
+        .ok_or("the timestamp is neither RFC 3339 nor Unix epoch seconds")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TraceParseError, TracePoint};
+    use chrono::DateTime;
+
+    #[test]
+    fn test_parse_csv() {
+        let input = "lat,lon,time\n\
+            42.5063,1.5218,2025-06-01T10:00:00Z\n\
+            \n\
+            42.5064, 1.5219, 1748772005.5\n\
+            42.5065,1.5220\n";
+        let points = TracePoint::parse_csv(input).unwrap();
+        assert_eq!(points.len(), 3);
+        assert!((points[0].coordinate.x - 1.5218).abs() < f64::EPSILON);
+        assert!((points[0].coordinate.y - 42.5063).abs() < f64::EPSILON);
+        assert_eq!(
+            points[0].time,
+            Some(DateTime::from_timestamp(1_748_772_000, 0).unwrap())
+        );
+        assert_eq!(
+            points[1].time,
+            Some(DateTime::from_timestamp(1_748_772_005, 500_000_000).unwrap())
+        );
+        assert_eq!(points[2].time, None);
+        assert_eq!(points[0].radius, None);
+
+        // Only the first line may be a header
+        assert!(matches!(
+            TracePoint::parse_csv("42.5,1.5\nlat,lon\n"),
+            Err(TraceParseError::InvalidPoint { line: 2, .. })
+        ));
+        assert!(matches!(
+            TracePoint::parse_csv("42.5,1.5,yesterday\n"),
+            Err(TraceParseError::InvalidPoint { line: 1, .. })
+        ));
+        assert!(matches!(
+            TracePoint::parse_csv("95.0,1.5\n"),
+            Err(TraceParseError::InvalidPoint { line: 1, .. })
+        ));
+    }
+
+    #[cfg(feature = "gpx")]
+    #[test]
+    fn test_parse_gpx() {
+        let input = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <wpt lat="42.0" lon="1.0"><name>Ignored</name></wpt>
+  <trk>
+    <name>Morning drive</name>
+    <trkseg>
+      <trkpt lat="42.5063" lon="1.5218"><ele>1023</ele><time>2025-06-01T10:00:00Z</time></trkpt>
+      <trkpt lat="42.5064" lon="1.5219"><time>2025-06-01T12:00:05+02:00</time></trkpt>
+    </trkseg>
+    <trkseg>
+      <trkpt lat="42.5065" lon="1.5220"/>
+    </trkseg>
+  </trk>
+</gpx>"#;
+        let points = TracePoint::parse_gpx(input).unwrap();
+        assert_eq!(points.len(), 3);
+        assert!((points[2].coordinate.x - 1.5220).abs() < f64::EPSILON);
+        assert_eq!(
+            points[1].time,
+            Some(DateTime::from_timestamp(1_748_772_005, 0).unwrap())
+        );
+        assert_eq!(points[2].time, None);
+
+        assert!(matches!(
+            TracePoint::parse_gpx(r#"<gpx><trk><trkseg><trkpt lat="42.5"/></trkseg></trk></gpx>"#),
+            Err(TraceParseError::InvalidPoint { line: 1, .. })
+        ));
+        assert!(matches!(
+            TracePoint::parse_gpx("<kml></kml>"),
+            Err(TraceParseError::NotGpx(_))
+        ));
+        assert!(matches!(
+            TracePoint::parse_gpx("<gpx>"),
+            Err(TraceParseError::Xml(_))
+        ));
+    }
+}
@@ -0,0 +1,55 @@
+//! # Live traffic
+//!
+//! Live traffic speeds come from a separate source than the routing graph
+//! (usually a traffic extract which is updated in place).
+//! Like Valhalla, the search only trusts live speeds near the origin,
+//! where they are most likely to still be accurate by the time the vehicle arrives,
+//! and decays their influence with distance along the route.
+//! Edges which live traffic reports as closed can't be used at all.
+
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use valhalla_graphtile::GraphId;
+use valhalla_graphtile::tile_provider::TrafficTileProvider;
+use valhalla_graphtile::traffic_tile::TrafficSpeed;
+
+/// A source of live traffic speeds.
+pub trait LiveTraffic {
+    /// The live speed of an edge, if there is any data for it.
+    fn live_speed(&self, edge_id: GraphId) -> Option<TrafficSpeed>;
+}
+
+/// Reads speeds from a traffic extract.
+///
+/// This relies on the same guarantees as [`TrafficTileProvider::get_speeds_for_edge`]:
+/// the extract must only be modified by writers which store whole 64-bit speed values
+/// (ex: [`TrafficExtractUpdater`](valhalla_graphtile::tile_provider::TrafficExtractUpdater)).
+impl<const MUT: bool> LiveTraffic for TrafficTileProvider<MUT> {
+    fn live_speed(&self, edge_id: GraphId) -> Option<TrafficSpeed> {
+        // SAFETY: See the documentation above.
+        let speed = unsafe { self.get_speeds_for_edge(edge_id) }.ok()?;
+        (speed.has_valid_speed() || speed.is_completely_closed()).then_some(speed)
+    }
+}
+
+/// In-memory live speeds, keyed by edge ID.
+impl<S: BuildHasher> LiveTraffic for HashMap<GraphId, TrafficSpeed, S> {
+    fn live_speed(&self, edge_id: GraphId) -> Option<TrafficSpeed> {
+        self.get(&edge_id).copied()
+    }
+}
+
+/// The weight of live traffic at a distance along the route, from 1 (at the origin) to 0.
+///
+/// The weight decays linearly, reaching zero at `decay_distance` meters.
+pub(crate) fn live_weight(distance: f64, decay_distance: f64) -> f32 {
+    if decay_distance <= 0.0 {
+        return 0.0;
+    }
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "The weight is between 0 and 1"
+    )]
+    let weight = (1.0 - (distance / decay_distance).min(1.0)) as f32;
+    weight
+}